@@ -53,6 +53,7 @@ impl<'a> CellularConfig<'a> for MyCelullarConfig {
     type ResetPin = OutputOpenDrain<'static>;
     type PowerPin = OutputOpenDrain<'static>;
     type VintPin = Input<'static>;
+    type DtrPin = ublox_cellular::config::NoPin;
 
     const FLOW_CONTROL: bool = true;
 
@@ -1,87 +1,106 @@
 #![no_std]
 #![no_main]
-#![allow(stable_features)]
-// #![feature(type_alias_impl_trait)]
-
-use atat::asynch::Client;
-use atat::heapless::String;
-use atat::ResponseSlot;
-use atat::UrcChannel;
-use core::cell::RefCell;
-use cortex_m_rt::entry;
+
 use defmt::*;
-use embassy_executor::{Executor, Spawner};
-use embassy_stm32::gpio::{AnyPin, Input, Level, Output, OutputOpenDrain, Pin, Pull, Speed};
-use embassy_stm32::peripherals::UART8;
+use embassy_executor::Spawner;
+use embassy_net::dns::DnsQueryType;
+use embassy_stm32::gpio::{Input, Level, Output, Pull, Speed};
 use embassy_stm32::rcc::VoltageScale;
-use embassy_stm32::time::{khz, mhz};
-use embassy_stm32::usart::{BufferedUart, BufferedUartRx, BufferedUartTx};
-use embassy_stm32::{bind_interrupts, interrupt, peripherals, usart, Config};
-use embassy_time::{Duration, Timer};
+use embassy_stm32::usart::{BufferedInterruptHandler, BufferedUart};
+use embassy_stm32::{bind_interrupts, peripherals, Config};
 use static_cell::StaticCell;
 use {defmt_rtt as _, panic_probe as _};
 
-// use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
-
-use ublox_cellular;
-use ublox_cellular::config::{Apn, CellularConfig, ReverseOutputPin};
-
-use atat::asynch::AtatClient;
-use atat::{AtDigester, AtatIngress, DefaultDigester, Ingress, Parser};
-use ublox_cellular::asynch::runner::Runner;
-use ublox_cellular::asynch::state::{LinkState, OperationState};
-use ublox_cellular::asynch::State;
-use ublox_cellular::command;
-use ublox_cellular::command::{Urc, AT};
+use ublox_cellular::asynch::ppp::{self, NetRunner, PppResources, PppRunner};
+use ublox_cellular::asynch::state::OperationState;
+use ublox_cellular::asynch::Resources;
+use ublox_cellular::config::{Apn, CellularConfig, ReverseOutputPin, Transport};
 
 bind_interrupts!(struct Irqs {
-    UART8 => embassy_stm32::usart::BufferedInterruptHandler<peripherals::UART8>;
+    UART8 => BufferedInterruptHandler<peripherals::UART8>;
 });
 
 const INGRESS_BUF_SIZE: usize = 1024;
 const URC_CAPACITY: usize = 2;
-const URC_SUBSCRIBERS: usize = 2;
+
+/// UART8 the SARA-R5 is attached to
+struct CellTransport(BufferedUart<'static>);
+
+impl Transport for CellTransport {
+    fn set_baudrate(&mut self, baudrate: u32) {
+        unwrap!(self.0.set_baudrate(baudrate));
+    }
+
+    fn split_ref(
+        &mut self,
+    ) -> (
+        impl embedded_io_async::Write,
+        impl embedded_io_async::Read + embedded_io_async::BufRead,
+    ) {
+        self.0.split_ref()
+    }
+}
+
+impl embedded_io_async::ErrorType for CellTransport {
+    type Error = embassy_stm32::usart::Error;
+}
+
+impl embedded_io_async::Read for CellTransport {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.0.read(buf).await
+    }
+}
+
+impl embedded_io_async::BufRead for CellTransport {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        self.0.fill_buf().await
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt)
+    }
+}
+
+impl embedded_io_async::Write for CellTransport {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.write(buf).await
+    }
+}
 
 struct MyCelullarConfig {
     reset_pin: Option<Output<'static>>,
-    // reset_pin: Option<NoPin>,
     power_pin: Option<ReverseOutputPin<Output<'static>>>,
-    // power_pin: Option<NoPin>,
     vint_pin: Option<Input<'static>>,
-    // vint_pin: Option<NoPin>
 }
 
 impl<'a> CellularConfig<'a> for MyCelullarConfig {
     type ResetPin = Output<'static>;
-    // type ResetPin = NoPin;
     type PowerPin = ReverseOutputPin<Output<'static>>;
-    // type PowerPin = NoPin;
     type VintPin = Input<'static>;
-    // type VintPin = NoPin;
+    type DtrPin = ublox_cellular::config::NoPin;
 
     const FLOW_CONTROL: bool = false;
-    const HEX_MODE: bool = true;
-    const APN: Apn<'a> = Apn::Given {
-        name: "hologram",
-        username: None,
-        password: None,
+
+    const PPP_CONFIG: embassy_net_ppp::Config<'a> = embassy_net_ppp::Config {
+        username: b"",
+        password: b"",
     };
+
     fn reset_pin(&mut self) -> Option<&mut Self::ResetPin> {
-        info!("reset_pin");
-        return self.reset_pin.as_mut();
+        self.reset_pin.as_mut()
     }
+
     fn power_pin(&mut self) -> Option<&mut Self::PowerPin> {
-        info!("power_pin");
-        return self.power_pin.as_mut();
+        self.power_pin.as_mut()
     }
+
     fn vint_pin(&mut self) -> Option<&mut Self::VintPin> {
-        info!("vint_pin = {}", self.vint_pin.as_mut()?.is_high());
-        return self.vint_pin.as_mut();
+        self.vint_pin.as_mut()
     }
 }
 
-#[embassy_executor::task]
-async fn main_task(spawner: Spawner) {
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
     let mut config = Config::default();
     {
         use embassy_stm32::rcc::*;
@@ -106,184 +125,73 @@ async fn main_task(spawner: Spawner) {
     }
     let p = embassy_stm32::init(config);
 
-    let led1_pin = p.PI12.degrade();
-    let led2_pin = p.PI13.degrade();
-    let led3_pin = p.PI14.degrade();
-
-    spawner.spawn(blinky(led1_pin)).unwrap();
-    spawner.spawn(blinky(led2_pin)).unwrap();
-    spawner.spawn(blinky(led3_pin)).unwrap();
+    static TX_BUF: StaticCell<[u8; 256]> = StaticCell::new();
+    static RX_BUF: StaticCell<[u8; 256]> = StaticCell::new();
 
-    static tx_buf: StaticCell<[u8; 16]> = StaticCell::new();
-    static rx_buf: StaticCell<[u8; 16]> = StaticCell::new();
-    static INGRESS_BUF: StaticCell<[u8; INGRESS_BUF_SIZE]> = StaticCell::new();
-
-    let (tx_pin, rx_pin, uart) = (p.PJ8, p.PJ9, p.UART8);
     let mut uart_config = embassy_stm32::usart::Config::default();
-    {
-        uart_config.baudrate = 115200;
-        // uart_config.baudrate = 9600;
-        uart_config.parity = embassy_stm32::usart::Parity::ParityNone;
-        uart_config.stop_bits = embassy_stm32::usart::StopBits::STOP1;
-        uart_config.data_bits = embassy_stm32::usart::DataBits::DataBits8;
-    }
-
-    let uart = BufferedUart::new(
-        uart,
+    uart_config.baudrate = 115200;
+    let uart = unwrap!(BufferedUart::new(
+        p.UART8,
         Irqs,
-        rx_pin,
-        tx_pin,
-        tx_buf.init([0u8; 16]),
-        rx_buf.init([0u8; 16]),
+        p.PJ9,
+        p.PJ8,
+        TX_BUF.init([0; 256]),
+        RX_BUF.init([0; 256]),
         uart_config,
-    );
-    let (writer, reader) = uart.unwrap().split();
-    // let power = Output::new(p.PJ4, Level::High, Speed::VeryHigh).degrade();
-    // let reset = Output::new(p.PF8, Level::High, Speed::VeryHigh).degrade();
-    let celullar_config = MyCelullarConfig {
+    ));
+
+    let cell_config = MyCelullarConfig {
         reset_pin: Some(Output::new(p.PF8, Level::High, Speed::Low)),
         power_pin: Some(ReverseOutputPin(Output::new(p.PJ4, Level::Low, Speed::Low))),
-        // reset_pin: Some(OutputOpenDrain::new(p.PF8, Level::High, Speed::Low, Pull::None).degrade()),
-        // power_pin: Some(OutputOpenDrain::new(p.PJ4, Level::High, Speed::Low, Pull::None).degrade()),
-        // power_pin: None,
         vint_pin: Some(Input::new(p.PJ3, Pull::Down)),
     };
 
-    static RES_SLOT: ResponseSlot<INGRESS_BUF_SIZE> = ResponseSlot::new();
-    static URC_CHANNEL: UrcChannel<command::Urc, URC_CAPACITY, URC_SUBSCRIBERS> = UrcChannel::new();
-    let ingress = Ingress::new(
-        DefaultDigester::<command::Urc>::default(),
-        INGRESS_BUF.init([0; INGRESS_BUF_SIZE]),
-        &RES_SLOT,
-        &URC_CHANNEL,
-    );
-    static buf: StaticCell<[u8; INGRESS_BUF_SIZE]> = StaticCell::new();
-    let mut client = Client::new(
-        writer,
-        &RES_SLOT,
-        buf.init([0; INGRESS_BUF_SIZE]),
-        atat::Config::default(),
+    static RESOURCES: StaticCell<Resources<INGRESS_BUF_SIZE, URC_CAPACITY>> = StaticCell::new();
+    static PPP_RESOURCES: StaticCell<PppResources<2, 2, 2>> = StaticCell::new();
+
+    let seed = 0x0123_4567_89ab_cdef; // chosen by fair dice roll. guarenteed to be random.
+
+    let (stack, control, cell_runner, net_runner) = ppp::new(
+        CellTransport(uart),
+        RESOURCES.init(Resources::new()),
+        PPP_RESOURCES.init(PppResources::new()),
+        cell_config,
+        seed,
     );
 
-    spawner.spawn(ingress_task(ingress, reader)).unwrap();
-
-    static state: StaticCell<State<Client<BufferedUartTx<UART8>, INGRESS_BUF_SIZE>>> =
-        StaticCell::new();
-    let (device, mut control, mut runner) = ublox_cellular::asynch::new(
-        state.init(State::new(client)),
-        &URC_CHANNEL,
-        celullar_config,
-    )
-    .await;
-    // defmt::info!("{:?}", runner.init().await);
-    // control.set_desired_state(PowerState::Connected).await;
-    // control
-    //     .send(&crate::command::network_service::SetOperatorSelection {
-    //         mode: crate::command::network_service::types::OperatorSelectionMode::Automatic,
-    //         format: Some(0),
-    //     })
-    //     .await;
-
-    defmt::unwrap!(spawner.spawn(cellular_task(runner)));
-    Timer::after(Duration::from_millis(1000)).await;
-    loop {
-        control
-            .set_desired_state(OperationState::DataEstablished)
-            .await;
-        info!("set_desired_state(PowerState::Alive)");
-        while control.operation_state() != OperationState::DataEstablished {
-            Timer::after(Duration::from_millis(1000)).await;
-        }
-        Timer::after(Duration::from_millis(10000)).await;
-
-        loop {
-            Timer::after(Duration::from_millis(1000)).await;
-            let operator = control.get_operator().await;
-            info!("{}", operator);
-            let signal_quality = control.get_signal_quality().await;
-            info!("{}", signal_quality);
-            if signal_quality.is_err() {
-                let desired_state = control.desired_state();
-                control.set_desired_state(desired_state).await
-            }
-            if let Ok(sq) = signal_quality {
-                if let Ok(op) = operator {
-                    if op.oper == None {
-                        continue;
-                    }
-                }
-                if sq.rxlev > 0 && sq.rsrp != 255 {
-                    break;
-                }
-            }
-        }
-        let dns = control
-            .send(&ublox_cellular::command::dns::ResolveNameIp {
-                resolution_type:
-                    ublox_cellular::command::dns::types::ResolutionType::DomainNameToIp,
-                ip_domain_string: "www.google.com",
-            })
-            .await;
-        debug!("dns: {:?}", dns);
-        Timer::after(Duration::from_millis(10000)).await;
-        control.set_desired_state(OperationState::PowerDown).await;
-        info!("set_desired_state(PowerState::PowerDown)");
-        while control.operation_state() != OperationState::PowerDown {
-            Timer::after(Duration::from_millis(1000)).await;
-        }
-
-        Timer::after(Duration::from_millis(5000)).await;
+    spawner.spawn(cell_task(cell_runner)).unwrap();
+    spawner.spawn(net_task(net_runner)).unwrap();
+
+    control.set_apn_config(Apn::Given {
+        name: unwrap!("hologram".try_into()),
+        username: None,
+        password: None,
+    });
+    control.set_desired_state(OperationState::DataEstablished);
+
+    stack.wait_config_up().await;
+    info!("We have network! {:?}", control.network_config());
+
+    match stack.dns_query("www.google.com", DnsQueryType::A).await {
+        Ok(addrs) => info!("www.google.com: {:?}", addrs),
+        Err(e) => warn!("DNS query failed: {:?}", e),
     }
 }
 
 #[embassy_executor::task]
-async fn ingress_task(
-    mut ingress: Ingress<
-        'static,
-        DefaultDigester<Urc>,
-        ublox_cellular::command::Urc,
-        { INGRESS_BUF_SIZE },
-        { URC_CAPACITY },
-        { URC_SUBSCRIBERS },
-    >,
-    mut reader: BufferedUartRx<'static, UART8>,
-) -> ! {
-    ingress.read_from(&mut reader).await;
-    defmt::panic!("ingress_task ended");
+async fn net_task(mut net_runner: NetRunner<'static>) -> ! {
+    net_runner.run().await
 }
 
 #[embassy_executor::task]
-async fn cellular_task(
-    runner: Runner<
+async fn cell_task(
+    mut cell_runner: PppRunner<
         'static,
-        atat::asynch::Client<'_, BufferedUartTx<'static, UART8>, { INGRESS_BUF_SIZE }>,
+        CellTransport,
         MyCelullarConfig,
-        { URC_CAPACITY },
+        INGRESS_BUF_SIZE,
+        URC_CAPACITY,
     >,
 ) -> ! {
-    runner.run().await
-}
-
-#[embassy_executor::task(pool_size = 3)]
-async fn blinky(mut led: AnyPin) {
-    let mut output = Output::new(led, Level::High, Speed::Low);
-    loop {
-        output.set_high();
-        Timer::after(Duration::from_millis(1000)).await;
-        output.set_low();
-        Timer::after(Duration::from_millis(1000)).await;
-    }
-}
-
-static EXECUTOR: StaticCell<Executor> = StaticCell::new();
-
-#[entry]
-fn main() -> ! {
-    info!("Hello World!");
-
-    let executor = EXECUTOR.init(Executor::new());
-
-    executor.run(|spawner| {
-        unwrap!(spawner.spawn(main_task(spawner)));
-    })
+    cell_runner.run().await
 }
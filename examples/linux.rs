@@ -0,0 +1,46 @@
+//! Bring up a modem on a serial port of a Linux host, and print its identity
+//! and signal quality once registered.
+//!
+//! `cargo run --example linux --features "std lara-r6" -- /dev/ttyUSB0`
+
+use embassy_futures::select::select;
+use static_cell::StaticCell;
+use ublox_cellular::asynch::{state::OperationState, std_transport::new_std, Resources};
+use ublox_cellular::config::{Apn, CellularConfig, NoPin};
+
+struct Config;
+
+impl CellularConfig<'static> for Config {
+    type ResetPin = NoPin;
+    type PowerPin = NoPin;
+    type VintPin = NoPin;
+    type DtrPin = NoPin;
+
+    const FLOW_CONTROL: bool = true;
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let port = std::env::args().nth(1).unwrap_or("/dev/ttyUSB0".into());
+    static RESOURCES: StaticCell<Resources<1024, 2>> = StaticCell::new();
+    let resources = RESOURCES.init(Resources::new());
+    let (control, runner) = new_std(&port, 115_200, resources, Config)?;
+
+    let app = async {
+        control.set_apn_config(Apn::Given {
+            name: "em".try_into().unwrap(),
+            username: None,
+            password: None,
+        });
+        control.set_desired_state(OperationState::Connected);
+        control
+            .wait_for_operation_state(OperationState::Connected)
+            .await;
+
+        println!("{:?}", control.identity().await);
+        println!("{:?}", control.get_signal_quality().await);
+    };
+
+    select(runner, app).await;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! The `linux` example on top of the blocking embedded-nal stack, as in
+//! applications written against the former `GsmClient`, plus a round trip
+//! with the u-blox echo server.
+//!
+//! `cargo run --example linux-blocking --features "std lara-r6 blocking" -- /dev/ttyUSB0`
+
+use core::pin::pin;
+
+use embedded_nal::{nb, TcpClientStack};
+use embedded_nal_async::{AddrType, Dns};
+use static_cell::StaticCell;
+use ublox_cellular::asynch::{
+    blocking::BlockingStack, state::OperationState, std_transport::new_std, Resources,
+};
+use ublox_cellular::config::{Apn, CellularConfig, NoPin};
+
+struct Config;
+
+impl CellularConfig<'static> for Config {
+    type ResetPin = NoPin;
+    type PowerPin = NoPin;
+    type VintPin = NoPin;
+    type DtrPin = NoPin;
+
+    const FLOW_CONTROL: bool = true;
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The serial port is a tokio one, whose reactor is driven by the workers
+    // while the superloop below blocks
+    let rt = tokio::runtime::Runtime::new()?;
+    let _guard = rt.enter();
+
+    let port = std::env::args().nth(1).unwrap_or("/dev/ttyUSB0".into());
+    static RESOURCES: StaticCell<Resources<1024, 2>> = StaticCell::new();
+    let resources = RESOURCES.init(Resources::new());
+    let (control, runner) = new_std(&port, 115_200, resources, Config)?;
+    let runner = pin!(runner);
+    let mut stack = BlockingStack::new(&control, runner);
+
+    control.set_apn_config(Apn::Given {
+        name: "em".try_into().unwrap(),
+        username: None,
+        password: None,
+    });
+    control.set_desired_state(OperationState::Connected);
+    stack.block_on(control.wait_for_operation_state(OperationState::Connected));
+
+    println!("{:?}", stack.block_on(control.identity()));
+    println!("{:?}", stack.block_on(control.get_signal_quality()));
+
+    let ip = stack
+        .block_on(control.get_host_by_name("echo.u-blox.com", AddrType::IPv4))
+        .expect("DNS lookup");
+    let mut socket = stack.socket().expect("socket");
+    nb::block!(stack.connect(&mut socket, (ip, 7).into())).expect("connect");
+    nb::block!(stack.send(&mut socket, b"Hello, echo!")).expect("send");
+
+    let mut buf = [0u8; 64];
+    loop {
+        // Keeps the runner going while waiting for the echo
+        match stack.receive(&mut socket, &mut buf) {
+            Ok(read) => {
+                println!("{:?}", core::str::from_utf8(&buf[..read]));
+                break;
+            }
+            Err(nb::Error::WouldBlock) => std::thread::sleep(std::time::Duration::from_millis(10)),
+            Err(nb::Error::Other(e)) => panic!("receive: {:?}", e),
+        }
+    }
+    stack.close(socket).expect("close");
+    Ok(())
+}
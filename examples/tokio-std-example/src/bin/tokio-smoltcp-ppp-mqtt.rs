@@ -37,6 +37,7 @@ impl<'a> CellularConfig<'a> for MyCelullarConfig {
     type ResetPin = NoPin;
     type PowerPin = NoPin;
     type VintPin = NoPin;
+    type DtrPin = NoPin;
 
     const FLOW_CONTROL: bool = true;
 
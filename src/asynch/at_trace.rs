@@ -0,0 +1,339 @@
+//! Trace of the raw AT traffic, for debugging units in the field where the
+//! UART can't be probed. Everything written to and read from the AT channel
+//! of the module is handed to the [`AtTrace`] sink of
+//! [`CellularConfig::at_trace`](crate::config::CellularConfig::at_trace),
+//! along with the time it passed.
+//!
+//! Lines longer than [`AT_TRACE_LINE_LEN`] are cut short and end with `...`,
+//! so the payload of a binary socket write doesn't flood the sink.
+
+use core::cell::RefCell;
+
+use embassy_time::Instant;
+use embedded_io_async::{ErrorType, Read, Write};
+use heapless::{Deque, Vec};
+
+/// Bytes of a line passed on to the sink, the rest is cut.
+pub const AT_TRACE_LINE_LEN: usize = 64;
+
+const ELLIPSIS: &[u8] = b"...";
+
+/// Longest chunk handed to the sink at once.
+pub const AT_TRACE_CHUNK_LEN: usize = AT_TRACE_LINE_LEN + ELLIPSIS.len();
+
+/// Sink of the raw AT traffic.
+///
+/// Both are called from the runner, with the bytes of one line at most,
+/// possibly only a part of it as they come in. `timestamp` is the time the
+/// bytes passed.
+pub trait AtTrace {
+    /// Bytes written to the module
+    fn tx(&mut self, timestamp: Instant, bytes: &[u8]);
+    /// Bytes read from the module
+    fn rx(&mut self, timestamp: Instant, bytes: &[u8]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    Tx,
+    Rx,
+}
+
+/// Logs the traffic with defmt, at info level.
+#[cfg(feature = "defmt")]
+pub struct DefmtTrace;
+
+#[cfg(feature = "defmt")]
+impl AtTrace for DefmtTrace {
+    fn tx(&mut self, timestamp: Instant, bytes: &[u8]) {
+        defmt::info!(
+            "[{=u64}] AT> {:?}",
+            timestamp.as_micros(),
+            atat::helpers::LossyStr(bytes)
+        );
+    }
+
+    fn rx(&mut self, timestamp: Instant, bytes: &[u8]) {
+        defmt::info!(
+            "[{=u64}] AT< {:?}",
+            timestamp.as_micros(),
+            atat::helpers::LossyStr(bytes)
+        );
+    }
+}
+
+/// A chunk of traffic kept by a [`RingTrace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TraceRecord {
+    pub direction: Direction,
+    pub timestamp: Instant,
+    pub data: Vec<u8, AT_TRACE_CHUNK_LEN>,
+}
+
+/// Direction, timestamp and length of a record in a [`RingTrace`].
+const RECORD_HEADER_LEN: usize = 1 + 8 + 1;
+
+/// Keeps the latest traffic in `N` bytes of RAM, dropping the oldest records
+/// as new ones come in, to be dumped after a failure, eg. from a panic
+/// handler or over a debug shell.
+pub struct RingTrace<const N: usize> {
+    buf: Deque<u8, N>,
+}
+
+impl<const N: usize> RingTrace<N> {
+    const SIZE_CHECK: () = assert!(
+        N >= RECORD_HEADER_LEN + AT_TRACE_CHUNK_LEN,
+        "A RingTrace must hold at least one full record"
+    );
+
+    pub const fn new() -> Self {
+        let () = Self::SIZE_CHECK;
+        Self { buf: Deque::new() }
+    }
+
+    /// The records kept, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = TraceRecord> + '_ {
+        let mut bytes = self.buf.iter().copied();
+        core::iter::from_fn(move || {
+            let direction = match bytes.next()? {
+                0 => Direction::Tx,
+                _ => Direction::Rx,
+            };
+            let mut ticks = [0u8; 8];
+            for b in ticks.iter_mut() {
+                *b = bytes.next()?;
+            }
+            let len = bytes.next()? as usize;
+            let data = bytes.by_ref().take(len).collect();
+
+            Some(TraceRecord {
+                direction,
+                timestamp: Instant::from_ticks(u64::from_le_bytes(ticks)),
+                data,
+            })
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    fn push(&mut self, direction: Direction, timestamp: Instant, bytes: &[u8]) {
+        let bytes = &bytes[..bytes.len().min(AT_TRACE_CHUNK_LEN)];
+        let len = RECORD_HEADER_LEN + bytes.len();
+
+        while N - self.buf.len() < len {
+            self.pop_record();
+        }
+
+        let direction = match direction {
+            Direction::Tx => 0,
+            Direction::Rx => 1,
+        };
+        let header = core::iter::once(direction)
+            .chain(timestamp.as_ticks().to_le_bytes())
+            .chain(core::iter::once(bytes.len() as u8));
+        for b in header.chain(bytes.iter().copied()) {
+            // Room was made above
+            let _ = self.buf.push_back(b);
+        }
+    }
+
+    fn pop_record(&mut self) {
+        let len = self.buf.iter().nth(RECORD_HEADER_LEN - 1).copied();
+        let len = RECORD_HEADER_LEN + len.unwrap_or(0) as usize;
+        for _ in 0..len {
+            self.buf.pop_front();
+        }
+    }
+}
+
+impl<const N: usize> Default for RingTrace<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> AtTrace for RingTrace<N> {
+    fn tx(&mut self, timestamp: Instant, bytes: &[u8]) {
+        self.push(Direction::Tx, timestamp, bytes);
+    }
+
+    fn rx(&mut self, timestamp: Instant, bytes: &[u8]) {
+        self.push(Direction::Rx, timestamp, bytes);
+    }
+}
+
+/// Splits `bytes` into lines, ending in `\r` or `\n`, and hands them to
+/// `emit`, cut to [`AT_TRACE_LINE_LEN`]. `line_len` is the length of the
+/// line so far, as a line may come in over several calls.
+fn for_each_line(bytes: &[u8], line_len: &mut usize, mut emit: impl FnMut(&[u8])) {
+    for piece in bytes.split_inclusive(|&b| b == b'\r' || b == b'\n') {
+        let budget = AT_TRACE_LINE_LEN.saturating_sub(*line_len);
+        if budget >= piece.len() {
+            emit(piece);
+        } else if budget > 0 {
+            let mut chunk = Vec::<u8, AT_TRACE_CHUNK_LEN>::new();
+            // Fits, as `budget` is at most `AT_TRACE_LINE_LEN`
+            let _ = chunk.extend_from_slice(&piece[..budget]);
+            let _ = chunk.extend_from_slice(ELLIPSIS);
+            emit(&chunk);
+        }
+
+        *line_len = match piece.last() {
+            Some(&(b'\r' | b'\n')) => 0,
+            _ => *line_len + piece.len(),
+        };
+    }
+}
+
+struct TracerState<'a> {
+    sink: &'a mut dyn AtTrace,
+    tx_line_len: usize,
+    rx_line_len: usize,
+}
+
+/// The sink of a runner, shared by the writing and the reading half of the
+/// AT channel.
+pub(crate) struct Tracer<'a> {
+    state: RefCell<TracerState<'a>>,
+}
+
+impl<'a> Tracer<'a> {
+    pub(crate) fn new(sink: &'a mut dyn AtTrace) -> Self {
+        Self {
+            state: RefCell::new(TracerState {
+                sink,
+                tx_line_len: 0,
+                rx_line_len: 0,
+            }),
+        }
+    }
+
+    fn tx(&self, bytes: &[u8]) {
+        let now = Instant::now();
+        let state = &mut *self.state.borrow_mut();
+        for_each_line(bytes, &mut state.tx_line_len, |line| {
+            state.sink.tx(now, line)
+        });
+    }
+
+    fn rx(&self, bytes: &[u8]) {
+        let now = Instant::now();
+        let state = &mut *self.state.borrow_mut();
+        for_each_line(bytes, &mut state.rx_line_len, |line| {
+            state.sink.rx(now, line)
+        });
+    }
+}
+
+/// Passes the traffic of `inner` on to the tracer, if any.
+pub(crate) struct TracedIo<'t, 'a, T> {
+    inner: T,
+    tracer: Option<&'t Tracer<'a>>,
+}
+
+impl<'t, 'a, T> TracedIo<'t, 'a, T> {
+    pub(crate) fn new(inner: T, tracer: Option<&'t Tracer<'a>>) -> Self {
+        Self { inner, tracer }
+    }
+}
+
+impl<T: ErrorType> ErrorType for TracedIo<'_, '_, T> {
+    type Error = T::Error;
+}
+
+impl<T: Read> Read for TracedIo<'_, '_, T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.inner.read(buf).await?;
+        if let Some(tracer) = self.tracer {
+            tracer.rx(&buf[..len]);
+        }
+        Ok(len)
+    }
+}
+
+impl<T: Write> Write for TracedIo<'_, '_, T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = self.inner.write(buf).await?;
+        if let Some(tracer) = self.tracer {
+            tracer.tx(&buf[..len]);
+        }
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(chunks: &[&[u8]]) -> std::vec::Vec<std::vec::Vec<u8>> {
+        let mut line_len = 0;
+        let mut out = std::vec::Vec::new();
+        for chunk in chunks {
+            for_each_line(chunk, &mut line_len, |line| out.push(line.to_vec()));
+        }
+        out
+    }
+
+    #[test]
+    fn lines_are_split() {
+        assert_eq!(
+            lines(&[b"\r\n+CEREG: 1\r\n\r\nOK\r\n"]),
+            [
+                &b"\r"[..],
+                b"\n",
+                b"+CEREG: 1\r",
+                b"\n",
+                b"\r",
+                b"\n",
+                b"OK\r",
+                b"\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn long_lines_are_cut() {
+        let data = [b'A'; 1024];
+        let out = lines(&[b"AT+USOWR=0,1024\r", &data[..100], &data[100..], b"\r\nOK"]);
+
+        assert_eq!(out.len(), 4);
+        assert_eq!(out[0], b"AT+USOWR=0,1024\r");
+        assert_eq!(out[1].len(), AT_TRACE_CHUNK_LEN);
+        assert!(out[1].ends_with(ELLIPSIS));
+        assert_eq!(out[2], b"\n");
+        assert_eq!(out[3], b"OK");
+    }
+
+    #[test]
+    fn ring_drops_oldest() {
+        const LEN: usize = 2 * (RECORD_HEADER_LEN + AT_TRACE_CHUNK_LEN);
+        let mut ring = RingTrace::<LEN>::new();
+
+        ring.tx(Instant::from_ticks(1), b"AT\r");
+        ring.rx(Instant::from_ticks(2), b"OK\r");
+        assert_eq!(ring.records().count(), 2);
+
+        ring.tx(Instant::from_ticks(3), &[b'A'; AT_TRACE_CHUNK_LEN]);
+        ring.tx(Instant::from_ticks(4), &[b'B'; AT_TRACE_CHUNK_LEN]);
+
+        let records: std::vec::Vec<_> = ring.records().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].timestamp, Instant::from_ticks(3));
+        assert_eq!(records[1].direction, Direction::Tx);
+        assert_eq!(records[1].data.as_slice(), &[b'B'; AT_TRACE_CHUNK_LEN]);
+
+        ring.clear();
+        ring.rx(Instant::from_ticks(5), b"OK\r");
+        let record = ring.records().next().unwrap();
+        assert_eq!(record.direction, Direction::Rx);
+        assert_eq!(record.data.as_slice(), b"OK\r");
+    }
+}
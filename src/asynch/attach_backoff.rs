@@ -0,0 +1,200 @@
+//! Backoff between failed attempts to attach to the network and to activate
+//! the data context. Networks penalize devices that retry these too eagerly,
+//! so the delay grows by the [`RetryPolicy`] of the configuration, and
+//! rejections caused by the SIM or the subscription stop the attempts
+//! altogether.
+
+use embassy_time::{Duration, Instant};
+
+use crate::{command::psn::types::RejectCause, config::RetryPolicy};
+
+pub(crate) struct AttachBackoff {
+    /// Delay after the last failure, zero after a success
+    delay: Duration,
+    /// Failures since the last success
+    failures: u32,
+    next_attempt: Option<Instant>,
+    /// Failures over the lifetime of the runner, across resets of the module
+    total_failures: u32,
+    /// Cause of a rejection that retrying does not fix
+    rejection: Option<RejectCause>,
+}
+
+impl AttachBackoff {
+    pub(crate) const fn new() -> Self {
+        Self {
+            delay: Duration::from_ticks(0),
+            failures: 0,
+            next_attempt: None,
+            total_failures: 0,
+            rejection: None,
+        }
+    }
+
+    /// Account a failure at `now`, with the cause the network gave for it if
+    /// any. Returns when to attempt again, or the cause if attempts should
+    /// stop.
+    pub(crate) fn on_failure(
+        &mut self,
+        policy: &RetryPolicy,
+        now: Instant,
+        cause: Option<RejectCause>,
+    ) -> Result<Instant, RejectCause> {
+        self.total_failures = self.total_failures.saturating_add(1);
+
+        if let Some(cause) = cause.filter(RejectCause::is_permanent) {
+            self.rejection = Some(cause);
+            self.next_attempt = None;
+            return Err(cause);
+        }
+
+        self.delay = match cause {
+            Some(RejectCause::Congestion) => policy.max_delay.max(policy.base_delay),
+            _ => policy.next_delay(self.failures, self.delay, now.as_ticks()),
+        };
+        self.failures = self.failures.saturating_add(1);
+        let next = now
+            .checked_add(self.delay)
+            .unwrap_or(Instant::from_ticks(u64::MAX));
+        self.next_attempt = Some(next);
+        Ok(next)
+    }
+
+    /// The attempt succeeded, so the next failure starts over at the base
+    /// delay.
+    pub(crate) fn on_success(&mut self) {
+        self.delay = Duration::from_ticks(0);
+        self.failures = 0;
+        self.next_attempt = None;
+    }
+
+    /// Earliest time for the next attempt, `None` to attempt right away.
+    pub(crate) fn next_attempt(&self) -> Option<Instant> {
+        self.next_attempt
+    }
+
+    pub(crate) fn rejection(&self) -> Option<RejectCause> {
+        self.rejection
+    }
+
+    /// Allow attempts again after a permanent rejection, eg. once another SIM
+    /// is inserted.
+    pub(crate) fn clear_rejection(&mut self) {
+        self.rejection = None;
+    }
+
+    pub(crate) fn total_failures(&self) -> u32 {
+        self.total_failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_delay: Duration::from_secs(60),
+        ..RetryPolicy::ATTACH
+    };
+
+    #[test]
+    fn delays_grow_up_to_cap() {
+        let mut backoff = AttachBackoff::new();
+        let mut now = Instant::from_secs(1000);
+        let mut previous = POLICY.base_delay;
+
+        for _ in 0..20 {
+            let next = backoff.on_failure(&POLICY, now, None).unwrap();
+            let delay = next - now;
+            assert!(delay >= POLICY.base_delay, "{:?}", delay);
+            assert!(delay <= POLICY.max_delay, "{:?}", delay);
+            assert!(delay.as_ticks() <= previous.as_ticks() * 3);
+
+            previous = delay;
+            now = next + Duration::from_millis(1237);
+        }
+        assert_eq!(backoff.total_failures(), 20);
+        assert_eq!(backoff.rejection(), None);
+    }
+
+    #[test]
+    fn jitter_decorrelates() {
+        let mut a = AttachBackoff::new();
+        let mut b = AttachBackoff::new();
+
+        let delays: [Duration; 2] = [(&mut a, 1000), (&mut b, 1001)].map(|(backoff, secs)| {
+            let now = Instant::from_secs(secs);
+            backoff.on_failure(&POLICY, now, None).unwrap();
+            let now = Instant::from_millis(secs * 1000 + 7919);
+            backoff.on_failure(&POLICY, now, None).unwrap() - now
+        });
+        assert_ne!(delays[0], delays[1]);
+    }
+
+    #[test]
+    fn success_starts_over() {
+        let mut backoff = AttachBackoff::new();
+        let now = Instant::from_secs(1000);
+
+        backoff
+            .on_failure(&POLICY, now, Some(RejectCause::Congestion))
+            .unwrap();
+        backoff.on_success();
+        assert_eq!(backoff.next_attempt(), None);
+
+        let next = backoff.on_failure(&POLICY, now, None).unwrap();
+        assert!(next - now <= POLICY.base_delay * 3);
+        assert_eq!(backoff.total_failures(), 2);
+    }
+
+    #[test]
+    fn congestion_waits_longest() {
+        let mut backoff = AttachBackoff::new();
+        let now = Instant::from_secs(1000);
+
+        let next = backoff
+            .on_failure(&POLICY, now, Some(RejectCause::Congestion))
+            .unwrap();
+        assert_eq!(next, now + POLICY.max_delay);
+        assert_eq!(backoff.next_attempt(), Some(next));
+    }
+
+    #[test]
+    fn permanent_rejection_stops() {
+        let mut backoff = AttachBackoff::new();
+        let now = Instant::from_secs(1000);
+
+        backoff.on_failure(&POLICY, now, None).unwrap();
+        for cause in [
+            RejectCause::IllegalUe,
+            RejectCause::IllegalMe,
+            RejectCause::EpsServicesNotAllowed,
+            RejectCause::EpsAndNonEpsServicesNotAllowed,
+        ] {
+            assert_eq!(backoff.on_failure(&POLICY, now, Some(cause)), Err(cause));
+            assert_eq!(backoff.rejection(), Some(cause));
+            assert_eq!(backoff.next_attempt(), None);
+        }
+
+        backoff.clear_rejection();
+        assert_eq!(backoff.rejection(), None);
+        assert_eq!(backoff.total_failures(), 5);
+    }
+
+    #[test]
+    fn delay_saturates() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_ticks(u64::MAX / 2),
+            max_delay: Duration::MAX,
+            ..RetryPolicy::ATTACH
+        };
+        let mut backoff = AttachBackoff::new();
+        let now = Instant::from_ticks(u64::MAX - 10);
+        for _ in 0..4 {
+            assert_eq!(
+                backoff.on_failure(&policy, now, None),
+                Ok(Instant::from_ticks(u64::MAX))
+            );
+        }
+    }
+}
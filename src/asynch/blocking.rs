@@ -0,0 +1,354 @@
+//! Blocking [`embedded_nal`] stack on top of the sockets of the internal
+//! stack of the modem, for applications written against the blocking
+//! `TcpClientStack`/`UdpClientStack`/`UdpFullStack`/`Dns` traits, eg. of the
+//! former `GsmClient`.
+//!
+//! There is no executor to run the [`Runner`](super::Runner) next to the
+//! application, so [`BlockingStack`] drives it itself: each call runs the
+//! runner along with the operation until the latter is done, with
+//! [`embassy_futures::block_on`]. Between the calls, the runner only makes
+//! progress in [`BlockingStack::poll`], which the superloop of the
+//! application has to call regularly. Otherwise URCs, eg. announcing data or
+//! a closed socket, pile up and the watchdog of the runner takes the stalled
+//! module for hung on the next call.
+//!
+//! `receive` only blocks to read data announced by the module, and returns
+//! [`nb::Error::WouldBlock`] otherwise, so `nb::block!` keeps the runner going
+//! while waiting for data.
+
+use core::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+};
+
+use embassy_futures::{
+    block_on, poll_once,
+    select::{select, Either},
+};
+use embedded_nal::{nb, AddrType, Dns, TcpClientStack, UdpClientStack, UdpFullStack};
+
+use super::{
+    control::Control,
+    socket::{TcpSocket, UdpSocket},
+};
+use crate::{command::ip_transport_layer::types::SocketErrorKind, error::Error};
+
+/// Blocking [`TcpClientStack`], [`UdpFullStack`] and [`Dns`] of the internal
+/// stack of the modem, driving the runner future `R` of the [`Control`] it was given,
+/// see the [module docs](self).
+///
+/// ```ignore
+/// let mut runner = pin!(runner.run());
+/// let mut stack = BlockingStack::new(&control, runner.as_mut());
+///
+/// let mut socket = stack.socket()?;
+/// nb::block!(stack.connect(&mut socket, remote))?;
+/// ```
+pub struct BlockingStack<'c, 'a, 'r, R, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    runner: Pin<&'r mut R>,
+}
+
+impl<'c, 'a, 'r, R: Future, const INGRESS_BUF_SIZE: usize>
+    BlockingStack<'c, 'a, 'r, R, INGRESS_BUF_SIZE>
+{
+    pub fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>, runner: Pin<&'r mut R>) -> Self {
+        Self { control, runner }
+    }
+
+    /// Let the runner handle what came in since the last call, without
+    /// blocking. To be called regularly from the superloop.
+    pub fn poll(&mut self) {
+        let _ = block_on(poll_once(self.runner.as_mut()));
+    }
+
+    /// Run `fut`, eg. any call of the [`Control`], to completion, driving the
+    /// runner meanwhile.
+    pub fn block_on<T>(&mut self, fut: impl Future<Output = T>) -> T {
+        match block_on(select(self.runner.as_mut(), fut)) {
+            Either::First(_) => unreachable!("The runner never returns"),
+            Either::Second(res) => res,
+        }
+    }
+
+    /// Whether data may be waiting for `handle`, without asking the module
+    /// once it told there is none, until it announces more.
+    fn maybe_readable(&mut self, handle: ublox_sockets::SocketHandle) -> bool {
+        self.poll();
+        self.control.is_socket_closed(handle)
+            || self.control.state_ch.socket_available(handle) != Some(0)
+    }
+}
+
+impl<'c, 'a, R: Future, const INGRESS_BUF_SIZE: usize> TcpClientStack
+    for BlockingStack<'c, 'a, '_, R, INGRESS_BUF_SIZE>
+{
+    type TcpSocket = TcpSocket<'c, 'a, INGRESS_BUF_SIZE>;
+    type Error = Error;
+
+    fn socket(&mut self) -> Result<Self::TcpSocket, Error> {
+        Ok(self.control.tcp_socket())
+    }
+
+    fn connect(
+        &mut self,
+        socket: &mut Self::TcpSocket,
+        remote: SocketAddr,
+    ) -> nb::Result<(), Error> {
+        Ok(self.block_on(socket.connect(remote.ip().into(), remote.port()))?)
+    }
+
+    fn send(&mut self, socket: &mut Self::TcpSocket, buffer: &[u8]) -> nb::Result<usize, Error> {
+        match self.block_on(socket.write(buffer)) {
+            Err(Error::Socket(SocketErrorKind::WouldBlock)) => Err(nb::Error::WouldBlock),
+            res => Ok(res?),
+        }
+    }
+
+    fn receive(
+        &mut self,
+        socket: &mut Self::TcpSocket,
+        buffer: &mut [u8],
+    ) -> nb::Result<usize, Error> {
+        let handle = socket.handle().ok_or(Error::InvalidStateTransition)?;
+        if !self.maybe_readable(handle) {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        let control = self.control;
+        match self.block_on(control.read_socket(handle, buffer))? {
+            0 if buffer.is_empty() => Ok(0),
+            0 if control.is_socket_closed(handle) => Err(nb::Error::Other(Error::Socket(
+                SocketErrorKind::ConnectionReset,
+            ))),
+            0 => Err(nb::Error::WouldBlock),
+            read => Ok(read),
+        }
+    }
+
+    fn close(&mut self, socket: Self::TcpSocket) -> Result<(), Error> {
+        self.block_on(socket.close())
+    }
+}
+
+/// UDP socket of [`BlockingStack`], sending to the peer it was connected to,
+/// or to any peer once bound.
+pub struct BlockingUdpSocket<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    socket: UdpSocket<'c, 'a, INGRESS_BUF_SIZE>,
+    remote: Option<SocketAddr>,
+}
+
+impl<'c, 'a, R: Future, const INGRESS_BUF_SIZE: usize> UdpClientStack
+    for BlockingStack<'c, 'a, '_, R, INGRESS_BUF_SIZE>
+{
+    type UdpSocket = BlockingUdpSocket<'c, 'a, INGRESS_BUF_SIZE>;
+    type Error = Error;
+
+    fn socket(&mut self) -> Result<Self::UdpSocket, Error> {
+        Ok(BlockingUdpSocket {
+            socket: self.control.udp_socket(),
+            remote: None,
+        })
+    }
+
+    fn connect(&mut self, socket: &mut Self::UdpSocket, remote: SocketAddr) -> Result<(), Error> {
+        self.block_on(socket.socket.bind(None))?;
+        socket.remote = Some(remote);
+        Ok(())
+    }
+
+    fn send(&mut self, socket: &mut Self::UdpSocket, buffer: &[u8]) -> nb::Result<(), Error> {
+        let remote = socket.remote.ok_or(Error::InvalidStateTransition)?;
+        match self.block_on(socket.socket.send_to(remote, buffer)) {
+            Err(Error::Socket(SocketErrorKind::WouldBlock)) => Err(nb::Error::WouldBlock),
+            res => Ok(res?),
+        }
+    }
+
+    fn receive(
+        &mut self,
+        socket: &mut Self::UdpSocket,
+        buffer: &mut [u8],
+    ) -> nb::Result<(usize, SocketAddr), Error> {
+        let handle = socket
+            .socket
+            .handle()
+            .ok_or(Error::InvalidStateTransition)?;
+        if !self.maybe_readable(handle) {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        // A closed socket fails in `recv_from`
+        let control = self.control;
+        if !control.is_socket_closed(handle)
+            && control.state_ch.socket_available(handle).is_none()
+            && self.block_on(control.udp_socket_available(handle))? == 0
+        {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(self.block_on(socket.socket.recv_from(buffer))?)
+    }
+
+    fn close(&mut self, socket: Self::UdpSocket) -> Result<(), Error> {
+        self.block_on(socket.socket.close())
+    }
+}
+
+impl<R: Future, const INGRESS_BUF_SIZE: usize> UdpFullStack
+    for BlockingStack<'_, '_, '_, R, INGRESS_BUF_SIZE>
+{
+    fn bind(&mut self, socket: &mut Self::UdpSocket, local_port: u16) -> Result<(), Error> {
+        self.block_on(socket.socket.bind(Some(local_port)))?;
+        socket.remote = None;
+        Ok(())
+    }
+
+    fn send_to(
+        &mut self,
+        socket: &mut Self::UdpSocket,
+        remote: SocketAddr,
+        buffer: &[u8],
+    ) -> nb::Result<(), Error> {
+        match self.block_on(socket.socket.send_to(remote, buffer)) {
+            Err(Error::Socket(SocketErrorKind::WouldBlock)) => Err(nb::Error::WouldBlock),
+            res => Ok(res?),
+        }
+    }
+}
+
+/// Resolves names with +UDNSRN, as the `Dns` of the [`Control`].
+impl<R: Future, const INGRESS_BUF_SIZE: usize> Dns
+    for BlockingStack<'_, '_, '_, R, INGRESS_BUF_SIZE>
+{
+    type Error = Error;
+
+    fn get_host_by_name(
+        &mut self,
+        hostname: &str,
+        addr_type: AddrType,
+    ) -> nb::Result<IpAddr, Error> {
+        let addr_type = match addr_type {
+            AddrType::IPv4 => embedded_nal_async::AddrType::IPv4,
+            AddrType::IPv6 => embedded_nal_async::AddrType::IPv6,
+            AddrType::Either => embedded_nal_async::AddrType::Either,
+        };
+        let control = self.control;
+        Ok(self.block_on(embedded_nal_async::Dns::get_host_by_name(
+            control, hostname, addr_type,
+        ))?)
+    }
+
+    fn get_host_by_address(&mut self, addr: IpAddr, result: &mut [u8]) -> nb::Result<usize, Error> {
+        let control = self.control;
+        Ok(self.block_on(embedded_nal_async::Dns::get_host_by_address(
+            control, addr, result,
+        ))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{net::Ipv4Addr, pin::pin};
+
+    use embassy_time::Duration;
+
+    use super::*;
+    use crate::asynch::modem_sim::{Fixture, Step, OK};
+
+    /// A bound socket sends to and receives from any peer, with the receive
+    /// keeping the runner going until +UUSORF announces the datagram.
+    #[test]
+    fn udp_full_stack() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=17,5000",
+                response: b"\r\n+USOCR: 0,17,0\r\n\r\nOK\r\n",
+            },
+            Step::Upload {
+                cmd: b"AT+USOST=0,",
+                prompt: b"@",
+                len: 3,
+                response: b"\r\n+USOST: 0,3\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,0",
+                response: b"\r\n+USORF: 0,0\r\n\r\nOK\r\n",
+            },
+            Step::Urc {
+                delay: Duration::from_millis(20),
+                urc: b"+UUSORF: 0,2",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,",
+                response: b"\r\n+USORF: 0,\"10.0.0.2\",123,2,\"4F4B\"\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCL=0",
+                response: OK,
+            },
+        ];
+        let peer = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 53);
+        let mut buf = [0u8; 16];
+        let received = {
+            let runner = pin!(io.serve(&mut sim, &script));
+            let mut stack = BlockingStack::new(&control, runner);
+
+            let mut socket = UdpClientStack::socket(&mut stack).unwrap();
+            stack.bind(&mut socket, 5000).unwrap();
+            nb::block!(stack.send_to(&mut socket, peer, b"abc")).unwrap();
+            let received = nb::block!(UdpClientStack::receive(&mut stack, &mut socket, &mut buf));
+            UdpClientStack::close(&mut stack, socket).unwrap();
+            received
+        };
+
+        assert_eq!(sim.uploaded.as_slice(), b"abc");
+        assert_eq!(
+            received,
+            Ok((
+                2,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 123)
+            ))
+        );
+        assert_eq!(&buf[..2], b"OK");
+        sim.assert_idle();
+    }
+
+    /// Names resolve both ways through the runner driven by the stack.
+    #[test]
+    fn dns() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+UDNSRN=0,\"echo.u-blox.com\"",
+                response: b"\r\n+UDNSRN: \"195.34.89.241\"\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+UDNSRN=1,\"195.34.89.241\"",
+                response: b"\r\n+UDNSRN: \"echo.u-blox.com\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let ip = IpAddr::V4(Ipv4Addr::new(195, 34, 89, 241));
+        let mut name = [0u8; 32];
+        let (resolved, len) = {
+            let runner = pin!(io.serve(&mut sim, &script));
+            let mut stack = BlockingStack::new(&control, runner);
+
+            let resolved = nb::block!(stack.get_host_by_name("echo.u-blox.com", AddrType::IPv4));
+            let len = nb::block!(stack.get_host_by_address(ip, &mut name));
+            (resolved, len)
+        };
+
+        assert_eq!(resolved, Ok(ip));
+        assert_eq!(len, Ok(15));
+        assert_eq!(&name[..15], b"echo.u-blox.com");
+        sim.assert_idle();
+    }
+}
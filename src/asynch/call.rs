@@ -0,0 +1,165 @@
+//! Voice calls. The module reports little about a call in progress, so its
+//! state is polled with +CLCC; only an incoming call is announced, with the
+//! RING and +CLIP URCs.
+
+use embassy_time::{Duration, Timer};
+use heapless::Vec;
+
+use crate::{
+    command::call_control::{
+        responses::CallStatus,
+        types::{CallDirection, CallState, CallingLineIdentificationMode, DtmfTone},
+        AnswerCall, DialVoice, HangUp, ListCurrentCalls, SendDtmf, SetCallingLineIdentification,
+        MAX_CALLS,
+    },
+    error::{Error, GenericError},
+    modules::supports_voice,
+};
+
+use super::control::Control;
+
+/// Interval of the +CLCC polls while waiting for a call to change state.
+const CALL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Voice call control, obtained through [`Control::call`].
+///
+/// The data-only variants (LARA-L6004D, LARA-R6001D and LARA-R6401D) answer
+/// every call with [`GenericError::Unsupported`]. Until the runner read the
+/// model of the module, every call fails with [`Error::Uninitialized`].
+pub struct CallService<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> CallService<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self { control }
+    }
+
+    /// Start a voice call to `number`. Returns the call as listed by +CLCC
+    /// once its setup is started, or `None` if it was released right away.
+    pub async fn dial(&self, number: &str) -> Result<Option<CallStatus>, Error> {
+        self.check_voice()?;
+        if number.len() > 32 {
+            return Err(Error::Overflow);
+        }
+
+        self.control
+            .send(&DialVoice {
+                number: atat::serde_bytes::Bytes::new(number.as_bytes()),
+            })
+            .await?;
+
+        Ok(self
+            .calls()
+            .await?
+            .into_iter()
+            .find(|call| call.direction == CallDirection::MobileOriginated))
+    }
+
+    /// Answer the incoming call.
+    pub async fn answer(&self) -> Result<(), Error> {
+        self.check_voice()?;
+        self.control.send(&AnswerCall).await?;
+        Ok(())
+    }
+
+    /// Hang up the active call, or reject the incoming one.
+    pub async fn hang_up(&self) -> Result<(), Error> {
+        self.check_voice()?;
+        self.control.send(&HangUp).await?;
+        Ok(())
+    }
+
+    /// Send `tones` as DTMF to the remote party of the active call.
+    pub async fn send_dtmf(&self, tones: &[DtmfTone]) -> Result<(), Error> {
+        self.check_voice()?;
+        for &tone in tones {
+            self.control.send(&SendDtmf { tone }).await?;
+        }
+        Ok(())
+    }
+
+    /// The current calls of the module.
+    pub async fn calls(&self) -> Result<Vec<CallStatus, MAX_CALLS>, Error> {
+        self.check_voice()?;
+        self.control.send(&ListCurrentCalls).await
+    }
+
+    /// Enable or disable the number of the calling party in the +CLIP URC.
+    pub async fn set_caller_id(&self, enabled: bool) -> Result<(), Error> {
+        self.check_voice()?;
+        let mode = if enabled {
+            CallingLineIdentificationMode::Enabled
+        } else {
+            CallingLineIdentificationMode::Disabled
+        };
+        self.control
+            .send(&SetCallingLineIdentification { mode })
+            .await?;
+        Ok(())
+    }
+
+    /// Wait for an incoming call, to [`answer`](Self::answer) or reject with
+    /// [`hang_up`](Self::hang_up).
+    pub async fn wait_incoming(&self) -> Result<CallStatus, Error> {
+        self.check_voice()?;
+        loop {
+            self.control.state_ch.wait_ringing().await;
+
+            // The RING may be stale, with the caller already gone
+            if let Some(call) = self
+                .calls()
+                .await?
+                .into_iter()
+                .find(|call| matches!(call.state, CallState::Incoming | CallState::Waiting))
+            {
+                return Ok(call);
+            }
+        }
+    }
+
+    /// Wait for the call with the identification number `id` to leave
+    /// `state`. Returns the call in its new state, or `None` once it is
+    /// released.
+    pub async fn wait_call_change(
+        &self,
+        id: u8,
+        state: CallState,
+    ) -> Result<Option<CallStatus>, Error> {
+        loop {
+            match self.calls().await?.into_iter().find(|call| call.id == id) {
+                Some(call) if call.state == state => Timer::after(CALL_POLL_INTERVAL).await,
+                call => return Ok(call),
+            }
+        }
+    }
+
+    fn check_voice(&self) -> Result<(), Error> {
+        let model = self.control.state_ch.identity().model;
+        if model.is_empty() {
+            Err(Error::Uninitialized)
+        } else if supports_voice(&model) {
+            Ok(())
+        } else {
+            Err(Error::Generic(GenericError::Unsupported))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asynch::modem_sim::Fixture;
+
+    /// Nothing is sent to a module whose model is not known yet, as it may be
+    /// a data-only variant.
+    #[test]
+    fn unknown_model() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let res = io.play(&mut sim, &[], control.call().dial("+4512345678"));
+        assert_eq!(res, Err(Error::Uninitialized));
+    }
+}
@@ -1,62 +1,283 @@
-use core::cell::Cell;
+use core::{cell::Cell, future::Future, net::IpAddr};
 
 use atat::{asynch::AtatClient, response_slot::ResponseSlotGuard};
-use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Sender, mutex::Mutex};
-use embassy_time::{with_timeout, Duration, Timer};
+use embassy_sync::{
+    blocking_mutex::raw::NoopRawMutex,
+    channel::Sender,
+    mutex::{Mutex, MutexGuard},
+};
+use embassy_time::{with_deadline, with_timeout, Duration, Instant, Timer};
 
 use crate::{
     command::{
-        general::{types::FirmwareVersion, GetCCID, GetFirmwareVersion},
+        device_data_security::{
+            profile::SecurityProfileBuilder,
+            responses::SecurityDataImport,
+            types::{SecurityDataType, SecurityProfileId},
+            PrepareSecurityDataImport, SendSecurityDataImport,
+        },
+        device_lock::{
+            types::PinStatusCode, ChangePassword, ChangePin, GetPinCounter, GetPinStatus, SetPin,
+        },
+        general::{types::FirmwareVersion, GetCCID, GetCIMI, GetFirmwareVersion, GetIMEI},
         gpio::{types::GpioMode, ReadGpioPin, SetGpioConfiguration},
+        mobile_control::{
+            responses::{ExtendedErrorReport, IndicatorControl},
+            types::CmeError,
+            GetIndicatorControl,
+        },
         network_service::{
             responses::{OperatorSelection, SignalQuality},
-            types::RatAct,
-            GetOperatorSelection, GetSignalQuality,
+            types::{
+                CellEnvironment, NeighborCell, OperatorList, OperatorSelectionMode, Plmn, RatAct,
+                MAX_NEIGHBOR_CELLS,
+            },
+            GetCellEnvironment, GetCellInfo, GetNetworkRegistrationStatus, GetOperatorSelection,
+            GetSignalQuality, GetSubscriberNumber, ScanOperators, SetCellEnvironmentReporting,
+            SetOperatorSelection,
         },
-        psn::GetPDPContextDefinition,
+        psn::{
+            responses::DataCounters,
+            types::{ContextId, PDPContextStatus, PdpContextInfo, RejectCause},
+            GetDataCounters, GetEPSNetworkRegistrationStatus, GetGPRSNetworkRegistrationStatus,
+            GetPDPContextDefinition, GetPDPContextState, SetDataCounters,
+        },
+        sim_access::{types::SimCommand, RestrictedSimAccess},
+        system_features::{
+            types::FirmwareInstallError, GetTemperature, InstallFirmware, PrevalidateFirmware,
+        },
+    },
+    config::{Apn, DIAGNOSTICS_CAPACITY, MAX_DIAGNOSTICS_SUBSCRIBERS, MAX_STATE_RECEIVERS},
+    error::{ConfigError, Error, InitError},
+};
+
+#[cfg(feature = "internal-network-stack")]
+use core::net::SocketAddr;
+
+#[cfg(feature = "internal-network-stack")]
+use crate::command::ip_transport_layer::{
+    responses::INGRESS_CHUNK_SIZE,
+    types::{
+        DataConfiguration, EgressData, RemoteAddr, SocketControlParam, SocketErrorKind,
+        SocketOption, SocketOptionName, SocketProtocol, TcpSocketStatus,
     },
-    config::Apn,
-    error::Error,
+    CloseSocket, ConnectSocket, CreateSocket, GetSocketOption, GetUDPSocketDataAvailable,
+    PrepareUDPSendToDataBinary, PrepareWriteSocketDataBinary, ReadUDPSocketData,
+    ReadUDPSocketDataBinary, SetDataConfiguration, SetSocketOption, SocketControl,
+    UDPSendToDataBinary, WriteSocketDataBinary, EGRESS_CHUNK_SIZE, UDP_EGRESS_CHUNK_SIZE,
+};
+
+#[cfg(feature = "internal-network-stack")]
+use crate::command::dns::{types::ResolutionType, ResolveNameIp, MAX_DNS_RESULTS};
+#[cfg(feature = "http")]
+use crate::command::http::types::HttpProfileId;
+#[cfg(feature = "lwm2m")]
+use crate::command::lwm2m::{
+    types::Lwm2mClientMode, GetLwm2mClient, SetLwm2mClient, UpdateLwm2mRegistration,
 };
+#[cfg(feature = "internal-network-stack")]
+use crate::config::MAX_SOCKETS;
+#[cfg(feature = "internal-network-stack")]
+use crate::error::GenericError;
+use crate::modules::ModuleParams as _;
+#[cfg(feature = "internal-network-stack")]
+use crate::modules::SocketContextBinding;
 
+#[cfg(feature = "audio")]
+use super::call::CallService;
+#[cfg(feature = "gnss")]
+use super::gnss::Gnss;
+#[cfg(feature = "http")]
+use super::http::HttpClient;
+#[cfg(feature = "mqtt")]
+use super::mqtt::MqttClient;
+#[cfg(feature = "sms")]
+use super::sms::SmsService;
 use super::{
-    runner::MAX_CMD_LEN,
-    state::{self, LinkState, OperationState},
+    diagnostics::{command_name, DiagnosticEvent, Diagnostics, DiagnosticsSubscriber},
+    digester::{CustomUrc, CustomUrcChannel, ErrorCode},
+    factory_test::FactoryTest,
+    file_system::FileSystemService,
+    progress::{Progress, ProgressReceiver},
+    runner::{OnDrop, MAX_CMD_LEN},
+    state::{
+        self, ErrorRecord, FirmwareInstallState, Identity, LinkState, LinkStateReceiver,
+        OperationState, OperationStateReceiver, OperatorScan, PsmTimers, RecoveryAction,
+        RegistrationStatus, ShutdownReport, SimState, SimStateReceiver, StateStats,
+        MAX_RECENT_ERRORS,
+    },
+};
+#[cfg(feature = "internal-network-stack")]
+use super::{
+    socket::{SocketSetStats, TcpClient, TcpSocket, UdpSocket},
+    socket_error, socket_ingress,
 };
 
+/// Time a single command of [`Control::diagnostic_snapshot`] may take.
+const SNAPSHOT_CMD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time all commands of [`Control::diagnostic_snapshot`] may take together.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Smallest chunk a write rejected by the module is split down to, before
+/// giving up.
+#[cfg(feature = "internal-network-stack")]
+const MIN_WRITE_CHUNK: usize = 64;
+
+/// Maximum number of retries of a rejected write, each with half the chunk.
+#[cfg(feature = "internal-network-stack")]
+const MAX_WRITE_RETRIES: u8 = 4;
+
+/// Time for the module to drain its buffer, before retrying a rejected write.
+#[cfg(feature = "internal-network-stack")]
+pub(crate) const WRITE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Whether a write was rejected as the buffer of the module is full, rather
+/// than failed.
+#[cfg(feature = "internal-network-stack")]
+fn is_buffer_full(e: &Error) -> bool {
+    matches!(
+        e,
+        Error::Socket(SocketErrorKind::WouldBlock | SocketErrorKind::NoBuffers)
+            | Error::Atat(atat::Error::CmeError(atat::CmeError::OperationNotAllowed))
+    )
+}
+
+/// Lock of the AT channel. It is taken for every command, and held across
+/// the commands of a sequence that must not be interleaved with others, eg.
+/// +USOWR and the data following its prompt.
+///
+/// It holds the time the response of a command is due by, if the command was
+/// cancelled while waiting for it. The module answers it before taking
+/// another command, so the response must not be taken for the next one.
+pub(crate) type AtLock = Mutex<NoopRawMutex, Option<Instant>>;
+
 pub(crate) struct ProxyClient<'a, const INGRESS_BUF_SIZE: usize> {
-    pub(crate) req_sender:
-        Mutex<NoopRawMutex, Sender<'a, NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>>,
+    pub(crate) req_sender: Sender<'a, NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
     pub(crate) res_slot: &'a atat::ResponseSlot<INGRESS_BUF_SIZE>,
+    at_lock: &'a AtLock,
     cooldown_timer: Cell<Option<Timer>>,
+    diagnostics: &'a Diagnostics,
+    /// Commands in a row whose response timed out
+    timeouts: Cell<u8>,
 }
 
 impl<'a, const INGRESS_BUF_SIZE: usize> ProxyClient<'a, INGRESS_BUF_SIZE> {
     pub fn new(
         req_sender: Sender<'a, NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
         res_slot: &'a atat::ResponseSlot<INGRESS_BUF_SIZE>,
+        at_lock: &'a AtLock,
+        diagnostics: &'a Diagnostics,
     ) -> Self {
         Self {
-            req_sender: Mutex::new(req_sender),
+            req_sender,
             res_slot,
+            at_lock,
             cooldown_timer: Cell::new(None),
+            diagnostics,
+            timeouts: Cell::new(0),
+        }
+    }
+
+    /// Number of commands in a row whose response timed out, reset by any
+    /// response.
+    pub(crate) fn timeouts(&self) -> u8 {
+        self.timeouts.get()
+    }
+
+    pub(crate) fn clear_timeouts(&self) {
+        self.timeouts.set(0);
+    }
+
+    fn count_timeout(&self) {
+        self.timeouts.set(self.timeouts.get().saturating_add(1));
+    }
+
+    /// Take the AT channel for a sequence of commands. Commands of every
+    /// other client sharing the lock wait until the returned client is
+    /// dropped.
+    pub(crate) async fn lock(&self) -> LockedClient<'_, 'a, INGRESS_BUF_SIZE> {
+        LockedClient {
+            client: self,
+            abandoned: self.at_lock.lock().await,
         }
     }
 
     async fn wait_response(
         &self,
+        abandoned: &mut Option<Instant>,
         timeout: Duration,
     ) -> Result<ResponseSlotGuard<'_, INGRESS_BUF_SIZE>, atat::Error> {
-        with_timeout(timeout, self.res_slot.get())
+        // Left set if the caller is cancelled before the response is in
+        *abandoned = Some(Instant::now() + timeout);
+        let res = with_timeout(timeout, self.res_slot.get())
             .await
-            .map_err(|_| atat::Error::Timeout)
+            .map_err(|_| atat::Error::Timeout);
+        *abandoned = None;
+        res
     }
-}
 
-impl<'a, const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
-    for &ProxyClient<'a, INGRESS_BUF_SIZE>
-{
-    async fn send<Cmd: atat::AtatCmd>(&mut self, cmd: &Cmd) -> Result<Cmd::Response, atat::Error> {
+    /// Wait for the response of a cancelled command, see [`AtLock`].
+    async fn drain_abandoned(&self, abandoned: &mut Option<Instant>) {
+        if let Some(deadline) = *abandoned {
+            warn!("Waiting for the response of a cancelled command");
+            let _ = with_deadline(deadline, self.res_slot.get()).await;
+            *abandoned = None;
+        }
+    }
+
+    /// Send `msg` as is, and copy the raw response into `response_buf`.
+    async fn send_raw(
+        &self,
+        msg: heapless::Vec<u8, MAX_CMD_LEN>,
+        raw_mode: &Cell<bool>,
+        timeout: Duration,
+        response_buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        info!("🔧 Raw AT Command: {:?}", atat::helpers::LossyStr(&msg));
+
+        let mut abandoned = self.at_lock.lock().await;
+
+        if let Some(cooldown) = self.cooldown_timer.take() {
+            cooldown.await
+        }
+
+        self.drain_abandoned(&mut abandoned).await;
+        self.res_slot.reset();
+
+        // The digester leaves raw mode by itself once the response is in,
+        // but not if it never arrives, or the call is cancelled
+        raw_mode.set(true);
+        let _raw_mode = OnDrop::new(|| raw_mode.set(false));
+        async {
+            with_timeout(Duration::from_secs(1), self.req_sender.send(msg))
+                .await
+                .map_err(|_| atat::Error::Timeout)?;
+
+            self.cooldown_timer.set(Some(Timer::after_millis(20)));
+
+            let response = self.wait_response(&mut abandoned, timeout).await?;
+            let response: &atat::Response<INGRESS_BUF_SIZE> = &response.borrow();
+            let response: Result<&[u8], _> = response.into();
+            let response = response.map_err(atat::Error::from)?;
+
+            let len = response.len().min(response_buf.len());
+            response_buf[..len].copy_from_slice(&response[..len]);
+            if len < response.len() {
+                return Err(Error::Overflow);
+            }
+            Ok(len)
+        }
+        .await
+    }
+
+    /// Send `cmd` and wait for its response. The caller holds the lock, with
+    /// `abandoned` in it.
+    async fn exchange<Cmd: atat::AtatCmd>(
+        &self,
+        abandoned: &mut Option<Instant>,
+        cmd: &Cmd,
+    ) -> Result<Cmd::Response, atat::Error> {
         let mut buf = [0u8; MAX_CMD_LEN];
         let len = cmd.write(&mut buf);
 
@@ -74,7 +295,7 @@ impl<'a, const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
             cooldown.await
         }
 
-        let sender = self.req_sender.lock().await;
+        self.drain_abandoned(abandoned).await;
 
         // Clear any stale response signal left over from prior commands or
         // late URC-like traffic, so wait_response below returns our command's
@@ -83,16 +304,19 @@ impl<'a, const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
 
         with_timeout(
             Duration::from_secs(1),
-            sender.send(heapless::Vec::try_from(&buf[..len]).unwrap()),
+            self.req_sender
+                .send(heapless::Vec::try_from(&buf[..len]).unwrap()),
         )
         .await
-        .map_err(|_| atat::Error::Timeout)?;
+        .map_err(|_| {
+            self.count_timeout();
+            atat::Error::Timeout
+        })?;
 
         self.cooldown_timer.set(Some(Timer::after_millis(20)));
 
         if !Cmd::EXPECTS_RESPONSE_CODE {
             debug!("AT Command expects no response, parsing empty response");
-            drop(sender);
             cmd.parse(Ok(&[]))
         } else {
             debug!(
@@ -100,11 +324,15 @@ impl<'a, const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
                 Cmd::MAX_TIMEOUT_MS
             );
             let response = self
-                .wait_response(Duration::from_millis(Cmd::MAX_TIMEOUT_MS.into()))
-                .await?;
-
-            // Release sender lock after receiving response
-            drop(sender);
+                .wait_response(abandoned, Duration::from_millis(Cmd::MAX_TIMEOUT_MS.into()))
+                .await
+                .inspect_err(|_| {
+                    self.count_timeout();
+                    self.diagnostics.publish(DiagnosticEvent::CommandTimeout {
+                        cmd: command_name::<Cmd>(),
+                    })
+                })?;
+            self.timeouts.set(0);
 
             let response: &atat::Response<INGRESS_BUF_SIZE> = &response.borrow();
             let response_result: Result<&[u8], _> = response.into();
@@ -127,9 +355,35 @@ impl<'a, const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
     }
 }
 
+impl<'a, const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
+    for &ProxyClient<'a, INGRESS_BUF_SIZE>
+{
+    async fn send<Cmd: atat::AtatCmd>(&mut self, cmd: &Cmd) -> Result<Cmd::Response, atat::Error> {
+        let mut abandoned = self.at_lock.lock().await;
+        self.exchange(&mut abandoned, cmd).await
+    }
+}
+
+/// A [`ProxyClient`] holding the AT channel, see [`ProxyClient::lock`].
+pub(crate) struct LockedClient<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    client: &'c ProxyClient<'a, INGRESS_BUF_SIZE>,
+    abandoned: MutexGuard<'c, NoopRawMutex, Option<Instant>>,
+}
+
+impl<const INGRESS_BUF_SIZE: usize> atat::asynch::AtatClient
+    for LockedClient<'_, '_, INGRESS_BUF_SIZE>
+{
+    async fn send<Cmd: atat::AtatCmd>(&mut self, cmd: &Cmd) -> Result<Cmd::Response, atat::Error> {
+        self.client.exchange(&mut self.abandoned, cmd).await
+    }
+}
+
 pub struct Control<'a, const INGRESS_BUF_SIZE: usize> {
-    state_ch: state::Runner<'a>,
+    pub(super) state_ch: state::Runner<'a>,
     at_client: ProxyClient<'a, INGRESS_BUF_SIZE>,
+    raw_mode: &'a Cell<bool>,
+    error_code: &'a Cell<Option<ErrorCode>>,
+    custom_urcs: &'a CustomUrcChannel,
 }
 
 impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
@@ -137,10 +391,18 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
         state_ch: state::Runner<'a>,
         req_sender: Sender<'a, NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
         res_slot: &'a atat::ResponseSlot<INGRESS_BUF_SIZE>,
+        raw_mode: &'a Cell<bool>,
+        error_code: &'a Cell<Option<ErrorCode>>,
+        custom_urcs: &'a CustomUrcChannel,
+        at_lock: &'a AtLock,
     ) -> Self {
+        let at_client = ProxyClient::new(req_sender, res_slot, at_lock, state_ch.diagnostics());
         Self {
             state_ch,
-            at_client: ProxyClient::new(req_sender, res_slot),
+            at_client,
+            raw_mode,
+            error_code,
+            custom_urcs,
         }
     }
 
@@ -152,14 +414,90 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
         self.state_ch.operation_state(None)
     }
 
+    /// Address and DNS servers negotiated over PPP, `None` unless the link is
+    /// up. The stack of [`ppp::new`](super::ppp::new) is configured with it.
+    #[cfg(feature = "ppp")]
+    pub fn network_config(&self) -> Option<embassy_net::StaticConfigV4> {
+        self.state_ch.network_config()
+    }
+
     pub fn is_connected(&self) -> bool {
         self.link_state() == LinkState::Up
     }
 
+    /// Subscribe to the changes of the operation state, to `select` on
+    /// alongside other work instead of polling [`Self::operation_state`].
+    /// [`changed`](embassy_sync::watch::Receiver::changed) returns a state
+    /// the receiver has not seen yet; a transition it was too slow to see is
+    /// overwritten by the next one.
+    ///
+    /// At most [`MAX_STATE_RECEIVERS`] receivers exist at a time, dropping
+    /// one makes room for another.
+    pub fn operation_state_receiver(&self) -> Result<OperationStateReceiver<'a>, Error> {
+        self.state_ch
+            .operation_state_receiver()
+            .ok_or(Error::SubscriberOverflow(
+                embassy_sync::pubsub::Error::MaximumSubscribersReached,
+            ))
+    }
+
+    /// Subscribe to the events of the runner that don't surface as errors of
+    /// a call, eg. timed out heartbeats, failed init commands, operation
+    /// state changes and their reason, and resets of a hung AT interface.
+    /// A subscriber that falls behind by more than [`DIAGNOSTICS_CAPACITY`]
+    /// events loses the oldest, which it learns with
+    /// [`WaitResult::Lagged`](embassy_sync::pubsub::WaitResult::Lagged).
+    ///
+    /// At most [`MAX_DIAGNOSTICS_SUBSCRIBERS`] subscribers exist at a time.
+    pub fn diagnostics(&self) -> Result<DiagnosticsSubscriber<'a>, Error> {
+        self.state_ch
+            .diagnostics_subscriber()
+            .ok_or(Error::SubscriberOverflow(
+                embassy_sync::pubsub::Error::MaximumSubscribersReached,
+            ))
+    }
+
+    /// Subscribe to the changes of the link state, like
+    /// [`Self::operation_state_receiver`].
+    pub fn link_state_receiver(&self) -> Result<LinkStateReceiver<'a>, Error> {
+        self.state_ch
+            .link_state_receiver()
+            .ok_or(Error::SubscriberOverflow(
+                embassy_sync::pubsub::Error::MaximumSubscribersReached,
+            ))
+    }
+
     pub async fn is_denied(&self) -> bool {
         self.state_ch.is_denied(None)
     }
 
+    /// Current network registration, including why the network rejected the
+    /// last registration attempt, if it did.
+    pub fn registration_status(&self) -> RegistrationStatus {
+        self.state_ch.registration_status()
+    }
+
+    /// PSM timers granted by the network with the last EPS registration, and
+    /// when the module last reported a registration or tracking area update,
+    /// to estimate when the device is reachable next with
+    /// [`PsmTimers::next_reachable`].
+    pub fn psm_timers(&self) -> PsmTimers {
+        self.state_ch.psm_timers()
+    }
+
+    /// Cause of the rejection that stopped the network attach, as retrying
+    /// would not fix it, see `CellularConfig::ATTACH_BACKOFF`. Attempts
+    /// resume once the desired state changes, or another SIM is inserted.
+    pub fn attach_rejection(&self) -> Option<RejectCause> {
+        self.state_ch.attach_rejection()
+    }
+
+    /// Failed network attach and data context activation attempts since the
+    /// runner was created.
+    pub fn attach_failures(&self) -> u32 {
+        self.state_ch.attach_failures()
+    }
+
     pub fn desired_state(&self) -> OperationState {
         self.state_ch.desired_state(None)
     }
@@ -168,6 +506,38 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
         self.state_ch.set_desired_state(ps);
     }
 
+    /// Switch the radio off with `AT+CFUN=4`, keeping the modem powered and
+    /// the SIM accessible. Any data connection is torn down, and open sockets
+    /// are closed. A powered down modem is started straight into airplane
+    /// mode. Leave it again by setting the desired state, e.g. to
+    /// [`OperationState::DataEstablished`].
+    pub async fn enter_airplane_mode(&self) {
+        self.set_desired_state(OperationState::AirplaneMode);
+        self.wait_for_operation_state(OperationState::AirplaneMode)
+            .await;
+    }
+
+    /// Power the modem down, after deactivating the data context (+CGACT=0),
+    /// detaching (+CGATT=0), deregistering (+COPS=2) and having it switch
+    /// itself off (+CPWROFF). Open sockets are closed when the link goes down.
+    /// Each step is bounded in time, and skipped past if it fails, so the
+    /// power pin is toggled in any case if the module is still on.
+    ///
+    /// Setting the desired state to [`OperationState::PowerDown`] takes the
+    /// same steps, see [`Control::shutdown_report`] for how they went.
+    pub async fn graceful_power_down(&self) -> ShutdownReport {
+        self.set_desired_state(OperationState::PowerDown);
+        self.wait_for_operation_state(OperationState::PowerDown)
+            .await;
+        self.shutdown_report()
+    }
+
+    /// Steps of the graceful shutdown taken before the modem was last powered
+    /// down.
+    pub fn shutdown_report(&self) -> ShutdownReport {
+        self.state_ch.shutdown_report()
+    }
+
     /// Make the next power-down skip the graceful AT teardown and hard
     /// power-cycle via GPIO. Call before driving the state to `PowerDown` when
     /// the modem is known unresponsive (e.g. the firmware keepalive), so the
@@ -180,6 +550,21 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
         self.state_ch.set_apn_config(apn);
     }
 
+    /// Set APNs to fall back between, in order. If attaching or activating the
+    /// context fails with one, the next one is tried. The last working one is
+    /// remembered, so reconnects start with it.
+    ///
+    /// Fails with [`Error::Overflow`] for more than
+    /// [`MAX_APN_CANDIDATES`](crate::config::MAX_APN_CANDIDATES) APNs.
+    pub fn set_apn_candidates(&self, apns: &[Apn]) -> Result<(), Error> {
+        self.state_ch.set_apn_candidates(apns)
+    }
+
+    /// The APN in use, and its index among the candidates.
+    pub fn current_apn(&self) -> (usize, Apn) {
+        (self.state_ch.apn_index(), self.state_ch.get_apn_config())
+    }
+
     pub async fn wait_for_link_state(&self, link_state: LinkState) {
         self.state_ch.wait_for_link_state(link_state).await;
     }
@@ -203,6 +588,25 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
         self.state_ch.wait_rat_change().await
     }
 
+    /// Signal quality last polled by the runner, if
+    /// [`SIGNAL_MONITOR`](crate::config::CellularConfig::SIGNAL_MONITOR) is
+    /// set. Cleared on every power-up.
+    pub fn signal_quality(&self) -> Option<SignalQuality> {
+        self.state_ch.signal_quality()
+    }
+
+    /// Whether the last polled signal quality is usable, by the thresholds of
+    /// [`SIGNAL_MONITOR`](crate::config::CellularConfig::SIGNAL_MONITOR).
+    pub fn signal_usable(&self) -> bool {
+        self.state_ch.is_signal_usable(None)
+    }
+
+    /// Wait for the signal to become usable or unusable, returning whether it
+    /// is usable now.
+    pub async fn wait_signal_change(&self) -> bool {
+        self.state_ch.wait_signal_change().await
+    }
+
     /// Wait for either DataEstablished state or powered down indicating something went bad.
     /// Returns Ok(()) if DataEstablished is reached, or Error if registration is denied.
     pub async fn wait_for_data_established_or_powered_down(&self) -> Result<(), Error> {
@@ -234,6 +638,9 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
             }
             Either::Second(_) => {
                 error!("❌ Module powered down while waiting for data connection");
+                if let Some(e) = self.init_error() {
+                    return Err(Error::Init(e));
+                }
                 Err(Error::Network(
                     crate::command::network_service::types::Error::RegistrationDenied,
                 ))
@@ -241,58 +648,2818 @@ impl<'a, const INGRESS_BUF_SIZE: usize> Control<'a, INGRESS_BUF_SIZE> {
         }
     }
 
-    pub async fn get_signal_quality(&self) -> Result<SignalQuality, Error> {
-        self.send(&GetSignalQuality).await
+    /// Install a modem firmware update from the update file `filename`,
+    /// previously stored in the module file system.
+    ///
+    /// The update file is validated before the installation is started. The
+    /// module reboots during the installation, which takes several minutes;
+    /// the runner monitors the installation progress and re-initializes the
+    /// module once it has completed. Returns when the installation has
+    /// completed, failed or `timeout` has expired. The progress is published
+    /// to the receivers of [`Self::install_firmware_with_progress`].
+    pub async fn install_firmware(&self, filename: &str, timeout: Duration) -> Result<(), Error> {
+        let res = self.run_firmware_install(filename, timeout).await;
+        if res.is_err() {
+            self.state_ch.set_firmware_progress(Progress::Failed);
+        }
+        res
     }
 
-    pub async fn get_operator(&self) -> Result<OperatorSelection, Error> {
-        self.send(&GetOperatorSelection).await
+    /// Like [`Self::install_firmware`], along with a receiver of the
+    /// installation progress reported by the module.
+    pub fn install_firmware_with_progress<'s>(
+        &'s self,
+        filename: &'s str,
+        timeout: Duration,
+    ) -> Result<
+        (
+            impl Future<Output = Result<(), Error>> + use<'s, 'a, INGRESS_BUF_SIZE>,
+            ProgressReceiver<'a>,
+        ),
+        Error,
+    > {
+        // Published first, so that the receiver does not start out with the
+        // outcome of an earlier installation
+        self.state_ch.set_firmware_progress(Progress::Percent(0));
+        let receiver =
+            self.state_ch
+                .firmware_progress_receiver()
+                .ok_or(Error::SubscriberOverflow(
+                    embassy_sync::pubsub::Error::MaximumSubscribersReached,
+                ))?;
+        Ok((self.install_firmware(filename, timeout), receiver))
     }
 
-    pub async fn get_version(&self) -> Result<FirmwareVersion, Error> {
-        let res = self.send(&GetFirmwareVersion).await?;
-        Ok(res.version)
+    async fn run_firmware_install(&self, filename: &str, timeout: Duration) -> Result<(), Error> {
+        let validation = self.send(&PrevalidateFirmware { filename }).await?;
+        if validation.result != 0 {
+            error!(
+                "❌ Firmware update file validation failed: {}",
+                validation.result
+            );
+            return Err(Error::FirmwareInstall(FirmwareInstallError::Validation(
+                validation.result,
+            )));
+        }
+
+        self.send(&InstallFirmware).await?;
+        info!("🔧 Firmware installation started");
+        // The runner gives up monitoring the installation at the same time
+        let deadline = Instant::now() + timeout;
+        self.state_ch.start_firmware_install(deadline);
+
+        let Ok(state) = with_deadline(
+            deadline,
+            core::future::poll_fn(|cx| match self.state_ch.firmware_install_state(Some(cx)) {
+                FirmwareInstallState::Installing(_) => core::task::Poll::Pending,
+                state => core::task::Poll::Ready(state),
+            }),
+        )
+        .await
+        else {
+            // Otherwise the state would stay `Installing` without a runner
+            // monitoring it
+            self.state_ch
+                .set_firmware_install_state(FirmwareInstallState::Failed(
+                    FirmwareInstallError::Timeout,
+                ));
+            return Err(Error::FirmwareInstall(FirmwareInstallError::Timeout));
+        };
+
+        match state {
+            FirmwareInstallState::Failed(e) => Err(Error::FirmwareInstall(e)),
+            _ => {
+                info!("✅ Firmware installation completed");
+                Ok(())
+            }
+        }
     }
 
-    pub async fn set_gpio_configuration(
+    /// Number of modem sessions so far, incremented on every (re-)initialization
+    /// of the modem. A change means the modem has been reset, and module side
+    /// resources, eg. sockets or profiles, have to be recreated.
+    pub fn session(&self) -> u32 {
+        self.state_ch.session(None)
+    }
+
+    /// Wait for the modem to be reset and re-initialized.
+    pub async fn wait_session_change(&self) -> u32 {
+        self.state_ch.wait_session_change().await
+    }
+
+    /// Number of SIM swaps detected so far, by comparing the ICCID on every
+    /// initialization and SIM insertion.
+    pub fn sim_changes(&self) -> u32 {
+        self.state_ch.sim_changes(None)
+    }
+
+    /// Wait for the SIM to be swapped. The runner has unlocked the new SIM
+    /// with `CellularConfig::sim_pin`, and taken the APN from
+    /// `CellularConfig::apn_lookup` by then, but the new subscription may
+    /// still need provisioning.
+    pub async fn wait_sim_change(&self) -> u32 {
+        self.state_ch.wait_sim_change().await
+    }
+
+    /// Whether a SIM is inserted. While it is [`SimState::Absent`], the
+    /// modem stays powered without attempting to attach, and the runner
+    /// picks the SIM up once inserted, re-initializing the modem for it.
+    pub fn sim_state(&self) -> SimState {
+        self.state_ch.sim_state(None)
+    }
+
+    /// Subscribe to the changes of the SIM state, eg. to learn when a SIM is
+    /// inserted, like [`Self::operation_state_receiver`].
+    pub fn sim_state_receiver(&self) -> Result<SimStateReceiver<'a>, Error> {
+        self.state_ch
+            .sim_state_receiver()
+            .ok_or(Error::SubscriberOverflow(
+                embassy_sync::pubsub::Error::MaximumSubscribersReached,
+            ))
+    }
+
+    /// Set when the runner gave up initializing the module, see
+    /// `CellularConfig::INIT_RETRY` and `CellularConfig::INIT_TIMEOUT`. The
+    /// module is kept powered down until a new desired state is set.
+    pub fn init_error(&self) -> Option<InitError> {
+        self.state_ch.init_error()
+    }
+
+    /// Last step of the reset ladder the runner took to recover a hung AT
+    /// interface, if it ever had to.
+    pub fn last_recovery_action(&self) -> Option<RecoveryAction> {
+        self.state_ch.recovery_action(None).1
+    }
+
+    /// Wait for the runner to escalate a hung AT interface to the next step
+    /// of the reset ladder, eg. to log it.
+    pub async fn wait_recovery_action(&self) -> RecoveryAction {
+        self.state_ch.wait_recovery_action().await
+    }
+
+    /// Progress of the last firmware installation.
+    pub fn firmware_install_state(&self) -> FirmwareInstallState {
+        self.state_ch.firmware_install_state(None)
+    }
+
+    /// Apply a complete SSL/TLS security profile, resetting the profile to
+    /// its factory-programmed values first. The profile can be used by the
+    /// HTTP and MQTT clients afterwards.
+    pub async fn configure_security_profile(
         &self,
-        gpio_id: u8,
-        gpio_mode: GpioMode,
+        profile: &SecurityProfileBuilder<'_>,
     ) -> Result<(), Error> {
-        self.send(&SetGpioConfiguration { gpio_id, gpio_mode })
-            .await?;
-        Ok(())
+        for cmd in profile.commands()? {
+            self.send(&cmd).await?;
+        }
+        self.register_security_profile(profile.profile_id())
     }
 
-    /// Send an AT command to the modem This is useful if you have special
-    /// configuration but might break the drivers functionality if your settings
-    /// interfere with the drivers settings
-    pub async fn send<Cmd: atat::AtatCmd>(&self, cmd: &Cmd) -> Result<Cmd::Response, Error> {
-        if self.operation_state() == OperationState::PowerDown {
-            return Err(Error::Uninitialized);
+    /// Mark a security profile that was configured by other means, eg. at
+    /// provisioning, as usable by the HTTP and MQTT clients. The module keeps
+    /// security profiles across reboots.
+    pub fn register_security_profile(&self, profile_id: SecurityProfileId) -> Result<(), Error> {
+        if self.state_ch.register_security_profile(profile_id) {
+            Ok(())
+        } else {
+            Err(ConfigError::SecurityProfile {
+                profile_id: profile_id.0,
+            }
+            .into())
         }
+    }
 
-        Ok((&self.at_client).send_retry::<Cmd>(cmd).await?)
+    /// Fail with [`Error::Config`] for a security profile the application
+    /// clients are about to be bound to, but that was never configured, rather
+    /// than at request time with an opaque +UHTTPER or +UMQTTER.
+    #[cfg(any(feature = "http", feature = "mqtt"))]
+    pub(crate) fn check_security_profile(
+        &self,
+        profile_id: SecurityProfileId,
+    ) -> Result<(), Error> {
+        if self.state_ch.has_security_profile(profile_id) {
+            Ok(())
+        } else {
+            Err(ConfigError::SecurityProfile {
+                profile_id: profile_id.0,
+            }
+            .into())
+        }
     }
 
-    pub async fn get_apn_info(&self) -> Result<heapless::String<62>, Error> {
-        let pdp_context = self.send(&GetPDPContextDefinition).await?;
+    /// Import a certificate or private key as `name` with +USECMNG, for use in
+    /// a security profile. `password` decrypts a PKCS8 encrypted private key.
+    ///
+    /// The data is sent in a single stream after the prompt, so it must fit a
+    /// single command of [`MAX_CMD_LEN`] bytes, or [`Error::Overflow`] is
+    /// returned. Larger data can be written to a file and imported from there.
+    pub async fn import_security_data(
+        &self,
+        data_type: SecurityDataType,
+        name: &str,
+        data: &[u8],
+        password: Option<&str>,
+    ) -> Result<SecurityDataImport, Error> {
+        if data.len() > MAX_CMD_LEN {
+            return Err(Error::Overflow);
+        }
 
-        if let Some(config) = pdp_context.first() {
-            Ok(config.apn.clone())
+        let mut at = self.exclusive().await?;
+        at.send(&PrepareSecurityDataImport {
+            data_type,
+            internal_name: name,
+            data_size: data.len(),
+            password,
+        })
+        .await?;
+        at.send(&SendSecurityDataImport {
+            data: atat::serde_bytes::Bytes::new(data),
+        })
+        .await
+    }
+
+    /// Time spent in each operation state so far, and the number of state
+    /// transitions, eg. for the time to reach `DataEstablished`, or the share
+    /// of uptime spent connected.
+    pub fn state_stats(&self) -> StateStats {
+        self.state_ch.state_stats()
+    }
+
+    /// Start counting the time spent in each operation state anew.
+    pub fn reset_state_stats(&self) {
+        self.state_ch.reset_state_stats();
+    }
+
+    /// Number of URCs lost so far, because they came in faster than they were
+    /// handled. A count that keeps growing calls for a larger `URC_CAPACITY`.
+    pub fn urc_overflow_count(&self) -> u32 {
+        self.state_ch.urc_overflow_count()
+    }
+
+    /// Number of socket URCs so far for sockets not created through the
+    /// driver, eg. the BIP sockets the SIM opens for OTA updates. These are
+    /// ignored.
+    #[cfg(feature = "internal-network-stack")]
+    pub fn unknown_socket_urc_count(&self) -> u32 {
+        self.state_ch.unknown_socket_urc_count()
+    }
+
+    /// Why the last attach, registration or context activation failed, as
+    /// reported by the module with +CEER. [`ExtendedErrorReport::cause`]
+    /// decodes the raw report.
+    pub fn last_extended_error(&self) -> Option<ExtendedErrorReport> {
+        self.state_ch.extended_error()
+    }
+
+    /// Last errors the runner ran into, oldest first.
+    pub fn recent_errors(&self) -> heapless::Vec<ErrorRecord, MAX_RECENT_ERRORS> {
+        self.state_ch.recent_errors()
+    }
+
+    /// Write a human readable snapshot of the modem state into `buf`, for
+    /// attaching to bug reports. Returns the number of bytes written, the
+    /// snapshot is truncated if `buf` is too small.
+    ///
+    /// Next to the state tracked by the driver, the registration status,
+    /// operator, signal quality, context states, IP address and firmware
+    /// version are queried from the module. Every query is bounded, so the
+    /// snapshot completes in about [`SNAPSHOT_TIMEOUT`] even if the module is
+    /// only partially registered or not answering at all. Queries that fail or
+    /// run out of time show up as `unavailable`.
+    pub async fn diagnostic_snapshot(&self, buf: &mut [u8]) -> usize {
+        use core::fmt::Write;
+
+        let deadline = Instant::now() + SNAPSHOT_TIMEOUT;
+        let mut w = SliceWriter { buf, len: 0 };
+
+        let registration = self.registration_status();
+        let _ = writeln!(w, "operation_state: {:?}", self.operation_state());
+        let _ = writeln!(w, "desired_state: {:?}", self.desired_state());
+        let _ = writeln!(w, "link_state: {:?}", self.link_state());
+        let _ = writeln!(w, "sim_state: {:?}", self.sim_state());
+        let _ = writeln!(
+            w,
+            "registration: registered={} denied={} act={:?} reject_cause={:?}",
+            registration.registered,
+            registration.denied,
+            registration.act,
+            registration.reject_cause
+        );
+        let psm = self.psm_timers();
+        let _ = writeln!(
+            w,
+            "psm: active_time={:?} periodic_tau={:?} last_tau={:?}",
+            psm.active_time, psm.periodic_tau, psm.last_tau
+        );
+
+        match budgeted(deadline, self.send(&GetNetworkRegistrationStatus)).await {
+            Some(r) => {
+                let _ = writeln!(w, "creg: {:?} lac={:?} ci={:?}", r.stat, r.lac, r.ci);
+            }
+            None => {
+                let _ = writeln!(w, "creg: unavailable");
+            }
+        }
+        match budgeted(deadline, self.send(&GetGPRSNetworkRegistrationStatus)).await {
+            Some(r) => {
+                let _ = writeln!(
+                    w,
+                    "cgreg: {:?} lac={:?} ci={:?} act={:?}",
+                    r.stat, r.lac, r.ci, r.act
+                );
+            }
+            None => {
+                let _ = writeln!(w, "cgreg: unavailable");
+            }
+        }
+        match budgeted(deadline, self.send(&GetEPSNetworkRegistrationStatus)).await {
+            Some(r) => {
+                let _ = writeln!(
+                    w,
+                    "cereg: {:?} tac={:?} ci={:?} act={:?} cause_type={:?} reject_cause={:?}",
+                    r.stat, r.tac, r.ci, r.act, r.cause_type, r.reject_cause
+                );
+            }
+            None => {
+                let _ = writeln!(w, "cereg: unavailable");
+            }
+        }
+        match budgeted(deadline, self.get_operator()).await {
+            Some(r) => {
+                let _ = writeln!(w, "cops: {:?} {:?} act={:?}", r.mode, r.oper, r.act);
+            }
+            None => {
+                let _ = writeln!(w, "cops: unavailable");
+            }
+        }
+        match budgeted(deadline, self.get_signal_quality()).await {
+            Some(r) => {
+                let _ = writeln!(w, "cesq: {:?}", r);
+            }
+            None => {
+                let _ = writeln!(w, "cesq: unavailable");
+            }
+        }
+
+        let mut active = None;
+        match budgeted(deadline, self.send(&GetPDPContextState)).await {
+            Some(contexts) => {
+                let _ = write!(w, "cgact:");
+                for context in contexts.iter() {
+                    let activated = context.status == PDPContextStatus::Activated;
+                    if activated && active.is_none() {
+                        active = Some(context.cid);
+                    }
+                    let _ = write!(w, " {}={}", context.cid.0, activated as u8);
+                }
+                let _ = writeln!(w);
+            }
+            None => {
+                let _ = writeln!(w, "cgact: unavailable");
+            }
+        }
+        if let Some(cid) = active {
+            match budgeted(deadline, self.pdp_context_info(cid)).await {
+                Some(info) => {
+                    let _ = writeln!(w, "ip: {} {:?}", cid.0, info.local_addr);
+                }
+                None => {
+                    let _ = writeln!(w, "ip: unavailable");
+                }
+            }
+        }
+
+        match budgeted(deadline, self.get_version()).await {
+            Some(version) => {
+                let _ = writeln!(w, "firmware: {:?}", version);
+            }
+            None => {
+                let _ = writeln!(w, "firmware: unavailable");
+            }
+        }
+
+        let _ = writeln!(w, "errors:");
+        for record in self.recent_errors() {
+            let _ = writeln!(w, "  {}s {}", record.at_secs, record.error);
+        }
+
+        w.len
+    }
+
+    /// Enable or disable the LwM2M client embedded in SARA-R4/R5 modules, used
+    /// by carriers for device management. The setting survives a reboot.
+    #[cfg(feature = "lwm2m")]
+    pub async fn set_lwm2m_client(&self, enabled: bool) -> Result<(), Error> {
+        let mode = if enabled {
+            Lwm2mClientMode::Enabled
         } else {
-            Err(Error::_Unknown)
+            Lwm2mClientMode::Disabled
+        };
+        self.send(&SetLwm2mClient { mode }).await
+    }
+
+    #[cfg(feature = "lwm2m")]
+    pub async fn lwm2m_client_enabled(&self) -> Result<bool, Error> {
+        Ok(self.send(&GetLwm2mClient).await?.mode == Lwm2mClientMode::Enabled)
+    }
+
+    /// Have the LwM2M client send a registration update to the server with
+    /// the short server ID `server_id`.
+    #[cfg(feature = "lwm2m")]
+    pub async fn update_lwm2m_registration(&self, server_id: u16) -> Result<(), Error> {
+        self.send(&UpdateLwm2mRegistration { server_id }).await
+    }
+
+    /// +CIND indicators, eg. whether the module is roaming. They are read once
+    /// per power-up, and kept up to date by +CIEV URCs for the indicators of
+    /// [`REPORTED_INDICATORS`](crate::config::CellularConfig::REPORTED_INDICATORS)
+    /// afterwards, so the others are only as fresh as that read.
+    pub async fn indicators(&self) -> Result<IndicatorControl, Error> {
+        if let Some(indicators) = self.state_ch.indicators() {
+            return Ok(indicators);
         }
+
+        let indicators = self.send(&GetIndicatorControl).await?;
+        self.state_ch.set_indicators(indicators);
+        Ok(indicators)
     }
 
-    pub async fn get_ccid(&self) -> Result<u128, Error> {
-        let ccid = self.send(&GetCCID).await?;
+    /// Temperature of the module in degrees Celsius, as measured by its
+    /// internal sensor.
+    pub async fn temperature(&self) -> Result<f32, Error> {
+        Ok(self.send(&GetTemperature).await?.celsius())
+    }
 
-        Ok(ccid.ccid)
+    pub async fn get_signal_quality(&self) -> Result<SignalQuality, Error> {
+        self.send(&GetSignalQuality).await
     }
-    pub async fn get_gpio_value(&self, gpio_id: u8) -> Result<u8, Error> {
-        let value = self.send(&ReadGpioPin { gpio_id }).await?;
 
-        Ok(value.gpio_val)
+    /// Serving cell details reported by +UCGED, with RSRP, RSRQ and SINR for
+    /// 4G cells. If another reporting mode is configured, eg. mode 5 with the
+    /// `ucged5` feature, the short form (mode 2) is only switched to for the
+    /// read, and the configured mode restored afterwards.
+    ///
+    /// Only supported by modules with +UCGED, eg. SARA-R5 and LARA-R6.
+    pub async fn cell_environment(&self) -> Result<CellEnvironment, Error> {
+        let mode = match self.send(&GetCellEnvironment).await {
+            Ok(res) if res.environment.mode == 2 => return Ok(res.environment),
+            Ok(res) => Some(res.environment.mode),
+            Err(_) => None,
+        };
+
+        self.send(&SetCellEnvironmentReporting { mode: 2 }).await?;
+        let res = self.send(&GetCellEnvironment).await;
+        if let Some(mode) = mode {
+            if let Err(e) = self.send(&SetCellEnvironmentReporting { mode }).await {
+                warn!("Failed to restore +UCGED mode {}: {:?}", mode, e);
+            }
+        }
+        Ok(res?.environment)
+    }
+
+    /// Neighbor cells with their signal levels, as reported by +UCELLINFO.
+    pub async fn neighbor_cells(
+        &self,
+    ) -> Result<heapless::Vec<NeighborCell, MAX_NEIGHBOR_CELLS>, Error> {
+        Ok(self.send(&GetCellInfo).await?.neighbor_cells.0)
+    }
+
+    pub async fn get_operator(&self) -> Result<OperatorSelection, Error> {
+        self.send(&GetOperatorSelection).await
+    }
+
+    /// Scan for the available operators with `AT+COPS=?`.
+    ///
+    /// The scan can take several minutes, during which the modem does not
+    /// answer any other AT command. Registration timeouts are paused for the
+    /// duration of the scan, and application level keepalives should check
+    /// [`Control::is_scanning_operators`] before treating the modem as hung.
+    ///
+    /// `INGRESS_BUF_SIZE` must be large enough to hold the complete scan
+    /// response, otherwise the operators are lost.
+    pub async fn scan_operators(&self) -> Result<OperatorList, Error> {
+        struct ScanGuard<'a, 'b>(&'b state::Runner<'a>);
+
+        impl Drop for ScanGuard<'_, '_> {
+            fn drop(&mut self) {
+                self.0.set_scanning_operators(false);
+            }
+        }
+
+        self.state_ch.set_scanning_operators(true);
+        let _guard = ScanGuard(&self.state_ch);
+
+        info!("📡 Scanning for operators");
+        let res = self.send(&ScanOperators).await?;
+        info!("📡 Operator scan found {} operators", res.operators.len());
+        Ok(res.operators)
+    }
+
+    /// Have the runner scan for the available operators with `AT+COPS=?` in
+    /// the background, and poll [`Control::operator_scan_results`] for the
+    /// outcome. The runner starts the scan once it is done with any pending
+    /// state change.
+    ///
+    /// Other commands still wait for the AT interface while the scan runs,
+    /// see [`Control::scan_operators`].
+    ///
+    /// Returns [`Error::Busy`] if a scan is pending already.
+    pub fn start_operator_scan(&self) -> Result<(), Error> {
+        if !self.state_ch.request_operator_scan() {
+            return Err(Error::Busy);
+        }
+        Ok(())
+    }
+
+    /// Outcome of the last background operator scan, `None` if none was
+    /// started yet.
+    pub fn operator_scan_results(&self) -> Option<OperatorScan> {
+        self.state_ch.operator_scan()
+    }
+
+    /// Abort the pending background operator scan. The scan is aborted by
+    /// sending a character to the module, which takes a moment before the
+    /// AT interface is available again.
+    ///
+    /// Returns `false` if no scan is pending.
+    pub fn abort_operator_scan(&self) -> bool {
+        self.state_ch.abort_operator_scan()
+    }
+
+    /// Lock the registration to the operator `plmn`, using manual operator
+    /// selection in numeric format.
+    ///
+    /// When the module is registered, the selection is applied right away and
+    /// this waits for the module to register on `plmn`. If that fails, the
+    /// operator selection is restored to automatic. Otherwise the selection is
+    /// applied on the next network registration. The lock is kept across
+    /// re-registrations until [`Control::set_automatic_operator`] is called.
+    pub async fn select_operator(&self, plmn: Plmn) -> Result<(), Error> {
+        self.state_ch.set_operator_selection(Some(plmn.clone()));
+
+        if self.state_ch.operation_state(None) < OperationState::Connected {
+            return Ok(());
+        }
+
+        info!("📡 Selecting operator {}", plmn.as_str());
+        let res = self
+            .change_operator_selection(&SetOperatorSelection {
+                mode: OperatorSelectionMode::Manual,
+                format: Some(2),
+                oper: Some(plmn.as_str()),
+            })
+            .await;
+
+        if let Err(e) = res {
+            error!(
+                "❌ Failed to register on operator {}: {:?}, reverting to automatic selection",
+                plmn.as_str(),
+                e
+            );
+            if let Err(e) = self.set_automatic_operator().await {
+                warn!("Failed to restore automatic operator selection: {:?}", e);
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Return to automatic operator selection, releasing any PLMN lock set by
+    /// [`Control::select_operator`].
+    pub async fn set_automatic_operator(&self) -> Result<(), Error> {
+        self.state_ch.set_operator_selection(None);
+
+        if self.state_ch.operation_state(None) < OperationState::Connected {
+            return Ok(());
+        }
+
+        info!("📡 Selecting operator automatically");
+        self.change_operator_selection(&SetOperatorSelection {
+            mode: OperatorSelectionMode::Automatic,
+            format: None,
+            oper: None,
+        })
+        .await
+    }
+
+    /// Issue `cmd` and wait for the registration status to confirm it, within
+    /// the 180 s +COPS timeout.
+    async fn change_operator_selection(&self, cmd: &SetOperatorSelection<'_>) -> Result<(), Error> {
+        with_timeout(Duration::from_secs(180), async {
+            self.send(cmd).await?;
+
+            // Refresh the registration state, in case the URCs were missed
+            // while the command was running.
+            let creg = self.send(&GetNetworkRegistrationStatus).await?;
+            self.state_ch
+                .update_registration_with(|state| state.compare_and_set(creg.into()));
+            let cereg = self.send(&GetEPSNetworkRegistrationStatus).await?;
+            self.state_ch
+                .update_registration_with(|state| state.compare_and_set(cereg.into()));
+
+            core::future::poll_fn(|cx| {
+                if self.state_ch.is_registered(Some(cx)) {
+                    core::task::Poll::Ready(Ok(()))
+                } else if self.state_ch.is_denied(None) {
+                    core::task::Poll::Ready(Err(Error::Network(
+                        crate::command::network_service::types::Error::RegistrationDenied,
+                    )))
+                } else {
+                    core::task::Poll::Pending
+                }
+            })
+            .await
+        })
+        .await?
+    }
+
+    /// Whether an operator scan is currently keeping the modem busy.
+    pub fn is_scanning_operators(&self) -> bool {
+        self.state_ch.is_scanning_operators(None)
+    }
+
+    /// Read `len` bytes at `offset` from the transparent elementary file
+    /// `fileid` on the SIM, using +CRSM READ BINARY. As P3 of the command, a
+    /// `len` of 0 reads 256 bytes.
+    ///
+    /// Returns `Error::SimAccess` with the status words reported by the SIM if
+    /// the read is rejected, eg. when the file does not exist.
+    pub async fn read_sim_file(
+        &self,
+        fileid: u16,
+        offset: u16,
+        len: u8,
+    ) -> Result<heapless::Vec<u8, 256>, Error> {
+        let res = self
+            .send(&RestrictedSimAccess {
+                command: SimCommand::ReadBinary,
+                fileid: Some(fileid),
+                p1: Some((offset >> 8) as u8),
+                p2: Some(offset as u8),
+                p3: Some(len),
+                data: None,
+            })
+            .await?;
+
+        let status = res.status_words();
+        if !status.is_ok() {
+            warn!(
+                "SIM read of file {} rejected: {:02X} {:02X}",
+                fileid, status.sw1, status.sw2
+            );
+            return Err(Error::SimAccess(status));
+        }
+
+        let len = match len {
+            0 => 256,
+            len => usize::from(len),
+        };
+        let mut data = heapless::Vec::new();
+        data.resize_default(len).map_err(|_| Error::Overflow)?;
+        let n = res.data(&mut data).ok_or(Error::Overflow)?;
+        data.truncate(n);
+        Ok(data)
+    }
+
+    /// Query the module for the TCP status of the socket `handle`, using
+    /// +USOCTL. Mostly useful for debugging connections that died on the
+    /// network side.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn socket_state(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+    ) -> Result<TcpSocketStatus, Error> {
+        let res = self
+            .send(&SocketControl {
+                socket: handle,
+                param_id: SocketControlParam::SocketStatus,
+            })
+            .await?;
+        res.tcp_status().ok_or(Error::_Unknown)
+    }
+
+    /// Number of bytes written to the TCP socket `handle` the peer did not
+    /// acknowledge yet, with +USOCTL.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn socket_unacknowledged(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+    ) -> Result<usize, Error> {
+        let res = self
+            .send_socket_command(
+                handle,
+                &SocketControl {
+                    socket: handle,
+                    param_id: SocketControlParam::OutgoingUnackData,
+                },
+            )
+            .await?;
+        Ok(res.param_val as usize)
+    }
+
+    /// Set `option` on the socket `handle` with +USOSO.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn set_socket_option(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        option: SocketOption,
+    ) -> Result<(), Error> {
+        self.send_socket_command(
+            handle,
+            &SetSocketOption {
+                socket: handle,
+                level: option.level(),
+                option,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Read the option `name` of the socket `handle` with +USOGO, along with
+    /// the linger time for [`SocketOptionName::Linger`].
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn get_socket_option(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        name: SocketOptionName,
+    ) -> Result<(u32, Option<u32>), Error> {
+        let res = self
+            .send_socket_command(
+                handle,
+                &GetSocketOption {
+                    socket: handle,
+                    level: name.level(),
+                    option_name: name,
+                },
+            )
+            .await?;
+        Ok((res.value, res.value2))
+    }
+
+    /// Create an internal socket with +USOCR, bound to `local_port` if given.
+    /// A local port bound by another socket of the same protocol fails with
+    /// [`SocketErrorKind::AddrInUse`], as does a bind the module rejects.
+    ///
+    /// With PPP, only traffic to the ports of the
+    /// [`EMBEDDED_PORT_FILTERING`](crate::config::CellularConfig::EMBEDDED_PORT_FILTERING)
+    /// range reaches the internal sockets while the dial-up connection is
+    /// active, so any other `local_port` fails with [`Error::PortNotFiltered`].
+    /// Without one, the module picks a port from the range.
+    ///
+    /// The socket uses the PDP context `cid` if given, otherwise the default
+    /// one. Modules taking the context with +USOCR bind the socket to it,
+    /// modules using the context of the active PSD profile only take that
+    /// one, and modules that can't select the context fail with
+    /// [`GenericError::Unsupported`].
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn create_socket(
+        &self,
+        protocol: SocketProtocol,
+        local_port: Option<u16>,
+        cid: Option<ContextId>,
+    ) -> Result<ublox_sockets::SocketHandle, Error> {
+        if let (Some(port), Some(filtering)) = (local_port, self.state_ch.embedded_port_filtering())
+        {
+            if !filtering.contains(port) {
+                return Err(Error::PortNotFiltered(port));
+            }
+        }
+
+        let cid = match cid {
+            Some(cid) => self.socket_context(cid)?,
+            None => None,
+        };
+
+        if let Some(port) = local_port {
+            if self.state_ch.socket_port_in_use(&protocol, port) {
+                warn!("Local port {} is in use by another socket", port);
+                return Err(Error::Socket(SocketErrorKind::AddrInUse));
+            }
+        }
+
+        let mut at = self.exclusive().await?;
+        let res = socket_error::create(
+            &mut at.client,
+            &CreateSocket {
+                protocol: protocol.clone(),
+                local_port,
+                preferred_protocol_type: None,
+                cid,
+                report_aon: None,
+            },
+        )
+        .await?;
+        self.state_ch
+            .register_socket(res.socket, protocol, local_port);
+        Ok(res.socket)
+    }
+
+    /// Create a TCP socket bound to the local port `local_port` and connect
+    /// it to `remote` on `port`, eg. for peers accepting a fixed range of
+    /// source ports only. The socket is closed again if it fails to connect.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn connect_from(
+        &self,
+        local_port: u16,
+        remote: RemoteAddr,
+        port: u16,
+    ) -> Result<ublox_sockets::SocketHandle, Error> {
+        self.connect_tcp(Some(local_port), remote, port).await
+    }
+
+    /// The socket is closed by the runner if the connect is cancelled once it
+    /// was created.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) async fn connect_tcp(
+        &self,
+        local_port: Option<u16>,
+        remote: RemoteAddr,
+        port: u16,
+    ) -> Result<ublox_sockets::SocketHandle, Error> {
+        let handle = self
+            .create_socket(SocketProtocol::TCP, local_port, None)
+            .await?;
+        let close = OnDrop::new(|| self.state_ch.defer_socket_close(handle));
+
+        if let Err(e) = self.connect_socket(handle, remote, port).await {
+            // Otherwise left to the runner
+            if self.close_socket(handle).await.is_ok() {
+                close.defuse();
+            }
+            return Err(e);
+        }
+        close.defuse();
+        Ok(handle)
+    }
+
+    /// Close the socket `handle` with +USOCL, freeing its local port. A socket
+    /// closed by the peer is only forgotten.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn close_socket(&self, handle: ublox_sockets::SocketHandle) -> Result<(), Error> {
+        if self.state_ch.is_socket_closed(handle) {
+            self.state_ch.unregister_socket(handle);
+            return Ok(());
+        }
+
+        match self
+            .send_socket_command(handle, &CloseSocket { socket: handle.0 })
+            .await
+        {
+            // Gone already, eg. closed by the peer
+            Ok(_) | Err(Error::Socket(SocketErrorKind::BadSocket)) => {
+                self.state_ch.unregister_socket(handle);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The `<cid>` of +USOCR selecting the context `cid` for a socket, `None`
+    /// if the socket uses it without.
+    #[cfg(feature = "internal-network-stack")]
+    fn socket_context(&self, cid: ContextId) -> Result<Option<u16>, Error> {
+        self.check_context_id(cid)?;
+
+        let binding = self
+            .state_ch
+            .module()
+            .map_or(SocketContextBinding::Unsupported, |module| {
+                module.socket_context()
+            });
+        match binding {
+            SocketContextBinding::Cid => Ok(Some(u16::from(cid.0))),
+            #[cfg(feature = "use-upsd-context-activation")]
+            SocketContextBinding::PsdProfile => match self.state_ch.psd_profile(cid) {
+                Some(_) => Ok(None),
+                None => Err(Error::InvalidStateTransition),
+            },
+            _ => Err(Error::Generic(GenericError::Unsupported)),
+        }
+    }
+
+    /// Connect the socket `handle` to `remote` on `port` with +USOCO. A domain
+    /// name is only taken by the modules resolving it themselves, others fail
+    /// with [`GenericError::Unsupported`].
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn connect_socket(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        remote: RemoteAddr,
+        port: u16,
+    ) -> Result<(), Error> {
+        let hostnames = self
+            .state_ch
+            .module()
+            .is_some_and(|module| module.socket_hostnames());
+        if remote.is_hostname() && !hostnames {
+            return Err(Error::Generic(GenericError::Unsupported));
+        }
+
+        self.send_socket_command(
+            handle,
+            &ConnectSocket {
+                socket: handle,
+                remote_addr: remote,
+                remote_port: port,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Set a parameter of the internal TCP/IP stack with +UDCONF, eg. the TCP
+    /// keepalive. It is lost on a reset of the module, so parameters to keep
+    /// belong in `CellularConfig::DATA_CONFIGURATION`.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn set_data_configuration(&self, config: DataConfiguration) -> Result<(), Error> {
+        self.send(&SetDataConfiguration { config }).await?;
+        Ok(())
+    }
+
+    /// Send a command operating on the socket `handle`, eg. +USOCO or +USORD.
+    /// If the module rejects it, the cause is read with +USOCTL and returned
+    /// as [`Error::Socket`]. Data is written with [`Self::write_socket_data`],
+    /// which keeps the +USOWR prompt and the data following it together.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn send_socket_command<Cmd: atat::AtatCmd>(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        cmd: &Cmd,
+    ) -> Result<Cmd::Response, Error> {
+        if self.operation_state() == OperationState::PowerDown {
+            return Err(Error::Uninitialized);
+        }
+
+        self.state_ch.wait_awake().await?;
+        socket_error::send(&mut &self.at_client, handle, cmd).await
+    }
+
+    /// Number of bytes the module buffers for the socket `handle`, with a
+    /// zero-length +USORD that does not consume any.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn socket_available(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+    ) -> Result<usize, Error> {
+        if self.operation_state() == OperationState::PowerDown {
+            return Err(Error::Uninitialized);
+        }
+
+        self.state_ch.wait_awake().await?;
+        let available = socket_ingress::available(&mut &self.at_client, handle).await?;
+        self.state_ch.set_socket_available(handle, Some(available));
+        Ok(available)
+    }
+
+    /// Ask the sockets for their buffered data, with at most `max_commands`
+    /// zero-length +USORD, eg. to bound the time a superloop spends on it.
+    /// Sockets take turns across calls, so all of them are serviced over
+    /// successive calls, and each one at most once per call. Returns the
+    /// sockets holding data.
+    ///
+    /// The buffered data is announced by +UUSORD anyway, so this is only
+    /// needed where URCs may be lost.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn poll_sockets(
+        &self,
+        max_commands: usize,
+    ) -> Result<heapless::Vec<ublox_sockets::SocketHandle, MAX_SOCKETS>, Error> {
+        let mut ready = heapless::Vec::new();
+        let mut polled = 0;
+        while polled < max_commands {
+            let Some((handle, count)) = self.state_ch.next_socket_to_poll() else {
+                break;
+            };
+            if polled >= count {
+                break;
+            }
+            polled += 1;
+
+            if self.socket_available(handle).await? > 0 {
+                // Cannot fail, each socket is polled once at most
+                let _ = ready.push(handle);
+            }
+        }
+        Ok(ready)
+    }
+
+    /// Read data received on the socket `handle` into `buf` with +USORD,
+    /// returning the number of bytes read, 0 if none are buffered or the
+    /// socket was closed by the peer, see [`Self::is_socket_closed`]. Unless a
+    /// +UUSORD URC told the buffered count since the last read, it is queried
+    /// first, so that `buf` is filled with as few reads as possible.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn read_socket(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        if self.operation_state() == OperationState::PowerDown {
+            return Err(Error::Uninitialized);
+        }
+        if self.state_ch.is_socket_closed(handle) {
+            return Ok(0);
+        }
+
+        self.state_ch.wait_awake().await?;
+        let available = self.state_ch.socket_available(handle);
+        self.state_ch.set_socket_available(handle, None);
+        let res = socket_ingress::read(
+            &mut &self.at_client,
+            handle,
+            available,
+            self.state_ch.hex_mode(),
+            buf,
+        )
+        .await;
+        let (read, remaining) = match res {
+            Ok(res) => res,
+            // Closed by the peer while reading
+            Err(_) if self.state_ch.is_socket_closed(handle) => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        self.state_ch.record_socket_rx(handle, read);
+        // At least as much is left for the next read, unless drained
+        if remaining > 0 {
+            self.state_ch.set_socket_available(handle, Some(remaining));
+        }
+        Ok(read)
+    }
+
+    /// Whether the socket `handle` was closed by the peer, as told by +UUSOCL,
+    /// or lost in a reset of the module. It is still to be closed with
+    /// [`Self::close_socket`].
+    #[cfg(feature = "internal-network-stack")]
+    pub fn is_socket_closed(&self, handle: ublox_sockets::SocketHandle) -> bool {
+        self.state_ch.is_socket_closed(handle)
+    }
+
+    /// Write `data` to the connected socket `handle` with +USOWR, in chunks of
+    /// up to [`EGRESS_CHUNK_SIZE`] bytes. Returns the number of bytes the
+    /// module took, which is short of `data.len()` when its buffer is full.
+    /// The rest is left to the caller to write again.
+    ///
+    /// A nearly full buffer makes some modules reject a write with "operation
+    /// not allowed", or one of the errors of a full buffer. The write is then
+    /// retried in smaller chunks, down to [`MIN_WRITE_CHUNK`] bytes. Fails with
+    /// [`SocketErrorKind::WouldBlock`] if the buffer of the module is full
+    /// before any of `data` was taken, and with
+    /// [`SocketErrorKind::ConnectionReset`] once the socket was closed by the
+    /// peer.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn write_socket_data(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        data: &[u8],
+    ) -> Result<usize, Error> {
+        if self.state_ch.is_socket_closed(handle) {
+            return Err(Error::Socket(SocketErrorKind::ConnectionReset));
+        }
+
+        let mut written = 0;
+        let mut chunk_size = EGRESS_CHUNK_SIZE;
+        let mut retries = 0;
+        while written < data.len() {
+            let chunk = &data[written..data.len().min(written + chunk_size)];
+            let accepted = match self.write_socket_chunk(handle, chunk).await {
+                Ok(accepted) => accepted,
+                Err(e)
+                    if is_buffer_full(&e)
+                        && retries < MAX_WRITE_RETRIES
+                        && chunk.len() > MIN_WRITE_CHUNK =>
+                {
+                    retries += 1;
+                    chunk_size = (chunk.len() / 2).max(MIN_WRITE_CHUNK);
+                    debug!(
+                        "[{}] Module buffer full, retrying with {} byte chunks",
+                        handle, chunk_size
+                    );
+                    Timer::after(WRITE_RETRY_DELAY).await;
+                    continue;
+                }
+                Err(e) if is_buffer_full(&e) => 0,
+                Err(e) => return Err(e),
+            };
+            self.state_ch
+                .record_socket_tx(handle, chunk.len(), accepted);
+
+            written += accepted;
+            if accepted < chunk.len() {
+                break;
+            }
+        }
+
+        if written == 0 && !data.is_empty() {
+            return Err(Error::Socket(SocketErrorKind::WouldBlock));
+        }
+        Ok(written)
+    }
+
+    /// Write a single chunk of at most [`EGRESS_CHUNK_SIZE`] bytes, returning
+    /// the number of bytes the module took.
+    #[cfg(feature = "internal-network-stack")]
+    async fn write_socket_chunk(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        chunk: &[u8],
+    ) -> Result<usize, Error> {
+        let mut at = self.exclusive().await?;
+        at.send_socket_command(
+            handle,
+            &PrepareWriteSocketDataBinary {
+                socket: handle,
+                length: chunk.len(),
+            },
+        )
+        .await?;
+        let res = at
+            .send_socket_command(
+                handle,
+                &WriteSocketDataBinary {
+                    // Cannot fail, chunks are at most EGRESS_CHUNK_SIZE long
+                    data: EgressData::new(chunk).ok_or(Error::Overflow)?,
+                },
+            )
+            .await?;
+        Ok(res.length)
+    }
+
+    /// Send `data` as a single datagram from the UDP socket `handle` to
+    /// `remote` on `port` with +USOST. Fails with [`Error::Overflow`] for more
+    /// than [`UDP_EGRESS_CHUNK_SIZE`] bytes, and with
+    /// [`SocketErrorKind::ConnectionReset`] once the socket was lost in a reset
+    /// of the module.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn send_socket_data_to(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        remote: RemoteAddr,
+        port: u16,
+        data: &[u8],
+    ) -> Result<usize, Error> {
+        if self.state_ch.is_socket_closed(handle) {
+            return Err(Error::Socket(SocketErrorKind::ConnectionReset));
+        }
+        let data = EgressData::<UDP_EGRESS_CHUNK_SIZE>::new(data).ok_or(Error::Overflow)?;
+
+        let mut at = self.exclusive().await?;
+        at.send_socket_command(
+            handle,
+            &PrepareUDPSendToDataBinary {
+                socket: handle,
+                remote_addr: remote,
+                remote_port: port,
+                length: data.as_bytes().len(),
+            },
+        )
+        .await?;
+        let len = data.as_bytes().len();
+        let res = at
+            .send_socket_command(handle, &UDPSendToDataBinary { data })
+            .await?;
+        self.state_ch.record_socket_tx(handle, len, res.length);
+        Ok(res.length)
+    }
+
+    /// Number of bytes the module buffers for the UDP socket `handle`, over
+    /// all datagrams, with a zero-length +USORF.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn udp_socket_available(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+    ) -> Result<usize, Error> {
+        let res = self
+            .send_socket_command(
+                handle,
+                &GetUDPSocketDataAvailable {
+                    socket: handle,
+                    length: 0,
+                },
+            )
+            .await?;
+        self.state_ch.set_socket_available(handle, Some(res.length));
+        Ok(res.length)
+    }
+
+    /// Read the next datagram received on the UDP socket `handle` into `buf`
+    /// with a single +USORF, returning its length and sender. A datagram longer
+    /// than `buf` is cut short, the rest of it is lost, as with `recvfrom`.
+    /// Datagrams longer than [`INGRESS_CHUNK_SIZE`] are not kept apart from
+    /// the next one by the module.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn recv_socket_data_from(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        buf: &mut [u8],
+    ) -> Result<(usize, SocketAddr), Error> {
+        if self.state_ch.is_socket_closed(handle) {
+            return Err(Error::Socket(SocketErrorKind::ConnectionReset));
+        }
+
+        // More may be left, which the next read asks for
+        self.state_ch.set_socket_available(handle, None);
+        if !self.state_ch.hex_mode() {
+            let res = self
+                .send_socket_command(
+                    handle,
+                    &ReadUDPSocketDataBinary {
+                        socket: handle,
+                        length: INGRESS_CHUNK_SIZE,
+                    },
+                )
+                .await?;
+            // All of the datagram is gone from the module, even if cut short here
+            self.state_ch.record_socket_rx(handle, res.length);
+            let n = res.payload.len().min(buf.len());
+            buf[..n].copy_from_slice(&res.payload[..n]);
+            return Ok((n, res.remote_endpoint()));
+        }
+
+        let mut res = self
+            .send_socket_command(
+                handle,
+                &ReadUDPSocketData {
+                    socket: handle,
+                    length: INGRESS_CHUNK_SIZE,
+                },
+            )
+            .await?;
+        let remote = res.remote_endpoint();
+        self.state_ch.record_socket_rx(handle, res.length);
+        let read = res
+            .decode_hex(|data| {
+                let n = data.len().min(buf.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                n
+            })
+            .ok_or(Error::Atat(atat::Error::Parse))?;
+        Ok((read, remote))
+    }
+
+    /// Resolve `name` with +UDNSRN, through the DNS servers of the active
+    /// context, returning all the addresses reported.
+    ///
+    /// The addresses are also handed out in turn by
+    /// [`Dns::get_host_by_name`](embedded_nal_async::Dns::get_host_by_name)
+    /// for the next [`DNS_ROTATION_WINDOW`](crate::config::DNS_ROTATION_WINDOW).
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn resolve(
+        &self,
+        name: &str,
+    ) -> Result<heapless::Vec<IpAddr, MAX_DNS_RESULTS>, Error> {
+        let results = self
+            .send(&ResolveNameIp {
+                resolution_type: ResolutionType::DomainNameToIp,
+                ip_domain_string: name,
+            })
+            .await?;
+
+        let addresses: heapless::Vec<IpAddr, MAX_DNS_RESULTS> = results
+            .iter()
+            .filter_map(|r| r.ip_domain_string.parse().ok())
+            .collect();
+        if addresses.is_empty() {
+            return Err(Error::HostNotFound);
+        }
+
+        self.state_ch.cache_dns_addresses(name, &addresses);
+        Ok(addresses)
+    }
+
+    /// Domain name of `addr`, by reverse resolution with +UDNSRN.
+    #[cfg(feature = "internal-network-stack")]
+    pub async fn reverse_resolve(&self, addr: IpAddr) -> Result<heapless::String<128>, Error> {
+        use core::fmt::Write;
+
+        let mut ip = heapless::String::<45>::new();
+        write!(ip, "{}", addr).map_err(|_| Error::Overflow)?;
+
+        let results = self
+            .send(&ResolveNameIp {
+                resolution_type: ResolutionType::IpToDomainName,
+                ip_domain_string: &ip,
+            })
+            .await?;
+        results
+            .into_iter()
+            .map(|r| r.ip_domain_string)
+            .find(|name| !name.is_empty())
+            .ok_or(Error::HostNotFound)
+    }
+
+    /// IMEI, ICCID, IMSI, model and firmware version of the modem and SIM.
+    ///
+    /// These are read by the runner while initializing the modem, and cached
+    /// afterwards, so this only talks to the modem for fields that could not
+    /// be read yet. The cached identity is returned while the modem is powered
+    /// down.
+    pub async fn identity(&self) -> Result<Identity, Error> {
+        let cached = self.state_ch.identity();
+        if self.operation_state() == OperationState::PowerDown
+            || (cached.imei.is_some() && cached.iccid.is_some() && cached.imsi.is_some())
+        {
+            return Ok(cached);
+        }
+
+        if cached.imei.is_none() {
+            let imei = self.send(&GetIMEI { snt: None }).await?.imei;
+            self.state_ch.update_identity(|id| id.imei = Some(imei));
+        }
+        if cached.iccid.is_none() {
+            let iccid = self.send(&GetCCID).await?.ccid;
+            self.state_ch.update_identity(|id| id.iccid = Some(iccid));
+        }
+        if cached.imsi.is_none() {
+            let imsi = self.send(&GetCIMI).await?.imsi;
+            self.state_ch.update_identity(|id| id.imsi = Some(imsi));
+        }
+
+        Ok(self.state_ch.identity())
+    }
+
+    /// Own phone number (MSISDN) as stored on the SIM, or `None` if the SIM
+    /// does not store any, which is common for data only subscriptions.
+    ///
+    /// The first number reported by +CNUM is returned. It is cached until
+    /// another SIM is inserted.
+    pub async fn own_number(&self) -> Result<Option<heapless::String<32>>, Error> {
+        if let Some(number) = self.state_ch.own_number() {
+            return Ok(number);
+        }
+
+        let number = match self.send(&GetSubscriberNumber).await {
+            Ok(numbers) => numbers
+                .into_iter()
+                .map(|n| n.number)
+                .find(|number| !number.is_empty()),
+            Err(Error::Cme(CmeError::NotFound)) => None,
+            Err(e) => return Err(e),
+        };
+        self.state_ch.set_own_number(number.clone());
+        Ok(number)
+    }
+
+    /// Current SIM lock state, as reported by +CPIN?
+    pub async fn pin_status(&self) -> Result<PinStatusCode, Error> {
+        Ok(self.send(&GetPinStatus).await?.code)
+    }
+
+    /// Unlock a PIN locked SIM.
+    ///
+    /// Unlike the automatic unlock during initialization, this enters the PIN
+    /// regardless of the attempts left, so the application is responsible for
+    /// not locking the SIM with the PUK.
+    pub async fn set_pin(&self, pin: &str) -> Result<(), Error> {
+        match self.pin_status().await? {
+            PinStatusCode::Ready => return Ok(()),
+            PinStatusCode::SimPin => {}
+            PinStatusCode::SimPuk => return Err(Error::SimPukRequired),
+            _ => return Err(Error::SimCard),
+        }
+        self.send(&SetPin { pin }).await?;
+        Ok(())
+    }
+
+    /// Change the SIM PIN from `old_pin` to `new_pin`. The PIN lock must be
+    /// enabled on the SIM.
+    pub async fn change_pin(&self, old_pin: &str, new_pin: &str) -> Result<(), Error> {
+        self.send(&ChangePassword {
+            fac: "SC",
+            oldpwd: old_pin,
+            newpwd: new_pin,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Unlock a PUK locked SIM, setting `new_pin` as the new PIN.
+    pub async fn unlock_puk(&self, puk: &str, new_pin: &str) -> Result<(), Error> {
+        self.send(&ChangePin {
+            puk,
+            newpin: new_pin,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Attempts left to enter the PIN and the PUK, in that order
+    pub async fn pin_attempts(&self) -> Result<(u8, u8), Error> {
+        let res = self.send(&GetPinCounter).await?;
+        Ok((res.pin_attempts, res.puk_attempts))
+    }
+
+    pub async fn get_version(&self) -> Result<FirmwareVersion, Error> {
+        let res = self.send(&GetFirmwareVersion).await?;
+        Ok(res.version)
+    }
+
+    pub async fn set_gpio_configuration(
+        &self,
+        gpio_id: u8,
+        gpio_mode: GpioMode,
+    ) -> Result<(), Error> {
+        self.send(&SetGpioConfiguration { gpio_id, gpio_mode })
+            .await?;
+        Ok(())
+    }
+
+    /// Access the module file system.
+    pub fn file_system(&self) -> FileSystemService<'_, 'a, INGRESS_BUF_SIZE> {
+        FileSystemService::new(self)
+    }
+
+    /// Enter the non-signaling RF test mode (`AT+UTEST`), for production line
+    /// tests.
+    ///
+    /// Refuses with [`Error::InvalidStateTransition`] unless the modem is
+    /// initialized with the radio off and deregistered, ie. the desired
+    /// state is [`OperationState::Initialized`] or
+    /// [`OperationState::AirplaneMode`] and has been reached.
+    pub async fn factory_test(&self) -> Result<FactoryTest<'_, 'a, INGRESS_BUF_SIZE>, Error> {
+        let radio_off = |state: OperationState| {
+            matches!(
+                state,
+                OperationState::Initialized | OperationState::AirplaneMode
+            )
+        };
+        if !radio_off(self.desired_state())
+            || self.operation_state() != self.desired_state()
+            || self.state_ch.is_registered(None)
+        {
+            warn!("Refusing RF test mode while the radio is on, or registered");
+            return Err(Error::InvalidStateTransition);
+        }
+
+        FactoryTest::enter(self).await
+    }
+
+    /// Access the GNSS receiver controlled through the modem.
+    #[cfg(feature = "gnss")]
+    pub fn gnss(&self) -> Gnss<'_, 'a, INGRESS_BUF_SIZE> {
+        Gnss::new(self)
+    }
+
+    /// Get an HTTP client using the given HTTP profile of the modem's internal
+    /// HTTP client.
+    #[cfg(feature = "http")]
+    pub fn http(&self, profile_id: HttpProfileId) -> HttpClient<'_, 'a, INGRESS_BUF_SIZE> {
+        HttpClient::new(self, profile_id)
+    }
+
+    /// Type, state and traffic counters of every socket, and the totals over
+    /// all of them, eg. to size the buffers of the application.
+    #[cfg(feature = "internal-network-stack")]
+    pub fn socket_stats(&self) -> SocketSetStats {
+        self.state_ch.socket_stats()
+    }
+
+    /// A TCP socket of the internal stack of the modem, closed in the
+    /// background if dropped.
+    #[cfg(feature = "internal-network-stack")]
+    pub fn tcp_socket(&self) -> TcpSocket<'_, 'a, INGRESS_BUF_SIZE> {
+        TcpSocket::new(self)
+    }
+
+    /// A [`TcpConnect`](embedded_nal_async::TcpConnect) of the internal stack
+    /// of the modem, with up to `N` connections at once.
+    #[cfg(feature = "internal-network-stack")]
+    pub fn tcp_client<const N: usize>(&self) -> TcpClient<'_, 'a, N, INGRESS_BUF_SIZE> {
+        TcpClient::new(self)
+    }
+
+    /// A UDP socket of the internal stack of the modem, created on the module
+    /// by [`UdpSocket::bind`].
+    #[cfg(feature = "internal-network-stack")]
+    pub fn udp_socket(&self) -> UdpSocket<'_, 'a, INGRESS_BUF_SIZE> {
+        UdpSocket::new(self)
+    }
+
+    /// Get a client for the modem's internal MQTT client.
+    #[cfg(feature = "mqtt")]
+    pub fn mqtt(&self) -> MqttClient<'_, 'a, INGRESS_BUF_SIZE> {
+        MqttClient::new(self)
+    }
+
+    /// Dial, answer and hang up voice calls.
+    #[cfg(feature = "audio")]
+    pub fn call(&self) -> CallService<'_, 'a, INGRESS_BUF_SIZE> {
+        CallService::new(self)
+    }
+
+    /// Send and read short messages in PDU mode.
+    #[cfg(feature = "sms")]
+    pub fn sms(&self) -> SmsService<'_, 'a, INGRESS_BUF_SIZE> {
+        SmsService::new(self)
+    }
+
+    /// Send an AT command to the modem This is useful if you have special
+    /// configuration but might break the drivers functionality if your settings
+    /// interfere with the drivers settings
+    pub async fn send<Cmd: atat::AtatCmd>(&self, cmd: &Cmd) -> Result<Cmd::Response, Error> {
+        if self.operation_state() == OperationState::PowerDown {
+            return Err(Error::Uninitialized);
+        }
+
+        self.state_ch.wait_awake().await?;
+
+        self.error_code.set(None);
+        (&self.at_client)
+            .send_retry::<Cmd>(cmd)
+            .await
+            .map_err(|e| self.decode_error(e))
+    }
+
+    /// Take the AT channel for a sequence of commands that must not be
+    /// interleaved with others, eg. a command answered with a prompt and the
+    /// data following it. Commands of the runner and other users of the
+    /// `Control` wait until the returned [`Exclusive`] is dropped.
+    pub(crate) async fn exclusive(&self) -> Result<Exclusive<'_, 'a, INGRESS_BUF_SIZE>, Error> {
+        if self.operation_state() == OperationState::PowerDown {
+            return Err(Error::Uninitialized);
+        }
+
+        self.state_ch.wait_awake().await?;
+        Ok(Exclusive {
+            control: self,
+            client: self.at_client.lock().await,
+        })
+    }
+
+    /// Decode the code of a +CME ERROR or +CMS ERROR, which atat only keeps
+    /// if it knows it.
+    fn decode_error(&self, e: atat::Error) -> Error {
+        if matches!(e, atat::Error::Timeout) {
+            // The module may have gone to PSM
+            self.state_ch.suspect_sleep();
+        }
+
+        match (e, self.error_code.take()) {
+            (atat::Error::CmeError(_), Some(ErrorCode::Cme(code))) => Error::Cme(code.into()),
+            (atat::Error::CmsError(_), Some(ErrorCode::Cms(code))) => Error::Cms(code.into()),
+            (e, _) => e.into(),
+        }
+    }
+
+    /// Send an arbitrary AT command, eg. `AT+UTEST=1` entered by an operator,
+    /// and copy everything the module replies up to the final result code
+    /// into `response_buf`, returning its length. `CR` is appended to `cmd`.
+    ///
+    /// Lines of the response are not mistaken for URCs, but URCs that arrive
+    /// while the command is pending end up in the response. Commands that
+    /// expect a `>` prompt for data are not supported.
+    ///
+    /// Returns [`Error::Overflow`] if the command is too long, or the
+    /// response does not fit `response_buf`, which then holds its beginning.
+    pub async fn send_raw(
+        &self,
+        cmd: &str,
+        response_buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<usize, Error> {
+        if self.operation_state() == OperationState::PowerDown {
+            return Err(Error::Uninitialized);
+        }
+
+        let mut msg = heapless::Vec::<u8, MAX_CMD_LEN>::new();
+        if msg.extend_from_slice(cmd.as_bytes()).is_err() || msg.push(b'\r').is_err() {
+            return Err(Error::Overflow);
+        }
+
+        self.state_ch.wait_awake().await?;
+        self.at_client
+            .send_raw(msg, self.raw_mode, timeout, response_buf)
+            .await
+    }
+
+    /// Wait for the next URC matching one of the
+    /// [`CUSTOM_URCS`](crate::config::CellularConfig::CUSTOM_URCS) prefixes.
+    ///
+    /// Only the oldest [`CUSTOM_URC_CAPACITY`](super::CUSTOM_URC_CAPACITY)
+    /// URCs are kept until they are received, newer ones are dropped.
+    pub async fn custom_urc(&self) -> CustomUrc {
+        self.custom_urcs.receive().await
+    }
+
+    pub async fn get_apn_info(&self) -> Result<heapless::String<62>, Error> {
+        let pdp_context = self.send(&GetPDPContextDefinition).await?;
+
+        if let Some(config) = pdp_context.first() {
+            Ok(config.apn.clone())
+        } else {
+            Err(Error::_Unknown)
+        }
+    }
+
+    /// Whether init found the module not answering with RTS/CTS flow
+    /// control, as configured by `CellularConfig::FLOW_CONTROL`, and went on
+    /// without flow control.
+    pub fn flow_control_fallback(&self) -> bool {
+        self.state_ch.flow_control_fallback()
+    }
+
+    /// Address of the data context, as read after it was (re)activated.
+    /// `None` until the context is active.
+    pub fn local_ip(&self) -> Option<IpAddr> {
+        self.state_ch.local_addr(None).0
+    }
+
+    /// Number of times the network assigned a new address to the data
+    /// context. Long-lived flows, eg. over UDP, have to be set up again when
+    /// it changes.
+    pub fn local_ip_changes(&self) -> u32 {
+        self.state_ch.local_addr(None).1
+    }
+
+    /// Wait for the network to assign a new address to the data context, and
+    /// return it.
+    pub async fn wait_local_ip_change(&self) -> IpAddr {
+        let (_, changes) = self.state_ch.local_addr(None);
+        core::future::poll_fn(|cx| match self.state_ch.local_addr(Some(cx)) {
+            (Some(addr), c) if c != changes => core::task::Poll::Ready(addr),
+            _ => core::task::Poll::Pending,
+        })
+        .await
+    }
+
+    /// Network assigned parameters of the active PDP context `cid`, eg. the
+    /// local IP address and DNS servers.
+    ///
+    /// Read with +CGCONTRDP, or with +UPSND when contexts are activated through
+    /// PSD profiles, in which case only the addresses are available.
+    pub async fn pdp_context_info(&self, cid: ContextId) -> Result<PdpContextInfo, Error> {
+        self.check_context_id(cid)?;
+
+        #[cfg(not(feature = "use-upsd-context-activation"))]
+        {
+            let params = self
+                .send(&crate::command::psn::GetPDPContextReadDynamicParams { cid })
+                .await?;
+            // Dual stack contexts report the IPv4 bearer first
+            params
+                .first()
+                .map(PdpContextInfo::from)
+                .ok_or(Error::_Unknown)
+        }
+
+        #[cfg(feature = "use-upsd-context-activation")]
+        {
+            use crate::command::psn::{
+                types::PacketSwitchedNetworkDataParam, GetPacketSwitchedNetworkAddress,
+            };
+
+            let profile_id = self
+                .state_ch
+                .psd_profile(cid)
+                .ok_or(Error::InvalidStateTransition)?;
+
+            let mut addresses = [None; 3];
+            for (addr, param) in addresses.iter_mut().zip([
+                PacketSwitchedNetworkDataParam::IPAddress,
+                PacketSwitchedNetworkDataParam::DNS1,
+                PacketSwitchedNetworkDataParam::DNS2,
+            ]) {
+                *addr = self
+                    .send(&GetPacketSwitchedNetworkAddress { profile_id, param })
+                    .await?
+                    .address();
+            }
+            let [local_addr, dns_primary, dns_secondary] = addresses;
+
+            Ok(PdpContextInfo {
+                cid,
+                apn: None,
+                local_addr,
+                subnet_mask: None,
+                gateway: None,
+                dns_primary,
+                dns_secondary,
+            })
+        }
+    }
+
+    /// Byte counters of the PDP context `cid`, as kept by the module. `None`
+    /// if the context is not active.
+    pub async fn data_counters(&self, cid: ContextId) -> Result<Option<DataCounters>, Error> {
+        self.check_context_id(cid)?;
+        let counters = self.send(&GetDataCounters).await?;
+        Ok(counters.into_iter().find(|c| c.cid == cid))
+    }
+
+    /// Reset the total byte counters of the PDP context `cid`.
+    pub async fn reset_data_counters(&self, cid: ContextId) -> Result<(), Error> {
+        self.check_context_id(cid)?;
+        self.send(&SetDataCounters {
+            cid,
+            total_bytes_sent: 0,
+            total_bytes_received: 0,
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Fail early with [`Error::Config`] for a context id the module does not
+    /// support, rather than with whatever error the module answers. Ids are
+    /// not checked before the module is identified.
+    fn check_context_id(&self, cid: ContextId) -> Result<(), Error> {
+        match self.state_ch.module() {
+            Some(module) => Ok(module.check_context_id(cid)?),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn get_ccid(&self) -> Result<u128, Error> {
+        let ccid = self.send(&GetCCID).await?;
+
+        Ok(ccid.ccid)
+    }
+    pub async fn get_gpio_value(&self, gpio_id: u8) -> Result<u8, Error> {
+        let value = self.send(&ReadGpioPin { gpio_id }).await?;
+
+        Ok(value.gpio_val)
+    }
+}
+
+/// Resolves names with +UDNSRN. Names resolving to several addresses hand
+/// out the next one on each query, see [`Control::resolve`].
+#[cfg(feature = "internal-network-stack")]
+impl<const INGRESS_BUF_SIZE: usize> embedded_nal_async::Dns for Control<'_, INGRESS_BUF_SIZE> {
+    type Error = Error;
+
+    async fn get_host_by_name(
+        &self,
+        host: &str,
+        addr_type: embedded_nal_async::AddrType,
+    ) -> Result<IpAddr, Error> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(ip);
+        }
+
+        if let Some(addr) = self.state_ch.next_dns_address(host, addr_type) {
+            return Ok(addr);
+        }
+        self.resolve(host).await?;
+        self.state_ch
+            .next_dns_address(host, addr_type)
+            .ok_or(Error::HostNotFound)
+    }
+
+    async fn get_host_by_address(&self, addr: IpAddr, result: &mut [u8]) -> Result<usize, Error> {
+        let name = self.reverse_resolve(addr).await?;
+        result
+            .get_mut(..name.len())
+            .ok_or(Error::Overflow)?
+            .copy_from_slice(name.as_bytes());
+        Ok(name.len())
+    }
+}
+
+/// Run a query of [`Control::diagnostic_snapshot`], unless the snapshot is
+/// already out of time.
+async fn budgeted<T>(
+    deadline: Instant,
+    query: impl core::future::Future<Output = Result<T, Error>>,
+) -> Option<T> {
+    let now = Instant::now();
+    if now >= deadline {
+        return None;
+    }
+
+    with_timeout(SNAPSHOT_CMD_TIMEOUT.min(deadline - now), query)
+        .await
+        .ok()?
+        .ok()
+}
+
+/// Writes as much as fits into a byte buffer, and silently drops the rest.
+struct SliceWriter<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+}
+
+impl core::fmt::Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let mut n = s.len().min(self.buf.len() - self.len);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// Exclusive use of the AT channel, see [`Control::exclusive`].
+pub(crate) struct Exclusive<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    client: LockedClient<'c, 'a, INGRESS_BUF_SIZE>,
+}
+
+impl<const INGRESS_BUF_SIZE: usize> Exclusive<'_, '_, INGRESS_BUF_SIZE> {
+    /// Like [`Control::send`].
+    pub(crate) async fn send<Cmd: atat::AtatCmd>(
+        &mut self,
+        cmd: &Cmd,
+    ) -> Result<Cmd::Response, Error> {
+        self.control.error_code.set(None);
+        self.client
+            .send_retry::<Cmd>(cmd)
+            .await
+            .map_err(|e| self.control.decode_error(e))
+    }
+
+    /// Like [`Control::send_socket_command`].
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) async fn send_socket_command<Cmd: atat::AtatCmd>(
+        &mut self,
+        handle: ublox_sockets::SocketHandle,
+        cmd: &Cmd,
+    ) -> Result<Cmd::Response, Error> {
+        socket_error::send(&mut self.client, handle, cmd).await
+    }
+}
+
+#[cfg(all(test, feature = "internal-network-stack"))]
+mod tests {
+    use core::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        task::Poll,
+    };
+
+    use embassy_futures::{join::join, poll_once, select::select};
+    use ublox_sockets::SocketHandle;
+
+    use crate::{
+        asynch::modem_sim::{Fixture, Step, OK},
+        command::{
+            ip_transport_layer::{
+                types::EgressData, PrepareWriteSocketDataBinary, WriteSocketDataBinary,
+            },
+            AT,
+        },
+    };
+
+    use super::*;
+
+    fn remote() -> RemoteAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)).into()
+    }
+
+    /// Commands of the runner sent while a `Control` is in the middle of an
+    /// upload wait for it to finish, instead of ending up in the data.
+    #[test]
+    fn upload_is_not_interleaved() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+
+        let runner_client = host.client();
+        let control_client = host.client();
+
+        let script = [
+            Step::Upload {
+                cmd: b"AT+USOWR=0,5",
+                prompt: b"@",
+                len: 5,
+                response: b"\r\n+USOWR: 0,5\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT",
+                response: OK,
+            },
+        ];
+
+        let upload = async {
+            let socket = SocketHandle(0);
+            let mut at = control_client.lock().await;
+            at.send(&PrepareWriteSocketDataBinary { socket, length: 5 })
+                .await
+                .unwrap();
+            let written = at
+                .send(&WriteSocketDataBinary {
+                    data: EgressData::new(b"hello").unwrap(),
+                })
+                .await
+                .unwrap();
+            assert_eq!(written.length, 5);
+        };
+
+        let spam = async {
+            for _ in 0..3 {
+                (&runner_client).send(&AT).await.unwrap();
+            }
+        };
+
+        io.play(&mut sim, &script, join(upload, spam));
+
+        assert_eq!(sim.uploaded.as_slice(), b"hello");
+    }
+
+    /// A TCP round trip through the `Control`, with the data announced by
+    /// +UUSORD.
+    #[test]
+    fn tcp_echo() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+            Step::Upload {
+                cmd: b"AT+USOWR=0,5",
+                prompt: b"@",
+                len: 5,
+                response: b"\r\n+USOWR: 0,5\r\n\r\nOK\r\n",
+            },
+            Step::Urc {
+                delay: Duration::from_millis(10),
+                urc: b"+UUSORD: 0,5",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,5",
+                response: b"\r\n+USORD: 0,5,\"68656C6C6F\"\r\n\r\nOK\r\n",
+            },
+        ];
+
+        let mut buf = [0u8; 16];
+        let read = io.play(&mut sim, &script, async {
+            let handle = control.connect_tcp(None, remote(), 7).await.unwrap();
+            assert_eq!(control.write_socket_data(handle, b"hello").await, Ok(5));
+
+            // Read what +UUSORD announced, without asking for the count
+            while control.state_ch.socket_available(handle).is_none() {
+                Timer::after_millis(5).await;
+            }
+            control.read_socket(handle, &mut buf).await.unwrap()
+        });
+
+        assert_eq!(sim.uploaded.as_slice(), b"hello");
+        assert_eq!(&buf[..read], b"hello");
+
+        let stats = control.socket_stats();
+        assert_eq!(stats.sockets.len(), 1);
+        assert_eq!(stats.totals.bytes_in, 5);
+        assert_eq!(stats.totals.bytes_out, 5);
+        assert_eq!(stats.totals.rx_high_water, 5);
+    }
+
+    /// Only what the module took is reported written, and a full buffer is
+    /// reported as `WouldBlock` rather than as an error.
+    #[test]
+    fn write_backpressure() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        let handle = SocketHandle(0);
+
+        let script = [Step::Upload {
+            cmd: b"AT+USOWR=0,5",
+            prompt: b"@",
+            len: 5,
+            response: b"\r\n+USOWR: 0,3\r\n\r\nOK\r\n",
+        }];
+        let res = io.play(
+            &mut sim,
+            &script,
+            control.write_socket_data(handle, b"hello"),
+        );
+        assert_eq!(res, Ok(3));
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOWR=0,2",
+                response: b"\r\nERROR\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCTL=0,1",
+                response: b"\r\n+USOCTL: 0,1,11\r\n\r\nOK\r\n",
+            },
+        ];
+        let res = io.play(&mut sim, &script, control.write_socket_data(handle, b"lo"));
+        assert_eq!(res, Err(Error::Socket(SocketErrorKind::WouldBlock)));
+
+        let script = [Step::Upload {
+            cmd: b"AT+USOWR=0,2",
+            prompt: b"@",
+            len: 2,
+            response: b"\r\n+USOWR: 0,0\r\n\r\nOK\r\n",
+        }];
+        let res = io.play(&mut sim, &script, control.write_socket_data(handle, b"lo"));
+        assert_eq!(res, Err(Error::Socket(SocketErrorKind::WouldBlock)));
+    }
+
+    /// Sockets take turns being polled, with no more commands per call than
+    /// the budget.
+    #[test]
+    fn budgeted_socket_polls() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        for socket in 0..3 {
+            control
+                .state_ch
+                .register_socket(SocketHandle(socket), SocketProtocol::TCP, None);
+        }
+
+        let usord = |cmd: &'static [u8], pending: &'static [u8]| Step::Command {
+            cmd,
+            response: pending,
+        };
+        let script = [
+            usord(b"AT+USORD=0,0", b"\r\n+USORD: 0,0\r\n\r\nOK\r\n"),
+            usord(b"AT+USORD=1,0", b"\r\n+USORD: 1,12\r\n\r\nOK\r\n"),
+        ];
+        let ready = io.play(&mut sim, &script, control.poll_sockets(2));
+        assert_eq!(ready.unwrap().as_slice(), &[SocketHandle(1)]);
+        assert_eq!(control.state_ch.socket_available(SocketHandle(1)), Some(12));
+
+        // The next call carries on with the socket left out
+        let script = [
+            usord(b"AT+USORD=2,0", b"\r\n+USORD: 2,0\r\n\r\nOK\r\n"),
+            usord(b"AT+USORD=0,0", b"\r\n+USORD: 0,3\r\n\r\nOK\r\n"),
+        ];
+        let ready = io.play(&mut sim, &script, control.poll_sockets(2));
+        assert_eq!(ready.unwrap().as_slice(), &[SocketHandle(0)]);
+
+        // Every socket once at most
+        let script = [
+            usord(b"AT+USORD=1,0", b"\r\n+USORD: 1,12\r\n\r\nOK\r\n"),
+            usord(b"AT+USORD=2,0", b"\r\n+USORD: 2,0\r\n\r\nOK\r\n"),
+            usord(b"AT+USORD=0,0", b"\r\n+USORD: 0,3\r\n\r\nOK\r\n"),
+        ];
+        let ready = io.play(&mut sim, &script, control.poll_sockets(10));
+        assert_eq!(ready.unwrap().len(), 2);
+    }
+
+    /// A read returning less than a +UUSORD announced leaves the count to be
+    /// asked again, rather than failing or dropping what is still buffered.
+    #[test]
+    fn short_read_keeps_reading() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        let handle = SocketHandle(0);
+
+        control
+            .state_ch
+            .register_socket(handle, SocketProtocol::TCP, None);
+        control.state_ch.set_socket_available(handle, Some(40));
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USORD=0,40",
+                response: b"\r\n+USORD: 0,10,\"30313233343536373839\"\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,0",
+                response: b"\r\n+USORD: 0,5\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,5",
+                response: b"\r\n+USORD: 0,5,\"6162636465\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let mut buf = [0u8; 64];
+        io.play(&mut sim, &script, async {
+            assert_eq!(control.read_socket(handle, &mut buf).await, Ok(10));
+            assert_eq!(&buf[..10], b"0123456789");
+            assert_eq!(control.read_socket(handle, &mut buf).await, Ok(5));
+            assert_eq!(&buf[..5], b"abcde");
+        });
+    }
+
+    /// Without a +UUSORD, a waiting read asks the module again after the poll
+    /// interval.
+    #[test]
+    fn read_polls_without_urc() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut socket = control
+            .tcp_socket()
+            .poll_interval(Duration::from_millis(50));
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,0",
+                response: b"\r\n+USORD: 0,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,0",
+                response: b"\r\n+USORD: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,2",
+                response: b"\r\n+USORD: 0,2,\"4F4B\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let mut buf = [0u8; 16];
+        let read = io.play(&mut sim, &script, async {
+            socket.connect(remote(), 7).await.unwrap();
+            let start = Instant::now();
+            let read = socket.read(&mut buf).await;
+            assert!(start.elapsed() >= Duration::from_millis(50));
+            read
+        });
+        assert_eq!(read, Ok(2));
+        assert_eq!(&buf[..2], b"OK");
+    }
+
+    /// Each datagram is received on its own along with its sender, and one
+    /// longer than the buffer is cut short.
+    #[test]
+    fn udp_datagrams() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut socket = control.udp_socket();
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=17,5000",
+                response: b"\r\n+USOCR: 0,17,0\r\n\r\nOK\r\n",
+            },
+            Step::Upload {
+                cmd: b"AT+USOST=0,",
+                prompt: b"@",
+                len: 3,
+                response: b"\r\n+USOST: 0,3\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,0",
+                response: b"\r\n+USORF: 0,7\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,",
+                response: b"\r\n+USORF: 0,\"10.0.0.1\",53,3,\"616263\"\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,0",
+                response: b"\r\n+USORF: 0,4\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,",
+                response: b"\r\n+USORF: 0,\"10.0.0.2\",123,4,\"64617461\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let mut first = [0u8; 16];
+        let mut second = [0u8; 2];
+        let (a, b) = io.play(&mut sim, &script, async {
+            socket.bind(Some(5000)).await.unwrap();
+            let dns = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 53);
+            socket.send_to(dns, b"abc").await.unwrap();
+            let a = socket.recv_from(&mut first).await;
+            let b = socket.recv_from(&mut second).await;
+            (a, b)
+        });
+        assert_eq!(sim.uploaded.as_slice(), b"abc");
+        assert_eq!(
+            a,
+            Ok((
+                3,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 53)
+            ))
+        );
+        assert_eq!(&first[..3], b"abc");
+        assert_eq!(
+            b,
+            Ok((
+                2,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 123)
+            ))
+        );
+        assert_eq!(&second, b"da");
+    }
+
+    /// A pending receive is woken by the +UUSORF announcing a datagram, well
+    /// before its poll interval.
+    #[test]
+    fn udp_wakes_on_urc() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut socket = control.udp_socket().poll_interval(Duration::from_secs(60));
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=17",
+                response: b"\r\n+USOCR: 0,17,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,0",
+                response: b"\r\n+USORF: 0,0\r\n\r\nOK\r\n",
+            },
+            Step::Urc {
+                delay: Duration::from_millis(50),
+                urc: b"+UUSORF: 0,5",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,",
+                response: b"\r\n+USORF: 0,\"10.0.0.1\",53,5,\"68656C6C6F\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let mut buf = [0u8; 16];
+        let res = io.play(&mut sim, &script, async {
+            socket.bind(None).await.unwrap();
+            with_timeout(Duration::from_secs(5), socket.recv_from(&mut buf)).await
+        });
+        assert_eq!(
+            res,
+            Ok(Ok((
+                5,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 53)
+            )))
+        );
+        assert_eq!(&buf[..5], b"hello");
+    }
+
+    /// With HEX mode disabled, a datagram holding a final result code and a
+    /// URC is taken whole by its length.
+    #[test]
+    fn udp_binary_datagram() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        control.state_ch.set_hex_mode(false);
+
+        let mut socket = control.udp_socket();
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=17",
+                response: b"\r\n+USOCR: 0,17,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORF=0,",
+                response:
+                    b"\r\n+USORF: 0,\"10.0.0.1\",53,20,\"\r\nOK\r\n+UUSORF: 0,9\r\n\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let mut buf = [0u8; 32];
+        let res = io.play(&mut sim, &script, async {
+            socket.bind(None).await.unwrap();
+            control
+                .state_ch
+                .set_socket_available(socket.handle().unwrap(), Some(20));
+            socket.recv_from(&mut buf).await
+        });
+        assert_eq!(
+            res,
+            Ok((
+                20,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 53)
+            ))
+        );
+        assert_eq!(&buf[..20], b"\r\nOK\r\n+UUSORF: 0,9\r\n");
+    }
+
+    /// A connection is only probed once the probe interval passed, and closed
+    /// once the module reports it gone.
+    #[test]
+    fn tcp_liveness_probe() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut socket = control
+            .tcp_socket()
+            .probe_interval(Duration::from_millis(50));
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+        ];
+        io.play(&mut sim, &script, async {
+            socket.connect(remote(), 7).await.unwrap();
+            assert_eq!(socket.is_connected().await, Ok(true));
+        });
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCTL=0,10",
+                response: b"\r\n+USOCTL: 0,10,4\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCTL=0,10",
+                response: b"\r\n+USOCTL: 0,10,9\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCL=0",
+                response: OK,
+            },
+        ];
+        io.play(&mut sim, &script, async {
+            Timer::after_millis(60).await;
+            assert_eq!(socket.is_connected().await, Ok(true));
+            Timer::after_millis(60).await;
+            assert_eq!(socket.is_connected().await, Ok(false));
+        });
+        assert_eq!(socket.handle(), None);
+        assert!(!control.state_ch.is_socket_known(SocketHandle(0)));
+    }
+
+    /// Closing waits for the peer to acknowledge the data written, or closes
+    /// regardless after the flush timeout, reporting it.
+    #[test]
+    fn close_flushes() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let connect = |handle: &'static [u8], usoco: &'static [u8]| {
+            [
+                Step::Command {
+                    cmd: b"AT+USOCR=6",
+                    response: handle,
+                },
+                Step::Command {
+                    cmd: usoco,
+                    response: OK,
+                },
+            ]
+        };
+        let unacked = |cmd: &'static [u8], response: &'static [u8]| Step::Command { cmd, response };
+
+        let mut socket = control.tcp_socket();
+        let script = connect(b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n", b"AT+USOCO=0,");
+        io.play(&mut sim, &script, socket.connect(remote(), 7))
+            .unwrap();
+        let script = [
+            unacked(b"AT+USOCTL=0,11", b"\r\n+USOCTL: 0,11,120\r\n\r\nOK\r\n"),
+            unacked(b"AT+USOCTL=0,11", b"\r\n+USOCTL: 0,11,0\r\n\r\nOK\r\n"),
+            Step::Command {
+                cmd: b"AT+USOCL=0",
+                response: OK,
+            },
+        ];
+        assert_eq!(io.play(&mut sim, &script, socket.close()), Ok(()));
+
+        let mut socket = control
+            .tcp_socket()
+            .flush_timeout(Duration::from_millis(300));
+        let script = connect(b"\r\n+USOCR: 1,6,0\r\n\r\nOK\r\n", b"AT+USOCO=1,");
+        io.play(&mut sim, &script, socket.connect(remote(), 7))
+            .unwrap();
+        let script = [
+            unacked(b"AT+USOCTL=1,11", b"\r\n+USOCTL: 1,11,120\r\n\r\nOK\r\n"),
+            unacked(b"AT+USOCTL=1,11", b"\r\n+USOCTL: 1,11,120\r\n\r\nOK\r\n"),
+            Step::Command {
+                cmd: b"AT+USOCL=1",
+                response: OK,
+            },
+        ];
+        assert_eq!(
+            io.play(&mut sim, &script, socket.close()),
+            Err(Error::Generic(GenericError::Timeout))
+        );
+        assert!(!control.state_ch.is_socket_known(SocketHandle(1)));
+    }
+
+    /// Options are set on the socket of the module, so not before it is
+    /// created.
+    #[test]
+    fn tcp_socket_options() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut socket = control.tcp_socket();
+        assert_eq!(
+            io.play(&mut sim, &[], socket.set_keepalive(None)),
+            Err(Error::InvalidStateTransition)
+        );
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+USOSO=0,6,2,60000",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+USOSO=0,65535,8,1",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+USOSO=0,65535,128,1,5",
+                response: OK,
+            },
+        ];
+        io.play(&mut sim, &script, async {
+            socket.connect(remote(), 7).await.unwrap();
+            assert_eq!(
+                socket.set_keepalive(Some(Duration::from_secs(60))).await,
+                Ok(())
+            );
+            assert_eq!(
+                socket.set_linger(Some(Duration::from_secs(5))).await,
+                Ok(())
+            );
+        });
+    }
+
+    /// A +UUSOCL ends a pending read with EOF, and fails further writes,
+    /// without any command for the socket gone.
+    #[test]
+    fn remote_close() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut socket = control.tcp_socket();
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,0",
+                response: b"\r\n+USORD: 0,0\r\n\r\nOK\r\n",
+            },
+            Step::Urc {
+                delay: Duration::from_millis(20),
+                urc: b"+UUSOCL: 0",
+            },
+        ];
+        let mut buf = [0u8; 16];
+        io.play(&mut sim, &script, async {
+            socket.connect(remote(), 7).await.unwrap();
+            assert_eq!(socket.read(&mut buf).await, Ok(0));
+        });
+        assert!(socket.is_closed());
+
+        assert_eq!(
+            io.play(&mut sim, &[], socket.write(b"hello")),
+            Err(Error::Socket(SocketErrorKind::ConnectionReset))
+        );
+        assert_eq!(io.play(&mut sim, &[], socket.close()), Ok(()));
+        assert!(!control.state_ch.is_socket_known(SocketHandle(0)));
+    }
+
+    /// A write the module rejects with "operation not allowed" is retried in
+    /// smaller chunks, until it can't be split any further.
+    #[test]
+    fn write_retried_in_smaller_chunks() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        let handle = SocketHandle(0);
+
+        const NOT_ALLOWED: &[u8] = b"\r\n+CME ERROR: 3\r\n";
+        const NO_SOCKET_ERROR: Step<'static> = Step::Command {
+            cmd: b"AT+USOCTL=0,1",
+            response: b"\r\n+USOCTL: 0,1,0\r\n\r\nOK\r\n",
+        };
+
+        let data = [b'x'; 200];
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOWR=0,200",
+                response: NOT_ALLOWED,
+            },
+            NO_SOCKET_ERROR,
+            Step::Upload {
+                cmd: b"AT+USOWR=0,100",
+                prompt: b"@",
+                len: 100,
+                response: b"\r\n+USOWR: 0,100\r\n\r\nOK\r\n",
+            },
+            Step::Upload {
+                cmd: b"AT+USOWR=0,100",
+                prompt: b"@",
+                len: 100,
+                response: b"\r\n+USOWR: 0,100\r\n\r\nOK\r\n",
+            },
+        ];
+        let res = io.play(&mut sim, &script, control.write_socket_data(handle, &data));
+        assert_eq!(res, Ok(200));
+        assert_eq!(sim.uploaded.len(), 200);
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOWR=0,64",
+                response: NOT_ALLOWED,
+            },
+            NO_SOCKET_ERROR,
+        ];
+        let res = io.play(
+            &mut sim,
+            &script,
+            control.write_socket_data(handle, &data[..64]),
+        );
+        assert_eq!(res, Err(Error::Socket(SocketErrorKind::WouldBlock)));
+    }
+
+    /// A connect cancelled after +USOCR leaves the socket to the runner to
+    /// close.
+    #[test]
+    fn cancelled_connect() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Silent {
+                cmd: b"AT+USOCO=0,",
+            },
+        ];
+        io.play(
+            &mut sim,
+            &script,
+            select(
+                control.connect_tcp(None, remote(), 7),
+                Timer::after_millis(50),
+            ),
+        );
+
+        assert_eq!(
+            poll_once(control.state_ch.wait_socket_close()),
+            Poll::Ready(SocketHandle(0))
+        );
+    }
+
+    /// A raw command cancelled before its response leaves raw mode, so the
+    /// response of the next command and the URCs are digested as usual.
+    #[test]
+    fn cancelled_send_raw() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let script = [Step::Silent { cmd: b"AT+UTEST=1" }];
+        let mut buf = [0u8; 64];
+        io.play(
+            &mut sim,
+            &script,
+            select(
+                control.send_raw("AT+UTEST=1", &mut buf, Duration::from_secs(10)),
+                Timer::after_millis(50),
+            ),
+        );
+
+        assert!(!control.raw_mode.get());
+    }
+
+    /// A connect given up on leaves the socket to connect again, without the
+    /// late answer of the module taken for that of the next command.
+    #[test]
+    fn connect_timeout() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        let mut socket = control.tcp_socket();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Silent {
+                cmd: b"AT+USOCO=0,",
+            },
+            Step::Urc {
+                delay: Duration::from_millis(100),
+                urc: b"ERROR",
+            },
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 1,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=1,",
+                response: OK,
+            },
+        ];
+        io.play(&mut sim, &script, async {
+            assert_eq!(
+                socket
+                    .connect_with_timeout(remote(), 7, Duration::from_millis(50))
+                    .await,
+                Err(Error::Generic(GenericError::Timeout))
+            );
+            assert_eq!(socket.handle(), None);
+            assert_eq!(
+                poll_once(control.state_ch.wait_socket_close()),
+                Poll::Ready(SocketHandle(0))
+            );
+
+            assert_eq!(socket.connect(remote(), 7).await, Ok(()));
+        });
+        assert_eq!(socket.handle(), Some(SocketHandle(1)));
+    }
+
+    /// A request the way an HTTP client makes it, through `TcpConnect` and
+    /// `Dns` alone.
+    #[test]
+    fn tcp_client() {
+        use embedded_io_async::{Read as _, Write as _};
+        use embedded_nal_async::{AddrType, Dns, TcpConnect};
+
+        async fn request<T: TcpConnect, D: Dns>(
+            tcp: &T,
+            dns: &D,
+            buf: &mut [u8],
+        ) -> Result<usize, T::Error>
+        where
+            T::Error: From<D::Error>,
+        {
+            let ip = dns.get_host_by_name("10.0.0.1", AddrType::IPv4).await?;
+            let mut conn = tcp.connect(SocketAddr::new(ip, 80)).await?;
+            conn.write_all(b"GET").await?;
+            conn.read(buf).await
+        }
+
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        let client = control.tcp_client::<1>();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+            Step::Upload {
+                cmd: b"AT+USOWR=0,3",
+                prompt: b"@",
+                len: 3,
+                response: b"\r\n+USOWR: 0,3\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,0",
+                response: b"\r\n+USORD: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USORD=0,2",
+                response: b"\r\n+USORD: 0,2,\"4F4B\"\r\n\r\nOK\r\n",
+            },
+        ];
+        let mut buf = [0u8; 16];
+        let read = io.play(&mut sim, &script, request(&client, &control, &mut buf));
+        assert_eq!(read, Ok(2));
+        assert_eq!(&buf[..2], b"OK");
+        assert_eq!(sim.uploaded.as_slice(), b"GET");
+
+        // The only connection is taken
+        let remote = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 80);
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 1,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=1,",
+                response: OK,
+            },
+        ];
+        let res = io.play(&mut sim, &script, async {
+            let _conn = client.connect(remote).await.unwrap();
+            client.connect(remote).await.err()
+        });
+        assert_eq!(res, Some(Error::Socket(SocketErrorKind::NoBuffers)));
+    }
+
+    /// A socket bound to a local port already in use fails to connect, both if
+    /// bound by another socket of the driver, or as told by the module.
+    #[test]
+    fn tcp_socket_local_port() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+
+        let mut bound = control.tcp_socket().local_port(6000);
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6,6000",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+        ];
+        assert_eq!(
+            io.play(&mut sim, &script, bound.connect(remote(), 7)),
+            Ok(())
+        );
+
+        // Refused without asking the module
+        let mut socket = control.tcp_socket().local_port(6000);
+        assert_eq!(
+            io.play(&mut sim, &[], socket.connect(remote(), 7)),
+            Err(Error::Socket(SocketErrorKind::AddrInUse))
+        );
+        assert_eq!(socket.handle(), None);
+
+        let mut socket = control.tcp_socket().local_port(6001);
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6,6001",
+                response: b"\r\nERROR\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOER",
+                response: b"\r\n+USOER: 98\r\n\r\nOK\r\n",
+            },
+        ];
+        assert_eq!(
+            io.play(&mut sim, &script, socket.connect(remote(), 7)),
+            Err(Error::Socket(SocketErrorKind::AddrInUse))
+        );
+        assert_eq!(bound.handle(), Some(SocketHandle(0)));
+    }
+
+    /// Reconnecting a socket is not held up by the previous one failing to
+    /// close, which is left to the runner.
+    #[test]
+    fn reconnect_after_failed_close() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let control = host.control();
+        let mut socket = control.tcp_socket();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 0,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=0,",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+USOCL=0",
+                response: b"\r\nERROR\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCTL=0,1",
+                response: b"\r\n+USOCTL: 0,1,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCR=6",
+                response: b"\r\n+USOCR: 1,6,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+USOCO=1,",
+                response: OK,
+            },
+        ];
+        let res = io.play(&mut sim, &script, async {
+            socket.connect(remote(), 7).await.unwrap();
+            socket.connect(remote(), 7).await
+        });
+
+        assert_eq!(res, Ok(()));
+        assert_eq!(socket.handle(), Some(SocketHandle(1)));
+        assert_eq!(
+            poll_once(control.state_ch.wait_socket_close()),
+            Poll::Ready(SocketHandle(0))
+        );
     }
 }
@@ -0,0 +1,195 @@
+//! Events of the runner that don't surface as errors of a call, eg. timed
+//! out commands of the watchdog or failed commands of the init sequence,
+//! for the application to collect. They are broadcast to the subscribers of
+//! [`Control::diagnostics`](super::control::Control::diagnostics), and a
+//! subscriber that falls behind loses the oldest ones rather than hold up
+//! the runner.
+
+use atat::asynch::AtatClient;
+use embassy_sync::{
+    blocking_mutex::raw::NoopRawMutex,
+    pubsub::{self, PubSubChannel},
+};
+
+use crate::config::{DIAGNOSTICS_CAPACITY, MAX_DIAGNOSTICS_SUBSCRIBERS};
+
+use super::state::{OperationState, RecoveryAction};
+
+/// Name of a command type, eg. `SetPDPContextState`, cut to 32 bytes.
+pub type CommandName = heapless::String<32>;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DiagnosticEvent {
+    /// The module did not answer a command in time
+    CommandTimeout { cmd: CommandName },
+    /// A command of the init sequence failed. It may still succeed on a
+    /// retry.
+    InitStepFailed {
+        step: CommandName,
+        error: atat::Error,
+    },
+    /// The operation state changed
+    StateTransition {
+        from: OperationState,
+        to: OperationState,
+        reason: TransitionReason,
+    },
+    /// The AT interface hung, and the runner took the next step of the
+    /// reset ladder
+    ResetEscalation { rung: RecoveryAction },
+}
+
+/// Why the operation state changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransitionReason {
+    /// On the way to the desired state
+    Requested,
+    /// The multiplexer of a freshly initialized module is running
+    Initialized,
+    /// The network registration was lost
+    RegistrationLost,
+    /// The data connection failed with the current APN, so registration is
+    /// repeated with the next one
+    ApnFallback,
+    /// The module is reset to recover a hung AT interface
+    Recovery,
+    /// The module reboots to install a firmware
+    FirmwareInstall,
+}
+
+/// Subscriber of the [`DiagnosticEvent`]s, see
+/// [`Control::diagnostics`](super::control::Control::diagnostics).
+pub type DiagnosticsSubscriber<'a> = pubsub::Subscriber<
+    'a,
+    NoopRawMutex,
+    DiagnosticEvent,
+    DIAGNOSTICS_CAPACITY,
+    MAX_DIAGNOSTICS_SUBSCRIBERS,
+    0,
+>;
+
+pub(crate) struct Diagnostics {
+    channel: PubSubChannel<
+        NoopRawMutex,
+        DiagnosticEvent,
+        DIAGNOSTICS_CAPACITY,
+        MAX_DIAGNOSTICS_SUBSCRIBERS,
+        0,
+    >,
+}
+
+impl Diagnostics {
+    pub(crate) const fn new() -> Self {
+        Self {
+            channel: PubSubChannel::new(),
+        }
+    }
+
+    /// Broadcast `event`, dropping the oldest one if a subscriber has not
+    /// seen it yet. Never waits.
+    pub(crate) fn publish(&self, event: DiagnosticEvent) {
+        debug!("Diagnostic event: {:?}", event);
+        self.channel.immediate_publisher().publish_immediate(event);
+    }
+
+    pub(crate) fn subscriber(&self) -> Result<DiagnosticsSubscriber<'_>, pubsub::Error> {
+        self.channel.subscriber()
+    }
+}
+
+/// The name of the command type `Cmd`, without its module path and generic
+/// arguments.
+pub(crate) fn command_name<Cmd>() -> CommandName {
+    let name = core::any::type_name::<Cmd>();
+    let name = name.split('<').next().unwrap_or(name);
+    let name = name.rsplit("::").next().unwrap_or(name);
+
+    let mut cmd = CommandName::new();
+    for c in name.chars() {
+        if cmd.push(c).is_err() {
+            break;
+        }
+    }
+    cmd
+}
+
+/// Client of the init sequence, reporting the commands that fail.
+pub(crate) struct DiagnosedClient<'d, A> {
+    inner: A,
+    diagnostics: &'d Diagnostics,
+}
+
+impl<'d, A> DiagnosedClient<'d, A> {
+    pub(crate) fn new(inner: A, diagnostics: &'d Diagnostics) -> Self {
+        Self { inner, diagnostics }
+    }
+}
+
+impl<A: AtatClient> AtatClient for DiagnosedClient<'_, A> {
+    async fn send<Cmd: atat::AtatCmd>(&mut self, cmd: &Cmd) -> Result<Cmd::Response, atat::Error> {
+        let res = self.inner.send(cmd).await;
+        if let Err(error) = &res {
+            if matches!(error, atat::Error::Timeout) {
+                self.diagnostics.publish(DiagnosticEvent::CommandTimeout {
+                    cmd: command_name::<Cmd>(),
+                });
+            }
+            self.diagnostics.publish(DiagnosticEvent::InitStepFailed {
+                step: command_name::<Cmd>(),
+                error: error.clone(),
+            });
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_sync::pubsub::WaitResult;
+
+    use crate::command::psn::SetPDPContextState;
+
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(command_name::<SetPDPContextState>(), "SetPDPContextState");
+        assert_eq!(
+            command_name::<crate::command::file_system::GetFileSize<'static>>(),
+            "GetFileSize"
+        );
+    }
+
+    #[test]
+    fn lagging_subscriber() {
+        let diagnostics = Diagnostics::new();
+        let mut subscriber = diagnostics.subscriber().unwrap();
+
+        for _ in 0..DIAGNOSTICS_CAPACITY + 1 {
+            diagnostics.publish(DiagnosticEvent::ResetEscalation {
+                rung: RecoveryAction::SoftReset,
+            });
+        }
+        diagnostics.publish(DiagnosticEvent::ResetEscalation {
+            rung: RecoveryAction::HardReset,
+        });
+
+        assert!(matches!(
+            subscriber.try_next_message(),
+            Some(WaitResult::Lagged(2))
+        ));
+
+        let mut last = None;
+        while let Some(WaitResult::Message(event)) = subscriber.try_next_message() {
+            last = Some(event);
+        }
+        assert_eq!(
+            last,
+            Some(DiagnosticEvent::ResetEscalation {
+                rung: RecoveryAction::HardReset
+            })
+        );
+    }
+}
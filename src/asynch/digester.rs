@@ -0,0 +1,492 @@
+//! Digester of the AT channel. It is atat's [`AtDigester`], except while a
+//! command sent with [`Control::send_raw`](super::control::Control::send_raw)
+//! is pending. Everything up to the final result code is taken as its
+//! response then, so lines of the response that look like URCs, eg.
+//! `+CEREG: 2,1` in reply to `AT+CEREG?`, are not handed out as URCs.
+//!
+//! The numeric code of a `+CME ERROR` or `+CMS ERROR` final result code is
+//! kept for [`Control`](super::control::Control) to decode, as atat keeps
+//! only the codes it knows.
+//!
+//! Lines starting with one of the
+//! [`CUSTOM_URCS`](crate::config::CellularConfig::CUSTOM_URCS) prefixes are
+//! taken out before atat sees them, and queued for
+//! [`Control::custom_urc`](super::control::Control::custom_urc).
+//!
+//! Binary +USORD and +USORF responses, read with HEX mode disabled, are taken
+//! by the `<length>` they announce, as their payload may contain anything,
+//! eg. `\r\nOK\r\n` or a line looking like a URC.
+
+use core::cell::Cell;
+
+use atat::{digest::DigestResult, AtDigester, InternalError};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
+
+use crate::command::Urc;
+
+/// Maximum length of a custom URC line. Longer lines are dropped.
+pub const CUSTOM_URC_LEN: usize = 128;
+
+/// Number of custom URCs queued for the application. While the queue is
+/// full, further custom URCs are dropped.
+pub const CUSTOM_URC_CAPACITY: usize = 4;
+
+/// A custom URC line, without the line ending.
+pub type CustomUrc = heapless::Vec<u8, CUSTOM_URC_LEN>;
+
+pub(crate) type CustomUrcChannel = Channel<NoopRawMutex, CustomUrc, CUSTOM_URC_CAPACITY>;
+
+/// Numeric code of the last `+CME ERROR` or `+CMS ERROR` final result code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ErrorCode {
+    Cme(u16),
+    Cms(u16),
+}
+
+pub struct Digester<'a> {
+    inner: AtDigester<Urc>,
+    raw_mode: &'a Cell<bool>,
+    error_code: &'a Cell<Option<ErrorCode>>,
+    custom_urcs: &'a CustomUrcChannel,
+    custom_prefixes: &'static [&'static str],
+}
+
+impl<'a> Digester<'a> {
+    pub(crate) fn new(
+        raw_mode: &'a Cell<bool>,
+        error_code: &'a Cell<Option<ErrorCode>>,
+        custom_urcs: &'a CustomUrcChannel,
+        custom_prefixes: &'static [&'static str],
+    ) -> Self {
+        Self {
+            inner: AtDigester::new(),
+            raw_mode,
+            error_code,
+            custom_urcs,
+            custom_prefixes,
+        }
+    }
+}
+
+impl atat::Digester for Digester<'_> {
+    fn digest<'a>(&mut self, buf: &'a [u8]) -> (DigestResult<'a>, usize) {
+        if !self.raw_mode.get() {
+            #[cfg(feature = "internal-network-stack")]
+            match binary_socket_data(buf) {
+                Frame::Complete(info, len) => return (DigestResult::Response(Ok(info)), len),
+                Frame::Incomplete => return (DigestResult::None, 0),
+                Frame::Other => {}
+            }
+
+            if let Some((line, len)) = take_custom_urc(self.custom_prefixes, buf) {
+                queue_custom_urc(self.custom_urcs, line);
+                return (DigestResult::None, len);
+            }
+
+            let (result, len) = self.inner.digest(buf);
+            if let DigestResult::Response(Err(
+                InternalError::CmeError(_) | InternalError::CmsError(_),
+            )) = result
+            {
+                self.error_code.set(error_code(&buf[..len]));
+            }
+            return (result, len);
+        }
+
+        let (result, len) = digest_raw(&mut self.inner, buf);
+        if len > 0 {
+            // Only ever a single response is raw
+            self.raw_mode.set(false);
+        }
+        (result, len)
+    }
+}
+
+/// The complete line at the start of `buf`, if it starts with one of
+/// `prefixes`, along with the number of bytes it takes up.
+fn take_custom_urc<'a>(prefixes: &[&str], buf: &'a [u8]) -> Option<(&'a [u8], usize)> {
+    let start = buf.iter().position(|b| !matches!(b, b'\r' | b'\n'))?;
+    let line = &buf[start..];
+    if !prefixes.iter().any(|p| line.starts_with(p.as_bytes())) {
+        return None;
+    }
+
+    // atat keeps an incomplete line in the buffer until the rest arrives
+    let end = line.windows(2).position(|w| w == b"\r\n")?;
+    Some((&line[..end], start + end + 2))
+}
+
+/// What the start of the buffer is to [`binary_socket_data`].
+#[cfg(feature = "internal-network-stack")]
+#[derive(Debug, PartialEq)]
+enum Frame<'a> {
+    /// A binary response, with its information text and the number of bytes
+    /// it takes up, up to and including the final result code
+    Complete(&'a [u8], usize),
+    /// Possibly a binary response, to be told once more bytes arrive
+    Incomplete,
+    /// Anything else, including HEX mode responses, for atat to digest
+    Other,
+}
+
+/// Why a header did not parse, see [`binary_socket_data`].
+#[cfg(feature = "internal-network-stack")]
+enum Mismatch {
+    Incomplete,
+    Other,
+}
+
+/// Take a binary +USORD or +USORF response at the start of `buf`, as
+/// `+USORD: <socket>,<length>,"<data>"` or
+/// `+USORF: <socket>,"<ip>",<port>,<length>,"<data>"`, by the `<length>`
+/// bytes of `<data>`.
+///
+/// The HEX mode is not known here, but the two tell apart: HEX data of
+/// `<length>` bytes takes twice as many digits, so only a binary payload is
+/// followed by the closing quote after `<length>` bytes.
+#[cfg(feature = "internal-network-stack")]
+fn binary_socket_data(buf: &[u8]) -> Frame<'_> {
+    let Some(start) = buf.iter().position(|b| !matches!(b, b'\r' | b'\n')) else {
+        return Frame::Other;
+    };
+    let line = &buf[start..];
+
+    let mut pos = 0;
+    match binary_header(line, &mut pos) {
+        Ok(length) => {
+            let end = pos + length;
+            match line.get(end) {
+                None => return Frame::Incomplete,
+                Some(b'"') => {}
+                Some(_) => return Frame::Other,
+            }
+
+            let rest = &line[end + 1..];
+            let trailer = rest.trim_ascii_start();
+            if trailer.starts_with(b"OK\r\n") {
+                let len = start + end + 1 + (rest.len() - trailer.len()) + 4;
+                Frame::Complete(&line[..end + 1], len)
+            } else if b"OK\r\n".starts_with(trailer) {
+                Frame::Incomplete
+            } else {
+                // Eg. a URC in between, which can't be told from the data
+                Frame::Other
+            }
+        }
+        Err(Mismatch::Incomplete) => Frame::Incomplete,
+        Err(Mismatch::Other) => Frame::Other,
+    }
+}
+
+/// Parse the header of a binary socket data response up to the opening quote
+/// of the data, returning the `<length>` with `pos` at the data.
+#[cfg(feature = "internal-network-stack")]
+fn binary_header(line: &[u8], pos: &mut usize) -> Result<usize, Mismatch> {
+    let prefix = line.get(..7).unwrap_or(line);
+    let udp = match prefix {
+        b"+USORD:" => false,
+        b"+USORF:" => true,
+        _ if prefix.len() < 7
+            && (b"+USORD:".starts_with(prefix) || b"+USORF:".starts_with(prefix)) =>
+        {
+            return Err(Mismatch::Incomplete)
+        }
+        _ => return Err(Mismatch::Other),
+    };
+    *pos = 7;
+    while line.get(*pos) == Some(&b' ') {
+        *pos += 1;
+    }
+
+    header_number(line, pos)?;
+    header_byte(line, pos, b',')?;
+    if udp {
+        header_byte(line, pos, b'"')?;
+        // The longest IPv6 address, in brackets
+        for _ in 0..48 {
+            match line.get(*pos) {
+                None => return Err(Mismatch::Incomplete),
+                Some(b'"') => break,
+                Some(b'\r' | b'\n') => return Err(Mismatch::Other),
+                Some(_) => *pos += 1,
+            }
+        }
+        header_byte(line, pos, b'"')?;
+        header_byte(line, pos, b',')?;
+        header_number(line, pos)?;
+        header_byte(line, pos, b',')?;
+    }
+    let length = header_number(line, pos)?;
+    header_byte(line, pos, b',')?;
+    header_byte(line, pos, b'"')?;
+    Ok(length)
+}
+
+#[cfg(feature = "internal-network-stack")]
+fn header_byte(line: &[u8], pos: &mut usize, byte: u8) -> Result<(), Mismatch> {
+    match line.get(*pos) {
+        None => Err(Mismatch::Incomplete),
+        Some(&b) if b == byte => {
+            *pos += 1;
+            Ok(())
+        }
+        Some(_) => Err(Mismatch::Other),
+    }
+}
+
+#[cfg(feature = "internal-network-stack")]
+fn header_number(line: &[u8], pos: &mut usize) -> Result<usize, Mismatch> {
+    let begin = *pos;
+    let mut n: usize = 0;
+    loop {
+        match line.get(*pos) {
+            None => return Err(Mismatch::Incomplete),
+            Some(b) if b.is_ascii_digit() && *pos - begin < 5 => {
+                n = n * 10 + usize::from(b - b'0');
+                *pos += 1;
+            }
+            Some(_) if *pos > begin => return Ok(n),
+            Some(_) => return Err(Mismatch::Other),
+        }
+    }
+}
+
+/// The numeric code of the `+CME ERROR` or `+CMS ERROR` in `buf`. Verbose
+/// codes are not decoded.
+fn error_code(buf: &[u8]) -> Option<ErrorCode> {
+    let (start, kind): (_, fn(u16) -> ErrorCode) = match buf
+        .windows(11)
+        .position(|w| w == b"+CME ERROR:" || w == b"+CMS ERROR:")
+    {
+        Some(i) if buf[i + 3] == b'E' => (i + 11, ErrorCode::Cme),
+        Some(i) => (i + 11, ErrorCode::Cms),
+        None => return None,
+    };
+    let code = buf[start..].trim_ascii();
+    core::str::from_utf8(code).ok()?.parse().ok().map(kind)
+}
+
+fn queue_custom_urc(custom_urcs: &CustomUrcChannel, line: &[u8]) {
+    let Ok(urc) = CustomUrc::from_slice(line) else {
+        warn!("Dropping custom URC of {} bytes", line.len());
+        return;
+    };
+    if custom_urcs.try_send(urc).is_err() {
+        warn!(
+            "Dropping custom URC {:?}, queue full",
+            atat::helpers::LossyStr(line)
+        );
+    }
+}
+
+/// Take everything up to the final result code in `buf` as the response.
+fn digest_raw<'a>(inner: &mut AtDigester<Urc>, buf: &'a [u8]) -> (DigestResult<'a>, usize) {
+    let mut start = 0;
+    while let Some(end) = buf[start..]
+        .windows(2)
+        .position(|w| w == b"\r\n")
+        .map(|i| start + i)
+    {
+        let line = &buf[start..end];
+        if line == b"OK" {
+            return (
+                DigestResult::Response(Ok(buf[..start].trim_ascii())),
+                end + 2,
+            );
+        }
+
+        if line == b"ERROR" || line.starts_with(b"+CME ERROR:") || line.starts_with(b"+CMS ERROR:")
+        {
+            // atat knows how to decode the error codes
+            let error = match inner.digest(&buf[start..end + 2]) {
+                (DigestResult::Response(Err(e)), _) => e,
+                _ => InternalError::Error,
+            };
+            return (DigestResult::Response(Err(error)), end + 2);
+        }
+
+        start = end + 2;
+    }
+    (DigestResult::None, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_response() {
+        let mut inner = AtDigester::<Urc>::new();
+
+        // Incomplete until the final result code
+        let buf = b"\r\n+CEREG: 2,1,\"4E2D\",\"01A2D001\",7\r\n";
+        assert_eq!(digest_raw(&mut inner, buf), (DigestResult::None, 0));
+
+        let buf = b"\r\n+CEREG: 2,1,\"4E2D\",\"01A2D001\",7\r\n\r\nOK\r\n";
+        assert_eq!(
+            digest_raw(&mut inner, buf),
+            (
+                DigestResult::Response(Ok(&b"+CEREG: 2,1,\"4E2D\",\"01A2D001\",7"[..])),
+                buf.len()
+            )
+        );
+
+        let buf = b"\r\n+UTEST: 1\r\n+UTEST: 2\r\nOK\r\n+UUSORD: 0,1\r\n";
+        assert_eq!(
+            digest_raw(&mut inner, buf),
+            (
+                DigestResult::Response(Ok(&b"+UTEST: 1\r\n+UTEST: 2"[..])),
+                28
+            )
+        );
+
+        let buf = b"\r\nERROR\r\n";
+        assert_eq!(
+            digest_raw(&mut inner, buf),
+            (DigestResult::Response(Err(InternalError::Error)), buf.len())
+        );
+    }
+
+    #[test]
+    fn raw_mode_is_single_shot() {
+        let raw_mode = Cell::new(true);
+        let error_code = Cell::new(None);
+        let custom_urcs = CustomUrcChannel::new();
+        let mut digester = Digester::new(&raw_mode, &error_code, &custom_urcs, &[]);
+
+        let buf = b"\r\n+CREG: 0,1\r\n\r\nOK\r\n";
+        let (result, _) = atat::Digester::digest(&mut digester, buf);
+        assert_eq!(result, DigestResult::Response(Ok(&b"+CREG: 0,1"[..])));
+        assert!(!raw_mode.get());
+    }
+
+    #[test]
+    fn custom_urc() {
+        let prefixes = ["+UFOTASTAT"];
+
+        assert_eq!(
+            take_custom_urc(&prefixes, b"\r\n+UFOTASTAT: 1,2\r\n+CREG: 1\r\n"),
+            Some((&b"+UFOTASTAT: 1,2"[..], 19))
+        );
+        // Wait for the rest of the line
+        assert_eq!(take_custom_urc(&prefixes, b"\r\n+UFOTASTAT: 1"), None);
+        assert_eq!(take_custom_urc(&prefixes, b"\r\n+CREG: 1\r\n"), None);
+        assert_eq!(take_custom_urc(&[], b"\r\n+UFOTASTAT: 1\r\n"), None);
+    }
+
+    #[test]
+    fn custom_urc_queue_is_bounded() {
+        let raw_mode = Cell::new(false);
+        let error_code = Cell::new(None);
+        let custom_urcs = CustomUrcChannel::new();
+        let mut digester = Digester::new(&raw_mode, &error_code, &custom_urcs, &["+UFOTASTAT"]);
+
+        let buf = b"\r\n+UFOTASTAT: 1\r\n";
+        for _ in 0..CUSTOM_URC_CAPACITY + 2 {
+            let (result, len) = atat::Digester::digest(&mut digester, buf);
+            assert_eq!((result, len), (DigestResult::None, buf.len()));
+        }
+        assert!(custom_urcs.is_full());
+        assert_eq!(
+            custom_urcs.try_receive().unwrap().as_slice(),
+            b"+UFOTASTAT: 1"
+        );
+
+        // Too long to queue, but still taken out of the buffer
+        while custom_urcs.try_receive().is_ok() {}
+        let mut buf = heapless::Vec::<u8, { CUSTOM_URC_LEN + 16 }>::new();
+        buf.extend_from_slice(b"+UFOTASTAT: ").unwrap();
+        buf.resize(CUSTOM_URC_LEN + 14, b'0').unwrap();
+        buf.extend_from_slice(b"\r\n").unwrap();
+        let (_, len) = atat::Digester::digest(&mut digester, &buf);
+        assert_eq!(len, buf.len());
+        assert!(custom_urcs.is_empty());
+    }
+
+    /// Raw modem bytes of binary +USORD and +USORF responses, whose payload
+    /// holds line endings, a final result code and a URC, are taken by their
+    /// length and parse with the commands that read them.
+    #[cfg(feature = "internal-network-stack")]
+    #[test]
+    fn binary_socket_data() {
+        use atat::AtatCmd;
+        use ublox_sockets::SocketHandle;
+
+        use crate::command::ip_transport_layer::{ReadSocketDataBinary, ReadUDPSocketDataBinary};
+
+        let raw_mode = Cell::new(false);
+        let error_code = Cell::new(None);
+        let custom_urcs = CustomUrcChannel::new();
+        let mut digester = Digester::new(&raw_mode, &error_code, &custom_urcs, &[]);
+
+        let buf = b"\r\n+USORD: 0,21,\"a\r\nOK\r\n+UUSORD: 0,1\r\n\"\r\n\r\nOK\r\n";
+        // Incomplete within the header, payload and final result code
+        for end in [4, 12, 30, buf.len() - 3] {
+            assert_eq!(
+                atat::Digester::digest(&mut digester, &buf[..end]),
+                (DigestResult::None, 0)
+            );
+        }
+        let (result, len) = atat::Digester::digest(&mut digester, buf);
+        assert_eq!(len, buf.len());
+        let DigestResult::Response(resp) = result else {
+            panic!("{:?}", result);
+        };
+        let cmd = ReadSocketDataBinary {
+            socket: SocketHandle(0),
+            length: 21,
+        };
+        let res = cmd.parse(resp).unwrap();
+        assert_eq!(res.data.length, 21);
+        assert_eq!(&res.data.payload[..], b"a\r\nOK\r\n+UUSORD: 0,1\r\n");
+
+        let buf =
+            b"\r\n+USORF: 1,\"[2001:db8::1]\",65535,4,\"\r\nOK\"\r\n\r\nOK\r\n+UUSORF: 1,4\r\n";
+        let (result, len) = atat::Digester::digest(&mut digester, buf);
+        assert_eq!(len, buf.len() - 14);
+        let DigestResult::Response(resp) = result else {
+            panic!("{:?}", result);
+        };
+        let cmd = ReadUDPSocketDataBinary {
+            socket: SocketHandle(1),
+            length: 4,
+        };
+        let res = cmd.parse(resp).unwrap();
+        assert_eq!(
+            res.remote_endpoint(),
+            "[2001:db8::1]:65535".parse().unwrap()
+        );
+        assert_eq!(&res.payload[..], b"\r\nOK");
+
+        // HEX mode and count-only responses are left to atat
+        for buf in [
+            &b"\r\n+USORD: 0,2,\"4F4B\"\r\n\r\nOK\r\n"[..],
+            b"\r\n+USORD: 0,5\r\n\r\nOK\r\n",
+            b"\r\n+USORF: 0,\"10.0.0.1\",53,2,\"4F4B\"\r\n\r\nOK\r\n",
+        ] {
+            assert_eq!(super::binary_socket_data(buf), Frame::Other);
+        }
+    }
+
+    #[test]
+    fn cme_error_code() {
+        let raw_mode = Cell::new(false);
+        let error_code = Cell::new(None);
+        let custom_urcs = CustomUrcChannel::new();
+        let mut digester = Digester::new(&raw_mode, &error_code, &custom_urcs, &[]);
+
+        let buf = b"\r\n+CME ERROR: 1521\r\n";
+        let (result, _) = atat::Digester::digest(&mut digester, buf);
+        assert!(matches!(
+            result,
+            DigestResult::Response(Err(InternalError::CmeError(_)))
+        ));
+        assert_eq!(error_code.get(), Some(ErrorCode::Cme(1521)));
+
+        assert_eq!(
+            super::error_code(b"\r\n+CMS ERROR: 330\r\n"),
+            Some(ErrorCode::Cms(330))
+        );
+        assert_eq!(super::error_code(b"\r\n+CME ERROR: SIM busy\r\n"), None);
+    }
+}
@@ -0,0 +1,343 @@
+//! Direct link mode (+USODL) of a single socket. Socket data is transferred
+//! transparently over the serial interface, rather than in chunks of +USOWR
+//! and +USORD, which gives far better throughput for bulk transfers.
+//!
+//! Direct link mode is entered on the CMUX data channel, so the AT channel
+//! keeps working meanwhile and its ingress never sees the payload.
+
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::task::Poll;
+
+use atat::asynch::{AtatClient, SimpleClient};
+use embassy_futures::select::{select, select3, Either, Either3};
+use embassy_sync::{
+    blocking_mutex::{raw::NoopRawMutex, Mutex},
+    pipe::Pipe,
+    waitqueue::WakerRegistration,
+};
+use embassy_time::{Duration, Timer};
+use embedded_io_async::{ErrorType, Read, Write};
+use ublox_sockets::SocketHandle;
+
+use crate::command::{ip_transport_layer::SetDirectLinkMode, Urc};
+use crate::error::Error;
+
+use super::runner::CMUX_CHANNEL_SIZE;
+
+/// Size of each of the buffers between the application and the data channel.
+pub const DIRECT_LINK_BUF_SIZE: usize = 512;
+
+/// Silence required around the "+++" escape sequence.
+const ESCAPE_GUARD_TIME: Duration = Duration::from_millis(1200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Request {
+    None,
+    Enter(SocketHandle),
+    Exit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LinkStatus {
+    Closed,
+    /// Waiting for the module to confirm direct link mode
+    Entering,
+    Open,
+    /// The module rejected direct link mode
+    Failed,
+}
+
+struct Shared {
+    request: Request,
+    status: LinkStatus,
+    runner_waker: WakerRegistration,
+    link_waker: WakerRegistration,
+}
+
+/// Buffers and state shared between [`DirectLink`] and the runner.
+pub struct State {
+    rx: Pipe<NoopRawMutex, DIRECT_LINK_BUF_SIZE>,
+    tx: Pipe<NoopRawMutex, DIRECT_LINK_BUF_SIZE>,
+    shared: Mutex<NoopRawMutex, RefCell<Shared>>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State {
+    pub const fn new() -> Self {
+        Self {
+            rx: Pipe::new(),
+            tx: Pipe::new(),
+            shared: Mutex::new(RefCell::new(Shared {
+                request: Request::None,
+                status: LinkStatus::Closed,
+                runner_waker: WakerRegistration::new(),
+                link_waker: WakerRegistration::new(),
+            })),
+        }
+    }
+
+    fn set_status(&self, status: LinkStatus) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.status = status;
+            s.link_waker.wake();
+        });
+    }
+
+    fn request(&self, request: Request) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.request = request;
+            s.runner_waker.wake();
+        });
+    }
+
+    async fn wait_status(&self, f: impl Fn(LinkStatus) -> bool) -> LinkStatus {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if f(s.status) {
+                    return Poll::Ready(s.status);
+                }
+                s.link_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    async fn wait_request(&self, f: impl Fn(Request) -> bool) -> Request {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if f(s.request) {
+                    return Poll::Ready(core::mem::replace(&mut s.request, Request::None));
+                }
+                s.runner_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+}
+
+/// Application side of direct link mode, reading and writing the data of the
+/// socket while the link is open. Reads return 0 once the link is closed.
+pub struct DirectLink<'d> {
+    state: &'d State,
+}
+
+impl<'d> DirectLink<'d> {
+    pub(crate) fn new(state: &'d State) -> Self {
+        Self { state }
+    }
+
+    /// Enter direct link mode on `socket`, which has to be connected already.
+    pub async fn open(&mut self, socket: SocketHandle) -> Result<(), Error> {
+        if self.status() != LinkStatus::Closed && self.status() != LinkStatus::Failed {
+            return Err(Error::Busy);
+        }
+
+        self.state.rx.clear();
+        self.state.tx.clear();
+        self.state.set_status(LinkStatus::Entering);
+        self.state.request(Request::Enter(socket));
+
+        match self.state.wait_status(|s| s != LinkStatus::Entering).await {
+            LinkStatus::Open => Ok(()),
+            _ => Err(Error::InvalidStateTransition),
+        }
+    }
+
+    /// Leave direct link mode, once everything written so far is sent.
+    pub async fn close(&mut self) {
+        if self.status() != LinkStatus::Open {
+            return;
+        }
+
+        self.state.request(Request::Exit);
+        self.state.wait_status(|s| s != LinkStatus::Open).await;
+    }
+
+    pub fn status(&self) -> LinkStatus {
+        self.state.shared.lock(|s| s.borrow().status)
+    }
+}
+
+impl ErrorType for DirectLink<'_> {
+    type Error = core::convert::Infallible;
+}
+
+impl Read for DirectLink<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match select(
+            self.state.rx.read(buf),
+            self.state.wait_status(|s| s != LinkStatus::Open),
+        )
+        .await
+        {
+            Either::First(n) => Ok(n),
+            // Hand out what was received before the link closed
+            Either::Second(_) => Ok(self.state.rx.try_read(buf).unwrap_or(0)),
+        }
+    }
+}
+
+impl Write for DirectLink<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if self.status() != LinkStatus::Open {
+            return Ok(0);
+        }
+        Ok(self.state.tx.write(buf).await)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        poll_fn(|cx| {
+            if self.state.tx.is_empty() || self.status() != LinkStatus::Open {
+                return Poll::Ready(());
+            }
+            // The runner wakes the link side once it drained the buffer
+            self.state
+                .shared
+                .lock(|s| s.borrow_mut().link_waker.register(cx.waker()));
+            Poll::Pending
+        })
+        .await;
+        Ok(())
+    }
+}
+
+/// Runner side of direct link mode, bridging the buffers to the CMUX data
+/// channel.
+pub(crate) struct DirectLinkRunner<'d> {
+    state: &'d State,
+}
+
+impl<'d> DirectLinkRunner<'d> {
+    pub(crate) fn new(state: &'d State) -> Self {
+        Self { state }
+    }
+
+    pub(crate) async fn run(
+        &self,
+        channel: &mut at_cmux::Channel<'_, CMUX_CHANNEL_SIZE>,
+        config: atat::Config,
+    ) -> ! {
+        // A link of a previous session is gone with the module reset
+        self.state.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.request = Request::None;
+            s.status = match s.status {
+                LinkStatus::Entering => LinkStatus::Failed,
+                LinkStatus::Open => LinkStatus::Closed,
+                status => status,
+            };
+            s.link_waker.wake();
+        });
+
+        loop {
+            let Request::Enter(socket) = self
+                .state
+                .wait_request(|r| matches!(r, Request::Enter(_)))
+                .await
+            else {
+                continue;
+            };
+
+            let mut buf = [0u8; 32];
+            let mut at_client = SimpleClient::new(
+                &mut *channel,
+                atat::AtDigester::<Urc>::new(),
+                &mut buf,
+                config,
+            );
+            if let Err(e) = at_client.send(&SetDirectLinkMode { socket }).await {
+                warn!("[{}] Failed to enter direct link mode: {:?}", socket, e);
+                self.state.set_status(LinkStatus::Failed);
+                continue;
+            }
+            drop(at_client);
+
+            info!("[{}] Direct link mode entered", socket);
+            self.state.set_status(LinkStatus::Open);
+
+            // The module drops DCD when the socket closes
+            channel.set_hangup_detection(0x04, 0x00);
+            let escape = self.bridge(channel).await;
+            channel.clear_hangup_detection();
+
+            if escape {
+                Timer::after(ESCAPE_GUARD_TIME).await;
+                let _ = channel.write_all(b"+++").await;
+                let _ = channel.flush().await;
+                Timer::after(ESCAPE_GUARD_TIME).await;
+            }
+            // Throw away what remains of the payload and the "DISCONNECT"
+            drain(channel).await;
+
+            info!("[{}] Direct link mode left", socket);
+            self.state.set_status(LinkStatus::Closed);
+        }
+    }
+
+    /// Move data until asked to leave, returning whether direct link mode
+    /// still has to be escaped.
+    async fn bridge(&self, channel: &mut at_cmux::Channel<'_, CMUX_CHANNEL_SIZE>) -> bool {
+        let mut rx_buf = [0u8; CMUX_CHANNEL_SIZE];
+        let mut tx_buf = [0u8; CMUX_CHANNEL_SIZE];
+
+        loop {
+            match select3(
+                channel.read(&mut rx_buf),
+                self.state.tx.read(&mut tx_buf),
+                self.state.wait_request(|r| r == Request::Exit),
+            )
+            .await
+            {
+                Either3::First(Ok(n)) if n > 0 => {
+                    self.state.rx.write_all(&rx_buf[..n]).await;
+                }
+                // Hung up by the module
+                Either3::First(_) => return false,
+                Either3::Second(n) => {
+                    if channel.write_all(&tx_buf[..n]).await.is_err() {
+                        return false;
+                    }
+                    let _ = channel.flush().await;
+                    if self.state.tx.is_empty() {
+                        self.state.shared.lock(|s| s.borrow_mut().link_waker.wake());
+                    }
+                }
+                Either3::Third(_) => {
+                    // Send everything written before the close
+                    while let Ok(n) = self.state.tx.try_read(&mut tx_buf) {
+                        if channel.write_all(&tx_buf[..n]).await.is_err() {
+                            return false;
+                        }
+                    }
+                    let _ = channel.flush().await;
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+/// Wait for the channel to fall idle.
+async fn drain(channel: &mut at_cmux::Channel<'_, CMUX_CHANNEL_SIZE>) {
+    let mut buf = [0u8; 64];
+    while let Ok(Ok(n)) =
+        embassy_time::with_timeout(Duration::from_millis(250), channel.read(&mut buf)).await
+    {
+        if n == 0 {
+            break;
+        }
+    }
+}
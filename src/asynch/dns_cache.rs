@@ -0,0 +1,145 @@
+//! Addresses of recently resolved names. Names often resolve to several
+//! addresses, and handing out the next one on each query for the same name
+//! keeps a single unreachable address from stranding the device, without
+//! querying the DNS servers for every connection attempt.
+
+use core::net::IpAddr;
+
+use embassy_time::Instant;
+use embedded_nal_async::AddrType;
+use heapless::{String, Vec};
+
+use crate::{
+    command::dns::MAX_DNS_RESULTS,
+    config::{DNS_ROTATION_WINDOW, MAX_DNS_CACHE_ENTRIES},
+};
+
+struct Entry {
+    name: String<128>,
+    addresses: Vec<IpAddr, MAX_DNS_RESULTS>,
+    /// Index of the address to hand out next
+    next: usize,
+    expires: Instant,
+}
+
+pub(crate) struct DnsCache {
+    /// Oldest entry first
+    entries: Vec<Entry, MAX_DNS_CACHE_ENTRIES>,
+}
+
+impl DnsCache {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// The next address of `name` of the type `addr_type`, or `None` if the
+    /// name was not resolved within the [`DNS_ROTATION_WINDOW`].
+    pub(crate) fn next(&mut self, name: &str, addr_type: AddrType, now: Instant) -> Option<IpAddr> {
+        self.entries.retain(|e| e.expires > now);
+
+        let entry = self.entries.iter_mut().find(|e| e.name == name)?;
+        let len = entry.addresses.len();
+        for _ in 0..len {
+            let addr = entry.addresses[entry.next];
+            entry.next = (entry.next + 1) % len;
+            if is_type(&addr, addr_type) {
+                return Some(addr);
+            }
+        }
+        None
+    }
+
+    /// Note the addresses `name` resolved to at `now`, restarting its
+    /// rotation. The oldest entry makes room if the cache is full.
+    pub(crate) fn insert(&mut self, name: &str, addresses: &[IpAddr], now: Instant) {
+        let (Ok(name), Ok(addresses)) = (String::try_from(name), Vec::from_slice(addresses)) else {
+            return;
+        };
+        if addresses.is_empty() {
+            return;
+        }
+
+        self.entries.retain(|e| e.name != name);
+        if self.entries.is_full() {
+            self.entries.remove(0);
+        }
+        let _ = self.entries.push(Entry {
+            name,
+            addresses,
+            next: 0,
+            expires: now.checked_add(DNS_ROTATION_WINDOW).unwrap_or(Instant::MAX),
+        });
+    }
+}
+
+fn is_type(addr: &IpAddr, addr_type: AddrType) -> bool {
+    match addr_type {
+        AddrType::IPv4 => addr.is_ipv4(),
+        AddrType::IPv6 => addr.is_ipv6(),
+        AddrType::Either => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    use embassy_time::Duration;
+
+    use super::*;
+
+    const A: IpAddr = IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34));
+    const B: IpAddr = IpAddr::V4(Ipv4Addr::new(93, 184, 216, 35));
+    const C: IpAddr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+
+    #[test]
+    fn rotates_addresses() {
+        let mut cache = DnsCache::new();
+        let now = Instant::from_secs(1000);
+        assert_eq!(cache.next("example.com", AddrType::Either, now), None);
+
+        cache.insert("example.com", &[A, B, C], now);
+        assert_eq!(cache.next("example.com", AddrType::Either, now), Some(A));
+        assert_eq!(cache.next("example.com", AddrType::Either, now), Some(B));
+        assert_eq!(cache.next("example.com", AddrType::Either, now), Some(C));
+        assert_eq!(cache.next("example.com", AddrType::IPv4, now), Some(A));
+        assert_eq!(cache.next("example.com", AddrType::IPv4, now), Some(B));
+        assert_eq!(cache.next("example.com", AddrType::IPv6, now), Some(C));
+        assert_eq!(cache.next("other.com", AddrType::Either, now), None);
+
+        // Resolving again starts over
+        cache.insert("example.com", &[B, A], now);
+        assert_eq!(cache.next("example.com", AddrType::Either, now), Some(B));
+    }
+
+    #[test]
+    fn expires() {
+        let mut cache = DnsCache::new();
+        let now = Instant::from_secs(1000);
+        cache.insert("example.com", &[A], now);
+
+        let later = now + DNS_ROTATION_WINDOW - Duration::from_millis(1);
+        assert_eq!(cache.next("example.com", AddrType::IPv4, later), Some(A));
+        assert_eq!(cache.next("example.com", AddrType::IPv6, later), None);
+        assert_eq!(
+            cache.next("example.com", AddrType::IPv4, now + DNS_ROTATION_WINDOW),
+            None
+        );
+    }
+
+    #[test]
+    fn evicts_oldest() {
+        let mut cache = DnsCache::new();
+        let now = Instant::from_secs(1000);
+        let names = ["a.com", "b.com", "c.com", "d.com", "e.com"];
+        assert!(names.len() > MAX_DNS_CACHE_ENTRIES);
+
+        for name in names {
+            cache.insert(name, &[A], now);
+        }
+        assert_eq!(cache.next("a.com", AddrType::Either, now), None);
+        assert_eq!(cache.next("e.com", AddrType::Either, now), Some(A));
+    }
+}
@@ -0,0 +1,103 @@
+use embassy_time::Duration;
+
+use crate::{
+    asynch::state::OperationState,
+    command::{
+        mobile_control::{types::Functionality, SetModuleFunctionality},
+        test::{
+            responses::RxMeasurement, types::TestMode, RxTest, SetTestMode, TxTest,
+            MAX_TEST_TIME_MS,
+        },
+    },
+    error::Error,
+    modules::ModuleParams as _,
+};
+
+use super::control::Control;
+
+/// The non-signaling RF test mode of the module, obtained through
+/// [`Control::factory_test`]. The module stays in test mode until
+/// [`FactoryTest::exit`] is called, or it is reset.
+pub struct FactoryTest<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> FactoryTest<'c, 'a, INGRESS_BUF_SIZE> {
+    /// Enter the test mode, from the AT+CFUN state the module requires.
+    pub(crate) async fn enter(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Result<Self, Error> {
+        let module = control.state_ch.module().ok_or(Error::Uninitialized)?;
+
+        control
+            .send(&SetModuleFunctionality {
+                fun: module.test_mode_cfun(),
+                rst: None,
+            })
+            .await?;
+        control
+            .send(&SetTestMode {
+                mode: TestMode::Test,
+            })
+            .await?;
+
+        info!("Entered RF test mode");
+        Ok(Self { control })
+    }
+
+    /// Transmit a carrier for `duration`, of up to 10 s.
+    pub async fn tx_carrier(
+        &mut self,
+        band: u16,
+        channel: u32,
+        power: i16,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        self.control
+            .send(&TxTest::new(band, channel, power, test_time(duration)?))
+            .await
+    }
+
+    /// Measure the received signal strength for `duration`, of up to 10 s.
+    pub async fn rx_rssi(
+        &mut self,
+        band: u16,
+        channel: u32,
+        gain: u8,
+        duration: Duration,
+    ) -> Result<RxMeasurement, Error> {
+        self.control
+            .send(&RxTest::new(band, channel, gain, test_time(duration)?))
+            .await
+    }
+
+    /// Leave the test mode, switching the radio off like before.
+    pub async fn exit(self) -> Result<(), Error> {
+        self.control
+            .send(&SetTestMode {
+                mode: TestMode::Normal,
+            })
+            .await?;
+
+        let fun = match self.control.state_ch.operation_state(None) {
+            OperationState::AirplaneMode => Functionality::AirplaneMode,
+            _ => self
+                .control
+                .state_ch
+                .module()
+                .ok_or(Error::Uninitialized)?
+                .radio_off_cfun(),
+        };
+        self.control
+            .send(&SetModuleFunctionality { fun, rst: None })
+            .await?;
+
+        info!("Left RF test mode");
+        Ok(())
+    }
+}
+
+fn test_time(duration: Duration) -> Result<u16, Error> {
+    match u16::try_from(duration.as_millis()) {
+        Ok(ms) if ms <= MAX_TEST_TIME_MS => Ok(ms),
+        _ => Err(Error::Overflow),
+    }
+}
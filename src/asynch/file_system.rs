@@ -0,0 +1,246 @@
+use core::future::Future;
+
+use heapless::{String, Vec};
+
+use crate::{
+    command::file_system::{
+        responses::READ_BLOCK_SIZE, DeleteFile, DownloadFile, GetFileSize, GetFreeSpace, ListFiles,
+        PrepareDownloadFile, ReadBlock,
+    },
+    error::Error,
+};
+
+use super::{
+    control::Control,
+    progress::{Progress, ProgressReceiver},
+    runner::MAX_CMD_LEN,
+};
+
+/// Access to the module file system, obtained through
+/// [`Control::file_system`].
+pub struct FileSystemService<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> FileSystemService<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self { control }
+    }
+
+    /// Write `data` to the file `name`. If the file already exists, the data
+    /// is appended. The bytes written so far are published to the receivers
+    /// of [`Self::write_file_with_progress`].
+    pub async fn write_file(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        let res = self.write_chunks(name, data).await;
+        self.control.state_ch.set_file_progress(match res {
+            Ok(()) => Progress::Completed,
+            Err(_) => Progress::Failed,
+        });
+        res
+    }
+
+    /// Like [`Self::write_file`], along with a receiver of its progress.
+    pub fn write_file_with_progress<'s>(
+        &'s self,
+        name: &'s str,
+        data: &'s [u8],
+    ) -> Result<
+        (
+            impl Future<Output = Result<(), Error>> + use<'s, 'c, 'a, INGRESS_BUF_SIZE>,
+            ProgressReceiver<'a>,
+        ),
+        Error,
+    > {
+        // Published first, so that the receiver does not start out with the
+        // outcome of an earlier write
+        self.control.state_ch.set_file_progress(Progress::Bytes {
+            done: 0,
+            total: data.len(),
+        });
+        let receiver =
+            self.control
+                .state_ch
+                .file_progress_receiver()
+                .ok_or(Error::SubscriberOverflow(
+                    embassy_sync::pubsub::Error::MaximumSubscribersReached,
+                ))?;
+        Ok((self.write_file(name, data), receiver))
+    }
+
+    async fn write_chunks(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        if data.len() > self.free_space().await? {
+            return Err(Error::NotEnoughSpace);
+        }
+
+        let mut done = 0;
+        for chunk in data.chunks(MAX_CMD_LEN) {
+            let mut at = self.control.exclusive().await?;
+            at.send(&PrepareDownloadFile {
+                filename: name,
+                size: chunk.len(),
+            })
+            .await?;
+            at.send(&DownloadFile {
+                text: atat::serde_bytes::Bytes::new(chunk),
+            })
+            .await?;
+
+            done += chunk.len();
+            self.control.state_ch.set_file_progress(Progress::Bytes {
+                done,
+                total: data.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Read the file `name` into `buf`, returning the number of bytes read.
+    /// Returns `Error::Overflow` if the file does not fit in `buf`.
+    pub async fn read_file_into(&self, name: &str, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut reader = self.read_file_stream(name);
+        let mut len = 0;
+        loop {
+            if len == buf.len() {
+                // Make sure there is nothing left of the file
+                let mut probe = [0u8; 1];
+                return match reader.read(&mut probe).await? {
+                    0 => Ok(len),
+                    _ => Err(Error::Overflow),
+                };
+            }
+
+            match reader.read(&mut buf[len..]).await? {
+                0 => return Ok(len),
+                n => len += n,
+            }
+        }
+    }
+
+    /// Read the file `name` in blocks of up to 512 bytes.
+    pub fn read_file_stream<'n>(&self, name: &'n str) -> FileReader<'c, 'a, 'n, INGRESS_BUF_SIZE> {
+        FileReader {
+            fs: FileSystemService {
+                control: self.control,
+            },
+            name,
+            offset: 0,
+        }
+    }
+
+    /// Read the file `name` in chunks of up to `chunk_size` bytes, handing
+    /// each to `f` in order. Returns the size of the file.
+    ///
+    /// The file is read up to the size reported for it beforehand, and
+    /// `Error::FileSizeMismatch` is returned if it turns out shorter.
+    /// `chunk_size` is capped at 512 bytes.
+    pub async fn read_file_chunks(
+        &self,
+        name: &str,
+        chunk_size: usize,
+        mut f: impl FnMut(&[u8]),
+    ) -> Result<usize, Error> {
+        let size = self.file_size(name).await?;
+        let chunk_size = chunk_size.clamp(1, READ_BLOCK_SIZE);
+
+        let mut reader = self.read_file_stream(name);
+        let mut buf = [0u8; READ_BLOCK_SIZE];
+        while reader.offset() < size {
+            let len = chunk_size.min(size - reader.offset());
+            let n = reader.read(&mut buf[..len]).await?;
+            if n > 0 {
+                f(&buf[..n]);
+            }
+
+            // A short block is the end of the file
+            if n < len {
+                break;
+            }
+        }
+
+        if reader.offset() != size {
+            return Err(Error::FileSizeMismatch);
+        }
+        Ok(size)
+    }
+
+    pub async fn delete_file(&self, name: &str) -> Result<(), Error> {
+        match self.control.send(&DeleteFile { filename: name }).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(self.map_not_found(name, e).await),
+        }
+    }
+
+    pub async fn list_files(&self) -> Result<Vec<String<248>, 10>, Error> {
+        self.control.send(&ListFiles).await
+    }
+
+    /// Size of the file `name`, in bytes.
+    pub async fn file_size(&self, name: &str) -> Result<usize, Error> {
+        match self.control.send(&GetFileSize { filename: name }).await {
+            Ok(size) => Ok(size.size),
+            Err(e) => Err(self.map_not_found(name, e).await),
+        }
+    }
+
+    /// Remaining free space of the file system, in bytes.
+    pub async fn free_space(&self) -> Result<usize, Error> {
+        Ok(self.control.send(&GetFreeSpace).await?.free)
+    }
+
+    /// The modem reports a missing file with a generic CME error, so check
+    /// whether the file exists to give a meaningful error.
+    async fn map_not_found(&self, name: &str, e: Error) -> Error {
+        if !matches!(e, Error::Cme(_) | Error::Atat(atat::Error::CmeError(_))) {
+            return e;
+        }
+
+        match self.list_files().await {
+            Ok(files) if !files.iter().any(|f| f == name) => Error::FileNotFound,
+            _ => e,
+        }
+    }
+}
+
+/// Reads a file from the module file system block by block, with
+/// [`FileSystemService::read_file_stream`].
+pub struct FileReader<'c, 'a, 'n, const INGRESS_BUF_SIZE: usize> {
+    fs: FileSystemService<'c, 'a, INGRESS_BUF_SIZE>,
+    name: &'n str,
+    offset: usize,
+}
+
+impl<const INGRESS_BUF_SIZE: usize> FileReader<'_, '_, '_, INGRESS_BUF_SIZE> {
+    /// Read the next block of the file into `buf`, returning the number of
+    /// bytes read, or 0 at the end of the file.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let block = match self
+            .fs
+            .control
+            .send(&ReadBlock {
+                filename: self.name,
+                offset: self.offset,
+                size: buf.len().min(READ_BLOCK_SIZE),
+            })
+            .await
+        {
+            Ok(block) => block,
+            Err(e) => return Err(self.fs.map_not_found(self.name, e).await),
+        };
+
+        let data = block.data();
+        let data = &data[..data.len().min(buf.len())];
+        buf[..data.len()].copy_from_slice(data);
+        self.offset += data.len();
+        Ok(data.len())
+    }
+
+    /// Current read position in the file.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
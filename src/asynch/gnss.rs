@@ -0,0 +1,95 @@
+use crate::{
+    command::gnss::{
+        responses::{GpsFixData, RecommendedMinimumData},
+        types::{AidingMode, GnssMode, GnssSystems, NmeaMode, UnsolicitedAidingMode},
+        GetGnssPower, GetGpsFixData, GetRecommendedMinimumData, SetAidingIndication, SetGnssPower,
+        SetGpsFixDataStorage, SetRecommendedMinimumDataStorage,
+    },
+    error::Error,
+};
+
+use super::control::Control;
+
+/// Control of a GNSS receiver attached to (or integrated in) the cellular
+/// module, obtained through [`Control::gnss`].
+pub struct Gnss<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> Gnss<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self { control }
+    }
+
+    /// Power on the GNSS receiver with the given aiding mode and GNSS
+    /// systems, and enable storing of the RMC and GGA sentences so they can
+    /// be polled with [`Gnss::rmc`] and [`Gnss::gga`].
+    pub async fn power_on(
+        &self,
+        aid_mode: AidingMode,
+        gnss_systems: GnssSystems,
+    ) -> Result<(), Error> {
+        if aid_mode != AidingMode::NONE {
+            self.control
+                .send(&SetAidingIndication {
+                    mode: UnsolicitedAidingMode::Enabled,
+                })
+                .await?;
+        }
+
+        self.control
+            .send(&SetGnssPower {
+                mode: GnssMode::On,
+                aid_mode: Some(aid_mode),
+                gnss_systems: Some(gnss_systems),
+            })
+            .await?;
+
+        self.control
+            .send(&SetRecommendedMinimumDataStorage {
+                mode: NmeaMode::Enabled,
+            })
+            .await?;
+        self.control
+            .send(&SetGpsFixDataStorage {
+                mode: NmeaMode::Enabled,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Power off the GNSS receiver with +UGPS=0. The stored NMEA sentences
+    /// are no longer updated afterwards.
+    pub async fn power_off(&self) -> Result<(), Error> {
+        self.control
+            .send(&SetGnssPower {
+                mode: GnssMode::Off,
+                aid_mode: None,
+                gnss_systems: None,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Whether the GNSS receiver is on, as reported by +UGPS?. The module
+    /// reports the mode last accepted, so it is on as soon as a power on
+    /// succeeded, before the receiver has a fix. As commands are serialized,
+    /// a query made during [`Gnss::power_on`] or [`Gnss::power_off`] is only
+    /// answered once that sequence is done.
+    pub async fn is_powered(&self) -> Result<bool, Error> {
+        let state = self.control.send(&GetGnssPower).await?;
+        Ok(state.mode == GnssMode::On)
+    }
+
+    /// Get the last RMC sentence stored by the module. Use
+    /// [`RecommendedMinimumData::has_fix`] to check for a valid fix.
+    pub async fn rmc(&self) -> Result<RecommendedMinimumData, Error> {
+        self.control.send(&GetRecommendedMinimumData).await
+    }
+
+    /// Get the last GGA sentence stored by the module.
+    pub async fn gga(&self) -> Result<GpsFixData, Error> {
+        self.control.send(&GetGpsFixData).await
+    }
+}
@@ -0,0 +1,169 @@
+use embassy_time::{with_timeout, Duration};
+
+use crate::{
+    command::{
+        device_data_security::types::SecurityProfileId,
+        http::{
+            types::{HttpContentType, HttpMethod, HttpParam, HttpProfileId, HttpSecurity},
+            GetHttpError, HttpCommand, SetHttpProfile,
+        },
+    },
+    error::Error,
+};
+
+use super::control::Control;
+
+/// File in the module file system the server response is stored in
+const RESPONSE_FILENAME: &str = "http_response";
+
+/// Client for the modem's internal HTTP(S) client, obtained through
+/// [`Control::http`].
+///
+/// The module stores the full server response, including the HTTP header, in
+/// its file system. Requests wait for the `+UUHTTPCR` URC and read the stored
+/// response back into the user buffer.
+pub struct HttpClient<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    profile_id: HttpProfileId,
+    timeout: Duration,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> HttpClient<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(
+        control: &'c Control<'a, INGRESS_BUF_SIZE>,
+        profile_id: HttpProfileId,
+    ) -> Self {
+        Self {
+            control,
+            profile_id,
+            timeout: Duration::from_secs(180),
+        }
+    }
+
+    /// Set how long to wait for the modem to complete a request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Configure the HTTP profile with a server and port. If a security
+    /// profile is given, the profile will use HTTPS with that security
+    /// profile, which has to be set up with
+    /// [`Control::configure_security_profile`] or
+    /// [`Control::register_security_profile`] first.
+    pub async fn configure(
+        &self,
+        server: &str,
+        port: u16,
+        security_profile: Option<SecurityProfileId>,
+    ) -> Result<(), Error> {
+        let server = heapless::String::try_from(server).map_err(|_| Error::Overflow)?;
+
+        self.set_param(HttpParam::ServerName(server)).await?;
+        self.set_param(HttpParam::ServerPort(port)).await?;
+
+        let security = match security_profile {
+            Some(_) => HttpSecurity::Enabled,
+            None => HttpSecurity::Disabled,
+        };
+        self.set_param(HttpParam::Secure(security, security_profile))
+            .await
+    }
+
+    pub async fn set_param(&self, param: HttpParam) -> Result<(), Error> {
+        if let HttpParam::Secure(_, Some(security_profile)) = param {
+            self.control.check_security_profile(security_profile)?;
+        }
+
+        self.control
+            .send(&SetHttpProfile {
+                profile_id: self.profile_id,
+                param,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Perform a GET request, returning the number of response bytes written
+    /// to `buf`.
+    pub async fn get(&self, path: &str, buf: &mut [u8]) -> Result<usize, Error> {
+        self.request(HttpMethod::Get, path, None, None, buf).await
+    }
+
+    /// Perform a POST request with `data` as body, returning the number of
+    /// response bytes written to `buf`.
+    pub async fn post(
+        &self,
+        path: &str,
+        data: &str,
+        content_type: HttpContentType,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        self.request(
+            HttpMethod::PostData,
+            path,
+            Some(data),
+            Some(content_type),
+            buf,
+        )
+        .await
+    }
+
+    /// Perform a request, returning the number of response bytes written to
+    /// `buf`. Returns `Error::NotEnoughSpace` without starting the request if
+    /// the file system has less room than `buf` for the response.
+    pub async fn request(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        payload: Option<&str>,
+        content_type: Option<HttpContentType>,
+        buf: &mut [u8],
+    ) -> Result<usize, Error> {
+        let fs = self.control.file_system();
+        if buf.len() > fs.free_space().await? {
+            return Err(Error::NotEnoughSpace);
+        }
+
+        self.control.state_ch.clear_http_response();
+
+        self.control
+            .send(&HttpCommand {
+                profile_id: self.profile_id,
+                method,
+                path,
+                response_filename: RESPONSE_FILENAME,
+                payload,
+                content_type,
+            })
+            .await?;
+
+        let response = with_timeout(
+            self.timeout,
+            self.control.state_ch.wait_http_response(self.profile_id.0),
+        )
+        .await?;
+
+        if response.http_result != 1 {
+            let err = self
+                .control
+                .send(&GetHttpError {
+                    profile_id: self.profile_id,
+                })
+                .await?;
+            error!(
+                "HTTP request failed. Class: {}, code: {}",
+                err.error_class, err.error_code
+            );
+            return Err(Error::Http(err));
+        }
+
+        let res = fs.read_file_into(RESPONSE_FILENAME, buf).await;
+
+        if fs.delete_file(RESPONSE_FILENAME).await.is_err() {
+            warn!("Failed to delete HTTP response file");
+        }
+
+        res
+    }
+}
@@ -1,11 +1,54 @@
+#[cfg(feature = "at-trace")]
+pub mod at_trace;
+mod attach_backoff;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "audio")]
+pub mod call;
 pub mod control;
+pub mod diagnostics;
+mod digester;
+#[cfg(all(feature = "internal-network-stack", not(feature = "ppp")))]
+pub mod direct_link;
+#[cfg(feature = "internal-network-stack")]
+mod dns_cache;
+pub mod factory_test;
+pub mod file_system;
+#[cfg(feature = "gnss")]
+pub mod gnss;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(test)]
+mod modem_sim;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
 mod network;
+#[cfg(feature = "ppp")]
+pub mod ppp;
+#[cfg(feature = "ppp")]
+mod ppp_supervision;
+pub mod progress;
 mod pwr;
 mod resources;
 pub mod runner;
+mod sim;
+#[cfg(feature = "sms")]
+pub mod sms;
+#[cfg(feature = "internal-network-stack")]
+pub mod socket;
+#[cfg(feature = "internal-network-stack")]
+mod socket_error;
+#[cfg(feature = "internal-network-stack")]
+mod socket_ingress;
+#[cfg(feature = "internal-network-stack")]
+mod socket_set;
 pub mod state;
+#[cfg(feature = "std")]
+pub mod std_transport;
 mod urc_handler;
+mod watchdog;
 
+pub use digester::{CustomUrc, CUSTOM_URC_CAPACITY, CUSTOM_URC_LEN};
 pub use resources::Resources;
 pub use runner::Runner;
 #[cfg(feature = "internal-network-stack")]
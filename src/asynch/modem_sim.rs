@@ -0,0 +1,387 @@
+//! A scripted modem at the other end of an in-memory serial line, to run the
+//! AT flows of the driver against on the host. The [`Step`]s of the script
+//! are taken in order, and any command but the expected one fails the test.
+//!
+//! The driver end of the line is a [`Host`], which sets up the AT channel the
+//! way the runner does, so the flows go through the same [`ProxyClient`]s,
+//! digester and URC handling as on the target.
+
+use core::{cell::Cell, convert::Infallible, future::Future};
+
+use atat::{helpers::LossyStr, AtatIngress as _, ResponseSlot, UrcChannel};
+use embassy_futures::{
+    join::{join, join3},
+    select::{select, Either},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel, pipe::Pipe};
+use embassy_time::{Duration, Timer};
+use embedded_io_async::Write as _;
+
+use crate::{
+    command::Urc,
+    config::{CellularConfig, NoPin},
+};
+
+use super::{
+    control::{AtLock, Control, ProxyClient},
+    digester::{CustomUrcChannel, Digester, ErrorCode},
+    runner::{MAX_CMD_LEN, URC_SUBSCRIBERS},
+    state,
+    urc_handler::UrcHandler,
+    Resources,
+};
+
+const PIPE_LEN: usize = 1024;
+
+/// Large enough for a +USORF response of `INGRESS_CHUNK_SIZE` bytes, up to
+/// 1024.
+pub(crate) const INGRESS_BUF_SIZE: usize = 2304;
+
+const URC_CAPACITY: usize = 4;
+
+pub(crate) type HostResources = Resources<INGRESS_BUF_SIZE, URC_CAPACITY>;
+
+/// The line of a test with the resources of its [`Host`], see
+/// [`Fixture::split`].
+pub(crate) struct Fixture {
+    duplex: Duplex,
+    resources: HostResources,
+}
+
+impl Fixture {
+    pub(crate) fn new() -> Self {
+        Self {
+            duplex: Duplex::new(),
+            resources: HostResources::new(),
+        }
+    }
+
+    /// The [`ModemSim`] on one end of the line, and the [`Host`] with its
+    /// [`HostIo`] on the other.
+    pub(crate) fn split(&mut self) -> (ModemSim<'_>, Host<'_>, HostIo<'_>) {
+        let Self { duplex, resources } = self;
+        let duplex = &*duplex;
+        let (host, io) = Host::new(resources, duplex);
+        (ModemSim::new(duplex.modem()), host, io)
+    }
+}
+
+/// The final result code of a command without an information response.
+pub(crate) const OK: &[u8] = b"\r\nOK\r\n";
+
+/// Both directions of the serial line between the host and the modem.
+pub(crate) struct Duplex {
+    to_modem: Pipe<NoopRawMutex, PIPE_LEN>,
+    to_host: Pipe<NoopRawMutex, PIPE_LEN>,
+}
+
+impl Duplex {
+    pub(crate) const fn new() -> Self {
+        Self {
+            to_modem: Pipe::new(),
+            to_host: Pipe::new(),
+        }
+    }
+
+    /// The end of the line the driver is connected to.
+    pub(crate) fn host(&self) -> Port<'_> {
+        Port {
+            rx: &self.to_host,
+            tx: &self.to_modem,
+        }
+    }
+
+    /// The end of the line the [`ModemSim`] is connected to.
+    pub(crate) fn modem(&self) -> Port<'_> {
+        Port {
+            rx: &self.to_modem,
+            tx: &self.to_host,
+        }
+    }
+}
+
+/// One end of a [`Duplex`].
+pub(crate) struct Port<'a> {
+    rx: &'a Pipe<NoopRawMutex, PIPE_LEN>,
+    tx: &'a Pipe<NoopRawMutex, PIPE_LEN>,
+}
+
+impl embedded_io_async::ErrorType for Port<'_> {
+    type Error = Infallible;
+}
+
+impl embedded_io_async::Read for Port<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        Ok(self.rx.read(buf).await)
+    }
+}
+
+impl embedded_io_async::Write for Port<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        Ok(self.tx.write(buf).await)
+    }
+}
+
+pub(crate) enum Step<'s> {
+    /// Answer the command starting with `cmd` with `response`, which is sent
+    /// as is, including the final result code.
+    Command { cmd: &'s [u8], response: &'s [u8] },
+    /// Take the command starting with `cmd` without ever answering it, as a
+    /// hung module does.
+    Silent { cmd: &'s [u8] },
+    /// Answer the command starting with `cmd` with `prompt`, then take `len`
+    /// bytes of binary data and answer them with `response`.
+    Upload {
+        cmd: &'s [u8],
+        prompt: &'s [u8],
+        len: usize,
+        response: &'s [u8],
+    },
+    /// Send the URC line `urc` after `delay`.
+    Urc { delay: Duration, urc: &'s [u8] },
+}
+
+pub(crate) struct ModemSim<'a> {
+    port: Port<'a>,
+    /// The binary data taken by the uploads so far
+    pub(crate) uploaded: heapless::Vec<u8, PIPE_LEN>,
+}
+
+impl<'a> ModemSim<'a> {
+    pub(crate) fn new(port: Port<'a>) -> Self {
+        Self {
+            port,
+            uploaded: heapless::Vec::new(),
+        }
+    }
+
+    /// Play `script`, returning once all of its steps are taken.
+    pub(crate) async fn run(&mut self, script: &[Step<'_>]) {
+        for step in script {
+            match *step {
+                Step::Command { cmd, response } => {
+                    self.expect(cmd).await;
+                    self.port.tx.write_all(response).await;
+                }
+                Step::Silent { cmd } => self.expect(cmd).await,
+                Step::Upload {
+                    cmd,
+                    prompt,
+                    len,
+                    response,
+                } => {
+                    self.expect(cmd).await;
+                    self.port.tx.write_all(prompt).await;
+                    for _ in 0..len {
+                        let byte = self.read_byte().await;
+                        self.uploaded.push(byte).unwrap();
+                    }
+                    self.port.tx.write_all(response).await;
+                }
+                Step::Urc { delay, urc } => {
+                    Timer::after(delay).await;
+                    self.port.tx.write_all(b"\r\n").await;
+                    self.port.tx.write_all(urc).await;
+                    self.port.tx.write_all(b"\r\n").await;
+                }
+            }
+        }
+    }
+
+    /// Fail the test if the host sent anything the script did not take.
+    pub(crate) fn assert_idle(&self) {
+        let mut pending = [0u8; 64];
+        let len = self.port.rx.try_read(&mut pending).unwrap_or(0);
+        assert!(len == 0, "unexpected {:?}", LossyStr(&pending[..len]));
+    }
+
+    async fn expect(&mut self, cmd: &[u8]) {
+        let mut line = heapless::Vec::<u8, 256>::new();
+        loop {
+            match self.read_byte().await {
+                b'\r' => break,
+                b'\n' if line.is_empty() => {}
+                byte => line.push(byte).unwrap(),
+            }
+        }
+        assert!(
+            line.starts_with(cmd),
+            "expected {:?}, got {:?}",
+            LossyStr(cmd),
+            LossyStr(&line)
+        );
+    }
+
+    async fn read_byte(&mut self) -> u8 {
+        let mut byte = [0u8; 1];
+        self.port.rx.read(&mut byte).await;
+        byte[0]
+    }
+}
+
+/// Configuration of a module without any pins, for the flows of the
+/// [`NetDevice`](super::network::NetDevice).
+pub(crate) struct SimConfig;
+
+impl<'a> CellularConfig<'a> for SimConfig {
+    type ResetPin = NoPin;
+    type PowerPin = NoPin;
+    type VintPin = NoPin;
+    type DtrPin = NoPin;
+
+    #[cfg(feature = "ppp")]
+    const PPP_CONFIG: embassy_net_ppp::Config<'a> = embassy_net_ppp::Config {
+        username: b"",
+        password: b"",
+    };
+}
+
+/// The driver end of a [`Duplex`], with the AT channel set up as by the
+/// runner: the [`ProxyClient`]s of the runner and the [`Control`] send their
+/// commands through the request channel, and the responses come back through
+/// an `Ingress` with the digester of the runner. URCs are handled by the
+/// [`UrcHandler`], updating the shared state.
+pub(crate) struct Host<'a> {
+    pub(crate) ch: state::Runner<'a>,
+    req_slot: &'a Channel<NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
+    res_slot: &'a ResponseSlot<INGRESS_BUF_SIZE>,
+    raw_mode: &'a Cell<bool>,
+    error_code: &'a Cell<Option<ErrorCode>>,
+    custom_urcs: &'a CustomUrcChannel,
+    at_lock: &'a AtLock,
+}
+
+/// Moves the traffic of a [`Host`] over the line, see [`HostIo::play`].
+pub(crate) struct HostIo<'a> {
+    ch: state::Runner<'a>,
+    duplex: &'a Duplex,
+    req_slot: &'a Channel<NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
+    urc_channel: &'a UrcChannel<Urc, URC_CAPACITY, URC_SUBSCRIBERS>,
+    ingress: atat::Ingress<'a, Digester<'a>, Urc, INGRESS_BUF_SIZE, URC_CAPACITY, URC_SUBSCRIBERS>,
+}
+
+impl<'a> Host<'a> {
+    pub(crate) fn new(resources: &'a mut HostResources, duplex: &'a Duplex) -> (Self, HostIo<'a>) {
+        let Resources {
+            ch,
+            res_slot,
+            req_slot,
+            raw_mode,
+            error_code,
+            custom_urcs,
+            at_lock,
+            urc_channel,
+            ingress_buf,
+            ..
+        } = resources;
+        let (res_slot, req_slot, raw_mode, error_code, custom_urcs, at_lock, urc_channel) = (
+            &*res_slot,
+            &*req_slot,
+            &*raw_mode,
+            &*error_code,
+            &*custom_urcs,
+            &*at_lock,
+            &*urc_channel,
+        );
+
+        let ch = state::Runner::new(ch);
+        let ingress = atat::Ingress::new(
+            Digester::new(raw_mode, error_code, custom_urcs, &[]),
+            ingress_buf,
+            res_slot,
+            urc_channel,
+        );
+
+        let io = HostIo {
+            ch: ch.clone(),
+            duplex,
+            req_slot,
+            urc_channel,
+            ingress,
+        };
+        let host = Self {
+            ch,
+            req_slot,
+            res_slot,
+            raw_mode,
+            error_code,
+            custom_urcs,
+            at_lock,
+        };
+        (host, io)
+    }
+
+    /// A client as used by the runner, eg. for the
+    /// [`NetDevice`](super::network::NetDevice).
+    pub(crate) fn client(&self) -> ProxyClient<'a, INGRESS_BUF_SIZE> {
+        ProxyClient::new(
+            self.req_slot.sender(),
+            self.res_slot,
+            self.at_lock,
+            self.ch.diagnostics(),
+        )
+    }
+
+    /// A `Control` of a module that is up and running.
+    pub(crate) fn control(&self) -> Control<'a, INGRESS_BUF_SIZE> {
+        self.ch
+            .set_operation_state(state::OperationState::Initialized);
+        Control::new(
+            self.ch.clone(),
+            self.req_slot.sender(),
+            self.res_slot,
+            self.raw_mode,
+            self.error_code,
+            self.custom_urcs,
+            self.at_lock,
+        )
+    }
+}
+
+impl HostIo<'_> {
+    /// Run `flow` on the [`Host`] against `sim` playing `script`. Returns the
+    /// output of `flow` once it is done and the script is played through.
+    pub(crate) fn play<F: Future>(
+        &mut self,
+        sim: &mut ModemSim<'_>,
+        script: &[Step<'_>],
+        flow: F,
+    ) -> F::Output {
+        let res = embassy_futures::block_on(select(self.run(), join(sim.run(script), flow)));
+        sim.assert_idle();
+        match res {
+            Either::First(never) => never,
+            Either::Second((_, output)) => output,
+        }
+    }
+
+    /// Move the traffic of the [`Host`] against `sim` playing `script`, for
+    /// flows that drive it themselves, eg. the blocking stack.
+    #[cfg(feature = "blocking")]
+    pub(crate) async fn serve(&mut self, sim: &mut ModemSim<'_>, script: &[Step<'_>]) -> ! {
+        join(self.run(), sim.run(script)).await.0
+    }
+
+    async fn run(&mut self) -> ! {
+        let Self {
+            ch,
+            duplex,
+            req_slot,
+            urc_channel,
+            ingress,
+        } = self;
+
+        let mut tx = duplex.host();
+        let tx_fut = async {
+            loop {
+                let msg = req_slot.receive().await;
+                tx.write_all(&msg).await.unwrap();
+            }
+        };
+        let mut urc_handler = UrcHandler::new(ch, *urc_channel, SimConfig::CONTEXT_ID);
+
+        join3(tx_fut, ingress.read_from(duplex.host()), urc_handler.run()).await;
+
+        unreachable!()
+    }
+}
@@ -0,0 +1,208 @@
+use core::fmt::Write as _;
+use core::future::poll_fn;
+use core::task::Poll;
+
+use embassy_time::{with_timeout, Duration};
+
+use crate::{
+    command::{
+        device_data_security::types::SecurityProfileId,
+        mqtt::{
+            responses::{MqttCommandResponse, MqttMessage},
+            types::{MqttParam, MqttQos},
+            GetMqttError, MqttLogin, MqttLogout, MqttPublish, MqttReadMessage, MqttSubscribe,
+            MqttUnsubscribe, SetMqttConfig,
+        },
+    },
+    error::Error,
+};
+
+use super::{control::Control, runner::MAX_CMD_LEN};
+
+/// Maximum length of a published message. As messages are published in hex
+/// mode, this allows binary payloads up to half this length.
+const MAX_MESSAGE_LEN: usize = 1024;
+
+/// Length of `AT+UMQTTC=2,<qos>,<retain>,1,"",""\r`, excluding topic and
+/// message.
+const PUBLISH_CMD_OVERHEAD: usize = 24;
+
+/// Client for the modem's internal MQTT client, obtained through
+/// [`Control::mqtt`].
+pub struct MqttClient<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    timeout: Duration,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> MqttClient<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self {
+            control,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Set how long to wait for the broker to acknowledge connect and
+    /// subscribe requests.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn set_param(&self, param: MqttParam) -> Result<(), Error> {
+        if let MqttParam::Secure(_, Some(security_profile)) = param {
+            self.control.check_security_profile(security_profile)?;
+        }
+
+        let res = self.control.send(&SetMqttConfig { param }).await?;
+        if res.result != 1 {
+            return Err(self.last_error().await);
+        }
+        Ok(())
+    }
+
+    /// Use TLS with the given security profile for the connection to the
+    /// broker, or plain TCP if `None`. The profile has to be set up with
+    /// [`Control::configure_security_profile`] or
+    /// [`Control::register_security_profile`] first.
+    pub async fn set_security(
+        &self,
+        security_profile: Option<SecurityProfileId>,
+    ) -> Result<(), Error> {
+        self.set_param(MqttParam::Secure(
+            security_profile.is_some(),
+            security_profile,
+        ))
+        .await
+    }
+
+    /// Connect to the broker configured with [`MqttClient::set_param`].
+    pub async fn connect(&self) -> Result<(), Error> {
+        self.control.state_ch.clear_mqtt_result();
+
+        let res = self.control.send(&MqttLogin).await?;
+        self.check(res).await?;
+
+        let result = with_timeout(self.timeout, self.control.state_ch.wait_mqtt_result(1)).await?;
+        if result.result != 0 {
+            error!("MQTT connection refused: {}", result.result);
+            return Err(self.last_error().await);
+        }
+
+        info!("✅ MQTT connected");
+        Ok(())
+    }
+
+    pub async fn disconnect(&self) -> Result<(), Error> {
+        let res = self.control.send(&MqttLogout).await?;
+        self.check(res).await
+    }
+
+    /// Whether the client is connected, as last reported by the modem.
+    pub fn is_connected(&self) -> bool {
+        self.control.state_ch.mqtt_connected(None)
+    }
+
+    /// Publish a message. The payload is sent hex encoded, so it can contain
+    /// arbitrary binary data, up to 512 bytes. Returns `Error::Overflow` if
+    /// the encoded command does not fit the AT command buffer.
+    pub async fn publish(
+        &self,
+        topic: &str,
+        payload: &[u8],
+        qos: MqttQos,
+        retain: bool,
+    ) -> Result<(), Error> {
+        if payload.len() * 2 > MAX_MESSAGE_LEN
+            || PUBLISH_CMD_OVERHEAD + topic.len() + payload.len() * 2 > MAX_CMD_LEN
+        {
+            return Err(Error::Overflow);
+        }
+
+        let mut message = heapless::String::<MAX_MESSAGE_LEN>::new();
+        for b in payload {
+            write!(message, "{:02X}", b).map_err(|_| Error::Overflow)?;
+        }
+
+        let res = self
+            .control
+            .send(&MqttPublish {
+                qos,
+                retain,
+                hex_mode: true,
+                topic,
+                message: &message,
+            })
+            .await?;
+        self.check(res).await
+    }
+
+    pub async fn subscribe(&self, topic_filter: &str, max_qos: MqttQos) -> Result<(), Error> {
+        self.control.state_ch.clear_mqtt_result();
+
+        let res = self
+            .control
+            .send(&MqttSubscribe {
+                max_qos,
+                topic_filter,
+            })
+            .await?;
+        self.check(res).await?;
+
+        let result = with_timeout(self.timeout, self.control.state_ch.wait_mqtt_result(4)).await?;
+        if result.result != 1 {
+            return Err(self.last_error().await);
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&self, topic_filter: &str) -> Result<(), Error> {
+        let res = self.control.send(&MqttUnsubscribe { topic_filter }).await?;
+        self.check(res).await
+    }
+
+    /// Number of received messages waiting to be read.
+    pub fn unread_messages(&self) -> u16 {
+        self.control.state_ch.mqtt_unread(None)
+    }
+
+    /// Wait for a message to be received and read it.
+    pub async fn receive(&self) -> Result<MqttMessage, Error> {
+        poll_fn(|cx| {
+            if self.control.state_ch.mqtt_unread(Some(cx)) > 0 {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        })
+        .await;
+
+        self.read_message().await
+    }
+
+    /// Read one of the received messages.
+    pub async fn read_message(&self) -> Result<MqttMessage, Error> {
+        let msg = self.control.send(&MqttReadMessage).await?;
+        self.control.state_ch.mqtt_message_read();
+        Ok(msg)
+    }
+
+    async fn check(&self, res: MqttCommandResponse) -> Result<(), Error> {
+        if res.result != 1 {
+            return Err(self.last_error().await);
+        }
+        Ok(())
+    }
+
+    async fn last_error(&self) -> Error {
+        match self.control.send(&GetMqttError).await {
+            Ok(err) => {
+                error!(
+                    "MQTT action failed. Class: {}, code: {}",
+                    err.error_class, err.error_code
+                );
+                Error::Mqtt(err)
+            }
+            Err(e) => e,
+        }
+    }
+}
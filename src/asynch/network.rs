@@ -3,20 +3,31 @@ use core::{cmp::Ordering, future::poll_fn, marker::PhantomData, task::Poll};
 #[cfg(feature = "use-upsd-context-activation")]
 use core::net::Ipv4Addr;
 
+#[cfg(feature = "use-upsd-context-activation")]
+use crate::command::psn::{
+    types::{
+        AuthenticationType, PacketSwitchedAction, PacketSwitchedNetworkDataParam,
+        PacketSwitchedParam,
+    },
+    GetPacketSwitchedNetworkData, SetPacketSwitchedAction, SetPacketSwitchedConfig,
+};
+
 use crate::{
-    asynch::state::OperationState,
+    asynch::state::{OperationState, OperatorScan, OperatorScanError, SimState},
     command::{
+        device_lock::{responses::PinStatus, types::PinStatusCode, GetPinStatus},
         general::GetCIMI,
         mobile_control::{
-            responses::ModuleFunctionality,
-            types::{Functionality, PowerMode},
-            GetExtendedErrorReport, GetModuleFunctionality, SetModuleFunctionality,
+            responses::{ExtendedErrorReport, ModuleFunctionality},
+            types::{ExtendedErrorCause, Functionality, PowerMode},
+            GetExtendedErrorReport, GetModuleFunctionality, ModuleSwitchOff,
+            SetModuleFunctionality,
         },
         network_service::{
             responses::OperatorSelection,
             types::{NetworkRegistrationUrcConfig, OperatorSelectionMode},
-            GetNetworkRegistrationStatus, GetOperatorSelection, SetNetworkRegistrationStatus,
-            SetOperatorSelection,
+            GetNetworkRegistrationStatus, GetOperatorSelection, GetSignalQuality, ScanOperators,
+            SetNetworkRegistrationStatus, SetOperatorSelection,
         },
         psn::{
             responses::GPRSAttached,
@@ -25,22 +36,28 @@ use crate::{
                 GPRSNetworkRegistrationUrcConfig, PDPContextStatus, ProfileId,
             },
             GetEPSNetworkRegistrationStatus, GetGPRSAttached, GetGPRSNetworkRegistrationStatus,
-            GetPDPContextState, SetEPSNetworkRegistrationStatus, SetGPRSNetworkRegistrationStatus,
-            SetPDPContextState,
+            GetPDPAddress, GetPDPContextState, SetEPSNetworkRegistrationStatus, SetGPRSAttached,
+            SetGPRSNetworkRegistrationStatus, SetPDPContextState,
         },
+        AT,
     },
-    config::{Apn, CellularConfig},
+    config::{Apn, CellularConfig, SignalMonitor},
     error::Error,
     modules::ModuleParams,
     registration::ProfileState,
 };
 
-use super::state;
+#[cfg(not(feature = "use-upsd-context-activation"))]
+use crate::command::psn::{
+    types::AuthenticationType, SetAuthParameters, SetPDPContextAuthentication,
+};
+
+use super::{diagnostics::TransitionReason, state};
 
 use atat::asynch::AtatClient;
-use embassy_futures::select::{select, Either};
+use embassy_futures::select::{select, select4, Either, Either4};
 
-use embassy_time::{Duration, Timer};
+use embassy_time::{Duration, Instant, Timer};
 
 /// Upper bound on the graceful network-teardown AT commands (COPS=2 deregister
 /// and CFUN radio-off) issued on the `Connected -> Initialized` descent. A
@@ -49,6 +66,23 @@ use embassy_time::{Duration, Timer};
 /// through to the GPIO power-cycle, which is a stronger reset anyway.
 const GRACEFUL_TEARDOWN_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Time the module takes to abort an operator scan.
+const OPERATOR_SCAN_ABORT_TIME: Duration = Duration::from_millis(500);
+
+/// Time to wait for the +UUPSDA result after the OK to AT+UPSDA, before
+/// checking the profile status instead. Not all modules send it.
+#[cfg(feature = "use-upsd-context-activation")]
+const PSD_RESULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Authentication types to try in order, until the module accepts one.
+#[cfg(not(feature = "use-upsd-context-activation"))]
+const AUTH_TYPES: [AuthenticationType; 4] = [
+    AuthenticationType::Auto,
+    AuthenticationType::None,
+    AuthenticationType::PAP,
+    AuthenticationType::CHAP,
+];
+
 pub struct NetDevice<'a, 'b, C, A> {
     ch: &'b state::Runner<'a>,
     at_client: A,
@@ -74,8 +108,9 @@ where
     ///
     /// Returns an error if any of the internal network operations fail.
     ///
-    async fn register_network(&mut self, mcc_mnc: Option<()>) -> Result<(), Error> {
+    async fn register_network(&mut self) -> Result<(), Error> {
         info!("🔧 NetDevice::register_network() - Starting network registration process");
+        let mcc_mnc = self.ch.operator_selection();
         debug!(
             "NetDevice::register_network() - MCC/MNC parameter: {:?}",
             mcc_mnc
@@ -108,6 +143,7 @@ where
                     .send(&SetOperatorSelection {
                         mode: OperatorSelectionMode::Automatic,
                         format: None,
+                        oper: None,
                     })
                     .await {
                     Ok(_) => info!("NetDevice::register_network() - Successfully set automatic operator selection"),
@@ -142,9 +178,7 @@ where
                 password,
             } = apn_info
             {
-                use crate::command::psn::{
-                    types::AuthenticationType, SetAuthParameters, SetPDPContextDefinition,
-                };
+                use crate::command::psn::SetPDPContextDefinition;
 
                 // Ensure radio is off (CGDCONT only takes effect on next attach)
                 let _ = self
@@ -172,13 +206,11 @@ where
                     .await?;
 
                 if let Some(username) = username {
-                    self.at_client
-                        .send(&SetAuthParameters {
-                            cid: C::CONTEXT_ID,
-                            auth_type: AuthenticationType::Auto,
-                            username: username.as_str(),
-                            password: password.unwrap_or_default().as_str(),
-                        })
+                    let password = password.unwrap_or_default();
+                    // Skip trying them all, once it is known what works
+                    let cached = self.ch.auth_type().map(|auth_type| [auth_type]);
+                    let candidates = cached.as_ref().map_or(&AUTH_TYPES[..], |c| &c[..]);
+                    self.set_auth_parameters(candidates, &username, &password)
                         .await?;
                 }
             }
@@ -205,30 +237,37 @@ where
             }
         }
 
-        if mcc_mnc.is_some() {
-            error!("NetDevice::register_network() - Manual operator selection with MCC/MNC is not implemented!");
-            // TODO: If MCC & MNC is set, register with manual operator selection.
-            // This is currently not supported!
-
-            // let crate::command::network_service::responses::OperatorSelection { mode, .. } = self
-            //     .at_client
-            //     .send(&crate::command::network_service::GetOperatorSelection)
-            //     .await?;
-
-            // // Only run AT+COPS=0 if currently de-registered, to avoid PLMN reselection
-            // if !matches!(
-            //     mode,
-            //     crate::command::network_service::types::OperatorSelectionMode::Automatic
-            //         | crate::command::network_service::types::OperatorSelectionMode::Manual
-            // ) {
-            //     self.at_client
-            //         .send(&crate::command::network_service::SetOperatorSelection {
-            //             mode: crate::command::network_service::types::OperatorSelectionMode::Automatic,
-            //             format: Some(C::OPERATOR_FORMAT as u8),
-            //         })
-            //         .await?;
-            // }
-            unimplemented!()
+        if let Some(plmn) = mcc_mnc {
+            info!(
+                "NetDevice::register_network() - Selecting operator {} manually",
+                plmn.as_str()
+            );
+            if let Err(e) = self
+                .at_client
+                .send(&SetOperatorSelection {
+                    mode: OperatorSelectionMode::Manual,
+                    format: Some(2),
+                    oper: Some(plmn.as_str()),
+                })
+                .await
+            {
+                let e = self.with_extended_error(e.into()).await;
+                // Don't stay camped nowhere on a PLMN that is not available
+                // (anymore), fall back to automatic selection instead.
+                error!(
+                    "NetDevice::register_network() - Manual selection of {} failed, reverting to automatic: {:?}",
+                    plmn.as_str(),
+                    e
+                );
+                self.ch.set_operator_selection(None);
+                self.at_client
+                    .send(&SetOperatorSelection {
+                        mode: OperatorSelectionMode::Automatic,
+                        format: None,
+                        oper: None,
+                    })
+                    .await?;
+            }
         }
 
         info!("✅ NetDevice::register_network() - Network registration completed successfully");
@@ -238,6 +277,27 @@ where
     async fn prepare_connect(&mut self) -> Result<(), Error> {
         info!("🔧 NetDevice::prepare_connect() - Starting connection preparation");
 
+        // A locked SIM never registers, so fail early with a useful error
+        match self.at_client.send(&GetPinStatus).await {
+            Ok(PinStatus {
+                code: PinStatusCode::SimPin,
+            }) => {
+                error!("NetDevice::prepare_connect() - SIM is PIN locked");
+                return Err(Error::SimPinRequired);
+            }
+            Ok(PinStatus {
+                code: PinStatusCode::SimPuk,
+            }) => {
+                error!("NetDevice::prepare_connect() - SIM is PUK locked");
+                return Err(Error::SimPukRequired);
+            }
+            Ok(_) => {}
+            Err(e) => warn!(
+                "NetDevice::prepare_connect() - Failed to read SIM PIN status: {:?}",
+                e
+            ),
+        }
+
         // CREG URC
         debug!("NetDevice::prepare_connect() - Setting up CREG URC (Network Registration)");
         match self
@@ -275,15 +335,34 @@ where
             }
         }
 
-        // CEREG URC
+        // CEREG URC, including the reject cause of denied registrations and
+        // the granted PSM timers where the module supports them
         debug!("NetDevice::prepare_connect() - Setting up CEREG URC (EPS Registration)");
-        match self
+        let mut res = self
             .at_client
             .send(&SetEPSNetworkRegistrationStatus {
-                n: EPSNetworkRegistrationUrcConfig::UrcEnabled,
+                n: EPSNetworkRegistrationUrcConfig::UrcVerbosePsmWithCause,
             })
-            .await
-        {
+            .await;
+        if res.is_err() {
+            warn!("NetDevice::prepare_connect() - CEREG PSM timers not supported");
+            res = self
+                .at_client
+                .send(&SetEPSNetworkRegistrationStatus {
+                    n: EPSNetworkRegistrationUrcConfig::UrcVerboseWithCause,
+                })
+                .await;
+        }
+        if res.is_err() {
+            warn!("NetDevice::prepare_connect() - CEREG reject causes not supported");
+            res = self
+                .at_client
+                .send(&SetEPSNetworkRegistrationStatus {
+                    n: EPSNetworkRegistrationUrcConfig::UrcEnabled,
+                })
+                .await;
+        }
+        match res {
             Ok(_) => info!("NetDevice::prepare_connect() - Successfully enabled CEREG URC"),
             Err(e) => {
                 error!(
@@ -308,6 +387,8 @@ where
                         "NetDevice::prepare_connect() - Module is ready! CIMI response: {:?}",
                         cimi_response.imsi
                     );
+                    self.ch
+                        .update_identity(|id| id.imsi = Some(cimi_response.imsi));
                     ready = true;
                     break;
                 }
@@ -404,18 +485,46 @@ where
                     return Ok(());
                 }
 
-                Timer::after_secs(3).await;
+                // Poll again, or as soon as a URC tells of a new registration
+                select(
+                    Timer::after_secs(3),
+                    state_runner.wait_registration_change(),
+                )
+                .await;
             }
         };
 
-        embassy_time::with_timeout(timeout, wait_fut)
-            .await
-            .map_err(|_| {
+        // An operator scan holds the AT interface for minutes, which would
+        // otherwise eat the registration timeout. Pause the deadline while one
+        // is running.
+        let timeout_fut = async {
+            let mut deadline = Instant::now() + timeout;
+            loop {
+                match select(
+                    Timer::at(deadline),
+                    state_runner.wait_for_operator_scan(true),
+                )
+                .await
+                {
+                    Either::First(_) => return,
+                    Either::Second(_) => {
+                        let start = Instant::now();
+                        state_runner.wait_for_operator_scan(false).await;
+                        deadline += start.elapsed();
+                    }
+                }
+            }
+        };
+
+        match select(wait_fut, timeout_fut).await {
+            Either::First(res) => res,
+            Either::Second(_) => {
                 error!(
                     "❌ NetDevice::wait_network_registered() - Failed to register within timeout"
                 );
-                Error::Generic(crate::error::GenericError::Timeout)
-            })?
+                Err(Error::Generic(crate::error::GenericError::Timeout))
+            }
+        }
     }
 
     async fn update_registration(&mut self) -> Result<(), Error> {
@@ -470,19 +579,7 @@ where
 
                 // If EPS is not registered, query CEER for the rejection cause
                 if eps_not_registered {
-                    match self.at_client.send(&GetExtendedErrorReport).await {
-                        Ok(report) => {
-                            warn!(
-                                "CEER: type={}, cause={}, desc={}",
-                                report.r#type.as_str(),
-                                report.cause,
-                                report.description.as_str()
-                            );
-                        }
-                        Err(e) => {
-                            debug!("Failed to get CEER: {:?}", e);
-                        }
-                    }
+                    self.extended_error().await;
                 }
             }
             Err(e) => {
@@ -505,15 +602,19 @@ where
     }
 
     async fn radio_off(&mut self) -> Result<(), Error> {
-        #[cfg(not(feature = "use-upsd-context-activation"))]
-        self.ch.set_profile_state(ProfileState::ShouldBeDown);
-
         let module_cfun = self
             .ch
             .module()
             .ok_or(Error::Uninitialized)?
             .radio_off_cfun();
 
+        self.set_radio_off(module_cfun).await
+    }
+
+    async fn set_radio_off(&mut self, module_cfun: Functionality) -> Result<(), Error> {
+        #[cfg(not(feature = "use-upsd-context-activation"))]
+        self.ch.set_profile_state(ProfileState::ShouldBeDown);
+
         let cfun_power_mode = PowerMode::try_from(module_cfun as u8).ok();
 
         let mut last_err = None;
@@ -569,26 +670,60 @@ where
             // always wins, so the next loop iteration re-runs against it.
             match select(self.run_to_desired(), ch.wait_for_desired_state_change()).await {
                 Either::First(res) => res?,
-                Either::Second(_) => continue,
+                Either::Second(_) => {
+                    // The application asks again, so try again after a
+                    // permanent rejection
+                    self.ch.clear_attach_rejection();
+                    continue;
+                }
             }
 
             // operation == desired now. Wait for a reason to act again: either
-            // the desired state moves, or the network registration status
-            // changes underneath us.
-            match select(
+            // the desired state moves, the network registration status
+            // changes underneath us, the signal quality is due, or an
+            // operator scan is requested.
+            let signal_poll = async {
+                match C::SIGNAL_MONITOR {
+                    Some(monitor) if ch.operation_state(None) >= OperationState::Connected => {
+                        Timer::after(monitor.interval).await;
+                        monitor
+                    }
+                    _ => core::future::pending().await,
+                }
+            };
+
+            match select4(
                 ch.wait_for_desired_state_change(),
                 ch.wait_registration_change(),
+                signal_poll,
+                select(
+                    ch.wait_operator_scan_request(),
+                    ch.wait_local_addr_refresh(),
+                ),
             )
             .await
             {
-                Either::First(_) => {
+                Either4::First(_) => {
                     info!("desired state change, run to desired state");
                 }
-                Either::Second(false) => {
-                    warn!("Lost network registration. Setting operating state back to initialized");
-                    self.ch.set_operation_state(OperationState::Initialized);
+                Either4::Third(monitor) => self.poll_signal_quality(&monitor).await,
+                Either4::Fourth(Either::First(())) => self.scan_operators().await,
+                Either4::Fourth(Either::Second(())) => {
+                    if self.ch.operation_state(None) == OperationState::DataEstablished {
+                        self.update_local_addr(C::CONTEXT_ID).await;
+                    }
                 }
-                Either::Second(true) => {
+                Either4::Second(false) => {
+                    // Switching to airplane mode deregisters on purpose
+                    if self.ch.operation_state(None) > OperationState::AirplaneMode {
+                        warn!("Lost network registration. Setting operating state back to initialized");
+                        self.ch.set_operation_state_for(
+                            OperationState::Initialized,
+                            TransitionReason::RegistrationLost,
+                        );
+                    }
+                }
+                Either4::Second(true) => {
                     info!("Network registration changed");
                     // This flag will be set if we had been knocked out
                     // of our PDP context by a network outage and need
@@ -599,13 +734,90 @@ where
                     if self.ch.get_profile_state() == ProfileState::RequiresReactivation {
                         self.activate_context(C::CONTEXT_ID, C::PROFILE_ID).await?;
                         self.ch.set_profile_state(ProfileState::ShouldBeUp);
+                        self.update_local_addr(C::CONTEXT_ID).await;
                     }
                 }
             }
         }
     }
 
+    /// Poll the signal quality, and whether it is usable by the thresholds
+    /// of `monitor`.
+    async fn poll_signal_quality(&mut self, monitor: &SignalMonitor) {
+        // The module does not answer in PSM
+        if self.ch.is_sleeping() {
+            return;
+        }
+
+        match self.at_client.send(&GetSignalQuality).await {
+            Ok(quality) => {
+                let usable = monitor.is_usable(&quality, self.ch.is_signal_usable(None));
+                self.ch.set_signal_quality(quality, usable);
+            }
+            Err(e) => warn!("Failed to poll the signal quality: {:?}", e),
+        }
+    }
+
+    /// Run the operator scan requested with `Control::start_operator_scan`,
+    /// until it completes or is aborted.
+    async fn scan_operators(&mut self) {
+        struct ScanGuard<'a, 'b>(&'b state::Runner<'a>);
+
+        impl Drop for ScanGuard<'_, '_> {
+            fn drop(&mut self) {
+                self.0.set_scanning_operators(false);
+            }
+        }
+
+        let ch = self.ch;
+        if ch.operation_state(None) == OperationState::PowerDown {
+            ch.set_operator_scan(OperatorScan::Failed(OperatorScanError::PoweredDown));
+            return;
+        }
+
+        ch.set_scanning_operators(true);
+        let _guard = ScanGuard(ch);
+
+        info!("📡 Scanning for operators in the background");
+        let scan = match select(
+            self.at_client.send(&ScanOperators),
+            ch.wait_operator_scan_abort(),
+        )
+        .await
+        {
+            Either::First(Ok(res)) => {
+                info!("📡 Operator scan found {} operators", res.operators.len());
+                OperatorScan::Ready {
+                    operators: res.operators,
+                    at_secs: Instant::now().as_secs(),
+                }
+            }
+            Either::First(Err(atat::Error::Timeout)) => {
+                OperatorScan::Failed(OperatorScanError::Timeout)
+            }
+            Either::First(Err(e)) => {
+                warn!("Operator scan failed: {:?}", e);
+                OperatorScan::Failed(OperatorScanError::Rejected)
+            }
+            Either::Second(()) => {
+                // +COPS=? is aborted by any character sent to the module.
+                // The `AT` takes the final result code of the aborted scan,
+                // the second one resynchronizes the AT interface.
+                info!("📡 Aborting the operator scan");
+                self.at_client.send(&AT).await.ok();
+                Timer::after(OPERATOR_SCAN_ABORT_TIME).await;
+                self.at_client.send(&AT).await.ok();
+                OperatorScan::Failed(OperatorScanError::Aborted)
+            }
+        };
+        ch.set_operator_scan(scan);
+    }
+
     async fn run_to_desired(&mut self) -> Result<(), Error> {
+        // Set once a hard reset skipped the teardown, so the rest of the
+        // descent skips it as well
+        let mut hard_reset = false;
+
         loop {
             let current_state = self.ch.operation_state(None);
             let desired_state = self.ch.desired_state(None);
@@ -623,28 +835,57 @@ where
                         .wait_for_operation_state(OperationState::Initialized)
                         .await
                 }
-                (OperationState::Initialized, Ordering::Greater) => {
+                (OperationState::Initialized, Ordering::Greater)
+                    if desired_state == OperationState::AirplaneMode =>
+                {
+                    info!("NetDevice::run_to_desired() - Entering airplane mode");
+                    self.set_radio_off(Functionality::AirplaneMode).await?;
+                    self.ch.set_operation_state(OperationState::AirplaneMode);
+                }
+                (OperationState::Initialized | OperationState::AirplaneMode, Ordering::Greater) => {
                     info!(
                         "NetDevice::run_to_desired() - Transitioning from Initialized to Connected"
                     );
+                    if self.ch.sim_state(None) == SimState::Absent {
+                        warn!("NetDevice::run_to_desired() - No SIM inserted, waiting for one");
+                        self.ch.wait_sim_inserted().await;
+                    }
+                    self.wait_attach_backoff().await;
                     debug!("NetDevice::run_to_desired() - Starting network registration process");
 
-                    self.register_network(None).await?;
-                    info!("NetDevice::run_to_desired() - Network registration completed, waiting for registration confirmation");
-
-                    self.wait_network_registered(Duration::from_secs(180))
-                        .await?;
+                    let res = match self.register_network().await {
+                        Ok(()) => {
+                            info!("NetDevice::run_to_desired() - Network registration completed, waiting for registration confirmation");
+                            self.wait_network_registered(Duration::from_secs(180)).await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    if let Err(err) = res {
+                        let err = self.attach_failed(err);
+                        if matches!(err, Error::AttachRejected(_)) {
+                            // Keep the module from retrying by itself
+                            let _ = self.radio_off().await;
+                            self.ch.record_error(&err);
+                            continue;
+                        }
+                        return Err(err);
+                    }
 
                     info!("NetDevice::run_to_desired() - Network registration confirmed, setting state to Connected");
+                    if desired_state == OperationState::Connected {
+                        self.ch.attach_succeeded();
+                    }
                     self.ch.set_operation_state(OperationState::Connected);
                 }
                 (OperationState::Connected, Ordering::Greater) => {
                     info!("NetDevice::run_to_desired() - Transitioning from Connected to DataEstablished");
                     info!("NetDevice::run_to_desired() - Operation state is connected, establishing data connection");
+                    self.wait_attach_backoff().await;
 
                     match self.connect(C::PROFILE_ID, C::CONTEXT_ID).await {
                         Ok(_) => {
                             info!("NetDevice::run_to_desired() - Data connection established successfully");
+                            self.ch.attach_succeeded();
                             #[cfg(not(feature = "use-upsd-context-activation"))]
                             self.ch
                                 .set_profile_state(crate::registration::ProfileState::ShouldBeUp);
@@ -654,14 +895,42 @@ where
                         }
                         Err(err) => {
                             error!("NetDevice::run_to_desired() - Failed to establish data connection: {:?}", err);
+                            let err = self.attach_failed(err);
                             // Switch radio off after failure
                             warn!("NetDevice::run_to_desired() - Switching radio off after connection failure");
                             let _ = self.radio_off().await;
+                            if matches!(err, Error::AttachRejected(_)) {
+                                self.ch.record_error(&err);
+                                self.ch.set_operation_state(OperationState::Initialized);
+                                continue;
+                            }
+                            // The APN has to be defined before registering,
+                            // so register again with the next candidate
+                            if self.ch.next_apn() {
+                                self.ch.set_operation_state_for(
+                                    OperationState::Initialized,
+                                    TransitionReason::ApnFallback,
+                                );
+                            }
                             return Err(err);
                         }
                     }
                 }
 
+                (OperationState::Connected, Ordering::Less)
+                    if desired_state == OperationState::AirplaneMode =>
+                {
+                    // CFUN=4 detaches from the network by itself, and keeps
+                    // the SIM accessible, unlike the full teardown below.
+                    info!("NetDevice::run_to_desired() - Entering airplane mode");
+                    embassy_time::with_timeout(
+                        GRACEFUL_TEARDOWN_TIMEOUT,
+                        self.set_radio_off(Functionality::AirplaneMode),
+                    )
+                    .await
+                    .map_err(|_| Error::Generic(crate::error::GenericError::Timeout))??;
+                    self.ch.set_operation_state(OperationState::AirplaneMode);
+                }
                 (OperationState::Connected, Ordering::Less) => {
                     // Deregister from network to clear cached NAS/EPS
                     // security context. Without this, the modem retains
@@ -679,17 +948,37 @@ where
                     // deregister — so the teardown is pure wasted latency.
                     if self.ch.take_hard_reset() {
                         warn!("Hard reset requested — skipping AT teardown, power-cycling");
+                        hard_reset = true;
                     } else {
                         // Otherwise still bound the teardown so a modem that
                         // wedges mid-descent can't block for the full 180s.
-                        let _ = embassy_time::with_timeout(
+                        if desired_state == OperationState::PowerDown {
+                            // Detach explicitly, so the network releases the
+                            // bearer right away
+                            let detached = embassy_time::with_timeout(
+                                GRACEFUL_TEARDOWN_TIMEOUT,
+                                self.at_client.send(&SetGPRSAttached {
+                                    state: GPRSAttachedState::Detached,
+                                }),
+                            )
+                            .await;
+                            self.ch.update_shutdown_report(|r| {
+                                r.detached = matches!(detached, Ok(Ok(_)));
+                            });
+                        }
+
+                        let deregistered = embassy_time::with_timeout(
                             GRACEFUL_TEARDOWN_TIMEOUT,
                             self.at_client.send(&SetOperatorSelection {
                                 mode: OperatorSelectionMode::Deregister,
                                 format: None,
+                                oper: None,
                             }),
                         )
                         .await;
+                        self.ch.update_shutdown_report(|r| {
+                            r.deregistered = matches!(deregistered, Ok(Ok(_)));
+                        });
                         let _ =
                             embassy_time::with_timeout(GRACEFUL_TEARDOWN_TIMEOUT, self.radio_off())
                                 .await;
@@ -697,17 +986,129 @@ where
                     self.ch.set_operation_state(OperationState::Initialized);
                 }
                 (OperationState::DataEstablished, Ordering::Less) => {
+                    // The data context doesn't survive leaving this state, so
+                    // take the link down to have open sockets closed.
+                    self.ch.set_link_state(state::LinkState::Down);
+                    if desired_state == OperationState::PowerDown && !self.ch.hard_reset_requested()
+                    {
+                        let deactivated = embassy_time::with_timeout(
+                            GRACEFUL_TEARDOWN_TIMEOUT,
+                            self.deactivate_context(),
+                        )
+                        .await;
+                        self.ch.update_shutdown_report(|r| {
+                            r.context_deactivated = matches!(deactivated, Ok(Ok(())));
+                        });
+                    }
                     self.ch.set_operation_state(OperationState::Connected);
                 }
+                (OperationState::AirplaneMode, Ordering::Less) => {
+                    // Only switch the radio again if the module uses a
+                    // different functionality level than airplane mode for
+                    // radio off.
+                    let module_cfun = self
+                        .ch
+                        .module()
+                        .ok_or(Error::Uninitialized)?
+                        .radio_off_cfun();
+                    if module_cfun != Functionality::AirplaneMode {
+                        self.set_radio_off(module_cfun).await?;
+                    }
+                    self.ch.set_operation_state(OperationState::Initialized);
+                }
 
                 (OperationState::DataEstablished, Ordering::Greater) => unreachable!(),
-                (OperationState::Initialized, Ordering::Less) => return Err(Error::PoweredDown),
+                (OperationState::Initialized, Ordering::Less) => {
+                    // Let the module save its settings and switch off by
+                    // itself, the power pin is only toggled if it did not
+                    if !hard_reset && !self.ch.take_hard_reset() {
+                        let switched_off = embassy_time::with_timeout(
+                            GRACEFUL_TEARDOWN_TIMEOUT,
+                            self.at_client.send(&ModuleSwitchOff),
+                        )
+                        .await;
+                        self.ch.update_shutdown_report(|r| {
+                            r.switched_off = matches!(switched_off, Ok(Ok(_)));
+                        });
+                    }
+                    return Err(Error::PoweredDown);
+                }
                 (OperationState::PowerDown, _) => return Err(Error::PoweredDown),
             }
         }
         Ok(())
     }
 
+    /// Wait until the backoff of the previous failed attach is over. After a
+    /// rejection that retrying does not fix, wait for the desired state to
+    /// change instead.
+    async fn wait_attach_backoff(&self) {
+        if let Some(cause) = self.ch.attach_rejection() {
+            warn!(
+                "Network attach rejected with {:?}, waiting for a new desired state",
+                cause
+            );
+            core::future::pending::<()>().await;
+        }
+        if let Some(next) = self.ch.next_attach_attempt() {
+            debug!(
+                "Backing off network attach for {} s",
+                next.saturating_duration_since(Instant::now()).as_secs()
+            );
+            Timer::at(next).await;
+        }
+    }
+
+    /// Account the failed attach or context activation `error` with the
+    /// backoff, and return the error to give up with. That is
+    /// [`Error::AttachRejected`] for causes that retrying does not fix.
+    fn attach_failed(&self, error: Error) -> Error {
+        let cause = match &error {
+            Error::Rejected(ExtendedErrorCause::Mobility(cause)) => Some(*cause),
+            _ => self.ch.registration_status().reject_cause,
+        };
+        match self
+            .ch
+            .attach_failed(&C::ATTACH_BACKOFF, Instant::now(), cause)
+        {
+            Ok(next) => {
+                warn!(
+                    "Network attach failed ({} in total), next attempt in {} s",
+                    self.ch.attach_failures(),
+                    next.saturating_duration_since(Instant::now()).as_secs()
+                );
+                error
+            }
+            Err(cause) => {
+                error!("Network attach rejected permanently: {:?}", cause);
+                Error::AttachRejected(cause)
+            }
+        }
+    }
+
+    /// Deactivate the data context, so the network releases it before the
+    /// detach.
+    async fn deactivate_context(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "use-upsd-context-activation")]
+        {
+            self.at_client
+                .send(&SetPacketSwitchedAction {
+                    profile_id: C::PROFILE_ID,
+                    action: PacketSwitchedAction::Deactivate,
+                })
+                .await?;
+            self.ch.set_psd_profile(None);
+        }
+        #[cfg(not(feature = "use-upsd-context-activation"))]
+        self.at_client
+            .send(&SetPDPContextState {
+                status: PDPContextStatus::Deactivated,
+                cid: Some(C::CONTEXT_ID),
+            })
+            .await?;
+        Ok(())
+    }
+
     #[allow(unused_variables)]
     async fn connect(&mut self, profile_id: ProfileId, context_id: ContextId) -> Result<(), Error> {
         info!("🔧 NetDevice::connect() - Starting data connection setup");
@@ -757,7 +1158,7 @@ where
 
         if !attached {
             error!("NetDevice::connect() - Failed to attach to network after 10 attempts!");
-            return Err(Error::AttachTimeout);
+            return Err(self.with_extended_error(Error::AttachTimeout).await);
         }
 
         info!("NetDevice::connect() - Network attached, now activating context");
@@ -768,8 +1169,12 @@ where
             let apn_info = self.ch.get_apn_config();
             info!("NetDevice::connect() - Using UPSD context activation");
             match self.activate_context_upsd(profile_id, apn_info).await {
-                Ok(_) => info!("NetDevice::connect() - Successfully activated context via UPSD"),
+                Ok(_) => {
+                    info!("NetDevice::connect() - Successfully activated context via UPSD");
+                    self.ch.set_psd_profile(Some((context_id, profile_id)));
+                }
                 Err(e) => {
+                    let e = self.with_extended_error(e).await;
                     error!(
                         "NetDevice::connect() - Failed to activate context via UPSD: {:?}",
                         e
@@ -781,9 +1186,13 @@ where
         #[cfg(not(feature = "use-upsd-context-activation"))]
         {
             info!("NetDevice::connect() - Using 3GPP context activation");
-            match self.activate_context(context_id, profile_id).await {
+            match self
+                .activate_context_with_auth(context_id, profile_id)
+                .await
+            {
                 Ok(_) => info!("NetDevice::connect() - Successfully activated context via 3GPP"),
                 Err(e) => {
+                    let e = self.with_extended_error(e).await;
                     error!(
                         "NetDevice::connect() - Failed to activate context via 3GPP: {:?}",
                         e
@@ -793,10 +1202,56 @@ where
             }
         }
 
+        self.update_local_addr(context_id).await;
+
         info!("✅ NetDevice::connect() - Data connection setup completed successfully");
         Ok(())
     }
 
+    /// Read the address of the context `cid` with +CGPADDR, which may have
+    /// changed after a reactivation, and keep it for
+    /// [`Control::local_ip`](crate::asynch::control::Control::local_ip).
+    async fn update_local_addr(&mut self, cid: ContextId) {
+        // The module does not answer in PSM
+        if self.ch.is_sleeping() {
+            return;
+        }
+
+        match self.at_client.send(&GetPDPAddress { cid }).await {
+            Ok(res) => self.ch.set_local_addr(res.address()),
+            Err(e) => warn!("Failed to read the context address: {:?}", e),
+        }
+    }
+
+    /// Read the +CEER report of the last failure, and keep it for
+    /// [`Control::last_extended_error`](crate::asynch::control::Control::last_extended_error).
+    async fn extended_error(&mut self) -> Option<ExtendedErrorReport> {
+        match self.at_client.send(&GetExtendedErrorReport).await {
+            Ok(report) => {
+                warn!(
+                    "CEER: type={}, cause={}, desc={}",
+                    report.r#type.as_str(),
+                    report.cause,
+                    report.description.as_str()
+                );
+                self.ch.set_extended_error(report.clone());
+                Some(report)
+            }
+            Err(e) => {
+                debug!("Failed to get CEER: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Replace `error` with the cause the module reports for it, if any.
+    async fn with_extended_error(&mut self, error: Error) -> Error {
+        match self.extended_error().await.and_then(|r| r.cause()) {
+            Some(cause) => Error::Rejected(cause),
+            None => error,
+        }
+    }
+
     // Make sure we are attached to the cellular network.
     async fn is_network_attached(&mut self) -> Result<bool, Error> {
         debug!("NetDevice::is_network_attached() - Checking GPRS attachment status");
@@ -825,35 +1280,51 @@ where
     async fn activate_context_upsd(
         &mut self,
         profile_id: ProfileId,
-        apn_info: crate::config::Apn<'_>,
+        apn_info: Apn,
     ) -> Result<(), Error> {
+        if self.is_psd_profile_active(profile_id).await {
+            info!("PSD profile {} already active", profile_id.0);
+            return Ok(());
+        }
+
         // SARA-U2 pattern: everything is done through AT+UPSD
-        // Set up the APN
-        if let crate::config::Apn::Given {
+        let mut authentication = AuthenticationType::None;
+        if let Apn::Given {
             name,
             username,
             password,
         } = apn_info
         {
+            // Set up the APN
             self.at_client
                 .send(&SetPacketSwitchedConfig {
                     profile_id,
-                    param: psn::types::PacketSwitchedParam::APN(
-                        String::<99>::try_from(name).unwrap(),
+                    param: PacketSwitchedParam::APN(
+                        heapless::String::try_from(name.as_str()).map_err(|_| Error::Overflow)?,
                     ),
                 })
                 .await?;
 
             // Set up the user name
-            if let Some(user_name) = username {
+            if let Some(username) = username {
                 self.at_client
                     .send(&SetPacketSwitchedConfig {
                         profile_id,
-                        param: psn::types::PacketSwitchedParam::Username(
-                            String::<64>::try_from(user_name).unwrap(),
+                        param: PacketSwitchedParam::Username(
+                            heapless::String::try_from(username.as_str())
+                                .map_err(|_| Error::Overflow)?,
                         ),
                     })
                     .await?;
+
+                // Automatic authentication protocol selection where
+                // supported, otherwise PAP, which networks requiring
+                // credentials commonly accept
+                authentication = if cfg!(feature = "authentication-mode-automatic") {
+                    AuthenticationType::Auto
+                } else {
+                    AuthenticationType::PAP
+                };
             }
 
             // Set up the password
@@ -861,41 +1332,194 @@ where
                 self.at_client
                     .send(&SetPacketSwitchedConfig {
                         profile_id,
-                        param: psn::types::PacketSwitchedParam::Password(
-                            String::<64>::try_from(password).unwrap(),
+                        param: PacketSwitchedParam::Password(
+                            heapless::String::try_from(password.as_str())
+                                .map_err(|_| Error::Overflow)?,
                         ),
                     })
                     .await?;
             }
         }
+
         // Set up the dynamic IP address assignment.
         self.at_client
             .send(&SetPacketSwitchedConfig {
                 profile_id,
-                param: PacketSwitchedParam::IPAddress(Ipv4Addr::unspecified().into()),
+                param: PacketSwitchedParam::IPAddress(Ipv4Addr::UNSPECIFIED.into()),
             })
             .await?;
 
-        // Automatic authentication protocol selection
         self.at_client
             .send(&SetPacketSwitchedConfig {
                 profile_id,
-                param: psn::types::PacketSwitchedParam::Authentication(AuthenticationType::Auto),
+                param: PacketSwitchedParam::Authentication(authentication),
             })
             .await?;
 
+        self.ch.clear_psd_result();
         self.at_client
             .send(&SetPacketSwitchedAction {
                 profile_id,
-                action: psn::types::PacketSwitchedAction::Activate,
+                action: PacketSwitchedAction::Activate,
             })
             .await?;
 
-        Ok(())
+        // Some modules only report the result with +UUPSDA, after the OK
+        match embassy_time::with_timeout(PSD_RESULT_TIMEOUT, self.ch.wait_psd_result()).await {
+            Ok(0) => Ok(()),
+            Ok(result) => {
+                error!("PSD profile activation failed, result {}", result);
+                Err(Error::_Unknown)
+            }
+            Err(_) if self.is_psd_profile_active(profile_id).await => Ok(()),
+            Err(_) => Err(Error::ContextActivationTimeout),
+        }
+    }
+
+    #[cfg(feature = "use-upsd-context-activation")]
+    async fn is_psd_profile_active(&mut self, profile_id: ProfileId) -> bool {
+        matches!(
+            self.at_client
+                .send(&GetPacketSwitchedNetworkData {
+                    profile_id,
+                    param: PacketSwitchedNetworkDataParam::PsdProfileStatus,
+                })
+                .await,
+            Ok(data) if data.param_tag == 1
+        )
     }
 
     /// Activate context using 3GPP commands
     #[cfg(not(feature = "use-upsd-context-activation"))]
+    /// Set the first authentication type of `candidates` the module accepts.
+    /// Not all firmwares support automatic selection.
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    async fn set_auth_parameters(
+        &mut self,
+        candidates: &[AuthenticationType],
+        username: &str,
+        password: &str,
+    ) -> Result<AuthenticationType, Error> {
+        let mut last_err = Error::_Unknown;
+        for &auth_type in candidates {
+            match self
+                .send_auth_parameters(auth_type, username, password)
+                .await
+            {
+                Ok(_) => return Ok(auth_type),
+                Err(e) => {
+                    warn!("Authentication type {:?} rejected: {:?}", auth_type, e);
+                    last_err = e.into();
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Send the authentication parameters with the command of the module,
+    /// falling back to the other one if the firmware does not know it.
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    async fn send_auth_parameters(
+        &mut self,
+        auth_type: AuthenticationType,
+        username: &str,
+        password: &str,
+    ) -> Result<(), atat::Error> {
+        let cgauth = self.ch.module().is_some_and(|m| m.uses_cgauth());
+        match self
+            .send_auth_command(cgauth, auth_type, username, password)
+            .await
+        {
+            Err(atat::Error::CmeError(atat::CmeError::OperationNotSupported)) => {
+                warn!(
+                    "{} not supported, falling back",
+                    if cgauth { "+CGAUTH" } else { "+UAUTHREQ" }
+                );
+                self.send_auth_command(!cgauth, auth_type, username, password)
+                    .await
+            }
+            res => res,
+        }
+    }
+
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    async fn send_auth_command(
+        &mut self,
+        cgauth: bool,
+        auth_type: AuthenticationType,
+        username: &str,
+        password: &str,
+    ) -> Result<(), atat::Error> {
+        if cgauth {
+            self.at_client
+                .send(&SetPDPContextAuthentication {
+                    cid: C::CONTEXT_ID,
+                    auth_type,
+                    username,
+                    password,
+                })
+                .await?;
+        } else {
+            self.at_client
+                .send(&SetAuthParameters {
+                    cid: C::CONTEXT_ID,
+                    auth_type,
+                    username,
+                    password,
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Activate the context, and if that fails with credentials configured,
+    /// try once more with every explicit authentication type. Some networks
+    /// require eg. CHAP regardless of the SIM profile. The type that works is
+    /// remembered, so later activations go straight to it.
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    async fn activate_context_with_auth(
+        &mut self,
+        cid: ContextId,
+        profile_id: ProfileId,
+    ) -> Result<(), Error> {
+        let res = self.activate_context(cid, profile_id).await;
+        if res.is_ok() || self.ch.auth_type().is_some() {
+            return res;
+        }
+
+        let Apn::Given {
+            username: Some(username),
+            password,
+            ..
+        } = self.ch.get_apn_config()
+        else {
+            return res;
+        };
+
+        let password = password.unwrap_or_default();
+        for auth_type in [
+            AuthenticationType::None,
+            AuthenticationType::PAP,
+            AuthenticationType::CHAP,
+        ] {
+            if self
+                .set_auth_parameters(&[auth_type], &username, &password)
+                .await
+                .is_err()
+            {
+                continue;
+            }
+
+            if self.activate_context(cid, profile_id).await.is_ok() {
+                info!("Context activated with {:?} authentication", auth_type);
+                self.ch.set_auth_type(Some(auth_type));
+                return Ok(());
+            }
+        }
+
+        res
+    }
+
     async fn activate_context(
         &mut self,
         cid: ContextId,
@@ -984,3 +1608,102 @@ where
         Err(Error::ContextActivationTimeout)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::asynch::modem_sim::{Fixture, SimConfig, Step, OK};
+
+    use super::*;
+
+    /// Registration is polled, and polled again once a URC tells of it.
+    #[test]
+    fn registration() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut device = NetDevice::<SimConfig, _>::new(&host.ch, &client);
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+CREG?",
+                response: b"\r\n+CREG: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CGREG?",
+                response: b"\r\n+CGREG: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CEREG?",
+                response: b"\r\n+CEREG: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CEER",
+                response: b"\r\n+CEER: \"No report available\",0,\"\"\r\n\r\nOK\r\n",
+            },
+            Step::Urc {
+                delay: Duration::from_millis(10),
+                urc: b"+CEREG: 5,\"4E2D\",\"01A2D001\",7",
+            },
+            Step::Command {
+                cmd: b"AT+CREG?",
+                response: b"\r\n+CREG: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CGREG?",
+                response: b"\r\n+CGREG: 0,2\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CEREG?",
+                response: b"\r\n+CEREG: 0,5\r\n\r\nOK\r\n",
+            },
+        ];
+
+        // The URC ends the wait long before the next poll is due
+        let res = io.play(
+            &mut sim,
+            &script,
+            embassy_time::with_timeout(
+                Duration::from_secs(1),
+                device.wait_network_registered(Duration::from_secs(10)),
+            ),
+        );
+        assert_eq!(res, Ok(Ok(())));
+        assert!(host.ch.is_registered(None));
+        assert!(!host.ch.is_denied(None));
+    }
+
+    #[cfg(not(any(
+        feature = "use-upsd-context-activation",
+        feature = "sara-r422",
+        feature = "context-mapping-required"
+    )))]
+    #[test]
+    fn context_activation() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut device = NetDevice::<SimConfig, _>::new(&host.ch, &client);
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+CGACT?",
+                response: b"\r\n+CGACT: 1,0\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CGACT=1,1",
+                response: OK,
+            },
+            Step::Command {
+                cmd: b"AT+CGACT?",
+                response: b"\r\n+CGACT: 1,1\r\n\r\nOK\r\n",
+            },
+        ];
+
+        let res = io.play(
+            &mut sim,
+            &script,
+            device.activate_context(SimConfig::CONTEXT_ID, SimConfig::PROFILE_ID),
+        );
+        assert_eq!(res, Ok(()));
+    }
+}
@@ -0,0 +1,141 @@
+//! embassy-net on top of the PPP connection of the modem. [`new`] sets up the
+//! runner along with the net stack, so the stack is configured from IPCP by
+//! the runner, and the application only spawns [`PppRunner::run`] and the
+//! runner of the stack.
+
+use core::task::Context;
+
+use embassy_net::{
+    driver::{Capabilities, Driver, HardwareAddress, LinkState},
+    Stack, StackResources,
+};
+
+use super::{control::Control, Resources, Runner};
+use crate::config::{CellularConfig, Transport};
+
+/// Runner of the net stack on top of a [`PppDevice`], to be spawned.
+pub type NetRunner<'d> = embassy_net::Runner<'d, PppDevice<'d>>;
+
+/// Buffers of the PPP connection and sockets of the net stack, see [`new`].
+pub struct PppResources<const N_RX: usize, const N_TX: usize, const SOCK: usize> {
+    ppp: embassy_net_ppp::State<N_RX, N_TX>,
+    stack: StackResources<SOCK>,
+}
+
+impl<const N_RX: usize, const N_TX: usize, const SOCK: usize> Default
+    for PppResources<N_RX, N_TX, SOCK>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N_RX: usize, const N_TX: usize, const SOCK: usize> PppResources<N_RX, N_TX, SOCK> {
+    pub fn new() -> Self {
+        Self {
+            ppp: embassy_net_ppp::State::new(),
+            stack: StackResources::new(),
+        }
+    }
+}
+
+/// Network device of the PPP connection, handed to embassy-net.
+pub struct PppDevice<'d> {
+    device: embassy_net_ppp::Device<'d>,
+}
+
+impl<'d> Driver for PppDevice<'d> {
+    type RxToken<'a>
+        = <embassy_net_ppp::Device<'d> as Driver>::RxToken<'a>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = <embassy_net_ppp::Device<'d> as Driver>::TxToken<'a>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, cx: &mut Context) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.device.receive(cx)
+    }
+
+    fn transmit(&mut self, cx: &mut Context) -> Option<Self::TxToken<'_>> {
+        self.device.transmit(cx)
+    }
+
+    fn link_state(&mut self, cx: &mut Context) -> LinkState {
+        self.device.link_state(cx)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.device.capabilities()
+    }
+
+    fn hardware_address(&self) -> HardwareAddress {
+        self.device.hardware_address()
+    }
+}
+
+/// The cellular [`Runner`] along with the net stack it configures.
+pub struct PppRunner<'a, T, C, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize> {
+    runner: Runner<'a, T, C, INGRESS_BUF_SIZE, URC_CAPACITY>,
+    stack: Stack<'a>,
+}
+
+impl<'a, T, C, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>
+    PppRunner<'a, T, C, INGRESS_BUF_SIZE, URC_CAPACITY>
+where
+    T: Transport,
+    C: CellularConfig<'a> + 'a,
+{
+    pub async fn run(&mut self) -> ! {
+        self.runner.run(self.stack).await
+    }
+}
+
+/// Set up the driver along with an embassy-net stack on its PPP connection.
+/// The stack is configured with the address and DNS servers negotiated with
+/// IPCP once the link is up, see [`Control::network_config`].
+///
+/// Returns the stack and the [`Control`], along with the runners of the
+/// driver and of the stack, which both have to be spawned.
+#[allow(clippy::type_complexity)]
+pub fn new<
+    'a,
+    T,
+    C,
+    const INGRESS_BUF_SIZE: usize,
+    const URC_CAPACITY: usize,
+    const N_RX: usize,
+    const N_TX: usize,
+    const SOCK: usize,
+>(
+    transport: T,
+    resources: &'a mut Resources<INGRESS_BUF_SIZE, URC_CAPACITY>,
+    ppp_resources: &'a mut PppResources<N_RX, N_TX, SOCK>,
+    config: C,
+    random_seed: u64,
+) -> (
+    Stack<'a>,
+    Control<'a, INGRESS_BUF_SIZE>,
+    PppRunner<'a, T, C, INGRESS_BUF_SIZE, URC_CAPACITY>,
+    NetRunner<'a>,
+)
+where
+    T: Transport,
+    C: CellularConfig<'a> + 'a,
+{
+    let (mut runner, control) = Runner::new(transport, resources, config);
+    let device = PppDevice {
+        device: runner.ppp_stack(&mut ppp_resources.ppp),
+    };
+
+    // The address is only known from IPCP, the runner sets it
+    let (stack, net_runner) = embassy_net::new(
+        device,
+        embassy_net::Config::default(),
+        &mut ppp_resources.stack,
+        random_seed,
+    );
+
+    (stack, control, PppRunner { runner, stack }, net_runner)
+}
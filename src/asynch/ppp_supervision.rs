@@ -0,0 +1,297 @@
+//! LCP echo keepalive of the PPP link. A PPP link can die without the
+//! module noticing, eg. when the network drops the context silently, in
+//! which case the data channel simply goes quiet. While the link is idle, an
+//! LCP Echo-Request is sent every [`LinkSupervision::interval`], and once
+//! [`LinkSupervision::max_failures`] of them in a row went unanswered, the
+//! link is considered dead and PPP is torn down.
+
+use embassy_futures::select::{select, Either};
+use embassy_time::{Instant, Timer};
+use embedded_io_async::{BufRead, ErrorKind, ErrorType, Write};
+
+use crate::config::LinkSupervision;
+
+/// Maximum length of an encoded Echo-Request frame, with every byte escaped.
+const ECHO_FRAME_LEN_MAX: usize = 2 + 14 * 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Check {
+    /// Send an Echo-Request with the given identifier
+    SendEcho(u8),
+    /// Too many Echo-Requests went unanswered
+    Dead,
+}
+
+pub(crate) struct LinkSupervisor {
+    config: LinkSupervision,
+    /// Echo-Requests sent since anything was last received
+    failures: u8,
+    next_check: Instant,
+    echo_id: u8,
+}
+
+impl LinkSupervisor {
+    pub(crate) fn new(config: LinkSupervision, now: Instant) -> Self {
+        Self {
+            config,
+            failures: 0,
+            next_check: after(now, config.interval),
+            echo_id: 0,
+        }
+    }
+
+    pub(crate) fn next_check(&self) -> Instant {
+        self.next_check
+    }
+
+    /// Anything received proves the link alive, including the Echo-Reply
+    /// itself.
+    pub(crate) fn on_rx(&mut self, now: Instant) {
+        self.failures = 0;
+        self.next_check = after(now, self.config.interval);
+    }
+
+    /// Nothing was received for a whole interval.
+    pub(crate) fn on_idle(&mut self, now: Instant) -> Check {
+        if self.failures >= self.config.max_failures {
+            return Check::Dead;
+        }
+
+        self.failures += 1;
+        self.echo_id = self.echo_id.wrapping_add(1);
+        self.next_check = after(now, self.config.interval);
+        Check::SendEcho(self.echo_id)
+    }
+}
+
+/// `interval` after `now`, saturating at [`Instant::MAX`] for intervals as
+/// long as [`Duration::MAX`](embassy_time::Duration::MAX) rather than
+/// overflowing.
+fn after(now: Instant, interval: embassy_time::Duration) -> Instant {
+    now.checked_add(interval).unwrap_or(Instant::MAX)
+}
+
+/// PPP frame check sequence (FCS-16), as of RFC 1662.
+fn fcs16(data: &[u8]) -> u16 {
+    let mut fcs = 0xFFFFu16;
+    for &b in data {
+        fcs ^= u16::from(b);
+        for _ in 0..8 {
+            fcs = if fcs & 1 != 0 {
+                (fcs >> 1) ^ 0x8408
+            } else {
+                fcs >> 1
+            };
+        }
+    }
+    !fcs
+}
+
+/// Encode an LCP Echo-Request frame into `buf`, returning its length.
+///
+/// All control characters are escaped, as the frame may be sent before the
+/// async control character map is negotiated.
+fn encode_echo_request(id: u8, buf: &mut [u8; ECHO_FRAME_LEN_MAX]) -> usize {
+    // Address, control, LCP protocol, Echo-Request code, identifier, length
+    // and a zero magic number, as no magic number is negotiated
+    let mut frame = [
+        0xFF, 0x03, 0xC0, 0x21, 0x09, id, 0x00, 0x08, 0, 0, 0, 0, 0, 0,
+    ];
+    let fcs = fcs16(&frame[..12]).to_le_bytes();
+    frame[12..].copy_from_slice(&fcs);
+
+    let mut len = 0;
+    buf[len] = 0x7E;
+    len += 1;
+    for b in frame {
+        if b < 0x20 || b == 0x7D || b == 0x7E {
+            buf[len] = 0x7D;
+            buf[len + 1] = b ^ 0x20;
+            len += 2;
+        } else {
+            buf[len] = b;
+            len += 1;
+        }
+    }
+    buf[len] = 0x7E;
+    len + 1
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) enum SupervisedIoError<E> {
+    Io(E),
+    /// The link stopped answering Echo-Requests
+    LinkDead,
+}
+
+impl<E: embedded_io_async::Error> embedded_io_async::Error for SupervisedIoError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => e.kind(),
+            Self::LinkDead => ErrorKind::TimedOut,
+        }
+    }
+}
+
+/// Data channel wrapper for the PPP runner, injecting Echo-Requests while the
+/// link is idle.
+///
+/// Echo-Requests are only written while the PPP runner waits for data, so
+/// they never end up in the middle of one of its frames.
+pub(crate) struct SupervisedIo<'a, T> {
+    inner: &'a mut T,
+    supervisor: LinkSupervisor,
+    link_dead: bool,
+}
+
+impl<'a, T> SupervisedIo<'a, T> {
+    pub(crate) fn new(inner: &'a mut T, config: LinkSupervision) -> Self {
+        Self {
+            inner,
+            supervisor: LinkSupervisor::new(config, Instant::now()),
+            link_dead: false,
+        }
+    }
+
+    pub(crate) fn is_link_dead(&self) -> bool {
+        self.link_dead
+    }
+}
+
+impl<T: ErrorType> ErrorType for SupervisedIo<'_, T> {
+    type Error = SupervisedIoError<T::Error>;
+}
+
+impl<T: BufRead + Write> BufRead for SupervisedIo<'_, T> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        loop {
+            match select(
+                self.inner.fill_buf(),
+                Timer::at(self.supervisor.next_check()),
+            )
+            .await
+            {
+                Either::First(Ok([])) => return Ok(&[]),
+                Either::First(Ok(_)) => break,
+                Either::First(Err(e)) => return Err(SupervisedIoError::Io(e)),
+                Either::Second(()) => match self.supervisor.on_idle(Instant::now()) {
+                    Check::Dead => {
+                        warn!("PPP link stopped answering LCP echo requests");
+                        self.link_dead = true;
+                        return Err(SupervisedIoError::LinkDead);
+                    }
+                    Check::SendEcho(id) => {
+                        let mut frame = [0u8; ECHO_FRAME_LEN_MAX];
+                        let len = encode_echo_request(id, &mut frame);
+                        self.inner
+                            .write_all(&frame[..len])
+                            .await
+                            .map_err(SupervisedIoError::Io)?;
+                        self.inner.flush().await.map_err(SupervisedIoError::Io)?;
+                    }
+                },
+            }
+        }
+
+        self.supervisor.on_rx(Instant::now());
+        // Returns right away, the data is already buffered
+        self.inner.fill_buf().await.map_err(SupervisedIoError::Io)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl<T: Write> Write for SupervisedIo<'_, T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.inner.write(buf).await.map_err(SupervisedIoError::Io)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await.map_err(SupervisedIoError::Io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embassy_time::Duration;
+
+    const CONFIG: LinkSupervision = LinkSupervision {
+        interval: Duration::from_secs(5),
+        max_failures: 3,
+    };
+
+    #[test]
+    fn echo_on_idle() {
+        let start = Instant::from_secs(100);
+        let mut supervisor = LinkSupervisor::new(CONFIG, start);
+        assert_eq!(supervisor.next_check(), start + CONFIG.interval);
+
+        let now = supervisor.next_check();
+        assert_eq!(supervisor.on_idle(now), Check::SendEcho(1));
+        assert_eq!(supervisor.next_check(), now + CONFIG.interval);
+    }
+
+    #[test]
+    fn dead_after_max_failures() {
+        let mut supervisor = LinkSupervisor::new(CONFIG, Instant::from_secs(0));
+
+        for id in 1..=CONFIG.max_failures {
+            let now = supervisor.next_check();
+            assert_eq!(supervisor.on_idle(now), Check::SendEcho(id));
+        }
+        let now = supervisor.next_check();
+        assert_eq!(supervisor.on_idle(now), Check::Dead);
+    }
+
+    #[test]
+    fn interval_saturates() {
+        let config = LinkSupervision {
+            interval: Duration::MAX,
+            max_failures: 3,
+        };
+        let start = Instant::MAX - Duration::from_secs(1);
+        let mut supervisor = LinkSupervisor::new(config, start);
+        assert_eq!(supervisor.next_check(), Instant::MAX);
+
+        supervisor.on_rx(start);
+        assert_eq!(supervisor.next_check(), Instant::MAX);
+        assert_eq!(supervisor.on_idle(Instant::MAX), Check::SendEcho(1));
+        assert_eq!(supervisor.next_check(), Instant::MAX);
+    }
+
+    #[test]
+    fn rx_resets_failures() {
+        let mut supervisor = LinkSupervisor::new(CONFIG, Instant::from_secs(0));
+
+        for _ in 1..CONFIG.max_failures {
+            let now = supervisor.next_check();
+            supervisor.on_idle(now);
+        }
+
+        let now = supervisor.next_check() - Duration::from_secs(1);
+        supervisor.on_rx(now);
+        assert_eq!(supervisor.next_check(), now + CONFIG.interval);
+
+        for _ in 0..CONFIG.max_failures {
+            let now = supervisor.next_check();
+            assert!(matches!(supervisor.on_idle(now), Check::SendEcho(_)));
+        }
+    }
+
+    #[test]
+    fn echo_request_frame() {
+        let mut buf = [0u8; ECHO_FRAME_LEN_MAX];
+        let len = encode_echo_request(1, &mut buf);
+        assert_eq!(
+            &buf[..len],
+            &[
+                0x7E, 0xFF, 0x7D, 0x23, 0xC0, 0x21, 0x7D, 0x29, 0x7D, 0x21, 0x7D, 0x20, 0x7D, 0x28,
+                0x7D, 0x20, 0x7D, 0x20, 0x7D, 0x20, 0x7D, 0x20, 0x6E, 0xF1, 0x7E
+            ]
+        );
+    }
+}
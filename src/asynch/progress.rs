@@ -0,0 +1,94 @@
+//! Progress of long operations on the module, eg. writing a file to its file
+//! system or installing a firmware update. Operations reporting it publish to
+//! a [`ProgressReceiver`], handed out along with the future of the operation.
+
+use embassy_sync::{
+    blocking_mutex::raw::NoopRawMutex,
+    watch::{self, Watch},
+};
+
+use crate::config::MAX_STATE_RECEIVERS;
+
+pub(crate) type ProgressWatch = Watch<NoopRawMutex, Progress, MAX_STATE_RECEIVERS>;
+
+/// Receiver of the progress of an operation, see [`Progress`].
+pub type ProgressReceiver<'a> = watch::Receiver<'a, NoopRawMutex, Progress, MAX_STATE_RECEIVERS>;
+
+/// Progress of a long operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Progress {
+    /// Done in percent, as reported by the module
+    Percent(u8),
+    /// Bytes done out of `total`, as counted by the driver
+    Bytes { done: usize, total: usize },
+    /// The operation succeeded
+    Completed,
+    /// The operation failed. The cause is returned by the operation itself.
+    Failed,
+}
+
+impl Progress {
+    /// Done in percent, 0 for a failed operation.
+    pub fn percent(&self) -> u8 {
+        match *self {
+            Self::Percent(percent) => percent.min(100),
+            Self::Bytes { total: 0, .. } => 100,
+            Self::Bytes { done, total } => (done.min(total) as u64 * 100 / total as u64) as u8,
+            Self::Completed => 100,
+            Self::Failed => 0,
+        }
+    }
+
+    /// Whether the operation is over, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed)
+    }
+}
+
+/// Publish `progress` to the receivers of `watch`, unless it is unchanged.
+pub(crate) fn publish(watch: &ProgressWatch, progress: Progress) {
+    watch.sender().send_if_modified(|current| {
+        if *current == Some(progress) {
+            return false;
+        }
+        *current = Some(progress);
+        true
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent() {
+        assert_eq!(Progress::Percent(42).percent(), 42);
+        assert_eq!(Progress::Percent(120).percent(), 100);
+        assert_eq!(
+            Progress::Bytes {
+                done: 1000,
+                total: 4000
+            }
+            .percent(),
+            25
+        );
+        assert_eq!(Progress::Bytes { done: 0, total: 0 }.percent(), 100);
+        assert_eq!(Progress::Failed.percent(), 0);
+        assert!(Progress::Completed.is_finished());
+        assert!(!Progress::Percent(100).is_finished());
+    }
+
+    #[test]
+    fn unchanged_not_published() {
+        let watch = ProgressWatch::new();
+        let mut receiver = watch.receiver().unwrap();
+
+        publish(&watch, Progress::Percent(10));
+        assert_eq!(receiver.try_changed(), Some(Progress::Percent(10)));
+        publish(&watch, Progress::Percent(10));
+        assert_eq!(receiver.try_changed(), None);
+        publish(&watch, Progress::Completed);
+        assert_eq!(receiver.try_changed(), Some(Progress::Completed));
+    }
+}
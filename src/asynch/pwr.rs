@@ -1,4 +1,4 @@
-use embassy_time::{with_timeout, Duration, Timer};
+use embassy_time::{with_timeout, Duration, Instant, Timer};
 use embedded_hal::digital::{InputPin as _, OutputPin as _};
 
 use crate::{
@@ -39,7 +39,8 @@ where
     }
 
     /// Reset the module by driving it's `RESET_N` pin low for
-    /// `Module::reset_hold()` duration
+    /// `Module::reset_hold()` duration. The module is still booting on
+    /// return, init waits for it to answer.
     ///
     /// **NOTE** This function will reset NVM settings!
     pub(crate) async fn reset(&mut self) -> Result<(), Error> {
@@ -54,13 +55,6 @@ where
             )
             .await;
             pin.set_high().ok();
-            Timer::after(
-                self.ch
-                    .module()
-                    .map(|m| m.boot_wait())
-                    .unwrap_or(Generic.boot_wait()),
-            )
-            .await;
         } else {
             warn!("No reset pin configured");
         }
@@ -71,7 +65,22 @@ where
         if !self.has_power()? {
             debug!("Attempting to power up device");
 
-            for generic_time in GENERIC_PWR_ON_TIMES {
+            let policy = C::POWER_ON_RETRY;
+            let max_attempts = policy
+                .max_attempts
+                .unwrap_or(if self.ch.module().is_some() {
+                    1
+                } else {
+                    GENERIC_PWR_ON_TIMES.len() as u32
+                });
+
+            for attempt in 0..max_attempts {
+                if attempt > 0 {
+                    Timer::after(policy.delay(attempt - 1, Instant::now().as_ticks())).await;
+                }
+
+                let generic_time =
+                    GENERIC_PWR_ON_TIMES[(attempt as usize).min(GENERIC_PWR_ON_TIMES.len() - 1)];
                 let pull_time = self
                     .ch
                     .module()
@@ -83,18 +92,24 @@ where
                     Timer::after(pull_time).await;
                     pin.set_high().map_err(|_| Error::IoPin)?;
 
-                    Timer::after(
+                    // VINT rises early in the boot, init waits for the module
+                    // to answer AT
+                    let _ = with_timeout(
                         self.ch
                             .module()
                             .map(|m| m.boot_wait())
                             .unwrap_or(Generic.boot_wait()),
+                        async {
+                            while !self.has_power()? {
+                                Timer::after(Duration::from_millis(10)).await;
+                            }
+                            Ok::<(), Error>(())
+                        },
                     )
                     .await;
 
                     if !self.has_power()? {
-                        if self.ch.module().is_some() {
-                            return Err(Error::PoweredDown);
-                        }
+                        debug!("Power up attempt {} failed", attempt + 1);
                         continue;
                     }
 
@@ -112,7 +127,57 @@ where
         }
     }
 
+    /// Whether VINT shows the module in PSM deep sleep. Without a VINT pin,
+    /// this cannot be told.
+    pub(crate) fn is_asleep(&mut self) -> Result<bool, Error> {
+        Ok(self.config.vint_pin().is_some() && !self.has_power()?)
+    }
+
+    /// Wake the module from PSM deep sleep with a pulse on `PWR_ON`, unless
+    /// VINT shows it awake already.
+    pub(crate) async fn wake(&mut self) -> Result<(), Error> {
+        if self.config.vint_pin().is_some() && self.has_power()? {
+            debug!("Module already awake");
+            return Ok(());
+        }
+
+        let pull_time = self
+            .ch
+            .module()
+            .map(|m| m.power_on_pull_time())
+            .unwrap_or(Generic.power_on_pull_time())
+            .unwrap_or(Duration::from_millis(GENERIC_PWR_ON_TIMES[0] as _));
+        match self.config.power_pin() {
+            Some(pin) => {
+                debug!("Waking module from PSM");
+                pin.set_low().map_err(|_| Error::IoPin)?;
+                Timer::after(pull_time).await;
+                pin.set_high().map_err(|_| Error::IoPin)?;
+            }
+            None => warn!("No power pin configured"),
+        }
+        Ok(())
+    }
+
     pub(crate) async fn power_down(&mut self) -> Result<(), Error> {
+        if self.ch.shutdown_report().switched_off {
+            // The module is switching itself off after +CPWROFF, toggling
+            // the power pin meanwhile would start it again
+            let _ = with_timeout(
+                self.ch
+                    .module()
+                    .map(|m| m.power_down_wait())
+                    .unwrap_or(Generic.power_down_wait()),
+                async {
+                    while self.has_power()? {
+                        Timer::after(Duration::from_millis(100)).await;
+                    }
+                    Ok::<(), Error>(())
+                },
+            )
+            .await;
+        }
+
         if self.has_power()? {
             if let Some(pin) = self.config.power_pin() {
                 pin.set_low().map_err(|_| Error::IoPin)?;
@@ -1,10 +1,16 @@
+use core::cell::Cell;
+
 use atat::{ResponseSlot, UrcChannel};
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
 
 use super::{
+    control::AtLock,
+    digester::{CustomUrcChannel, ErrorCode},
     runner::{CMUX_CHANNELS, CMUX_CHANNEL_SIZE, MAX_CMD_LEN, URC_SUBSCRIBERS},
     state,
 };
+#[cfg(feature = "internal-network-stack")]
+use crate::command::ip_transport_layer::responses::{INGRESS_CHUNK_SIZE, INGRESS_FRAMING};
 use crate::command::Urc;
 
 pub struct Resources<const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize> {
@@ -12,6 +18,13 @@ pub struct Resources<const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize> {
 
     pub(crate) res_slot: ResponseSlot<INGRESS_BUF_SIZE>,
     pub(crate) req_slot: Channel<NoopRawMutex, heapless::Vec<u8, { MAX_CMD_LEN }>, 1>,
+    /// Set while the response to a raw command is pending
+    pub(crate) raw_mode: Cell<bool>,
+    /// Code of the last +CME ERROR or +CMS ERROR
+    pub(crate) error_code: Cell<Option<ErrorCode>>,
+    pub(crate) custom_urcs: CustomUrcChannel,
+    /// Held by whoever talks on the AT channel, the runner or the `Control`
+    pub(crate) at_lock: AtLock,
 
     pub(crate) urc_channel: UrcChannel<Urc, URC_CAPACITY, URC_SUBSCRIBERS>,
     pub(crate) ingress_buf: [u8; INGRESS_BUF_SIZE],
@@ -30,12 +43,31 @@ impl<const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize> Default
 impl<const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>
     Resources<INGRESS_BUF_SIZE, URC_CAPACITY>
 {
+    /// A URC channel without capacity loses every URC.
+    const CAPACITY_CHECK: () = assert!(URC_CAPACITY > 0, "URC_CAPACITY must be at least 1");
+
+    /// A +USORD or +USORF response carries up to `INGRESS_CHUNK_SIZE` bytes
+    /// hex encoded, along with up to `INGRESS_FRAMING` bytes around them.
+    #[cfg(feature = "internal-network-stack")]
+    const INGRESS_CHECK: () = assert!(
+        INGRESS_BUF_SIZE >= INGRESS_CHUNK_SIZE * 2 + INGRESS_FRAMING,
+        "INGRESS_BUF_SIZE is too small for INGRESS_CHUNK_SIZE"
+    );
+
     pub fn new() -> Self {
+        let () = Self::CAPACITY_CHECK;
+        #[cfg(feature = "internal-network-stack")]
+        let () = Self::INGRESS_CHECK;
+
         Self {
             ch: state::State::new(),
 
             res_slot: ResponseSlot::new(),
             req_slot: Channel::new(),
+            raw_mode: Cell::new(false),
+            error_code: Cell::new(None),
+            custom_urcs: Channel::new(),
+            at_lock: AtLock::new(None),
 
             urc_channel: UrcChannel::new(),
             ingress_buf: [0; INGRESS_BUF_SIZE],
@@ -44,3 +76,65 @@ impl<const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>
         }
     }
 }
+
+#[cfg(all(test, feature = "internal-network-stack"))]
+mod tests {
+    use atat::AtatIngress as _;
+    use embassy_time::{with_timeout, Duration};
+
+    use super::*;
+    use crate::asynch::digester::Digester;
+
+    const MIN_INGRESS_BUF_SIZE: usize = INGRESS_CHUNK_SIZE * 2 + INGRESS_FRAMING;
+
+    /// The longest +USORF response fits the smallest ingress buffer allowed.
+    #[test]
+    fn longest_response_fits() {
+        let () = Resources::<MIN_INGRESS_BUF_SIZE, 1>::INGRESS_CHECK;
+
+        let mut frame = heapless::Vec::<u8, MIN_INGRESS_BUF_SIZE>::new();
+        frame
+            .extend_from_slice(
+                b"\r\n+USORF: 6,\"[ffff:ffff:ffff:ffff:ffff:ffff:255.255.255.255]\",65535,",
+            )
+            .unwrap();
+        let mut length = heapless::String::<8>::new();
+        core::fmt::write(&mut length, format_args!("{},\"", INGRESS_CHUNK_SIZE)).unwrap();
+        frame.extend_from_slice(length.as_bytes()).unwrap();
+        frame
+            .resize(frame.len() + INGRESS_CHUNK_SIZE * 2, b'A')
+            .unwrap();
+        frame.extend_from_slice(b"\"\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(frame.len(), MIN_INGRESS_BUF_SIZE);
+
+        let raw_mode = Cell::new(false);
+        let error_code = Cell::new(None);
+        let custom_urcs = CustomUrcChannel::new();
+        let res_slot = ResponseSlot::<MIN_INGRESS_BUF_SIZE>::new();
+        let urc_channel = UrcChannel::<Urc, 1, URC_SUBSCRIBERS>::new();
+        let mut buf = [0; MIN_INGRESS_BUF_SIZE];
+        let mut ingress = atat::Ingress::new(
+            Digester::new(&raw_mode, &error_code, &custom_urcs, &[]),
+            &mut buf,
+            &res_slot,
+            &urc_channel,
+        );
+
+        let mut rest = frame.as_slice();
+        while !rest.is_empty() {
+            let free = ingress.write_buf();
+            let n = free.len().min(rest.len());
+            assert!(n > 0, "Ingress buffer full");
+            free[..n].copy_from_slice(&rest[..n]);
+            ingress.try_advance(n).unwrap();
+            rest = &rest[n..];
+        }
+
+        let response =
+            embassy_futures::block_on(with_timeout(Duration::from_secs(1), res_slot.get()))
+                .expect("No response");
+        let response: &atat::Response<MIN_INGRESS_BUF_SIZE> = &response.borrow();
+        let response: Result<&[u8], _> = response.into();
+        assert_eq!(response.unwrap(), &frame[2..frame.len() - 8]);
+    }
+}
@@ -5,65 +5,153 @@ use crate::{
     command::{
         control::{
             types::{
-                BaudRate, Circuit108Behaviour, Circuit109Behaviour, Echo, ResultCodeSelection,
+                BaudRate, Circuit108Behaviour, Circuit109Behaviour, Echo, LocalFlowControl,
+                ResultCodeSelection,
             },
             SetCircuit108Behaviour, SetCircuit109Behaviour, SetDataRate, SetEcho,
-            SetResultCodeSelection,
+            SetLocalFlowControl, SetResultCodeSelection,
+        },
+        general::{
+            responses::FirmwareVersion, GetCCID, GetCIMI, GetFirmwareVersion, GetIMEI, GetModelId,
         },
-        device_lock::{responses::PinStatus, types::PinStatusCode, GetPinStatus},
-        general::{responses::FirmwareVersion, GetCCID, GetFirmwareVersion, GetModelId},
         ip_transport_layer::{
             types::{PreferredProtocolType, SocketProtocol},
             CloseSocket, CreateSocket,
         },
         ipc::SetMultiplexing,
         mobile_control::{
-            types::{Functionality, TerminationErrorMode},
-            SetModuleFunctionality, SetReportMobileTerminationError,
+            types::{EventReportingMode, Functionality, IndicatorReporting, TerminationErrorMode},
+            SetIndicatorConfiguration, SetMobileTerminationEventReporting, SetModuleFunctionality,
+            SetReportMobileTerminationError,
         },
-        network_service::SetChannelAndNetworkEnvDesc,
+        network_service::SetCellEnvironmentReporting,
         networking::SetEmbeddedPortFiltering,
-        psn::EnterPPP,
-        system_features::{types::PowerSavingMode, SetPowerSavingControl},
+        psn::{types::PSEventReportingMode, EnterPPP, SetPacketSwitchedEventReporting},
+        system_features::{
+            types::{FirmwareInstallError, PowerSavingMode},
+            urc::FirmwareInstallProgress,
+            SetPowerSavingControl, SetPsmStateReporting,
+        },
         Urc, AT,
     },
     config::{CellularConfig, Transport},
-    error::Error,
-    modules::{Module, ModuleParams as _},
+    error::{Error, InitError},
+    modules::{Generic, InitCommand, Module, ModuleParams as _},
     DEFAULT_BAUD_RATE,
 };
 
 use super::{
-    control::{Control, ProxyClient},
+    control::{AtLock, Control, ProxyClient},
+    diagnostics::{DiagnosedClient, TransitionReason},
+    digester::Digester,
     pwr::PwrCtrl,
-    state,
+    sim::{self, SimCheck},
+    state::{self, FirmwareInstallState, PsmAction, RecoveryAction, SimState},
     urc_handler::UrcHandler,
+    watchdog::{self, ResetLadder},
     Resources,
 };
 
+#[cfg(feature = "at-trace")]
+use super::at_trace::{TracedIo, Tracer};
+#[cfg(all(feature = "internal-network-stack", not(feature = "ppp")))]
+use super::direct_link::{self, DirectLink, DirectLinkRunner};
+#[cfg(feature = "ppp")]
+use super::ppp_supervision::SupervisedIo;
+#[cfg(feature = "internal-network-stack")]
+use super::socket_ingress;
+#[cfg(feature = "internal-network-stack")]
+use crate::command::ip_transport_layer::{
+    ConnectSocket, PrepareUDPSendToDataBinary, UDPSendToDataBinary, WriteSocketDataBinary,
+};
+#[cfg(feature = "ppp")]
+use crate::command::networking::types::EmbeddedPortFilteringMode;
+#[cfg(feature = "sms")]
+use crate::command::sms::SendMessagePdu;
+
 use atat::{
     asynch::{AtatClient, SimpleClient},
     AtatIngress as _, UrcChannel,
 };
 
+#[cfg(not(feature = "ppp"))]
+use embassy_futures::select::{select3, Either3};
 use embassy_futures::{
     join::join,
-    select::{select3, Either3},
+    select::{select, select4, Either, Either4},
 };
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
 use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
 use embedded_io_async::BufRead as _;
 use embedded_io_async::Write as _;
 
 pub(crate) const URC_SUBSCRIBERS: usize = 2;
 
-pub(crate) const MAX_CMD_LEN: usize = 128;
+// The URC handler subscribes, and so does the internal network stack
+const _: () = assert!(URC_SUBSCRIBERS > cfg!(feature = "internal-network-stack") as usize);
+
+#[cfg(any(feature = "sms", feature = "internal-network-stack"))]
+const fn max(a: usize, b: usize) -> usize {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+// SMS PDUs are sent with a single command, and so is socket data. The remote
+// host of a socket may be a domain name. Only the features built in count.
+pub(crate) const MAX_CMD_LEN: usize = {
+    let len = 128;
+    #[cfg(feature = "sms")]
+    let len = max(len, <SendMessagePdu<'static> as atat::AtatCmd>::MAX_LEN);
+    #[cfg(feature = "internal-network-stack")]
+    let len = max(
+        len,
+        max(
+            max(
+                <WriteSocketDataBinary<'static> as atat::AtatCmd>::MAX_LEN,
+                <UDPSendToDataBinary<'static> as atat::AtatCmd>::MAX_LEN,
+            ),
+            max(
+                <ConnectSocket as atat::AtatCmd>::MAX_LEN,
+                <PrepareUDPSendToDataBinary as atat::AtatCmd>::MAX_LEN,
+            ),
+        ),
+    );
+    len
+};
 
 pub const CMUX_MAX_FRAME_SIZE: usize = 256;
 pub const CMUX_CHANNEL_SIZE: usize = CMUX_MAX_FRAME_SIZE * 8;
 
 pub const CMUX_CHANNELS: usize = 2;
 
+/// Time the module needs to leave UART power saving after DTR is asserted.
+const DTR_WAKE_TIME: Duration = Duration::from_millis(20);
+
+/// De-assert DTR once no AT command was sent for this long.
+const DTR_IDLE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time an `AT` probe of a booting module waits for the answer. The module
+/// is probed until it answers, for up to its `boot_wait`.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Time the module takes to go down after it was told to reboot, so that
+/// the probes don't catch it before.
+const REBOOT_SETTLE: Duration = Duration::from_secs(1);
+
+/// Lines of a module that finished booting: the answer to a probe, +PACSP
+/// (reported by most modules once the SIM is read) and the greeting text
+/// set up with +CSGT.
+const READY_LINES: [&[u8]; 3] = [b"OK", b"+PACSP", b"AT ready"];
+
+/// Upper bound on a modem firmware installation, including the reboots
+/// before and after it, unless started by `Control::install_firmware` with a
+/// timeout of its own.
+const DEFAULT_FIRMWARE_INSTALL_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
 /// Drain any late/pending bytes still queued on the PPP data channel.
 ///
 /// During roaming registration churn the modem can answer the LARA-R6
@@ -94,27 +182,124 @@ async fn drain_data_channel(channel: &mut at_cmux::Channel<'_, CMUX_CHANNEL_SIZE
     .await;
 }
 
-async fn at_bridge<'a, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>(
+/// What the AT bridge has to do next.
+enum TxEvent {
+    Request(heapless::Vec<u8, MAX_CMD_LEN>),
+    Psm(PsmAction),
+}
+
+async fn next_tx_event(
+    req_slot: &Channel<NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
+    ch: &state::Runner<'_>,
+    track_psm: bool,
+) -> TxEvent {
+    let psm_action = async {
+        match track_psm {
+            true => ch.wait_psm_action().await,
+            false => core::future::pending().await,
+        }
+    };
+
+    match select(req_slot.receive(), psm_action).await {
+        Either::First(msg) => TxEvent::Request(msg),
+        Either::Second(action) => TxEvent::Psm(action),
+    }
+}
+
+async fn at_bridge<
+    'a,
+    C: CellularConfig<'a>,
+    const INGRESS_BUF_SIZE: usize,
+    const URC_CAPACITY: usize,
+>(
     (rx, tx): (
         &mut at_cmux::ChannelRx<'a, CMUX_CHANNEL_SIZE>,
         &mut at_cmux::ChannelTx<'a, CMUX_CHANNEL_SIZE>,
     ),
     req_slot: &Channel<NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
+    res_slot: &atat::ResponseSlot<INGRESS_BUF_SIZE>,
     ingress: &mut atat::Ingress<
         'a,
-        atat::AtDigester<Urc>,
+        Digester<'a>,
         Urc,
         INGRESS_BUF_SIZE,
         URC_CAPACITY,
         URC_SUBSCRIBERS,
     >,
+    ch: &state::Runner<'a>,
+    config: &mut C,
+    #[cfg(feature = "at-trace")] tracer: Option<&Tracer<'a>>,
 ) -> ! {
     ingress.clear();
 
+    #[cfg(feature = "at-trace")]
+    let (rx, tx) = (TracedIo::new(rx, tracer), &mut TracedIo::new(tx, tracer));
+
     let tx_fut = async {
+        // DTR is left asserted by `init()`
+        let mut awake = true;
         loop {
-            let msg = req_slot.receive().await;
-            let _ = tx.write_all(&msg).await;
+            let event = match config.dtr_pin() {
+                Some(pin) if awake => {
+                    match embassy_time::with_timeout(
+                        DTR_IDLE_TIMEOUT,
+                        next_tx_event(req_slot, ch, C::WAKE_FROM_PSM),
+                    )
+                    .await
+                    {
+                        Ok(event) => event,
+                        Err(_) => {
+                            // Let the module enter power saving, unless a PPP
+                            // data connection keeps the UART busy
+                            if !cfg!(feature = "ppp")
+                                || ch.operation_state(None) != OperationState::DataEstablished
+                            {
+                                pin.set_high().ok();
+                                awake = false;
+                            }
+                            continue;
+                        }
+                    }
+                }
+                Some(pin) => {
+                    let data_established = poll_fn(|cx| match ch.operation_state(Some(cx)) {
+                        OperationState::DataEstablished if cfg!(feature = "ppp") => Poll::Ready(()),
+                        _ => Poll::Pending,
+                    });
+
+                    let event = match select(
+                        next_tx_event(req_slot, ch, C::WAKE_FROM_PSM),
+                        data_established,
+                    )
+                    .await
+                    {
+                        Either::First(event) => Some(event),
+                        Either::Second(_) => None,
+                    };
+
+                    pin.set_low().ok();
+                    Timer::after(DTR_WAKE_TIME).await;
+                    awake = true;
+
+                    match event {
+                        Some(event) => event,
+                        None => continue,
+                    }
+                }
+                None => next_tx_event(req_slot, ch, C::WAKE_FROM_PSM).await,
+            };
+
+            match event {
+                TxEvent::Request(msg) => {
+                    let _ = tx.write_all(&msg).await;
+                }
+                TxEvent::Psm(PsmAction::CheckSleep) => {
+                    if PwrCtrl::new(ch, config).is_asleep().unwrap_or(false) {
+                        ch.set_sleeping(true);
+                    }
+                }
+                TxEvent::Psm(PsmAction::Wake) => wake_from_psm(ch, config, tx, res_slot).await,
+            }
         }
     };
 
@@ -123,6 +308,65 @@ async fn at_bridge<'a, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>
     unreachable!()
 }
 
+/// Switch RTS/CTS flow control on if `enabled`, and check that the module
+/// still answers. A module without RTS wired holds back all of its responses
+/// then, so flow control is switched off again, which it still takes. Returns
+/// whether flow control is in use.
+async fn setup_flow_control<A: AtatClient>(at_client: &mut A, enabled: bool) -> bool {
+    if enabled {
+        let rts_cts = SetLocalFlowControl {
+            dce_by_dte: LocalFlowControl::RtsCts,
+            dte_by_dce: LocalFlowControl::RtsCts,
+        };
+        if at_client.send(&rts_cts).await.is_ok() && at_client.send(&AT).await.is_ok() {
+            return true;
+        }
+        warn!("No answer with RTS/CTS flow control, continuing without. Are RTS and CTS wired?");
+    }
+
+    let _ = at_client
+        .send(&SetLocalFlowControl {
+            dce_by_dte: LocalFlowControl::None,
+            dte_by_dce: LocalFlowControl::None,
+        })
+        .await;
+    false
+}
+
+/// Wake the module from PSM deep sleep, and wait for it to answer AT again.
+async fn wake_from_psm<'a, C: CellularConfig<'a>, const INGRESS_BUF_SIZE: usize>(
+    ch: &state::Runner<'a>,
+    config: &mut C,
+    tx: &mut impl embedded_io_async::Write,
+    res_slot: &atat::ResponseSlot<INGRESS_BUF_SIZE>,
+) {
+    if let Err(e) = PwrCtrl::new(ch, config).wake().await {
+        warn!("Failed to wake the module from PSM: {:?}", e);
+    }
+
+    let module = ch.module();
+    let deadline = Instant::now() + module.map(|m| m.boot_wait()).unwrap_or(Generic.boot_wait());
+    let delay = module
+        .map(|m| m.command_delay_default())
+        .unwrap_or(Generic.command_delay_default());
+    while Instant::now() < deadline {
+        res_slot.reset();
+        let _ = tx.write_all(b"AT\r").await;
+        if embassy_time::with_timeout(PROBE_TIMEOUT, res_slot.get())
+            .await
+            .is_ok()
+        {
+            ch.set_sleeping(false);
+            return;
+        }
+        Timer::after(delay).await;
+    }
+
+    // Let the waiting commands time out, rather than wait forever
+    warn!("Module did not wake from PSM");
+    ch.set_sleeping(false);
+}
+
 /// Background runner for the Ublox Module.
 ///
 /// You must call `.run()` in a background task for the Ublox Module to operate.
@@ -133,16 +377,11 @@ pub struct Runner<'a, T, C, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: u
     pub config: C,
     pub urc_channel: &'a UrcChannel<Urc, URC_CAPACITY, URC_SUBSCRIBERS>,
 
-    pub ingress: atat::Ingress<
-        'a,
-        atat::AtDigester<Urc>,
-        Urc,
-        INGRESS_BUF_SIZE,
-        URC_CAPACITY,
-        URC_SUBSCRIBERS,
-    >,
+    pub ingress:
+        atat::Ingress<'a, Digester<'a>, Urc, INGRESS_BUF_SIZE, URC_CAPACITY, URC_SUBSCRIBERS>,
     pub res_slot: &'a atat::ResponseSlot<INGRESS_BUF_SIZE>,
     pub req_slot: &'a Channel<NoopRawMutex, heapless::Vec<u8, MAX_CMD_LEN>, 1>,
+    at_lock: &'a AtLock,
 
     pub mux_runner: at_cmux::Runner<'a, CMUX_CHANNELS, CMUX_CHANNEL_SIZE>,
 
@@ -153,8 +392,16 @@ pub struct Runner<'a, T, C, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: u
     ),
     data_channel: at_cmux::Channel<'a, CMUX_CHANNEL_SIZE>,
 
+    reset_ladder: ResetLadder,
+
+    #[cfg(feature = "at-trace")]
+    at_trace: Option<Tracer<'a>>,
+
     #[cfg(feature = "ppp")]
     pub ppp_runner: Option<embassy_net_ppp::Runner<'a>>,
+
+    #[cfg(all(feature = "internal-network-stack", not(feature = "ppp")))]
+    direct_link: Option<DirectLinkRunner<'a>>,
 }
 
 impl<'a, T, C, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>
@@ -166,12 +413,20 @@ where
     pub fn new(
         transport: T,
         resources: &'a mut Resources<INGRESS_BUF_SIZE, URC_CAPACITY>,
-        config: C,
+        #[allow(unused_mut)] mut config: C,
     ) -> (Self, Control<'a, INGRESS_BUF_SIZE>) {
+        #[cfg(feature = "at-trace")]
+        let at_trace = config.at_trace().map(Tracer::new);
+
         let ch_runner = state::Runner::new(&mut resources.ch);
 
         let ingress = atat::Ingress::new(
-            atat::AtDigester::<Urc>::new(),
+            Digester::new(
+                &resources.raw_mode,
+                &resources.error_code,
+                &resources.custom_urcs,
+                C::CUSTOM_URCS,
+            ),
             &mut resources.ingress_buf,
             &resources.res_slot,
             &resources.urc_channel,
@@ -187,6 +442,10 @@ where
             ch_runner.clone(),
             resources.req_slot.sender(),
             &resources.res_slot,
+            &resources.raw_mode,
+            &resources.error_code,
+            &resources.custom_urcs,
+            &resources.at_lock,
         );
 
         (
@@ -200,14 +459,23 @@ where
                 ingress,
                 res_slot: &resources.res_slot,
                 req_slot: &resources.req_slot,
+                at_lock: &resources.at_lock,
 
                 mux_runner,
 
                 at_channel,
                 data_channel,
 
+                reset_ladder: ResetLadder::new(C::HANG_TIMEOUTS),
+
+                #[cfg(feature = "at-trace")]
+                at_trace,
+
                 #[cfg(feature = "ppp")]
                 ppp_runner: None,
+
+                #[cfg(all(feature = "internal-network-stack", not(feature = "ppp")))]
+                direct_link: None,
             },
             control,
         )
@@ -223,6 +491,14 @@ where
         net_device
     }
 
+    /// Direct link mode (+USODL) of a single socket, for bulk transfers. It
+    /// runs on the CMUX data channel, which is only free without PPP.
+    #[cfg(all(feature = "internal-network-stack", not(feature = "ppp")))]
+    pub fn direct_link(&mut self, state: &'a direct_link::State) -> DirectLink<'a> {
+        self.direct_link.replace(DirectLinkRunner::new(state));
+        DirectLink::new(state)
+    }
+
     #[cfg(feature = "internal-network-stack")]
     pub fn internal_stack(&mut self) -> state::Device<URC_CAPACITY> {
         // let data_channel = self.data_channel;
@@ -305,6 +581,123 @@ where
         Ok(())
     }
 
+    /// Follow a running firmware installation by reading the `+UUFWINSTALL`
+    /// URCs the module reports on the UART after its first reboot, until the
+    /// installation has completed or failed.
+    async fn monitor_firmware_install(&mut self) -> FirmwareInstallState {
+        let mut line = heapless::Vec::<u8, 32>::new();
+        loop {
+            let Ok(buf) = self.transport.fill_buf().await else {
+                Timer::after_millis(100).await;
+                continue;
+            };
+            let len = buf.len();
+            let mut result = None;
+
+            for &b in buf {
+                if b != b'\n' {
+                    if line.push(b).is_err() {
+                        line.clear();
+                    }
+                    continue;
+                }
+
+                let status = core::str::from_utf8(&line)
+                    .ok()
+                    .and_then(|l| l.trim().strip_prefix("+UUFWINSTALL:"))
+                    .and_then(|s| s.trim().parse::<u8>().ok());
+                line.clear();
+
+                match status {
+                    Some(progress) if progress <= 100 => {
+                        info!("🔄 Firmware install progress: {}%", progress);
+                        self.ch
+                            .set_firmware_install_state(FirmwareInstallState::Installing(progress));
+                    }
+                    Some(FirmwareInstallProgress::COMPLETED) => {
+                        result = Some(FirmwareInstallState::Completed);
+                        break;
+                    }
+                    Some(code) => {
+                        error!("❌ Firmware install failed: {}", code);
+                        result = Some(FirmwareInstallState::Failed(FirmwareInstallError::Install(
+                            code,
+                        )));
+                        break;
+                    }
+                    None => {}
+                }
+            }
+
+            self.transport.consume(len);
+
+            if let Some(result) = result {
+                return result;
+            }
+        }
+    }
+
+    /// Wait for the module to answer after it was powered up or reset,
+    /// probing it with `AT` at the current baud rate, or seeing one of its
+    /// [`READY_LINES`]. Returns `false` if it did not within `boot_wait`, eg.
+    /// as it runs at another baud rate.
+    async fn wait_responsive(&mut self) -> bool {
+        let module = self.ch.module();
+        let boot_wait = module.map(|m| m.boot_wait()).unwrap_or(Generic.boot_wait());
+        let delay = module
+            .map(|m| m.command_delay_default())
+            .unwrap_or(Generic.command_delay_default());
+
+        let transport = &mut self.transport;
+        let res = embassy_time::with_timeout(boot_wait, async {
+            let mut line = heapless::Vec::<u8, 32>::new();
+            loop {
+                let _ = transport.write_all(b"AT\r").await;
+
+                let answered = embassy_time::with_timeout(PROBE_TIMEOUT, async {
+                    loop {
+                        let Ok(buf) = transport.fill_buf().await else {
+                            Timer::after_millis(10).await;
+                            continue;
+                        };
+                        let len = buf.len();
+                        let mut ready = false;
+                        for &b in buf {
+                            if b != b'\n' {
+                                if line.push(b).is_err() {
+                                    line.clear();
+                                }
+                                continue;
+                            }
+
+                            let l = line.trim_ascii();
+                            ready |= READY_LINES.iter().any(|r| l.starts_with(r));
+                            line.clear();
+                        }
+                        transport.consume(len);
+
+                        if ready {
+                            return;
+                        }
+                    }
+                })
+                .await
+                .is_ok();
+                if answered {
+                    return;
+                }
+
+                Timer::after(delay).await;
+            }
+        })
+        .await;
+
+        if res.is_err() {
+            debug!("Module did not answer within {}ms", boot_wait.as_millis());
+        }
+        res.is_ok()
+    }
+
     /// Drain all pending bytes from the transport buffer
     async fn flush_transport(&mut self) {
         let _ = embassy_time::with_timeout(Duration::from_millis(100), async {
@@ -336,6 +729,18 @@ where
             return Err(e);
         };
 
+        // Keep the module awake throughout init, in case power saving is
+        // still enabled from a previous session
+        if let Some(pin) = self.config.dtr_pin() {
+            pin.set_low().map_err(|_| Error::IoPin)?;
+            Timer::after(DTR_WAKE_TIME).await;
+        }
+
+        // Proceed as soon as a booting module answers, rather than waiting
+        // out `boot_wait`. This covers resets and firmware installs too,
+        // which all power the module up again through here.
+        self.wait_responsive().await;
+
         // Rid the transport of garbage bytes from powercycle
         self.flush_transport().await;
 
@@ -387,46 +792,77 @@ where
         // the reply to GetModelId (which would desync module identification).
         self.flush_transport().await;
 
-        let mut cmd_buf = [0u8; 128];
-        let mut at_client = SimpleClient::new(
-            &mut self.transport,
-            atat::AtDigester::<Urc>::new(),
-            &mut cmd_buf,
-            C::AT_CONFIG,
-        );
+        let flow_control = {
+            let mut cmd_buf = [0u8; 32];
+            let mut at_client = SimpleClient::new(
+                &mut self.transport,
+                atat::AtDigester::<Urc>::new(),
+                &mut cmd_buf,
+                C::AT_CONFIG,
+            );
+            setup_flow_control(&mut at_client, C::FLOW_CONTROL).await
+        };
+        self.ch
+            .set_flow_control_fallback(C::FLOW_CONTROL && !flow_control);
+        if !flow_control {
+            // Responses held back while RTS was off come out now
+            self.flush_transport().await;
+        }
 
-        // FIXME:
-        // // Tell module whether we support flow control
-        // let flow_control = if C::FLOW_CONTROL {
-        //     FlowControl::RtsCts
-        // } else {
-        //     FlowControl::Disabled
-        // };
+        #[cfg(feature = "at-trace")]
+        let mut transport = TracedIo::new(&mut self.transport, self.at_trace.as_ref());
+        #[cfg(not(feature = "at-trace"))]
+        let mut transport = &mut self.transport;
 
-        // at_client
-        //     .send_retry(&SetFlowControl {
-        //         value: flow_control,
-        //     })
-        //     .await?;
+        let mut cmd_buf = [0u8; 128];
+        let mut at_client = DiagnosedClient::new(
+            SimpleClient::new(
+                &mut transport,
+                atat::AtDigester::<Urc>::new(),
+                &mut cmd_buf,
+                C::AT_CONFIG,
+            ),
+            self.ch.diagnostics(),
+        );
 
         let model_id = at_client.send_retry(&GetModelId).await?;
-        self.ch.set_module(Module::from_model_id(&model_id));
+        let module = Module::from_model_id(&model_id);
+        self.ch.set_module(module);
+
+        module.check_context_id(C::CONTEXT_ID)?;
+        #[cfg(feature = "use-upsd-context-activation")]
+        module.check_profile_id(C::PROFILE_ID)?;
 
         let FirmwareVersion { version } = at_client.send_retry(&GetFirmwareVersion).await?;
         info!("Found module to be: {:?}, {:?}", self.ch.module(), version);
 
+        let imei = at_client.send_retry(&GetIMEI { snt: None }).await.ok();
+        self.ch.update_identity(|id| {
+            id.model = core::str::from_utf8(&model_id.model)
+                .ok()
+                .and_then(|m| heapless::String::try_from(m.trim()).ok())
+                .unwrap_or_default();
+            id.firmware_version = Some(version);
+            id.imei = imei.map(|res| res.imei);
+        });
+
+        // With PPP, the filtering is applied before each dial-up instead
+        #[cfg(not(feature = "ppp"))]
         at_client
             .send_retry(&SetEmbeddedPortFiltering {
                 mode: C::EMBEDDED_PORT_FILTERING,
             })
             .await?;
+        #[cfg(feature = "ppp")]
+        self.ch
+            .set_embedded_port_filtering(Some(C::EMBEDDED_PORT_FILTERING));
 
         // Echo off
         at_client
             .send_retry(&SetEcho { enabled: Echo::Off })
             .await?;
 
-        // Extended errors on
+        // Extended errors on, with the numeric codes `Control` decodes
         at_client
             .send_retry(&SetReportMobileTerminationError {
                 n: TerminationErrorMode::Enabled,
@@ -434,18 +870,29 @@ where
             .await?;
 
         #[cfg(feature = "internal-network-stack")]
-        if C::HEX_MODE {
-            at_client
-                .send_retry(&crate::command::ip_transport_layer::SetHexMode {
-                    hex_mode_disable: crate::command::ip_transport_layer::types::HexMode::Enabled,
-                })
-                .await?;
-        } else {
+        {
+            use crate::command::ip_transport_layer::{
+                types::{DataConfiguration, HexMode},
+                SetDataConfiguration,
+            };
+
+            let hex_mode = if C::HEX_MODE {
+                HexMode::Enabled
+            } else {
+                HexMode::Disabled
+            };
             at_client
-                .send_retry(&crate::command::ip_transport_layer::SetHexMode {
-                    hex_mode_disable: crate::command::ip_transport_layer::types::HexMode::Disabled,
+                .send_retry(&SetDataConfiguration {
+                    config: DataConfiguration::HexMode(hex_mode),
                 })
                 .await?;
+            self.ch.set_hex_mode(C::HEX_MODE);
+
+            for &config in C::DATA_CONFIGURATION {
+                at_client
+                    .send_retry(&SetDataConfiguration { config })
+                    .await?;
+            }
         }
 
         // DCD circuit (109) changes in accordance with the carrier
@@ -462,27 +909,92 @@ where
             })
             .await?;
 
-        // Check sim status
+        // Report the indicators with +CIEV, eg. SIM insertion to catch SIM
+        // swaps while running. Not every module supports selecting the
+        // indicators.
+        if C::REPORTED_INDICATORS != 0 {
+            at_client
+                .send_retry(&SetIndicatorConfiguration {
+                    conf: C::REPORTED_INDICATORS,
+                })
+                .await
+                .ok();
+            at_client
+                .send_retry(&SetMobileTerminationEventReporting {
+                    mode: EventReportingMode::Discard,
+                    keyp: 0,
+                    disp: 0,
+                    ind: IndicatorReporting::Changes,
+                })
+                .await
+                .ok();
+        }
+
+        // Report packet domain events with +CGEV, to learn which context the
+        // network deactivated. Buffered while the link is reserved, and
+        // flushed once it is free again.
+        at_client
+            .send_retry(&SetPacketSwitchedEventReporting {
+                mode: PSEventReportingMode::BufferUrcs,
+                bfr: Some(1),
+            })
+            .await?;
+
+        // Check sim status. Right after power up or SIM insertion the SIM can
+        // be busy for a while, so back off between attempts.
         let sim_status = async {
-            for _ in 0..2 {
-                if let Ok(res) = at_client.send_retry(&GetCCID).await {
-                    return Ok(res.ccid);
+            let mut backoff = Duration::from_millis(250);
+            for _ in 0..5 {
+                match at_client.send_retry(&GetCCID).await {
+                    Ok(res) => return Ok(res.ccid),
+                    Err(e) if sim::is_absent(&e) => return Err(SimState::Absent),
+                    Err(_) => {}
                 }
 
-                Timer::after_secs(1).await;
+                Timer::after(backoff).await;
+                backoff = backoff * 2;
             }
-            Err(Error::SimCard)
+            Err(SimState::Unknown)
         };
 
-        match sim_status.await {
+        let iccid = match sim_status.await {
             Ok(ccid) => {
                 info!("CCID: {}", ccid);
+                self.ch.set_sim_state(SimState::Present);
+                Some(ccid)
+            }
+            Err(SimState::Absent) => {
+                warn!("No SIM inserted, waiting for one");
+                self.ch.set_sim_state(SimState::Absent);
+                None
             }
-            Err(_) => {
-                warn!("Faild to get CCID, SIM card missing or not ready. continuing anyway");
+            Err(state) => {
+                warn!("Faild to get CCID, SIM card not ready. continuing anyway");
+                self.ch.set_sim_state(state);
+                None
             }
         };
 
+        let swapped = self.ch.set_iccid(iccid);
+        if iccid.is_some() {
+            sim::unlock(&mut at_client, self.config.sim_pin()).await;
+        }
+
+        // The IMSI is only readable once the SIM is ready, and is read again
+        // when connecting otherwise.
+        let imsi = match iccid {
+            Some(_) => at_client
+                .send_retry(&GetCIMI)
+                .await
+                .ok()
+                .map(|res| res.imsi),
+            None => None,
+        };
+        self.ch.update_identity(|id| id.imsi = imsi);
+        if swapped {
+            sim::reconfigure(&self.ch, imsi, |plmn| self.config.apn_lookup(plmn));
+        }
+
         at_client
             .send_retry(&SetResultCodeSelection {
                 value: ResultCodeSelection::ConnectOnly,
@@ -490,39 +1002,40 @@ where
             .await
             .ok();
 
-        #[cfg(all(
-            feature = "ucged",
-            any(
-                feature = "sara-r410m",
-                feature = "sara-r412m",
-                feature = "sara-r422",
-                feature = "lara-r6"
-            )
-        ))]
-        at_client
-            .send_retry(&SetChannelAndNetworkEnvDesc {
-                mode: if cfg!(feature = "ucged5") { 5 } else { 2 },
-            })
-            .await?;
+        // Track the PSM deep sleep, to wake the module for commands
+        if C::WAKE_FROM_PSM {
+            at_client
+                .send_retry(&SetPsmStateReporting { mode: 1 })
+                .await
+                .ok();
+        }
 
-        // Switch off UART power saving until it is integrated into this API
+        // UART power saving is only safe when DTR can wake the module before
+        // sending to it
         at_client
             .send_retry(&SetPowerSavingControl {
-                mode: PowerSavingMode::Disabled,
+                mode: if self.config.dtr_pin().is_some() {
+                    PowerSavingMode::CtrlByDtr
+                } else {
+                    PowerSavingMode::Disabled
+                },
                 timeout: None,
             })
             .await?;
 
-        if self.ch.desired_state(None) == OperationState::Initialized {
+        let module = self.ch.module().ok_or(Error::Uninitialized)?;
+        for cmd in module.init_commands() {
+            send_init_command(&mut at_client, cmd).await?;
+        }
+
+        let radio_off_cfun = match self.ch.desired_state(None) {
+            OperationState::Initialized => Some(module.radio_off_cfun()),
+            OperationState::AirplaneMode => Some(Functionality::AirplaneMode),
+            _ => None,
+        };
+        if let Some(fun) = radio_off_cfun {
             at_client
-                .send_retry(&SetModuleFunctionality {
-                    fun: self
-                        .ch
-                        .module()
-                        .ok_or(Error::Uninitialized)?
-                        .radio_off_cfun(),
-                    rst: None,
-                })
+                .send_retry(&SetModuleFunctionality { fun, rst: None })
                 .await?;
         }
 
@@ -530,8 +1043,63 @@ where
     }
 
     pub async fn run(&mut self, #[cfg(feature = "ppp")] stack: embassy_net::Stack<'_>) -> ! {
+        // Failed init attempts in a row, and when the first of them started
+        let mut init_attempts = 0;
+        let mut init_started = None;
+
         loop {
-            let _ = PwrCtrl::new(&self.ch, &mut self.config).power_down().await;
+            if self.ch.is_installing_firmware(None) {
+                // The module reboots on its own to install the firmware, so
+                // the lost CMUX session is expected. Power-cycling it now
+                // could brick it.
+                self.ch.set_link_state(state::LinkState::Down);
+                self.ch.set_operation_state_for(
+                    OperationState::PowerDown,
+                    TransitionReason::FirmwareInstall,
+                );
+
+                let deadline = self
+                    .ch
+                    .firmware_install_deadline()
+                    .unwrap_or_else(|| Instant::now() + DEFAULT_FIRMWARE_INSTALL_TIMEOUT);
+                let state = embassy_time::with_deadline(deadline, self.monitor_firmware_install())
+                    .await
+                    .unwrap_or(FirmwareInstallState::Failed(FirmwareInstallError::Timeout));
+                self.ch.set_firmware_install_state(state);
+
+                // Let the module go down for the final reboot before init
+                // probes it again
+                Timer::after(REBOOT_SETTLE).await;
+            } else {
+                let mut pwr = PwrCtrl::new(&self.ch, &mut self.config);
+                match self.reset_ladder.take_pending() {
+                    Some(action @ (RecoveryAction::SoftReset | RecoveryAction::HardReset)) => {
+                        self.ch.set_link_state(state::LinkState::Down);
+                        self.ch.set_operation_state_for(
+                            OperationState::PowerDown,
+                            TransitionReason::Recovery,
+                        );
+
+                        if action == RecoveryAction::HardReset {
+                            let _ = pwr.reset().await;
+                        } else {
+                            // The watchdog already sent `AT+CFUN=15`, let
+                            // the module go down before init probes it
+                            Timer::after(REBOOT_SETTLE).await;
+                        }
+                    }
+                    Some(RecoveryAction::PowerCycle) => {
+                        self.ch.set_operation_state_for(
+                            OperationState::PowerDown,
+                            TransitionReason::Recovery,
+                        );
+                        let _ = pwr.power_down().await;
+                    }
+                    None => {
+                        let _ = pwr.power_down().await;
+                    }
+                }
+            }
 
             // Wait for the desired state to change to anything but `PowerDown`
             poll_fn(|cx| match self.ch.desired_state(Some(cx)) {
@@ -540,9 +1108,50 @@ where
             })
             .await;
 
-            if self.init().await.is_err() {
+            let started = *init_started.get_or_insert_with(|| {
+                self.ch.set_init_error(None);
+                Instant::now()
+            });
+
+            if let Err(e) = self.init().await {
+                self.ch.record_error(&e);
+
+                // The last recovery didn't bring the module back
+                if self.reset_ladder.is_recovering() {
+                    let action = self.reset_ladder.escalate();
+                    self.ch.set_recovery_action(action);
+                }
+
+                init_attempts += 1;
+                let error = if let Error::Config(config) = e {
+                    Some(InitError::Config(config))
+                } else if C::INIT_TIMEOUT.is_some_and(|t| started.elapsed() >= t) {
+                    Some(InitError::Timeout)
+                } else if C::INIT_RETRY.is_exhausted(init_attempts) {
+                    Some(InitError::AttemptsExhausted)
+                } else {
+                    None
+                };
+
+                if let Some(error) = error {
+                    // Stay powered down until asked to try again
+                    error!(
+                        "Giving up initializing the module after {} attempts: {:?}",
+                        init_attempts, error
+                    );
+                    init_attempts = 0;
+                    init_started = None;
+                    self.ch.set_init_error(Some(error));
+                    self.ch.set_desired_state(OperationState::PowerDown);
+                } else {
+                    Timer::after(C::INIT_RETRY.delay(init_attempts - 1, Instant::now().as_ticks()))
+                        .await;
+                }
                 continue;
             }
+            init_attempts = 0;
+            init_started = None;
+            self.ch.start_session();
 
             #[cfg(feature = "ppp")]
             let ppp_fut = async {
@@ -620,14 +1229,27 @@ where
                         #[cfg(feature = "lara-r6")]
                         drain_data_channel(&mut self.data_channel).await;
 
-                        // Send AT command to enter PPP mode
-                        let mut buf = [0u8; 25];
+                        // Must be large enough to hold 'AT+UEMBPF=1,"65000-65535"\r'
+                        let mut buf = [0u8; 32];
                         let mut at_client = SimpleClient::new(
                             &mut self.data_channel,
                             atat::AtDigester::<Urc>::new(),
                             &mut buf,
                             C::AT_CONFIG,
                         );
+
+                        // Keep the traffic of the internal sockets away from
+                        // the PPP connection
+                        if let Err(e) = at_client
+                            .send(&SetEmbeddedPortFiltering {
+                                mode: C::EMBEDDED_PORT_FILTERING,
+                            })
+                            .await
+                        {
+                            warn!("Failed to set embedded port filtering: {:?}", e);
+                        }
+
+                        // Send AT command to enter PPP mode
                         let res = at_client.send(&EnterPPP { cid: C::CONTEXT_ID }).await;
 
                         if let Err(e) = res {
@@ -654,6 +1276,7 @@ where
                         // Set stack to None might not be needed, but it will just be set again
                         // when we get a new connection
                         stack.set_config_v4(embassy_net::ConfigV4::None);
+                        self.ch.set_network_config(None);
                         self.ch.set_link_state(state::LinkState::Down);
                         // Setting desired state will trigger cell_device runner to run to desired state
                         // If this is not changed when we lose connection it will
@@ -663,34 +1286,53 @@ where
                         }
                     });
 
+                    let on_ipv4_up = |ipv4: embassy_net_ppp::Ipv4Status| {
+                        debug!("Running on_ipv4_up for cellular!");
+
+                        let Some(addr) = ipv4.address else {
+                            warn!("PPP did not provide an IP address.");
+                            return;
+                        };
+                        let mut dns_servers = heapless::Vec::new();
+                        for s in ipv4.dns_servers.iter().flatten() {
+                            let _ = dns_servers.push(*s);
+                        }
+                        let config = embassy_net::StaticConfigV4 {
+                            address: embassy_net::Ipv4Cidr::new(addr, 0),
+                            gateway: None,
+                            dns_servers,
+                        };
+                        self.ch.set_network_config(Some(config.clone()));
+                        self.ch.set_link_state(state::LinkState::Up);
+                        stack.set_config_v4(embassy_net::ConfigV4::Static(config));
+                    };
+
                     info!("RUNNING PPP");
-                    let res = self
-                        .ppp_runner
-                        .as_mut()
-                        .unwrap()
-                        .run(&mut self.data_channel, C::PPP_CONFIG, |ipv4| {
-                            debug!("Running on_ipv4_up for cellular!");
-
-                            let Some(addr) = ipv4.address else {
-                                warn!("PPP did not provide an IP address.");
-                                return;
-                            };
-                            let mut dns_servers = heapless::Vec::new();
-                            for s in ipv4.dns_servers.iter().flatten() {
-                                let _ = dns_servers.push(*s);
+                    let ppp_runner = self.ppp_runner.as_mut().unwrap();
+                    match C::PPP_LINK_SUPERVISION {
+                        Some(supervision) => {
+                            let mut io = SupervisedIo::new(&mut self.data_channel, supervision);
+                            let res = ppp_runner.run(&mut io, C::PPP_CONFIG, on_ipv4_up).await;
+                            info!("ppp failed: {:?}", res);
+
+                            // Same as the network deactivating the context
+                            #[cfg(not(feature = "use-upsd-context-activation"))]
+                            if io.is_link_dead()
+                                && self.ch.get_profile_state()
+                                    == crate::registration::ProfileState::ShouldBeUp
+                            {
+                                self.ch.set_profile_state(
+                                    crate::registration::ProfileState::RequiresReactivation,
+                                );
                             }
-                            let config =
-                                embassy_net::ConfigV4::Static(embassy_net::StaticConfigV4 {
-                                    address: embassy_net::Ipv4Cidr::new(addr, 0),
-                                    gateway: None,
-                                    dns_servers,
-                                });
-                            self.ch.set_link_state(state::LinkState::Up);
-                            stack.set_config_v4(config);
-                        })
-                        .await;
-
-                    info!("ppp failed: {:?}", res);
+                        }
+                        None => {
+                            let res = ppp_runner
+                                .run(&mut self.data_channel, C::PPP_CONFIG, on_ipv4_up)
+                                .await;
+                            info!("ppp failed: {:?}", res);
+                        }
+                    }
 
                     drop(ondrop);
                     self.data_channel.clear_hangup_detection();
@@ -709,6 +1351,19 @@ where
                         .send(&heapless::String::<5>::try_from("+++\r\n").unwrap())
                         .await;
 
+                    // Without a dial-up connection, the filtering is moot
+                    if !matches!(
+                        C::EMBEDDED_PORT_FILTERING,
+                        EmbeddedPortFilteringMode::Disable
+                    ) {
+                        at_client
+                            .send(&SetEmbeddedPortFiltering {
+                                mode: EmbeddedPortFilteringMode::Disable,
+                            })
+                            .await
+                            .ok();
+                    }
+
                     // Must be large enough to hold CreateSocket cmd
                     #[cfg(feature = "lara-r6")]
                     if let Some(socket_id) = open_socket_id {
@@ -773,7 +1428,10 @@ where
                     // when the MUX is opened successfully, instead of waiting a fixed time
                     Timer::after_secs(3).await;
                     debug!("DONE waiting 3 sec set operation state to initialized");
-                    self.ch.set_operation_state(OperationState::Initialized);
+                    self.ch.set_operation_state_for(
+                        OperationState::Initialized,
+                        TransitionReason::Initialized,
+                    );
                 };
 
                 join(
@@ -786,57 +1444,195 @@ where
             let device_fut = async {
                 let (at_rx, at_tx, _) = &mut self.at_channel;
 
-                let at_client = ProxyClient::new(self.req_slot.sender(), self.res_slot);
+                let at_client = ProxyClient::new(
+                    self.req_slot.sender(),
+                    self.res_slot,
+                    self.at_lock,
+                    self.ch.diagnostics(),
+                );
                 let mut cell_device = NetDevice::<C, _>::new(&self.ch, &at_client);
 
-                let mut urc_handler = UrcHandler::new(&self.ch, self.urc_channel);
+                let mut urc_handler = UrcHandler::new(&self.ch, self.urc_channel, C::CONTEXT_ID);
+
+                let reset_ladder = &mut self.reset_ladder;
+                let watchdog_fut = async {
+                    loop {
+                        Timer::after(reset_ladder.interval(at_client.timeouts())).await;
+                        // The module does not answer in PSM
+                        if self.ch.is_sleeping() {
+                            continue;
+                        }
+                        if let Some(action) = watchdog::heartbeat(&at_client, reset_ladder).await {
+                            self.ch.set_recovery_action(action);
+                            break;
+                        }
+                    }
+                };
 
-                select3(
-                    at_bridge((at_rx, at_tx), self.req_slot, &mut self.ingress),
+                // A new SIM needs the module re-initialized, to unlock it and
+                // re-apply the configuration
+                let sim_fut = async {
+                    let mut client = &at_client;
+                    loop {
+                        // Poll for modules that don't report the insertion
+                        let recheck = async {
+                            match self.ch.sim_state(None) {
+                                SimState::Absent => Timer::after(C::SIM_RECHECK_INTERVAL).await,
+                                _ => core::future::pending().await,
+                            }
+                        };
+                        select(self.ch.wait_sim_check(), recheck).await;
+
+                        // The SIM is busy for a while after insertion
+                        let mut backoff = Duration::from_millis(250);
+                        for _ in 0..5 {
+                            Timer::after(backoff).await;
+                            match sim::check(&mut client, self.ch.last_iccid()).await {
+                                SimCheck::Absent => {
+                                    self.ch.set_sim_state(SimState::Absent);
+                                    break;
+                                }
+                                SimCheck::NotReady => backoff = backoff * 2,
+                                SimCheck::Unchanged => {
+                                    self.ch.set_sim_state(SimState::Present);
+                                    break;
+                                }
+                                SimCheck::Changed(iccid) => {
+                                    info!("SIM {} inserted, re-initializing", iccid);
+                                    self.ch.set_sim_state(SimState::Present);
+                                    self.ch.clear_attach_rejection();
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                };
+
+                // Sockets dropped without being closed would otherwise take up
+                // the few the module has, until it is reset. After an overflow
+                // of the URC channel, a lost +UUSORD would stall the socket.
+                let close_fut = async {
+                    #[cfg(feature = "internal-network-stack")]
+                    loop {
+                        match select(self.ch.wait_socket_close(), self.ch.wait_socket_poll()).await
+                        {
+                            Either::First(handle) => {
+                                let closed = self.ch.wait_awake().await.is_ok()
+                                    && (&at_client)
+                                        .send(&CloseSocket { socket: handle.0 })
+                                        .await
+                                        .is_ok();
+                                if !closed {
+                                    // Eg. as the context is down, which takes
+                                    // the socket along
+                                    warn!("[{}] Failed to close dropped socket", handle);
+                                }
+                                self.ch.unregister_socket(handle);
+                            }
+                            Either::Second(handles) => {
+                                for handle in handles {
+                                    if self.ch.wait_awake().await.is_err() {
+                                        break;
+                                    }
+                                    match socket_ingress::available(&mut &at_client, handle).await {
+                                        Ok(available) => {
+                                            self.ch.set_socket_available(handle, Some(available))
+                                        }
+                                        Err(e) => {
+                                            warn!("[{}] Failed to poll socket: {:?}", handle, e)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    #[cfg(not(feature = "internal-network-stack"))]
+                    core::future::pending::<()>().await
+                };
+
+                let res = select4(
+                    at_bridge(
+                        (at_rx, at_tx),
+                        self.req_slot,
+                        self.res_slot,
+                        &mut self.ingress,
+                        &self.ch,
+                        &mut self.config,
+                        #[cfg(feature = "at-trace")]
+                        self.at_trace.as_ref(),
+                    ),
                     urc_handler.run(),
                     cell_device.run(),
+                    select(watchdog_fut, select(sim_fut, close_fut)),
                 )
-                .await
+                .await;
+
+                if let Either4::Third(Err(e)) = &res {
+                    self.ch.record_error(e);
+                }
             };
 
+            let firmware_install_fut =
+                poll_fn(|cx| match self.ch.is_installing_firmware(Some(cx)) {
+                    true => Poll::Ready(()),
+                    false => Poll::Pending,
+                });
+
             #[cfg(feature = "ppp")]
-            match select3(mux_fut, ppp_fut, device_fut).await {
-                Either3::First(_) => {
+            match select4(mux_fut, ppp_fut, device_fut, firmware_install_fut).await {
+                Either4::First(_) => {
                     warn!("Breaking to reboot modem from multiplexer");
                 }
-                Either3::Second(_) => {
+                Either4::Second(_) => {
                     warn!("Breaking to reboot modem from PPP");
                 }
-                Either3::Third(_) => {
+                Either4::Third(_) => {
                     warn!("Breaking to reboot modem from network runner");
                 }
+                Either4::Fourth(_) => {
+                    info!("Breaking to monitor firmware installation");
+                }
             }
 
+            // Direct link mode takes the data channel, that only PPP uses otherwise
+            #[cfg(all(feature = "internal-network-stack", not(feature = "ppp")))]
+            let device_fut = select(device_fut, async {
+                match self.direct_link.as_ref() {
+                    Some(direct_link) => {
+                        direct_link.run(&mut self.data_channel, C::AT_CONFIG).await
+                    }
+                    None => core::future::pending().await,
+                }
+            });
+
             #[cfg(not(feature = "ppp"))]
-            match embassy_futures::select::select(mux_fut, device_fut).await {
-                embassy_futures::select::Either::First(_) => {
+            match select3(mux_fut, device_fut, firmware_install_fut).await {
+                Either3::First(_) => {
                     warn!("Breaking to reboot modem from multiplexer");
                 }
-                embassy_futures::select::Either::Second(_) => {
+                Either3::Second(_) => {
                     warn!("Breaking to reboot modem from network runner");
                 }
+                Either3::Third(_) => {
+                    info!("Breaking to monitor firmware installation");
+                }
             }
         }
     }
 }
 
-struct OnDrop<F: FnOnce()> {
+pub(crate) struct OnDrop<F: FnOnce()> {
     f: core::mem::MaybeUninit<F>,
 }
 
 impl<F: FnOnce()> OnDrop<F> {
-    fn new(f: F) -> Self {
+    pub(crate) fn new(f: F) -> Self {
         Self {
             f: core::mem::MaybeUninit::new(f),
         }
     }
 
-    fn defuse(self) {
+    pub(crate) fn defuse(self) {
         core::mem::forget(self)
     }
 }
@@ -846,3 +1642,18 @@ impl<F: FnOnce()> Drop for OnDrop<F> {
         unsafe { self.f.as_ptr().read()() }
     }
 }
+
+/// Send a module specific [`InitCommand`].
+async fn send_init_command<A: AtatClient>(
+    at_client: &mut A,
+    cmd: &InitCommand,
+) -> Result<(), atat::Error> {
+    match *cmd {
+        InitCommand::CellEnvironmentReporting { mode } => {
+            at_client
+                .send_retry(&SetCellEnvironmentReporting { mode })
+                .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! SIM card handling. The SIM may be swapped while the module is powered
+//! down, or while it is running, after which the cached identity is stale and
+//! the configuration of the old SIM may not fit the new subscription. The
+//! module reports SIM insertion with `+CIEV: 12,1`, upon which the runner
+//! checks the ICCID, and re-initializes the module for a new SIM.
+//!
+//! Devices may also ship without a SIM, to be inserted later. The module
+//! then answers SIM commands with `+CME ERROR: 10`, and the runner idles
+//! without attempting to attach, re-checking every
+//! `CellularConfig::SIM_RECHECK_INTERVAL` for modules that do not report the
+//! insertion.
+
+use atat::asynch::AtatClient;
+
+use crate::command::{
+    device_lock::{
+        responses::PinStatus, types::PinStatusCode, GetPinCounter, GetPinStatus, SetPin,
+    },
+    general::GetCCID,
+};
+use crate::config::Apn;
+
+use super::state;
+
+/// Countries with three digit MNCs. The length of the MNC is not part of the
+/// IMSI, so it has to be known from the MCC.
+const THREE_DIGIT_MNC_MCCS: [u16; 24] = [
+    302, 310, 311, 312, 313, 314, 315, 316, 334, 338, 342, 344, 346, 348, 354, 356, 358, 360, 365,
+    376, 405, 708, 722, 732,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SimCheck {
+    /// No SIM is inserted
+    Absent,
+    /// The SIM is still busy after insertion
+    NotReady,
+    Unchanged,
+    Changed(u128),
+}
+
+/// Check whether another SIM than `last` is inserted.
+pub(crate) async fn check<A: AtatClient>(at_client: &mut A, last: Option<u128>) -> SimCheck {
+    match at_client.send(&GetCCID).await {
+        Ok(res) if Some(res.ccid) == last => SimCheck::Unchanged,
+        Ok(res) => SimCheck::Changed(res.ccid),
+        Err(e) if is_absent(&e) => SimCheck::Absent,
+        // Not every module tells a missing SIM when reading the ICCID
+        Err(_) => match at_client.send(&GetPinStatus).await {
+            Err(e) if is_absent(&e) => SimCheck::Absent,
+            _ => SimCheck::NotReady,
+        },
+    }
+}
+
+/// Whether the module answered a SIM command with `+CME ERROR: 10`, SIM not
+/// inserted.
+pub(crate) fn is_absent(error: &atat::Error) -> bool {
+    matches!(error, atat::Error::CmeError(atat::CmeError::SimNotInserted))
+}
+
+/// Unlock the SIM if it is PIN protected. Failing to do so is only logged,
+/// so the application can still unlock it through `Control`. Registration
+/// reports the SIM lock meanwhile.
+pub(crate) async fn unlock<A: AtatClient>(at_client: &mut A, pin: Option<&str>) {
+    match at_client.send_retry(&GetPinStatus).await {
+        Ok(PinStatus {
+            code: PinStatusCode::SimPin,
+        }) => match pin {
+            Some(pin) => {
+                let attempts = at_client
+                    .send_retry(&GetPinCounter)
+                    .await
+                    .map(|c| c.pin_attempts)
+                    .unwrap_or(0);
+
+                // Only ever use the first attempt, a wrong PIN must not be
+                // retried into PUK territory.
+                if attempts < 3 {
+                    error!(
+                        "SIM PIN required, but not entering it with {} attempts left",
+                        attempts
+                    );
+                } else if let Err(e) = at_client.send(&SetPin { pin }).await {
+                    error!("Failed to unlock SIM with the configured PIN: {:?}", e);
+                } else {
+                    info!("SIM unlocked");
+                }
+            }
+            None => error!("SIM PIN required, but no PIN configured"),
+        },
+        Ok(PinStatus {
+            code: PinStatusCode::SimPuk,
+        }) => error!("SIM is PUK locked"),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to read SIM PIN status: {:?}", e),
+    }
+}
+
+/// Re-apply the configuration that depends on the subscription, after a SIM
+/// swap. The APN is looked up for the home network of the new SIM, keeping
+/// the configured APNs if the lookup returns [`Apn::None`].
+pub(crate) fn reconfigure(
+    ch: &state::Runner<'_>,
+    imsi: Option<u64>,
+    apn_lookup: impl FnOnce((u16, u16)) -> Apn,
+) {
+    // The authentication type found for the old APN may not fit the new one
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    ch.set_auth_type(None);
+
+    let Some(imsi) = imsi else {
+        warn!("IMSI of the new SIM unknown, keeping the APN configuration");
+        return;
+    };
+
+    match apn_lookup(home_plmn(imsi)) {
+        Apn::None => {}
+        apn => ch.set_apn_config(apn),
+    }
+}
+
+/// MCC and MNC of the network that issued the 15 digit `imsi`.
+pub(crate) fn home_plmn(imsi: u64) -> (u16, u16) {
+    let mcc = (imsi / 1_000_000_000_000) as u16;
+    let mnc = if THREE_DIGIT_MNC_MCCS.contains(&mcc) {
+        (imsi / 1_000_000_000) % 1000
+    } else {
+        (imsi / 10_000_000_000) % 100
+    };
+    (mcc, mnc as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asynch::modem_sim::{Fixture, Step, OK};
+
+    use super::*;
+
+    const ICCID: u128 = 89_462_032_210_002_634_991;
+
+    const CCID: Step<'static> = Step::Command {
+        cmd: b"AT+CCID",
+        response: b"\r\n+CCID: 89462032210002634991\r\n\r\nOK\r\n",
+    };
+
+    const SIM_NOT_INSERTED: &[u8] = b"\r\n+CME ERROR: 10\r\n";
+
+    const ERROR: &[u8] = b"\r\nERROR\r\n";
+
+    #[test]
+    fn detect_sim_change() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        assert_eq!(
+            io.play(&mut sim, &[CCID], check(&mut &client, Some(ICCID))),
+            SimCheck::Unchanged
+        );
+        assert_eq!(
+            io.play(&mut sim, &[CCID], check(&mut &client, Some(ICCID + 1))),
+            SimCheck::Changed(ICCID)
+        );
+        // The first SIM inserted is a change as well, it still has to be set up
+        assert_eq!(
+            io.play(&mut sim, &[CCID], check(&mut &client, None)),
+            SimCheck::Changed(ICCID)
+        );
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+CCID",
+                response: ERROR,
+            },
+            Step::Command {
+                cmd: b"AT+CPIN?",
+                response: ERROR,
+            },
+        ];
+        assert_eq!(
+            io.play(&mut sim, &script, check(&mut &client, Some(ICCID))),
+            SimCheck::NotReady
+        );
+    }
+
+    #[test]
+    fn detect_missing_sim() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        let script = [Step::Command {
+            cmd: b"AT+CCID",
+            response: SIM_NOT_INSERTED,
+        }];
+        assert_eq!(
+            io.play(&mut sim, &script, check(&mut &client, None)),
+            SimCheck::Absent
+        );
+
+        // Only +CPIN? tells the SIM is missing
+        let script = [
+            Step::Command {
+                cmd: b"AT+CCID",
+                response: ERROR,
+            },
+            Step::Command {
+                cmd: b"AT+CPIN?",
+                response: SIM_NOT_INSERTED,
+            },
+        ];
+        assert_eq!(
+            io.play(&mut sim, &script, check(&mut &client, Some(ICCID))),
+            SimCheck::Absent
+        );
+    }
+
+    #[test]
+    fn swap_clears_identity() {
+        let mut state = state::State::new();
+        let ch = state::Runner::new(&mut state);
+
+        assert!(!ch.set_iccid(Some(ICCID)));
+        ch.update_identity(|id| id.imsi = Some(240_011_234_567_890));
+
+        // Removing and reinserting the same SIM is no swap
+        assert!(!ch.set_iccid(None));
+        assert!(!ch.set_iccid(Some(ICCID)));
+        assert_eq!(ch.sim_changes(None), 0);
+        assert_eq!(ch.identity().imsi, Some(240_011_234_567_890));
+
+        assert!(ch.set_iccid(Some(ICCID + 1)));
+        assert_eq!(ch.sim_changes(None), 1);
+        assert_eq!(ch.identity().iccid, Some(ICCID + 1));
+        assert_eq!(ch.identity().imsi, None);
+    }
+
+    #[test]
+    fn unlock_with_pin() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+CPIN?",
+                response: b"\r\n+CPIN: SIM PIN\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+UPINCNT",
+                response: b"\r\n+UPINCNT: 3,3,10,10\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+CPIN=\"1234\"",
+                response: OK,
+            },
+        ];
+        io.play(&mut sim, &script, unlock(&mut &client, Some("1234")));
+
+        // Never use up the last attempts
+        let script = [
+            Step::Command {
+                cmd: b"AT+CPIN?",
+                response: b"\r\n+CPIN: SIM PIN\r\n\r\nOK\r\n",
+            },
+            Step::Command {
+                cmd: b"AT+UPINCNT",
+                response: b"\r\n+UPINCNT: 2,3,10,10\r\n\r\nOK\r\n",
+            },
+        ];
+        io.play(&mut sim, &script, unlock(&mut &client, Some("1234")));
+
+        let script = [Step::Command {
+            cmd: b"AT+CPIN?",
+            response: b"\r\n+CPIN: READY\r\n\r\nOK\r\n",
+        }];
+        io.play(&mut sim, &script, unlock(&mut &client, Some("1234")));
+    }
+
+    #[test]
+    fn reconfigure_apn() {
+        let mut state = state::State::new();
+        let ch = state::Runner::new(&mut state);
+
+        let apn = |name: &str| Apn::Given {
+            name: heapless::String::try_from(name).unwrap(),
+            username: None,
+            password: None,
+        };
+        ch.set_apn_config(apn("old"));
+
+        reconfigure(&ch, Some(240_011_234_567_890), |plmn| {
+            assert_eq!(plmn, (240, 1));
+            apn("new")
+        });
+        assert!(ch.get_apn_config().same_as(&apn("new")));
+
+        // Without a known APN for the network, the configured one is kept
+        reconfigure(&ch, Some(310_410_123_456_789), |plmn| {
+            assert_eq!(plmn, (310, 410));
+            Apn::None
+        });
+        assert!(ch.get_apn_config().same_as(&apn("new")));
+    }
+}
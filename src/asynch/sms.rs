@@ -0,0 +1,88 @@
+use heapless::Vec;
+
+use crate::{
+    command::{
+        hex,
+        sms::{
+            pdu::{Payload, SmsDeliver, SmsSubmit, MAX_SUBMIT_HEX_LEN, MAX_SUBMIT_LEN},
+            responses::MessagePdu,
+            types::MessageFormat,
+            PrepareSendMessagePdu, ReadMessage, SendMessagePdu, SetMessageFormat,
+        },
+    },
+    error::Error,
+};
+
+use super::control::Control;
+
+/// Short messages in PDU mode, obtained through [`Control::sms`].
+pub struct SmsService<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> SmsService<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self { control }
+    }
+
+    /// Send `payload` to `destination` as a single message, returning the
+    /// message reference. Text that is not in the GSM 7 bit alphabet is sent
+    /// as UCS2.
+    pub async fn send(&self, destination: &str, payload: Payload<'_>) -> Result<u8, Error> {
+        self.send_submit(&SmsSubmit {
+            destination,
+            payload,
+            concatenation: None,
+        })
+        .await
+    }
+
+    /// Send `submit`, eg. a part of a concatenated message, through the
+    /// service centre of the SIM. Returns the message reference.
+    pub async fn send_submit(&self, submit: &SmsSubmit<'_>) -> Result<u8, Error> {
+        let tpdu = submit.encode().map_err(Error::Pdu)?;
+
+        let mut pdu = Vec::<u8, MAX_SUBMIT_HEX_LEN>::new();
+        // No SMSC address, use the one of the SIM
+        pdu.extend_from_slice(b"00").ok();
+        pdu.extend_from_slice(
+            hex::encode::<{ 2 * MAX_SUBMIT_LEN }>(&tpdu)
+                .ok_or(Error::Overflow)?
+                .as_bytes(),
+        )
+        .map_err(|_| Error::Overflow)?;
+
+        self.set_pdu_mode().await?;
+        let mut at = self.control.exclusive().await?;
+        at.send(&PrepareSendMessagePdu { length: tpdu.len() })
+            .await?;
+        let res = at
+            .send(&SendMessagePdu {
+                pdu: atat::serde_bytes::Bytes::new(&pdu),
+            })
+            .await?;
+        Ok(res.reference)
+    }
+
+    /// Read and decode the received message at `index` of the message
+    /// storage.
+    pub async fn read(&self, index: u16) -> Result<SmsDeliver, Error> {
+        self.read_pdu(index).await?.decode().map_err(Error::Pdu)
+    }
+
+    /// Read the message at `index` of the message storage, without decoding
+    /// its PDU.
+    pub async fn read_pdu(&self, index: u16) -> Result<MessagePdu, Error> {
+        self.set_pdu_mode().await?;
+        Ok(self.control.send(&ReadMessage { index }).await?.message)
+    }
+
+    async fn set_pdu_mode(&self) -> Result<(), Error> {
+        self.control
+            .send(&SetMessageFormat {
+                mode: MessageFormat::Pdu,
+            })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,596 @@
+use core::{cell::Cell, net::SocketAddr};
+
+use embassy_time::{with_timeout, Duration, Instant, Timer};
+use ublox_sockets::SocketHandle;
+
+use crate::{
+    command::ip_transport_layer::types::{
+        RemoteAddr, SocketErrorKind, SocketOption, SocketProtocol,
+    },
+    config::{MAX_SOCKETS, SOCKET_FLUSH_TIMEOUT, SOCKET_POLL_INTERVAL, SOCKET_PROBE_INTERVAL},
+    error::Error,
+};
+
+use super::{
+    control::{Control, WRITE_RETRY_DELAY},
+    runner::OnDrop,
+};
+
+/// How often [`TcpSocket::flush`] asks the module for the data not
+/// acknowledged yet.
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// TCP socket of the internal stack of the modem, obtained through
+/// [`Control::tcp_socket`].
+///
+/// The socket on the module is only created by [`Self::connect`]. A socket
+/// dropped without [`Self::close`] is closed by the runner in the background,
+/// as the module only has a few of them.
+pub struct TcpSocket<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    local_port: Option<u16>,
+    poll_interval: Duration,
+    probe_interval: Duration,
+    flush_timeout: Duration,
+    /// Last time the module told the connection alive
+    last_probe: Option<Instant>,
+    handle: Option<SocketHandle>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> TcpSocket<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self {
+            control,
+            local_port: None,
+            poll_interval: SOCKET_POLL_INTERVAL,
+            probe_interval: SOCKET_PROBE_INTERVAL,
+            flush_timeout: SOCKET_FLUSH_TIMEOUT,
+            last_probe: None,
+            handle: None,
+        }
+    }
+
+    /// Bind the socket to the local port `port` when connecting, instead of
+    /// one picked by the module. Connecting fails with `AddrInUse` if another
+    /// socket is bound to it, or the module rejects the bind, see
+    /// [`Control::create_socket`].
+    pub fn local_port(mut self, port: u16) -> Self {
+        self.local_port = Some(port);
+        self
+    }
+
+    /// Ask the module for data every `interval` while waiting in
+    /// [`Self::read`], rather than every [`SOCKET_POLL_INTERVAL`]. Data is
+    /// read as soon as +UUSORD announces it either way.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Ask the module for the TCP status in [`Self::is_connected`] at most
+    /// every `interval`, rather than every [`SOCKET_PROBE_INTERVAL`].
+    pub fn probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+
+    /// Wait up to `timeout` in [`Self::close`] for the data written to be
+    /// acknowledged, rather than [`SOCKET_FLUSH_TIMEOUT`].
+    pub fn flush_timeout(mut self, timeout: Duration) -> Self {
+        self.flush_timeout = timeout;
+        self
+    }
+
+    /// Handle of the socket on the module, once connected.
+    pub fn handle(&self) -> Option<SocketHandle> {
+        self.handle
+    }
+
+    /// Connect to `remote` on `port`, see [`Control::connect_socket`]. A
+    /// socket connected already is closed first, or by the runner if that
+    /// fails.
+    pub async fn connect(&mut self, remote: RemoteAddr, port: u16) -> Result<(), Error> {
+        let _ = self.close_handle().await;
+        let handle = self
+            .control
+            .connect_tcp(self.local_port, remote, port)
+            .await?;
+        self.handle = Some(handle);
+        self.last_probe = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Like [`Self::connect`], giving up after `timeout` rather than the
+    /// minutes the module may take. The half-open socket is then closed by the
+    /// runner, and this one is left unconnected to connect again.
+    ///
+    /// Dropping a connect future is just as safe. The module still answers
+    /// the +USOCO in progress, which the next command waits for rather than
+    /// take the answer for its own.
+    pub async fn connect_with_timeout(
+        &mut self,
+        remote: RemoteAddr,
+        port: u16,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        with_timeout(timeout, self.connect(remote, port)).await?
+    }
+
+    /// Read received data into `buf`, see [`Control::read_socket`], waiting
+    /// for some to arrive. Returns 0 once the socket was closed by the peer.
+    ///
+    /// The wait ends with the +UUSORD announcing data, or otherwise after the
+    /// [`Self::poll_interval`] to ask the module again.
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        loop {
+            let read = self.control.read_socket(handle, buf).await?;
+            if read > 0 || buf.is_empty() || self.control.is_socket_closed(handle) {
+                return Ok(read);
+            }
+            let _ = with_timeout(
+                self.poll_interval,
+                self.control.state_ch.wait_socket_readable(handle),
+            )
+            .await;
+        }
+    }
+
+    /// Whether the socket was closed by the peer, see
+    /// [`Control::is_socket_closed`].
+    pub fn is_closed(&self) -> bool {
+        self.handle
+            .is_some_and(|handle| self.control.is_socket_closed(handle))
+    }
+
+    /// Whether the socket is connected. A connection that died on the network
+    /// side without the peer closing it, eg. as a NAT dropped it, is only
+    /// noticed by asking the module for the TCP status with
+    /// [`Control::socket_state`], which is done at most every
+    /// [`Self::probe_interval`]. A connection the module reports gone is then
+    /// closed, and this socket left unconnected to connect again.
+    pub async fn is_connected(&mut self) -> Result<bool, Error> {
+        let Some(handle) = self.handle else {
+            return Ok(false);
+        };
+        if self.control.is_socket_closed(handle) {
+            return Ok(false);
+        }
+        if self
+            .last_probe
+            .is_some_and(|at| at.elapsed() < self.probe_interval)
+        {
+            return Ok(true);
+        }
+
+        let status = self.control.socket_state(handle).await?;
+        if status.is_closed() {
+            warn!("[{}] Connection gone: {:?}", handle, status);
+            let _ = self.close_handle().await;
+            return Ok(false);
+        }
+        self.last_probe = Some(Instant::now());
+        Ok(true)
+    }
+
+    /// Enable TCP keepalive, sending the first probe once the connection was
+    /// idle for `idle`, or disable it with `None`. The options go with the
+    /// socket on the module, so this fails with
+    /// [`Error::InvalidStateTransition`] before [`Self::connect`].
+    pub async fn set_keepalive(&mut self, idle: Option<Duration>) -> Result<(), Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        match idle {
+            Some(idle) => {
+                let idle_ms = u32::try_from(idle.as_millis()).unwrap_or(u32::MAX);
+                self.control
+                    .set_socket_option(handle, SocketOption::KeepIdle(idle_ms))
+                    .await?;
+                self.control
+                    .set_socket_option(handle, SocketOption::KeepAlive(1))
+                    .await
+            }
+            None => {
+                self.control
+                    .set_socket_option(handle, SocketOption::KeepAlive(0))
+                    .await
+            }
+        }
+    }
+
+    /// Linger on close for up to `timeout` while data is left to send, or
+    /// disable lingering with `None`. Fails with
+    /// [`Error::InvalidStateTransition`] before [`Self::connect`], see
+    /// [`Self::set_keepalive`].
+    pub async fn set_linger(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        let option = match timeout {
+            Some(timeout) => {
+                SocketOption::Linger(1, u16::try_from(timeout.as_secs()).unwrap_or(u16::MAX))
+            }
+            None => SocketOption::Linger(0, 0),
+        };
+        self.control.set_socket_option(handle, option).await
+    }
+
+    /// Write `data`, see [`Control::write_socket_data`].
+    pub async fn write(&mut self, data: &[u8]) -> Result<usize, Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        self.control.write_socket_data(handle, data).await
+    }
+
+    /// Wait until the peer acknowledged all the data written, asking the
+    /// module with [`Control::socket_unacknowledged`]. Fails with
+    /// [`SocketErrorKind::ConnectionReset`] if the socket was closed by the
+    /// peer before.
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        loop {
+            if self.control.is_socket_closed(handle) {
+                return Err(Error::Socket(SocketErrorKind::ConnectionReset));
+            }
+            if self.control.socket_unacknowledged(handle).await? == 0 {
+                return Ok(());
+            }
+            Timer::after(FLUSH_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Close the socket with +USOCL, once the data written is acknowledged by
+    /// the peer, see [`Self::flush`]. Waiting for it is given up after the
+    /// [`Self::flush_timeout`], and the socket closed nonetheless, failing with
+    /// the error of the flush, so that data the peer may have missed is not
+    /// gone unnoticed.
+    pub async fn close(mut self) -> Result<(), Error> {
+        let flushed = match self.handle {
+            Some(handle) if !self.control.is_socket_closed(handle) => {
+                match with_timeout(self.flush_timeout, self.flush()).await {
+                    Ok(res) => res,
+                    Err(e) => Err(e.into()),
+                }
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = &flushed {
+            warn!("Closing without flushing: {:?}", e);
+        }
+        self.close_handle().await?;
+        flushed
+    }
+
+    async fn close_handle(&mut self) -> Result<(), Error> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        // The runner tries again if this fails or is cancelled, and forgets
+        // the socket either way
+        let control = self.control;
+        let retry = OnDrop::new(|| control.state_ch.defer_socket_close(handle));
+        let res = control.close_socket(handle).await;
+        if res.is_ok() {
+            retry.defuse();
+        }
+        res
+    }
+}
+
+impl<const INGRESS_BUF_SIZE: usize> Drop for TcpSocket<'_, '_, INGRESS_BUF_SIZE> {
+    fn drop(&mut self) {
+        // Never connected, there is nothing to close on the module
+        if let Some(handle) = self.handle.take() {
+            self.control.state_ch.defer_socket_close(handle);
+        }
+    }
+}
+
+/// UDP socket of the internal stack of the modem, obtained through
+/// [`Control::udp_socket`].
+///
+/// Each [`Self::send_to`] is sent as one datagram, and each
+/// [`Self::recv_from`] returns one datagram, along with its sender. The
+/// socket on the module is only created by [`Self::bind`], and closed by the
+/// runner in the background when dropped without [`Self::close`].
+pub struct UdpSocket<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    poll_interval: Duration,
+    handle: Option<SocketHandle>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> UdpSocket<'c, 'a, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self {
+            control,
+            poll_interval: SOCKET_POLL_INTERVAL,
+            handle: None,
+        }
+    }
+
+    /// Ask the module for datagrams every `interval` while waiting in
+    /// [`Self::recv_from`], rather than every [`SOCKET_POLL_INTERVAL`].
+    /// Datagrams are read as soon as +UUSORF announces them either way.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Handle of the socket on the module, once bound.
+    pub fn handle(&self) -> Option<SocketHandle> {
+        self.handle
+    }
+
+    /// Create the socket on the module, bound to `local_port` if given, see
+    /// [`Control::create_socket`]. A socket bound already is closed first, or
+    /// by the runner if that fails.
+    pub async fn bind(&mut self, local_port: Option<u16>) -> Result<(), Error> {
+        let _ = self.close_handle().await;
+        let handle = self
+            .control
+            .create_socket(SocketProtocol::UDP, local_port, None)
+            .await?;
+        self.handle = Some(handle);
+        Ok(())
+    }
+
+    /// Send `data` as a single datagram to `remote`, see
+    /// [`Control::send_socket_data_to`]. A datagram the module only takes part
+    /// of fails with `NoBuffers` rather than being sent cut short.
+    pub async fn send_to(&mut self, remote: SocketAddr, data: &[u8]) -> Result<(), Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        let sent = self
+            .control
+            .send_socket_data_to(handle, remote.ip().into(), remote.port(), data)
+            .await?;
+        if sent < data.len() {
+            return Err(Error::Socket(SocketErrorKind::NoBuffers));
+        }
+        Ok(())
+    }
+
+    /// Receive the next datagram into `buf`, returning its length and sender,
+    /// see [`Control::recv_socket_data_from`]. A datagram longer than `buf` is
+    /// cut short.
+    ///
+    /// The wait ends with the +UUSORF announcing a datagram, or otherwise after
+    /// the [`Self::poll_interval`] to ask the module again. Fails with
+    /// `ConnectionReset` once the socket was lost in a reset of the module.
+    pub async fn recv_from(&mut self, buf: &mut [u8]) -> Result<(usize, SocketAddr), Error> {
+        let handle = self.handle.ok_or(Error::InvalidStateTransition)?;
+        loop {
+            if self.control.is_socket_closed(handle) {
+                return Err(Error::Socket(SocketErrorKind::ConnectionReset));
+            }
+            let available = match self.control.state_ch.socket_available(handle) {
+                Some(available) => available,
+                None => self.control.udp_socket_available(handle).await?,
+            };
+            if available > 0 {
+                return self.control.recv_socket_data_from(handle, buf).await;
+            }
+            let _ = with_timeout(
+                self.poll_interval,
+                self.control.state_ch.wait_socket_readable(handle),
+            )
+            .await;
+            // Ask the module again, unless +UUSORF told already
+            if self.control.state_ch.socket_available(handle) == Some(0) {
+                self.control.state_ch.set_socket_available(handle, None);
+            }
+        }
+    }
+
+    /// Close the socket on the module, see [`Control::close_socket`].
+    pub async fn close(mut self) -> Result<(), Error> {
+        self.close_handle().await
+    }
+
+    async fn close_handle(&mut self) -> Result<(), Error> {
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        // The runner tries again if this fails or is cancelled
+        let control = self.control;
+        let retry = OnDrop::new(|| control.state_ch.defer_socket_close(handle));
+        let res = control.close_socket(handle).await;
+        if res.is_ok() {
+            retry.defuse();
+        }
+        res
+    }
+}
+
+impl<const INGRESS_BUF_SIZE: usize> Drop for UdpSocket<'_, '_, INGRESS_BUF_SIZE> {
+    fn drop(&mut self) {
+        // Never bound, there is nothing to close on the module
+        if let Some(handle) = self.handle.take() {
+            self.control.state_ch.defer_socket_close(handle);
+        }
+    }
+}
+
+/// [`TcpConnect`](embedded_nal_async::TcpConnect) of the internal stack of
+/// the modem, with up to `N` connections at once, obtained through
+/// [`Control::tcp_client`]. Along with the [`Dns`](embedded_nal_async::Dns) of
+/// [`Control`], this is what crates like `reqwless` take:
+///
+/// ```ignore
+/// let client = control.tcp_client::<2>();
+/// let mut http = reqwless::client::HttpClient::new(&client, &control);
+/// ```
+pub struct TcpClient<'c, 'a, const N: usize, const INGRESS_BUF_SIZE: usize> {
+    control: &'c Control<'a, INGRESS_BUF_SIZE>,
+    connections: Cell<usize>,
+}
+
+impl<'c, 'a, const N: usize, const INGRESS_BUF_SIZE: usize> TcpClient<'c, 'a, N, INGRESS_BUF_SIZE> {
+    pub(crate) fn new(control: &'c Control<'a, INGRESS_BUF_SIZE>) -> Self {
+        Self {
+            control,
+            connections: Cell::new(0),
+        }
+    }
+}
+
+impl<'a, const N: usize, const INGRESS_BUF_SIZE: usize> embedded_nal_async::TcpConnect
+    for TcpClient<'_, 'a, N, INGRESS_BUF_SIZE>
+{
+    type Error = Error;
+    type Connection<'m>
+        = TcpConnection<'m, 'a, INGRESS_BUF_SIZE>
+    where
+        Self: 'm;
+
+    /// Fails with [`SocketErrorKind::NoBuffers`] while `N` connections are
+    /// open.
+    async fn connect<'m>(&'m self, remote: SocketAddr) -> Result<Self::Connection<'m>, Error> {
+        if self.connections.get() >= N {
+            return Err(Error::Socket(SocketErrorKind::NoBuffers));
+        }
+
+        // Counted from here, so that a failed connect gives the slot back
+        let mut connection = TcpConnection {
+            socket: TcpSocket::new(self.control),
+            connections: &self.connections,
+        };
+        self.connections.set(self.connections.get() + 1);
+        connection
+            .socket
+            .connect(remote.ip().into(), remote.port())
+            .await?;
+        Ok(connection)
+    }
+}
+
+/// Connection of a [`TcpClient`], closed in the background once dropped.
+pub struct TcpConnection<'c, 'a, const INGRESS_BUF_SIZE: usize> {
+    socket: TcpSocket<'c, 'a, INGRESS_BUF_SIZE>,
+    connections: &'c Cell<usize>,
+}
+
+impl<'c, 'a, const INGRESS_BUF_SIZE: usize> TcpConnection<'c, 'a, INGRESS_BUF_SIZE> {
+    pub fn socket(&mut self) -> &mut TcpSocket<'c, 'a, INGRESS_BUF_SIZE> {
+        &mut self.socket
+    }
+}
+
+impl<const INGRESS_BUF_SIZE: usize> Drop for TcpConnection<'_, '_, INGRESS_BUF_SIZE> {
+    fn drop(&mut self) {
+        self.connections.set(self.connections.get() - 1);
+    }
+}
+
+impl<const INGRESS_BUF_SIZE: usize> embedded_io_async::ErrorType
+    for TcpConnection<'_, '_, INGRESS_BUF_SIZE>
+{
+    type Error = Error;
+}
+
+impl<const INGRESS_BUF_SIZE: usize> embedded_io_async::Read
+    for TcpConnection<'_, '_, INGRESS_BUF_SIZE>
+{
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.socket.read(buf).await
+    }
+}
+
+impl<const INGRESS_BUF_SIZE: usize> embedded_io_async::Write
+    for TcpConnection<'_, '_, INGRESS_BUF_SIZE>
+{
+    /// Waits for the module to take at least a byte, rather than failing with
+    /// `WouldBlock` while its buffer is full.
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        loop {
+            match self.socket.write(buf).await {
+                Err(Error::Socket(SocketErrorKind::WouldBlock)) => {
+                    Timer::after(WRITE_RETRY_DELAY).await
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Waits for the peer to acknowledge the data written, see
+    /// [`TcpSocket::flush`].
+    async fn flush(&mut self) -> Result<(), Error> {
+        self.socket.flush().await
+    }
+}
+
+/// Traffic counters of a socket, for sizing the buffers of the module and the
+/// application. All counters saturate instead of wrapping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SocketStats {
+    /// Bytes read from the module
+    pub bytes_in: u32,
+    /// Bytes taken by the module
+    pub bytes_out: u32,
+    /// Most bytes the module reported buffered for reading at once
+    pub rx_high_water: u32,
+    /// Most bytes the module took with a single write
+    pub tx_high_water: u32,
+    /// Bytes of writes the module did not take, as its buffer was full
+    pub tx_rejected: u32,
+}
+
+impl SocketStats {
+    pub(crate) fn on_available(&mut self, available: usize) {
+        self.rx_high_water = self.rx_high_water.max(saturate(available));
+    }
+
+    pub(crate) fn on_rx(&mut self, read: usize) {
+        self.bytes_in = self.bytes_in.saturating_add(saturate(read));
+    }
+
+    /// `sent` bytes of a write of `len` were taken by the module.
+    pub(crate) fn on_tx(&mut self, len: usize, sent: usize) {
+        self.bytes_out = self.bytes_out.saturating_add(saturate(sent));
+        self.tx_high_water = self.tx_high_water.max(saturate(sent));
+        self.tx_rejected = self
+            .tx_rejected
+            .saturating_add(saturate(len.saturating_sub(sent)));
+    }
+
+    /// Add up the counters, keeping the highest high-water marks.
+    pub(crate) fn accumulate(&mut self, other: &Self) {
+        self.bytes_in = self.bytes_in.saturating_add(other.bytes_in);
+        self.bytes_out = self.bytes_out.saturating_add(other.bytes_out);
+        self.rx_high_water = self.rx_high_water.max(other.rx_high_water);
+        self.tx_high_water = self.tx_high_water.max(other.tx_high_water);
+        self.tx_rejected = self.tx_rejected.saturating_add(other.tx_rejected);
+    }
+}
+
+fn saturate(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+/// State of a socket tracked by the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SocketState {
+    Open,
+    /// Dropped, and waiting for the runner to close it
+    ClosePending,
+    /// Gone on the module, and waiting for the application to close it
+    Closed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SocketInfo {
+    pub handle: SocketHandle,
+    pub protocol: SocketProtocol,
+    pub local_port: Option<u16>,
+    pub state: SocketState,
+    pub stats: SocketStats,
+}
+
+/// Counters of all sockets tracked by the driver, see
+/// [`Control::socket_stats`]. The counters of a socket go with it once
+/// closed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SocketSetStats {
+    pub sockets: heapless::Vec<SocketInfo, MAX_SOCKETS>,
+    /// Counters summed over the sockets
+    pub totals: SocketStats,
+}
@@ -0,0 +1,208 @@
+//! Causes of failed socket operations. The module answers a failing +USOCO,
+//! +USOWR or +USORD with a generic error only, the cause of which has to be
+//! read with +USOCTL afterwards. A failing +USOCR leaves no socket to ask, so
+//! its cause is read with +USOER.
+
+use atat::{asynch::AtatClient, AtatCmd};
+use ublox_sockets::SocketHandle;
+
+use crate::command::ip_transport_layer::{
+    responses::CreateSocketResponse,
+    types::{SocketControlParam, SocketErrorKind},
+    CreateSocket, GetSocketError, SocketControl,
+};
+use crate::error::Error;
+
+/// Send `cmd` operating on `socket`, replacing a generic error with the
+/// error of the socket as [`Error::Socket`].
+pub(crate) async fn send<A: AtatClient, Cmd: AtatCmd>(
+    at_client: &mut A,
+    socket: SocketHandle,
+    cmd: &Cmd,
+) -> Result<Cmd::Response, Error> {
+    match at_client.send(cmd).await {
+        Ok(res) => Ok(res),
+        Err(e @ (atat::Error::Error | atat::Error::CmeError(_))) => {
+            match last_error(at_client, socket).await {
+                Some(kind) => {
+                    warn!("[{}] Socket error: {:?}", socket, kind);
+                    Err(Error::Socket(kind))
+                }
+                None => Err(e.into()),
+            }
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Create a socket with `cmd`, replacing a generic error with the last socket
+/// error as [`Error::Socket`], eg. [`SocketErrorKind::AddrInUse`] if the local
+/// port can't be bound.
+pub(crate) async fn create<A: AtatClient>(
+    at_client: &mut A,
+    cmd: &CreateSocket,
+) -> Result<CreateSocketResponse, Error> {
+    match at_client.send(cmd).await {
+        Ok(res) => Ok(res),
+        Err(e @ (atat::Error::Error | atat::Error::CmeError(_))) => {
+            match at_client
+                .send(&GetSocketError)
+                .await
+                .ok()
+                .and_then(|res| SocketErrorKind::from_errno(res.error.into()))
+            {
+                Some(kind) => {
+                    warn!("Failed to create socket: {:?}", kind);
+                    Err(Error::Socket(kind))
+                }
+                None => Err(e.into()),
+            }
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Last error of `socket`. Firmwares without +USOCTL only report the last
+/// error of any socket, with +USOER.
+pub(crate) async fn last_error<A: AtatClient>(
+    at_client: &mut A,
+    socket: SocketHandle,
+) -> Option<SocketErrorKind> {
+    let errno = match at_client
+        .send(&SocketControl {
+            socket,
+            param_id: SocketControlParam::LastSocketError,
+        })
+        .await
+    {
+        Ok(res) => res.param_val,
+        Err(_) => at_client.send(&GetSocketError).await.ok()?.error.into(),
+    };
+    SocketErrorKind::from_errno(errno)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{IpAddr, Ipv4Addr};
+
+    use crate::asynch::modem_sim::{Fixture, Step};
+    use crate::command::ip_transport_layer::{types::SocketProtocol, ConnectSocket};
+
+    use super::*;
+
+    const ERROR: &[u8] = b"\r\nERROR\r\n";
+
+    const CONNECT_FAILED: Step<'static> = Step::Command {
+        cmd: b"AT+USOCO=0,",
+        response: ERROR,
+    };
+
+    fn connect() -> ConnectSocket {
+        ConnectSocket {
+            socket: SocketHandle(0),
+            remote_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)).into(),
+            remote_port: 8080,
+        }
+    }
+
+    #[test]
+    fn refused_connection() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        let script = [
+            CONNECT_FAILED,
+            Step::Command {
+                cmd: b"AT+USOCTL=0,1",
+                response: b"\r\n+USOCTL: 0,1,111\r\n\r\nOK\r\n",
+            },
+        ];
+        assert_eq!(
+            io.play(
+                &mut sim,
+                &script,
+                send(&mut &client, SocketHandle(0), &connect())
+            ),
+            Err(Error::Socket(SocketErrorKind::ConnectionRefused))
+        );
+
+        // Without +USOCTL, the last error of any socket
+        let script = [
+            CONNECT_FAILED,
+            Step::Command {
+                cmd: b"AT+USOCTL=0,1",
+                response: ERROR,
+            },
+            Step::Command {
+                cmd: b"AT+USOER",
+                response: b"\r\n+USOER: 110\r\n\r\nOK\r\n",
+            },
+        ];
+        assert_eq!(
+            io.play(
+                &mut sim,
+                &script,
+                send(&mut &client, SocketHandle(0), &connect())
+            ),
+            Err(Error::Socket(SocketErrorKind::TimedOut))
+        );
+
+        // No error reported for the socket
+        let script = [
+            CONNECT_FAILED,
+            Step::Command {
+                cmd: b"AT+USOCTL=0,1",
+                response: b"\r\n+USOCTL: 0,1,0\r\n\r\nOK\r\n",
+            },
+        ];
+        assert_eq!(
+            io.play(
+                &mut sim,
+                &script,
+                send(&mut &client, SocketHandle(0), &connect())
+            ),
+            Err(Error::Atat(atat::Error::Error))
+        );
+    }
+
+    #[test]
+    fn bind_rejected() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        let cmd = CreateSocket {
+            protocol: SocketProtocol::TCP,
+            local_port: Some(6000),
+            preferred_protocol_type: None,
+            cid: None,
+            report_aon: None,
+        };
+
+        let script = [
+            Step::Command {
+                cmd: b"AT+USOCR=6,6000",
+                response: ERROR,
+            },
+            Step::Command {
+                cmd: b"AT+USOER",
+                response: b"\r\n+USOER: 98\r\n\r\nOK\r\n",
+            },
+        ];
+        assert_eq!(
+            io.play(&mut sim, &script, create(&mut &client, &cmd)),
+            Err(Error::Socket(SocketErrorKind::AddrInUse))
+        );
+
+        let script = [Step::Command {
+            cmd: b"AT+USOCR=6,6000",
+            response: b"\r\n+USOCR: 3,6,0\r\n\r\nOK\r\n",
+        }];
+        assert_eq!(
+            io.play(&mut sim, &script, create(&mut &client, &cmd))
+                .map(|res| res.socket),
+            Ok(SocketHandle(3))
+        );
+    }
+}
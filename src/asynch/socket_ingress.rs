@@ -0,0 +1,249 @@
+//! Reads of received socket data with +USORD. The +UUSORD URCs only tell the
+//! data available at the time they were sent, while a zero-length read
+//! returns the exact number of buffered bytes without consuming any. Knowing
+//! it, the data is read in as few chunks of [`INGRESS_CHUNK_SIZE`] as fit the
+//! buffer, back to back.
+
+use atat::asynch::AtatClient;
+use ublox_sockets::SocketHandle;
+
+use crate::command::ip_transport_layer::{
+    responses::INGRESS_CHUNK_SIZE, ReadSocketData, ReadSocketDataBinary,
+};
+use crate::error::Error;
+
+use super::socket_error;
+
+/// Number of bytes buffered by the module for `socket`, with a zero-length
+/// +USORD.
+pub(crate) async fn available<A: AtatClient>(
+    at_client: &mut A,
+    socket: SocketHandle,
+) -> Result<usize, Error> {
+    let res = socket_error::send(at_client, socket, &ReadSocketData { socket, length: 0 }).await?;
+    Ok(res.length)
+}
+
+/// Read data of `socket` into `buf`, from the `available` bytes the module
+/// buffers, or as many as a zero-length read tells if `None`. Returns the
+/// number of bytes read, and the number still buffered.
+pub(crate) async fn read<A: AtatClient>(
+    at_client: &mut A,
+    socket: SocketHandle,
+    available: Option<usize>,
+    hex_mode: bool,
+    buf: &mut [u8],
+) -> Result<(usize, usize), Error> {
+    let mut available = match available {
+        Some(n) => n,
+        None => self::available(at_client, socket).await?,
+    };
+
+    let mut read = 0;
+    while read < buf.len() && available > 0 {
+        let length = (buf.len() - read).min(available).min(INGRESS_CHUNK_SIZE);
+        let dst = &mut buf[read..];
+
+        let n = if hex_mode {
+            let mut res =
+                socket_error::send(at_client, socket, &ReadSocketData { socket, length }).await?;
+            res.decode_hex(|data| {
+                let n = data.len().min(dst.len());
+                dst[..n].copy_from_slice(&data[..n]);
+                n
+            })
+            .ok_or(Error::Atat(atat::Error::Parse))?
+        } else {
+            let res =
+                socket_error::send(at_client, socket, &ReadSocketDataBinary { socket, length })
+                    .await?;
+            let n = res.data.payload.len().min(dst.len());
+            dst[..n].copy_from_slice(&res.data.payload[..n]);
+            n
+        };
+
+        read += n;
+        if n < length {
+            // Less buffered than told, eg. the URC was stale
+            available = 0;
+            break;
+        }
+        available -= n;
+    }
+
+    Ok((read, available))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Write as _;
+    use std::string::String;
+
+    use crate::asynch::modem_sim::{Fixture, Step};
+
+    use super::*;
+
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    /// The +USORD commands and responses of a module holding `pending` bytes
+    /// for socket 0, read with the given lengths, in HEX or binary mode. A
+    /// zero length read tells the count. The n-th byte of each chunk is n.
+    fn usord(
+        mut pending: usize,
+        lengths: &[usize],
+        hex_mode: bool,
+    ) -> std::vec::Vec<(String, std::vec::Vec<u8>)> {
+        lengths
+            .iter()
+            .map(|&length| {
+                let mut res = String::new();
+                let mut data = std::vec::Vec::new();
+                if length == 0 {
+                    write!(res, "\r\n+USORD: 0,{}\r\n", pending).unwrap();
+                } else {
+                    let n = length.min(pending);
+                    pending -= n;
+                    write!(res, "\r\n+USORD: 0,{},\"", n).unwrap();
+                    for i in 0..n {
+                        let byte = i as u8;
+                        if hex_mode {
+                            res.push(HEX[usize::from(byte >> 4)] as char);
+                            res.push(HEX[usize::from(byte & 0xf)] as char);
+                        } else {
+                            data.push(byte);
+                        }
+                    }
+                    data.extend_from_slice(b"\"\r\n");
+                }
+                data.extend_from_slice(b"\r\nOK\r\n");
+                let mut response = res.into_bytes();
+                response.append(&mut data);
+                (std::format!("AT+USORD=0,{}", length), response)
+            })
+            .collect()
+    }
+
+    fn script(exchanges: &[(String, std::vec::Vec<u8>)]) -> std::vec::Vec<Step<'_>> {
+        exchanges
+            .iter()
+            .map(|(cmd, response)| Step::Command {
+                cmd: cmd.as_bytes(),
+                response: response.as_slice(),
+            })
+            .collect()
+    }
+
+    /// Lengths of the reads of `len` bytes, in full chunks.
+    fn chunks(len: usize) -> impl Iterator<Item = usize> {
+        (0..len)
+            .step_by(INGRESS_CHUNK_SIZE)
+            .map(move |start| (len - start).min(INGRESS_CHUNK_SIZE))
+    }
+
+    #[test]
+    fn burst_in_few_reads() {
+        const BURST: usize = 10 * 1024;
+
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        // A single query of the count, and full chunks only
+        let lengths: std::vec::Vec<_> = core::iter::once(0).chain(chunks(BURST)).collect();
+        let exchanges = usord(BURST, &lengths, true);
+        let mut buf = [0u8; BURST];
+        let res = io.play(
+            &mut sim,
+            &script(&exchanges),
+            read(&mut &client, SocketHandle(0), None, true, &mut buf),
+        );
+        assert_eq!(res, Ok((BURST, 0)));
+        assert_eq!(buf[INGRESS_CHUNK_SIZE + 1], 1);
+
+        // The count is known from the URC
+        let lengths: std::vec::Vec<_> = chunks(1000).collect();
+        let exchanges = usord(BURST, &lengths, true);
+        let res = io.play(
+            &mut sim,
+            &script(&exchanges),
+            read(
+                &mut &client,
+                SocketHandle(0),
+                Some(BURST),
+                true,
+                &mut buf[..1000],
+            ),
+        );
+        assert_eq!(res, Ok((1000, BURST - 1000)));
+    }
+
+    #[test]
+    fn stale_count() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+
+        let mut buf = [0u8; 64];
+        let exchanges = usord(10, &[40], true);
+        let res = io.play(
+            &mut sim,
+            &script(&exchanges),
+            read(&mut &client, SocketHandle(0), Some(40), true, &mut buf),
+        );
+        assert_eq!(res, Ok((10, 0)));
+
+        let exchanges = usord(0, &[0], true);
+        let res = io.play(
+            &mut sim,
+            &script(&exchanges),
+            read(&mut &client, SocketHandle(0), None, true, &mut buf),
+        );
+        assert_eq!(res, Ok((0, 0)));
+    }
+
+    /// Host throughput of a burst read back to back, digesting and parsing
+    /// every chunk, in HEX and binary mode. The binary chunks hold quotes,
+    /// line endings and `OK`. Run with `--nocapture` for the rates.
+    #[test]
+    fn throughput() {
+        const BURST: usize = 64 * 1024;
+
+        for hex_mode in [true, false] {
+            let mut fixture = Fixture::new();
+            let (mut sim, host, mut io) = fixture.split();
+            let client = host.client();
+
+            let lengths: std::vec::Vec<_> = chunks(BURST).collect();
+            let exchanges = usord(BURST, &lengths, hex_mode);
+            let mut buf = std::vec![0u8; BURST];
+            let start = std::time::Instant::now();
+            let res = io.play(
+                &mut sim,
+                &script(&exchanges),
+                read(
+                    &mut &client,
+                    SocketHandle(0),
+                    Some(BURST),
+                    hex_mode,
+                    &mut buf,
+                ),
+            );
+            let elapsed = start.elapsed();
+
+            assert_eq!(res, Ok((BURST, 0)));
+            assert!(buf
+                .iter()
+                .enumerate()
+                .all(|(i, &b)| b == (i % INGRESS_CHUNK_SIZE) as u8));
+            std::println!(
+                "{} mode: {} bytes in {} reads of {} in {:?}, {:.1} MiB/s",
+                if hex_mode { "HEX" } else { "Binary" },
+                BURST,
+                lengths.len(),
+                INGRESS_CHUNK_SIZE,
+                elapsed,
+                BURST as f64 / elapsed.as_secs_f64() / (1024.0 * 1024.0)
+            );
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Internal sockets created through the driver, along with the local port each
+//! one is bound to. The module rejects a bind to a port taken by another socket
+//! with a generic error only, so collisions are caught before +USOCR.
+//!
+//! A socket closed by the peer, or lost with a reset of the module, stays in
+//! the set until closed by the application, so that it is told its socket is
+//! gone rather than the module reusing the handle for another socket.
+
+use embassy_sync::waitqueue::MultiWakerRegistration;
+use heapless::Vec;
+use ublox_sockets::SocketHandle;
+
+use crate::{command::ip_transport_layer::types::SocketProtocol, config::MAX_SOCKETS};
+
+use super::socket::{SocketInfo, SocketSetStats, SocketState, SocketStats};
+
+struct Entry {
+    handle: SocketHandle,
+    protocol: SocketProtocol,
+    local_port: Option<u16>,
+    /// Bytes buffered by the module, unless stale as data was read since
+    available: Option<usize>,
+    /// Dropped without being closed, to be closed by the runner
+    close_pending: bool,
+    /// Gone on the module, as closed by the peer or lost in a reset
+    closed: bool,
+    stats: SocketStats,
+}
+
+pub(crate) struct SocketSet {
+    entries: Vec<Entry, MAX_SOCKETS>,
+    /// Whether the runner is to ask every socket for its buffered data, as a
+    /// +UUSORD may have been lost
+    poll_requested: bool,
+    /// Position of the socket to poll next, see [`Self::next_to_poll`]
+    poll_cursor: usize,
+    /// Readers waiting for data or for their socket to close
+    waker: MultiWakerRegistration<MAX_SOCKETS>,
+}
+
+impl SocketSet {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            poll_requested: false,
+            poll_cursor: 0,
+            waker: MultiWakerRegistration::new(),
+        }
+    }
+
+    fn open(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|e| !e.closed)
+    }
+
+    /// The open socket to poll for buffered data next, taking turns.
+    pub(crate) fn next_to_poll(&mut self) -> Option<SocketHandle> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let pos = self.poll_cursor % len;
+        self.poll_cursor = pos + 1;
+        self.open().nth(pos).map(|e| e.handle)
+    }
+
+    /// Number of open sockets.
+    pub(crate) fn len(&self) -> usize {
+        self.open().count()
+    }
+
+    /// Handles of the open sockets.
+    pub(crate) fn handles(&self) -> Vec<SocketHandle, MAX_SOCKETS> {
+        self.open().map(|e| e.handle).collect()
+    }
+
+    /// Have the runner ask every socket for its buffered data. Returns `false`
+    /// without any socket to ask.
+    pub(crate) fn request_poll(&mut self) -> bool {
+        self.poll_requested = self.len() > 0;
+        self.poll_requested
+    }
+
+    pub(crate) fn take_poll_request(&mut self) -> bool {
+        core::mem::take(&mut self.poll_requested)
+    }
+
+    /// Whether an open socket of `protocol` is bound to the local port `port`.
+    pub(crate) fn port_in_use(&self, protocol: &SocketProtocol, port: u16) -> bool {
+        self.open()
+            .any(|e| e.protocol == *protocol && e.local_port == Some(port))
+    }
+
+    /// Note the socket `handle` created by the module. The module reuses the
+    /// handles of closed sockets, so an entry of the same handle is replaced.
+    pub(crate) fn insert(
+        &mut self,
+        handle: SocketHandle,
+        protocol: SocketProtocol,
+        local_port: Option<u16>,
+    ) {
+        self.remove(handle);
+        if self
+            .entries
+            .push(Entry {
+                handle,
+                protocol,
+                local_port,
+                available: None,
+                close_pending: false,
+                closed: false,
+                stats: SocketStats::default(),
+            })
+            .is_err()
+        {
+            warn!("[{}] Too many sockets to track", handle);
+        }
+    }
+
+    pub(crate) fn contains(&self, handle: SocketHandle) -> bool {
+        self.entries.iter().any(|e| e.handle == handle)
+    }
+
+    /// Whether the known socket `handle` is gone on the module.
+    pub(crate) fn is_closed(&self, handle: SocketHandle) -> bool {
+        self.entries.iter().any(|e| e.handle == handle && e.closed)
+    }
+
+    /// Note the socket `handle` gone on the module, eg. as told by +UUSOCL.
+    /// Returns `false` if it is not known.
+    pub(crate) fn close(&mut self, handle: SocketHandle) -> bool {
+        let Some(e) = self.entries.iter_mut().find(|e| e.handle == handle) else {
+            return false;
+        };
+        e.closed = true;
+        e.available = None;
+        self.waker.wake();
+        true
+    }
+
+    /// Note all sockets gone, eg. as the module was reset. Sockets dropped
+    /// already are forgotten, as there is nothing left to close.
+    pub(crate) fn close_all(&mut self) {
+        self.entries.retain(|e| !e.close_pending);
+        for e in self.entries.iter_mut() {
+            e.closed = true;
+            e.available = None;
+        }
+        self.waker.wake();
+    }
+
+    /// Whether `handle` has data to read, or is closed. Otherwise the waker of
+    /// `cx` is woken once either changes.
+    pub(crate) fn poll_readable(
+        &mut self,
+        handle: SocketHandle,
+        cx: &mut core::task::Context<'_>,
+    ) -> bool {
+        let readable = match self.entries.iter().find(|e| e.handle == handle) {
+            Some(e) => e.closed || e.available.is_some_and(|n| n > 0),
+            None => true,
+        };
+        if !readable {
+            self.waker.register(cx.waker());
+        }
+        readable
+    }
+
+    /// Bytes buffered for `handle`, `None` if unknown or stale.
+    pub(crate) fn available(&self, handle: SocketHandle) -> Option<usize> {
+        self.entries
+            .iter()
+            .find(|e| e.handle == handle)
+            .and_then(|e| e.available)
+    }
+
+    /// Note the bytes buffered for `handle`, as told by a URC or a
+    /// zero-length read. `None` marks the count stale.
+    pub(crate) fn set_available(&mut self, handle: SocketHandle, available: Option<usize>) {
+        if let Some(e) = self.entries.iter_mut().find(|e| e.handle == handle) {
+            e.available = available;
+            if let Some(available) = available {
+                e.stats.on_available(available);
+                if available > 0 {
+                    self.waker.wake();
+                }
+            }
+        }
+    }
+
+    /// Count `read` bytes read from `handle`.
+    pub(crate) fn record_rx(&mut self, handle: SocketHandle, read: usize) {
+        if let Some(e) = self.entries.iter_mut().find(|e| e.handle == handle) {
+            e.stats.on_rx(read);
+        }
+    }
+
+    /// Count `sent` bytes of a write of `len` to `handle` taken by the module.
+    pub(crate) fn record_tx(&mut self, handle: SocketHandle, len: usize, sent: usize) {
+        if let Some(e) = self.entries.iter_mut().find(|e| e.handle == handle) {
+            e.stats.on_tx(len, sent);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> SocketSetStats {
+        let mut stats = SocketSetStats::default();
+        for e in self.entries.iter() {
+            stats.totals.accumulate(&e.stats);
+            // Cannot fail, there are as many entries at most
+            let _ = stats.sockets.push(SocketInfo {
+                handle: e.handle,
+                protocol: e.protocol.clone(),
+                local_port: e.local_port,
+                state: if e.closed {
+                    SocketState::Closed
+                } else if e.close_pending {
+                    SocketState::ClosePending
+                } else {
+                    SocketState::Open
+                },
+                stats: e.stats,
+            });
+        }
+        stats
+    }
+
+    /// Have the socket `handle` closed by the runner. Returns `false` if there
+    /// is nothing to close, as it is not known or gone on the module already,
+    /// in which case it is forgotten.
+    pub(crate) fn defer_close(&mut self, handle: SocketHandle) -> bool {
+        match self.entries.iter_mut().find(|e| e.handle == handle) {
+            Some(e) if e.closed => {
+                self.remove(handle);
+                false
+            }
+            Some(e) => {
+                e.close_pending = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take the next socket to close. It stays in the set, keeping its local
+    /// port, until closed.
+    pub(crate) fn take_pending_close(&mut self) -> Option<SocketHandle> {
+        let e = self.entries.iter_mut().find(|e| e.close_pending)?;
+        e.close_pending = false;
+        Some(e.handle)
+    }
+
+    /// Forget the socket `handle`. Returns `false` if it was not known.
+    pub(crate) fn remove(&mut self, handle: SocketHandle) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|e| e.handle != handle);
+        if self.entries.len() == len {
+            return false;
+        }
+        self.waker.wake();
+        true
+    }
+
+    /// Forget all sockets, eg. as the module was reset.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_collisions() {
+        let mut set = SocketSet::new();
+        set.insert(SocketHandle(0), SocketProtocol::TCP, Some(6000));
+        set.insert(SocketHandle(1), SocketProtocol::TCP, None);
+
+        assert!(set.port_in_use(&SocketProtocol::TCP, 6000));
+        assert!(!set.port_in_use(&SocketProtocol::UDP, 6000));
+        assert!(!set.port_in_use(&SocketProtocol::TCP, 6001));
+
+        // The handle is reused for a socket without a local port
+        set.insert(SocketHandle(0), SocketProtocol::UDP, None);
+        assert!(!set.port_in_use(&SocketProtocol::TCP, 6000));
+
+        assert_eq!(set.available(SocketHandle(1)), None);
+        set.set_available(SocketHandle(1), Some(12));
+        assert_eq!(set.available(SocketHandle(1)), Some(12));
+
+        assert!(set.contains(SocketHandle(1)));
+        assert!(set.remove(SocketHandle(1)));
+        assert!(!set.contains(SocketHandle(1)));
+        assert!(!set.remove(SocketHandle(1)));
+
+        set.insert(SocketHandle(2), SocketProtocol::UDP, Some(6000));
+        set.clear();
+        assert!(!set.defer_close(SocketHandle(2)));
+        assert!(!set.port_in_use(&SocketProtocol::UDP, 6000));
+    }
+
+    #[test]
+    fn deferred_close() {
+        let mut set = SocketSet::new();
+        set.insert(SocketHandle(0), SocketProtocol::TCP, Some(6000));
+        set.insert(SocketHandle(1), SocketProtocol::TCP, None);
+        assert_eq!(set.take_pending_close(), None);
+
+        assert!(set.defer_close(SocketHandle(0)));
+        assert!(!set.defer_close(SocketHandle(4)));
+        assert_eq!(set.take_pending_close(), Some(SocketHandle(0)));
+        assert_eq!(set.take_pending_close(), None);
+
+        // The port stays taken until the socket is closed
+        assert!(set.port_in_use(&SocketProtocol::TCP, 6000));
+        set.remove(SocketHandle(0));
+        assert!(!set.port_in_use(&SocketProtocol::TCP, 6000));
+    }
+
+    #[test]
+    fn remote_close() {
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+
+        let mut set = SocketSet::new();
+        set.insert(SocketHandle(0), SocketProtocol::TCP, Some(6000));
+        set.insert(SocketHandle(1), SocketProtocol::TCP, None);
+        set.set_available(SocketHandle(0), Some(0));
+        assert!(!set.poll_readable(SocketHandle(0), &mut cx));
+        set.set_available(SocketHandle(0), Some(8));
+        assert!(set.poll_readable(SocketHandle(0), &mut cx));
+
+        assert!(set.close(SocketHandle(0)));
+        assert!(!set.close(SocketHandle(4)));
+        assert!(set.is_closed(SocketHandle(0)));
+        assert!(!set.is_closed(SocketHandle(1)));
+        assert!(!set.is_closed(SocketHandle(4)));
+        assert!(set.poll_readable(SocketHandle(0), &mut cx));
+        assert_eq!(set.available(SocketHandle(0)), None);
+
+        // Kept until closed by the application, but no longer polled
+        assert!(set.contains(SocketHandle(0)));
+        assert!(!set.port_in_use(&SocketProtocol::TCP, 6000));
+        assert_eq!(set.handles().as_slice(), &[SocketHandle(1)]);
+        assert_eq!(set.next_to_poll(), Some(SocketHandle(1)));
+        assert_eq!(set.next_to_poll(), Some(SocketHandle(1)));
+        assert_eq!(set.stats().sockets[0].state, SocketState::Closed);
+
+        // There is nothing left to close on the module
+        assert!(!set.defer_close(SocketHandle(0)));
+        assert!(!set.contains(SocketHandle(0)));
+
+        set.insert(SocketHandle(2), SocketProtocol::TCP, None);
+        set.defer_close(SocketHandle(2));
+        set.close_all();
+        assert!(set.is_closed(SocketHandle(1)));
+        assert!(!set.request_poll());
+        assert!(!set.contains(SocketHandle(2)));
+        assert_eq!(set.take_pending_close(), None);
+    }
+
+    #[test]
+    fn traffic_counters() {
+        let mut set = SocketSet::new();
+        set.insert(SocketHandle(0), SocketProtocol::TCP, None);
+        set.insert(SocketHandle(1), SocketProtocol::UDP, Some(5000));
+
+        set.set_available(SocketHandle(0), Some(600));
+        set.record_rx(SocketHandle(0), 512);
+        set.set_available(SocketHandle(0), Some(88));
+        set.record_tx(SocketHandle(0), 1024, 1024);
+        set.record_tx(SocketHandle(0), 1024, 100);
+        set.record_tx(SocketHandle(1), 10, 10);
+        set.defer_close(SocketHandle(1));
+
+        let stats = set.stats();
+        assert_eq!(
+            stats.sockets[0].stats,
+            SocketStats {
+                bytes_in: 512,
+                bytes_out: 1124,
+                rx_high_water: 600,
+                tx_high_water: 1024,
+                tx_rejected: 924,
+            }
+        );
+        assert_eq!(stats.sockets[1].state, SocketState::ClosePending);
+        assert_eq!(stats.totals.bytes_out, 1134);
+        assert_eq!(stats.totals.tx_high_water, 1024);
+
+        // The counters saturate
+        set.record_rx(SocketHandle(0), usize::MAX);
+        assert_eq!(set.stats().sockets[0].stats.bytes_in, u32::MAX);
+    }
+}
@@ -1,14 +1,68 @@
 #![allow(dead_code)]
 
-use crate::command::network_service::types::RatAct;
-use crate::config::Apn;
+use crate::command::device_data_security::types::SecurityProfileId;
+use crate::command::general::types::FirmwareVersion;
+#[cfg(feature = "http")]
+use crate::command::http::urc::HttpResponse;
+#[cfg(feature = "internal-network-stack")]
+use crate::command::ip_transport_layer::types::SocketProtocol;
+use crate::command::mobile_control::{
+    responses::{ExtendedErrorReport, IndicatorControl},
+    urc::IndicatorEvent,
+};
+#[cfg(feature = "mqtt")]
+use crate::command::mqtt::urc::MqttCommandResult;
+use crate::command::network_service::responses::SignalQuality;
+use crate::command::network_service::types::{OperatorList, Plmn, RatAct};
+use crate::command::networking::types::EmbeddedPortFilteringMode;
+#[cfg(not(feature = "use-upsd-context-activation"))]
+use crate::command::psn::types::AuthenticationType;
+use crate::command::psn::types::RejectCause;
+#[cfg(feature = "use-upsd-context-activation")]
+use crate::command::psn::types::{ContextId, ProfileId};
+use crate::command::system_features::types::FirmwareInstallError;
+#[cfg(feature = "internal-network-stack")]
+use crate::config::MAX_SOCKETS;
+use crate::config::{Apn, RetryPolicy, MAX_APN_CANDIDATES, MAX_PENDING_WAKE, MAX_STATE_RECEIVERS};
+use crate::error::{Error, InitError};
 use core::cell::RefCell;
 use core::future::poll_fn;
+use core::net::IpAddr;
 use core::task::{Context, Poll};
 
 use embassy_sync::blocking_mutex::raw::NoopRawMutex;
 use embassy_sync::blocking_mutex::Mutex;
-use embassy_sync::waitqueue::WakerRegistration;
+use embassy_sync::waitqueue::{MultiWakerRegistration, WakerRegistration};
+use embassy_sync::watch::{self, Watch};
+use embassy_time::{Duration, Instant};
+
+use super::attach_backoff::AttachBackoff;
+use super::diagnostics::{DiagnosticEvent, Diagnostics, DiagnosticsSubscriber, TransitionReason};
+#[cfg(feature = "internal-network-stack")]
+use super::dns_cache::DnsCache;
+use super::progress::{self, Progress, ProgressReceiver, ProgressWatch};
+#[cfg(feature = "internal-network-stack")]
+use super::{socket::SocketSetStats, socket_set::SocketSet};
+
+/// Highest id of the USECMNG security profiles.
+const MAX_SECURITY_PROFILE_ID: u8 = 4;
+
+/// What the runner has to do about the PSM deep sleep of the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PsmAction {
+    /// Commands wait for the sleeping module
+    Wake,
+    /// A command timed out, the module may have gone to sleep
+    CheckSleep,
+}
+
+struct PendingWake<'a, 'b>(&'b Runner<'a>);
+
+impl Drop for PendingWake<'_, '_> {
+    fn drop(&mut self) {
+        self.0.shared.lock(|s| s.borrow_mut().pending_wake -= 1);
+    }
+}
 
 /// The link state of a network device.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -20,21 +74,202 @@ pub enum LinkState {
     Up,
 }
 
+/// Whether a SIM is inserted.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimState {
+    /// Not checked yet, or the SIM did not answer
+    Unknown,
+    /// The module reports no SIM inserted. The runner does not attempt to
+    /// attach until one is.
+    Absent,
+    /// A SIM is inserted
+    Present,
+}
+
 /// If the celular modem is up and responding to AT.
-#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+///
+/// The states are ordered as they are brought up, with `AirplaneMode` between
+/// `Initialized` and `Connected`, regardless of its discriminant.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum OperationState {
     PowerDown = 0,
     Initialized = 1,
     Connected = 2,
     DataEstablished = 3,
+    /// Initialized with the radio switched off by `AT+CFUN=4`, independent of
+    /// the module's `radio_off_cfun()`. The SIM stays accessible.
+    AirplaneMode = 4,
+}
+
+impl OperationState {
+    /// Position of the state in the bring up.
+    const fn rank(self) -> u8 {
+        match self {
+            Self::PowerDown => 0,
+            Self::Initialized => 1,
+            Self::AirplaneMode => 2,
+            Self::Connected => 3,
+            Self::DataEstablished => 4,
+        }
+    }
+}
+
+impl Ord for OperationState {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl PartialOrd for OperationState {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Progress of a modem firmware installation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FirmwareInstallState {
+    Idle,
+    /// Installation is running, with the progress in percent as reported by
+    /// `+UUFWINSTALL`.
+    Installing(u8),
+    /// The module reported a successful installation.
+    Completed,
+    Failed(FirmwareInstallError),
+}
+
+/// Progress of an operator scan started with
+/// [`Control::start_operator_scan`](super::control::Control::start_operator_scan).
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OperatorScan {
+    /// The scan is running, or waiting for the runner to start it.
+    Pending,
+    /// The operators found, and when the scan completed, in seconds since
+    /// boot.
+    Ready {
+        operators: OperatorList,
+        at_secs: u64,
+    },
+    Failed(OperatorScanError),
+}
+
+/// Why an operator scan has no result.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OperatorScanError {
+    /// Aborted with
+    /// [`Control::abort_operator_scan`](super::control::Control::abort_operator_scan),
+    /// or by a reset of the module.
+    Aborted,
+    /// The module is powered down.
+    PoweredDown,
+    /// The module did not complete the scan in time.
+    Timeout,
+    /// The module answered the scan with an error.
+    Rejected,
+}
+
+/// Step of the reset ladder the runner climbs when the module stops answering
+/// AT commands.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecoveryAction {
+    /// Silent reset with `AT+CFUN=15`.
+    SoftReset,
+    /// Reset by holding the `RESET_N` pin low for `ModuleParams::reset_hold()`.
+    HardReset,
+    /// Power the module down and up again with the power pin.
+    PowerCycle,
+}
+
+/// Summary of the network registration, see [`Runner::registration_status`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegistrationStatus {
+    pub registered: bool,
+    pub denied: bool,
+    pub act: Option<RatAct>,
+    /// Why the network rejected the last EPS registration, if it did
+    pub reject_cause: Option<RejectCause>,
+}
+
+/// PSM timers granted by the network with the last EPS registration, see
+/// [`Runner::psm_timers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PsmTimers {
+    /// Time the module stays reachable after going idle (T3324), None if
+    /// PSM is not granted
+    pub active_time: Option<Duration>,
+    /// Interval of the periodic tracking area updates (T3412), None if
+    /// deactivated or not reported
+    pub periodic_tau: Option<Duration>,
+    /// When the module last reported a registration or tracking area update
+    pub last_tau: Option<Instant>,
+}
+
+impl PsmTimers {
+    /// Estimate of when the module is reachable next, at its next periodic
+    /// tracking area update. The module is reachable for `active_time` from
+    /// then on.
+    pub fn next_reachable(&self) -> Option<Instant> {
+        Some(self.last_tau? + self.periodic_tau?)
+    }
+}
+
+/// Identity of the modem and the SIM, read during initialization.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Identity {
+    pub imei: Option<u64>,
+    pub iccid: Option<u128>,
+    pub imsi: Option<u64>,
+    pub model: heapless::String<16>,
+    pub firmware_version: Option<FirmwareVersion>,
+}
+
+impl Identity {
+    const fn new() -> Self {
+        Self {
+            imei: None,
+            iccid: None,
+            imsi: None,
+            model: heapless::String::new(),
+            firmware_version: None,
+        }
+    }
 }
 
 use crate::modules::Module;
 use crate::registration::{ProfileState, RegistrationState};
 
+/// Receiver of the [`OperationState`] changes, see
+/// [`Control::operation_state_receiver`](super::control::Control::operation_state_receiver).
+pub type OperationStateReceiver<'a> =
+    watch::Receiver<'a, NoopRawMutex, OperationState, MAX_STATE_RECEIVERS>;
+
+/// Receiver of the [`LinkState`] changes, see
+/// [`Control::link_state_receiver`](super::control::Control::link_state_receiver).
+pub type LinkStateReceiver<'a> = watch::Receiver<'a, NoopRawMutex, LinkState, MAX_STATE_RECEIVERS>;
+
+/// Receiver of the [`SimState`] changes, see
+/// [`Control::sim_state_receiver`](super::control::Control::sim_state_receiver).
+pub type SimStateReceiver<'a> = watch::Receiver<'a, NoopRawMutex, SimState, MAX_STATE_RECEIVERS>;
+
 pub struct State {
     shared: Mutex<NoopRawMutex, RefCell<Shared>>,
+    /// Broadcast the operation and link state on top of `state_waker`, which
+    /// wakes a single task only.
+    operation_state_watch: Watch<NoopRawMutex, OperationState, MAX_STATE_RECEIVERS>,
+    link_state_watch: Watch<NoopRawMutex, LinkState, MAX_STATE_RECEIVERS>,
+    sim_state_watch: Watch<NoopRawMutex, SimState, MAX_STATE_RECEIVERS>,
+    file_progress_watch: ProgressWatch,
+    firmware_progress_watch: ProgressWatch,
+    diagnostics: Diagnostics,
 }
 
 impl Default for State {
@@ -55,12 +290,89 @@ impl State {
                 state_waker: WakerRegistration::new(),
                 registration_waker: WakerRegistration::new(),
                 rat_waker: WakerRegistration::new(),
-                #[cfg(not(feature = "automatic-apn"))]
-                apn_config: Apn::None,
-                #[cfg(any(feature = "automatic-apn"))]
-                apn_config: Apn::Automatic,
+                apn_candidates: heapless::Vec::new(),
+                apn_index: 0,
+                #[cfg(not(feature = "use-upsd-context-activation"))]
+                auth_type: None,
                 hard_reset: false,
+                firmware_install: FirmwareInstallState::Idle,
+                firmware_install_deadline: None,
+                recoveries: 0,
+                init_error: None,
+                recent_errors: heapless::Deque::new(),
+                state_stats: StateStats::new(),
+                shutdown: ShutdownReport::new(),
+                signal_quality: None,
+                signal_usable: false,
+                signal_waker: WakerRegistration::new(),
+                urc_overflows: 0,
+                embedded_port_filtering: None,
+                #[cfg(feature = "internal-network-stack")]
+                unknown_socket_urcs: 0,
+                extended_error: None,
+                indicators: None,
+                last_recovery: None,
+                scanning_operators: false,
+                operator_scan: None,
+                operator_scan_requested: false,
+                operator_scan_abort: false,
+                operator_scan_waker: WakerRegistration::new(),
+                operator_selection: None,
+                #[cfg(feature = "use-upsd-context-activation")]
+                psd_profile: None,
+                #[cfg(feature = "use-upsd-context-activation")]
+                psd_result: None,
+                #[cfg(feature = "use-upsd-context-activation")]
+                psd_waker: WakerRegistration::new(),
+                session: 0,
+                identity: Identity::new(),
+                last_iccid: None,
+                sim_state: SimState::Unknown,
+                own_number: None,
+                sim_check: false,
+                sleeping: false,
+                sleep_suspected: false,
+                pending_wake: 0,
+                psm_waker: WakerRegistration::new(),
+                awake_waker: MultiWakerRegistration::new(),
+                sim_changes: 0,
+                #[cfg(feature = "http")]
+                http_response: None,
+                #[cfg(feature = "http")]
+                http_waker: WakerRegistration::new(),
+                #[cfg(feature = "mqtt")]
+                mqtt: MqttState {
+                    connected: false,
+                    unread: 0,
+                    last_result: None,
+                },
+                #[cfg(feature = "mqtt")]
+                mqtt_waker: WakerRegistration::new(),
+                ringing: false,
+                ring_waker: WakerRegistration::new(),
+                local_addr: None,
+                local_addr_changes: 0,
+                local_addr_refresh: false,
+                local_addr_waker: WakerRegistration::new(),
+                local_addr_refresh_waker: WakerRegistration::new(),
+                flow_control_fallback: false,
+                security_profiles: 0,
+                attach_backoff: AttachBackoff::new(),
+                #[cfg(feature = "internal-network-stack")]
+                dns_cache: DnsCache::new(),
+                #[cfg(feature = "internal-network-stack")]
+                sockets: SocketSet::new(),
+                #[cfg(feature = "internal-network-stack")]
+                hex_mode: true,
+                #[cfg(feature = "ppp")]
+                network_config: None,
             })),
+            operation_state_watch: Watch::new_with(OperationState::PowerDown),
+            link_state_watch: Watch::new_with(LinkState::Down),
+            sim_state_watch: Watch::new_with(SimState::Unknown),
+            file_progress_watch: Watch::new(),
+            firmware_progress_watch: Watch::new(),
+            diagnostics: Diagnostics::new(),
         }
     }
 }
@@ -75,27 +387,310 @@ pub struct Shared {
     state_waker: WakerRegistration,
     registration_waker: WakerRegistration,
     rat_waker: WakerRegistration,
-    apn_config: Apn,
+    /// APNs to try in order. Without any, the default APN is used.
+    apn_candidates: heapless::Vec<Apn, MAX_APN_CANDIDATES>,
+    /// Candidate in use. Kept after a successful activation, so reconnects
+    /// start with the APN that worked last.
+    apn_index: usize,
+    /// Authentication type the context was last activated with, once it had
+    /// to be found by trying them one by one.
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    auth_type: Option<AuthenticationType>,
     /// When set, the next `Connected -> Initialized` descent skips the graceful
     /// AT teardown (COPS=2 deregister + CFUN radio-off) and goes straight to the
     /// GPIO power-cycle. Set by the firmware keepalive, which only fires once the
     /// modem has proven unresponsive — talking AT to a dead modem just burns the
     /// commands' timeouts (~20s) before the power-cycle that actually recovers it.
     hard_reset: bool,
+    /// While a firmware installation is running, the module reboots on its own
+    /// and must not be power-cycled. The runner monitors the installation on
+    /// the UART instead of treating the lost CMUX session as a failure.
+    firmware_install: FirmwareInstallState,
+    /// When the installation started by `Control::install_firmware` times
+    /// out, as given by its caller
+    firmware_install_deadline: Option<Instant>,
+    /// Number of times the runner had to recover a hung AT interface, and the
+    /// last step of the reset ladder it took.
+    recoveries: u32,
+    last_recovery: Option<RecoveryAction>,
+    /// Set when the runner gave up initializing the module. Cleared once it
+    /// is asked to try again.
+    init_error: Option<InitError>,
+    /// Last errors the runner ran into, oldest first.
+    recent_errors: heapless::Deque<ErrorRecord, MAX_RECENT_ERRORS>,
+    /// Time spent in each operation state.
+    state_stats: StateStats,
+    /// Graceful shutdown steps of the current session.
+    shutdown: ShutdownReport,
+    /// Last signal quality polled by the runner, and whether the signal was
+    /// usable by the thresholds of `CellularConfig::SIGNAL_MONITOR`.
+    signal_quality: Option<SignalQuality>,
+    signal_usable: bool,
+    signal_waker: WakerRegistration,
+    /// Number of URCs lost, because the URC channel was full.
+    urc_overflows: u32,
+    /// +UEMBPF range applied before dialing up PPP, which the local ports of
+    /// internal sockets have to be in. `None` without PPP.
+    embedded_port_filtering: Option<EmbeddedPortFilteringMode>,
+    /// Number of socket URCs for sockets the driver did not create.
+    #[cfg(feature = "internal-network-stack")]
+    unknown_socket_urcs: u32,
+    /// Last +CEER report, read after a failed attach, registration or context
+    /// activation.
+    extended_error: Option<ExtendedErrorReport>,
+    /// +CIND indicators, kept up to date by +CIEV URCs.
+    indicators: Option<IndicatorControl>,
+    /// An operator scan (`AT+COPS=?`) keeps the AT interface busy for minutes.
+    /// Registration timeouts are paused meanwhile, so the silence is not
+    /// mistaken for a hung modem.
+    scanning_operators: bool,
+    /// Background operator scan, and whether the runner has yet to start or
+    /// abort it.
+    operator_scan: Option<OperatorScan>,
+    operator_scan_requested: bool,
+    operator_scan_abort: bool,
+    operator_scan_waker: WakerRegistration,
+    /// PLMN to register on with manual operator selection. `None` selects the
+    /// operator automatically.
+    operator_selection: Option<Plmn>,
+    /// PSD profile activated with +UPSDA, and the context it is mapped to.
+    #[cfg(feature = "use-upsd-context-activation")]
+    psd_profile: Option<(ContextId, ProfileId)>,
+    /// Result code of the last +UUPSDA, until the activation waiting for it
+    /// takes it.
+    #[cfg(feature = "use-upsd-context-activation")]
+    psd_result: Option<u8>,
+    #[cfg(feature = "use-upsd-context-activation")]
+    psd_waker: WakerRegistration,
+    /// Incremented every time the modem has been (re-)initialized. State the
+    /// module held in a previous session, eg. sockets or the MQTT connection,
+    /// is gone after a reset.
+    session: u32,
+    /// Kept across sessions, so it can still be read while the modem is
+    /// powered down.
+    identity: Identity,
+    /// ICCID of the last SIM seen. Unlike the identity, it is kept while the
+    /// SIM is removed, to tell a swap from the same SIM being reinserted.
+    last_iccid: Option<u128>,
+    sim_state: SimState,
+    /// MSISDN of the SIM, `Some(None)` if the SIM has none stored. Cleared on
+    /// a SIM swap.
+    own_number: Option<Option<heapless::String<32>>>,
+    /// Set when a SIM was inserted, until the runner has checked which SIM it
+    /// is.
+    sim_check: bool,
+    /// Whether the module is in PSM deep sleep, see
+    /// `CellularConfig::WAKE_FROM_PSM`.
+    sleeping: bool,
+    /// A command timed out, so the runner checks whether the module sleeps.
+    sleep_suspected: bool,
+    /// Number of commands waiting for the module to wake.
+    pending_wake: usize,
+    psm_waker: WakerRegistration,
+    awake_waker: MultiWakerRegistration<MAX_PENDING_WAKE>,
+    /// Number of SIM swaps detected.
+    sim_changes: u32,
+    /// Last `+UUHTTPCR` result, consumed by the HTTP client waiting for it.
+    #[cfg(feature = "http")]
+    http_response: Option<HttpResponse>,
+    #[cfg(feature = "http")]
+    http_waker: WakerRegistration,
+    #[cfg(feature = "mqtt")]
+    mqtt: MqttState,
+    #[cfg(feature = "mqtt")]
+    mqtt_waker: WakerRegistration,
+    /// A RING or +CLIP URC announced an incoming call since the last look.
+    ringing: bool,
+    ring_waker: WakerRegistration,
+    /// Address of the data context, as last read with +CGPADDR.
+    local_addr: Option<IpAddr>,
+    /// Number of times a new address was read.
+    local_addr_changes: u32,
+    local_addr_waker: WakerRegistration,
+    /// A +UUPSDA URC announced a (re)activation, so the runner reads the
+    /// address again.
+    local_addr_refresh: bool,
+    local_addr_refresh_waker: WakerRegistration,
+    /// RTS/CTS flow control is configured, but the module did not answer
+    /// with it, so init went on without.
+    flow_control_fallback: bool,
+    /// Bitmask of the USECMNG security profiles known to be configured,
+    /// kept across reboots as the module keeps them in NVM.
+    security_profiles: u8,
+    /// Backoff of the network attach, kept across resets of the module
+    attach_backoff: AttachBackoff,
+    /// Addresses of recently resolved names, handed out in turn
+    #[cfg(feature = "internal-network-stack")]
+    dns_cache: DnsCache,
+    #[cfg(feature = "internal-network-stack")]
+    sockets: SocketSet,
+    /// Whether socket data is exchanged hex encoded, see
+    /// `CellularConfig::HEX_MODE`.
+    #[cfg(feature = "internal-network-stack")]
+    hex_mode: bool,
+    /// Address and DNS servers negotiated with IPCP, while the PPP link is up
+    #[cfg(feature = "ppp")]
+    network_config: Option<embassy_net::StaticConfigV4>,
+}
+
+/// State of the modem's internal MQTT client, as tracked from `+UUMQTTC` and
+/// `+UUMQTTCM` URCs.
+#[cfg(feature = "mqtt")]
+struct MqttState {
+    connected: bool,
+    unread: u16,
+    /// Last `+UUMQTTC` result, consumed by the MQTT client waiting for it.
+    last_result: Option<MqttCommandResult>,
+}
+
+/// Number of errors kept in the journal of [`Runner::recent_errors`].
+pub const MAX_RECENT_ERRORS: usize = 8;
+
+/// An error the runner ran into, as kept in its journal.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorRecord {
+    /// Seconds since boot
+    pub at_secs: u64,
+    /// Debug representation of the error, truncated
+    pub error: heapless::String<48>,
+}
+
+/// Steps of the graceful shutdown taken before the modem was last powered
+/// down. A step is `false` if it failed, timed out or was skipped, eg. with
+/// no data connection up, or on a hard reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ShutdownReport {
+    /// The PDP context was deactivated
+    pub context_deactivated: bool,
+    /// Detached from the packet domain (+CGATT=0)
+    pub detached: bool,
+    /// Deregistered from the network (+COPS=2)
+    pub deregistered: bool,
+    /// The module switched itself off (+CPWROFF), before the power pin was
+    /// toggled
+    pub switched_off: bool,
+}
+
+impl ShutdownReport {
+    pub const fn new() -> Self {
+        Self {
+            context_deactivated: false,
+            detached: false,
+            deregistered: false,
+            switched_off: false,
+        }
+    }
+}
+
+/// Time the modem spent in each [`OperationState`], and the number of state
+/// transitions, since boot or the last [`Control::reset_state_stats`].
+///
+/// [`Control::reset_state_stats`]: super::control::Control::reset_state_stats
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateStats {
+    /// Indexed by `OperationState as usize`
+    time_in: [Duration; 5],
+    transitions: u32,
+    state: OperationState,
+    last_transition: Instant,
+}
+
+impl StateStats {
+    const fn new() -> Self {
+        Self {
+            time_in: [Duration::from_ticks(0); 5],
+            transitions: 0,
+            state: OperationState::PowerDown,
+            last_transition: Instant::from_ticks(0),
+        }
+    }
+
+    fn record(&mut self, state: OperationState, now: Instant) {
+        self.settle(now);
+        self.state = state;
+        self.transitions = self.transitions.saturating_add(1);
+    }
+
+    /// Account the time in the current state up to `now`.
+    fn settle(&mut self, now: Instant) {
+        let time_in = &mut self.time_in[self.state as usize];
+        *time_in = Duration::from_ticks(
+            time_in.as_ticks().saturating_add(
+                now.saturating_duration_since(self.last_transition)
+                    .as_ticks(),
+            ),
+        );
+        self.last_transition = now;
+    }
+
+    fn reset(&mut self, now: Instant) {
+        *self = Self {
+            state: self.state,
+            last_transition: now,
+            ..Self::new()
+        };
+    }
+
+    /// Total time spent in `state`, including the current stay.
+    pub fn time_in(&self, state: OperationState) -> Duration {
+        self.time_in[state as usize]
+    }
+
+    /// Number of state transitions.
+    pub fn transitions(&self) -> u32 {
+        self.transitions
+    }
+
+    /// The state the modem is in.
+    pub fn state(&self) -> OperationState {
+        self.state
+    }
+
+    /// When the modem entered its current state, eg. to tell how long it is
+    /// stuck in it.
+    pub fn last_transition(&self) -> Instant {
+        self.last_transition
+    }
 }
 
 #[derive(Clone)]
 pub struct Runner<'d> {
     pub(crate) shared: &'d Mutex<NoopRawMutex, RefCell<Shared>>,
+    operation_state_watch: &'d Watch<NoopRawMutex, OperationState, MAX_STATE_RECEIVERS>,
+    link_state_watch: &'d Watch<NoopRawMutex, LinkState, MAX_STATE_RECEIVERS>,
+    sim_state_watch: &'d Watch<NoopRawMutex, SimState, MAX_STATE_RECEIVERS>,
+    file_progress_watch: &'d ProgressWatch,
+    firmware_progress_watch: &'d ProgressWatch,
+    diagnostics: &'d Diagnostics,
 }
 
 impl<'d> Runner<'d> {
     pub fn new(state: &'d mut State) -> Self {
         Self {
             shared: &state.shared,
+            operation_state_watch: &state.operation_state_watch,
+            link_state_watch: &state.link_state_watch,
+            sim_state_watch: &state.sim_state_watch,
+            file_progress_watch: &state.file_progress_watch,
+            firmware_progress_watch: &state.firmware_progress_watch,
+            diagnostics: &state.diagnostics,
         }
     }
 
+    pub(crate) fn diagnostics(&self) -> &'d Diagnostics {
+        self.diagnostics
+    }
+
+    /// A new subscriber of the diagnostic events, unless there are
+    /// [`MAX_DIAGNOSTICS_SUBSCRIBERS`](crate::config::MAX_DIAGNOSTICS_SUBSCRIBERS)
+    /// already.
+    pub fn diagnostics_subscriber(&self) -> Option<DiagnosticsSubscriber<'d>> {
+        self.diagnostics.subscriber().ok()
+    }
+
     pub(crate) fn module(&self) -> Option<Module> {
         self.shared.lock(|s| s.borrow().module)
     }
@@ -152,6 +747,29 @@ impl<'d> Runner<'d> {
         })
     }
 
+    pub fn registration_status(&self) -> RegistrationStatus {
+        self.shared.lock(|s| {
+            let r = &s.borrow().registration_state;
+            RegistrationStatus {
+                registered: r.is_registered(),
+                denied: r.is_denied(),
+                act: r.current_act(),
+                reject_cause: r.reject_cause(),
+            }
+        })
+    }
+
+    pub fn psm_timers(&self) -> PsmTimers {
+        self.shared.lock(|s| {
+            let r = &s.borrow().registration_state;
+            PsmTimers {
+                active_time: r.active_time,
+                periodic_tau: r.periodic_tau,
+                last_tau: r.last_tau,
+            }
+        })
+    }
+
     pub fn is_denied(&self, cx: Option<&mut Context>) -> bool {
         self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
@@ -177,11 +795,22 @@ impl<'d> Runner<'d> {
     }
 
     pub fn set_link_state(&self, state: LinkState) {
-        self.shared.lock(|s| {
+        let changed = self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
+            let changed = s.link_state != state;
             s.link_state = state;
             s.state_waker.wake();
+            changed
         });
+        if changed {
+            self.link_state_watch.sender().send(state);
+        }
+    }
+
+    /// A new receiver of the link state changes, unless there are
+    /// [`MAX_STATE_RECEIVERS`] already.
+    pub fn link_state_receiver(&self) -> Option<LinkStateReceiver<'d>> {
+        self.link_state_watch.receiver()
     }
 
     pub fn link_state(&self, cx: Option<&mut Context>) -> LinkState {
@@ -195,7 +824,13 @@ impl<'d> Runner<'d> {
     }
 
     pub fn set_operation_state(&self, state: OperationState) {
-        self.shared.lock(|s| {
+        self.set_operation_state_for(state, TransitionReason::Requested);
+    }
+
+    /// Set the operation state, reporting why it changed to the subscribers
+    /// of the diagnostic events.
+    pub fn set_operation_state_for(&self, state: OperationState, reason: TransitionReason) {
+        let prev_state = self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
             let prev_state = s.operation_state;
             if prev_state != state {
@@ -204,11 +839,99 @@ impl<'d> Runner<'d> {
                     prev_state, state
                 );
                 s.operation_state = state;
+                s.state_stats.record(state, Instant::now());
+                // The sockets are gone along with the module, which their
+                // owners are told on their next use
+                #[cfg(feature = "internal-network-stack")]
+                if state == OperationState::PowerDown {
+                    s.sockets.close_all();
+                }
                 s.state_waker.wake();
+                Some(prev_state)
             } else {
                 debug!("State: Operation state unchanged: {:?}", state);
+                None
             }
         });
+        if let Some(from) = prev_state {
+            self.operation_state_watch.sender().send(state);
+            self.diagnostics.publish(DiagnosticEvent::StateTransition {
+                from,
+                to: state,
+                reason,
+            });
+        }
+    }
+
+    /// A new receiver of the operation state changes, unless there are
+    /// [`MAX_STATE_RECEIVERS`] already.
+    pub fn operation_state_receiver(&self) -> Option<OperationStateReceiver<'d>> {
+        self.operation_state_watch.receiver()
+    }
+
+    /// Snapshot of the time spent in each operation state, up to now.
+    pub fn state_stats(&self) -> StateStats {
+        self.shared.lock(|s| {
+            let mut stats = s.borrow().state_stats.clone();
+            let entered = stats.last_transition;
+            stats.settle(Instant::now());
+            stats.last_transition = entered;
+            stats
+        })
+    }
+
+    pub fn reset_state_stats(&self) {
+        self.shared
+            .lock(|s| s.borrow_mut().state_stats.reset(Instant::now()));
+    }
+
+    pub fn signal_quality(&self) -> Option<SignalQuality> {
+        self.shared.lock(|s| s.borrow().signal_quality.clone())
+    }
+
+    pub fn is_signal_usable(&self, cx: Option<&mut Context>) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.signal_waker.register(cx.waker());
+            }
+            s.signal_usable
+        })
+    }
+
+    pub(crate) fn set_signal_quality(&self, quality: SignalQuality, usable: bool) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.signal_quality = Some(quality);
+            if s.signal_usable != usable {
+                info!("Signal {}", if usable { "usable" } else { "unusable" });
+                s.signal_usable = usable;
+                s.signal_waker.wake();
+            }
+        })
+    }
+
+    /// Wait for the signal to become usable or unusable, returning whether it
+    /// is usable now.
+    pub async fn wait_signal_change(&self) -> bool {
+        let old = self.is_signal_usable(None);
+
+        poll_fn(|cx| {
+            let usable = self.is_signal_usable(Some(cx));
+            if usable != old {
+                return Poll::Ready(usable);
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    pub fn shutdown_report(&self) -> ShutdownReport {
+        self.shared.lock(|s| s.borrow().shutdown)
+    }
+
+    pub(crate) fn update_shutdown_report(&self, f: impl FnOnce(&mut ShutdownReport)) {
+        self.shared.lock(|s| f(&mut s.borrow_mut().shutdown))
     }
 
     /// Request that the next power-down skips the graceful AT teardown and
@@ -220,6 +943,10 @@ impl<'d> Runner<'d> {
     }
 
     /// Read and clear the hard-reset request.
+    pub(crate) fn hard_reset_requested(&self) -> bool {
+        self.shared.lock(|s| s.borrow().hard_reset)
+    }
+
     pub(crate) fn take_hard_reset(&self) -> bool {
         self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
@@ -227,126 +954,1363 @@ impl<'d> Runner<'d> {
         })
     }
 
-    pub fn set_apn_config(&self, apn: Apn) {
+    pub fn set_firmware_install_state(&self, state: FirmwareInstallState) {
         self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
-            let prev_apn = s.apn_config.clone();
-            let changed = match (&prev_apn, &apn) {
-                (Apn::Given { name: n1, .. }, Apn::Given { name: n2, .. }) => n1 != n2,
-                (Apn::None, Apn::None) => false,
-                _ => true,
-            };
-
-            if changed {
-                info!("🔄 APN configuration changed: {:?} -> {:?}", prev_apn, apn);
-            } else {
-                debug!("State: APN configuration unchanged");
+            if s.firmware_install != state {
+                debug!("Firmware install state: {:?}", state);
+                s.firmware_install = state;
+                s.state_waker.wake();
+            }
+            if !matches!(state, FirmwareInstallState::Installing(_)) {
+                s.firmware_install_deadline = None;
             }
-            s.apn_config = apn;
         });
+
+        let progress = match state {
+            FirmwareInstallState::Idle => return,
+            FirmwareInstallState::Installing(percent) => Progress::Percent(percent),
+            FirmwareInstallState::Completed => Progress::Completed,
+            FirmwareInstallState::Failed(_) => Progress::Failed,
+        };
+        progress::publish(self.firmware_progress_watch, progress);
     }
 
-    pub fn get_apn_config(&self) -> Apn {
+    /// A new receiver of the progress of firmware installations, unless there
+    /// are [`MAX_STATE_RECEIVERS`] already.
+    pub fn firmware_progress_receiver(&self) -> Option<ProgressReceiver<'d>> {
+        self.firmware_progress_watch.receiver()
+    }
+
+    pub(crate) fn set_firmware_progress(&self, progress: Progress) {
+        progress::publish(self.firmware_progress_watch, progress);
+    }
+
+    /// A new receiver of the progress of file writes, unless there are
+    /// [`MAX_STATE_RECEIVERS`] already.
+    pub fn file_progress_receiver(&self) -> Option<ProgressReceiver<'d>> {
+        self.file_progress_watch.receiver()
+    }
+
+    pub(crate) fn set_file_progress(&self, progress: Progress) {
+        progress::publish(self.file_progress_watch, progress);
+    }
+
+    /// Note an installation started by the `Control`, to be given up at
+    /// `deadline`.
+    pub(crate) fn start_firmware_install(&self, deadline: Instant) {
         self.shared.lock(|s| {
-            let s = &mut *s.borrow_mut();
-            s.apn_config.clone()
-        })
+            s.borrow_mut().firmware_install_deadline = Some(deadline);
+        });
+        self.set_firmware_install_state(FirmwareInstallState::Installing(0));
     }
 
-    pub fn operation_state(&self, cx: Option<&mut Context>) -> OperationState {
+    /// Deadline of the running installation, `None` if it was not started
+    /// by the `Control`, eg. with a raw command.
+    pub(crate) fn firmware_install_deadline(&self) -> Option<Instant> {
+        self.shared.lock(|s| s.borrow().firmware_install_deadline)
+    }
+
+    pub fn firmware_install_state(&self, cx: Option<&mut Context>) -> FirmwareInstallState {
         self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
             if let Some(cx) = cx {
                 s.state_waker.register(cx.waker());
             }
-            s.operation_state
+            s.firmware_install
         })
     }
 
-    pub fn set_desired_state(&self, ps: OperationState) {
-        info!(
-            "🔄 Desired state changed: {:?} -> {:?}",
-            self.desired_state(None),
-            ps
-        );
+    pub(crate) fn set_recovery_action(&self, action: RecoveryAction) {
         self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
-            s.desired_state = ps;
+            warn!("AT interface hung, recovering with {:?}", action);
+            s.recoveries = s.recoveries.wrapping_add(1);
+            s.last_recovery = Some(action);
             s.state_waker.wake();
         });
+        self.diagnostics
+            .publish(DiagnosticEvent::ResetEscalation { rung: action });
     }
 
-    pub fn desired_state(&self, cx: Option<&mut Context>) -> OperationState {
+    /// Number of recoveries of a hung AT interface so far, and the last step
+    /// of the reset ladder taken.
+    pub fn recovery_action(&self, cx: Option<&mut Context>) -> (u32, Option<RecoveryAction>) {
         self.shared.lock(|s| {
             let s = &mut *s.borrow_mut();
             if let Some(cx) = cx {
                 s.state_waker.register(cx.waker());
             }
-            s.desired_state
+            (s.recoveries, s.last_recovery)
         })
     }
 
-    pub async fn wait_for_link_state(&self, ls: LinkState) {
-        if self.link_state(None) == ls {
-            return;
-        }
+    pub async fn wait_recovery_action(&self) -> RecoveryAction {
+        let (old_recoveries, _) = self.recovery_action(None);
 
-        poll_fn(|cx| {
-            if self.link_state(Some(cx)) == ls {
-                return Poll::Ready(());
-            }
-            Poll::Pending
+        poll_fn(|cx| match self.recovery_action(Some(cx)) {
+            (recoveries, Some(action)) if recoveries != old_recoveries => Poll::Ready(action),
+            _ => Poll::Pending,
         })
         .await
     }
 
-    pub async fn wait_for_desired_state(&self, ps: OperationState) {
-        if self.desired_state(None) == ps {
-            return;
-        }
+    pub(crate) fn set_init_error(&self, error: Option<InitError>) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.init_error = error;
+            s.state_waker.wake();
+        });
+    }
 
-        poll_fn(|cx| {
-            if self.desired_state(Some(cx)) == ps {
-                return Poll::Ready(());
-            }
-            Poll::Pending
-        })
-        .await
+    pub fn init_error(&self) -> Option<InitError> {
+        self.shared.lock(|s| s.borrow().init_error)
     }
 
-    pub async fn wait_for_operation_state(&self, ps: OperationState) {
-        if self.operation_state(None) == ps {
-            return;
-        }
+    /// Record an error in the journal, dropping the oldest one if it is full.
+    pub(crate) fn record_error(&self, error: &Error) {
+        use core::fmt::Write;
 
-        poll_fn(|cx| {
-            if self.operation_state(Some(cx)) == ps {
-                return Poll::Ready(());
+        let mut record = ErrorRecord {
+            at_secs: Instant::now().as_secs(),
+            error: heapless::String::new(),
+        };
+        // Errors that don't fit are truncated
+        let _ = write!(Truncate(&mut record.error), "{:?}", error);
+
+        self.shared.lock(|s| {
+            let errors = &mut s.borrow_mut().recent_errors;
+            if errors.is_full() {
+                errors.pop_front();
             }
-            Poll::Pending
-        })
-        .await
+            let _ = errors.push_back(record);
+        });
     }
 
-    pub async fn wait_for_desired_state_change(&self) -> OperationState {
-        let old_desired = self.desired_state(None);
+    pub fn embedded_port_filtering(&self) -> Option<EmbeddedPortFilteringMode> {
+        self.shared.lock(|s| s.borrow().embedded_port_filtering)
+    }
 
-        poll_fn(|cx| {
-            let current_desired = self.desired_state(Some(cx));
-            if current_desired != old_desired {
-                return Poll::Ready(current_desired);
-            }
-            Poll::Pending
-        })
-        .await
+    #[cfg(feature = "ppp")]
+    pub(crate) fn set_embedded_port_filtering(&self, mode: Option<EmbeddedPortFilteringMode>) {
+        self.shared
+            .lock(|s| s.borrow_mut().embedded_port_filtering = mode);
     }
 
-    pub async fn wait_registration_change(&self) -> bool {
-        let old_state = self.is_registered(None);
+    pub(crate) fn record_urc_overflow(&self, lost: u64) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            let lost = u32::try_from(lost).unwrap_or(u32::MAX);
+            s.urc_overflows = s.urc_overflows.saturating_add(lost);
+        });
+    }
 
-        poll_fn(|cx| {
-            let current_state = self.is_registered(Some(cx));
-            if current_state != old_state {
+    /// Number of URCs lost so far, because the URC channel was full.
+    pub fn urc_overflow_count(&self) -> u32 {
+        self.shared.lock(|s| s.borrow().urc_overflows)
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn record_unknown_socket_urc(&self) -> u32 {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.unknown_socket_urcs = s.unknown_socket_urcs.saturating_add(1);
+            s.unknown_socket_urcs
+        })
+    }
+
+    /// Number of socket URCs so far for sockets the driver did not create,
+    /// eg. the BIP sockets of the SIM.
+    #[cfg(feature = "internal-network-stack")]
+    pub fn unknown_socket_urc_count(&self) -> u32 {
+        self.shared.lock(|s| s.borrow().unknown_socket_urcs)
+    }
+
+    pub(crate) fn set_extended_error(&self, report: ExtendedErrorReport) {
+        self.shared
+            .lock(|s| s.borrow_mut().extended_error = Some(report));
+    }
+
+    /// Last +CEER report, read after a failed attach, registration or context
+    /// activation.
+    pub fn extended_error(&self) -> Option<ExtendedErrorReport> {
+        self.shared.lock(|s| s.borrow().extended_error.clone())
+    }
+
+    pub(crate) fn set_indicators(&self, indicators: IndicatorControl) {
+        self.shared
+            .lock(|s| s.borrow_mut().indicators = Some(indicators));
+    }
+
+    pub(crate) fn update_indicators(&self, ev: &IndicatorEvent) {
+        self.shared.lock(|s| {
+            if let Some(indicators) = s.borrow_mut().indicators.as_mut() {
+                indicators.update(ev);
+            }
+        });
+    }
+
+    /// +CIND indicators of the module, as last read and updated by +CIEV
+    /// URCs since.
+    pub fn indicators(&self) -> Option<IndicatorControl> {
+        self.shared.lock(|s| s.borrow().indicators)
+    }
+
+    /// Last errors the runner ran into, oldest first.
+    pub fn recent_errors(&self) -> heapless::Vec<ErrorRecord, MAX_RECENT_ERRORS> {
+        self.shared
+            .lock(|s| s.borrow().recent_errors.iter().cloned().collect())
+    }
+
+    pub(crate) fn is_installing_firmware(&self, cx: Option<&mut Context>) -> bool {
+        matches!(
+            self.firmware_install_state(cx),
+            FirmwareInstallState::Installing(_)
+        )
+    }
+
+    pub(crate) fn set_scanning_operators(&self, scanning: bool) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.scanning_operators != scanning {
+                s.scanning_operators = scanning;
+                s.state_waker.wake();
+            }
+        });
+    }
+
+    pub fn is_scanning_operators(&self, cx: Option<&mut Context>) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.state_waker.register(cx.waker());
+            }
+            s.scanning_operators
+        })
+    }
+
+    pub async fn wait_for_operator_scan(&self, scanning: bool) {
+        if self.is_scanning_operators(None) == scanning {
+            return;
+        }
+
+        poll_fn(|cx| {
+            if self.is_scanning_operators(Some(cx)) == scanning {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Have the runner start a background operator scan. Returns `false` if
+    /// one is pending already.
+    pub(crate) fn request_operator_scan(&self) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.operator_scan == Some(OperatorScan::Pending) {
+                return false;
+            }
+            s.operator_scan = Some(OperatorScan::Pending);
+            s.operator_scan_requested = true;
+            s.operator_scan_abort = false;
+            s.operator_scan_waker.wake();
+            true
+        })
+    }
+
+    /// Abort the pending background operator scan. Returns `false` if there
+    /// is none.
+    pub(crate) fn abort_operator_scan(&self) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.operator_scan != Some(OperatorScan::Pending) {
+                return false;
+            }
+            if s.operator_scan_requested {
+                // Not started by the runner yet
+                s.operator_scan_requested = false;
+                s.operator_scan = Some(OperatorScan::Failed(OperatorScanError::Aborted));
+            } else {
+                s.operator_scan_abort = true;
+                s.operator_scan_waker.wake();
+            }
+            true
+        })
+    }
+
+    /// Wait for a background operator scan to be requested, and take the
+    /// request.
+    pub(crate) async fn wait_operator_scan_request(&self) {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if core::mem::take(&mut s.operator_scan_requested) {
+                    return Poll::Ready(());
+                }
+                s.operator_scan_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// Wait for the running background operator scan to be aborted.
+    pub(crate) async fn wait_operator_scan_abort(&self) {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if core::mem::take(&mut s.operator_scan_abort) {
+                    return Poll::Ready(());
+                }
+                s.operator_scan_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    pub(crate) fn set_operator_scan(&self, scan: OperatorScan) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.operator_scan = Some(scan);
+            s.operator_scan_abort = false;
+        });
+    }
+
+    pub fn operator_scan(&self) -> Option<OperatorScan> {
+        self.shared.lock(|s| s.borrow().operator_scan.clone())
+    }
+
+    pub fn set_operator_selection(&self, plmn: Option<Plmn>) {
+        self.shared.lock(|s| {
+            s.borrow_mut().operator_selection = plmn;
+        });
+    }
+
+    pub fn operator_selection(&self) -> Option<Plmn> {
+        self.shared.lock(|s| s.borrow().operator_selection.clone())
+    }
+
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub(crate) fn set_psd_profile(&self, profile: Option<(ContextId, ProfileId)>) {
+        self.shared.lock(|s| {
+            s.borrow_mut().psd_profile = profile;
+        });
+    }
+
+    /// Forget the PSD profile `profile_id`, after the module deactivated it.
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub(crate) fn clear_psd_profile(&self, profile_id: ProfileId) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if matches!(s.psd_profile, Some((_, p)) if p == profile_id) {
+                s.psd_profile = None;
+            }
+        });
+    }
+
+    /// Forget the PSD profile of the context `cid`, after the network
+    /// deactivated it.
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub(crate) fn clear_psd_context(&self, cid: ContextId) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if matches!(s.psd_profile, Some((c, _)) if c == cid) {
+                s.psd_profile = None;
+            }
+        });
+    }
+
+    /// PSD profile activated for the context `cid`, if any
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub fn psd_profile(&self, cid: ContextId) -> Option<ProfileId> {
+        self.shared.lock(|s| match s.borrow().psd_profile {
+            Some((c, profile_id)) if c == cid => Some(profile_id),
+            _ => None,
+        })
+    }
+
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub(crate) fn set_psd_result(&self, result: u8) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.psd_result = Some(result);
+            s.psd_waker.wake();
+        });
+    }
+
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub(crate) fn clear_psd_result(&self) {
+        self.shared.lock(|s| {
+            s.borrow_mut().psd_result = None;
+        });
+    }
+
+    /// Wait for the `+UUPSDA` result of a PSD profile activation.
+    #[cfg(feature = "use-upsd-context-activation")]
+    pub(crate) async fn wait_psd_result(&self) -> u8 {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                match s.psd_result.take() {
+                    Some(result) => Poll::Ready(result),
+                    None => {
+                        s.psd_waker.register(cx.waker());
+                        Poll::Pending
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    pub fn identity(&self) -> Identity {
+        self.shared.lock(|s| s.borrow().identity.clone())
+    }
+
+    pub(crate) fn update_identity(&self, f: impl FnOnce(&mut Identity)) {
+        self.shared.lock(|s| f(&mut s.borrow_mut().identity))
+    }
+
+    /// Note the ICCID read from the SIM, returning whether it belongs to
+    /// another SIM than the last one seen. The IMSI of the old SIM is cleared
+    /// on a swap.
+    pub(crate) fn set_iccid(&self, iccid: Option<u128>) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.identity.iccid = iccid;
+
+            let Some(iccid) = iccid else {
+                return false;
+            };
+            let swapped = s.last_iccid.is_some_and(|last| last != iccid);
+            s.last_iccid = Some(iccid);
+
+            if swapped {
+                warn!("SIM swapped, ICCID now {}", iccid);
+                s.identity.imsi = None;
+                s.own_number = None;
+                s.sim_changes = s.sim_changes.wrapping_add(1);
+                s.state_waker.wake();
+            }
+            swapped
+        })
+    }
+
+    pub(crate) fn set_sim_state(&self, state: SimState) {
+        let changed = self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            let changed = s.sim_state != state;
+            s.sim_state = state;
+            s.state_waker.wake();
+            changed
+        });
+        if changed {
+            self.sim_state_watch.sender().send(state);
+        }
+    }
+
+    pub fn sim_state(&self, cx: Option<&mut Context>) -> SimState {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.state_waker.register(cx.waker());
+            }
+            s.sim_state
+        })
+    }
+
+    /// A new receiver of the SIM state changes, unless there are
+    /// [`MAX_STATE_RECEIVERS`] already.
+    pub fn sim_state_receiver(&self) -> Option<SimStateReceiver<'d>> {
+        self.sim_state_watch.receiver()
+    }
+
+    /// Wait until the SIM is no longer known to be absent.
+    pub(crate) async fn wait_sim_inserted(&self) {
+        poll_fn(|cx| match self.sim_state(Some(cx)) {
+            SimState::Absent => Poll::Pending,
+            _ => Poll::Ready(()),
+        })
+        .await
+    }
+
+    /// Cached MSISDN, `None` if it was not read from the current SIM yet.
+    pub(crate) fn own_number(&self) -> Option<Option<heapless::String<32>>> {
+        self.shared.lock(|s| s.borrow().own_number.clone())
+    }
+
+    pub(crate) fn set_own_number(&self, number: Option<heapless::String<32>>) {
+        self.shared
+            .lock(|s| s.borrow_mut().own_number = Some(number));
+    }
+
+    pub(crate) fn last_iccid(&self) -> Option<u128> {
+        self.shared.lock(|s| s.borrow().last_iccid)
+    }
+
+    /// Have the runner check which SIM is inserted.
+    pub(crate) fn request_sim_check(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.sim_check = true;
+            s.state_waker.wake();
+        });
+    }
+
+    pub(crate) async fn wait_sim_check(&self) {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if core::mem::take(&mut s.sim_check) {
+                    return Poll::Ready(());
+                }
+                s.state_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// Whether the module is in PSM deep sleep.
+    pub fn is_sleeping(&self) -> bool {
+        self.shared.lock(|s| s.borrow().sleeping)
+    }
+
+    pub(crate) fn set_sleeping(&self, sleeping: bool) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.sleeping != sleeping {
+                info!("Module {} PSM", if sleeping { "entered" } else { "left" });
+            }
+            s.sleeping = sleeping;
+            s.sleep_suspected = false;
+            if !sleeping {
+                s.awake_waker.wake();
+            }
+            s.psm_waker.wake();
+        });
+    }
+
+    /// Have the runner check whether the module sleeps, after a command timed
+    /// out.
+    pub(crate) fn suspect_sleep(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if !s.sleeping {
+                s.sleep_suspected = true;
+                s.psm_waker.wake();
+            }
+        });
+    }
+
+    /// Wait for the module to be awake, having the runner wake it if it
+    /// sleeps. Fails with [`Error::Busy`] if [`MAX_PENDING_WAKE`] commands are
+    /// waiting already.
+    pub(crate) async fn wait_awake(&self) -> Result<(), Error> {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if !s.sleeping {
+                return Ok(());
+            }
+            if s.pending_wake >= MAX_PENDING_WAKE {
+                return Err(Error::Busy);
+            }
+            s.pending_wake += 1;
+            s.psm_waker.wake();
+            Ok(())
+        })?;
+
+        // Leaves the queue even if the command is dropped meanwhile
+        let _pending = PendingWake(self);
+
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if !s.sleeping {
+                    return Poll::Ready(());
+                }
+                s.awake_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Wait for the runner to have something to do about PSM.
+    pub(crate) async fn wait_psm_action(&self) -> PsmAction {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if s.sleeping && s.pending_wake > 0 {
+                    return Poll::Ready(PsmAction::Wake);
+                }
+                if core::mem::take(&mut s.sleep_suspected) {
+                    return Poll::Ready(PsmAction::CheckSleep);
+                }
+                s.psm_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// Number of SIM swaps detected so far. After a swap, the configuration
+    /// of the old SIM, eg. the APN, may not fit the new one.
+    pub fn sim_changes(&self, cx: Option<&mut Context>) -> u32 {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.state_waker.register(cx.waker());
+            }
+            s.sim_changes
+        })
+    }
+
+    pub async fn wait_sim_change(&self) -> u32 {
+        let old_changes = self.sim_changes(None);
+
+        poll_fn(|cx| {
+            let changes = self.sim_changes(Some(cx));
+            if changes != old_changes {
+                return Poll::Ready(changes);
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Start a new modem session after a successful (re-)initialization. Any
+    /// module side state tracked from the previous session is discarded, so
+    /// users waiting on it see the connection as closed rather than stale.
+    pub(crate) fn start_session(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.session > 0 {
+                warn!("Modem was reset, discarding state of session {}", s.session);
+                // Sockets of the previous session would fail one by one, or
+                // worse, their handles be taken by new ones
+                #[cfg(feature = "internal-network-stack")]
+                s.sockets.close_all();
+            }
+            s.session = s.session.wrapping_add(1);
+            s.shutdown = ShutdownReport::new();
+            s.signal_quality = None;
+            s.signal_usable = false;
+            s.signal_waker.wake();
+            s.indicators = None;
+            // A background operator scan does not survive the reset
+            if s.operator_scan == Some(OperatorScan::Pending) {
+                s.operator_scan = Some(OperatorScan::Failed(OperatorScanError::Aborted));
+            }
+            s.operator_scan_requested = false;
+            s.operator_scan_abort = false;
+            // Freshly initialized, so awake
+            s.sleeping = false;
+            s.sleep_suspected = false;
+            s.awake_waker.wake();
+
+            #[cfg(feature = "mqtt")]
+            {
+                s.mqtt = MqttState {
+                    connected: false,
+                    unread: 0,
+                    last_result: None,
+                };
+                s.mqtt_waker.wake();
+            }
+            #[cfg(feature = "http")]
+            {
+                s.http_response = None;
+                s.http_waker.wake();
+            }
+            s.ringing = false;
+            // The context is activated again, the address is read then
+            s.local_addr = None;
+            s.local_addr_refresh = false;
+            s.local_addr_waker.wake();
+            #[cfg(feature = "use-upsd-context-activation")]
+            {
+                s.psd_profile = None;
+                s.psd_result = None;
+            }
+            s.state_waker.wake();
+        });
+    }
+
+    /// Number of modem sessions so far. A change means the modem has been
+    /// reset, and module side resources have to be recreated.
+    pub fn session(&self, cx: Option<&mut Context>) -> u32 {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.state_waker.register(cx.waker());
+            }
+            s.session
+        })
+    }
+
+    pub async fn wait_session_change(&self) -> u32 {
+        let old_session = self.session(None);
+
+        poll_fn(|cx| {
+            let session = self.session(Some(cx));
+            if session != old_session {
+                return Poll::Ready(session);
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    pub fn set_apn_config(&self, apn: Apn) {
+        // A single candidate always fits
+        let _ = self.set_apn_candidates(&[apn]);
+    }
+
+    /// Set the APNs to try in order, if the previous one fails to attach or
+    /// activate the context.
+    pub fn set_apn_candidates(&self, apns: &[Apn]) -> Result<(), crate::error::Error> {
+        let apns = heapless::Vec::from_slice(apns).map_err(|_| crate::error::Error::Overflow)?;
+
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            let changed = s.apn_candidates.len() != apns.len()
+                || s.apn_candidates
+                    .iter()
+                    .zip(apns.iter())
+                    .any(|(a, b)| !a.same_as(b));
+
+            if changed {
+                info!(
+                    "🔄 APN configuration changed: {:?} -> {:?}",
+                    s.apn_candidates.as_slice(),
+                    apns.as_slice()
+                );
+                s.apn_index = 0;
+                #[cfg(not(feature = "use-upsd-context-activation"))]
+                {
+                    s.auth_type = None;
+                }
+            } else {
+                debug!("State: APN configuration unchanged");
+            }
+            s.apn_candidates = apns;
+        });
+        Ok(())
+    }
+
+    /// The APN candidate in use.
+    pub fn get_apn_config(&self) -> Apn {
+        self.shared.lock(|s| {
+            let s = &*s.borrow();
+            match s.apn_candidates.get(s.apn_index) {
+                Some(apn) => apn.clone(),
+                #[cfg(not(feature = "automatic-apn"))]
+                None => Apn::None,
+                #[cfg(any(feature = "automatic-apn"))]
+                None => Apn::Automatic,
+            }
+        })
+    }
+
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    pub(crate) fn auth_type(&self) -> Option<AuthenticationType> {
+        self.shared.lock(|s| s.borrow().auth_type)
+    }
+
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    pub(crate) fn set_auth_type(&self, auth_type: Option<AuthenticationType>) {
+        self.shared.lock(|s| s.borrow_mut().auth_type = auth_type);
+    }
+
+    /// Index of the APN candidate in use.
+    pub fn apn_index(&self) -> usize {
+        self.shared.lock(|s| s.borrow().apn_index)
+    }
+
+    /// Fall back to the next APN candidate, wrapping around after the last.
+    /// Returns `false` if there is no other candidate to try.
+    pub(crate) fn next_apn(&self) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.apn_candidates.len() < 2 {
+                return false;
+            }
+            s.apn_index = (s.apn_index + 1) % s.apn_candidates.len();
+            info!(
+                "Falling back to APN candidate {}: {:?}",
+                s.apn_index, s.apn_candidates[s.apn_index]
+            );
+            true
+        })
+    }
+
+    #[cfg(feature = "http")]
+    pub(crate) fn set_http_response(&self, response: HttpResponse) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.http_response.replace(response);
+            s.http_waker.wake();
+        });
+    }
+
+    #[cfg(feature = "http")]
+    pub(crate) fn clear_http_response(&self) {
+        self.shared.lock(|s| {
+            s.borrow_mut().http_response = None;
+        });
+    }
+
+    #[cfg(feature = "http")]
+    /// Wait for the `+UUHTTPCR` result of the given HTTP profile.
+    pub(crate) async fn wait_http_response(&self, profile_id: u8) -> HttpResponse {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                match s.http_response.take() {
+                    Some(res) if res.profile_id == profile_id => Poll::Ready(res),
+                    other => {
+                        s.http_response = other;
+                        s.http_waker.register(cx.waker());
+                        Poll::Pending
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    #[cfg(feature = "mqtt")]
+    pub(crate) fn set_mqtt_result(&self, result: MqttCommandResult) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            match result.op_code {
+                0 => s.mqtt.connected = false,
+                1 => s.mqtt.connected = result.result == 0,
+                _ => {}
+            }
+            s.mqtt.last_result.replace(result);
+            s.mqtt_waker.wake();
+        });
+    }
+
+    #[cfg(feature = "mqtt")]
+    pub(crate) fn set_mqtt_unread(&self, unread: u16) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.mqtt.unread = unread;
+            s.mqtt_waker.wake();
+        });
+    }
+
+    #[cfg(feature = "mqtt")]
+    /// Account for a message read from the modem.
+    pub(crate) fn mqtt_message_read(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.mqtt.unread = s.mqtt.unread.saturating_sub(1);
+        });
+    }
+
+    #[cfg(feature = "mqtt")]
+    pub(crate) fn clear_mqtt_result(&self) {
+        self.shared.lock(|s| {
+            s.borrow_mut().mqtt.last_result = None;
+        });
+    }
+
+    #[cfg(feature = "mqtt")]
+    pub fn mqtt_connected(&self, cx: Option<&mut Context>) -> bool {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.mqtt_waker.register(cx.waker());
+            }
+            s.mqtt.connected
+        })
+    }
+
+    #[cfg(feature = "mqtt")]
+    pub fn mqtt_unread(&self, cx: Option<&mut Context>) -> u16 {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.mqtt_waker.register(cx.waker());
+            }
+            s.mqtt.unread
+        })
+    }
+
+    pub(crate) fn set_ringing(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.ringing = true;
+            s.ring_waker.wake();
+        });
+    }
+
+    /// Wait for a RING or +CLIP URC, announcing an incoming call.
+    pub(crate) async fn wait_ringing(&self) {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if core::mem::take(&mut s.ringing) {
+                    return Poll::Ready(());
+                }
+                s.ring_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// Account a failed network attach or context activation, see
+    /// [`AttachBackoff::on_failure`].
+    pub(crate) fn attach_failed(
+        &self,
+        policy: &RetryPolicy,
+        now: Instant,
+        cause: Option<RejectCause>,
+    ) -> Result<Instant, RejectCause> {
+        self.shared
+            .lock(|s| s.borrow_mut().attach_backoff.on_failure(policy, now, cause))
+    }
+
+    pub(crate) fn attach_succeeded(&self) {
+        self.shared
+            .lock(|s| s.borrow_mut().attach_backoff.on_success());
+    }
+
+    pub(crate) fn next_attach_attempt(&self) -> Option<Instant> {
+        self.shared
+            .lock(|s| s.borrow().attach_backoff.next_attempt())
+    }
+
+    /// Cause of the rejection that stopped the network attach, if any.
+    pub fn attach_rejection(&self) -> Option<RejectCause> {
+        self.shared.lock(|s| s.borrow().attach_backoff.rejection())
+    }
+
+    pub(crate) fn clear_attach_rejection(&self) {
+        self.shared
+            .lock(|s| s.borrow_mut().attach_backoff.clear_rejection());
+    }
+
+    /// Failed network attach and context activation attempts since the
+    /// runner was created.
+    pub fn attach_failures(&self) -> u32 {
+        self.shared
+            .lock(|s| s.borrow().attach_backoff.total_failures())
+    }
+
+    /// The next cached address of `name`, rotating through the addresses it
+    /// resolved to.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn next_dns_address(
+        &self,
+        name: &str,
+        addr_type: embedded_nal_async::AddrType,
+    ) -> Option<core::net::IpAddr> {
+        self.shared.lock(|s| {
+            s.borrow_mut()
+                .dns_cache
+                .next(name, addr_type, Instant::now())
+        })
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn cache_dns_addresses(&self, name: &str, addresses: &[core::net::IpAddr]) {
+        self.shared.lock(|s| {
+            s.borrow_mut()
+                .dns_cache
+                .insert(name, addresses, Instant::now())
+        });
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn socket_port_in_use(&self, protocol: &SocketProtocol, port: u16) -> bool {
+        self.shared
+            .lock(|s| s.borrow().sockets.port_in_use(protocol, port))
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn register_socket(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        protocol: SocketProtocol,
+        local_port: Option<u16>,
+    ) {
+        self.shared
+            .lock(|s| s.borrow_mut().sockets.insert(handle, protocol, local_port));
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn is_socket_known(&self, handle: ublox_sockets::SocketHandle) -> bool {
+        self.shared.lock(|s| s.borrow().sockets.contains(handle))
+    }
+
+    /// Note the socket `handle` closed by the peer. It is kept, marked closed,
+    /// until closed by the application. Returns `false` if it is not known.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn close_remote_socket(&self, handle: ublox_sockets::SocketHandle) -> bool {
+        self.shared.lock(|s| s.borrow_mut().sockets.close(handle))
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn is_socket_closed(&self, handle: ublox_sockets::SocketHandle) -> bool {
+        self.shared.lock(|s| s.borrow().sockets.is_closed(handle))
+    }
+
+    /// Wait for data to read on the socket `handle`, or for it to close. A
+    /// socket not known is ready straight away.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) async fn wait_socket_readable(&self, handle: ublox_sockets::SocketHandle) {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                if s.borrow_mut().sockets.poll_readable(handle, cx) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+        })
+        .await
+    }
+
+    /// Bytes buffered by the module for `handle`, `None` if not known since
+    /// the last read.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn socket_available(&self, handle: ublox_sockets::SocketHandle) -> Option<usize> {
+        self.shared.lock(|s| s.borrow().sockets.available(handle))
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn set_socket_available(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        available: Option<usize>,
+    ) {
+        self.shared
+            .lock(|s| s.borrow_mut().sockets.set_available(handle, available));
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn record_socket_rx(&self, handle: ublox_sockets::SocketHandle, read: usize) {
+        self.shared
+            .lock(|s| s.borrow_mut().sockets.record_rx(handle, read));
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn record_socket_tx(
+        &self,
+        handle: ublox_sockets::SocketHandle,
+        len: usize,
+        sent: usize,
+    ) {
+        self.shared
+            .lock(|s| s.borrow_mut().sockets.record_tx(handle, len, sent));
+    }
+
+    /// Traffic counters of the sockets tracked by the driver.
+    #[cfg(feature = "internal-network-stack")]
+    pub fn socket_stats(&self) -> SocketSetStats {
+        self.shared.lock(|s| s.borrow().sockets.stats())
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn hex_mode(&self) -> bool {
+        self.shared.lock(|s| s.borrow().hex_mode)
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn set_hex_mode(&self, hex_mode: bool) {
+        self.shared.lock(|s| s.borrow_mut().hex_mode = hex_mode);
+    }
+
+    /// Have the dropped socket `handle` closed by the runner, unless it is gone
+    /// already.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn defer_socket_close(&self, handle: ublox_sockets::SocketHandle) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.sockets.defer_close(handle) {
+                s.state_waker.wake();
+            }
+        });
+    }
+
+    /// The socket to poll for buffered data next, and the number of sockets.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn next_socket_to_poll(&self) -> Option<(ublox_sockets::SocketHandle, usize)> {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            let handle = s.sockets.next_to_poll()?;
+            Some((handle, s.sockets.len()))
+        })
+    }
+
+    /// Have the runner ask every socket for its buffered data, eg. as the URC
+    /// channel overflowed and a +UUSORD may have been lost.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn request_socket_poll(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.sockets.request_poll() {
+                s.state_waker.wake();
+            }
+        });
+    }
+
+    /// Wait for [`Self::request_socket_poll`], returning the sockets to ask.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) async fn wait_socket_poll(
+        &self,
+    ) -> heapless::Vec<ublox_sockets::SocketHandle, MAX_SOCKETS> {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if s.sockets.take_poll_request() {
+                    return Poll::Ready(s.sockets.handles());
+                }
+                s.state_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// Wait for a dropped socket to close.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) async fn wait_socket_close(&self) -> ublox_sockets::SocketHandle {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if let Some(handle) = s.sockets.take_pending_close() {
+                    return Poll::Ready(handle);
+                }
+                s.state_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    /// Forget the socket `handle` once closed. Returns `false` if it was not
+    /// known.
+    #[cfg(feature = "internal-network-stack")]
+    pub(crate) fn unregister_socket(&self, handle: ublox_sockets::SocketHandle) -> bool {
+        self.shared.lock(|s| s.borrow_mut().sockets.remove(handle))
+    }
+
+    /// Mark the security profile `profile_id` as configured. Returns `false`
+    /// for ids out of the range 0-4.
+    pub(crate) fn register_security_profile(&self, profile_id: SecurityProfileId) -> bool {
+        if profile_id.0 > MAX_SECURITY_PROFILE_ID {
+            return false;
+        }
+        self.shared
+            .lock(|s| s.borrow_mut().security_profiles |= 1 << profile_id.0);
+        true
+    }
+
+    pub(crate) fn has_security_profile(&self, profile_id: SecurityProfileId) -> bool {
+        profile_id.0 <= MAX_SECURITY_PROFILE_ID
+            && self
+                .shared
+                .lock(|s| s.borrow().security_profiles & (1 << profile_id.0) != 0)
+    }
+
+    pub(crate) fn flow_control_fallback(&self) -> bool {
+        self.shared.lock(|s| s.borrow().flow_control_fallback)
+    }
+
+    pub(crate) fn set_flow_control_fallback(&self, fallback: bool) {
+        self.shared
+            .lock(|s| s.borrow_mut().flow_control_fallback = fallback);
+    }
+
+    /// Address of the data context, and the number of times it changed.
+    pub fn local_addr(&self, cx: Option<&mut Context>) -> (Option<IpAddr>, u32) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.local_addr_waker.register(cx.waker());
+            }
+            (s.local_addr, s.local_addr_changes)
+        })
+    }
+
+    /// Keep the address read with +CGPADDR, counting it as a change if it
+    /// differs from the previous one.
+    pub(crate) fn set_local_addr(&self, addr: Option<IpAddr>) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if s.local_addr == addr {
+                return;
+            }
+            if let Some(addr) = addr {
+                info!("Local address is now {}", addr);
+                s.local_addr_changes = s.local_addr_changes.wrapping_add(1);
+            }
+            s.local_addr = addr;
+            s.local_addr_waker.wake();
+        });
+    }
+
+    /// Network configuration of the PPP link, as handed to the stack.
+    #[cfg(feature = "ppp")]
+    pub fn network_config(&self) -> Option<embassy_net::StaticConfigV4> {
+        self.shared.lock(|s| s.borrow().network_config.clone())
+    }
+
+    #[cfg(feature = "ppp")]
+    pub(crate) fn set_network_config(&self, config: Option<embassy_net::StaticConfigV4>) {
+        self.shared.lock(|s| {
+            s.borrow_mut().network_config = config;
+        });
+    }
+
+    /// Have the runner read the address of the data context again.
+    pub(crate) fn request_local_addr_refresh(&self) {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.local_addr_refresh = true;
+            s.local_addr_refresh_waker.wake();
+        });
+    }
+
+    /// Wait for a refresh of the address to be requested, and take the
+    /// request.
+    pub(crate) async fn wait_local_addr_refresh(&self) {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                if core::mem::take(&mut s.local_addr_refresh) {
+                    return Poll::Ready(());
+                }
+                s.local_addr_refresh_waker.register(cx.waker());
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    #[cfg(feature = "mqtt")]
+    /// Wait for the `+UUMQTTC` result of the given MQTT action.
+    pub(crate) async fn wait_mqtt_result(&self, op_code: u8) -> MqttCommandResult {
+        poll_fn(|cx| {
+            self.shared.lock(|s| {
+                let s = &mut *s.borrow_mut();
+                match s.mqtt.last_result.take() {
+                    Some(res) if res.op_code == op_code => Poll::Ready(res),
+                    other => {
+                        s.mqtt.last_result = other;
+                        s.mqtt_waker.register(cx.waker());
+                        Poll::Pending
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    pub fn operation_state(&self, cx: Option<&mut Context>) -> OperationState {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.state_waker.register(cx.waker());
+            }
+            s.operation_state
+        })
+    }
+
+    pub fn set_desired_state(&self, ps: OperationState) {
+        info!(
+            "🔄 Desired state changed: {:?} -> {:?}",
+            self.desired_state(None),
+            ps
+        );
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            s.desired_state = ps;
+            s.state_waker.wake();
+        });
+    }
+
+    pub fn desired_state(&self, cx: Option<&mut Context>) -> OperationState {
+        self.shared.lock(|s| {
+            let s = &mut *s.borrow_mut();
+            if let Some(cx) = cx {
+                s.state_waker.register(cx.waker());
+            }
+            s.desired_state
+        })
+    }
+
+    pub async fn wait_for_link_state(&self, ls: LinkState) {
+        if self.link_state(None) == ls {
+            return;
+        }
+
+        poll_fn(|cx| {
+            if self.link_state(Some(cx)) == ls {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    pub async fn wait_for_desired_state(&self, ps: OperationState) {
+        if self.desired_state(None) == ps {
+            return;
+        }
+
+        poll_fn(|cx| {
+            if self.desired_state(Some(cx)) == ps {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    pub async fn wait_for_operation_state(&self, ps: OperationState) {
+        if self.operation_state(None) == ps {
+            return;
+        }
+
+        poll_fn(|cx| {
+            if self.operation_state(Some(cx)) == ps {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    pub async fn wait_for_desired_state_change(&self) -> OperationState {
+        let old_desired = self.desired_state(None);
+
+        poll_fn(|cx| {
+            let current_desired = self.desired_state(Some(cx));
+            if current_desired != old_desired {
+                return Poll::Ready(current_desired);
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    pub async fn wait_registration_change(&self) -> bool {
+        let old_state = self.is_registered(None);
+
+        poll_fn(|cx| {
+            let current_state = self.is_registered(Some(cx));
+            if current_state != old_state {
                 return Poll::Ready(current_state);
             }
             Poll::Pending
@@ -445,3 +2409,375 @@ impl<'d, const URC_CAPACITY: usize> Device<'d, URC_CAPACITY> {
         .await
     }
 }
+
+/// Writes as much as fits into a string, and silently drops the rest.
+struct Truncate<'a, const N: usize>(&'a mut heapless::String<N>);
+
+impl<const N: usize> core::fmt::Write for Truncate<'_, N> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            if self.0.push(c).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operation_state_order() {
+        // The discriminants from before airplane mode are kept
+        assert_eq!(OperationState::PowerDown as u8, 0);
+        assert_eq!(OperationState::Initialized as u8, 1);
+        assert_eq!(OperationState::Connected as u8, 2);
+        assert_eq!(OperationState::DataEstablished as u8, 3);
+        assert_eq!(OperationState::AirplaneMode as u8, 4);
+
+        assert!(OperationState::Initialized < OperationState::AirplaneMode);
+        assert!(OperationState::AirplaneMode < OperationState::Connected);
+        assert!(OperationState::Connected < OperationState::DataEstablished);
+    }
+
+    #[test]
+    fn state_stats_accumulate() {
+        let at = |secs| Instant::from_ticks(0) + Duration::from_secs(secs);
+        let mut stats = StateStats::new();
+
+        stats.record(OperationState::Initialized, at(5));
+        stats.record(OperationState::Connected, at(15));
+        stats.record(OperationState::Initialized, at(45));
+        stats.record(OperationState::Connected, at(50));
+        stats.settle(at(60));
+
+        assert_eq!(stats.transitions(), 4);
+        assert_eq!(
+            stats.time_in(OperationState::PowerDown),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            stats.time_in(OperationState::Initialized),
+            Duration::from_secs(15)
+        );
+        assert_eq!(
+            stats.time_in(OperationState::Connected),
+            Duration::from_secs(40)
+        );
+
+        stats.reset(at(60));
+        assert_eq!(stats.transitions(), 0);
+        assert_eq!(stats.state(), OperationState::Connected);
+        assert_eq!(
+            stats.time_in(OperationState::Connected),
+            Duration::from_secs(0)
+        );
+    }
+
+    #[test]
+    fn wake_queue_is_bounded() {
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::Waker;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Awake, so nothing waits
+        assert!(pin!(ch.wait_awake()).poll(&mut cx).is_ready());
+
+        ch.set_sleeping(true);
+        let mut waiting = [(); MAX_PENDING_WAKE].map(|_| Box::pin(ch.wait_awake()));
+        for fut in waiting.iter_mut() {
+            assert!(fut.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(
+            pin!(ch.wait_awake()).poll(&mut cx),
+            Poll::Ready(Err(Error::Busy))
+        );
+        assert_eq!(
+            embassy_futures::block_on(ch.wait_psm_action()),
+            PsmAction::Wake
+        );
+
+        ch.set_sleeping(false);
+        for fut in waiting.iter_mut() {
+            assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        }
+        assert_eq!(ch.shared.lock(|s| s.borrow().pending_wake), 0);
+    }
+
+    #[test]
+    fn operation_state_receivers() {
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+
+        let mut receivers = [(); MAX_STATE_RECEIVERS].map(|_| ch.operation_state_receiver());
+        assert!(receivers.iter().all(Option::is_some));
+        assert!(ch.operation_state_receiver().is_none());
+
+        ch.set_operation_state(OperationState::Initialized);
+        ch.set_operation_state(OperationState::Connected);
+        for receiver in receivers.iter_mut().flatten() {
+            assert_eq!(
+                embassy_futures::block_on(receiver.changed()),
+                OperationState::Connected
+            );
+            assert_eq!(receiver.try_changed(), None);
+        }
+
+        receivers[0].take();
+        assert!(ch.operation_state_receiver().is_some());
+    }
+
+    #[test]
+    fn sim_insertion() {
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::Waker;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut receiver = ch.sim_state_receiver().unwrap();
+
+        ch.set_sim_state(SimState::Absent);
+        assert_eq!(
+            embassy_futures::block_on(receiver.changed()),
+            SimState::Absent
+        );
+        let mut inserted = pin!(ch.wait_sim_inserted());
+        assert!(inserted.as_mut().poll(&mut cx).is_pending());
+
+        ch.set_sim_state(SimState::Present);
+        assert!(inserted.as_mut().poll(&mut cx).is_ready());
+        assert_eq!(
+            embassy_futures::block_on(receiver.changed()),
+            SimState::Present
+        );
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    #[test]
+    fn deferred_socket_close() {
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::Waker;
+
+        use ublox_sockets::SocketHandle;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        ch.register_socket(SocketHandle(2), SocketProtocol::TCP, Some(6000));
+        {
+            let mut close = pin!(ch.wait_socket_close());
+            assert!(close.as_mut().poll(&mut cx).is_pending());
+
+            // Gone with a reset of the module, nothing to close
+            ch.defer_socket_close(SocketHandle(3));
+            assert!(close.as_mut().poll(&mut cx).is_pending());
+
+            ch.defer_socket_close(SocketHandle(2));
+            assert_eq!(close.as_mut().poll(&mut cx), Poll::Ready(SocketHandle(2)));
+        }
+        assert!(ch.socket_port_in_use(&SocketProtocol::TCP, 6000));
+        assert!(ch.unregister_socket(SocketHandle(2)));
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    #[test]
+    fn socket_poll_request() {
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::Waker;
+
+        use ublox_sockets::SocketHandle;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut poll = pin!(ch.wait_socket_poll());
+        // Without sockets, there is nothing to ask
+        ch.request_socket_poll();
+        assert!(poll.as_mut().poll(&mut cx).is_pending());
+
+        ch.register_socket(SocketHandle(0), SocketProtocol::TCP, None);
+        ch.register_socket(SocketHandle(3), SocketProtocol::UDP, None);
+        ch.request_socket_poll();
+        match poll.as_mut().poll(&mut cx) {
+            Poll::Ready(handles) => {
+                assert_eq!(handles.as_slice(), &[SocketHandle(0), SocketHandle(3)])
+            }
+            Poll::Pending => panic!("no poll requested"),
+        }
+    }
+
+    /// Sockets of the module are closed by a reset, rather than left to fail
+    /// the next command one by one.
+    #[cfg(feature = "internal-network-stack")]
+    #[test]
+    fn sockets_closed_on_reset() {
+        use ublox_sockets::SocketHandle;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+
+        ch.start_session();
+        ch.register_socket(SocketHandle(0), SocketProtocol::TCP, Some(6000));
+        ch.set_socket_available(SocketHandle(0), Some(10));
+        ch.register_socket(SocketHandle(1), SocketProtocol::TCP, None);
+        ch.defer_socket_close(SocketHandle(1));
+
+        ch.start_session();
+        assert!(ch.is_socket_closed(SocketHandle(0)));
+        assert_eq!(ch.socket_available(SocketHandle(0)), None);
+        assert!(!ch.socket_port_in_use(&SocketProtocol::TCP, 6000));
+        // There is no closing a socket of the previous session
+        assert!(!ch.is_socket_known(SocketHandle(1)));
+
+        // Powering down closes them just as well
+        ch.set_operation_state(OperationState::Initialized);
+        ch.register_socket(SocketHandle(0), SocketProtocol::UDP, None);
+        ch.set_operation_state(OperationState::PowerDown);
+        assert!(ch.is_socket_closed(SocketHandle(0)));
+    }
+
+    #[test]
+    fn firmware_install_progress() {
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let mut receiver = ch.firmware_progress_receiver().unwrap();
+
+        ch.set_firmware_install_state(FirmwareInstallState::Installing(0));
+        assert_eq!(receiver.try_changed(), Some(Progress::Percent(0)));
+        ch.set_firmware_install_state(FirmwareInstallState::Installing(40));
+        assert_eq!(receiver.try_changed(), Some(Progress::Percent(40)));
+        ch.set_firmware_install_state(FirmwareInstallState::Failed(FirmwareInstallError::Timeout));
+        assert_eq!(receiver.try_changed(), Some(Progress::Failed));
+
+        // Back to idle keeps the outcome
+        ch.set_firmware_install_state(FirmwareInstallState::Idle);
+        assert_eq!(receiver.try_changed(), None);
+    }
+
+    #[test]
+    fn security_profiles() {
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+
+        assert!(!ch.has_security_profile(SecurityProfileId(2)));
+        assert!(ch.register_security_profile(SecurityProfileId(2)));
+        assert!(ch.has_security_profile(SecurityProfileId(2)));
+        assert!(!ch.has_security_profile(SecurityProfileId(0)));
+
+        assert!(ch.register_security_profile(SecurityProfileId(4)));
+        assert!(!ch.register_security_profile(SecurityProfileId(5)));
+        assert!(!ch.has_security_profile(SecurityProfileId(5)));
+    }
+
+    #[test]
+    fn own_number_cleared_on_swap() {
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+
+        assert!(!ch.set_iccid(Some(1)));
+        assert_eq!(ch.own_number(), None);
+        ch.set_own_number(None);
+        assert_eq!(ch.own_number(), Some(None));
+
+        // Reinserting the same SIM keeps the cached number
+        assert!(!ch.set_iccid(None));
+        assert!(!ch.set_iccid(Some(1)));
+        assert_eq!(ch.own_number(), Some(None));
+
+        assert!(ch.set_iccid(Some(2)));
+        assert_eq!(ch.own_number(), None);
+    }
+
+    #[test]
+    fn state_transition_diagnostics() {
+        use embassy_sync::pubsub::WaitResult;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let mut subscriber = ch.diagnostics_subscriber().unwrap();
+
+        ch.set_operation_state(OperationState::Initialized);
+        ch.set_operation_state(OperationState::Initialized);
+        ch.set_operation_state_for(OperationState::PowerDown, TransitionReason::Recovery);
+        ch.set_recovery_action(RecoveryAction::HardReset);
+
+        let expected = [
+            DiagnosticEvent::StateTransition {
+                from: OperationState::PowerDown,
+                to: OperationState::Initialized,
+                reason: TransitionReason::Requested,
+            },
+            DiagnosticEvent::StateTransition {
+                from: OperationState::Initialized,
+                to: OperationState::PowerDown,
+                reason: TransitionReason::Recovery,
+            },
+            DiagnosticEvent::ResetEscalation {
+                rung: RecoveryAction::HardReset,
+            },
+        ];
+        for event in expected {
+            match subscriber.try_next_message() {
+                Some(WaitResult::Message(e)) => assert_eq!(e, event),
+                _ => panic!("expected {:?}", event),
+            }
+        }
+        assert!(subscriber.try_next_message().is_none());
+    }
+
+    #[test]
+    fn local_addr_changes() {
+        use core::net::Ipv4Addr;
+
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+        let addr = |last| Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)));
+
+        ch.set_local_addr(addr(1));
+        ch.set_local_addr(addr(1));
+        assert_eq!(ch.local_addr(None), (addr(1), 1));
+
+        ch.set_local_addr(addr(2));
+        assert_eq!(ch.local_addr(None), (addr(2), 2));
+
+        // Losing the address is not a change to rebind on
+        ch.start_session();
+        assert_eq!(ch.local_addr(None), (None, 2));
+    }
+
+    #[test]
+    fn operator_scan_abort() {
+        let mut state = State::new();
+        let ch = Runner::new(&mut state);
+
+        assert!(!ch.abort_operator_scan());
+        assert!(ch.request_operator_scan());
+        assert!(!ch.request_operator_scan());
+
+        // Aborted before the runner took the request
+        assert!(ch.abort_operator_scan());
+        assert_eq!(
+            ch.operator_scan(),
+            Some(OperatorScan::Failed(OperatorScanError::Aborted))
+        );
+
+        // Aborted while running
+        assert!(ch.request_operator_scan());
+        embassy_futures::block_on(ch.wait_operator_scan_request());
+        assert!(ch.abort_operator_scan());
+        embassy_futures::block_on(ch.wait_operator_scan_abort());
+        assert_eq!(ch.operator_scan(), Some(OperatorScan::Pending));
+    }
+}
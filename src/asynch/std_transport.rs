@@ -0,0 +1,151 @@
+//! Transport over a serial port of the host, eg. a modem on a USB serial
+//! adapter of a Linux machine, for prototyping on top of tokio. The runner
+//! does the ingress of the AT channel by itself, so the future returned by
+//! [`new_std`] is all that needs to be driven.
+
+#[cfg(not(feature = "ppp"))]
+use core::future::Future;
+
+use embedded_io_adapters::tokio_1::FromTokio;
+use embedded_io_async::{BufRead, ErrorType, Read, Write};
+use tokio::io::BufReader;
+use tokio_serial::{FlowControl, SerialPort as _, SerialPortBuilderExt as _, SerialStream};
+
+use crate::config::Transport;
+#[cfg(not(feature = "ppp"))]
+use crate::{
+    asynch::{control::Control, Resources, Runner},
+    config::CellularConfig,
+};
+
+/// [`Transport`] over a tokio serial port.
+pub struct SerialTransport {
+    port: FromTokio<BufReader<SerialStream>>,
+}
+
+impl SerialTransport {
+    /// Open the serial port at `path`, eg. `/dev/ttyUSB0`, with RTS/CTS flow
+    /// control if `flow_control` is set.
+    pub fn open(
+        path: &str,
+        baud_rate: u32,
+        flow_control: bool,
+    ) -> Result<Self, tokio_serial::Error> {
+        let port = tokio_serial::new(path, baud_rate)
+            .flow_control(if flow_control {
+                FlowControl::Hardware
+            } else {
+                FlowControl::None
+            })
+            .open_native_async()?;
+
+        Ok(Self {
+            port: FromTokio::new(BufReader::new(port)),
+        })
+    }
+}
+
+impl Transport for SerialTransport {
+    fn set_baudrate(&mut self, baudrate: u32) {
+        if self
+            .port
+            .inner_mut()
+            .get_mut()
+            .set_baud_rate(baudrate)
+            .is_err()
+        {
+            error!(
+                "Failed to set the baud rate of the serial port to {}",
+                baudrate
+            );
+        }
+    }
+
+    fn split_ref(&mut self) -> (impl Write, impl Read + BufRead) {
+        let (rx, tx) = tokio::io::split(self.port.inner_mut());
+        (FromTokio::new(tx), FromTokio::new(BufReader::new(rx)))
+    }
+}
+
+impl ErrorType for SerialTransport {
+    type Error = std::io::Error;
+}
+
+impl Read for SerialTransport {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.port.read(buf).await
+    }
+}
+
+impl BufRead for SerialTransport {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        self.port.fill_buf().await
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.port.consume(amt)
+    }
+}
+
+impl Write for SerialTransport {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.port.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.port.flush().await
+    }
+}
+
+/// Open the serial port at `port_path` and set up the driver on it, with
+/// `resources` that live for the rest of the program, eg. in a `StaticCell`.
+/// The port is opened at `baud_rate`, which does not have to match the one
+/// the module currently uses: the runner probes the module at each of the
+/// baud rates on init, and then switches both to `CellularConfig::BAUD_RATE`.
+///
+/// Returns the [`Control`] along with the future of the runner, to spawn or
+/// `join` with the application.
+///
+/// ```no_run
+/// # use ublox_cellular::config::{CellularConfig, NoPin};
+/// # struct Config;
+/// # impl CellularConfig<'static> for Config {
+/// #     type ResetPin = NoPin;
+/// #     type PowerPin = NoPin;
+/// #     type VintPin = NoPin;
+/// #     type DtrPin = NoPin;
+/// # }
+/// use embassy_futures::select::select;
+/// use static_cell::StaticCell;
+/// use ublox_cellular::asynch::{std_transport::new_std, Resources};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// static RESOURCES: StaticCell<Resources<1024, 2>> = StaticCell::new();
+/// let resources = RESOURCES.init(Resources::new());
+/// let (control, runner) = new_std("/dev/ttyUSB0", 115_200, resources, Config)?;
+///
+/// select(runner, async {
+///     println!("{:?}", control.identity().await);
+/// })
+/// .await;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(feature = "ppp"))]
+pub fn new_std<C, const INGRESS_BUF_SIZE: usize, const URC_CAPACITY: usize>(
+    port_path: &str,
+    baud_rate: u32,
+    resources: &'static mut Resources<INGRESS_BUF_SIZE, URC_CAPACITY>,
+    config: C,
+) -> Result<(Control<'static, INGRESS_BUF_SIZE>, impl Future<Output = ()>), tokio_serial::Error>
+where
+    C: CellularConfig<'static> + 'static,
+{
+    let transport = SerialTransport::open(port_path, baud_rate, C::FLOW_CONTROL)?;
+    let (mut runner, control) = Runner::new(transport, resources, config);
+
+    Ok((control, async move {
+        runner.run().await;
+    }))
+}
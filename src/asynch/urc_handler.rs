@@ -1,48 +1,93 @@
 use atat::{UrcChannel, UrcSubscription};
+use embassy_sync::pubsub::WaitResult;
+#[cfg(feature = "internal-network-stack")]
+use embassy_time::{Duration, Instant};
 
-use crate::command::Urc;
+use crate::command::{
+    mobile_control::urc::IndicatorEvent,
+    psn::types::{ContextId, PSEvent, PSEventKind},
+    system_features::{
+        types::FirmwareInstallError,
+        urc::{FirmwareInstallProgress, PsmState},
+    },
+    Urc,
+};
 
 use super::{runner::URC_SUBSCRIBERS, state};
 
+/// Least time between warnings about URCs of unknown sockets.
+#[cfg(feature = "internal-network-stack")]
+const UNKNOWN_SOCKET_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct UrcHandler<'a, 'b, const URC_CAPACITY: usize> {
     ch: &'b state::Runner<'a>,
     urc_subscription: UrcSubscription<'a, Urc, URC_CAPACITY, URC_SUBSCRIBERS>,
+    /// The context of our data connection, see `CellularConfig::CONTEXT_ID`
+    data_cid: ContextId,
+    /// When the last URC of an unknown socket was warned about
+    #[cfg(feature = "internal-network-stack")]
+    unknown_socket_warning: Option<Instant>,
 }
 
 impl<'a, 'b, const URC_CAPACITY: usize> UrcHandler<'a, 'b, URC_CAPACITY> {
     pub fn new(
         ch: &'b state::Runner<'a>,
         urc_channel: &'a UrcChannel<Urc, URC_CAPACITY, URC_SUBSCRIBERS>,
+        data_cid: ContextId,
     ) -> Self {
         Self {
             ch,
             urc_subscription: urc_channel.subscribe().unwrap(),
+            data_cid,
+            #[cfg(feature = "internal-network-stack")]
+            unknown_socket_warning: None,
         }
     }
 
     pub async fn run(&mut self) -> ! {
         loop {
-            let event = self.urc_subscription.next_message_pure().await;
-            self.handle_urc(event).await;
+            match self.urc_subscription.next_message().await {
+                WaitResult::Message(event) => self.handle_urc(event).await,
+                WaitResult::Lagged(lost) => {
+                    warn!("URC channel overflowed, {} URCs lost", lost);
+                    self.ch.record_urc_overflow(lost);
+                    #[cfg(feature = "internal-network-stack")]
+                    self.ch.request_socket_poll();
+                }
+            }
         }
     }
 
     async fn handle_urc(&mut self, event: Urc) {
         match event {
             // Handle network URCs
-            Urc::NetworkDetach => warn!("Network detached"),
-            Urc::MobileStationDetach => warn!("Mobile station detached"),
-            Urc::NetworkDeactivate => warn!("Network deactivated"),
-            Urc::MobileStationDeactivate => warn!("Mobile station deactivated"),
-            Urc::NetworkPDNDeactivate => warn!("Network PDN deactivated"),
-            Urc::MobileStationPDNDeactivate => warn!("Mobile station PDN deactivated"),
+            Urc::PacketDomainEvent(ev) => self.packet_domain_event(ev.event),
             #[cfg(feature = "internal-network-stack")]
-            Urc::SocketDataAvailable(_) => warn!("Socket data available"),
+            Urc::SocketDataAvailable(ev) => {
+                if self.is_known_socket(ev.socket) {
+                    debug!("[{}] Socket data available: {}", ev.socket, ev.length);
+                    self.ch.set_socket_available(ev.socket, Some(ev.length));
+                }
+            }
             #[cfg(feature = "internal-network-stack")]
-            Urc::SocketDataAvailableUDP(_) => warn!("Socket data available UDP"),
-            Urc::DataConnectionActivated(_) => warn!("Data connection activated"),
-            Urc::DataConnectionDeactivated(_) => {
+            Urc::SocketDataAvailableUDP(ev) => {
+                if self.is_known_socket(ev.socket) {
+                    debug!("[{}] Socket data available UDP: {}", ev.socket, ev.length);
+                    self.ch.set_socket_available(ev.socket, Some(ev.length));
+                }
+            }
+            Urc::DataConnectionActivated(res) => {
+                warn!("Data connection activated, result {}", res.result);
+                #[cfg(feature = "use-upsd-context-activation")]
+                self.ch.set_psd_result(res.result);
+                // The network may have assigned another address
+                self.ch.request_local_addr_refresh();
+            }
+            #[allow(unused_variables)]
+            Urc::DataConnectionDeactivated(res) => {
                 warn!("Data connection deactivated");
+                #[cfg(feature = "use-upsd-context-activation")]
+                self.ch.clear_psd_profile(res.profile_id);
                 #[cfg(not(feature = "use-upsd-context-activation"))]
                 if self.ch.get_profile_state() == crate::registration::ProfileState::ShouldBeUp {
                     // Set the state so that, should we re-register with the
@@ -52,10 +97,87 @@ impl<'a, 'b, const URC_CAPACITY: usize> UrcHandler<'a, 'b, URC_CAPACITY> {
                 }
             }
             #[cfg(feature = "internal-network-stack")]
-            Urc::SocketClosed(_) => warn!("Socket closed"),
+            Urc::SocketClosed(closed) => {
+                if self.ch.close_remote_socket(closed.socket) {
+                    warn!("[{}] Socket closed", closed.socket);
+                } else {
+                    self.unknown_socket(closed.socket);
+                }
+            }
+            #[cfg(feature = "sms")]
             Urc::MessageWaitingIndication(_) => warn!("Message waiting indication"),
             Urc::ExtendedPSNetworkRegistration(_) => warn!("Extended PS network registration"),
-            Urc::HttpResponse(_) => warn!("HTTP response"),
+            #[cfg(feature = "http")]
+            Urc::HttpResponse(res) => {
+                debug!(
+                    "HTTP response on profile {}: {}",
+                    res.profile_id, res.http_result
+                );
+                self.ch.set_http_response(res);
+            }
+            #[cfg(feature = "mqtt")]
+            Urc::MqttCommandResult(res) => {
+                if res.op_code == 0 {
+                    warn!("MQTT disconnected");
+                }
+                self.ch.set_mqtt_result(res);
+            }
+            #[cfg(feature = "mqtt")]
+            Urc::MqttUnreadMessages(msg) => self.ch.set_mqtt_unread(msg.unread),
+            Urc::FirmwareInstallProgress(progress) => {
+                info!("🔄 Firmware install progress: {}", progress.status);
+                self.ch.set_firmware_install_state(match progress.status {
+                    status @ 0..=100 => state::FirmwareInstallState::Installing(status),
+                    FirmwareInstallProgress::COMPLETED => state::FirmwareInstallState::Completed,
+                    code => {
+                        state::FirmwareInstallState::Failed(FirmwareInstallError::Install(code))
+                    }
+                });
+            }
+            Urc::ThermalWarning(warning) => {
+                warn!("🌡️ Module temperature: {:?}", warning.state())
+            }
+            #[cfg(feature = "gnss")]
+            Urc::GnssAidingIndication(ind) => {
+                if ind.result == 0 {
+                    info!("GNSS aiding {} completed", ind.aid_mode.0)
+                } else {
+                    warn!(
+                        "GNSS aiding {} failed with result {}",
+                        ind.aid_mode.0, ind.result
+                    )
+                }
+            }
+            Urc::PsmState(psm) => match psm.state {
+                PsmState::AWAKE => self.ch.set_sleeping(false),
+                PsmState::ENTERING => self.ch.set_sleeping(true),
+                _ => debug!("PSM entry blocked: {:?}", psm.param),
+            },
+            #[cfg(feature = "lwm2m")]
+            Urc::Lwm2mStatus(status) => debug!("LwM2M client event: {:?}", status),
+            Urc::IndicatorEvent(ev) => {
+                self.ch.update_indicators(&ev);
+                if ev.descr == IndicatorEvent::SIMIND {
+                    match ev.value {
+                        0 => {
+                            warn!("SIM removed");
+                            self.ch.set_sim_state(state::SimState::Absent);
+                        }
+                        1 => {
+                            info!("SIM inserted");
+                            self.ch.request_sim_check();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            #[cfg(feature = "audio")]
+            Urc::Ring => self.ch.set_ringing(),
+            #[cfg(feature = "audio")]
+            Urc::CallingLineIdentification(clip) => {
+                debug!("Incoming call from {:?}", clip.number.as_str());
+                self.ch.set_ringing();
+            }
             Urc::NetworkRegistration(reg) => {
                 self.ch
                     .update_registration_with(|state| state.compare_and_set(reg.into()));
@@ -70,4 +192,188 @@ impl<'a, 'b, const URC_CAPACITY: usize> UrcHandler<'a, 'b, URC_CAPACITY> {
             }
         };
     }
+
+    fn packet_domain_event(&self, event: PSEvent) {
+        let data_context_down = match event.kind {
+            // All contexts are gone with the detach
+            PSEventKind::NetworkDetach => {
+                warn!("Network detached");
+                true
+            }
+            PSEventKind::MobileStationDetach => {
+                warn!("Mobile station detached");
+                true
+            }
+            PSEventKind::Other => false,
+            kind if kind.is_deactivation() => {
+                warn!("{:?} of context {:?}", kind, event.cid);
+                // Without a cid the context can't be told, so take it as ours
+                event.cid.is_none_or(|cid| cid == self.data_cid)
+            }
+            kind => {
+                debug!("{:?} of context {:?}", kind, event.cid);
+                false
+            }
+        };
+
+        if data_context_down {
+            self.data_context_down();
+        }
+    }
+
+    /// Mark the data context down, to have it activated again once registered.
+    fn data_context_down(&self) {
+        #[cfg(feature = "use-upsd-context-activation")]
+        self.ch.clear_psd_context(self.data_cid);
+        #[cfg(not(feature = "use-upsd-context-activation"))]
+        if self.ch.get_profile_state() == crate::registration::ProfileState::ShouldBeUp {
+            self.ch
+                .set_profile_state(crate::registration::ProfileState::RequiresReactivation);
+        }
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    fn is_known_socket(&mut self, socket: ublox_sockets::SocketHandle) -> bool {
+        let known = self.ch.is_socket_known(socket);
+        if !known {
+            self.unknown_socket(socket);
+        }
+        known
+    }
+
+    /// Account a URC of a socket the driver did not create, eg. one the SIM
+    /// opened for an OTA update, and otherwise ignore it.
+    #[cfg(feature = "internal-network-stack")]
+    fn unknown_socket(&mut self, socket: ublox_sockets::SocketHandle) {
+        let count = self.ch.record_unknown_socket_urc();
+        let now = Instant::now();
+        if self.unknown_socket_warning.is_none_or(|last| {
+            now.saturating_duration_since(last) >= UNKNOWN_SOCKET_WARNING_INTERVAL
+        }) {
+            warn!(
+                "[{}] Ignoring URC of unknown socket, {} so far",
+                socket, count
+            );
+            self.unknown_socket_warning = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "internal-network-stack")]
+    use ublox_sockets::SocketHandle;
+
+    #[cfg(feature = "internal-network-stack")]
+    use crate::command::ip_transport_layer::{
+        types::SocketProtocol,
+        urc::{SocketClosed, SocketDataAvailable},
+    };
+
+    use super::*;
+
+    #[cfg(not(feature = "use-upsd-context-activation"))]
+    #[test]
+    fn packet_domain_events() {
+        use crate::command::psn::urc::PacketDomainEvent;
+        use crate::registration::ProfileState;
+
+        let mut state = state::State::new();
+        let ch = state::Runner::new(&mut state);
+        let urc_channel = UrcChannel::<Urc, 4, URC_SUBSCRIBERS>::new();
+        let mut handler = UrcHandler::new(&ch, &urc_channel, ContextId(1));
+        let mut handle = |event: &[u8]| {
+            embassy_futures::block_on(handler.handle_urc(Urc::PacketDomainEvent(
+                PacketDomainEvent {
+                    event: PSEvent::parse(event),
+                },
+            )))
+        };
+
+        ch.set_profile_state(ProfileState::ShouldBeUp);
+        // Another context going down leaves ours alone
+        handle(b"+CGEV: NW PDN DEACT 2");
+        handle(b"+CGEV: NW DEACT \"IP\",\"10.160.23.5\",3");
+        handle(b"+CGEV: ME PDN ACT 1");
+        assert_eq!(ch.get_profile_state(), ProfileState::ShouldBeUp);
+
+        handle(b"+CGEV: NW DEACT 1,1,0");
+        assert_eq!(ch.get_profile_state(), ProfileState::RequiresReactivation);
+
+        ch.set_profile_state(ProfileState::ShouldBeUp);
+        handle(b"+CGEV: NW DETACH");
+        assert_eq!(ch.get_profile_state(), ProfileState::RequiresReactivation);
+    }
+
+    #[test]
+    fn firmware_install_progress() {
+        use state::FirmwareInstallState;
+
+        let mut state = state::State::new();
+        let ch = state::Runner::new(&mut state);
+        let urc_channel = UrcChannel::<Urc, 4, URC_SUBSCRIBERS>::new();
+        let mut handler = UrcHandler::new(&ch, &urc_channel, ContextId(1));
+        let mut handle = |status: u8| {
+            embassy_futures::block_on(handler.handle_urc(Urc::FirmwareInstallProgress(
+                FirmwareInstallProgress { status },
+            )))
+        };
+
+        handle(42);
+        assert_eq!(
+            ch.firmware_install_state(None),
+            FirmwareInstallState::Installing(42)
+        );
+        handle(FirmwareInstallProgress::COMPLETED);
+        assert_eq!(
+            ch.firmware_install_state(None),
+            FirmwareInstallState::Completed
+        );
+        handle(131);
+        assert_eq!(
+            ch.firmware_install_state(None),
+            FirmwareInstallState::Failed(FirmwareInstallError::Install(131))
+        );
+    }
+
+    #[cfg(feature = "internal-network-stack")]
+    #[test]
+    fn unknown_socket_urcs() {
+        let mut state = state::State::new();
+        let ch = state::Runner::new(&mut state);
+        let urc_channel = UrcChannel::<Urc, 4, URC_SUBSCRIBERS>::new();
+        let mut handler = UrcHandler::new(&ch, &urc_channel, ContextId(1));
+
+        ch.register_socket(SocketHandle(0), SocketProtocol::TCP, Some(6000));
+        ch.register_socket(SocketHandle(1), SocketProtocol::UDP, None);
+
+        // The SIM closing its BIP socket amid events of our sockets
+        for urc in [
+            Urc::SocketClosed(SocketClosed {
+                socket: SocketHandle(5),
+            }),
+            Urc::SocketDataAvailable(SocketDataAvailable {
+                socket: SocketHandle(0),
+                length: 10,
+            }),
+            Urc::SocketDataAvailableUDP(SocketDataAvailable {
+                socket: SocketHandle(5),
+                length: 4,
+            }),
+            Urc::SocketClosed(SocketClosed {
+                socket: SocketHandle(0),
+            }),
+            Urc::SocketClosed(SocketClosed {
+                socket: SocketHandle(6),
+            }),
+        ] {
+            embassy_futures::block_on(handler.handle_urc(urc));
+        }
+
+        assert_eq!(ch.unknown_socket_urc_count(), 3);
+        // Kept until closed by the application, without its port
+        assert!(ch.is_socket_closed(SocketHandle(0)));
+        assert!(!ch.socket_port_in_use(&SocketProtocol::TCP, 6000));
+        assert!(ch.is_socket_known(SocketHandle(1)));
+    }
 }
@@ -0,0 +1,311 @@
+//! Supervision of the AT interface. Modules occasionally stop answering AT
+//! commands altogether while the UART stays alive, which no amount of
+//! retrying recovers from. The runner sends a periodic heartbeat, and once
+//! `CellularConfig::HANG_TIMEOUTS` of its commands time out in a row, the
+//! heartbeats or any other, it climbs a ladder of increasingly hard resets
+//! until the module answers again.
+
+use atat::asynch::AtatClient;
+use embassy_time::Duration;
+
+use crate::command::{
+    mobile_control::{types::Functionality, SetModuleFunctionality},
+    AT,
+};
+
+use super::{control::ProxyClient, state::RecoveryAction};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Heartbeat interval after a timeout, to confirm a hang quickly.
+const HEARTBEAT_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+
+pub(crate) struct ResetLadder {
+    /// Commands in a row that have to time out for a hang
+    hang_timeouts: u8,
+    /// Rung to take on the next hang. Only drops back to the bottom once the
+    /// module has answered again.
+    next: RecoveryAction,
+    /// Rung taken, but not yet carried out by the runner.
+    pending: Option<RecoveryAction>,
+}
+
+impl ResetLadder {
+    pub(crate) const fn new(hang_timeouts: u8) -> Self {
+        Self {
+            hang_timeouts,
+            next: RecoveryAction::SoftReset,
+            pending: None,
+        }
+    }
+
+    /// Time until the next heartbeat, with `timeouts` commands in a row timed
+    /// out so far.
+    pub(crate) fn interval(&self, timeouts: u8) -> Duration {
+        if timeouts > 0 {
+            HEARTBEAT_RETRY_INTERVAL
+        } else {
+            HEARTBEAT_INTERVAL
+        }
+    }
+
+    /// The module answered, so any previous recovery succeeded.
+    pub(crate) fn on_response(&mut self) {
+        self.next = RecoveryAction::SoftReset;
+    }
+
+    /// Returns the recovery to take, once `timeouts` commands in a row timed
+    /// out reach the threshold.
+    pub(crate) fn on_timeouts(&mut self, timeouts: u8) -> Option<RecoveryAction> {
+        if timeouts < self.hang_timeouts {
+            return None;
+        }
+        Some(self.escalate())
+    }
+
+    /// Take the next rung of the ladder, eg. because the module could not be
+    /// re-initialized after the previous one.
+    pub(crate) fn escalate(&mut self) -> RecoveryAction {
+        let action = self.next;
+        self.next = match action {
+            RecoveryAction::SoftReset => RecoveryAction::HardReset,
+            RecoveryAction::HardReset | RecoveryAction::PowerCycle => RecoveryAction::PowerCycle,
+        };
+        self.pending = Some(action);
+        action
+    }
+
+    /// Whether a recovery was taken, that the module has not yet answered
+    /// after.
+    pub(crate) fn is_recovering(&self) -> bool {
+        self.next != RecoveryAction::SoftReset
+    }
+
+    pub(crate) fn take_pending(&mut self) -> Option<RecoveryAction> {
+        self.pending.take()
+    }
+}
+
+/// Send a single heartbeat, returning the recovery to take if the AT
+/// interface is considered hung. The commands of the runner timed out before
+/// the heartbeat count towards the hang, as counted by `client`.
+///
+/// The soft reset is issued right away, as `AT+CFUN=15` has to go through the
+/// multiplexer that is torn down afterwards. The remaining rungs are carried
+/// out by the runner.
+pub(crate) async fn heartbeat<const INGRESS_BUF_SIZE: usize>(
+    client: &ProxyClient<'_, INGRESS_BUF_SIZE>,
+    ladder: &mut ResetLadder,
+) -> Option<RecoveryAction> {
+    let mut at_client = client;
+    match at_client.send(&AT).await {
+        Err(atat::Error::Timeout) => {
+            warn!(
+                "AT heartbeat timed out, {} commands in a row",
+                client.timeouts()
+            );
+            let action = ladder.on_timeouts(client.timeouts())?;
+            // The module gets as many commands again to answer after the reset
+            client.clear_timeouts();
+            if action == RecoveryAction::SoftReset {
+                let _ = at_client
+                    .send(&SetModuleFunctionality {
+                        fun: Functionality::SilentReset,
+                        rst: None,
+                    })
+                    .await;
+            }
+            Some(action)
+        }
+        // Any complete response, even an error, proves the interface alive
+        _ => {
+            ladder.on_response();
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::asynch::modem_sim::{Fixture, Step, OK};
+
+    use super::*;
+
+    /// The default of `CellularConfig::HANG_TIMEOUTS`.
+    const HANG_TIMEOUTS: u8 = 3;
+
+    /// A heartbeat the module does not answer.
+    const SILENT: Step<'static> = Step::Silent { cmd: b"AT" };
+
+    const ANSWERED: Step<'static> = Step::Command {
+        cmd: b"AT",
+        response: OK,
+    };
+
+    const SOFT_RESET: Step<'static> = Step::Command {
+        cmd: b"AT+CFUN=15",
+        response: OK,
+    };
+
+    /// Heartbeats until the interface is considered hung.
+    async fn hang<const N: usize>(
+        client: &ProxyClient<'_, N>,
+        ladder: &mut ResetLadder,
+    ) -> Option<RecoveryAction> {
+        for _ in 1..HANG_TIMEOUTS {
+            assert_eq!(heartbeat(client, ladder).await, None);
+        }
+        heartbeat(client, ladder).await
+    }
+
+    #[test]
+    fn healthy() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+
+        io.play(&mut sim, &[ANSWERED; 10], async {
+            for _ in 0..10 {
+                assert_eq!(heartbeat(&client, &mut ladder).await, None);
+            }
+        });
+        assert_eq!(ladder.interval(client.timeouts()), HEARTBEAT_INTERVAL);
+        assert!(!ladder.is_recovering());
+    }
+
+    #[test]
+    fn below_threshold() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+
+        let res = io.play(&mut sim, &[SILENT], heartbeat(&client, &mut ladder));
+        assert_eq!(res, None);
+        assert_eq!(ladder.interval(client.timeouts()), HEARTBEAT_RETRY_INTERVAL);
+
+        // A single answer in between resets the count
+        io.play(&mut sim, &[ANSWERED], heartbeat(&client, &mut ladder));
+
+        // No soft reset is sent
+        io.play(&mut sim, &[SILENT; HANG_TIMEOUTS as usize - 1], async {
+            for _ in 1..HANG_TIMEOUTS {
+                assert_eq!(heartbeat(&client, &mut ladder).await, None);
+            }
+        });
+    }
+
+    #[test]
+    fn soft_reset() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+
+        let mut script = heapless::Vec::<_, 8>::new();
+        for _ in 0..HANG_TIMEOUTS {
+            script.push(SILENT).ok();
+        }
+        script.push(SOFT_RESET).ok();
+
+        assert_eq!(
+            io.play(&mut sim, &script, hang(&client, &mut ladder)),
+            Some(RecoveryAction::SoftReset)
+        );
+        assert_eq!(ladder.take_pending(), Some(RecoveryAction::SoftReset));
+        assert_eq!(ladder.take_pending(), None);
+    }
+
+    #[test]
+    fn hard_reset() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+
+        // The soft reset did not help, the next rung is not sent as AT
+        assert_eq!(ladder.escalate(), RecoveryAction::SoftReset);
+        assert_eq!(
+            io.play(
+                &mut sim,
+                &[SILENT; HANG_TIMEOUTS as usize],
+                hang(&client, &mut ladder)
+            ),
+            Some(RecoveryAction::HardReset)
+        );
+    }
+
+    #[test]
+    fn power_cycle() {
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+        let mut hang = || {
+            for timeouts in 1..HANG_TIMEOUTS {
+                assert_eq!(ladder.on_timeouts(timeouts), None);
+            }
+            ladder.on_timeouts(HANG_TIMEOUTS)
+        };
+
+        assert_eq!(hang(), Some(RecoveryAction::SoftReset));
+        assert_eq!(hang(), Some(RecoveryAction::HardReset));
+        assert_eq!(hang(), Some(RecoveryAction::PowerCycle));
+        assert_eq!(hang(), Some(RecoveryAction::PowerCycle));
+    }
+
+    #[test]
+    fn escalate_on_failed_init() {
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+        assert!(!ladder.is_recovering());
+
+        assert_eq!(ladder.escalate(), RecoveryAction::SoftReset);
+        assert!(ladder.is_recovering());
+        assert_eq!(ladder.escalate(), RecoveryAction::HardReset);
+        assert_eq!(ladder.take_pending(), Some(RecoveryAction::HardReset));
+    }
+
+    #[test]
+    fn recovered() {
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut ladder = ResetLadder::new(HANG_TIMEOUTS);
+
+        ladder.escalate();
+        assert!(ladder.is_recovering());
+
+        let res = io.play(&mut sim, &[ANSWERED], heartbeat(&client, &mut ladder));
+        assert_eq!(res, None);
+        assert!(!ladder.is_recovering());
+
+        // Back at the bottom of the ladder
+        for timeouts in 1..HANG_TIMEOUTS {
+            assert_eq!(ladder.on_timeouts(timeouts), None);
+        }
+        assert_eq!(
+            ladder.on_timeouts(HANG_TIMEOUTS),
+            Some(RecoveryAction::SoftReset)
+        );
+    }
+
+    /// Other commands of the runner timing out count towards the hang, and
+    /// the threshold is the configured one.
+    #[test]
+    fn command_timeouts_count() {
+        use crate::command::general::GetManufacturerId;
+
+        let mut fixture = Fixture::new();
+        let (mut sim, host, mut io) = fixture.split();
+        let client = host.client();
+        let mut ladder = ResetLadder::new(2);
+
+        let script = [Step::Silent { cmd: b"AT+CGMI" }, SILENT, SOFT_RESET];
+        let res = io.play(&mut sim, &script, async {
+            let res = (&client).send(&GetManufacturerId).await;
+            assert_eq!(res.err(), Some(atat::Error::Timeout));
+            assert_eq!(ladder.interval(client.timeouts()), HEARTBEAT_RETRY_INTERVAL);
+            heartbeat(&client, &mut ladder).await
+        });
+        assert_eq!(res, Some(RecoveryAction::SoftReset));
+        assert_eq!(client.timeouts(), 0);
+    }
+}
@@ -0,0 +1,155 @@
+//! ### 17 - Audio interface
+//! The section describes the AT commands configuring the audio paths of the
+//! voice capable u-blox cellular modules, ie. which of the analog or digital
+//! (I2S) interfaces carries the call audio, and its gains.
+//!
+//! **NOTES:**
+//! - **LARA-R6**: The audio interface is digital only. Route the call audio
+//!   with [`SetI2sConfiguration`]; the gains are those of the external codec.
+//! - **LARA-L6004D / LARA-R6001D / LARA-R6401D**: Audio is not supported.
+pub mod responses;
+pub mod types;
+
+use atat::atat_derive::AtatCmd;
+
+use self::responses::{AudioPathMode, I2sConfiguration, MicrophoneGain, Sidetone, SpeakerGain};
+use self::types::{DownlinkPath, I2sClockEdge, I2sPort, I2sRole, I2sSampleRate, UplinkPath};
+
+use super::NoResponse;
+
+/// 17.7 Audio path mode setting +USPM
+///
+/// Selects the uplink (microphone) and downlink (speaker) paths of the call
+/// audio, and where the alert sounds are played.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USPM", NoResponse)]
+pub struct SetAudioPathMode {
+    #[at_arg(position = 0)]
+    pub main_uplink: UplinkPath,
+    #[at_arg(position = 1)]
+    pub main_downlink: DownlinkPath,
+    /// 0: alert sounds on the main downlink path; 1: on the loudspeaker
+    #[at_arg(position = 2)]
+    pub alert_sound: u8,
+    /// 0: the headset indication is not considered; 1: switch to the headset
+    /// paths while a headset is detected
+    #[at_arg(position = 3)]
+    pub headset_indication: u8,
+}
+
+/// 17.7 Audio path mode setting +USPM
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USPM?", AudioPathMode)]
+pub struct GetAudioPathMode;
+
+/// 17.9 Microphone gain control +UMGC
+///
+/// Sets the gains of the microphone of an uplink path.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UMGC", NoResponse)]
+pub struct SetMicrophoneGain {
+    #[at_arg(position = 0)]
+    pub uplink: UplinkPath,
+    /// Analog gain, in 3 dB steps from 0 (0 dB) to 13 (+39 dB)
+    #[at_arg(position = 1)]
+    pub analog_gain: u8,
+    /// Digital gain, 0 - 32767, with 8192 for unity gain
+    #[at_arg(position = 2)]
+    pub digital_gain: u16,
+}
+
+/// 17.9 Microphone gain control +UMGC
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UMGC?", MicrophoneGain)]
+pub struct GetMicrophoneGain;
+
+/// 17.10 Speaker gain control +USGC
+///
+/// Sets the gains of the speaker of a downlink path.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USGC", NoResponse)]
+pub struct SetSpeakerGain {
+    #[at_arg(position = 0)]
+    pub downlink: DownlinkPath,
+    /// Analog gain of the speaker amplifier, 0 - 13
+    #[at_arg(position = 1)]
+    pub analog_gain: u8,
+    /// Digital gain, 0 - 32767, with 8192 for unity gain
+    #[at_arg(position = 2)]
+    pub digital_gain: u16,
+}
+
+/// 17.10 Speaker gain control +USGC
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USGC?", SpeakerGain)]
+pub struct GetSpeakerGain;
+
+/// 17.11 Sidetone +USTN
+///
+/// Sets the gain of the uplink audio fed back into a downlink path.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USTN", NoResponse)]
+pub struct SetSidetone {
+    #[at_arg(position = 0)]
+    pub downlink: DownlinkPath,
+    /// Sidetone gain, 0 - 32767, with 0 disabling the sidetone
+    #[at_arg(position = 1)]
+    pub gain: u16,
+}
+
+/// 17.11 Sidetone +USTN
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USTN?", Sidetone)]
+pub struct GetSidetone;
+
+/// 17.12 I2S digital interface mode +UI2S
+///
+/// Configures the I2S digital audio interface. The setting is saved in the
+/// NVM, and applies from the next call.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UI2S", NoResponse)]
+pub struct SetI2sConfiguration {
+    /// I2S mode: 0 - 12 are the PCM modes, 13 - 26 the normal I2S modes, see
+    /// the AT manual for the modes of each module
+    #[at_arg(position = 0)]
+    pub mode: u8,
+    #[at_arg(position = 1)]
+    pub port: I2sPort,
+    #[at_arg(position = 2)]
+    pub clock_edge: I2sClockEdge,
+    #[at_arg(position = 3)]
+    pub sample_rate: Option<I2sSampleRate>,
+    #[at_arg(position = 4)]
+    pub role: Option<I2sRole>,
+}
+
+/// 17.12 I2S digital interface mode +UI2S
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UI2S?", I2sConfiguration)]
+pub struct GetI2sConfiguration;
+
+#[cfg(test)]
+mod tests {
+    use atat::AtatCmd;
+
+    use super::*;
+
+    #[test]
+    fn serialize_i2s_configuration() {
+        let mut buf = [0u8; 32];
+
+        let len = SetI2sConfiguration {
+            mode: 25,
+            port: I2sPort::I2s,
+            clock_edge: I2sClockEdge::RisingEdge,
+            sample_rate: Some(I2sSampleRate::Hz16000),
+            role: Some(I2sRole::Slave),
+        }
+        .write(&mut buf);
+        assert_eq!(&buf[..len], b"AT+UI2S=25,1,0,3,1\r");
+
+        let config = GetI2sConfiguration.parse(Ok(b"+UI2S: 25,1,0,3,1")).unwrap();
+        assert_eq!(config.sample_rate, I2sSampleRate::Hz16000);
+        assert_eq!(config.role, I2sRole::Slave);
+    }
+}
@@ -0,0 +1,68 @@
+//! Responses for Audio Interface Commands
+use atat::atat_derive::AtatResp;
+
+use super::types::{DownlinkPath, I2sClockEdge, I2sPort, I2sRole, I2sSampleRate, UplinkPath};
+
+/// 17.7 Audio path mode setting +USPM
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AudioPathMode {
+    #[at_arg(position = 0)]
+    pub main_uplink: UplinkPath,
+    #[at_arg(position = 1)]
+    pub main_downlink: DownlinkPath,
+    #[at_arg(position = 2)]
+    pub alert_sound: u8,
+    #[at_arg(position = 3)]
+    pub headset_indication: u8,
+}
+
+/// 17.9 Microphone gain control +UMGC
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MicrophoneGain {
+    #[at_arg(position = 0)]
+    pub uplink: UplinkPath,
+    #[at_arg(position = 1)]
+    pub analog_gain: u8,
+    #[at_arg(position = 2)]
+    pub digital_gain: u16,
+}
+
+/// 17.10 Speaker gain control +USGC
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SpeakerGain {
+    #[at_arg(position = 0)]
+    pub downlink: DownlinkPath,
+    #[at_arg(position = 1)]
+    pub analog_gain: u8,
+    #[at_arg(position = 2)]
+    pub digital_gain: u16,
+}
+
+/// 17.11 Sidetone +USTN
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sidetone {
+    #[at_arg(position = 0)]
+    pub downlink: DownlinkPath,
+    #[at_arg(position = 1)]
+    pub gain: u16,
+}
+
+/// 17.12 I2S digital interface mode +UI2S
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct I2sConfiguration {
+    #[at_arg(position = 0)]
+    pub mode: u8,
+    #[at_arg(position = 1)]
+    pub port: I2sPort,
+    #[at_arg(position = 2)]
+    pub clock_edge: I2sClockEdge,
+    #[at_arg(position = 3)]
+    pub sample_rate: I2sSampleRate,
+    #[at_arg(position = 4)]
+    pub role: I2sRole,
+}
@@ -0,0 +1,85 @@
+//! Argument and parameter types used by Audio Interface Commands and Responses
+
+use atat::atat_derive::AtatEnum;
+
+/// Uplink (microphone) audio path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UplinkPath {
+    /// 0: handset microphone, on the analog input
+    HandsetMicrophone = 0,
+    /// 1: headset microphone, on the analog input
+    HeadsetMicrophone = 1,
+    /// 2: I2S input line
+    I2s = 2,
+    /// 3: hands-free microphone, on the analog input
+    HandsFreeMicrophone = 3,
+}
+
+/// Downlink (speaker) audio path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DownlinkPath {
+    /// 0: normal earpiece, on the analog output
+    Earpiece = 0,
+    /// 1: mono headset, on the analog output
+    Headset = 1,
+    /// 2: loudspeaker, on the analog output
+    Loudspeaker = 2,
+    /// 3: I2S output line
+    I2s = 3,
+}
+
+/// I2S interface of the module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum I2sPort {
+    /// 1: I2S (on LARA-R6, the only digital audio interface)
+    I2s = 1,
+    /// 2: I2S1, on the modules with a second interface
+    I2s1 = 2,
+}
+
+/// Edge of the clock the word alignment signal changes on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum I2sClockEdge {
+    /// 0 (factory-programmed value): rising edge
+    RisingEdge = 0,
+    /// 1: falling edge
+    FallingEdge = 1,
+}
+
+/// I2S sample rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum I2sSampleRate {
+    /// 0 (factory-programmed value): 8 kHz
+    Hz8000 = 0,
+    /// 1: 11.025 kHz
+    Hz11025 = 1,
+    /// 2: 12 kHz
+    Hz12000 = 2,
+    /// 3: 16 kHz
+    Hz16000 = 3,
+    /// 4: 22.05 kHz
+    Hz22050 = 4,
+    /// 5: 24 kHz
+    Hz24000 = 5,
+    /// 6: 32 kHz
+    Hz32000 = 6,
+    /// 7: 44.1 kHz
+    Hz44100 = 7,
+    /// 8: 48 kHz
+    Hz48000 = 8,
+}
+
+/// Whether the module drives the I2S clock and word alignment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum I2sRole {
+    /// 0 (factory-programmed value): master, the module drives the clocks
+    Master = 0,
+    /// 1: slave, the external codec drives the clocks
+    Slave = 1,
+}
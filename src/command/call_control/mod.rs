@@ -1,12 +1,18 @@
 //! ### 6 - Call control
-mod types;
+pub mod responses;
+pub mod types;
+pub mod urc;
 
 use atat::atat_derive::AtatCmd;
 
-use self::types::AddressType;
+use self::responses::CallStatus;
+use self::types::{AddressType, CallingLineIdentificationMode, DtmfTone};
 
 use super::NoResponse;
 
+/// Maximum number of calls listed by +CLCC
+pub const MAX_CALLS: usize = 4;
+
 /// 6.1 Select type of address +CSTA
 ///
 /// Selects the type of number for further dialling commands (D) according to
@@ -55,3 +61,119 @@ pub struct Dial<'a> {
     #[at_arg(position = 0, len = 32)]
     pub number: &'a str,
 }
+
+/// 6.2 Dial command D, for a voice call
+///
+/// The trailing `;` of the dial string selects a voice call. The module
+/// answers with OK as soon as the call setup is started; the progress of the
+/// call is polled with [`ListCurrentCalls`].
+///
+/// **NOTES:**
+/// - **LARA-L6004D / LARA-R6001D / LARA-R6401D**: Voice calls are not
+///   supported.
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "D",
+    NoResponse,
+    abortable = true,
+    timeout_ms = 180000,
+    value_sep = false,
+    termination = ";\r"
+)]
+pub struct DialVoice<'a> {
+    /// Dial string, sent as is, see [`Dial`]
+    #[at_arg(position = 0, len = 32)]
+    pub number: &'a atat::serde_bytes::Bytes,
+}
+
+/// 6.6 Call answer A
+///
+/// Answers an incoming call.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("A", NoResponse, attempts = 1, timeout_ms = 20000)]
+pub struct AnswerCall;
+
+/// 6.7 Hook control H
+///
+/// Disconnects the active call, or rejects the incoming one.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("H", NoResponse, timeout_ms = 20000)]
+pub struct HangUp;
+
+/// 6.9 Hang up call +CHUP
+///
+/// Releases all calls, like [`HangUp`], as specified by 3GPP TS 27.007.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CHUP", NoResponse, timeout_ms = 20000)]
+pub struct HangUpCalls;
+
+/// 6.10 List current calls +CLCC
+///
+/// Lists the current calls of the module. There is no information text
+/// response without any calls.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CLCC", heapless::Vec<CallStatus, MAX_CALLS>)]
+pub struct ListCurrentCalls;
+
+/// 7.19 Calling line identification presentation +CLIP
+///
+/// Enables the +CLIP URC with the number of the calling party, following
+/// each RING of an incoming call.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CLIP", NoResponse)]
+pub struct SetCallingLineIdentification {
+    #[at_arg(position = 0)]
+    pub mode: CallingLineIdentificationMode,
+}
+
+/// 17.2 DTMF and tone generation +VTS
+///
+/// Sends a DTMF tone to the remote party of the active voice call.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+VTS", NoResponse, timeout_ms = 20000)]
+pub struct SendDtmf {
+    #[at_arg(position = 0)]
+    pub tone: DtmfTone,
+}
+
+#[cfg(test)]
+mod tests {
+    use atat::AtatCmd;
+
+    use super::*;
+    use crate::command::call_control::types::{CallDirection, CallState};
+
+    #[test]
+    fn serialize_call_commands() {
+        let mut buf = [0u8; 48];
+
+        let len = DialVoice {
+            number: atat::serde_bytes::Bytes::new(b"+4512345678"),
+        }
+        .write(&mut buf);
+        assert_eq!(&buf[..len], b"ATD+4512345678;\r");
+
+        let len = SendDtmf {
+            tone: DtmfTone::new('#').unwrap(),
+        }
+        .write(&mut buf);
+        assert_eq!(&buf[..len], b"AT+VTS=#\r");
+        assert_eq!(DtmfTone::new('E'), None);
+    }
+
+    #[test]
+    fn parse_current_calls() {
+        let calls = ListCurrentCalls
+            .parse(Ok(
+                b"+CLCC: 1,0,3,0,0,\"+4512345678\",145\r\n+CLCC: 2,1,5,0,0,\"\",129",
+            ))
+            .unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].direction, CallDirection::MobileOriginated);
+        assert_eq!(calls[0].state, CallState::Alerting);
+        assert_eq!(calls[0].number.as_deref(), Some("+4512345678"));
+        assert_eq!(calls[1].state, CallState::Waiting);
+
+        assert!(ListCurrentCalls.parse(Ok(b"")).unwrap().is_empty());
+    }
+}
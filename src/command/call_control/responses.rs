@@ -0,0 +1,28 @@
+//! Responses for Call Control Commands
+use atat::atat_derive::AtatResp;
+use heapless::String;
+
+use super::types::{CallDirection, CallMode, CallState};
+
+/// 6.10 List current calls +CLCC
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CallStatus {
+    /// Call identification number, as used by +CHLD
+    #[at_arg(position = 0)]
+    pub id: u8,
+    #[at_arg(position = 1)]
+    pub direction: CallDirection,
+    #[at_arg(position = 2)]
+    pub state: CallState,
+    #[at_arg(position = 3)]
+    pub mode: CallMode,
+    /// 1 if the call is part of a multiparty conference call
+    #[at_arg(position = 4)]
+    pub multiparty: u8,
+    #[at_arg(position = 5)]
+    pub number: Option<String<32>>,
+    /// Type of address, 145 for international numbers
+    #[at_arg(position = 6)]
+    pub number_type: Option<u8>,
+}
@@ -1,6 +1,8 @@
 //! Argument and parameter types used by Call Control Commands and Responses
 
 use atat::atat_derive::AtatEnum;
+use atat::AtatLen;
+use serde::{Serialize, Serializer};
 
 /// Type of address in integer format
 #[derive(Clone, Default, PartialEq, Eq, AtatEnum)]
@@ -12,3 +14,79 @@ pub enum AddressType {
     #[default]
     NationalCodedString = 129,
 }
+
+/// Direction of a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CallDirection {
+    /// 0: mobile originated call
+    MobileOriginated = 0,
+    /// 1: mobile terminated call
+    MobileTerminated = 1,
+}
+
+/// State of a call, as listed by +CLCC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CallState {
+    /// 0: active
+    Active = 0,
+    /// 1: held
+    Held = 1,
+    /// 2: dialling (mobile originated call)
+    Dialing = 2,
+    /// 3: alerting the remote party (mobile originated call)
+    Alerting = 3,
+    /// 4: incoming (mobile terminated call)
+    Incoming = 4,
+    /// 5: waiting (mobile terminated call)
+    Waiting = 5,
+}
+
+/// Bearer of a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CallMode {
+    /// 0: voice
+    Voice = 0,
+    /// 1: data
+    Data = 1,
+    /// 2: fax
+    Fax = 2,
+    /// 9: unknown
+    Unknown = 9,
+}
+
+/// Presentation of the +CLIP URC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+pub enum CallingLineIdentificationMode {
+    /// 0 (factory-programmed value): disable the +CLIP URC
+    Disabled = 0,
+    /// 1: enable the +CLIP URC
+    Enabled = 1,
+}
+
+/// A single DTMF tone: `0`-`9`, `*`, `#` or `A`-`D`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DtmfTone(u8);
+
+impl DtmfTone {
+    pub fn new(tone: char) -> Option<Self> {
+        matches!(tone, '0'..='9' | '*' | '#' | 'A'..='D').then_some(Self(tone as u8))
+    }
+}
+
+impl AtatLen for DtmfTone {
+    const LEN: usize = 1;
+    const ESCAPED_LEN: usize = 1;
+}
+
+impl Serialize for DtmfTone {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&[self.0])
+    }
+}
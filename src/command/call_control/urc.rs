@@ -0,0 +1,18 @@
+//! Unsolicited responses for Call Control Commands
+use atat::atat_derive::AtatResp;
+use heapless::String;
+
+/// 7.19 Calling line identification presentation +CLIP
+///
+/// Reports the number of the calling party after each RING, once enabled
+/// with +CLIP=1.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CallingLineIdentification {
+    /// Empty if the number is withheld or not available
+    #[at_arg(position = 0)]
+    pub number: String<32>,
+    /// Type of address, 145 for international numbers
+    #[at_arg(position = 1)]
+    pub number_type: u8,
+}
@@ -9,8 +9,8 @@ use super::NoResponse;
 use atat::atat_derive::AtatCmd;
 use responses::DataRate;
 use types::{
-    BaudRate, Circuit108Behaviour, Circuit109Behaviour, Echo, FlowControl, ResultCodeSelection,
-    SoftwareFlowControl,
+    BaudRate, Circuit108Behaviour, Circuit109Behaviour, Echo, FlowControl, LocalFlowControl,
+    ResultCodeSelection, SoftwareFlowControl,
 };
 
 /// 15.2 Circuit 109 behavior &C
@@ -53,6 +53,22 @@ pub struct SetFlowControl {
     pub value: FlowControl,
 }
 
+/// DTE-DCE local flow control +IFC
+///
+/// Sets the flow control in both directions of the UART, like &K. With
+/// RTS/CTS flow control, the module holds back its output while RTS is off,
+/// so a board without RTS wired gets no more responses.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+IFC", NoResponse)]
+pub struct SetLocalFlowControl {
+    /// Flow control of the data from the DTE
+    #[at_arg(position = 0)]
+    pub dce_by_dte: LocalFlowControl,
+    /// Flow control of the data from the module
+    #[at_arg(position = 1)]
+    pub dte_by_dce: LocalFlowControl,
+}
+
 /// 15.8 Set flow control \Q
 ///
 /// Controls the operation of the local flow control between DTE and DCE. It is
@@ -57,6 +57,18 @@ pub enum FlowControl {
     XonXoff = 4,
 }
 
+/// Flow control of one direction of the UART, for +IFC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+pub enum LocalFlowControl {
+    /// - 0: no flow control
+    None = 0,
+    /// - 1: DC1/DC3 (XON/XOFF)
+    XonXoff = 1,
+    /// - 2 (**default value**): RTS for the data from the DTE, CTS for the
+    ///   data from the module
+    RtsCts = 2,
+}
+
 #[derive(Clone, PartialEq, Eq, AtatEnum)]
 pub enum SoftwareFlowControl {
     /// - 0: Software flow control off
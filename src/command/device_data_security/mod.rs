@@ -39,6 +39,7 @@
 //! - The secure re-negotiation and the SSL/TLS session resumption are currently
 //!   not supported, and if mandated by the server the SSL/TLS connection will
 //!   fail with an Generic SSL/TLS handshake alert.
+pub mod profile;
 pub mod responses;
 pub mod types;
 
@@ -0,0 +1,284 @@
+//! Typed configuration of a USECMNG security profile, to avoid assembling
+//! `+USECPRF` operations by hand.
+
+use heapless::String;
+
+use super::{
+    types::{
+        CertificateValidationLevel, CipherSuite, PskEncoding, SecurityProfileId,
+        SecurityProfileOperation, TlsVersion,
+    },
+    SecurityProfileManager,
+};
+use crate::error::Error;
+
+/// Number of distinct `+USECPRF` operations a builder can issue.
+const OPERATIONS: usize = 10;
+
+/// Builder for a complete SSL/TLS security profile.
+///
+/// Applying the profile first resets it to its factory-programmed values, and
+/// then issues one `+USECPRF` set command per configured setting, so settings
+/// left out of the builder never linger from a previous configuration.
+///
+/// ```ignore
+/// let profile = SecurityProfileBuilder::new(SecurityProfileId(0))
+///     .validation_level(CertificateValidationLevel::RootCertValidationWithValidityDate)
+///     .tls_version_min(TlsVersion::Tls1_2)
+///     .root_ca("ca")
+///     .server_name_indication("example.com");
+///
+/// // Async
+/// control.configure_security_profile(&profile).await?;
+///
+/// // Blocking
+/// for cmd in profile.commands()? {
+///     client.send(&cmd)?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SecurityProfileBuilder<'a> {
+    profile_id: SecurityProfileId,
+    validation_level: Option<CertificateValidationLevel>,
+    tls_version_min: Option<TlsVersion>,
+    cipher_suite: Option<CipherSuite>,
+    root_ca: Option<&'a str>,
+    expected_hostname: Option<&'a str>,
+    client_cert: Option<&'a str>,
+    client_key: Option<&'a str>,
+    client_key_password: Option<&'a str>,
+    psk: Option<(&'a str, &'a str, PskEncoding)>,
+    server_name_indication: Option<&'a str>,
+}
+
+impl<'a> SecurityProfileBuilder<'a> {
+    pub const fn new(profile_id: SecurityProfileId) -> Self {
+        Self {
+            profile_id,
+            validation_level: None,
+            tls_version_min: None,
+            cipher_suite: None,
+            root_ca: None,
+            expected_hostname: None,
+            client_cert: None,
+            client_key: None,
+            client_key_password: None,
+            psk: None,
+            server_name_indication: None,
+        }
+    }
+
+    pub fn profile_id(&self) -> SecurityProfileId {
+        self.profile_id
+    }
+
+    #[must_use]
+    pub fn validation_level(mut self, level: CertificateValidationLevel) -> Self {
+        self.validation_level = Some(level);
+        self
+    }
+
+    #[must_use]
+    pub fn tls_version_min(mut self, version: TlsVersion) -> Self {
+        self.tls_version_min = Some(version);
+        self
+    }
+
+    #[must_use]
+    pub fn cipher_suite(mut self, suite: CipherSuite) -> Self {
+        self.cipher_suite = Some(suite);
+        self
+    }
+
+    /// Internal name of an imported trusted root certificate.
+    #[must_use]
+    pub fn root_ca(mut self, name: &'a str) -> Self {
+        self.root_ca = Some(name);
+        self
+    }
+
+    /// Hostname the server certificate is checked against, with validation
+    /// level 2 and above.
+    #[must_use]
+    pub fn expected_hostname(mut self, hostname: &'a str) -> Self {
+        self.expected_hostname = Some(hostname);
+        self
+    }
+
+    /// Internal name of an imported client certificate.
+    #[must_use]
+    pub fn client_cert(mut self, name: &'a str) -> Self {
+        self.client_cert = Some(name);
+        self
+    }
+
+    /// Internal name of an imported client private key, with an optional
+    /// password if it is password protected.
+    #[must_use]
+    pub fn client_key(mut self, name: &'a str, password: Option<&'a str>) -> Self {
+        self.client_key = Some(name);
+        self.client_key_password = password;
+        self
+    }
+
+    /// Pre-shared key and pre-shared key identity, both in `encoding`.
+    #[must_use]
+    pub fn psk(mut self, key: &'a str, identity: &'a str, encoding: PskEncoding) -> Self {
+        self.psk = Some((key, identity, encoding));
+        self
+    }
+
+    #[must_use]
+    pub fn server_name_indication(mut self, sni: &'a str) -> Self {
+        self.server_name_indication = Some(sni);
+        self
+    }
+
+    /// Check all settings against the limits of the module, so a profile is
+    /// never left half configured because of a setting rejected late.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.profile_id.0 > 4 {
+            return Err(Error::Overflow);
+        }
+
+        let check = |value: Option<&str>, max: usize| match value {
+            Some(v) if v.len() > max => Err(Error::Overflow),
+            _ => Ok(()),
+        };
+
+        check(self.root_ca, 200)?;
+        check(self.expected_hostname, 256)?;
+        check(self.client_cert, 200)?;
+        check(self.client_key, 200)?;
+        check(self.client_key_password, 128)?;
+        check(self.server_name_indication, 128)?;
+
+        if let Some((key, identity, encoding)) = self.psk {
+            let (key_max, identity_max) = match encoding {
+                PskEncoding::Ascii => (64, 128),
+                PskEncoding::Hex => (128, 256),
+            };
+            check(Some(key), key_max)?;
+            check(Some(identity), identity_max)?;
+        }
+
+        Ok(())
+    }
+
+    /// The `+USECPRF` sequence applying this profile, starting with a reset
+    /// of the profile.
+    pub fn commands(&self) -> Result<impl Iterator<Item = SecurityProfileManager> + '_, Error> {
+        self.validate()?;
+
+        let reset = SecurityProfileManager {
+            profile_id: self.profile_id,
+            operation: None,
+        };
+
+        Ok(
+            core::iter::once(reset).chain((0..OPERATIONS).filter_map(|i| {
+                self.operation(i).map(|operation| SecurityProfileManager {
+                    profile_id: self.profile_id,
+                    operation: Some(operation),
+                })
+            })),
+        )
+    }
+
+    fn operation(&self, index: usize) -> Option<SecurityProfileOperation> {
+        // Lengths are checked by `validate`
+        fn s<const N: usize>(value: &str) -> String<N> {
+            String::try_from(value).unwrap_or_default()
+        }
+
+        match index {
+            0 => self
+                .validation_level
+                .clone()
+                .map(SecurityProfileOperation::CertificateValidationLevel),
+            1 => self
+                .tls_version_min
+                .map(SecurityProfileOperation::SslTslVersion),
+            2 => self
+                .cipher_suite
+                .map(|c| SecurityProfileOperation::CipherSuite(c as u8)),
+            3 => self
+                .root_ca
+                .map(|v| SecurityProfileOperation::TrustedRootCertificateInternalName(s(v))),
+            4 => self
+                .expected_hostname
+                .map(|v| SecurityProfileOperation::ExpectedServerHostname(s(v))),
+            5 => self
+                .client_cert
+                .map(|v| SecurityProfileOperation::ClientCertificateInternalName(s(v))),
+            6 => self
+                .client_key
+                .map(|v| SecurityProfileOperation::ClientPrivateKeyInternalName(s(v))),
+            7 => self
+                .client_key_password
+                .map(|v| SecurityProfileOperation::ClientPrivateKeyPassword(s(v))),
+            8 => self
+                .psk
+                .map(|(key, _, encoding)| SecurityProfileOperation::PresharedKey(s(key), encoding)),
+            9 => self.psk.map(|(_, identity, encoding)| {
+                SecurityProfileOperation::PresharedKeyIdentity(s(identity), encoding)
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atat::AtatCmd;
+
+    fn written(
+        cmds: impl Iterator<Item = SecurityProfileManager>,
+    ) -> heapless::Vec<String<64>, 12> {
+        cmds.map(|cmd| {
+            let mut buf = [0u8; 64];
+            let len = cmd.write(&mut buf);
+            String::try_from(core::str::from_utf8(&buf[..len]).unwrap().trim_end()).unwrap()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn reset_first() {
+        let profile = SecurityProfileBuilder::new(SecurityProfileId(1))
+            .tls_version_min(TlsVersion::Tls1_2)
+            .cipher_suite(CipherSuite::RsaWithAes128CbcSha256)
+            .root_ca("ca")
+            .psk("secret", "device", PskEncoding::Ascii);
+
+        let cmds = written(profile.commands().unwrap());
+        assert_eq!(
+            cmds.as_slice(),
+            &[
+                "AT+USECPRF=1",
+                "AT+USECPRF=1,1,3",
+                "AT+USECPRF=1,2,2",
+                "AT+USECPRF=1,3,\"ca\"",
+                "AT+USECPRF=1,8,\"secret\",0",
+                "AT+USECPRF=1,9,\"device\",0",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let long = core::str::from_utf8(&[b'a'; 65]).unwrap();
+
+        let profile =
+            SecurityProfileBuilder::new(SecurityProfileId(0)).psk(long, "id", PskEncoding::Ascii);
+        assert!(matches!(profile.commands(), Err(Error::Overflow)));
+
+        let profile =
+            SecurityProfileBuilder::new(SecurityProfileId(0)).psk(long, "id", PskEncoding::Hex);
+        assert!(profile.commands().is_ok());
+
+        let profile = SecurityProfileBuilder::new(SecurityProfileId(5));
+        assert!(matches!(profile.commands(), Err(Error::Overflow)));
+    }
+}
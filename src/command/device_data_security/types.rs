@@ -5,7 +5,8 @@ use heapless::String;
 use serde::{Deserialize, Serialize};
 
 /// Type of operation
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SecurityOperation {
     /// 0: import a certificate or a private key (data provided by the stream of byte)
     ImportStream = 0,
@@ -20,7 +21,8 @@ pub enum SecurityOperation {
 }
 
 /// Type of the security data
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SecurityDataType {
     /// 0: trusted root CA (certificate authority) certificate
     TrustedRootCA = 0,
@@ -51,7 +53,7 @@ pub enum PinningLevel {
 }
 
 /// certificate validation level
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
 pub enum CertificateValidationLevel {
     /// * 0 (factory-programmed value): level 0 - No validation; the server
     ///   certificate will not be checked or verified. The server in this case
@@ -73,6 +75,75 @@ pub enum CertificateValidationLevel {
     RootCertValidationWithValidityDate = 3,
 }
 
+/// Minimum SSL/TLS version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TlsVersion {
+    /// * 0 (factory-programmed value): any; server can use any version for the
+    ///   connection
+    Any = 0,
+    /// * 1: TLSv1.0
+    Tls1_0 = 1,
+    /// * 2: TLSv1.1
+    Tls1_1 = 2,
+    /// * 3: TLSv1.2
+    Tls1_2 = 3,
+    /// * 4: TLSv1.3
+    Tls1_3 = 4,
+}
+
+/// Cipher suite, with the IANA enumeration in the comments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CipherSuite {
+    /// * 0 (factory-programmed value): (0x0000) automatic, negotiated in the
+    ///   handshake process
+    Automatic = 0,
+    /// * 1: (0x002f)
+    RsaWithAes128CbcSha = 1,
+    /// * 2: (0x003C)
+    RsaWithAes128CbcSha256 = 2,
+    /// * 3: (0x0035)
+    RsaWithAes256CbcSha = 3,
+    /// * 4: (0x003D)
+    RsaWithAes256CbcSha256 = 4,
+    /// * 5: (0x000a)
+    RsaWith3desEdeCbcSha = 5,
+    /// * 6: (0x008c)
+    PskWithAes128CbcSha = 6,
+    /// * 7: (0x008d)
+    PskWithAes256CbcSha = 7,
+    /// * 8: (0x008b)
+    PskWith3desEdeCbcSha = 8,
+    /// * 9: (0x0094)
+    RsaPskWithAes128CbcSha = 9,
+    /// * 10: (0x0095)
+    RsaPskWithAes256CbcSha = 10,
+    /// * 11: (0x0093)
+    RsaPskWith3desEdeCbcSha = 11,
+    /// * 12: (0x00ae)
+    PskWithAes128CbcSha256 = 12,
+    /// * 13: (0x00af)
+    PskWithAes256CbcSha384 = 13,
+    /// * 14: (0x00b6)
+    RsaPskWithAes128CbcSha256 = 14,
+    /// * 15: (0x00b7)
+    RsaPskWithAes256CbcSha384 = 15,
+}
+
+/// Encoding of a pre-shared key or pre-shared key identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PskEncoding {
+    /// * 0 (default value): ASCII string, with at most 64 characters for the
+    ///   key and 128 characters for the identity
+    Ascii = 0,
+    /// * 1: hexadecimal string, with at most 128 characters for the key and
+    ///   256 characters for the identity
+    Hex = 1,
+}
+
 #[derive(Clone, PartialEq, Eq, AtatEnum)]
 pub enum SecurityProfileOperation {
     /// - 0: certificate validation level;
@@ -88,7 +159,7 @@ pub enum SecurityProfileOperation {
     ///     * 3: TLSv1.2; connection allowed only to TLS/SSL servers which
     ///       support TLSv1.2
     #[at_arg(value = 1)]
-    SslTslVersion,
+    SslTslVersion(TlsVersion),
     /// - 2: cipher suite; allowed values for <param_val1> define which cipher
     ///   suite will be used:
     ///     * 0 (factory-programmed value): (0x0000) Automatic the cipher suite
@@ -156,7 +227,7 @@ pub enum SecurityProfileOperation {
     ///         - 1: <preshared_key> is an hexadecimal string and its maximum
     ///           length is 128 characters
     #[at_arg(value = 8)]
-    PresharedKey,
+    PresharedKey(String<128>, PskEncoding),
     ///  - 9: pre-shared key identity;
     ///     * <preshared_key_id> (string) is the pre-shared key identity used
     ///       for connection; the factoryprogrammed value is an empty string.
@@ -169,7 +240,7 @@ pub enum SecurityProfileOperation {
     ///         - 1: <preshared_key_id> is an hexadecimal string and its maximum
     ///           length is 256 characters
     #[at_arg(value = 9)]
-    PresharedKeyIdentity,
+    PresharedKeyIdentity(String<256>, PskEncoding),
     ///  - 10: SNI (Server Name Indication);
     ///     * <param_val1> (string) value for the additional negotiation header
     ///       SNI (Server Name Indication) used in SSL/TLS connection
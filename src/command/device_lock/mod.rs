@@ -5,7 +5,7 @@ pub mod responses;
 pub mod types;
 
 use atat::atat_derive::AtatCmd;
-use responses::PinStatus;
+use responses::{PinCounter, PinStatus};
 
 use super::NoResponse;
 
@@ -26,7 +26,7 @@ pub struct GetPinStatus;
 #[derive(Clone, AtatCmd)]
 #[at_cmd("+CPIN", NoResponse)]
 pub struct SetPin<'a> {
-    #[at_arg(position = 0, len = 4)]
+    #[at_arg(position = 0, len = 8)]
     pub pin: &'a str,
 }
 
@@ -40,6 +40,28 @@ pub struct SetPin<'a> {
 pub struct ChangePin<'a> {
     #[at_arg(position = 0, len = 8)]
     pub puk: &'a str,
-    #[at_arg(position = 1, len = 4)]
+    #[at_arg(position = 1, len = 8)]
     pub newpin: &'a str,
 }
+
+/// 9.3 Change password +CPWD
+///
+/// Sets a new password for the facility lock function defined by command
+/// +CLCK, eg. `"SC"` for the SIM PIN.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CPWD", NoResponse)]
+pub struct ChangePassword<'a> {
+    #[at_arg(position = 0, len = 2)]
+    pub fac: &'a str,
+    #[at_arg(position = 1, len = 8)]
+    pub oldpwd: &'a str,
+    #[at_arg(position = 2, len = 8)]
+    pub newpwd: &'a str,
+}
+
+/// Read remaining SIM PIN attempts +UPINCNT
+///
+/// Returns the number of attempts left to enter the PIN, PIN2, PUK and PUK2.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UPINCNT", PinCounter)]
+pub struct GetPinCounter;
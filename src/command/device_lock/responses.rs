@@ -9,3 +9,17 @@ pub struct PinStatus {
     #[at_arg(position = 0)]
     pub code: PinStatusCode,
 }
+
+/// Remaining SIM PIN attempts +UPINCNT
+#[derive(Clone, Debug, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PinCounter {
+    #[at_arg(position = 0)]
+    pub pin_attempts: u8,
+    #[at_arg(position = 1)]
+    pub pin2_attempts: u8,
+    #[at_arg(position = 2)]
+    pub puk_attempts: u8,
+    #[at_arg(position = 3)]
+    pub puk2_attempts: u8,
+}
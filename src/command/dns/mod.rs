@@ -20,6 +20,9 @@ use atat::atat_derive::AtatCmd;
 use responses::ResolveNameIpResponse;
 use types::ResolutionType;
 
+/// Maximum number of results kept from a single +UDNSRN resolution
+pub const MAX_DNS_RESULTS: usize = 4;
+
 /// 24.1 Resolve name / IP number through DNS +UDNSRN
 ///
 /// Translates a domain name to an IP address or an IP address to a domain name
@@ -48,11 +51,44 @@ use types::ResolutionType;
 ///   DNS can be put into action if the corresponding profile is activated (if
 ///   the user sets a DNS for a profile, and a different profile is activated,
 ///   the user DNS has no action and the network DNS is used if available).
+/// - A name may resolve to several addresses, reported one per line. Results
+///   beyond [`MAX_DNS_RESULTS`] are dropped.
 #[derive(Clone, AtatCmd)]
-#[at_cmd("+UDNSRN", ResolveNameIpResponse, attempts = 1, timeout_ms = 120000)]
+#[at_cmd(
+    "+UDNSRN",
+    heapless::Vec<ResolveNameIpResponse, MAX_DNS_RESULTS>,
+    attempts = 1,
+    timeout_ms = 120000
+)]
 pub struct ResolveNameIp<'a> {
     #[at_arg(position = 0)]
     pub resolution_type: ResolutionType,
     #[at_arg(position = 1, len = 128)]
     pub ip_domain_string: &'a str,
 }
+
+#[cfg(test)]
+mod tests {
+    use atat::AtatCmd;
+
+    use super::*;
+
+    #[test]
+    fn parse_resolved_addresses() {
+        let cmd = ResolveNameIp {
+            resolution_type: ResolutionType::DomainNameToIp,
+            ip_domain_string: "www.u-blox.com",
+        };
+        let results = cmd
+            .parse(Ok(
+                b"+UDNSRN: \"93.184.216.34\"\r\n+UDNSRN: \"93.184.216.35\"",
+            ))
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].ip_domain_string.as_str(), "93.184.216.34");
+        assert_eq!(results[1].ip_domain_string.as_str(), "93.184.216.35");
+
+        let results = cmd.parse(Ok(b"+UDNSRN: \"93.184.216.34\"")).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+}
@@ -3,7 +3,7 @@ use atat::atat_derive::AtatResp;
 use heapless::String;
 
 /// 24.1 Resolve name / IP number through DNS +UDNSRN
-#[derive(Clone, PartialEq, Eq, AtatResp)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ResolveNameIpResponse {
     #[at_arg(position = 0)]
@@ -7,7 +7,7 @@ pub mod responses;
 
 use atat::atat_derive::AtatCmd;
 use heapless::{String, Vec};
-use responses::{ReadBlockResponse, ReadFileResponse};
+use responses::{FileSize, FreeSpace, ReadBlockResponse, ReadFileResponse};
 
 use super::NoResponse;
 
@@ -91,6 +91,23 @@ pub struct DownloadFile<'a> {
 #[at_cmd("+ULSTFILE=0", Vec<String<248>, 10>, value_sep = false)]
 pub struct ListFiles;
 
+/// 22.3 List files information +ULSTFILE
+///
+/// Retrieves the remaining free FS space expressed in bytes.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+ULSTFILE=1", FreeSpace, value_sep = false)]
+pub struct GetFreeSpace;
+
+/// 22.3 List files information +ULSTFILE
+///
+/// Retrieves the size of the file `filename` expressed in bytes.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+ULSTFILE=2,", FileSize, value_sep = false)]
+pub struct GetFileSize<'a> {
+    #[at_arg(position = 0, len = 248)]
+    pub filename: &'a str,
+}
+
 /// 22.4 Read file +URDFILE
 ///
 /// Retrieves a file from the file system.
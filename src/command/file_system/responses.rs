@@ -1,87 +1,205 @@
 //! Responses for File system Commands
 use atat::atat_derive::AtatResp;
-use atat::heapless_bytes::Bytes;
-use heapless::String;
+use heapless::{String, Vec};
+use serde::{de, Deserialize, Deserializer};
+
+/// Maximum number of bytes read from the file system with a single
+/// [`ReadBlock`](super::ReadBlock)
+pub const READ_BLOCK_SIZE: usize = 512;
+
+/// Maximum number of bytes read from the file system with a single
+/// [`ReadFile`](super::ReadFile)
+pub const READ_FILE_SIZE: usize = 1024;
+
+/// 22.3 List files information +ULSTFILE
+#[derive(Clone, Debug, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FreeSpace {
+    #[at_arg(position = 0)]
+    pub free: usize,
+}
+
+/// 22.3 List files information +ULSTFILE
+#[derive(Clone, Debug, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FileSize {
+    #[at_arg(position = 0)]
+    pub size: usize,
+}
 
 /// 22.4 Read file +URDFILE
-#[derive(Debug, PartialEq, Eq, AtatResp)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ReadFileResponse {
     #[at_arg(position = 0)]
-    pub filename: String<248>,
-    #[at_arg(position = 1)]
-    pub size: usize,
-    // TODO: Streaming data?
-    #[at_arg(position = 2)]
-    pub data: Bytes<{ 1024 + 2 }>,
+    pub file: FileData<READ_FILE_SIZE>,
+}
+
+impl ReadFileResponse {
+    /// The file content.
+    pub fn data(&self) -> &[u8] {
+        &self.file.data
+    }
 }
 
 /// 22.5 Partial read file +URDBLOCK
 #[derive(Clone, Debug, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ReadBlockResponse {
     #[at_arg(position = 0)]
+    pub block: FileData<READ_BLOCK_SIZE>,
+}
+
+impl ReadBlockResponse {
+    /// The block content.
+    pub fn data(&self) -> &[u8] {
+        &self.block.data
+    }
+}
+
+/// File content of a +URDFILE or +URDBLOCK response.
+///
+/// The content is sent as raw bytes between quotes, and may itself contain
+/// quotes, commas or CR LF. It can only be delimited using the size that
+/// precedes it, so it is parsed from the raw response rather than by the
+/// derived deserialization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FileData<const N: usize> {
     pub filename: String<248>,
-    #[at_arg(position = 1)]
+    /// Number of bytes read
     pub size: usize,
-    #[at_arg(position = 2)]
-    pub data: Bytes<{ 512 + 2 }>,
+    pub data: Vec<u8, N>,
+}
+
+impl<const N: usize> FileData<N> {
+    /// Parse the raw information response, eg.
+    /// `+URDBLOCK: "file.txt",4,"a,"b"`
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        let input = input.trim_ascii_start();
+        let input = input
+            .strip_prefix(b"+URDFILE:")
+            .or_else(|| input.strip_prefix(b"+URDBLOCK:"))
+            .unwrap_or(input);
+
+        let rest = input.trim_ascii_start().strip_prefix(b"\"")?;
+        let end = rest.iter().position(|&c| c == b'"')?;
+        let filename = core::str::from_utf8(&rest[..end]).ok()?;
+        let (size, rest) = split_number(rest[end + 1..].strip_prefix(b",")?)?;
+
+        let rest = rest.strip_prefix(b",\"")?;
+        let data = rest.get(..size)?;
+        if rest.get(size) != Some(&b'"') {
+            return None;
+        }
+
+        Some(Self {
+            filename: String::try_from(filename).ok()?,
+            size,
+            data: Vec::from_slice(data).ok()?,
+        })
+    }
+}
+
+/// Split the leading decimal number off `input`
+fn split_number(input: &[u8]) -> Option<(usize, &[u8])> {
+    let end = input
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let n = core::str::from_utf8(&input[..end]).ok()?.parse().ok()?;
+    Some((n, &input[end..]))
+}
+
+impl<'de, const N: usize> Deserialize<'de> for FileData<N> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FileDataVisitor<const N: usize>;
+
+        impl<'de, const N: usize> de::Visitor<'de> for FileDataVisitor<N> {
+            type Value = FileData<N>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a +URDFILE or +URDBLOCK response")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                FileData::parse(value).ok_or_else(|| E::custom("malformed file content"))
+            }
+        }
+
+        deserializer.deserialize_bytes(FileDataVisitor)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// The quoted content of `resp`, as taken by the size field
+    fn content(resp: &[u8]) -> &[u8] {
+        let start = resp.windows(2).position(|w| w == b",\"").unwrap() + 2;
+        &resp[start..resp.len() - 1]
+    }
+
     #[test]
-    #[ignore]
     fn deserialize_read_file_response() {
-        let resp = b"+URDFILE: \"response.txt\",655,\"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nContent-Length: 74\r\nConnection: close\r\nDate: Wed, 14 Jul 2021 11:50:30 GMT\r\nx-amzn-RequestId: 021e3877-1e6d-447d-996e-4bc89087bdc5\r\nx-amz-apigw-id: CdVdFFJhjoEFc0w=\r\nX-Amzn-Trace-Id: Root=1-60eecf86-227f4f986747c3113846cd63;Sampled=1\r\nVia: 1.1 32e3b86ae254a231182567c0124af893.cloudfront.net (CloudFront), 1.1 2afacc6ad96dbba3f0b477cd95f16459.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Error from cloudfront\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Amz-Cf-Id: FELxJa2hgelObvyEP16HS4yEK-emXa1NiMsRXl-rmarzg309KeD34g==\r\n\r\n{\"uuid\": \"what\"}\"";
-
-        let exp = ReadFileResponse {
-            filename: String::try_from("response.txt").unwrap(),
-            size: 655,
-            data: Bytes::try_from(b"\"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nContent-Length: 74\r\nConnection: close\r\nDate: Wed, 14 Jul 2021 11:50:30 GMT\r\nx-amzn-RequestId: 021e3877-1e6d-447d-996e-4bc89087bdc5\r\nx-amz-apigw-id: CdVdFFJhjoEFc0w=\r\nX-Amzn-Trace-Id: Root=1-60eecf86-227f4f986747c3113846cd63;Sampled=1\r\nVia: 1.1 32e3b86ae254a231182567c0124af893.cloudfront.net (CloudFront), 1.1 2afacc6ad96dbba3f0b477cd95f16459.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Error from cloudfront\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Amz-Cf-Id: FELxJa2hgelObvyEP16HS4yEK-emXa1NiMsRXl-rmarzg309KeD34g==\r\n\r\n{\"uuid\": \"what\"}\"").unwrap(),
-        };
+        let resp = b"+URDFILE: \"response.txt\",597,\"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nContent-Length: 74\r\nConnection: close\r\nDate: Wed, 14 Jul 2021 11:50:30 GMT\r\nx-amzn-RequestId: 021e3877-1e6d-447d-996e-4bc89087bdc5\r\nx-amz-apigw-id: CdVdFFJhjoEFc0w=\r\nX-Amzn-Trace-Id: Root=1-60eecf86-227f4f986747c3113846cd63;Sampled=1\r\nVia: 1.1 32e3b86ae254a231182567c0124af893.cloudfront.net (CloudFront), 1.1 2afacc6ad96dbba3f0b477cd95f16459.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Error from cloudfront\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Amz-Cf-Id: FELxJa2hgelObvyEP16HS4yEK-emXa1NiMsRXl-rmarzg309KeD34g==\r\n\r\n{\"uuid\": \"what\"}\"";
 
-        assert_eq!(atat::serde_at::from_slice(resp), Ok(exp));
+        let file = FileData::<READ_FILE_SIZE>::parse(resp).unwrap();
+        assert_eq!(file.filename, "response.txt");
+        assert_eq!(file.size, 597);
+        assert_eq!(file.data, content(resp));
     }
 
     #[test]
-    #[ignore]
     fn deserialize_partial_block_response() {
         let resp = b"+URDBLOCK: \"response.txt\",512,\"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 25\r\nConnection: close\r\nDate: Mon, 19 Jul 2021 07:23:35 GMT\r\nx-amzn-RequestId: 4a50eb56-5c1a-4388-9a2f-a1966ba9c8a2\r\nx-amz-apigw-id: CtNCvF1SDoEF2dw=\r\nX-Amzn-Trace-Id: Root=1-60f52877-6f5b63ac154d314436832848;Sampled=1\r\nVia: 1.1 58b222ebbb6cc6c8c8c9a46127ae3a3e.cloudfront.net (CloudFront), 1.1 6fa33d47af6f4da7007689083cfe9b9c.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Error from cloudfront\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Amz-\"";
 
-        let exp = ReadBlockResponse {
-            filename: String::try_from("response.txt").unwrap(),
-            size: 512,
-            data: Bytes::try_from(b"\"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 25\r\nConnection: close\r\nDate: Mon, 19 Jul 2021 07:23:35 GMT\r\nx-amzn-RequestId: 4a50eb56-5c1a-4388-9a2f-a1966ba9c8a2\r\nx-amz-apigw-id: CtNCvF1SDoEF2dw=\r\nX-Amzn-Trace-Id: Root=1-60f52877-6f5b63ac154d314436832848;Sampled=1\r\nVia: 1.1 58b222ebbb6cc6c8c8c9a46127ae3a3e.cloudfront.net (CloudFront), 1.1 6fa33d47af6f4da7007689083cfe9b9c.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Error from cloudfront\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Amz-\"").unwrap(),
-        };
-
-        assert_eq!(atat::serde_at::from_slice(resp), Ok(exp));
+        let file = FileData::<READ_BLOCK_SIZE>::parse(resp).unwrap();
+        assert_eq!(file.filename, "response.txt");
+        assert_eq!(file.size, 512);
+        assert_eq!(file.data, content(resp));
     }
 
     #[test]
-    #[ignore]
     fn deserialize_partial_block_response_ok() {
         let resp = b"+URDBLOCK: \"response.txt\",512,\"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2553\r\nConnection: close\r\nVary: Accept-Encoding\r\nDate: Mon, 19 Jul 2021 07:47:39 GMT\r\nx-amzn-RequestId: 436ba5b8-2aad-4089-a4fd-1b1c38773c87\r\nx-amz-apigw-id: CtQkMFE_DoEFUzg=\r\nX-Amzn-Trace-Id: Root=1-60f52e1a-0a05343260f3ba3331eea9d6;Sampled=1\r\nVia: 1.1 f99b5b46e77cfe9c3413f99dc8a4088c.cloudfront.net (CloudFront), 1.1 2f194b62c8c43859cbf5af8e53a8d2a7.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Miss from cloudfront\r\nX-Amz-Cf-Pop\"";
 
-        let exp = ReadBlockResponse {
-            filename: String::try_from("response.txt").unwrap(),
-            size: 512,
-            data: Bytes::try_from(b"\"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2553\r\nConnection: close\r\nVary: Accept-Encoding\r\nDate: Mon, 19 Jul 2021 07:47:39 GMT\r\nx-amzn-RequestId: 436ba5b8-2aad-4089-a4fd-1b1c38773c87\r\nx-amz-apigw-id: CtQkMFE_DoEFUzg=\r\nX-Amzn-Trace-Id: Root=1-60f52e1a-0a05343260f3ba3331eea9d6;Sampled=1\r\nVia: 1.1 f99b5b46e77cfe9c3413f99dc8a4088c.cloudfront.net (CloudFront), 1.1 2f194b62c8c43859cbf5af8e53a8d2a7.cloudfront.net (CloudFront)\r\nX-Amz-Cf-Pop: FRA2-C2\r\nX-Cache: Miss from cloudfront\r\nX-Amz-Cf-Pop\"").unwrap(),
-        };
-
-        assert_eq!(atat::serde_at::from_slice(resp), Ok(exp));
+        let file = FileData::<READ_BLOCK_SIZE>::parse(resp).unwrap();
+        assert_eq!(file.filename, "response.txt");
+        assert_eq!(file.size, 512);
+        assert_eq!(file.data, content(resp));
     }
 
     #[test]
     fn deserialize_certificate() {
-        let resp = b"+URDBLOCK: \"response.txt\",512,\": FRA2-C2\r\nX-Amz-Cf-Id: _5ZSzv-MrL1yMkdklMqbtggquF-NEe6lO36pw9cYsKJVEITyIdrbqQ==\r\n\r\n{\"Data\":{\"certificate_pem\":\"-----BEGIN CERTIFICATE-----\nMIIDWjCCAkKgAwIBAgIVANeQUG3TupBxD8FLSz+AAqxU7rU0MA0GCSqGSIb3DQEB\nCwUAME0xSzBJBgNVBAsMQkFtYXpvbiBXZWIgU2VydmljZXMgTz1BbWF6b24uY29t\nIEluYy4gTD1TZWF0dGxlIFNUPVdhc2hpbmd0b24gQz1VUzAeFw0yMTA3MjIwOTA2\nMTlaFw00OTEyMzEyMzU5NTlaMB4xHDAaBgNVBAMME0FXUyBJb1QgQ2VydGlmaWNh\ndGUwggEiMA0GCSqGSIb3DQEBAQUAA4IBDwAwggEKAoIBAQCqCFWHSRH35wSjP0SR\nQijGwEfWArPaqr33S80y9D\"";
+        let resp = b"+URDBLOCK: \"response.txt\",487,\": FRA2-C2\r\nX-Amz-Cf-Id: _5ZSzv-MrL1yMkdklMqbtggquF-NEe6lO36pw9cYsKJVEITyIdrbqQ==\r\n\r\n{\"Data\":{\"certificate_pem\":\"-----BEGIN CERTIFICATE-----\nMIIDWjCCAkKgAwIBAgIVANeQUG3TupBxD8FLSz+AAqxU7rU0MA0GCSqGSIb3DQEB\nCwUAME0xSzBJBgNVBAsMQkFtYXpvbiBXZWIgU2VydmljZXMgTz1BbWF6b24uY29t\nIEluYy4gTD1TZWF0dGxlIFNUPVdhc2hpbmd0b24gQz1VUzAeFw0yMTA3MjIwOTA2\nMTlaFw00OTEyMzEyMzU5NTlaMB4xHDAaBgNVBAMME0FXUyBJb1QgQ2VydGlmaWNh\ndGUwggEiMA0GCSqGSIb3DQEBAQUAA4IBDwAwggEKAoIBAQCqCFWHSRH35wSjP0SR\nQijGwEfWArPaqr33S80y9D\"";
 
-        let exp = ReadBlockResponse {
-            filename: String::try_from("response.txt").unwrap(),
-            size: 512,
-            data: Bytes::try_from(b"\": FRA2-C2\r\nX-Amz-Cf-Id: _5ZSzv-MrL1yMkdklMqbtggquF-NEe6lO36pw9cYsKJVEITyIdrbqQ==\r\n\r\n{\"Data\":{\"certificate_pem\":\"-----BEGIN CERTIFICATE-----\nMIIDWjCCAkKgAwIBAgIVANeQUG3TupBxD8FLSz+AAqxU7rU0MA0GCSqGSIb3DQEB\nCwUAME0xSzBJBgNVBAsMQkFtYXpvbiBXZWIgU2VydmljZXMgTz1BbWF6b24uY29t\nIEluYy4gTD1TZWF0dGxlIFNUPVdhc2hpbmd0b24gQz1VUzAeFw0yMTA3MjIwOTA2\nMTlaFw00OTEyMzEyMzU5NTlaMB4xHDAaBgNVBAMME0FXUyBJb1QgQ2VydGlmaWNh\ndGUwggEiMA0GCSqGSIb3DQEBAQUAA4IBDwAwggEKAoIBAQCqCFWHSRH35wSjP0SR\nQijGwEfWArPaqr33S80y9D\"").unwrap(),
-        };
+        let file = FileData::<READ_BLOCK_SIZE>::parse(resp).unwrap();
+        assert_eq!(file.filename, "response.txt");
+        assert_eq!(file.size, 487);
+        assert_eq!(file.data, content(resp));
+    }
+
+    #[test]
+    fn parse_embedded_quotes() {
+        let block = FileData::<READ_BLOCK_SIZE>::parse(b"\"a.txt\",7,\"\",\r\nOK\"\"").unwrap();
+        assert_eq!(block.data, b"\",\r\nOK\"");
 
-        assert_eq!(atat::serde_at::from_slice(resp), Ok(exp));
+        // The content does not end where the size says
+        assert_eq!(
+            FileData::<READ_BLOCK_SIZE>::parse(b"\"a.txt\",3,\"ab\""),
+            None
+        );
+        assert_eq!(
+            FileData::<READ_BLOCK_SIZE>::parse(b"\"a.txt\",1,\"ab\""),
+            None
+        );
     }
 }
@@ -5,23 +5,28 @@ use atat::heapless_bytes::Bytes;
 
 /// 4.1 Manufacturer identification
 /// Text string identifying the manufacturer.
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ManufacturerId {
     #[at_arg(position = 0)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
     pub manufacturer: Bytes<10>,
 }
 
 /// 4.3 Model identification
 /// Text string identifying the model identification.
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ModelId {
     #[at_arg(position = 0)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
     pub model: Bytes<16>,
 }
 
 /// 4.5 Firmware version identification
 /// Returns the firmware version of the module.
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FirmwareVersion {
     #[at_arg(position = 0)]
     pub version: types::FirmwareVersion,
@@ -31,7 +36,8 @@ pub struct FirmwareVersion {
 ///
 /// Returns the product serial number, the International Mobile Equipment
 /// Identity (IMEI) of the MT.
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IMEI {
     #[at_arg(position = 0)]
     pub imei: u64,
@@ -41,15 +47,18 @@ pub struct IMEI {
 ///
 /// Returns some module information as the module type number and some details
 /// about the firmware version.
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IdentificationInformationResponse {
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
     pub app_ver: Bytes<32>,
 }
 
 /// 4.11 International mobile subscriber identification +CIM
 ///
 /// Request the IMSI (International Mobile Subscriber Identity).
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CIMI {
     /// International Mobile Subscriber Identity
     #[at_arg(position = 0)]
@@ -60,7 +69,8 @@ pub struct CIMI {
 ///
 /// Returns the ICCID (Integrated Circuit Card ID) of the SIM-card. ICCID is a
 /// serial number identifying the SIM.
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CCID {
     #[at_arg(position = 0)]
     pub ccid: u128,
@@ -0,0 +1,89 @@
+//! ### 26 - GNSS
+//!
+//! u-blox cellular modules can control a u-blox GNSS receiver connected to the
+//! module's DDC (I2C) interface, or the GNSS receiver integrated in the module
+//! (e.g. SARA-R510M8S). The receiver is powered and configured through the
+//! cellular module, which also handles the aiding (AssistNow) data and can
+//! store the last NMEA sentences for retrieval over the AT interface.
+pub mod responses;
+pub mod types;
+pub mod urc;
+
+use atat::atat_derive::AtatCmd;
+use responses::{GnssPowerState, GpsFixData, RecommendedMinimumData};
+use types::{AidingMode, GnssMode, GnssSystems, NmeaMode, UnsolicitedAidingMode};
+
+use super::NoResponse;
+
+/// 26.2 GNSS power management +UGPS
+///
+/// Switches on or off a u-blox GNSS receiver connected to the cellular module
+/// via a dedicated DDC (I2C) interface. Furthermore it configures the aiding
+/// mode and the GNSS systems used by the receiver.
+///
+/// **NOTES:**
+/// - The first time the receiver is switched on, the module needs some time to
+///   configure it, and the response can be delayed by several seconds.
+/// - <aid_mode> and <GNSS_systems> are only allowed when <mode>=1.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGPS", NoResponse, timeout_ms = 10000)]
+pub struct SetGnssPower {
+    #[at_arg(position = 0)]
+    pub mode: GnssMode,
+    #[at_arg(position = 1)]
+    pub aid_mode: Option<AidingMode>,
+    #[at_arg(position = 2)]
+    pub gnss_systems: Option<GnssSystems>,
+}
+
+/// 26.2 GNSS power management +UGPS
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGPS?", GnssPowerState)]
+pub struct GetGnssPower;
+
+/// 26.3 Assisted GNSS unsolicited indication +UGIND
+///
+/// Enables or disables sending of the `+UUGIND` URC, which reports the result
+/// of the aiding operations configured with +UGPS.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGIND", NoResponse)]
+pub struct SetAidingIndication {
+    #[at_arg(position = 0)]
+    pub mode: UnsolicitedAidingMode,
+}
+
+/// 26.15 Get GPS fix data +UGGGA
+///
+/// Enables or disables the storing of the last GGA NMEA message. The GNSS
+/// receiver must be powered on with +UGPS before setting <mode>=1.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGGGA", NoResponse)]
+pub struct SetGpsFixDataStorage {
+    #[at_arg(position = 0)]
+    pub mode: NmeaMode,
+}
+
+/// 26.15 Get GPS fix data +UGGGA
+///
+/// Reads the last stored GGA NMEA message.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGGGA?", GpsFixData, timeout_ms = 10000)]
+pub struct GetGpsFixData;
+
+/// 26.17 Get recommended minimum GNSS data +UGRMC
+///
+/// Enables or disables the storing of the last RMC NMEA message. The GNSS
+/// receiver must be powered on with +UGPS before setting <mode>=1.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGRMC", NoResponse)]
+pub struct SetRecommendedMinimumDataStorage {
+    #[at_arg(position = 0)]
+    pub mode: NmeaMode,
+}
+
+/// 26.17 Get recommended minimum GNSS data +UGRMC
+///
+/// Reads the last stored RMC NMEA message.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGRMC?", RecommendedMinimumData, timeout_ms = 10000)]
+pub struct GetRecommendedMinimumData;
@@ -0,0 +1,115 @@
+//! Responses for GNSS Commands
+use super::types::{AidingMode, GnssMode, GnssSystems};
+use atat::atat_derive::AtatResp;
+use atat::heapless_bytes::Bytes;
+
+/// Maximum length of an NMEA sentence is 82 characters, including the
+/// trailing `<CR><LF>`.
+pub const NMEA_SENTENCE_LEN: usize = 82;
+
+/// 26.2 GNSS power management +UGPS
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssPowerState {
+    #[at_arg(position = 0)]
+    pub mode: GnssMode,
+    #[at_arg(position = 1)]
+    pub aid_mode: Option<AidingMode>,
+    #[at_arg(position = 2)]
+    pub gnss_systems: Option<GnssSystems>,
+}
+
+/// 26.15 Get GPS fix data +UGGGA
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GpsFixData {
+    #[at_arg(position = 0)]
+    pub mode: u8,
+    /// Last stored `$G?GGA` sentence, or `Not available`.
+    #[at_arg(position = 1)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub sentence: Bytes<NMEA_SENTENCE_LEN>,
+}
+
+impl GpsFixData {
+    /// The stored NMEA sentence, or `None` if the module has nothing stored
+    /// yet.
+    pub fn sentence(&self) -> Option<&[u8]> {
+        nmea_sentence(&self.sentence)
+    }
+
+    /// Whether the GGA sentence reports a fix (fix quality field, 6th field,
+    /// is non-zero).
+    pub fn has_fix(&self) -> bool {
+        matches!(
+            self.sentence().and_then(|s| nmea_field(s, 6)),
+            Some(q) if !q.is_empty() && q != b"0"
+        )
+    }
+}
+
+/// 26.17 Get recommended minimum GNSS data +UGRMC
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecommendedMinimumData {
+    #[at_arg(position = 0)]
+    pub mode: u8,
+    /// Last stored `$G?RMC` sentence, or `Not available`.
+    #[at_arg(position = 1)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub sentence: Bytes<NMEA_SENTENCE_LEN>,
+}
+
+impl RecommendedMinimumData {
+    /// The stored NMEA sentence, or `None` if the module has nothing stored
+    /// yet.
+    pub fn sentence(&self) -> Option<&[u8]> {
+        nmea_sentence(&self.sentence)
+    }
+
+    /// Whether the RMC sentence reports a valid fix (status field, 2nd field,
+    /// is `A`).
+    pub fn has_fix(&self) -> bool {
+        self.sentence().and_then(|s| nmea_field(s, 2)) == Some(b"A")
+    }
+}
+
+fn nmea_sentence(raw: &[u8]) -> Option<&[u8]> {
+    let trimmed = raw.trim_ascii();
+    if trimmed.first() == Some(&b'$') {
+        Some(trimmed)
+    } else {
+        None
+    }
+}
+
+/// Get the `index`'th comma separated field of an NMEA sentence, where index 0
+/// is the talker/sentence identifier.
+fn nmea_field(sentence: &[u8], index: usize) -> Option<&[u8]> {
+    let data = sentence.split(|&b| b == b'*').next()?;
+    data.split(|&b| b == b',').nth(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_rmc() {
+        let resp =
+            b"+UGRMC: 1,$GPRMC,083559.00,A,4717.11437,N,00833.91522,E,0.004,77.52,091202,,,A*57";
+        let rmc: RecommendedMinimumData = atat::serde_at::from_slice(resp).unwrap();
+
+        assert_eq!(rmc.mode, 1);
+        assert!(rmc.has_fix());
+        assert_eq!(
+            rmc.sentence(),
+            Some(&b"$GPRMC,083559.00,A,4717.11437,N,00833.91522,E,0.004,77.52,091202,,,A*57"[..])
+        );
+
+        let resp = b"+UGRMC: 1,Not available";
+        let rmc: RecommendedMinimumData = atat::serde_at::from_slice(resp).unwrap();
+        assert_eq!(rmc.sentence(), None);
+        assert!(!rmc.has_fix());
+    }
+}
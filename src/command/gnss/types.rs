@@ -0,0 +1,98 @@
+//! Argument and parameter types used by GNSS Commands and Responses
+use atat::atat_derive::{AtatEnum, AtatLen};
+use serde::{Deserialize, Serialize};
+
+/// GNSS receiver power state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GnssMode {
+    /// • 0 (default value): GNSS receiver powered off
+    Off = 0,
+    /// • 1: GNSS receiver powered on
+    On = 1,
+}
+
+/// Aiding mode bitmask. Values can be combined, e.g.
+/// `AidingMode::AUTOMATIC_LOCAL | AidingMode::ASSIST_NOW_ONLINE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatLen, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AidingMode(pub u8);
+
+impl AidingMode {
+    /// 0 (default value): no aiding
+    pub const NONE: Self = Self(0);
+    /// 1: automatic local aiding
+    pub const AUTOMATIC_LOCAL: Self = Self(1);
+    /// 2: AssistNow Offline
+    pub const ASSIST_NOW_OFFLINE: Self = Self(2);
+    /// 4: AssistNow Online
+    pub const ASSIST_NOW_ONLINE: Self = Self(4);
+    /// 8: AssistNow Autonomous
+    pub const ASSIST_NOW_AUTONOMOUS: Self = Self(8);
+}
+
+impl core::ops::BitOr for AidingMode {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// GNSS systems bitmask. Values can be combined, e.g.
+/// `GnssSystems::GPS | GnssSystems::GLONASS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatLen, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GnssSystems(pub u8);
+
+impl GnssSystems {
+    /// 1: GPS
+    pub const GPS: Self = Self(1);
+    /// 2: SBAS
+    pub const SBAS: Self = Self(2);
+    /// 4: Galileo
+    pub const GALILEO: Self = Self(4);
+    /// 8: BeiDou
+    pub const BEIDOU: Self = Self(8);
+    /// 16: IMES
+    pub const IMES: Self = Self(16);
+    /// 32: QZSS
+    pub const QZSS: Self = Self(32);
+    /// 64: GLONASS
+    pub const GLONASS: Self = Self(64);
+}
+
+impl Default for GnssSystems {
+    /// GPS + SBAS, the factory-programmed value
+    fn default() -> Self {
+        Self::GPS | Self::SBAS
+    }
+}
+
+impl core::ops::BitOr for GnssSystems {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Storing of the last NMEA message of a given type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NmeaMode {
+    /// • 0 (default value): disabled
+    Disabled = 0,
+    /// • 1: enabled
+    Enabled = 1,
+}
+
+/// Indicates whether the +UUGIND URC is enabled or not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UnsolicitedAidingMode {
+    /// • 0 (default value): disable the +UUGIND URC
+    Disabled = 0,
+    /// • 1: enable the +UUGIND URC
+    Enabled = 1,
+}
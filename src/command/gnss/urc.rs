@@ -0,0 +1,34 @@
+//! Unsolicited responses for GNSS Commands
+use super::types::AidingMode;
+use atat::atat_derive::AtatResp;
+
+/// 26.3 Assisted GNSS unsolicited indication +UUGIND
+///
+/// Reports the result of an aiding operation, when enabled with +UGIND.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AidingIndication {
+    /// The aiding mode the result refers to
+    #[at_arg(position = 0)]
+    pub aid_mode: AidingMode,
+    /// Result of the aiding operation:
+    /// - 0: no error
+    /// - 1: wrong URL (for AssistNow Online)
+    /// - 2: HTTP error (for AssistNow Online)
+    /// - 3: create socket error (for AssistNow Online)
+    /// - 4: close socket error (for AssistNow Online)
+    /// - 5: write to socket error (for AssistNow Online)
+    /// - 6: read from socket error (for AssistNow Online)
+    /// - 7: connection / DNS error (for AssistNow Online)
+    /// - 8: file system error
+    /// - 9: generic error
+    /// - 10: no answer from GNSS (for local aiding and AssistNow Autonomous)
+    /// - 11: data collection in progress (for local aiding)
+    /// - 12: GNSS configuration failed (for AssistNow Autonomous)
+    /// - 13: RTC calibration failed (for local aiding)
+    /// - 14: feature not supported
+    /// - 15: feature partially supported
+    /// - 16: authentication token missing (for AssistNow Online)
+    #[at_arg(position = 1)]
+    pub result: u8,
+}
@@ -3,7 +3,8 @@ use super::types::GpioMode;
 use atat::atat_derive::AtatResp;
 
 /// 20.2 GPIO select configuration command +UGPIOC
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GpioConfiguration {
     /// GPIO pin identifier: pin number
     /// See the GPIO mapping for the available GPIO pins, their mapping and factoryprogrammed values on different u-blox cellular modules series and product version.
@@ -17,7 +18,8 @@ pub struct GpioConfiguration {
 }
 
 /// 20.3.3 Defined values
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GpioPinValue {
     /// Number GPIO pin identifier: pin number
     #[at_arg(position = 0)]
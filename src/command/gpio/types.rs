@@ -3,14 +3,16 @@
 use atat::atat_derive::AtatEnum;
 
 /// GPIO output value (for output function <`gpio_mode>=0` only):
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GpioOutValue {
     Low = 0,
     High = 1,
 }
 
 /// GPIO input value (for input function <`gpio_mode>=1` only):
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GpioInPull {
     /// (default value): no resistor activated
     NoPull = 0,
@@ -20,7 +22,8 @@ pub enum GpioInPull {
     PullDown = 2,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GpioMode {
     /// • 0: output
     #[at_arg(value = 0)]
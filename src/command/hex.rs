@@ -0,0 +1,70 @@
+//! Hex encoding of the SIM access, SMS and socket command and response
+//! payloads
+use core::fmt::Write;
+use heapless::String;
+
+/// Hex encode `data` with upper case digits. Returns `None` if the encoded
+/// data does not fit `N`.
+pub fn encode<const N: usize>(data: &[u8]) -> Option<String<N>> {
+    let mut out = String::new();
+    for b in data {
+        write!(out, "{:02X}", b).ok()?;
+    }
+    Some(out)
+}
+
+/// Decode the hex string `hex` into `buf`. Returns the number of bytes
+/// written, or `None` if `hex` is malformed or does not fit `buf`.
+pub fn decode(hex: &str, buf: &mut [u8]) -> Option<usize> {
+    let hex = hex.as_bytes();
+    if hex.len() % 2 != 0 || hex.len() / 2 > buf.len() {
+        return None;
+    }
+
+    for (out, pair) in buf.iter_mut().zip(hex.chunks_exact(2)) {
+        *out = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Some(hex.len() / 2)
+}
+
+/// Decode the hex digits in `buf` over the start of `buf` itself, avoiding a
+/// second buffer. Returns the number of decoded bytes, or `None` if `buf` is
+/// malformed, in which case the contents of `buf` are unspecified.
+pub fn decode_in_place(buf: &mut [u8]) -> Option<usize> {
+    if buf.len() % 2 != 0 {
+        return None;
+    }
+
+    // Byte `i` is written after hex digits `2i` and `2i + 1` have been read,
+    // so the output never overtakes the input.
+    for i in 0..buf.len() / 2 {
+        buf[i] = (nibble(buf[2 * i])? << 4) | nibble(buf[2 * i + 1])?;
+    }
+    Some(buf.len() / 2)
+}
+
+fn nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_hex_in_place() {
+        let mut buf = *b"48656c6C6f0D0a";
+        assert_eq!(decode_in_place(&mut buf), Some(7));
+        assert_eq!(&buf[..7], b"Hello\r\n");
+
+        let mut odd = *b"486";
+        assert_eq!(decode_in_place(&mut odd), None);
+        let mut invalid = *b"4G";
+        assert_eq!(decode_in_place(&mut invalid), None);
+    }
+}
@@ -19,4 +19,90 @@
 //!   If using `CellLocate`® and HTTP commands HTTP profiles in the range 1-3 must
 //!   be used.
 
+pub mod responses;
+pub mod types;
 pub mod urc;
+
+use atat::atat_derive::AtatCmd;
+use responses::HttpError;
+use types::{HttpContentType, HttpMethod, HttpParam, HttpProfileId};
+
+use super::NoResponse;
+
+/// 29.1 HTTP control +UHTTP
+///
+/// Configures, reads or resets (to the factory-programmed values) the HTTP
+/// application profile parameters. Up to 4 different HTTP profiles can be
+/// defined. To set all the parameters in an HTTP profile, a set command for
+/// each <op_code> needs to be issued.
+///
+/// **NOTES:**
+/// - The configured HTTP profile parameters are not saved in the non volatile
+///   memory.
+/// - Setting the server name (<op_code>=1) resets the server IP address
+///   (<op_code>=0) and vice versa.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UHTTP", NoResponse)]
+pub struct SetHttpProfile {
+    #[at_arg(position = 0)]
+    pub profile_id: HttpProfileId,
+    #[at_arg(position = 1)]
+    pub param: HttpParam,
+}
+
+/// 29.1 HTTP control +UHTTP
+///
+/// Resets all the parameters of the HTTP profile to the factory-programmed
+/// values.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UHTTP", NoResponse)]
+pub struct ResetHttpProfile {
+    #[at_arg(position = 0)]
+    pub profile_id: HttpProfileId,
+}
+
+/// 29.3 HTTP command +UHTTPC
+///
+/// Triggers the HTTP command specified with <http_command> parameter, using the
+/// HTTP application profile parameters (previously set up by +UHTTP AT
+/// command), specified by <profile_id>. The response indicates if sending the
+/// command request to HTTP process was successful or not. The final result of
+/// HTTP command will be returned to the user via the +UUHTTPCR URC, and the
+/// server response is stored in <response_filename> in the file system.
+///
+/// **NOTES:**
+/// - The server response stored in <response_filename> includes the HTTP
+///   header.
+/// - If <response_filename> already exists, it is overwritten.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UHTTPC", NoResponse, timeout_ms = 10000)]
+pub struct HttpCommand<'a> {
+    #[at_arg(position = 0)]
+    pub profile_id: HttpProfileId,
+    #[at_arg(position = 1)]
+    pub method: HttpMethod,
+    /// Path of HTTP server resource; the maximum length is 128 characters.
+    #[at_arg(position = 2, len = 128)]
+    pub path: &'a str,
+    /// Filename where the HTTP server response will be stored.
+    #[at_arg(position = 3, len = 248)]
+    pub response_filename: &'a str,
+    /// - PUT / POST file: the filename of the file system content to send
+    /// - POST data: the data to send, maximum 128 characters
+    #[at_arg(position = 4, len = 248)]
+    pub payload: Option<&'a str>,
+    /// Content type of the payload, mandatory for PUT and POST
+    #[at_arg(position = 5)]
+    pub content_type: Option<HttpContentType>,
+}
+
+/// 29.4 HTTP protocol error +UHTTPER
+///
+/// Retrieves the error class and code of the last HTTP operation on the
+/// specified HTTP profile.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UHTTPER", HttpError)]
+pub struct GetHttpError {
+    #[at_arg(position = 0)]
+    pub profile_id: HttpProfileId,
+}
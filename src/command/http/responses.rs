@@ -0,0 +1,17 @@
+//! Responses for HTTP Commands
+use super::types::HttpProfileId;
+use atat::atat_derive::AtatResp;
+
+/// 29.4 HTTP protocol error +UHTTPER
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HttpError {
+    #[at_arg(position = 0)]
+    pub profile_id: HttpProfileId,
+    /// Error class, see the HTTP error class appendix (0: no error)
+    #[at_arg(position = 1)]
+    pub error_class: u8,
+    /// Error code within the error class
+    #[at_arg(position = 2)]
+    pub error_code: u16,
+}
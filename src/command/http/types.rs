@@ -0,0 +1,103 @@
+//! Argument and parameter types used by HTTP Commands and Responses
+use atat::atat_derive::{AtatEnum, AtatLen};
+use heapless::String;
+use serde::{Deserialize, Serialize};
+
+use crate::command::device_data_security::types::SecurityProfileId;
+
+/// HTTP profile identifier, in range 0-3
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, AtatLen)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HttpProfileId(pub u8);
+
+#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[at_arg(u8)]
+pub enum HttpParam {
+    /// • 0: HTTP server IP address; <param_val> is the text string of the IP
+    /// address in dotted decimal notation form. The factory-programmed value
+    /// is "0.0.0.0".
+    #[at_arg(value = 0)]
+    ServerIp(String<45>),
+    /// • 1: HTTP server name; <param_val> is a text string of the FQDN. The
+    /// maximum length is 128 characters. The factory-programmed value is an
+    /// empty string.
+    #[at_arg(value = 1)]
+    ServerName(String<128>),
+    /// • 2: username; the maximum length is 30 characters.
+    #[at_arg(value = 2)]
+    Username(String<30>),
+    /// • 3: password; the maximum length is 30 characters.
+    #[at_arg(value = 3)]
+    Password(String<30>),
+    /// • 4: authentication type:
+    #[at_arg(value = 4)]
+    Authentication(HttpAuthentication),
+    /// • 5: HTTP server port, in range 1-65535. The factory-programmed value
+    /// is 80.
+    #[at_arg(value = 5)]
+    ServerPort(u16),
+    /// • 6: HTTP secure option (HTTPS) usage; <param_val1> optionally selects
+    /// the USECMNG security profile (0-4) used for the TLS connection. The
+    /// factory-programmed value is 0 (no HTTPS).
+    #[at_arg(value = 6)]
+    Secure(HttpSecurity, Option<SecurityProfileId>),
+    /// • 9: HTTP add custom request header; <param_val> is in the format
+    /// "<header_id>:<header_name>:<header_value>"
+    #[at_arg(value = 9)]
+    CustomHeader(String<256>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HttpAuthentication {
+    /// • 0 (factory-programmed value): no authentication
+    None = 0,
+    /// • 1: basic authentication (the password and username must be set)
+    Basic = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HttpSecurity {
+    /// • 0 (factory-programmed value): HTTP without TLS
+    Disabled = 0,
+    /// • 1: HTTPS
+    Enabled = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HttpMethod {
+    /// • 0: HEAD command; issue an HEAD request to the HTTP server.
+    Head = 0,
+    /// • 1: GET command; perform a GET request to the HTTP server.
+    Get = 1,
+    /// • 2: DELETE command; send a DELETE request to the HTTP server.
+    Delete = 2,
+    /// • 3: PUT command; perform a PUT request to the HTTP server, sending the
+    /// content of a file system file.
+    Put = 3,
+    /// • 4: POST file command; issue a POST request for sending a file system
+    /// file to the HTTP server.
+    PostFile = 4,
+    /// • 5: POST data command; send a POST request to the HTTP server using
+    /// the data specified in the payload parameter.
+    PostData = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HttpContentType {
+    /// • 0: application/x-www-form-urlencoded
+    FormUrlEncoded = 0,
+    /// • 1: text/plain
+    TextPlain = 1,
+    /// • 2: application/octet-stream
+    OctetStream = 2,
+    /// • 3: multipart/form-data
+    MultipartFormData = 3,
+    /// • 4: application/json
+    Json = 4,
+    /// • 5: application/xml
+    Xml = 5,
+}
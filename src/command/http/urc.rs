@@ -1,13 +1,15 @@
 //! Unsolicited responses for HTTP Commands
 use atat::atat_derive::AtatResp;
 
-/// 7.14 Network registration status +CREG
-#[derive(Debug, Clone, AtatResp)]
+/// 29.3 HTTP command result +UUHTTPCR
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct HttpResponse {
     #[at_arg(position = 0)]
     pub profile_id: u8,
     #[at_arg(position = 1)]
     pub http_command: u8,
+    /// 0: fail, 1: success
     #[at_arg(position = 2)]
     pub http_result: u8,
 }
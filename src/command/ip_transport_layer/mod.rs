@@ -50,18 +50,35 @@ mod internal_network_stack {
     use super::urc;
 
     use super::responses::{
-        CreateSocketResponse, SocketControlResponse, SocketData, SocketErrorResponse,
-        UDPSendToDataResponse, UDPSocketData, WriteSocketDataResponse,
+        BinarySocketData, CreateSocketResponse, SocketControlResponse, SocketData,
+        SocketDataBinary, SocketErrorResponse, SocketOptionResponse, UDPSendToDataResponse,
+        UDPSocketData, UDPSocketDataAvailable, UDPSocketDataBinary, WriteSocketDataResponse,
     };
     use super::types::{
-        HexMode, PreferredProtocolType, SocketControlParam, SocketProtocol, SslTlsStatus,
+        DataConfiguration, EgressData, PreferredProtocolType, RemoteAddr, SocketControlParam,
+        SocketOption, SocketOptionLevel, SocketOptionName, SocketProtocol, SslTlsStatus,
     };
     use atat::atat_derive::AtatCmd;
-    use core::net::IpAddr;
 
     use super::NoResponse;
     use ublox_sockets::SocketHandle;
 
+    /// Maximum number of bytes written to a socket with a single +USOWR.
+    /// Modules short on RAM can use smaller writes, see the
+    /// `egress-chunk-256` feature.
+    #[cfg(not(feature = "egress-chunk-256"))]
+    pub const EGRESS_CHUNK_SIZE: usize = 1024;
+    #[cfg(feature = "egress-chunk-256")]
+    pub const EGRESS_CHUNK_SIZE: usize = 256;
+
+    /// Maximum number of bytes sent with a single +USOST. The modules take
+    /// at most 512 bytes per datagram in binary mode.
+    pub const UDP_EGRESS_CHUNK_SIZE: usize = if EGRESS_CHUNK_SIZE < 512 {
+        EGRESS_CHUNK_SIZE
+    } else {
+        512
+    };
+
     /// 25.4 SSL/TLS mode configuration on TCP socket +USOSEC
     ///
     /// Enables or disables the use of SSL/TLS connection on a TCP socket. The
@@ -88,6 +105,43 @@ mod internal_network_stack {
         pub ssl_tls_status: SslTlsStatus,
     }
 
+    /// 25.5 Set socket option +USOSO
+    ///
+    /// Sets the specified standard option for the specified socket, like the
+    /// BSD setsockopt routine. The `level` must match the level of `option`,
+    /// see [`SocketOption::level`].
+    ///
+    /// **Notes:**
+    /// - The socket must have been created with +USOCR, otherwise the command
+    ///   fails with an error result code.
+    #[derive(Clone, AtatCmd)]
+    #[at_cmd("+USOSO", NoResponse)]
+    pub struct SetSocketOption {
+        // len 1 as ublox devices only support 7 sockets but needs to be changed if this changes!
+        #[at_arg(position = 0, len = 1)]
+        pub socket: SocketHandle,
+        #[at_arg(position = 1)]
+        pub level: SocketOptionLevel,
+        #[at_arg(position = 2)]
+        pub option: SocketOption,
+    }
+
+    /// 25.6 Get Socket Option +USOGO
+    ///
+    /// Retrieves the specified standard option for the specified socket, like
+    /// the BSD getsockopt routine.
+    #[derive(Clone, AtatCmd)]
+    #[at_cmd("+USOGO", SocketOptionResponse)]
+    pub struct GetSocketOption {
+        // len 1 as ublox devices only support 7 sockets but needs to be changed if this changes!
+        #[at_arg(position = 0, len = 1)]
+        pub socket: SocketHandle,
+        #[at_arg(position = 1)]
+        pub level: SocketOptionLevel,
+        #[at_arg(position = 2)]
+        pub option_name: SocketOptionName,
+    }
+
     /// 25.7 Close Socket +USOCL
     ///
     /// Closes the specified socket, like the BSD close routine. In case of remote
@@ -128,8 +182,8 @@ mod internal_network_stack {
         // len 1 as ublox devices only support 7 sockets but needs to be changed if this changes!
         #[at_arg(position = 0, len = 1)]
         pub socket: SocketHandle,
-        #[at_arg(position = 1, len = 39)]
-        pub remote_addr: IpAddr,
+        #[at_arg(position = 1)]
+        pub remote_addr: RemoteAddr,
         #[at_arg(position = 2)]
         pub remote_port: u16,
     }
@@ -203,10 +257,8 @@ mod internal_network_stack {
         force_receive_state = true
     )]
     pub struct WriteSocketDataBinary<'a> {
-        // FIXME:
-        // #[at_arg(position = 0, len = EgressChunkSize::to_usize())]
-        #[at_arg(position = 0, len = 1024)]
-        pub data: &'a atat::serde_bytes::Bytes,
+        #[at_arg(position = 0)]
+        pub data: EgressData<'a, EGRESS_CHUNK_SIZE>,
     }
 
     ///25.11 `SendTo` command (UDP only) +USOST
@@ -222,8 +274,8 @@ mod internal_network_stack {
         // len 1 as ublox devices only support 7 sockets but needs to be changed if this changes!
         #[at_arg(position = 0, len = 1)]
         pub socket: SocketHandle,
-        #[at_arg(position = 1, len = 39)]
-        pub remote_addr: IpAddr,
+        #[at_arg(position = 1)]
+        pub remote_addr: RemoteAddr,
         #[at_arg(position = 2)]
         pub remote_port: u16,
         #[at_arg(position = 3)]
@@ -240,8 +292,8 @@ mod internal_network_stack {
         force_receive_state = true
     )]
     pub struct UDPSendToDataBinary<'a> {
-        #[at_arg(position = 0, len = 512)]
-        pub data: &'a atat::serde_bytes::Bytes,
+        #[at_arg(position = 0)]
+        pub data: EgressData<'a, UDP_EGRESS_CHUNK_SIZE>,
     }
 
     /// 25.12 Read Socket Data +USORD
@@ -270,6 +322,40 @@ mod internal_network_stack {
         pub length: usize,
     }
 
+    /// 25.12 Read Socket Data +USORD
+    ///
+    /// Same as [`ReadSocketData`], for use with HEX mode disabled, where the
+    /// data is returned as raw bytes. The payload may look like anything, so
+    /// the response is parsed by its length, see [`BinarySocketData`].
+    #[derive(Clone)]
+    pub struct ReadSocketDataBinary {
+        pub socket: SocketHandle,
+        pub length: usize,
+    }
+
+    impl atat::AtatCmd for ReadSocketDataBinary {
+        type Response = SocketDataBinary;
+
+        const MAX_LEN: usize = <ReadSocketData as atat::AtatCmd>::MAX_LEN;
+        const MAX_TIMEOUT_MS: u32 = <ReadSocketData as atat::AtatCmd>::MAX_TIMEOUT_MS;
+
+        fn write(&self, buf: &mut [u8]) -> usize {
+            let cmd = ReadSocketData {
+                socket: self.socket,
+                length: self.length,
+            };
+            atat::AtatCmd::write(&cmd, buf)
+        }
+
+        fn parse(
+            &self,
+            resp: Result<&[u8], atat::InternalError>,
+        ) -> Result<SocketDataBinary, atat::Error> {
+            let data = BinarySocketData::parse(resp?).ok_or(atat::Error::Parse)?;
+            Ok(SocketDataBinary { data })
+        }
+    }
+
     /// 25.13 Receive From command (UDP only) +USORF
     ///
     /// Reads the specified amount of data from the specified UDP socket, like the
@@ -287,15 +373,62 @@ mod internal_network_stack {
         pub length: usize,
     }
 
-    /// 25.16 HEX mode configuration +UDCONF=1
+    /// 25.13 Receive From command (UDP only) +USORF
     ///
-    /// Enables/disables the HEX mode for +USOWR, +USOST, +USORD and +USORF AT
-    /// commands.
+    /// Same as [`ReadUDPSocketData`], for use with HEX mode disabled, see
+    /// [`ReadSocketDataBinary`].
+    #[derive(Clone)]
+    pub struct ReadUDPSocketDataBinary {
+        pub socket: SocketHandle,
+        pub length: usize,
+    }
+
+    impl atat::AtatCmd for ReadUDPSocketDataBinary {
+        type Response = UDPSocketDataBinary;
+
+        const MAX_LEN: usize = <ReadUDPSocketData as atat::AtatCmd>::MAX_LEN;
+        const MAX_TIMEOUT_MS: u32 = <ReadUDPSocketData as atat::AtatCmd>::MAX_TIMEOUT_MS;
+
+        fn write(&self, buf: &mut [u8]) -> usize {
+            let cmd = ReadUDPSocketData {
+                socket: self.socket,
+                length: self.length,
+            };
+            atat::AtatCmd::write(&cmd, buf)
+        }
+
+        fn parse(
+            &self,
+            resp: Result<&[u8], atat::InternalError>,
+        ) -> Result<UDPSocketDataBinary, atat::Error> {
+            UDPSocketDataBinary::parse(resp?).ok_or(atat::Error::Parse)
+        }
+    }
+
+    /// 25.13 Receive From command (UDP only) +USORF
+    ///
+    /// Returns the total amount of unread data of the specified UDP socket,
+    /// without reading any. The `length` has to be 0.
     #[derive(Clone, AtatCmd)]
-    #[at_cmd("+UDCONF=1,", NoResponse, value_sep = false)]
-    pub struct SetHexMode {
+    #[at_cmd("+USORF", UDPSocketDataAvailable)]
+    pub struct GetUDPSocketDataAvailable {
+        // len 1 as ublox devices only support 7 sockets but needs to be changed if this changes!
+        #[at_arg(position = 0, len = 1)]
+        pub socket: SocketHandle,
+        #[at_arg(position = 1)]
+        pub length: usize,
+    }
+
+    /// 25.16 Data configuration +UDCONF
+    ///
+    /// Sets a parameter of the internal TCP/IP stack, eg. the HEX mode for
+    /// +USOWR, +USOST, +USORD and +USORF AT commands (+UDCONF=1), or the TCP
+    /// keepalive (+UDCONF=7).
+    #[derive(Clone, AtatCmd)]
+    #[at_cmd("+UDCONF", NoResponse)]
+    pub struct SetDataConfiguration {
         #[at_arg(position = 0)]
-        pub hex_mode_disable: HexMode,
+        pub config: DataConfiguration,
     }
 
     /// 25.25 Socket control +USOCTL
@@ -310,4 +443,82 @@ mod internal_network_stack {
         #[at_arg(position = 1)]
         pub param_id: SocketControlParam,
     }
+
+    /// 25.24 Set socket in Direct Link mode +USODL
+    ///
+    /// Switches the serial interface into direct link mode, in which all data
+    /// is transferred transparently to and from the connected socket. The
+    /// "CONNECT" intermediate result code confirms direct link mode.
+    ///
+    /// Direct link mode is left with the "+++" escape sequence, surrounded by
+    /// at least 1 s of silence, after which the module answers "DISCONNECT".
+    #[derive(Clone, AtatCmd)]
+    #[at_cmd(
+        "+USODL",
+        NoResponse,
+        attempts = 1,
+        timeout_ms = 10000,
+        abortable = true
+    )]
+    pub struct SetDirectLinkMode {
+        // len 1 as ublox devices only support 7 sockets but needs to be changed if this changes!
+        #[at_arg(position = 0, len = 1)]
+        pub socket: SocketHandle,
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use atat::AtatCmd;
+        use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+        use super::*;
+        use crate::command::ip_transport_layer::types::HexMode;
+
+        #[test]
+        fn serialize_data_configuration() {
+            let mut buf = [0u8; 32];
+
+            let len = SetDataConfiguration {
+                config: DataConfiguration::HexMode(HexMode::Enabled),
+            }
+            .write(&mut buf);
+            assert_eq!(&buf[..len], b"AT+UDCONF=1,1\r");
+
+            let len = SetDataConfiguration {
+                config: DataConfiguration::TcpKeepAlive(60, 10, 5),
+            }
+            .write(&mut buf);
+            assert_eq!(&buf[..len], b"AT+UDCONF=7,60,10,5\r");
+        }
+
+        #[test]
+        fn serialize_remote_addr() {
+            let mut buf = [0u8; 192];
+
+            let len = ConnectSocket {
+                socket: SocketHandle(3),
+                remote_addr: IpAddr::V4(Ipv4Addr::new(151, 9, 34, 66)).into(),
+                remote_port: 2000,
+            }
+            .write(&mut buf);
+            assert_eq!(&buf[..len], b"AT+USOCO=3,\"151.9.34.66\",2000\r");
+
+            let len = ConnectSocket {
+                socket: SocketHandle(3),
+                remote_addr: IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)).into(),
+                remote_port: 2000,
+            }
+            .write(&mut buf);
+            assert_eq!(&buf[..len], b"AT+USOCO=3,\"[2001:db8::1]\",2000\r");
+
+            let len = PrepareUDPSendToDataBinary {
+                socket: SocketHandle(0),
+                remote_addr: RemoteAddr::hostname("echo.u-blox.com").unwrap(),
+                remote_port: 7,
+                length: 16,
+            }
+            .write(&mut buf);
+            assert_eq!(&buf[..len], b"AT+USOST=0,\"echo.u-blox.com\",7,16\r");
+        }
+    }
 }
@@ -3,7 +3,8 @@
 use atat::atat_derive::AtatResp;
 
 #[cfg(not(feature = "internal-network-stack"))]
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CreateSocketResponse {
     #[at_arg(position = 0)]
     pub socket: u8,
@@ -13,16 +14,51 @@ pub struct CreateSocketResponse {
 pub use internal_network_stack_respones::*;
 #[cfg(feature = "internal-network-stack")]
 pub mod internal_network_stack_respones {
-    use crate::command::ip_transport_layer::types::{AoNState, SocketControlParam, SocketProtocol};
+    use crate::command::hex;
+    use crate::command::ip_transport_layer::types::{
+        AoNState, SocketControlParam, SocketProtocol, TcpSocketStatus,
+    };
     use atat::atat_derive::AtatResp;
-    use core::net::IpAddr;
-    use heapless::String;
+    use core::net::{IpAddr, SocketAddr};
+    use heapless::{String, Vec};
     use ublox_sockets::SocketHandle;
 
-    pub const INGRESS_CHUNK_SIZE: usize = 256;
+    /// Maximum number of bytes read from a socket with a single +USORD or
+    /// +USORF, 256 unless set at build time with the
+    /// `UBLOX_CELLULAR_INGRESS_CHUNK_SIZE` environment variable. Modules with
+    /// hardware flow control can safely use larger reads, up to the 1024
+    /// bytes of the module.
+    pub const INGRESS_CHUNK_SIZE: usize = match option_env!("UBLOX_CELLULAR_INGRESS_CHUNK_SIZE") {
+        Some(size) => match usize::from_str_radix(size, 10) {
+            Ok(size @ 1..=1024) => size,
+            _ => panic!("UBLOX_CELLULAR_INGRESS_CHUNK_SIZE must be within 1 to 1024"),
+        },
+        None => 256,
+    };
+
+    /// Most bytes around the data of a +USORD or +USORF response, taken by a
+    /// +USORF from an IPv6 peer:
+    /// `\r\n+USORF: 0,"[<ipv6>]",65535,<length>,"<data>"\r\n\r\nOK\r\n`.
+    pub const INGRESS_FRAMING: usize = b"\r\n+USORF: 0,\"[".len()
+        // The longest IPv6 address, with an embedded IPv4 address
+        + b"ffff:ffff:ffff:ffff:ffff:ffff:255.255.255.255".len()
+        + b"]\",65535,".len()
+        + digits(INGRESS_CHUNK_SIZE)
+        + b",\"".len()
+        + b"\"\r\n\r\nOK\r\n".len();
+
+    const fn digits(mut n: usize) -> usize {
+        let mut digits = 1;
+        while n >= 10 {
+            n /= 10;
+            digits += 1;
+        }
+        digits
+    }
 
     /// 25.3 Create Socket +USOCR
-    #[derive(Debug, Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct CreateSocketResponse {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
@@ -32,7 +68,8 @@ pub mod internal_network_stack_respones {
         pub aon_state: AoNState,
     }
 
-    #[derive(Debug, Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct CloseSocketResponse {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
@@ -42,15 +79,28 @@ pub mod internal_network_stack_respones {
         pub aon_state: AoNState,
     }
 
+    /// 25.6 Get Socket Option +USOGO
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct SocketOptionResponse {
+        #[at_arg(position = 0)]
+        pub value: u32,
+        /// Linger time in seconds, for SO_LINGER only
+        #[at_arg(position = 1)]
+        pub value2: Option<u32>,
+    }
+
     /// 25.8 Get Socket Error +USOER
-    #[derive(Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct SocketErrorResponse {
         #[at_arg(position = 0)]
         pub error: u8,
     }
 
     /// 25.10 Write socket data +USOWR
-    #[derive(Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct WriteSocketDataResponse {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
@@ -59,7 +109,8 @@ pub mod internal_network_stack_respones {
     }
 
     /// 25.11 UDP Send To data +USOST:
-    #[derive(Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct UDPSendToDataResponse {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
@@ -68,7 +119,8 @@ pub mod internal_network_stack_respones {
     }
 
     /// 25.12 Read Socket Data +USORD
-    #[derive(Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct SocketData {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
@@ -79,12 +131,27 @@ pub mod internal_network_stack_respones {
         pub data: Option<String<{ INGRESS_CHUNK_SIZE * 2 }>>,
     }
 
+    impl SocketData {
+        /// Hex decode the payload in place over the response buffer, and hand
+        /// the decoded bytes to `f`, eg. to enqueue them directly in a socket
+        /// rx buffer. The payload is consumed by the decoding.
+        ///
+        /// Fails on an odd number of hex digits, or a payload longer than
+        /// `length`. A shorter payload, eg. because less data was available
+        /// than requested, is handed to `f` as is.
+        pub fn decode_hex<R>(&mut self, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+            decode_payload(self.data.as_mut(), self.length, f)
+        }
+    }
+
     /// 25.13 Read UDP Socket Data +USORF
-    #[derive(Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct UDPSocketData {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
         #[at_arg(position = 1)]
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
         pub remote_addr: IpAddr,
         #[at_arg(position = 2)]
         pub remote_port: u16,
@@ -95,8 +162,171 @@ pub mod internal_network_stack_respones {
         pub data: Option<String<{ INGRESS_CHUNK_SIZE * 2 }>>,
     }
 
+    /// 25.13 Read UDP Socket Data +USORF, with a zero length
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct UDPSocketDataAvailable {
+        #[at_arg(position = 0)]
+        pub socket: SocketHandle,
+        /// Total number of unread bytes, over all datagrams
+        #[at_arg(position = 1)]
+        pub length: usize,
+    }
+
+    impl UDPSocketData {
+        /// Address and port of the peer that sent the datagram
+        pub fn remote_endpoint(&self) -> SocketAddr {
+            SocketAddr::new(self.remote_addr, self.remote_port)
+        }
+
+        /// Hex decode the payload in place over the response buffer, and hand
+        /// the decoded bytes to `f`, eg. to enqueue them directly in a socket
+        /// rx buffer. The payload is consumed by the decoding.
+        ///
+        /// Fails on an odd number of hex digits, or a payload longer than
+        /// `length`. A shorter payload, eg. because less data was available
+        /// than requested, is handed to `f` as is.
+        pub fn decode_hex<R>(&mut self, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+            decode_payload(self.data.as_mut(), self.length, f)
+        }
+    }
+
+    fn decode_payload<const N: usize, R>(
+        data: Option<&mut String<N>>,
+        length: usize,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Option<R> {
+        let data = data?;
+        // SAFETY: The decoded bytes are only handed out as a byte slice, and
+        // the string is cleared before it can be observed as a `str` again.
+        let bytes = unsafe { data.as_mut_vec() };
+        let res = hex::decode_in_place(bytes)
+            .filter(|&n| n <= length)
+            .map(|n| f(&bytes[..n]));
+        bytes.clear();
+        res
+    }
+
+    /// 25.12 Read Socket Data +USORD, with HEX mode disabled, see
+    /// [`BinarySocketData`]
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct SocketDataBinary {
+        pub data: BinarySocketData,
+    }
+
+    impl atat::AtatResp for SocketDataBinary {}
+
+    /// Binary +USORD payload.
+    ///
+    /// Without HEX mode the payload is sent as raw bytes between quotes, and may
+    /// itself contain quotes, commas or CR LF. It can only be delimited using
+    /// the length that precedes it, so the digester takes the response by its
+    /// length, and it is parsed from the raw response rather than by the
+    /// derived deserialization.
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct BinarySocketData {
+        pub socket: SocketHandle,
+        /// Number of bytes read, or the number of unread bytes when the
+        /// response carries no payload
+        pub length: usize,
+        pub payload: Vec<u8, INGRESS_CHUNK_SIZE>,
+    }
+
+    impl BinarySocketData {
+        pub fn parse(input: &[u8]) -> Option<Self> {
+            let input = input.trim_ascii_start();
+            let input = input.strip_prefix(b"+USORD:").unwrap_or(input);
+            let (socket, rest) = split_number(input.trim_ascii_start())?;
+            let (length, rest) = split_number(rest.strip_prefix(b",")?)?;
+
+            let payload = match rest.strip_prefix(b",") {
+                Some(rest) => split_payload(rest, length)?,
+                None => Vec::new(),
+            };
+
+            Some(Self {
+                socket: SocketHandle(u8::try_from(socket).ok()?),
+                length,
+                payload,
+            })
+        }
+    }
+
+    /// 25.13 Read UDP Socket Data +USORF, with HEX mode disabled, see
+    /// [`BinarySocketData`]
+    #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct UDPSocketDataBinary {
+        pub socket: SocketHandle,
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+        pub remote_addr: IpAddr,
+        pub remote_port: u16,
+        pub length: usize,
+        pub payload: Vec<u8, INGRESS_CHUNK_SIZE>,
+    }
+
+    impl atat::AtatResp for UDPSocketDataBinary {}
+
+    impl UDPSocketDataBinary {
+        pub fn parse(input: &[u8]) -> Option<Self> {
+            let input = input.trim_ascii_start();
+            let input = input.strip_prefix(b"+USORF:").unwrap_or(input);
+            let (socket, rest) = split_number(input.trim_ascii_start())?;
+
+            let rest = rest.strip_prefix(b",\"")?;
+            let end = rest.iter().position(|&c| c == b'"')?;
+            let addr = core::str::from_utf8(&rest[..end]).ok()?;
+            // IPv6 addresses may come in brackets
+            let addr = addr
+                .strip_prefix('[')
+                .and_then(|a| a.strip_suffix(']'))
+                .unwrap_or(addr);
+            let remote_addr = addr.parse().ok()?;
+
+            let (remote_port, rest) = split_number(rest[end + 1..].strip_prefix(b",")?)?;
+            let (length, rest) = split_number(rest.strip_prefix(b",")?)?;
+            let payload = split_payload(rest.strip_prefix(b",")?, length)?;
+
+            Some(Self {
+                socket: SocketHandle(u8::try_from(socket).ok()?),
+                remote_addr,
+                remote_port: u16::try_from(remote_port).ok()?,
+                length,
+                payload,
+            })
+        }
+
+        /// Address and port of the peer that sent the datagram
+        pub fn remote_endpoint(&self) -> SocketAddr {
+            SocketAddr::new(self.remote_addr, self.remote_port)
+        }
+    }
+
+    /// Split the leading decimal number off `input`
+    fn split_number(input: &[u8]) -> Option<(usize, &[u8])> {
+        let end = input
+            .iter()
+            .position(|c| !c.is_ascii_digit())
+            .unwrap_or(input.len());
+        let n = core::str::from_utf8(&input[..end]).ok()?.parse().ok()?;
+        Some((n, &input[end..]))
+    }
+
+    /// The `length` bytes of the quoted binary payload at the start of `input`
+    fn split_payload(input: &[u8], length: usize) -> Option<Vec<u8, INGRESS_CHUNK_SIZE>> {
+        let rest = input.strip_prefix(b"\"")?;
+        let data = rest.get(..length)?;
+        if rest.get(length) != Some(&b'"') {
+            return None;
+        }
+        Vec::from_slice(data).ok()
+    }
+
     /// 25.25 Socket control +USOCTL
-    #[derive(Clone, AtatResp)]
+    #[derive(Debug, Clone, PartialEq, AtatResp)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
     pub struct SocketControlResponse {
         #[at_arg(position = 0)]
         pub socket: SocketHandle,
@@ -105,4 +335,73 @@ pub mod internal_network_stack_respones {
         #[at_arg(position = 2)]
         pub param_val: u32,
     }
+
+    impl SocketControlResponse {
+        /// TCP socket status, if this is a response to a
+        /// [`SocketControlParam::SocketStatus`] query
+        pub fn tcp_status(&self) -> Option<TcpSocketStatus> {
+            match self.param_id {
+                SocketControlParam::SocketStatus => Some(TcpSocketStatus::from(self.param_val)),
+                _ => None,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parse_binary_socket_data() {
+            let data = BinarySocketData::parse(b"+USORD: 3,9,\"a\"b,\r\nc\"d\"\r\n").unwrap();
+            assert_eq!(data.socket, SocketHandle(3));
+            assert_eq!(data.length, 9);
+            assert_eq!(&data.payload[..], b"a\"b,\r\nc\"d");
+
+            let unread = BinarySocketData::parse(b"0,42").unwrap();
+            assert_eq!(unread.length, 42);
+            assert!(unread.payload.is_empty());
+
+            // Payload shorter than the advertised length
+            assert_eq!(BinarySocketData::parse(b"0,5,\"ab\""), None);
+        }
+
+        #[test]
+        fn parse_udp_socket_data() {
+            let mut data: UDPSocketData =
+                atat::serde_at::from_slice(b"+USORF: 3,\"192.168.1.10\",5683,2,\"6869\"").unwrap();
+            assert_eq!(data.socket, SocketHandle(3));
+            assert_eq!(
+                data.remote_endpoint(),
+                SocketAddr::new(IpAddr::V4(core::net::Ipv4Addr::new(192, 168, 1, 10)), 5683)
+            );
+            assert_eq!(data.decode_hex(|d| d == b"hi"), Some(true));
+        }
+
+        fn socket_data(length: usize, data: &str) -> SocketData {
+            SocketData {
+                socket: SocketHandle(0),
+                length,
+                data: Some(String::try_from(data).unwrap()),
+            }
+        }
+
+        #[test]
+        fn decode_hex_socket_data() {
+            let mut data = socket_data(3, "616263");
+            assert_eq!(data.decode_hex(|d| d == b"abc"), Some(true));
+
+            // Shorter than declared
+            let mut short = socket_data(5, "6162");
+            assert_eq!(short.decode_hex(|d| d == b"ab"), Some(true));
+
+            // Longer than declared
+            let mut long = socket_data(1, "6162");
+            assert_eq!(long.decode_hex(|_| ()), None);
+
+            // Odd number of hex digits
+            let mut odd = socket_data(2, "616");
+            assert_eq!(odd.decode_hex(|_| ()), None);
+        }
+    }
 }
@@ -1,8 +1,13 @@
 //! Argument and parameter types used by Internet protocol transport layer Commands and Responses
 use crate::command::device_data_security::types::SecurityProfileId;
 use atat::atat_derive::AtatEnum;
+use atat::AtatLen;
+use core::fmt::Write;
+use core::net::IpAddr;
+use serde::{Serialize, Serializer};
 
 #[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SocketProtocol {
     TCP = 6,
     UDP = 17,
@@ -22,7 +27,7 @@ pub enum SslTlsStatus {
 
 /// Enables/disables the HEX mode for +USOWR, +USOST, +USORD and +USORF AT
 /// commands.
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
 pub enum HexMode {
     /// 0 (factory-programmed value): HEX mode disabled
     Disabled = 0,
@@ -30,8 +35,25 @@ pub enum HexMode {
     Enabled = 1,
 }
 
+/// Parameter of the internal TCP/IP stack, set with +UDCONF. The settings
+/// apply to all sockets, and are not saved in the NVM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[at_enum(u8)]
+pub enum DataConfiguration {
+    /// 1: HEX mode of +USOWR, +USOST, +USORD and +USORF
+    #[at_arg(value = 1)]
+    HexMode(HexMode),
+    /// 7: TCP keepalive, with the idle time before the first probe and the
+    /// interval between probes in seconds, and the number of unanswered
+    /// probes after which the connection is dropped. An idle time of 0
+    /// disables the keepalive
+    #[at_arg(value = 7)]
+    TcpKeepAlive(u16, u16, u8),
+}
+
 /// Control request identifier
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SocketControlParam {
     /// 0: query for socket type
     SocketType = 0,
@@ -50,6 +72,117 @@ pub enum SocketControlParam {
     // /// 5-9, 12-99: RFU
 }
 
+/// TCP socket status, as reported by +USOCTL with
+/// [`SocketControlParam::SocketStatus`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TcpSocketStatus {
+    /// 0: the socket is in INACTIVE status
+    Inactive,
+    /// 1: the socket is in LISTEN status
+    Listen,
+    /// 2: the socket is in SYN_SENT status
+    SynSent,
+    /// 3: the socket is in SYN_RCVD status
+    SynReceived,
+    /// 4: the socket is in ESTABILISHED status
+    Established,
+    /// 5: the socket is in FIN_WAIT_1 status
+    FinWait1,
+    /// 6: the socket is in FIN_WAIT_2 status
+    FinWait2,
+    /// 7: the socket is in CLOSE_WAIT status
+    CloseWait,
+    /// 8: the socket is in CLOSING status
+    Closing,
+    /// 9: the socket is in LAST_ACK status
+    LastAck,
+    /// 10: the socket is in TIME_WAIT status
+    TimeWait,
+    Unknown(u32),
+}
+
+impl From<u32> for TcpSocketStatus {
+    fn from(v: u32) -> Self {
+        match v {
+            0 => Self::Inactive,
+            1 => Self::Listen,
+            2 => Self::SynSent,
+            3 => Self::SynReceived,
+            4 => Self::Established,
+            5 => Self::FinWait1,
+            6 => Self::FinWait2,
+            7 => Self::CloseWait,
+            8 => Self::Closing,
+            9 => Self::LastAck,
+            10 => Self::TimeWait,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+impl TcpSocketStatus {
+    /// The connection is gone on the module side, and no more data can be
+    /// exchanged with the peer.
+    pub fn is_closed(&self) -> bool {
+        matches!(
+            self,
+            Self::Inactive | Self::Closing | Self::LastAck | Self::TimeWait
+        )
+    }
+}
+
+/// Error of a socket operation, from the BSD errno reported by +USOCTL with
+/// [`SocketControlParam::LastSocketError`], or by +USOER
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SocketErrorKind {
+    /// 9: EBADF, the socket does not exist
+    BadSocket,
+    /// 11: EAGAIN, the operation would block, eg. no data to read
+    WouldBlock,
+    /// 32: EPIPE, the connection is closed for writing
+    BrokenPipe,
+    /// 98: EADDRINUSE, the local port is bound by another socket
+    AddrInUse,
+    /// 101: ENETUNREACH
+    NetworkUnreachable,
+    /// 104: ECONNRESET, the connection was reset by the peer
+    ConnectionReset,
+    /// 105: ENOBUFS, the module ran out of buffers
+    NoBuffers,
+    /// 107: ENOTCONN, the socket is not connected
+    NotConnected,
+    /// 110: ETIMEDOUT
+    TimedOut,
+    /// 111: ECONNREFUSED
+    ConnectionRefused,
+    /// 113: EHOSTUNREACH
+    HostUnreachable,
+    Other(u32),
+}
+
+impl SocketErrorKind {
+    /// The error for `errno`, or `None` if it is 0, which means no error.
+    pub fn from_errno(errno: u32) -> Option<Self> {
+        Some(match errno {
+            0 => return None,
+            9 => Self::BadSocket,
+            11 => Self::WouldBlock,
+            32 => Self::BrokenPipe,
+            98 => Self::AddrInUse,
+            101 => Self::NetworkUnreachable,
+            104 => Self::ConnectionReset,
+            105 => Self::NoBuffers,
+            107 => Self::NotConnected,
+            110 => Self::TimedOut,
+            111 => Self::ConnectionRefused,
+            113 => Self::HostUnreachable,
+            v => Self::Other(v),
+        })
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, AtatEnum)]
 #[repr(u8)]
 pub enum PreferredProtocolType {
@@ -58,7 +191,234 @@ pub enum PreferredProtocolType {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum AoNState {
     DoNotReport = 0,
     Report = 1,
 }
+
+/// Level of a socket option, see +USOSO and +USOGO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[at_enum(u16)]
+pub enum SocketOptionLevel {
+    /// 6: TCP protocol (IPPROTO_TCP)
+    Tcp = 6,
+    /// 65535: socket (SOL_SOCKET)
+    Socket = 65535,
+}
+
+/// Socket option names, used to read an option with +USOGO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[at_enum(u16)]
+pub enum SocketOptionName {
+    /// 1: TCP_NODELAY (IPPROTO_TCP)
+    NoDelay = 1,
+    /// 2: TCP_KEEPIDLE (IPPROTO_TCP)
+    KeepIdle = 2,
+    /// 4: SO_REUSEADDR (SOL_SOCKET)
+    ReuseAddr = 4,
+    /// 8: SO_KEEPALIVE (SOL_SOCKET)
+    KeepAlive = 8,
+    /// 128: SO_LINGER (SOL_SOCKET)
+    Linger = 128,
+}
+
+impl SocketOptionName {
+    pub fn level(&self) -> SocketOptionLevel {
+        match self {
+            Self::NoDelay | Self::KeepIdle => SocketOptionLevel::Tcp,
+            Self::ReuseAddr | Self::KeepAlive | Self::Linger => SocketOptionLevel::Socket,
+        }
+    }
+}
+
+/// Socket option and its value, set with +USOSO
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[at_enum(u16)]
+pub enum SocketOption {
+    /// 1: TCP_NODELAY, 0 (default) Nagle algorithm enabled, 1 disabled
+    #[at_arg(value = 1)]
+    NoDelay(u8),
+    /// 2: TCP_KEEPIDLE, idle time in milliseconds before the first keepalive
+    /// probe is sent. Default 7200000 (2 hours)
+    #[at_arg(value = 2)]
+    KeepIdle(u32),
+    /// 4: SO_REUSEADDR, 0 (default) disabled, 1 enabled
+    #[at_arg(value = 4)]
+    ReuseAddr(u8),
+    /// 8: SO_KEEPALIVE, 0 (default) disabled, 1 enabled
+    #[at_arg(value = 8)]
+    KeepAlive(u8),
+    /// 128: SO_LINGER, 0 (default) disabled, 1 enabled, followed by the linger
+    /// time in seconds
+    #[at_arg(value = 128)]
+    Linger(u8, u16),
+}
+
+impl SocketOption {
+    pub fn name(&self) -> SocketOptionName {
+        match self {
+            Self::NoDelay(_) => SocketOptionName::NoDelay,
+            Self::KeepIdle(_) => SocketOptionName::KeepIdle,
+            Self::ReuseAddr(_) => SocketOptionName::ReuseAddr,
+            Self::KeepAlive(_) => SocketOptionName::KeepAlive,
+            Self::Linger(..) => SocketOptionName::Linger,
+        }
+    }
+
+    pub fn level(&self) -> SocketOptionLevel {
+        self.name().level()
+    }
+}
+
+/// Binary socket data of at most `N` bytes, written after the `@` prompt of
+/// +USOWR or +USOST. The bound sizes the command buffer of atat, so it has to
+/// be known at compile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EgressData<'a, const N: usize>(&'a [u8]);
+
+impl<'a, const N: usize> EgressData<'a, N> {
+    /// `None` if `data` is longer than `N` bytes.
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        (data.len() <= N).then_some(Self(data))
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<const N: usize> AtatLen for EgressData<'_, N> {
+    const LEN: usize = N;
+    const ESCAPED_LEN: usize = N;
+}
+
+impl<const N: usize> Serialize for EgressData<'_, N> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Maximum length of a domain name given as the remote host of +USOCO and
+/// +USOST.
+pub const MAX_HOSTNAME_LEN: usize = 128;
+
+/// Remote host of +USOCO and +USOST, rendered the way the modules take it: a
+/// dotted IPv4 address, an IPv6 address in brackets, or a domain name. The
+/// latter is only taken by the firmwares resolving it themselves, see
+/// `ModuleParams::socket_hostnames`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteAddr {
+    host: heapless::String<MAX_HOSTNAME_LEN>,
+    is_hostname: bool,
+}
+
+impl RemoteAddr {
+    pub fn ip(addr: IpAddr) -> Self {
+        let mut host = heapless::String::new();
+        // At most 41 characters, for a bracketed IPv6 address
+        match addr {
+            IpAddr::V4(addr) => write!(host, "{}", addr),
+            IpAddr::V6(addr) => write!(host, "[{}]", addr),
+        }
+        .ok();
+        Self {
+            host,
+            is_hostname: false,
+        }
+    }
+
+    /// `None` if `name` is empty, longer than [`MAX_HOSTNAME_LEN`], or has
+    /// characters other than letters, digits, `-` and `.`.
+    pub fn hostname(name: &str) -> Option<Self> {
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+        {
+            return None;
+        }
+        Some(Self {
+            host: heapless::String::try_from(name).ok()?,
+            is_hostname: true,
+        })
+    }
+
+    pub fn is_hostname(&self) -> bool {
+        self.is_hostname
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.host
+    }
+}
+
+impl From<IpAddr> for RemoteAddr {
+    fn from(addr: IpAddr) -> Self {
+        Self::ip(addr)
+    }
+}
+
+impl AtatLen for RemoteAddr {
+    // Quoted, with nothing to escape
+    const LEN: usize = MAX_HOSTNAME_LEN + 2;
+    const ESCAPED_LEN: usize = MAX_HOSTNAME_LEN + 2;
+}
+
+impl Serialize for RemoteAddr {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn egress_data_is_bounded() {
+        assert_eq!(
+            EgressData::<4>::new(b"abcd").map(|d| d.as_bytes()),
+            Some(&b"abcd"[..])
+        );
+        assert_eq!(EgressData::<4>::new(b"abcde"), None);
+    }
+
+    #[test]
+    fn remote_addr_forms() {
+        assert_eq!(
+            RemoteAddr::ip(IpAddr::V4(Ipv4Addr::new(151, 9, 34, 66))).as_str(),
+            "151.9.34.66"
+        );
+        assert_eq!(
+            RemoteAddr::ip(IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1))).as_str(),
+            "[2001:db8::1]"
+        );
+        assert_eq!(
+            RemoteAddr::ip(IpAddr::V6(Ipv6Addr::new(
+                0x2001, 0xdb8, 0x85a3, 0x1234, 0x5678, 0x8a2e, 0x370, 0x7334
+            )))
+            .as_str(),
+            "[2001:db8:85a3:1234:5678:8a2e:370:7334]"
+        );
+
+        let host = RemoteAddr::hostname("echo.u-blox.com").unwrap();
+        assert!(host.is_hostname());
+        assert_eq!(host.as_str(), "echo.u-blox.com");
+        assert_eq!(RemoteAddr::hostname(""), None);
+        assert_eq!(RemoteAddr::hostname("echo\",u-blox.com"), None);
+        let long = [b'a'; MAX_HOSTNAME_LEN + 1];
+        assert_eq!(
+            RemoteAddr::hostname(core::str::from_utf8(&long).unwrap()),
+            None
+        );
+    }
+}
@@ -3,7 +3,7 @@ use atat::atat_derive::AtatResp;
 use ublox_sockets::SocketHandle;
 
 /// +UUSORD/+UUSORF
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SocketDataAvailable {
     #[at_arg(position = 0)]
@@ -13,7 +13,7 @@ pub struct SocketDataAvailable {
 }
 
 /// +UUSOCL
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SocketClosed {
     #[at_arg(position = 0)]
@@ -0,0 +1,86 @@
+//! ### LwM2M
+//!
+//! SARA-R4 and SARA-R5 modules embed a LwM2M client, used by carriers (eg.
+//! Verizon, AT&T) for device management. It runs alongside the application,
+//! registering with the carrier's servers by itself once enabled.
+pub mod responses;
+pub mod types;
+pub mod urc;
+
+use atat::atat_derive::AtatCmd;
+use responses::Lwm2mClientState;
+use types::{Lwm2mClientMode, Lwm2mStatusReporting};
+
+use super::NoResponse;
+
+/// LwM2M client activation/deactivation +ULWM2M
+///
+/// Enables or disables the embedded LwM2M client. The setting is saved in
+/// the non volatile memory.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+ULWM2M", NoResponse, timeout_ms = 10000)]
+pub struct SetLwm2mClient {
+    #[at_arg(position = 0)]
+    pub mode: Lwm2mClientMode,
+}
+
+/// LwM2M client activation/deactivation +ULWM2M
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+ULWM2M?", Lwm2mClientState)]
+pub struct GetLwm2mClient;
+
+/// LwM2M client registration update +ULWM2MREG
+///
+/// Triggers a registration update with the LwM2M server, eg. after the
+/// objects of the client changed.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+ULWM2MREG", NoResponse, timeout_ms = 10000)]
+pub struct UpdateLwm2mRegistration {
+    /// Short server ID of the LwM2M server
+    #[at_arg(position = 0)]
+    pub server_id: u16,
+}
+
+/// LwM2M client status reporting +ULWM2MSTAT
+///
+/// Enables or disables the +ULWM2MSTAT URC, reporting the events of the
+/// LwM2M client.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+ULWM2MSTAT", NoResponse)]
+pub struct SetLwm2mStatusReporting {
+    #[at_arg(position = 0)]
+    pub mode: Lwm2mStatusReporting,
+}
+
+#[cfg(test)]
+mod tests {
+    use atat::AtatCmd;
+
+    use super::*;
+
+    #[test]
+    fn serialize_client_commands() {
+        let mut buf = [0u8; 32];
+
+        let len = SetLwm2mClient {
+            mode: Lwm2mClientMode::Disabled,
+        }
+        .write(&mut buf);
+        assert_eq!(&buf[..len], b"AT+ULWM2M=1\r");
+
+        let len = UpdateLwm2mRegistration { server_id: 123 }.write(&mut buf);
+        assert_eq!(&buf[..len], b"AT+ULWM2MREG=123\r");
+
+        let state = GetLwm2mClient.parse(Ok(b"+ULWM2M: 0")).unwrap();
+        assert_eq!(state.mode, Lwm2mClientMode::Enabled);
+    }
+
+    #[test]
+    fn parse_status() {
+        let status: urc::Lwm2mStatus = atat::serde_at::from_slice(b"+ULWM2MSTAT: 1,123,0").unwrap();
+        assert_eq!(status.event_type, 1);
+        assert_eq!(status.param1, Some(123));
+        assert_eq!(status.param2, Some(0));
+        assert_eq!(status.param3, None);
+    }
+}
@@ -0,0 +1,11 @@
+//! Responses for LwM2M Commands
+use super::types::Lwm2mClientMode;
+use atat::atat_derive::AtatResp;
+
+/// LwM2M client activation/deactivation +ULWM2M
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Lwm2mClientState {
+    #[at_arg(position = 0)]
+    pub mode: Lwm2mClientMode,
+}
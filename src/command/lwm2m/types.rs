@@ -0,0 +1,22 @@
+//! Argument and parameter types used by LwM2M Commands and Responses
+use atat::atat_derive::AtatEnum;
+
+/// State of the embedded LwM2M client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lwm2mClientMode {
+    /// • 0: LwM2M client enabled
+    Enabled = 0,
+    /// • 1: LwM2M client disabled
+    Disabled = 1,
+}
+
+/// Reporting of the LwM2M client events with the +ULWM2MSTAT URC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lwm2mStatusReporting {
+    /// • 0 (default value): URC disabled
+    Disabled = 0,
+    /// • 1: URC enabled
+    Enabled = 1,
+}
@@ -0,0 +1,20 @@
+//! Unsolicited responses for LwM2M Commands
+use atat::atat_derive::AtatResp;
+
+/// LwM2M client status reporting +ULWM2MSTAT
+///
+/// Reports an event of the LwM2M client, eg. the registration with a server,
+/// once enabled with +ULWM2MSTAT. The meaning of the parameters depends on
+/// the event type, see the AT commands manual of the module.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Lwm2mStatus {
+    #[at_arg(position = 0)]
+    pub event_type: u8,
+    #[at_arg(position = 1)]
+    pub param1: Option<u32>,
+    #[at_arg(position = 2)]
+    pub param2: Option<u32>,
+    #[at_arg(position = 3)]
+    pub param3: Option<u32>,
+}
@@ -3,12 +3,16 @@
 
 pub mod responses;
 pub mod types;
+pub mod urc;
 use atat::atat_derive::AtatCmd;
 use responses::{
     DateTime, ExtendedErrorReport, IndicatorControl, ModuleFunctionality,
     ReportMobileTerminationError,
 };
-use types::{AutomaticTimezone, Functionality, ResetMode, TerminationErrorMode};
+use types::{
+    AutomaticTimezone, EventReportingMode, Functionality, IndicatorReporting, ResetMode,
+    TerminationErrorMode,
+};
 
 use super::NoResponse;
 
@@ -57,6 +61,36 @@ pub struct GetModuleFunctionality;
 #[at_cmd("+CIND?", IndicatorControl)]
 pub struct GetIndicatorControl;
 
+/// 5.5 Indicator configuration +UCIND
+///
+/// Selects the indicators reported by +CIEV URCs. `conf` is a bitmask following
+/// the order of the +CIND indicators, "battchg" being bit 0.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UCIND", NoResponse)]
+pub struct SetIndicatorConfiguration {
+    #[at_arg(position = 0)]
+    pub conf: u16,
+}
+
+/// 5.6 Mobile termination event reporting +CMER
+///
+/// Configures the sending of URCs from the MT to the DTE, eg. the +CIEV URC on
+/// changes of the +CIND indicators.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CMER", NoResponse)]
+pub struct SetMobileTerminationEventReporting {
+    #[at_arg(position = 0)]
+    pub mode: EventReportingMode,
+    /// Keypad event reporting, not supported
+    #[at_arg(position = 1)]
+    pub keyp: u8,
+    /// Display event reporting, not supported
+    #[at_arg(position = 2)]
+    pub disp: u8,
+    #[at_arg(position = 3)]
+    pub ind: IndicatorReporting,
+}
+
 /// 5.7 Clock +CCLK
 ///
 /// Sets the real-time clock of the MT
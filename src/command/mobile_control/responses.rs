@@ -1,10 +1,15 @@
 //! Responses for Mobile equipment control and status Commands
-use super::types::{PowerMode, ReportMobileTerminationErrorStatus, STKMode};
+use super::types::{
+    ExtendedErrorCause, PowerMode, ReportMobileTerminationErrorStatus, STKMode, SessionCause,
+};
+use super::urc::IndicatorEvent;
+use crate::command::psn::types::RejectCause;
 use atat::atat_derive::AtatResp;
 
 /// 5.3 Set module functionality +CFUN
 /// Selects the level of functionality <fun> in the MT.
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ModuleFunctionality {
     #[at_arg(position = 0)]
     pub power_mode: PowerMode,
@@ -19,7 +24,8 @@ pub struct ModuleFunctionality {
 /// state (see <descr> parameter).
 /// The list of indications for set and read commands follows the indexes reported in the <descr> parameter, so
 /// that the first <ind> corresponds to "battchg" and so on
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IndicatorControl {
     /// "battchg": battery charge level (0-5)
     #[at_arg(position = 0)]
@@ -87,10 +93,52 @@ pub struct IndicatorControl {
     pub simind: u8,
 }
 
+impl IndicatorControl {
+    /// Apply the change reported by a +CIEV URC.
+    pub fn update(&mut self, ev: &IndicatorEvent) {
+        let value = ev.value;
+        let narrow = u8::try_from(value).unwrap_or(u8::MAX);
+        match ev.descr {
+            IndicatorEvent::BATTCHG => self.battchg = narrow,
+            IndicatorEvent::SIGNAL => self.signal = narrow,
+            IndicatorEvent::SERVICE => self.service = value,
+            IndicatorEvent::SOUNDER => self.sounder = narrow,
+            IndicatorEvent::MESSAGE => self.message = narrow,
+            IndicatorEvent::CALL => self.call = narrow,
+            IndicatorEvent::ROAM => self.roam = value,
+            IndicatorEvent::SMSFULL => self.smsfull = narrow,
+            IndicatorEvent::GPRS => self.gprs = value,
+            IndicatorEvent::CALLSETUP => self.callsetup = narrow,
+            IndicatorEvent::CALLHELD => self.callheld = narrow,
+            IndicatorEvent::SIMIND => self.simind = narrow,
+            _ => {}
+        }
+    }
+
+    /// Whether the module is registered to a network, if known.
+    pub fn has_service(&self) -> Option<bool> {
+        match self.service {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Whether the module is registered to a roaming network, if known.
+    pub fn is_roaming(&self) -> Option<bool> {
+        match self.roam {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
 /// 5.7 Clock +CCLK
 ///
 /// Reads the real-time clock of the MT
-#[derive(Clone, Debug, AtatResp)]
+#[derive(Clone, PartialEq, Debug, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DateTime {
     pub time: heapless::String<20>,
 }
@@ -101,7 +149,8 @@ pub struct DateTime {
 /// functionality of the MT. When enabled, MT related errors cause +CME ERROR: <err> final result code instead
 /// of the regular ERROR final result code. The error result code is returned normally when an error is related to
 /// syntax, invalid parameters or MT functionality
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ReportMobileTerminationError {
     #[at_arg(position = 0)]
     pub status: ReportMobileTerminationErrorStatus,
@@ -128,7 +177,8 @@ pub struct ReportMobileTerminationError {
 /// reason for the last SM STATUS message sent to the network. When <type>="SM
 /// STATUS msg sent" is reported, it is suggested to reset the PS data
 /// connection.
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ExtendedErrorReport {
     #[at_arg(position = 0)]
     pub r#type: heapless::String<32>,
@@ -137,3 +187,90 @@ pub struct ExtendedErrorReport {
     #[at_arg(position = 2)]
     pub description: heapless::String<64>,
 }
+
+impl ExtendedErrorReport {
+    /// Decode the cause, based on the type of the report. `None` if the
+    /// module has no cause to report.
+    ///
+    /// Attach and detach reports carry GMM / EMM causes, activation and
+    /// deactivation reports SM / ESM causes.
+    pub fn cause(&self) -> Option<ExtendedErrorCause> {
+        if self.cause == 0 {
+            return None;
+        }
+
+        let r#type = self.r#type.as_str();
+        let cause = match u8::try_from(self.cause) {
+            Ok(c) if r#type.contains("attach") || r#type.contains("MM") => {
+                ExtendedErrorCause::Mobility(RejectCause::new(0, c))
+            }
+            Ok(c) if r#type.contains("activation") || r#type.contains("SM") => {
+                ExtendedErrorCause::Session(SessionCause::from(c))
+            }
+            _ => ExtendedErrorCause::Other(self.cause),
+        };
+        Some(cause)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_extended_error_cause() {
+        let report: ExtendedErrorReport = atat::serde_at::from_slice(
+            b"+CEER: \"SM activation error\",27,\"Missing or unknown APN\"",
+        )
+        .unwrap();
+        assert_eq!(
+            report.cause(),
+            Some(ExtendedErrorCause::Session(SessionCause::UnknownApn))
+        );
+
+        let report: ExtendedErrorReport = atat::serde_at::from_slice(
+            b"+CEER: \"SM attach error\",7,\"GPRS services not allowed\"",
+        )
+        .unwrap();
+        assert_eq!(
+            report.cause(),
+            Some(ExtendedErrorCause::Mobility(
+                RejectCause::EpsServicesNotAllowed
+            ))
+        );
+
+        let report: ExtendedErrorReport =
+            atat::serde_at::from_slice(b"+CEER: \"CC setup error\",17,\"User busy\"").unwrap();
+        assert_eq!(report.cause(), Some(ExtendedErrorCause::Other(17)));
+
+        let report: ExtendedErrorReport =
+            atat::serde_at::from_slice(b"+CEER: \"No report available\",0,\"\"").unwrap();
+        assert_eq!(report.cause(), None);
+    }
+
+    #[test]
+    fn indicator_changes() {
+        let mut ind: IndicatorControl =
+            atat::serde_at::from_slice(b"+CIND: 5,3,1,0,0,0,0,0,2,0,0,1").unwrap();
+        assert_eq!(ind.has_service(), Some(true));
+        assert_eq!(ind.is_roaming(), Some(false));
+        assert_eq!(ind.gprs, 2);
+
+        ind.update(&IndicatorEvent {
+            descr: IndicatorEvent::ROAM,
+            value: 1,
+        });
+        ind.update(&IndicatorEvent {
+            descr: IndicatorEvent::SIGNAL,
+            value: 5,
+        });
+        assert_eq!(ind.is_roaming(), Some(true));
+        assert_eq!(ind.signal, 5);
+
+        ind.update(&IndicatorEvent {
+            descr: IndicatorEvent::SERVICE,
+            value: 65535,
+        });
+        assert_eq!(ind.has_service(), None);
+    }
+}
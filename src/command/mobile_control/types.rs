@@ -1,6 +1,8 @@
 //! Argument and parameter types used by Mobile equipment control and status Commands and Responses
 use atat::atat_derive::AtatEnum;
 
+use crate::command::psn::types::RejectCause;
+
 #[derive(Clone, Copy, PartialEq, Eq, AtatEnum)]
 pub enum Functionality {
     /// 0: Sets the MT to minimum functionality (disable both transmit and receive RF
@@ -58,6 +60,22 @@ pub enum Functionality {
     ))]
     AirplaneMode = 4,
 
+    /// 5: Sets the MT into the test mode, in which the non-signaling RF test of
+    /// +UTEST can be entered
+    #[cfg(any(
+        feature = "lisa-u1",
+        feature = "lisa-u2",
+        feature = "sara-u2",
+        feature = "sara-u201",
+        feature = "toby-r2",
+        feature = "lara-r2",
+        feature = "leon-g1",
+        feature = "sara-g3",
+        feature = "sara-g4",
+        feature = "any-module",
+    ))]
+    TestMode = 5,
+
     /// 6: Enables the SIM toolkit interface in dedicated mode and fetching of proactive
     /// commands by SIM Application Toolkit from the SIM card
     #[cfg(any(
@@ -207,6 +225,31 @@ pub enum TerminationErrorMode {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, AtatEnum)]
+pub enum EventReportingMode {
+    /// 0 (default value): buffer the URCs in the MT
+    Buffer = 0,
+    /// 1: discard the URCs while the MT-DTE link is reserved, forward them
+    /// otherwise
+    Discard = 1,
+    /// 2: buffer the URCs while the MT-DTE link is reserved, and flush them
+    /// afterwards
+    BufferAndFlush = 2,
+    /// 3: forward the URCs directly to the DTE
+    Forward = 3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, AtatEnum)]
+pub enum IndicatorReporting {
+    /// 0 (default value): no +CIEV URCs
+    Disabled = 0,
+    /// 1: +CIEV URCs on indicator changes, that were not caused by +CIND
+    Changes = 1,
+    /// 2: +CIEV URCs on all indicator changes
+    All = 2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PowerMode {
     ///MT is switched on with minimum functionality
     Minimum = 0,
@@ -220,7 +263,8 @@ pub enum PowerMode {
     MinimumWithoutSim = 19,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum STKMode {
     ///the SIM-toolkit interface in dedicated mode and fetching of proactive commands by SIM-APPL from the SIM-card are enabled
     DedicatedMode = 6,
@@ -230,7 +274,8 @@ pub enum STKMode {
     RawMode = 9,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ReportMobileTerminationErrorStatus {
     ///+CME ERROR: <err> result code disabled and ERROR used
     DisabledERRORused = 0,
@@ -239,3 +284,299 @@ pub enum ReportMobileTerminationErrorStatus {
     ///+CME ERROR: <err> result code enabled and verbose <err> values used
     EnabledVerbose = 2,
 }
+
+/// SM / ESM cause of a failed context activation, as reported by +CEER. See
+/// 3GPP TS 24.008 Annex I and TS 24.301 Annex B.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SessionCause {
+    /// #8: Operator determined barring
+    OperatorDeterminedBarring,
+    /// #26: Insufficient resources
+    InsufficientResources,
+    /// #27: Missing or unknown APN
+    UnknownApn,
+    /// #28: Unknown PDP address or PDP type
+    UnknownPdpAddressOrType,
+    /// #29: User authentication failed
+    AuthenticationFailed,
+    /// #30: Activation rejected by GGSN, Serving GW or PDN GW
+    RejectedByGateway,
+    /// #31: Activation rejected, unspecified
+    ActivationRejected,
+    /// #32: Service option not supported
+    ServiceOptionNotSupported,
+    /// #33: Requested service option not subscribed
+    ServiceOptionNotSubscribed,
+    /// #34: Service option temporarily out of order
+    ServiceOptionOutOfOrder,
+    /// #36: Regular deactivation
+    RegularDeactivation,
+    /// #38: Network failure
+    NetworkFailure,
+    /// #50: PDP type IPv4 only allowed
+    Ipv4OnlyAllowed,
+    /// #51: PDP type IPv6 only allowed
+    Ipv6OnlyAllowed,
+    /// #55: Multiple PDN connections for a given APN not allowed
+    MultiplePdnConnectionsNotAllowed,
+    /// #65: Maximum number of PDP contexts reached
+    MaxContextsReached,
+    /// #66: Requested APN not supported in current RAT and PLMN combination
+    ApnNotSupportedInRat,
+    /// #111: Protocol error, unspecified
+    ProtocolError,
+    /// Any other SM cause
+    Other(u8),
+}
+
+impl From<u8> for SessionCause {
+    fn from(v: u8) -> Self {
+        match v {
+            8 => Self::OperatorDeterminedBarring,
+            26 => Self::InsufficientResources,
+            27 => Self::UnknownApn,
+            28 => Self::UnknownPdpAddressOrType,
+            29 => Self::AuthenticationFailed,
+            30 => Self::RejectedByGateway,
+            31 => Self::ActivationRejected,
+            32 => Self::ServiceOptionNotSupported,
+            33 => Self::ServiceOptionNotSubscribed,
+            34 => Self::ServiceOptionOutOfOrder,
+            36 => Self::RegularDeactivation,
+            38 => Self::NetworkFailure,
+            50 => Self::Ipv4OnlyAllowed,
+            51 => Self::Ipv6OnlyAllowed,
+            55 => Self::MultiplePdnConnectionsNotAllowed,
+            65 => Self::MaxContextsReached,
+            66 => Self::ApnNotSupportedInRat,
+            111 => Self::ProtocolError,
+            c => Self::Other(c),
+        }
+    }
+}
+
+/// Decoded cause of an extended error report (+CEER).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExtendedErrorCause {
+    /// Failed attach or detach, with a GMM / EMM cause
+    Mobility(RejectCause),
+    /// Failed context activation or deactivation, with an SM / ESM cause
+    Session(SessionCause),
+    /// Cause of a report type that is not decoded, eg. a call control cause
+    Other(u32),
+}
+
+/// Mobile termination error of a failed command, reported as `+CME ERROR:
+/// <err>` with the numeric <err> values of +CMEE=1. See 3GPP TS 27.007
+/// clause 9.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CmeError {
+    /// 0: Phone failure
+    PhoneFailure,
+    /// 1: No connection to phone
+    NoConnection,
+    /// 3: Operation not allowed
+    OperationNotAllowed,
+    /// 4: Operation not supported
+    OperationNotSupported,
+    /// 5: PH-SIM PIN required
+    PhSimPinRequired,
+    /// 10: SIM not inserted
+    SimNotInserted,
+    /// 11: SIM PIN required
+    SimPinRequired,
+    /// 12: SIM PUK required
+    SimPukRequired,
+    /// 13: SIM failure
+    SimFailure,
+    /// 14: SIM busy
+    SimBusy,
+    /// 15: SIM wrong
+    SimWrong,
+    /// 16: Incorrect password
+    IncorrectPassword,
+    /// 17: SIM PIN2 required
+    SimPin2Required,
+    /// 18: SIM PUK2 required
+    SimPuk2Required,
+    /// 20: Memory full
+    MemoryFull,
+    /// 21: Invalid index
+    InvalidIndex,
+    /// 22: Not found
+    NotFound,
+    /// 23: Memory failure
+    MemoryFailure,
+    /// 24: Text string too long
+    TextTooLong,
+    /// 25: Invalid characters in text string
+    InvalidCharactersInText,
+    /// 26: Dial string too long
+    DialStringTooLong,
+    /// 27: Invalid characters in dial string
+    InvalidCharactersInDialString,
+    /// 30: No network service
+    NoNetworkService,
+    /// 31: Network timeout
+    NetworkTimeout,
+    /// 32: Network not allowed, emergency calls only
+    EmergencyCallsOnly,
+    /// 40: Network personalisation PIN required
+    NetworkPersonalisationPinRequired,
+    /// 50: Incorrect parameters
+    IncorrectParameters,
+    /// 100: Unknown
+    Unknown,
+    /// 103: Illegal MS
+    IllegalMs,
+    /// 106: Illegal ME
+    IllegalMe,
+    /// 107: GPRS services not allowed
+    GprsServicesNotAllowed,
+    /// 111: PLMN not allowed
+    PlmnNotAllowed,
+    /// 112: Location area not allowed
+    LocationAreaNotAllowed,
+    /// 113: Roaming not allowed in this location area
+    RoamingNotAllowed,
+    /// 132: Service option not supported
+    ServiceOptionNotSupported,
+    /// 133: Requested service option not subscribed
+    ServiceOptionNotSubscribed,
+    /// 134: Service option temporarily out of order
+    ServiceOptionOutOfOrder,
+    /// 148: Unspecified GPRS error
+    UnspecifiedGprsError,
+    /// 149: PDP authentication failure
+    PdpAuthenticationFailure,
+    /// 150: Invalid mobile class
+    InvalidMobileClass,
+    /// Any other code, eg. one of the module specific codes of the file
+    /// system or +USECMNG, listed in the AT commands manual
+    Other(u16),
+}
+
+impl From<u16> for CmeError {
+    fn from(v: u16) -> Self {
+        match v {
+            0 => Self::PhoneFailure,
+            1 => Self::NoConnection,
+            3 => Self::OperationNotAllowed,
+            4 => Self::OperationNotSupported,
+            5 => Self::PhSimPinRequired,
+            10 => Self::SimNotInserted,
+            11 => Self::SimPinRequired,
+            12 => Self::SimPukRequired,
+            13 => Self::SimFailure,
+            14 => Self::SimBusy,
+            15 => Self::SimWrong,
+            16 => Self::IncorrectPassword,
+            17 => Self::SimPin2Required,
+            18 => Self::SimPuk2Required,
+            20 => Self::MemoryFull,
+            21 => Self::InvalidIndex,
+            22 => Self::NotFound,
+            23 => Self::MemoryFailure,
+            24 => Self::TextTooLong,
+            25 => Self::InvalidCharactersInText,
+            26 => Self::DialStringTooLong,
+            27 => Self::InvalidCharactersInDialString,
+            30 => Self::NoNetworkService,
+            31 => Self::NetworkTimeout,
+            32 => Self::EmergencyCallsOnly,
+            40 => Self::NetworkPersonalisationPinRequired,
+            50 => Self::IncorrectParameters,
+            100 => Self::Unknown,
+            103 => Self::IllegalMs,
+            106 => Self::IllegalMe,
+            107 => Self::GprsServicesNotAllowed,
+            111 => Self::PlmnNotAllowed,
+            112 => Self::LocationAreaNotAllowed,
+            113 => Self::RoamingNotAllowed,
+            132 => Self::ServiceOptionNotSupported,
+            133 => Self::ServiceOptionNotSubscribed,
+            134 => Self::ServiceOptionOutOfOrder,
+            148 => Self::UnspecifiedGprsError,
+            149 => Self::PdpAuthenticationFailure,
+            150 => Self::InvalidMobileClass,
+            c => Self::Other(c),
+        }
+    }
+}
+
+/// Message service error of a failed SMS command, reported as `+CMS ERROR:
+/// <err>`. See 3GPP TS 27.005 clause 3.2.5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CmsError {
+    /// 300: ME failure
+    MeFailure,
+    /// 301: SMS service of ME reserved
+    ServiceReserved,
+    /// 302: Operation not allowed
+    OperationNotAllowed,
+    /// 303: Operation not supported
+    OperationNotSupported,
+    /// 304: Invalid PDU mode parameter
+    InvalidPduModeParameter,
+    /// 305: Invalid text mode parameter
+    InvalidTextModeParameter,
+    /// 310: SIM not inserted
+    SimNotInserted,
+    /// 311: SIM PIN required
+    SimPinRequired,
+    /// 313: SIM failure
+    SimFailure,
+    /// 314: SIM busy
+    SimBusy,
+    /// 315: SIM wrong
+    SimWrong,
+    /// 316: SIM PUK required
+    SimPukRequired,
+    /// 320: Memory failure
+    MemoryFailure,
+    /// 321: Invalid memory index
+    InvalidMemoryIndex,
+    /// 322: Memory full
+    MemoryFull,
+    /// 330: SMSC address unknown
+    SmscAddressUnknown,
+    /// 331: No network service
+    NoNetworkService,
+    /// 332: Network timeout
+    NetworkTimeout,
+    /// 500: Unknown error
+    Unknown,
+    /// Any other code
+    Other(u16),
+}
+
+impl From<u16> for CmsError {
+    fn from(v: u16) -> Self {
+        match v {
+            300 => Self::MeFailure,
+            301 => Self::ServiceReserved,
+            302 => Self::OperationNotAllowed,
+            303 => Self::OperationNotSupported,
+            304 => Self::InvalidPduModeParameter,
+            305 => Self::InvalidTextModeParameter,
+            310 => Self::SimNotInserted,
+            311 => Self::SimPinRequired,
+            313 => Self::SimFailure,
+            314 => Self::SimBusy,
+            315 => Self::SimWrong,
+            316 => Self::SimPukRequired,
+            320 => Self::MemoryFailure,
+            321 => Self::InvalidMemoryIndex,
+            322 => Self::MemoryFull,
+            330 => Self::SmscAddressUnknown,
+            331 => Self::NoNetworkService,
+            332 => Self::NetworkTimeout,
+            500 => Self::Unknown,
+            c => Self::Other(c),
+        }
+    }
+}
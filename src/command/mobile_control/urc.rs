@@ -0,0 +1,43 @@
+//! Unsolicited responses for Mobile equipment control and status Commands
+use atat::atat_derive::AtatResp;
+
+/// 5.6 Indicator event +CIEV
+///
+/// Reports the change of a +CIND indicator, once enabled with +CMER.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IndicatorEvent {
+    /// Index of the indicator in the +CIND list, starting at 1 for "battchg"
+    #[at_arg(position = 0)]
+    pub descr: u8,
+    #[at_arg(position = 1)]
+    pub value: u16,
+}
+
+impl IndicatorEvent {
+    pub const BATTCHG: u8 = 1;
+    pub const SIGNAL: u8 = 2;
+    pub const SERVICE: u8 = 3;
+    pub const SOUNDER: u8 = 4;
+    pub const MESSAGE: u8 = 5;
+    pub const CALL: u8 = 6;
+    pub const ROAM: u8 = 7;
+    pub const SMSFULL: u8 = 8;
+    pub const GPRS: u8 = 9;
+    pub const CALLSETUP: u8 = 10;
+    pub const CALLHELD: u8 = 11;
+    /// "simind": 0 when no SIM is detected, 1 when a SIM is detected
+    pub const SIMIND: u8 = 12;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sim_indicator() {
+        let ev: IndicatorEvent = atat::serde_at::from_slice(b"+CIEV: 12,1").unwrap();
+        assert_eq!(ev.descr, IndicatorEvent::SIMIND);
+        assert_eq!(ev.value, 1);
+    }
+}
@@ -1,6 +1,9 @@
 //! AT Commands for u-blox cellular module family\
 //! Following the [u-blox cellular modules AT commands manual](https://content.u-blox.com/sites/default/files/u-blox-CEL_ATCommands_UBX-13002752.pdf)
 
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "audio")]
 pub mod call_control;
 pub mod control;
 pub mod device_data_security;
@@ -8,16 +11,28 @@ pub mod device_lock;
 pub mod dns;
 pub mod file_system;
 pub mod general;
+#[cfg(feature = "gnss")]
+pub mod gnss;
 pub mod gpio;
+pub mod hex;
+#[cfg(feature = "http")]
 pub mod http;
 pub mod ip_transport_layer;
 pub mod ipc;
+#[cfg(feature = "lwm2m")]
+pub mod lwm2m;
 pub mod mobile_control;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
 pub mod network_service;
 pub mod networking;
 pub mod psn;
+pub mod records;
+pub mod sim_access;
+#[cfg(feature = "sms")]
 pub mod sms;
 pub mod system_features;
+pub mod test;
 
 use atat::{
     atat_derive::{AtatCmd, AtatResp, AtatUrc},
@@ -31,20 +46,11 @@ pub struct NoResponse;
 #[at_cmd("", NoResponse, attempts = 3)]
 pub struct AT;
 
-#[derive(Debug, Clone, AtatUrc)]
+#[derive(Debug, Clone, PartialEq, AtatUrc)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Urc {
-    #[at_urc("+CGEV: NW DETACH")]
-    NetworkDetach,
-    #[at_urc("+CGEV: ME DETACH")]
-    MobileStationDetach,
-    #[at_urc("+CGEV: NW DEACT")]
-    NetworkDeactivate,
-    #[at_urc("+CGEV: ME DEACT")]
-    MobileStationDeactivate,
-    #[at_urc("+CGEV: NW PDN DEACT")]
-    NetworkPDNDeactivate,
-    #[at_urc("+CGEV: ME PDN DEACT")]
-    MobileStationPDNDeactivate,
+    #[at_urc("+CGEV")]
+    PacketDomainEvent(psn::urc::PacketDomainEvent),
 
     #[cfg(feature = "internal-network-stack")]
     #[at_urc("+UUSORD")]
@@ -61,6 +67,7 @@ pub enum Urc {
     #[at_urc("+UUPSDD")]
     DataConnectionDeactivated(psn::urc::DataConnectionDeactivated),
 
+    #[cfg(feature = "sms")]
     #[at_urc("+UMWI")]
     MessageWaitingIndication(sms::urc::MessageWaitingIndication),
     #[at_urc("+CREG", parse = custom_cxreg_parse)]
@@ -72,8 +79,41 @@ pub enum Urc {
     #[at_urc("+UREG")]
     ExtendedPSNetworkRegistration(psn::urc::ExtendedPSNetworkRegistration),
 
+    #[cfg(feature = "http")]
     #[at_urc("+UUHTTPCR")]
     HttpResponse(http::urc::HttpResponse),
+
+    #[cfg(feature = "mqtt")]
+    #[at_urc("+UUMQTTCM")]
+    MqttUnreadMessages(mqtt::urc::MqttUnreadMessages),
+    #[cfg(feature = "mqtt")]
+    #[at_urc("+UUMQTTC")]
+    MqttCommandResult(mqtt::urc::MqttCommandResult),
+
+    #[at_urc("+UUFWINSTALL")]
+    FirmwareInstallProgress(system_features::urc::FirmwareInstallProgress),
+    #[at_urc("+UUSTS")]
+    ThermalWarning(system_features::urc::ThermalWarning),
+    #[at_urc("+UUPSMR")]
+    PsmState(system_features::urc::PsmState),
+
+    #[cfg(feature = "gnss")]
+    #[at_urc("+UUGIND")]
+    GnssAidingIndication(gnss::urc::AidingIndication),
+
+    #[cfg(feature = "lwm2m")]
+    #[at_urc("+ULWM2MSTAT")]
+    Lwm2mStatus(lwm2m::urc::Lwm2mStatus),
+
+    #[at_urc("+CIEV")]
+    IndicatorEvent(mobile_control::urc::IndicatorEvent),
+
+    #[cfg(feature = "audio")]
+    #[at_urc("RING")]
+    Ring,
+    #[cfg(feature = "audio")]
+    #[at_urc("+CLIP")]
+    CallingLineIdentification(call_control::urc::CallingLineIdentification),
 }
 
 fn custom_cxreg_parse<'a, T, Error: nom::error::ParseError<&'a [u8]> + core::fmt::Debug>(
@@ -89,11 +129,13 @@ where
         let index = urc.iter().position(|&x| x == b':').unwrap_or(urc.len());
         let arguments = &urc[index + 1..];
 
-        // "+CxREG?" response will always have atleast 2 arguments, both being
-        // integers.
+        // "+CxREG?" response will always have at least 2 arguments, both being
+        // integers, as it starts with the configured <n>.
         //
-        // "+CxREG:" URC will always have at least 1 integer argument, and the
-        // second argument, if present, will be a string.
+        // "+CxREG:" URC will always have at least 1 integer argument, <stat>.
+        // The second argument, if present, is the location area, which is
+        // reported as a string of 4 hex digits, quoted or not, or left empty,
+        // eg. a denied "+CEREG: 3,,,,0,15" has no location.
 
         // Parse the first
         let (rem, _) = nom::sequence::tuple((
@@ -103,20 +145,17 @@ where
         ))(arguments)?;
 
         if !rem.is_empty() {
-            // If we have more arguments, we want to make sure this is a quoted string for the URC case.
-            nom::sequence::tuple((
-                nom::character::complete::space0,
-                nom::sequence::delimited(
-                    nom::bytes::complete::tag("\""),
-                    nom::bytes::complete::escaped(
-                        nom::character::streaming::none_of("\"\\"),
-                        '\\',
-                        nom::character::complete::one_of("\"\\"),
-                    ),
-                    nom::bytes::complete::tag("\""),
-                ),
-                nom::branch::alt((nom::combinator::eof, nom::bytes::complete::tag(","))),
-            ))(rem)?;
+            // If we have more arguments, the second one must not be a small
+            // integer, which would be the <stat> of a response.
+            let second = rem.split(|&x| x == b',').next().unwrap_or(rem);
+            let second = second.trim_ascii();
+
+            if (1..=2).contains(&second.len()) && second.iter().all(u8::is_ascii_digit) {
+                return Err(nom::Err::Error(Error::from_error_kind(
+                    arguments,
+                    nom::error::ErrorKind::Verify,
+                )));
+            }
         }
 
         Ok((i, (urc, len)))
@@ -136,6 +175,8 @@ mod tests {
         let creg_resp = b"\r\n+CREG: 2,5,\"9E9A\",\"019624BD\",2\r\n";
         let creg_urc_min = b"\r\n+CREG: 0\r\n";
         let creg_urc_full = b"\r\n+CREG: 5,\"9E9A\",\"0196BDB0\",2\r\n";
+        let cereg_urc_denied = b"\r\n+CEREG: 3,,,,0,15\r\n";
+        let cereg_resp_denied = b"\r\n+CEREG: 3,3,,,,0,15\r\n";
 
         assert!(
             custom_cxreg_parse::<&[u8], nom::error::Error<&[u8]>>(&b"+CREG"[..])(creg_resp)
@@ -149,6 +190,140 @@ mod tests {
             custom_cxreg_parse::<&[u8], nom::error::Error<&[u8]>>(&b"+CREG"[..])(creg_urc_full)
                 .is_ok()
         );
+        assert!(
+            custom_cxreg_parse::<&[u8], nom::error::Error<&[u8]>>(&b"+CEREG"[..])(cereg_urc_denied)
+                .is_ok()
+        );
+        assert!(
+            custom_cxreg_parse::<&[u8], nom::error::Error<&[u8]>>(&b"+CEREG"[..])(
+                cereg_resp_denied
+            )
+            .is_err()
+        );
+    }
+
+    fn is_cxreg_urc(token: &[u8], line: &[u8]) -> bool {
+        custom_cxreg_parse::<&[u8], nom::error::Error<&[u8]>>(token)(line).is_ok()
+    }
+
+    /// Every URC shape of +CREG, +CGREG and +CEREG for the configured n.
+    #[test]
+    fn test_custom_parse_cxreg_urc_formats() {
+        let creg_urcs: [&[u8]; 6] = [
+            b"\r\n+CREG: 1\r\n",
+            b"\r\n+CREG: 5,\"9E9A\",\"0196BDB0\"\r\n",
+            b"\r\n+CREG: 5,\"9E9A\",\"0196BDB0\",2\r\n",
+            b"\r\n+CREG: 5,9E9A,0196BDB0,2\r\n",
+            b"\r\n+CREG: 3,\"9E9A\",\"0196BDB0\",2,0,15\r\n",
+            b"\r\n+CREG: 3,,,,0,15\r\n",
+        ];
+        for urc in creg_urcs {
+            assert!(is_cxreg_urc(b"+CREG", urc), "{:?}", urc);
+        }
+
+        let cgreg_urcs: [&[u8]; 7] = [
+            b"\r\n+CGREG: 1\r\n",
+            b"\r\n+CGREG: 1,\"9E9A\",\"0196BDB0\",2,\"01\"\r\n",
+            b"\r\n+CGREG: 1,9E9A,0196BDB0,2,01\r\n",
+            b"\r\n+CGREG: 3,\"9E9A\",\"0196BDB0\",2,\"01\",0,15\r\n",
+            b"\r\n+CGREG: 1,\"9E9A\",\"0196BDB0\",2,\"01\",,,\"00100100\",\"01000111\",\"00000010\"\r\n",
+            b"\r\n+CGREG: 3,\"9E9A\",\"0196BDB0\",2,\"01\",0,15,\"00100100\",\"01000111\",\"00000010\"\r\n",
+            b"\r\n+CGREG: 3,,,,,0,15\r\n",
+        ];
+        for urc in cgreg_urcs {
+            assert!(is_cxreg_urc(b"+CGREG", urc), "{:?}", urc);
+        }
+
+        let cereg_urcs: [&[u8]; 7] = [
+            b"\r\n+CEREG: 1\r\n",
+            b"\r\n+CEREG: 5,\"4E2D\",\"01A2D001\",7\r\n",
+            b"\r\n+CEREG: 5,4E2D,01A2D001,7\r\n",
+            b"\r\n+CEREG: 3,\"4E2D\",\"01A2D001\",7,0,15\r\n",
+            b"\r\n+CEREG: 1,\"4E2D\",\"01A2D001\",7,,,\"00100100\",\"01000111\"\r\n",
+            b"\r\n+CEREG: 1,\"4E2D\",\"01A2D001\",7,0,15,\"00100100\",\"01000111\"\r\n",
+            b"\r\n+CEREG: 3,,,,0,15\r\n",
+        ];
+        for urc in cereg_urcs {
+            assert!(is_cxreg_urc(b"+CEREG", urc), "{:?}", urc);
+        }
+    }
+
+    /// Every response shape of +CREG?, +CGREG? and +CEREG? for the
+    /// configured n.
+    #[test]
+    fn test_custom_parse_cxreg_responses() {
+        let responses: [(&[u8], &[u8]); 9] = [
+            (b"+CREG", b"\r\n+CREG: 0,1\r\n"),
+            (b"+CREG", b"\r\n+CREG: 3,3,,,,0,15\r\n"),
+            (b"+CREG", b"\r\n+CREG: 2, 5,9E9A,0196BDB0,2\r\n"),
+            (b"+CGREG", b"\r\n+CGREG: 0,1\r\n"),
+            (b"+CGREG", b"\r\n+CGREG: 2,1,\"9E9A\",\"0196BDB0\",2,\"01\"\r\n"),
+            (b"+CGREG", b"\r\n+CGREG: 5,1,\"9E9A\",\"0196BDB0\",2,\"01\",,,\"00100100\",\"01000111\",\"00000010\"\r\n"),
+            (b"+CEREG", b"\r\n+CEREG: 0,1\r\n"),
+            (b"+CEREG", b"\r\n+CEREG: 2,5,4E2D,01A2D001,7\r\n"),
+            (b"+CEREG", b"\r\n+CEREG: 4,1,\"4E2D\",\"01A2D001\",7,,,\"00100100\",\"01000111\"\r\n"),
+        ];
+        for (token, response) in responses {
+            assert!(!is_cxreg_urc(token, response), "{:?}", response);
+        }
+    }
+
+    #[test]
+    fn test_cxreg_urc_fields() {
+        let Some(Urc::NetworkRegistration(creg)) =
+            <Urc as atat::AtatUrc>::parse(b"+CREG: 3,9E9A,0196BDB0,2,0,15")
+        else {
+            panic!("expected +CREG");
+        };
+        assert_eq!(
+            creg.stat,
+            network_service::types::NetworkRegistrationStat::RegistrationDenied
+        );
+        assert_eq!(creg.lac.as_deref(), Some("9E9A"));
+        assert_eq!(creg.ci.as_deref(), Some("0196BDB0"));
+        assert_eq!(creg.act_status, Some(2));
+        assert_eq!(creg.cause_type, Some(0));
+        assert_eq!(creg.reject_cause, Some(15));
+
+        let Some(Urc::GPRSNetworkRegistration(cgreg)) = <Urc as atat::AtatUrc>::parse(
+            b"+CGREG: 1,\"9E9A\",\"0196BDB0\",2,\"01\",,,\"00100100\",\"01000111\",\"00000010\"",
+        ) else {
+            panic!("expected +CGREG");
+        };
+        assert_eq!(
+            cgreg.stat,
+            psn::types::GPRSNetworkRegistrationStat::Registered
+        );
+        assert_eq!(cgreg.rac.as_deref(), Some("01"));
+        assert_eq!(cgreg.cause_type, None);
+        assert_eq!(cgreg.reject_cause, None);
+        assert_eq!(cgreg.active_time.as_deref(), Some("00100100"));
+        assert_eq!(cgreg.periodic_rau.as_deref(), Some("01000111"));
+        assert_eq!(cgreg.gprs_ready_timer.as_deref(), Some("00000010"));
+
+        let Some(Urc::EPSNetworkRegistration(cereg)) = <Urc as atat::AtatUrc>::parse(
+            b"+CEREG: 3,\"4E2D\",\"01A2D001\",7,0,15,\"00100100\",\"01000111\"",
+        ) else {
+            panic!("expected +CEREG");
+        };
+        assert_eq!(
+            cereg.stat,
+            psn::types::EPSNetworkRegistrationStat::RegistrationDenied
+        );
+        assert_eq!(cereg.tac.as_deref(), Some("4E2D"));
+        assert_eq!(cereg.cause_type, Some(0));
+        assert_eq!(cereg.reject_cause, Some(15));
+        assert_eq!(cereg.active_time.as_deref(), Some("00100100"));
+        assert_eq!(cereg.periodic_tau.as_deref(), Some("01000111"));
+
+        let Some(Urc::EPSNetworkRegistration(cereg)) =
+            <Urc as atat::AtatUrc>::parse(b"+CEREG: 3,,,,0,15")
+        else {
+            panic!("expected +CEREG");
+        };
+        assert_eq!(cereg.tac, None);
+        assert_eq!(cereg.act, None);
+        assert_eq!(cereg.reject_cause, Some(15));
     }
 
     #[test]
@@ -164,4 +339,149 @@ mod tests {
 
         let s = cmd.write(&mut buf);
     }
+
+    fn assert_impls<T: core::fmt::Debug + Clone + PartialEq>() {}
+
+    #[cfg(feature = "defmt")]
+    fn assert_format<T: defmt::Format>() {}
+
+    macro_rules! assert_derives {
+        ($($ty:ty),* $(,)?) => {
+            $(
+                assert_impls::<$ty>();
+                #[cfg(feature = "defmt")]
+                assert_format::<$ty>();
+            )*
+        };
+    }
+
+    /// Every response and URC can be logged and compared.
+    #[test]
+    fn response_derives() {
+        assert_derives!(
+            Urc,
+            control::responses::DataRate,
+            device_data_security::responses::SecurityData,
+            device_data_security::responses::SecurityDataImport,
+            device_lock::responses::PinCounter,
+            device_lock::responses::PinStatus,
+            dns::responses::ResolveNameIpResponse,
+            file_system::responses::FileSize,
+            file_system::responses::FreeSpace,
+            file_system::responses::ReadBlockResponse,
+            file_system::responses::ReadFileResponse,
+            general::responses::CCID,
+            general::responses::CIMI,
+            general::responses::FirmwareVersion,
+            general::responses::IMEI,
+            general::responses::IdentificationInformationResponse,
+            general::responses::ManufacturerId,
+            general::responses::ModelId,
+            gpio::responses::GpioConfiguration,
+            gpio::responses::GpioPinValue,
+            ip_transport_layer::responses::CreateSocketResponse,
+            mobile_control::responses::DateTime,
+            mobile_control::responses::ExtendedErrorReport,
+            mobile_control::responses::IndicatorControl,
+            mobile_control::responses::ModuleFunctionality,
+            mobile_control::responses::ReportMobileTerminationError,
+            mobile_control::urc::IndicatorEvent,
+            network_service::responses::AvailableOperators,
+            network_service::responses::CellEnvironmentResponse,
+            network_service::responses::CellInfo,
+            network_service::responses::NetworkRegistrationStatus,
+            network_service::responses::OperatorSelection,
+            network_service::responses::RadioAccessTechnology,
+            network_service::responses::SignalQuality,
+            network_service::urc::NetworkRegistration,
+            networking::responses::EmbeddedPortFiltering,
+            psn::responses::DataCounters,
+            psn::responses::EPSNetworkRegistrationStatus,
+            psn::responses::ExtendedPSNetworkRegistrationStatus,
+            psn::responses::GPRSAttached,
+            psn::responses::GPRSNetworkRegistrationStatus,
+            psn::responses::PDPAddress,
+            psn::responses::PDPContextDefinition,
+            psn::responses::PDPContextDynamicParameters,
+            psn::responses::PDPContextState,
+            psn::responses::PacketSwitchedConfig,
+            psn::responses::PacketSwitchedNetworkAddress,
+            psn::responses::PacketSwitchedNetworkData,
+            psn::urc::DataConnectionActivated,
+            psn::urc::DataConnectionDeactivated,
+            psn::urc::EPSNetworkRegistration,
+            psn::urc::ExtendedPSNetworkRegistration,
+            psn::urc::GPRSNetworkRegistration,
+            sim_access::responses::GenericSimAccessResponse,
+            sim_access::responses::RestrictedSimAccessResponse,
+            system_features::responses::FactoryConfiguration,
+            system_features::responses::FirmwarePrevalidation,
+            system_features::responses::PowerSavingControl,
+            system_features::responses::SmartTemperatureSupervisor,
+            system_features::responses::Temperature,
+            system_features::urc::FirmwareInstallProgress,
+            system_features::urc::PsmState,
+            system_features::urc::ThermalWarning,
+            test::responses::RxMeasurement,
+        );
+
+        #[cfg(feature = "internal-network-stack")]
+        assert_derives!(
+            ip_transport_layer::responses::CloseSocketResponse,
+            ip_transport_layer::responses::SocketControlResponse,
+            ip_transport_layer::responses::SocketData,
+            ip_transport_layer::responses::SocketDataBinary,
+            ip_transport_layer::responses::SocketErrorResponse,
+            ip_transport_layer::responses::SocketOptionResponse,
+            ip_transport_layer::responses::UDPSendToDataResponse,
+            ip_transport_layer::responses::UDPSocketData,
+            ip_transport_layer::responses::UDPSocketDataAvailable,
+            ip_transport_layer::responses::UDPSocketDataBinary,
+            ip_transport_layer::responses::WriteSocketDataResponse,
+            ip_transport_layer::urc::SocketClosed,
+            ip_transport_layer::urc::SocketDataAvailable,
+        );
+
+        #[cfg(feature = "gnss")]
+        assert_derives!(
+            gnss::responses::GnssPowerState,
+            gnss::responses::GpsFixData,
+            gnss::responses::RecommendedMinimumData,
+            gnss::urc::AidingIndication,
+        );
+
+        #[cfg(feature = "http")]
+        assert_derives!(http::responses::HttpError, http::urc::HttpResponse,);
+
+        #[cfg(feature = "lwm2m")]
+        assert_derives!(lwm2m::responses::Lwm2mClientState, lwm2m::urc::Lwm2mStatus,);
+
+        #[cfg(feature = "mqtt")]
+        assert_derives!(
+            mqtt::responses::MqttCommandResponse,
+            mqtt::responses::MqttConfigResponse,
+            mqtt::responses::MqttError,
+            mqtt::responses::MqttMessage,
+            mqtt::urc::MqttCommandResult,
+            mqtt::urc::MqttUnreadMessages,
+        );
+
+        #[cfg(feature = "sms")]
+        assert_derives!(
+            sms::responses::MessageReference,
+            sms::responses::StoredMessage,
+            sms::urc::MessageWaitingIndication,
+        );
+
+        #[cfg(feature = "audio")]
+        assert_derives!(
+            call_control::responses::CallStatus,
+            call_control::urc::CallingLineIdentification,
+            audio::responses::AudioPathMode,
+            audio::responses::I2sConfiguration,
+            audio::responses::MicrophoneGain,
+            audio::responses::Sidetone,
+            audio::responses::SpeakerGain,
+        );
+    }
 }
@@ -0,0 +1,126 @@
+//! ### 33 - MQTT
+//!
+//! u-blox cellular modules with an embedded MQTT client can connect to an MQTT
+//! broker, publish messages and subscribe to topics without an external MQTT
+//! implementation on top of the socket commands. The MQTT profile is
+//! configured with +UMQTT and the actions are triggered with +UMQTTC; the final
+//! results of the actions are reported with the +UUMQTTC URC.
+//!
+//! **NOTES:**
+//! - A PSD connection must be activated before using the MQTT AT commands.
+//! - The maximum length of a published or received message is 1024 bytes.
+pub mod responses;
+pub mod types;
+pub mod urc;
+
+use atat::atat_derive::AtatCmd;
+use responses::{MqttCommandResponse, MqttConfigResponse, MqttError, MqttMessage};
+use types::{MqttParam, MqttQos};
+
+/// 33.1 MQTT profile configuration +UMQTT
+///
+/// Configures or reads the parameter value of an MQTT client profile. Issue a
+/// set command for each <op_code> parameter to set all the parameters in an
+/// MQTT client profile.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UMQTT", MqttConfigResponse)]
+pub struct SetMqttConfig {
+    #[at_arg(position = 0)]
+    pub param: MqttParam,
+}
+
+/// 33.3 MQTT command +UMQTTC - Logout
+///
+/// Disconnects from the MQTT broker. The final result is reported with a
+/// `+UUMQTTC: 0,<result>` URC.
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "+UMQTTC=0",
+    MqttCommandResponse,
+    value_sep = false,
+    timeout_ms = 10000
+)]
+pub struct MqttLogout;
+
+/// 33.3 MQTT command +UMQTTC - Login
+///
+/// Connects to the MQTT broker using the configured MQTT profile. The final
+/// result is reported with a `+UUMQTTC: 1,<result>` URC.
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "+UMQTTC=1",
+    MqttCommandResponse,
+    value_sep = false,
+    timeout_ms = 10000
+)]
+pub struct MqttLogin;
+
+/// 33.3 MQTT command +UMQTTC - Publish
+///
+/// Publishes a message to the MQTT broker. With `hex_mode` set, the message
+/// is given as hexadecimal string, which allows publishing binary data and
+/// characters otherwise not allowed in an AT string argument.
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "+UMQTTC=2,",
+    MqttCommandResponse,
+    value_sep = false,
+    timeout_ms = 10000
+)]
+pub struct MqttPublish<'a> {
+    #[at_arg(position = 0)]
+    pub qos: MqttQos,
+    #[at_arg(position = 1)]
+    pub retain: bool,
+    #[at_arg(position = 2)]
+    pub hex_mode: bool,
+    #[at_arg(position = 3, len = 256)]
+    pub topic: &'a str,
+    #[at_arg(position = 4, len = 1024)]
+    pub message: &'a str,
+}
+
+/// 33.3 MQTT command +UMQTTC - Subscribe
+///
+/// Subscribes to a topic filter. The final result is reported with a
+/// `+UUMQTTC: 4,<result>,<QoS>,<topic>` URC.
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "+UMQTTC=4,",
+    MqttCommandResponse,
+    value_sep = false,
+    timeout_ms = 10000
+)]
+pub struct MqttSubscribe<'a> {
+    #[at_arg(position = 0)]
+    pub max_qos: MqttQos,
+    #[at_arg(position = 1, len = 256)]
+    pub topic_filter: &'a str,
+}
+
+/// 33.3 MQTT command +UMQTTC - Unsubscribe
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "+UMQTTC=5,",
+    MqttCommandResponse,
+    value_sep = false,
+    timeout_ms = 10000
+)]
+pub struct MqttUnsubscribe<'a> {
+    #[at_arg(position = 0, len = 256)]
+    pub topic_filter: &'a str,
+}
+
+/// 33.3 MQTT command +UMQTTC - Read message
+///
+/// Reads one of the received messages, reported by the `+UUMQTTCM` URC.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UMQTTC=6,1", MqttMessage, value_sep = false, timeout_ms = 10000)]
+pub struct MqttReadMessage;
+
+/// 33.4 MQTT error +UMQTTER
+///
+/// Retrieves the error class and code of the last MQTT operation.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UMQTTER", MqttError)]
+pub struct GetMqttError;
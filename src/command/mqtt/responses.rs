@@ -0,0 +1,84 @@
+//! Responses for MQTT Commands
+use atat::atat_derive::AtatResp;
+use atat::heapless_bytes::Bytes;
+use heapless::String;
+
+/// 33.1 MQTT profile configuration +UMQTT
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttConfigResponse {
+    #[at_arg(position = 0)]
+    pub op_code: u8,
+    /// 0: fail, 1: success
+    #[at_arg(position = 1)]
+    pub result: u8,
+}
+
+/// 33.3 MQTT command +UMQTTC
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttCommandResponse {
+    #[at_arg(position = 0)]
+    pub op_code: u8,
+    /// 0: fail, 1: success
+    #[at_arg(position = 1)]
+    pub result: u8,
+}
+
+/// 33.3 MQTT command +UMQTTC - Read message
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttMessage {
+    #[at_arg(position = 0)]
+    pub op_code: u8,
+    #[at_arg(position = 1)]
+    pub qos: u8,
+    #[at_arg(position = 2)]
+    pub topic_length: usize,
+    #[at_arg(position = 3)]
+    pub topic: String<256>,
+    #[at_arg(position = 4)]
+    pub message_length: usize,
+    /// The quoted message. As the message may contain any character, it is
+    /// not parsed as a string; use [`MqttMessage::payload`] to get the message
+    /// content.
+    #[at_arg(position = 5)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+    pub message: Bytes<{ 1024 + 2 }>,
+}
+
+impl MqttMessage {
+    /// The message payload, as delimited by the reported message length.
+    pub fn payload(&self) -> &[u8] {
+        let data = self
+            .message
+            .strip_prefix(b"\"")
+            .unwrap_or(&self.message[..]);
+        &data[..self.message_length.min(data.len())]
+    }
+}
+
+/// 33.4 MQTT error +UMQTTER
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttError {
+    #[at_arg(position = 0)]
+    pub error_class: u8,
+    #[at_arg(position = 1)]
+    pub error_code: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_message() {
+        let resp = b"+UMQTTC: 6,1,13,\"sensor/report\",11,\"\"a\",b,\r\nc\"d\"";
+        let msg: MqttMessage = atat::serde_at::from_slice(resp).unwrap();
+
+        assert_eq!(msg.qos, 1);
+        assert_eq!(msg.topic.as_str(), "sensor/report");
+        assert_eq!(msg.payload(), b"\"a\",b,\r\nc\"d");
+    }
+}
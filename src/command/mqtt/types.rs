@@ -0,0 +1,49 @@
+//! Argument and parameter types used by MQTT Commands and Responses
+use atat::atat_derive::AtatEnum;
+use heapless::String;
+
+use crate::command::device_data_security::types::SecurityProfileId;
+
+#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[at_arg(u8)]
+pub enum MqttParam {
+    /// • 0: MQTT client ID; the maximum length is 256 characters. The
+    /// factory-programmed value is based on the IMEI.
+    #[at_arg(value = 0)]
+    ClientId(String<64>),
+    /// • 1: local port number of the MQTT client, in range 1-65535.
+    #[at_arg(value = 1)]
+    LocalPort(u16),
+    /// • 2: MQTT server name and optional port.
+    #[at_arg(value = 2)]
+    ServerName(String<128>, Option<u16>),
+    /// • 3: MQTT server IP address and optional port.
+    #[at_arg(value = 3)]
+    ServerIp(String<45>, Option<u16>),
+    /// • 4: username and optional password.
+    #[at_arg(value = 4)]
+    Credentials(String<64>, Option<String<64>>),
+    /// • 10: inactivity timeout (keep alive) in seconds. 0 (factory-programmed
+    /// value) means no timeout.
+    #[at_arg(value = 10)]
+    InactivityTimeout(u16),
+    /// • 11: MQTT secure option (TLS) usage; <param2> optionally selects the
+    /// USECMNG security profile (0-4) used for the connection.
+    #[at_arg(value = 11)]
+    Secure(bool, Option<SecurityProfileId>),
+    /// • 12: clean session flag.
+    #[at_arg(value = 12)]
+    CleanSession(bool),
+}
+
+/// MQTT quality of service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MqttQos {
+    /// • 0 (default value): at most once delivery
+    AtMostOnce = 0,
+    /// • 1: at least once delivery
+    AtLeastOnce = 1,
+    /// • 2: exactly once delivery
+    ExactlyOnce = 2,
+}
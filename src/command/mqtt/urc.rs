@@ -0,0 +1,34 @@
+//! Unsolicited responses for MQTT Commands
+use atat::atat_derive::AtatResp;
+use heapless::String;
+
+/// 33.3 MQTT command result +UUMQTTC
+///
+/// Reports the final result of an MQTT action:
+/// - `0`: logout / disconnect. Also sent when the broker closes the connection.
+/// - `1`: login. <result> is the CONNACK return code, 0 meaning connection
+///   accepted.
+/// - `4`: subscribe, with <qos> and <topic> of the subscription. <result> 1
+///   meaning success.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttCommandResult {
+    #[at_arg(position = 0)]
+    pub op_code: u8,
+    #[at_arg(position = 1)]
+    pub result: u8,
+    #[at_arg(position = 2)]
+    pub qos: Option<u8>,
+    #[at_arg(position = 3)]
+    pub topic: Option<String<256>>,
+}
+
+/// 33.3 MQTT unread messages +UUMQTTCM
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttUnreadMessages {
+    #[at_arg(position = 0)]
+    pub op_code: u8,
+    #[at_arg(position = 1)]
+    pub unread: u16,
+}
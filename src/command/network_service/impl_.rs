@@ -1,5 +1,11 @@
-use super::types::Error;
-use super::types::NetworkRegistrationStat;
+use super::types::{
+    AvailableOperator, CellEnvironment, CellMeasurement, Error, GsmCellEnvironment,
+    LteCellEnvironment, NeighborCell, NetworkRegistrationStat, OperatorList, OperatorStatus,
+    RatAct, ServingCell, MAX_SCANNED_OPERATORS,
+};
+use crate::command::records::Record;
+use heapless::{String, Vec};
+use serde::{de, Deserialize, Deserializer};
 
 impl NetworkRegistrationStat {
     #[must_use]
@@ -14,3 +20,444 @@ impl NetworkRegistrationStat {
         }
     }
 }
+
+impl From<u8> for OperatorStatus {
+    fn from(v: u8) -> Self {
+        match v {
+            1 => Self::Available,
+            2 => Self::Current,
+            3 => Self::Forbidden,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<u8> for RatAct {
+    fn from(v: u8) -> Self {
+        match v {
+            0 => Self::Gsm,
+            1 => Self::GsmCompact,
+            2 => Self::Utran,
+            3 => Self::GsmGprsEdge,
+            4 => Self::UtranHspda,
+            5 => Self::UtranHsupa,
+            6 => Self::UtranHspdaHsupa,
+            7 => Self::Lte,
+            8 => Self::EcGsmIot,
+            9 => Self::Eutran,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Find the index of the first `needle` in `input` that is not inside a
+/// quoted string.
+fn find_unquoted(input: &[u8], needle: u8) -> Option<usize> {
+    let mut quoted = false;
+    input.iter().position(|&c| {
+        if c == b'"' {
+            quoted = !quoted;
+        }
+        !quoted && c == needle
+    })
+}
+
+fn unquote(field: &[u8]) -> Option<&str> {
+    let field = field.trim_ascii();
+    let inner = field.strip_prefix(b"\"")?.strip_suffix(b"\"")?;
+    core::str::from_utf8(inner).ok()
+}
+
+/// Copy as much of `s` as fits, cutting on a char boundary
+fn truncated<const N: usize>(s: &str) -> String<N> {
+    let mut out = String::new();
+    for c in s.chars() {
+        if out.push(c).is_err() {
+            break;
+        }
+    }
+    out
+}
+
+fn parse_number(field: &[u8]) -> Option<u8> {
+    core::str::from_utf8(field.trim_ascii()).ok()?.parse().ok()
+}
+
+/// Parse a single `<stat>,"<long>","<short>","<numeric>"[,<AcT>]` tuple.
+/// Returns `None` for the trailing tuples listing the supported modes and
+/// formats, which are recognizable by the unquoted second element.
+fn parse_operator(mut tuple: &[u8]) -> Option<AvailableOperator> {
+    let mut fields: [&[u8]; 5] = [&[]; 5];
+    let mut count = 0;
+    while count < fields.len() {
+        match find_unquoted(tuple, b',') {
+            Some(i) => {
+                fields[count] = &tuple[..i];
+                tuple = &tuple[i + 1..];
+            }
+            None => {
+                fields[count] = tuple;
+                tuple = &[];
+            }
+        }
+        count += 1;
+        if tuple.is_empty() {
+            break;
+        }
+    }
+
+    if count < 4 {
+        return None;
+    }
+
+    Some(AvailableOperator {
+        stat: parse_number(fields[0])?.into(),
+        long_name: truncated(unquote(fields[1])?),
+        short_name: truncated(unquote(fields[2])?),
+        numeric: truncated(unquote(fields[3])?),
+        act: if count == 5 {
+            parse_number(fields[4]).map(RatAct::from)
+        } else {
+            None
+        },
+    })
+}
+
+impl OperatorList {
+    /// Parse the raw information response of `AT+COPS=?`, eg.
+    /// `(2,"Operator, Inc","OP","23802",7),(1,"Other","OT","23801"),,(0,1,2,3,4),(0,1,2)`
+    pub fn parse(mut input: &[u8]) -> Self {
+        let mut operators = Vec::new();
+
+        if let Some(rest) = input.strip_prefix(b"+COPS:") {
+            input = rest;
+        }
+
+        loop {
+            input = input.trim_ascii_start();
+            match input.first() {
+                Some(b'(') => {}
+                Some(b',') if input.get(1) != Some(&b',') => {
+                    input = &input[1..];
+                    continue;
+                }
+                // `,,` separates the operator list from the supported modes
+                _ => break,
+            }
+
+            let Some(end) = find_unquoted(input, b')') else {
+                break;
+            };
+            let Some(operator) = parse_operator(&input[1..end]) else {
+                break;
+            };
+            input = &input[end + 1..];
+
+            if operators.push(operator).is_err() {
+                warn!(
+                    "Operator scan returned more than {} operators",
+                    MAX_SCANNED_OPERATORS
+                );
+                break;
+            }
+        }
+
+        Self(operators)
+    }
+}
+
+impl<'de> Deserialize<'de> for OperatorList {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OperatorListVisitor;
+
+        impl<'de> de::Visitor<'de> for OperatorListVisitor {
+            type Value = OperatorList;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a +COPS=? operator list")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(OperatorList::parse(value))
+            }
+        }
+
+        deserializer.deserialize_bytes(OperatorListVisitor)
+    }
+}
+
+fn strip_quotes(field: &[u8]) -> &[u8] {
+    field
+        .strip_prefix(b"\"")
+        .and_then(|f| f.strip_suffix(b"\""))
+        .unwrap_or(field)
+}
+
+fn parse_hex(field: &[u8]) -> Option<u32> {
+    u32::from_str_radix(core::str::from_utf8(field).ok()?, 16).ok()
+}
+
+fn parse_decimal<T: core::str::FromStr>(field: &[u8]) -> Option<T> {
+    core::str::from_utf8(field).ok()?.parse().ok()
+}
+
+/// RSRP is reported as 0-97 (3GPP TS 36.133), 255 if not known
+fn rsrp_dbm(v: u8) -> Option<i16> {
+    (v <= 97).then(|| i16::from(v) - 141)
+}
+
+/// RSRQ is reported as 0-34 (3GPP TS 36.133), 255 if not known
+fn rsrq_db(v: u8) -> Option<f32> {
+    (v <= 34).then(|| f32::from(v) / 2.0 - 20.0)
+}
+
+impl CellEnvironment {
+    /// Parse the raw information response of `AT+UCGED?`. The short form
+    /// reports the RAT, service state, MCC and MNC on the line following the
+    /// mode, and the serving cell on the line after that, eg. for 4G:
+    ///
+    /// `2\r\n4,0,001,01\r\n2525,5,25,50,2b67,69f6bd2,111,00000000,ffff,ff,67,19,0.00,...`
+    ///
+    /// The serving cell line has a different number of fields per RAT, so the
+    /// fields are interpreted according to the reported RAT.
+    pub fn parse(input: &[u8]) -> Self {
+        let input = input.strip_prefix(b"+UCGED:").unwrap_or(input);
+        let mut fields = input
+            .split(|&c| c == b',' || c == b'\r' || c == b'\n')
+            .map(<[u8]>::trim_ascii)
+            .filter(|f| !f.is_empty());
+
+        let mut env = Self {
+            mode: fields.next().and_then(parse_decimal).unwrap_or_default(),
+            rat: None,
+            mcc: String::new(),
+            mnc: String::new(),
+            serving_cell: ServingCell::Unknown,
+        };
+
+        if env.mode != 2 {
+            return env;
+        }
+
+        env.rat = fields.next().and_then(parse_decimal);
+        let _svc = fields.next();
+        env.mcc = fields
+            .next()
+            .and_then(|f| core::str::from_utf8(f).ok())
+            .map(truncated)
+            .unwrap_or_default();
+        env.mnc = fields
+            .next()
+            .and_then(|f| core::str::from_utf8(f).ok())
+            .map(truncated)
+            .unwrap_or_default();
+
+        let mut cell: [&[u8]; 13] = [&[]; 13];
+        for (slot, field) in cell.iter_mut().zip(fields) {
+            *slot = field;
+        }
+
+        env.serving_cell = match env.rat {
+            Some(4 | 6 | 7) => {
+                Self::parse_lte(&cell).map_or(ServingCell::Unknown, ServingCell::Lte)
+            }
+            Some(2) => Self::parse_gsm(&cell).map_or(ServingCell::Unknown, ServingCell::Gsm),
+            _ => ServingCell::Unknown,
+        };
+
+        env
+    }
+
+    /// `<EARFCN>,<Lband>,<ul_BW>,<dl_BW>,<TAC>,<LcellId>,<P-CID>,<mTmsi>,
+    /// <mmeGrId>,<mmeCode>,<RSRP>,<RSRQ>,<Lsinr>,...`
+    fn parse_lte(cell: &[&[u8]; 13]) -> Option<LteCellEnvironment> {
+        Some(LteCellEnvironment {
+            earfcn: parse_decimal(cell[0])?,
+            band: parse_decimal(cell[1])?,
+            tac: parse_hex(cell[4])?,
+            cell_id: parse_hex(cell[5])?,
+            pci: parse_decimal(cell[6])?,
+            rsrp_dbm: parse_decimal(cell[10]).and_then(rsrp_dbm),
+            rsrq_db: parse_decimal(cell[11]).and_then(rsrq_db),
+            sinr_db: parse_decimal(cell[12]),
+        })
+    }
+
+    /// `<arfcn>,<band1900>,<GcellId>,<BSIC>,<Glac>,<Grac>,<Grxlev>,...`
+    fn parse_gsm(cell: &[&[u8]; 13]) -> Option<GsmCellEnvironment> {
+        Some(GsmCellEnvironment {
+            arfcn: parse_decimal(cell[0])?,
+            cell_id: parse_hex(cell[2])?,
+            bsic: parse_hex(cell[3])?.try_into().ok()?,
+            lac: parse_hex(cell[4])?,
+            rxlev: parse_decimal::<u8>(cell[6]).filter(|&v| v <= 63),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for CellEnvironment {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct CellEnvironmentVisitor;
+
+        impl<'de> de::Visitor<'de> for CellEnvironmentVisitor {
+            type Value = CellEnvironment;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a +UCGED cell environment")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(CellEnvironment::parse(value))
+            }
+        }
+
+        deserializer.deserialize_bytes(CellEnvironmentVisitor)
+    }
+}
+
+impl Record for NeighborCell {
+    const PREFIX: &'static [u8] = b"+UCELLINFO:";
+
+    /// `<mode>,<type>,<MCC>,<MNC>,<LAC/TAC>,<CI>,...` with the remaining
+    /// fields depending on `<type>`. Serving cells (even types) are skipped.
+    fn parse(line: &[u8]) -> Option<Self> {
+        let mut fields: [&[u8]; 11] = [&[]; 11];
+        for (slot, field) in fields.iter_mut().zip(line.split(|&c| c == b',')) {
+            *slot = strip_quotes(field.trim_ascii());
+        }
+
+        let measurement = match parse_decimal::<u8>(fields[1])? {
+            // 2G: <BSIC>,<arfcn>,<RxLev>
+            1 => CellMeasurement::Gsm {
+                bsic: parse_hex(fields[6])?.try_into().ok()?,
+                rxlev: parse_decimal(fields[8])?,
+            },
+            // 3G: <DL frequency>,<UL frequency>,<SC>,<RSCP lev>,<ecn0_lev>
+            3 => CellMeasurement::Umts {
+                scrambling_code: parse_decimal(fields[8])?,
+                rscp_lev: parse_decimal(fields[9])?,
+                ecn0_lev: parse_decimal(fields[10])?,
+            },
+            // 4G: <EARFCN>,<PhysCellID>,<RSRP>,<RSRQ>
+            5 => CellMeasurement::Lte {
+                pci: parse_decimal(fields[7])?,
+                rsrp_dbm: parse_decimal(fields[8]).and_then(rsrp_dbm),
+                rsrq_db: parse_decimal(fields[9]).and_then(rsrq_db),
+            },
+            _ => return None,
+        };
+
+        let channel = match measurement {
+            CellMeasurement::Gsm { .. } => parse_decimal(fields[7])?,
+            _ => parse_decimal(fields[6])?,
+        };
+
+        Some(Self {
+            mcc: core::str::from_utf8(fields[2]).ok().map(truncated)?,
+            mnc: core::str::from_utf8(fields[3]).ok().map(truncated)?,
+            area_code: parse_hex(fields[4])?,
+            cell_id: parse_hex(fields[5])?,
+            channel,
+            measurement,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::records::Records;
+
+    #[test]
+    fn parse_operator_scan() {
+        let list = OperatorList::parse(
+            b"(2,\"Operator, Inc\",\"OP\",\"23802\",7),(1,\"Other\",\"OT\",\"23801\"),(3,\"Blocked\",\"BL\",\"23820\",0),,(0,1,2,3,4),(0,1,2)",
+        );
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].stat, OperatorStatus::Current);
+        assert_eq!(list[0].long_name.as_str(), "Operator, Inc");
+        assert_eq!(list[0].short_name.as_str(), "OP");
+        assert_eq!(list[0].numeric.as_str(), "23802");
+        assert_eq!(list[0].act, Some(RatAct::Lte));
+        assert_eq!(list[1].stat, OperatorStatus::Available);
+        assert_eq!(list[1].act, None);
+        assert_eq!(list[2].stat, OperatorStatus::Forbidden);
+        assert_eq!(list[2].act, Some(RatAct::Gsm));
+    }
+
+    #[test]
+    fn parse_empty_operator_scan() {
+        assert!(OperatorList::parse(b"+COPS: ,,(0,1,2,3,4),(0,1,2)").is_empty());
+    }
+
+    #[test]
+    fn parse_cell_environment() {
+        let env = CellEnvironment::parse(
+            b"2\r\n4,0,001,01\r\n2525,5,25,50,2b67,69f6bd2,111,00000000,ffff,ff,67,19,13.50,255,255,255,67,11,255,0,255,255,0,0",
+        );
+        assert_eq!(env.rat, Some(4));
+        assert_eq!(env.mcc.as_str(), "001");
+        assert_eq!(env.mnc.as_str(), "01");
+        let ServingCell::Lte(cell) = env.serving_cell else {
+            panic!("expected LTE serving cell");
+        };
+        assert_eq!(cell.earfcn, 2525);
+        assert_eq!(cell.tac, 0x2b67);
+        assert_eq!(cell.cell_id, 0x69f6bd2);
+        assert_eq!(cell.pci, 111);
+        assert_eq!(cell.rsrp_dbm, Some(-74));
+        assert_eq!(cell.rsrq_db, Some(-10.5));
+        assert_eq!(cell.sinr_db, Some(13.5));
+
+        let env = CellEnvironment::parse(b"2\r\n2,4,001,01\r\n810,1,1234,0a,52bb,02,35,0,255,1");
+        let ServingCell::Gsm(cell) = env.serving_cell else {
+            panic!("expected 2G serving cell");
+        };
+        assert_eq!(cell.arfcn, 810);
+        assert_eq!(cell.cell_id, 0x1234);
+        assert_eq!(cell.bsic, 0x0a);
+        assert_eq!(cell.lac, 0x52bb);
+        assert_eq!(cell.rxlev, Some(35));
+    }
+
+    #[test]
+    fn parse_neighbor_cells() {
+        let cells = Records::<NeighborCell, 4>::parse(
+            b"0,4,238,02,61EF,1A2B,1300,125,67,19\r\n+UCELLINFO: 0,5,238,02,61EF,1A2C,1300,126,60,15\r\n+UCELLINFO: 0,1,238,01,\"00A1\",\"1234\",0a,810,35",
+        );
+        assert_eq!(cells.len(), 2);
+        assert_eq!(cells[0].cell_id, 0x1A2C);
+        assert_eq!(cells[0].channel, 1300);
+        assert_eq!(
+            cells[0].measurement,
+            CellMeasurement::Lte {
+                pci: 126,
+                rsrp_dbm: Some(-81),
+                rsrq_db: Some(-12.5),
+            }
+        );
+        assert_eq!(cells[1].mnc.as_str(), "01");
+        assert_eq!(cells[1].area_code, 0xA1);
+        assert_eq!(cells[1].channel, 810);
+        assert_eq!(
+            cells[1].measurement,
+            CellMeasurement::Gsm {
+                bsic: 0x0a,
+                rxlev: 35
+            }
+        );
+    }
+}
@@ -8,10 +8,23 @@ pub mod urc;
 use super::NoResponse;
 use atat::atat_derive::AtatCmd;
 use responses::{
-    NetworkRegistrationStatus, OperatorSelection, RadioAccessTechnology, SignalQuality,
+    AvailableOperators, CellEnvironmentResponse, CellInfo, NetworkRegistrationStatus,
+    OperatorSelection, RadioAccessTechnology, SignalQuality, SubscriberNumber,
 };
 use types::{NetworkRegistrationStat, NetworkRegistrationUrcConfig, OperatorSelectionMode};
 
+/// Maximum number of MSISDNs kept from a +CNUM response
+pub const MAX_SUBSCRIBER_NUMBERS: usize = 4;
+
+/// 7.1 Subscriber number +CNUM
+///
+/// Returns the MSISDNs related to the subscriber, one line each, as stored
+/// on the SIM. There is no information text response if the SIM has no
+/// MSISDN stored, and some SIMs answer with +CME ERROR: not found instead.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CNUM", heapless::Vec<SubscriberNumber, MAX_SUBSCRIBER_NUMBERS>)]
+pub struct GetSubscriberNumber;
+
 /// 7.4 Extended signal quality +CESQ
 ///
 /// Returns the radio signal strength <`signal_power`> and <qual> from the MT.
@@ -44,19 +57,34 @@ use types::{NetworkRegistrationStat, NetworkRegistrationUrcConfig, OperatorSelec
 pub struct GetSignalQuality;
 
 /// 7.5 Operator selection +COPS
+///
+/// With a manual `mode`, `oper` selects the operator in the given `format`
+/// (2 for numeric). The command returns once the module has registered, or
+/// failed to register, to the selected operator.
 #[derive(Clone, AtatCmd)]
 #[at_cmd("+COPS", NoResponse, attempts = 1, timeout_ms = 180000)]
-pub struct SetOperatorSelection {
+pub struct SetOperatorSelection<'a> {
     #[at_arg(position = 0)]
     pub mode: OperatorSelectionMode,
     #[at_arg(position = 1)]
     pub format: Option<u8>,
+    #[at_arg(position = 2, len = 24)]
+    pub oper: Option<&'a str>,
 }
 
 #[derive(Clone, AtatCmd)]
 #[at_cmd("+COPS?", OperatorSelection, attempts = 1, timeout_ms = 180000)]
 pub struct GetOperatorSelection;
 
+/// 7.5 Operator selection +COPS=?
+///
+/// Scans for the available networks. The scan can take several minutes
+/// depending on the number of supported bands and RATs, during which no other
+/// AT command is answered.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+COPS=?", AvailableOperators, attempts = 1, timeout_ms = 360000)]
+pub struct ScanOperators;
+
 /// 7.8 Radio Access Technology (RAT) selection +URAT Forces the selection of
 /// the Radio Access Technology (RAT) in the protocol stack. On the subsequent
 /// network registration (+COPS, +CGATT) the selected RAT is used.
@@ -153,6 +181,56 @@ pub struct GetNetworkRegistrationStatus;
 /// returns also the information on the neighbor cells.
 #[derive(Clone, AtatCmd)]
 #[at_cmd("+UCGED", NoResponse)]
-pub struct SetChannelAndNetworkEnvDesc {
+pub struct SetCellEnvironmentReporting {
     pub mode: u8,
 }
+
+#[deprecated(note = "renamed to `SetCellEnvironmentReporting`")]
+pub type SetChannelAndNetworkEnvDesc = SetCellEnvironmentReporting;
+
+/// 7.15 Channel and network environment description +UCGED
+///
+/// Reads the serving cell information collected in the reporting mode set
+/// with [`SetCellEnvironmentReporting`]. Only the short form reporting
+/// (<mode>=2) is parsed into the serving cell details.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UCGED?", CellEnvironmentResponse)]
+pub struct GetCellEnvironment;
+
+/// 7.17 Cell environment description +UCELLINFO
+///
+/// Reports the serving and neighbor cells found by the protocol stack, one
+/// cell per line. Only the neighbor cells are kept in the response.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UCELLINFO?", CellInfo)]
+pub struct GetCellInfo;
+
+#[cfg(test)]
+mod tests {
+    use atat::AtatCmd;
+
+    use super::*;
+
+    #[test]
+    fn parse_subscriber_numbers() {
+        let numbers = GetSubscriberNumber
+            .parse(Ok(b"+CNUM: \"Own\",\"+4512345678\",145"))
+            .unwrap();
+        assert_eq!(numbers.len(), 1);
+        assert_eq!(numbers[0].alpha.as_deref(), Some("Own"));
+        assert_eq!(numbers[0].number.as_str(), "+4512345678");
+        assert_eq!(numbers[0].number_type, 145);
+
+        let numbers = GetSubscriberNumber
+            .parse(Ok(
+                b"+CNUM: ,\"+4512345678\",145\r\n+CNUM: \"Data\",\"12345679\",129",
+            ))
+            .unwrap();
+        assert_eq!(numbers.len(), 2);
+        assert_eq!(numbers[0].alpha, None);
+        assert_eq!(numbers[1].number.as_str(), "12345679");
+        assert_eq!(numbers[1].number_type, 129);
+
+        assert!(GetSubscriberNumber.parse(Ok(b"")).unwrap().is_empty());
+    }
+}
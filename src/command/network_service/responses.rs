@@ -1,13 +1,15 @@
 //! Responses for Network service Commands
 use super::types::{
-    NetworkRegistrationStat, NetworkRegistrationUrcConfig, OperatorNameFormat,
-    OperatorSelectionMode, RadioAccessTechnologySelected, RatAct,
+    CellEnvironment, NeighborCell, NetworkRegistrationStat, NetworkRegistrationUrcConfig,
+    OperatorList, OperatorNameFormat, OperatorSelectionMode, RadioAccessTechnologySelected, RatAct,
+    MAX_NEIGHBOR_CELLS,
 };
+use crate::command::records::Records;
 use atat::atat_derive::AtatResp;
 use heapless::String;
 
 /// 7.4 Extended signal quality +CESQ
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SignalQuality {
     #[at_arg(position = 0)]
@@ -25,7 +27,7 @@ pub struct SignalQuality {
 }
 
 /// 7.5 Operator selection +COPS
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct OperatorSelection {
     #[at_arg(position = 0)]
@@ -36,8 +38,16 @@ pub struct OperatorSelection {
     pub act: Option<RatAct>,
 }
 
+/// 7.5 Operator selection +COPS=?
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AvailableOperators {
+    #[at_arg(position = 0)]
+    pub operators: OperatorList,
+}
+
 /// 7.8 Radio Access Technology (RAT) selection +URAT
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct RadioAccessTechnology {
     #[at_arg(position = 0)]
@@ -45,7 +55,7 @@ pub struct RadioAccessTechnology {
 }
 
 /// 7.14 Network registration status +CREG
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NetworkRegistrationStatus {
     #[at_arg(position = 0)]
@@ -59,3 +69,33 @@ pub struct NetworkRegistrationStatus {
     #[at_arg(position = 4)]
     pub act_status: Option<u8>,
 }
+
+/// 7.15 Channel and network environment description +UCGED
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CellEnvironmentResponse {
+    #[at_arg(position = 0)]
+    pub environment: CellEnvironment,
+}
+
+/// 7.17 Cell environment description +UCELLINFO
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CellInfo {
+    #[at_arg(position = 0)]
+    pub neighbor_cells: Records<NeighborCell, MAX_NEIGHBOR_CELLS>,
+}
+
+/// 7.1 Subscriber number +CNUM
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SubscriberNumber {
+    /// Alphanumeric name of the number, often left empty
+    #[at_arg(position = 0)]
+    pub alpha: Option<String<32>>,
+    #[at_arg(position = 1)]
+    pub number: String<32>,
+    /// Type of address, 145 for international numbers
+    #[at_arg(position = 2)]
+    pub number_type: u8,
+}
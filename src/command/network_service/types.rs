@@ -1,6 +1,6 @@
 //! Argument and parameter types used by Network service Commands and Responses
 use atat::atat_derive::AtatEnum;
-use heapless::String;
+use heapless::{String, Vec};
 
 /// Is used to chose whether the network selection is automatically done by the
 /// MT or is forced by this command to the operator <oper> given in the format
@@ -57,7 +57,7 @@ pub enum RatAct {
     Unknown = 10,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum NetworkRegistrationUrcConfig {
     /// • 0 (default value and factory-programmed value): network registration URC disabled
@@ -192,3 +192,169 @@ pub enum Error {
     ActivationFailed,
     _Unknown,
 }
+
+/// Numeric PLMN identity, the MCC followed by a 2 or 3 digit MNC, eg.
+/// `"23802"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Plmn(String<6>);
+
+impl Plmn {
+    /// Returns `None` unless `numeric` consists of 5 or 6 digits.
+    pub fn new(numeric: &str) -> Option<Self> {
+        if !(5..=6).contains(&numeric.len()) || !numeric.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        String::try_from(numeric).ok().map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Maximum number of operators kept from a single +COPS=? scan
+pub const MAX_SCANNED_OPERATORS: usize = 16;
+
+/// Availability of an operator reported by the +COPS=? network scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OperatorStatus {
+    /// • 0: unknown
+    Unknown = 0,
+    /// • 1: available
+    Available = 1,
+    /// • 2: current
+    Current = 2,
+    /// • 3: forbidden
+    Forbidden = 3,
+}
+
+/// Single `(<stat>,<long_oper>,<short_oper>,<numeric_oper>[,<AcT>])` entry
+/// of the +COPS=? network scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AvailableOperator {
+    pub stat: OperatorStatus,
+    pub long_name: String<32>,
+    pub short_name: String<16>,
+    /// Numeric PLMN, MCC followed by a 2 or 3 digit MNC
+    pub numeric: String<6>,
+    /// Not reported by all modules
+    pub act: Option<RatAct>,
+}
+
+/// Operators found by the +COPS=? network scan. The trailing lists of
+/// supported modes and formats are discarded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OperatorList(pub Vec<AvailableOperator, MAX_SCANNED_OPERATORS>);
+
+impl AvailableOperator {
+    pub fn plmn(&self) -> Option<Plmn> {
+        Plmn::new(&self.numeric)
+    }
+}
+
+impl core::ops::Deref for OperatorList {
+    type Target = [AvailableOperator];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Serving cell details of a 4G (LTE, LTE Cat M1 or NB-IoT) cell, as reported
+/// by +UCGED short form reporting
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LteCellEnvironment {
+    pub earfcn: u32,
+    pub band: u8,
+    /// Tracking area code
+    pub tac: u32,
+    /// E-UTRAN cell identity
+    pub cell_id: u32,
+    /// Physical cell ID
+    pub pci: u16,
+    pub rsrp_dbm: Option<i16>,
+    pub rsrq_db: Option<f32>,
+    pub sinr_db: Option<f32>,
+}
+
+/// Serving cell details of a 2G cell, as reported by +UCGED short form
+/// reporting
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GsmCellEnvironment {
+    pub arfcn: u16,
+    pub cell_id: u32,
+    pub bsic: u8,
+    /// Location area code
+    pub lac: u32,
+    /// Received signal level, 0-63
+    pub rxlev: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ServingCell {
+    Lte(LteCellEnvironment),
+    Gsm(GsmCellEnvironment),
+    /// No serving cell, or a RAT without parsed details
+    Unknown,
+}
+
+/// Channel and network environment reported by +UCGED
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CellEnvironment {
+    /// Reporting mode, the serving cell is only parsed in mode 2
+    pub mode: u8,
+    /// • 2: 2G
+    /// • 3: 3G
+    /// • 4: 4G
+    /// • 5: unknown
+    /// • 6: LTE Cat M1
+    /// • 7: NB1
+    pub rat: Option<u8>,
+    pub mcc: String<3>,
+    pub mnc: String<3>,
+    pub serving_cell: ServingCell,
+}
+
+/// Maximum number of neighbor cells kept from a single +UCELLINFO report
+pub const MAX_NEIGHBOR_CELLS: usize = 16;
+
+/// Signal measurement of a neighbor cell, depending on its RAT
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CellMeasurement {
+    /// Received signal level, 0-63
+    Gsm { bsic: u8, rxlev: u8 },
+    /// RSCP and Ec/N0 levels as reported (3GPP TS 25.133)
+    Umts {
+        scrambling_code: u16,
+        rscp_lev: u8,
+        ecn0_lev: u8,
+    },
+    Lte {
+        pci: u16,
+        rsrp_dbm: Option<i16>,
+        rsrq_db: Option<f32>,
+    },
+}
+
+/// Neighbor cell reported by +UCELLINFO
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeighborCell {
+    pub mcc: String<3>,
+    pub mnc: String<3>,
+    /// Location area code, or tracking area code for 4G cells
+    pub area_code: u32,
+    pub cell_id: u32,
+    /// ARFCN, UARFCN or EARFCN, depending on the RAT
+    pub channel: u32,
+    pub measurement: CellMeasurement,
+}
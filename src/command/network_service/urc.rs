@@ -4,7 +4,12 @@ use atat::atat_derive::AtatResp;
 use heapless::String;
 
 /// 7.14 Network registration status +CREG
-#[derive(Debug, Clone, AtatResp)]
+///
+/// The fields after `stat` are reported depending on the configured
+/// [`NetworkRegistrationUrcConfig`](super::types::NetworkRegistrationUrcConfig),
+/// the reject cause with n=3.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct NetworkRegistration {
     #[at_arg(position = 1)]
     pub stat: NetworkRegistrationStat,
@@ -14,4 +19,8 @@ pub struct NetworkRegistration {
     pub ci: Option<String<8>>,
     #[at_arg(position = 4)]
     pub act_status: Option<u8>,
+    #[at_arg(position = 5)]
+    pub cause_type: Option<u8>,
+    #[at_arg(position = 6)]
+    pub reject_cause: Option<u8>,
 }
@@ -2,7 +2,7 @@
 use atat::atat_derive::AtatResp;
 
 /// 34.4 Configure port filtering for embedded applications +UEMBPF
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EmbeddedPortFiltering {
     #[at_arg(position = 0)]
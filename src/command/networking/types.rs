@@ -5,7 +5,7 @@ use atat::AtatLen;
 use serde::{Serialize, Serializer};
 
 /// Port filtering enable/disable
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum EmbeddedPortFilteringMode {
     /// 0: disable. The configured range is removed.
@@ -14,6 +14,17 @@ pub enum EmbeddedPortFilteringMode {
     Enable(u16, u16),
 }
 
+impl EmbeddedPortFilteringMode {
+    /// Whether traffic to the local `port` is directed to the embedded
+    /// applications while a dial-up connection is active.
+    pub fn contains(&self, port: u16) -> bool {
+        match *self {
+            Self::Disable => false,
+            Self::Enable(start, end) => (start..=end).contains(&port),
+        }
+    }
+}
+
 impl AtatLen for EmbeddedPortFilteringMode {
     const LEN: usize = 20;
     const ESCAPED_LEN: usize = 20;
@@ -72,4 +83,13 @@ mod test {
 
         assert_eq!(&buf[..s], b"1,\"6000-6200\"")
     }
+
+    #[test]
+    fn embedded_port_filtering_range() {
+        let mode = EmbeddedPortFilteringMode::Enable(6000, 6200);
+        assert!(mode.contains(6000));
+        assert!(mode.contains(6200));
+        assert!(!mode.contains(5999));
+        assert!(!EmbeddedPortFilteringMode::Disable.contains(6000));
+    }
 }
@@ -26,9 +26,9 @@ pub mod types;
 pub mod urc;
 use atat::atat_derive::AtatCmd;
 use responses::{
-    EPSNetworkRegistrationStatus, ExtendedPSNetworkRegistrationStatus, GPRSAttached,
-    GPRSNetworkRegistrationStatus, PDPContextState, PacketSwitchedConfig,
-    PacketSwitchedNetworkData,
+    DataCounters, EPSNetworkRegistrationStatus, ExtendedPSNetworkRegistrationStatus, GPRSAttached,
+    GPRSNetworkRegistrationStatus, PDPAddress, PDPContextDynamicParameters, PDPContextState,
+    PacketSwitchedConfig, PacketSwitchedNetworkAddress, PacketSwitchedNetworkData,
 };
 use types::{
     AuthenticationType, ContextId, EPSNetworkRegistrationUrcConfig,
@@ -172,6 +172,19 @@ pub struct GetPacketSwitchedNetworkData {
     pub param: PacketSwitchedNetworkDataParam,
 }
 
+/// 18.9 Get Packet switched network-assigned data +UPSND
+///
+/// Same as [`GetPacketSwitchedNetworkData`], for the parameters returning an
+/// address: `IPAddress`, `DNS1` and `DNS2`.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UPSND", PacketSwitchedNetworkAddress)]
+pub struct GetPacketSwitchedNetworkAddress {
+    #[at_arg(position = 0)]
+    pub profile_id: ProfileId,
+    #[at_arg(position = 1)]
+    pub param: PacketSwitchedNetworkDataParam,
+}
+
 /// 18.14 Set GPRS attach or detach +CGATT
 ///
 /// Register (attach) the MT to, or deregister (detach) the MT from the GPRS
@@ -271,6 +284,30 @@ pub struct SetPDPContextState {
 #[at_cmd("+CGACT?", heapless::Vec<PDPContextState, 7>, attempts = 1, timeout_ms = 150000, abortable = true)]
 pub struct GetPDPContextState;
 
+/// PDP context read dynamic parameters +CGCONTRDP
+///
+/// Returns the parameters negotiated with the network for the active PDP
+/// context `cid`: bearer id, APN, local address and subnet mask, gateway and
+/// DNS servers.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CGCONTRDP", heapless::Vec<PDPContextDynamicParameters, 2>)]
+pub struct GetPDPContextReadDynamicParams {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+}
+
+/// Show PDP address +CGPADDR
+///
+/// Returns the addresses assigned to the context `cid`, one for a single stack
+/// context and two for a dual stack one. They may change after a tracking
+/// area update or a reactivation of the context.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CGPADDR", PDPAddress)]
+pub struct GetPDPAddress {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+}
+
 /// 18.21 Enter PPP state/GPRS dial-up D*
 ///
 /// The V.24 dial command "D", similar to the command with the syntax
@@ -512,3 +549,91 @@ pub struct SetAuthParameters<'a> {
     )]
     pub password: &'a str,
 }
+
+/// PDP context authentication parameters +CGAUTH
+///
+/// The 3GPP counterpart of [`SetAuthParameters`], which newer firmwares of
+/// e.g. LENA-R8 and LARA-R6 provide instead. The authentication parameters
+/// are sent during the context activation like those of +UAUTHREQ.
+///
+/// **NOTES:**
+/// - Automatic selection of the authentication type
+///   ([`AuthenticationType::Auto`]) is not supported by all firmwares.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CGAUTH", NoResponse)]
+pub struct SetPDPContextAuthentication<'a> {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+    #[at_arg(position = 1)]
+    pub auth_type: AuthenticationType,
+    #[at_arg(position = 2, len = 64)]
+    pub username: &'a str,
+    #[at_arg(position = 3, len = 64)]
+    pub password: &'a str,
+}
+
+/// 18.30 GPRS data counters +UGCNTRD
+///
+/// Reads the sent and received byte counters of all active PDP contexts, one
+/// line per context.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGCNTRD", heapless::Vec<DataCounters, 8>)]
+pub struct GetDataCounters;
+
+/// 18.31 Set/reset GPRS data counters +UGCNTSET
+///
+/// Sets the total sent and received byte counters of the PDP context `cid`.
+/// Setting both to zero resets the counters.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UGCNTSET", NoResponse)]
+pub struct SetDataCounters {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+    #[at_arg(position = 1)]
+    pub total_bytes_sent: u64,
+    #[at_arg(position = 2)]
+    pub total_bytes_received: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use atat::AtatCmd;
+
+    use super::*;
+
+    #[test]
+    fn serialize_auth_parameters() {
+        let mut buf = [0u8; 64];
+
+        let cmd = SetPDPContextAuthentication {
+            cid: ContextId(1),
+            auth_type: AuthenticationType::PAP,
+            username: "user",
+            password: "pass",
+        };
+        let len = cmd.write(&mut buf);
+        assert_eq!(&buf[..len], b"AT+CGAUTH=1,1,\"user\",\"pass\"\r");
+
+        let cmd = SetAuthParameters {
+            cid: ContextId(1),
+            auth_type: AuthenticationType::CHAP,
+            username: "user",
+            password: "pass",
+        };
+        let len = cmd.write(&mut buf);
+        #[cfg(not(any(
+            feature = "sara-r410m",
+            feature = "sara-r412m",
+            feature = "sara-r422",
+            feature = "lara-r6"
+        )))]
+        assert_eq!(&buf[..len], b"AT+UAUTHREQ=1,2,\"user\",\"pass\"\r");
+        #[cfg(any(
+            feature = "sara-r410m",
+            feature = "sara-r412m",
+            feature = "sara-r422",
+            feature = "lara-r6"
+        ))]
+        assert_eq!(&buf[..len], b"AT+UAUTHREQ=1,2,\"pass\",\"user\"\r");
+    }
+}
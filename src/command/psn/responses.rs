@@ -3,13 +3,14 @@ use super::types::{
     ContextId, EPSNetworkRegistrationStat, EPSNetworkRegistrationUrcConfig,
     ExtendedPSNetworkRegistrationState, ExtendedPSNetworkRegistrationUrcConfig, GPRSAttachedState,
     GPRSNetworkRegistrationStat, GPRSNetworkRegistrationUrcConfig, PDPContextStatus,
-    PacketSwitchedNetworkDataParam, PacketSwitchedParam, ProfileId,
+    PacketSwitchedNetworkDataParam, PacketSwitchedParam, PdpContextInfo, ProfileId,
 };
 use crate::command::network_service::types::RatAct;
 use atat::atat_derive::AtatResp;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use heapless::String;
 
-#[derive(AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PDPContextDefinition {
     #[at_arg(position = 0)]
@@ -36,7 +37,8 @@ pub struct PDPContextDefinition {
 //  data connection using the internal IP stack and related AT commands for
 //  sockets. To set all the parameters of the PSD profile a set command for each
 //  parameter needs to be issued.
-#[derive(AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PacketSwitchedConfig {
     #[at_arg(position = 0)]
     pub profile_id: ProfileId,
@@ -48,7 +50,8 @@ pub struct PacketSwitchedConfig {
 /// (dynamic) network-assigned or network-negotiated value of the specified
 /// parameter for the active PDP context associated with the specified PSD
 /// profile.
-#[derive(Debug, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PacketSwitchedNetworkData {
     #[at_arg(position = 0)]
     pub profile: ProfileId,
@@ -58,6 +61,172 @@ pub struct PacketSwitchedNetworkData {
     pub param_tag: u8, // TODO: Create struct to contain
 }
 
+/// 18.9 Packet switched network-assigned data +UPSND, for the address
+/// parameters: IP address, DNS1 and DNS2
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketSwitchedNetworkAddress {
+    #[at_arg(position = 0)]
+    pub profile: ProfileId,
+    #[at_arg(position = 1)]
+    pub param: PacketSwitchedNetworkDataParam,
+    #[at_arg(position = 2)]
+    pub address: String<64>,
+}
+
+impl PacketSwitchedNetworkAddress {
+    pub fn address(&self) -> Option<IpAddr> {
+        parse_addr(&self.address)
+    }
+}
+
+/// Show PDP address +CGPADDR
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PDPAddress {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+    #[at_arg(position = 1)]
+    pub pdp_addr_1: Option<String<64>>,
+    /// The IPv6 address of a dual stack context
+    #[at_arg(position = 2)]
+    pub pdp_addr_2: Option<String<64>>,
+}
+
+impl PDPAddress {
+    /// The first address, the IPv4 one of a dual stack context.
+    pub fn address(&self) -> Option<IpAddr> {
+        parse_addr(self.pdp_addr_1.as_deref()?)
+    }
+
+    /// The IPv6 address of a dual stack context.
+    pub fn address_2(&self) -> Option<IpAddr> {
+        parse_addr(self.pdp_addr_2.as_deref()?)
+    }
+}
+
+/// PDP context read dynamic parameters +CGCONTRDP
+///
+/// One response is given per bearer, so dual stack contexts return one for
+/// the IPv4 and one for the IPv6 parameters. Addresses are given as strings
+/// in the format reported by the module, use the accessors to parse them.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PDPContextDynamicParameters {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+    #[at_arg(position = 1)]
+    pub bearer_id: u8,
+    #[at_arg(position = 2)]
+    pub apn: String<99>,
+    /// Local address and subnet mask, as a single dot separated string, eg.
+    /// `"10.1.2.3.255.255.255.0"`
+    #[at_arg(position = 3)]
+    pub local_addr_and_subnet_mask: Option<String<128>>,
+    #[at_arg(position = 4)]
+    pub gw_addr: Option<String<64>>,
+    #[at_arg(position = 5)]
+    pub dns_prim_addr: Option<String<64>>,
+    #[at_arg(position = 6)]
+    pub dns_sec_addr: Option<String<64>>,
+    #[at_arg(position = 7)]
+    pub p_cscf_prim_addr: Option<String<64>>,
+    #[at_arg(position = 8)]
+    pub p_cscf_sec_addr: Option<String<64>>,
+    #[at_arg(position = 9)]
+    pub im_cn_signalling_flag: Option<u8>,
+    #[at_arg(position = 10)]
+    pub lipa_indication: Option<u8>,
+}
+
+impl PDPContextDynamicParameters {
+    pub fn local_addr(&self) -> Option<IpAddr> {
+        split_addr_and_mask(self.local_addr_and_subnet_mask.as_deref()?).map(|(addr, _)| addr)
+    }
+
+    pub fn subnet_mask(&self) -> Option<IpAddr> {
+        split_addr_and_mask(self.local_addr_and_subnet_mask.as_deref()?).map(|(_, mask)| mask)
+    }
+
+    pub fn gateway(&self) -> Option<IpAddr> {
+        parse_addr(self.gw_addr.as_deref()?)
+    }
+
+    pub fn dns_primary(&self) -> Option<IpAddr> {
+        parse_addr(self.dns_prim_addr.as_deref()?)
+    }
+
+    pub fn dns_secondary(&self) -> Option<IpAddr> {
+        parse_addr(self.dns_sec_addr.as_deref()?)
+    }
+}
+
+impl From<&PDPContextDynamicParameters> for PdpContextInfo {
+    fn from(params: &PDPContextDynamicParameters) -> Self {
+        Self {
+            cid: params.cid,
+            apn: Some(params.apn.clone()),
+            local_addr: params.local_addr(),
+            subnet_mask: params.subnet_mask(),
+            gateway: params.gateway(),
+            dns_primary: params.dns_primary(),
+            dns_secondary: params.dns_secondary(),
+        }
+    }
+}
+
+/// Parse an address in either the usual notation, or as dot separated
+/// decimal bytes, which some modules use for IPv6
+fn parse_addr(s: &str) -> Option<IpAddr> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if let Ok(addr) = s.parse() {
+        return Some(addr);
+    }
+
+    let mut octets = [0u8; 16];
+    let n = parse_octets(s, &mut octets)?;
+    octets_to_addr(&octets[..n])
+}
+
+/// Split a combined address and subnet mask, either separated by a space or
+/// as a single run of dot separated decimal bytes
+fn split_addr_and_mask(s: &str) -> Option<(IpAddr, IpAddr)> {
+    let s = s.trim();
+    if let Some((addr, mask)) = s.split_once(' ') {
+        return Some((parse_addr(addr)?, parse_addr(mask)?));
+    }
+
+    let mut octets = [0u8; 32];
+    let n = parse_octets(s, &mut octets)?;
+    if n != 8 && n != 32 {
+        return None;
+    }
+    let (addr, mask) = octets[..n].split_at(n / 2);
+    Some((octets_to_addr(addr)?, octets_to_addr(mask)?))
+}
+
+fn parse_octets(s: &str, octets: &mut [u8]) -> Option<usize> {
+    let mut n = 0;
+    for part in s.split('.') {
+        *octets.get_mut(n)? = part.parse().ok()?;
+        n += 1;
+    }
+    Some(n)
+}
+
+fn octets_to_addr(octets: &[u8]) -> Option<IpAddr> {
+    if let Ok(v4) = <[u8; 4]>::try_from(octets) {
+        Some(Ipv4Addr::from(v4).into())
+    } else if let Ok(v6) = <[u8; 16]>::try_from(octets) {
+        Some(Ipv6Addr::from(v6).into())
+    } else {
+        None
+    }
+}
+
 /// 18.14 GPRS attach or detach +CGATT Register (attach) the MT to, or
 /// deregister (detach) the MT from the GPRS service. After this command the MT
 /// remains in AT command mode. If the MT is already in the requested state
@@ -66,7 +235,7 @@ pub struct PacketSwitchedNetworkData {
 /// returned. The command can be aborted if a character is sent to the DCE
 /// during the command execution. Any active PDP context will be automatically
 /// deactivated when the GPRS registration state changes to detached.
-#[derive(AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GPRSAttached {
     #[at_arg(position = 0)]
@@ -74,7 +243,7 @@ pub struct GPRSAttached {
 }
 
 /// 18.16 PDP context activate or deactivate +CGACT
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PDPContextState {
     #[at_arg(position = 0)]
@@ -84,7 +253,7 @@ pub struct PDPContextState {
 }
 
 /// 18.27 GPRS network registration status +CGREG
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GPRSNetworkRegistrationStatus {
     #[at_arg(position = 0)]
@@ -102,7 +271,7 @@ pub struct GPRSNetworkRegistrationStatus {
 }
 
 /// 18.28 Extended network registration status +UREG
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ExtendedPSNetworkRegistrationStatus {
     #[at_arg(position = 0)]
@@ -112,7 +281,7 @@ pub struct ExtendedPSNetworkRegistrationStatus {
 }
 
 /// 18.36 EPS network registration status +CEREG
-#[derive(Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EPSNetworkRegistrationStatus {
     #[at_arg(position = 0)]
@@ -125,4 +294,100 @@ pub struct EPSNetworkRegistrationStatus {
     pub ci: Option<String<8>>,
     #[at_arg(position = 4)]
     pub act: Option<RatAct>,
+    #[at_arg(position = 5)]
+    pub cause_type: Option<u8>,
+    #[at_arg(position = 6)]
+    pub reject_cause: Option<u8>,
+    /// Active time granted by the network, as a GPRS Timer 2 bit string
+    #[at_arg(position = 7)]
+    pub active_time: Option<String<8>>,
+    /// Periodic TAU time granted by the network, as a GPRS Timer 3 bit
+    /// string
+    #[at_arg(position = 8)]
+    pub periodic_tau: Option<String<8>>,
+}
+
+/// Data counters of a single PDP context, as reported by +UGCNTRD. Session
+/// counters restart on every activation of the context, total counters are
+/// only cleared with +UGCNTSET.
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataCounters {
+    #[at_arg(position = 0)]
+    pub cid: ContextId,
+    /// Bytes sent in the current session
+    #[at_arg(position = 1)]
+    pub sent_sess_bytes: u64,
+    /// Bytes received in the current session
+    #[at_arg(position = 2)]
+    pub received_sess_bytes: u64,
+    /// Bytes sent since the last reset
+    #[at_arg(position = 3)]
+    pub sent_total_bytes: u64,
+    /// Bytes received since the last reset
+    #[at_arg(position = 4)]
+    pub received_total_bytes: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dynamic_parameter_addresses() {
+        let (addr, mask) = split_addr_and_mask("10.160.23.5.255.255.255.0").unwrap();
+        assert_eq!(addr, IpAddr::V4(Ipv4Addr::new(10, 160, 23, 5)));
+        assert_eq!(mask, IpAddr::V4(Ipv4Addr::new(255, 255, 255, 0)));
+
+        let (addr, mask) = split_addr_and_mask("10.160.23.5 255.255.0.0").unwrap();
+        assert_eq!(addr, IpAddr::V4(Ipv4Addr::new(10, 160, 23, 5)));
+        assert_eq!(mask, IpAddr::V4(Ipv4Addr::new(255, 255, 0, 0)));
+
+        assert_eq!(
+            parse_addr("32.1.13.184.0.0.0.0.0.0.0.0.0.0.0.1"),
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0x0db8, 0, 0, 0, 0, 0, 1)))
+        );
+        assert_eq!(
+            parse_addr("2001:db8::1"),
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0x0db8, 0, 0, 0, 0, 0, 1)))
+        );
+        assert_eq!(parse_addr(""), None);
+        assert_eq!(split_addr_and_mask("10.160.23.5"), None);
+    }
+
+    #[test]
+    fn parse_data_counters() {
+        let counters: DataCounters =
+            atat::serde_at::from_slice(b"+UGCNTRD: 1,1024,2048,4294967296,8192").unwrap();
+        assert_eq!(
+            counters,
+            DataCounters {
+                cid: ContextId(1),
+                sent_sess_bytes: 1024,
+                received_sess_bytes: 2048,
+                sent_total_bytes: 4_294_967_296,
+                received_total_bytes: 8192,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_pdp_address() {
+        let res: PDPAddress = atat::serde_at::from_slice(b"+CGPADDR: 1,\"10.160.23.5\"").unwrap();
+        assert_eq!(res.cid, ContextId(1));
+        assert_eq!(
+            res.address(),
+            Some(IpAddr::V4(Ipv4Addr::new(10, 160, 23, 5)))
+        );
+        assert_eq!(res.address_2(), None);
+
+        let res: PDPAddress = atat::serde_at::from_slice(
+            b"+CGPADDR: 1,\"10.160.23.5\",\"32.1.13.184.0.0.0.0.0.0.0.0.0.0.0.1\"",
+        )
+        .unwrap();
+        assert_eq!(
+            res.address_2(),
+            Some(IpAddr::V6(Ipv6Addr::new(0x2001, 0x0db8, 0, 0, 0, 0, 0, 1)))
+        );
+    }
 }
@@ -4,7 +4,7 @@ use heapless::String;
 use serde::{Deserialize, Serialize};
 
 /// Indicates the state of PDP context activation
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PDPContextStatus {
     /// 0: deactivated
@@ -13,7 +13,8 @@ pub enum PDPContextStatus {
     Activated = 1,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[at_arg(u8)]
 pub enum PacketSwitchedParam {
     /// • 0: Protocol type; the allowed values of <param_val> parameter are
@@ -40,7 +41,11 @@ pub enum PacketSwitchedParam {
     /// notation form (2001:DB8:: address compression is allowed). The
     /// factory-programmed value is "0.0.0.0".
     #[at_arg(value = 4)]
-    DNS1(#[at_arg(len = 45)] IpAddr),
+    DNS1(
+        #[at_arg(len = 45)]
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+        IpAddr,
+    ),
     /// • 5: DNS2 - <param_val> is the text string of the secondary DNS address.
     /// IPv4 DNS addresses are specified in dotted decimal notation form (i.e.
     /// four numbers in range 0-255 separated by periods, e.g.
@@ -48,7 +53,11 @@ pub enum PacketSwitchedParam {
     /// notation form (2001:DB8:: address compression is allowed). The
     /// factory-programmed value is "0.0.0.0".
     #[at_arg(value = 5)]
-    DNS2(#[at_arg(len = 45)] IpAddr),
+    DNS2(
+        #[at_arg(len = 45)]
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+        IpAddr,
+    ),
     /// • 6: authentication - the <param_val> parameter selects the
     /// authentication type:
     #[at_arg(value = 6)]
@@ -60,7 +69,11 @@ pub enum PacketSwitchedParam {
     /// "0.0.0.0" means dynamic IP address assigned during PDP context
     /// activation
     #[at_arg(value = 7)]
-    IPAddress(#[at_arg(len = 45)] IpAddr),
+    IPAddress(
+        #[at_arg(len = 45)]
+        #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
+        IpAddr,
+    ),
     /// • 8: data compression - the <param_val> parameter refers to the default
     /// parameter named d_comp and selects the data compression type:
     #[at_arg(value = 8)]
@@ -367,7 +380,8 @@ pub enum PacketSwitchedParamReq {
     MapProfile = 100,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ProtocolType {
     /// (factory-programmed value): IPv4
     IPv4 = 0,
@@ -379,7 +393,8 @@ pub enum ProtocolType {
     IPv4v6PreferV6Internal = 3,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum AuthenticationType {
     /// (factory-programmed value): none
     None = 0,
@@ -391,7 +406,8 @@ pub enum AuthenticationType {
     Auto = 3,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DataCompression {
     /// (factory-programmed value): off
     Off = 0,
@@ -401,7 +417,8 @@ pub enum DataCompression {
     V42Bits = 2,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum HeaderCompression {
     /// (factory-programmed value): off
     Off = 0,
@@ -415,7 +432,8 @@ pub enum HeaderCompression {
     RFC3095 = 4,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum QoSPrecedence {
     /// (factory-programmed value): subscribed
     Subscribed = 0,
@@ -427,7 +445,8 @@ pub enum QoSPrecedence {
     Low = 3,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum QoSDelay {
     /// (factory-programmed value): subscribed
     Subscribed = 0,
@@ -441,7 +460,8 @@ pub enum QoSDelay {
     BestEffort = 4,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum QoSReliability {
     /// (factory-programmed value): subscribed
     Subscribed = 0,
@@ -476,6 +496,7 @@ pub enum PacketSwitchedAction {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PacketSwitchedNetworkDataParam {
     /// • 0: IP address: dynamic IP address assigned during PDP context
     /// activation;
@@ -519,7 +540,7 @@ pub enum GPRSAttachedState {
     Attached = 1,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum GPRSNetworkRegistrationUrcConfig {
     /// • 0 (default value and factory-programmed value): network registration
@@ -569,7 +590,7 @@ pub enum GPRSNetworkRegistrationStat {
     /// when <AcT> indicates 2,4,5,6)
     AttachedEmergencyOnly = 8,
 }
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ExtendedPSNetworkRegistrationUrcConfig {
     /// • 0: network registration attach status URC disabled
@@ -604,7 +625,7 @@ pub enum ExtendedPSNetworkRegistrationState {
 }
 
 /// Mode configuration
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum EPSNetworkRegistrationUrcConfig {
     /// • 0: network registration URC disabled
@@ -614,16 +635,111 @@ pub enum EPSNetworkRegistrationUrcConfig {
     /// • 2: network registration and location information URC +CEREG:
     /// <stat>[,[<tac>],[<ci>],[<AcT>]] enabled
     UrcVerbose = 2,
-    // • 3: network registration, location information and EMM cause value
-    // information URC +CEREG:
-    // <stat>[,[<tac>],[<ci>],[<AcT>][,<cause_type>,<reject_cause>]] enabled •
-    // 4: PSM, network registration and location information information URC
-    // +CEREG:
-    // <stat>[,[<tac>],[<ci>],[<AcT>][,,[,[<Assigned_Active_Time>[,<Assigned_Periodic_TAU>]]]]]
-    // enabled • 5: PSM, network registration, location information and EMM
-    // cause value information URC +CEREG:
-    // <stat>[,[<tac>],[<ci>],[<AcT>][,[<cause_type>],[<reject_cause>][,[<Assigned_Active_Time>,[<Assigned_Periodic_TAU>]]]]]
-    // enabled
+    /// • 3: network registration, location information and EMM cause value
+    /// information URC +CEREG:
+    /// <stat>[,[<tac>],[<ci>],[<AcT>][,<cause_type>,<reject_cause>]] enabled
+    UrcVerboseWithCause = 3,
+    /// • 4: PSM, network registration and location information information URC
+    /// +CEREG:
+    /// <stat>[,[<tac>],[<ci>],[<AcT>][,,[,[<Assigned_Active_Time>[,<Assigned_Periodic_TAU>]]]]]
+    /// enabled
+    UrcVerbosePsm = 4,
+    /// • 5: PSM, network registration, location information and EMM
+    /// cause value information URC +CEREG:
+    /// <stat>[,[<tac>],[<ci>],[<AcT>][,[<cause_type>],[<reject_cause>][,[<Assigned_Active_Time>,[<Assigned_Periodic_TAU>]]]]]
+    /// enabled
+    UrcVerbosePsmWithCause = 5,
+}
+
+/// EMM cause of a rejected EPS registration, as reported in the
+/// `<reject_cause>` of +CEREG. See 3GPP TS 24.301 Annex A.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RejectCause {
+    /// #2: IMSI unknown in HSS
+    ImsiUnknownInHss,
+    /// #3: Illegal UE
+    IllegalUe,
+    /// #5: IMEI not accepted
+    ImeiNotAccepted,
+    /// #6: Illegal ME
+    IllegalMe,
+    /// #7: EPS services not allowed
+    EpsServicesNotAllowed,
+    /// #8: EPS services and non-EPS services not allowed
+    EpsAndNonEpsServicesNotAllowed,
+    /// #9: UE identity cannot be derived by the network
+    UeIdentityNotDerived,
+    /// #10: Implicitly detached
+    ImplicitlyDetached,
+    /// #11: PLMN not allowed
+    PlmnNotAllowed,
+    /// #12: Tracking area not allowed
+    TrackingAreaNotAllowed,
+    /// #13: Roaming not allowed in this tracking area
+    RoamingNotAllowedInTrackingArea,
+    /// #14: EPS services not allowed in this PLMN
+    EpsServicesNotAllowedInPlmn,
+    /// #15: No suitable cells in tracking area
+    NoSuitableCellsInTrackingArea,
+    /// #17: Network failure
+    NetworkFailure,
+    /// #22: Congestion
+    Congestion,
+    /// #35: Requested service option not authorized in this PLMN
+    ServiceNotAuthorizedInPlmn,
+    /// #111: Protocol error, unspecified
+    ProtocolError,
+    /// Any other EMM cause
+    Other(u8),
+    /// Manufacturer specific cause, when `<cause_type>` is 1
+    ManufacturerSpecific(u8),
+}
+
+impl RejectCause {
+    /// Build the cause from the `<cause_type>` and `<reject_cause>` of +CEREG
+    pub fn new(cause_type: u8, reject_cause: u8) -> Self {
+        if cause_type != 0 {
+            return Self::ManufacturerSpecific(reject_cause);
+        }
+
+        match reject_cause {
+            2 => Self::ImsiUnknownInHss,
+            3 => Self::IllegalUe,
+            5 => Self::ImeiNotAccepted,
+            6 => Self::IllegalMe,
+            7 => Self::EpsServicesNotAllowed,
+            8 => Self::EpsAndNonEpsServicesNotAllowed,
+            9 => Self::UeIdentityNotDerived,
+            10 => Self::ImplicitlyDetached,
+            11 => Self::PlmnNotAllowed,
+            12 => Self::TrackingAreaNotAllowed,
+            13 => Self::RoamingNotAllowedInTrackingArea,
+            14 => Self::EpsServicesNotAllowedInPlmn,
+            15 => Self::NoSuitableCellsInTrackingArea,
+            17 => Self::NetworkFailure,
+            22 => Self::Congestion,
+            35 => Self::ServiceNotAuthorizedInPlmn,
+            111 => Self::ProtocolError,
+            c => Self::Other(c),
+        }
+    }
+
+    /// The rejection is caused by the SIM or the subscription, rather than by
+    /// the coverage, and will not go away by retrying.
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            Self::ImsiUnknownInHss
+                | Self::IllegalUe
+                | Self::ImeiNotAccepted
+                | Self::IllegalMe
+                | Self::EpsServicesNotAllowed
+                | Self::EpsAndNonEpsServicesNotAllowed
+                | Self::PlmnNotAllowed
+                | Self::EpsServicesNotAllowedInPlmn
+        )
+    }
 }
 
 /// EPS registration status
@@ -656,3 +772,64 @@ pub struct ProfileId(pub u8);
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, AtatLen)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ContextId(pub u8);
+
+/// Network assigned parameters of an active PDP context. Fields the module
+/// does not report are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdpContextInfo {
+    pub cid: ContextId,
+    pub apn: Option<String<99>>,
+    pub local_addr: Option<IpAddr>,
+    pub subnet_mask: Option<IpAddr>,
+    pub gateway: Option<IpAddr>,
+    pub dns_primary: Option<IpAddr>,
+    pub dns_secondary: Option<IpAddr>,
+}
+
+/// Event of a +CGEV URC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PSEventKind {
+    /// NW DETACH: the network detached the MT, deactivating all contexts
+    NetworkDetach,
+    /// ME DETACH: the MT detached itself
+    MobileStationDetach,
+    /// NW DEACT: the network deactivated a context
+    NetworkDeactivate,
+    /// ME DEACT: the MT deactivated a context
+    MobileStationDeactivate,
+    /// NW PDN DEACT: the network deactivated a PDN connection
+    NetworkPDNDeactivate,
+    /// ME PDN DEACT: the MT deactivated a PDN connection
+    MobileStationPDNDeactivate,
+    /// NW PDN ACT: the network activated a PDN connection
+    NetworkPDNActivate,
+    /// ME PDN ACT: the MT activated a PDN connection
+    MobileStationPDNActivate,
+    /// Any other event, eg. NW CLASS or NW MODIFY
+    Other,
+}
+
+/// Packet domain event reported with +CGEV, see
+/// [`SetPacketSwitchedEventReporting`](super::SetPacketSwitchedEventReporting).
+/// Fields the event does not carry are `None`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PSEvent {
+    pub kind: PSEventKind,
+    pub pdp_type: Option<String<8>>,
+    pub pdp_addr: Option<String<64>>,
+    /// Context affected by the event
+    pub cid: Option<ContextId>,
+}
+
+impl PSEventKind {
+    /// Whether the event takes a context down, all of them for a detach.
+    #[must_use]
+    pub fn is_deactivation(&self) -> bool {
+        !matches!(
+            self,
+            Self::NetworkPDNActivate | Self::MobileStationPDNActivate | Self::Other
+        )
+    }
+}
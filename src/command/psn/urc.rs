@@ -1,30 +1,39 @@
 //! Unsolicited responses for Packet Switched Data Services Commands
 use super::types::{
-    EPSNetworkRegistrationStat, ExtendedPSNetworkRegistrationState, GPRSNetworkRegistrationStat,
+    ContextId, EPSNetworkRegistrationStat, ExtendedPSNetworkRegistrationState,
+    GPRSNetworkRegistrationStat, PSEvent, PSEventKind,
 };
 use crate::{command::network_service::types::RatAct, command::psn::types::ProfileId};
 use atat::atat_derive::AtatResp;
 use core::net::IpAddr;
 use heapless::String;
+use serde::{de, Deserialize, Deserializer};
 
 /// +UUPSDA
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DataConnectionActivated {
     #[at_arg(position = 0)]
     pub result: u8,
     #[at_arg(position = 1, len = 39)]
+    #[cfg_attr(feature = "defmt", defmt(Debug2Format))]
     pub ip_addr: Option<IpAddr>,
 }
 
 /// +UUPSDD
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct DataConnectionDeactivated {
     #[at_arg(position = 0)]
     pub profile_id: ProfileId,
 }
 
 /// 18.27 GPRS network registration status +CGREG
-#[derive(Debug, Clone, AtatResp)]
+///
+/// The fields after `stat` are reported depending on the configured
+/// [`GPRSNetworkRegistrationUrcConfig`](super::types::GPRSNetworkRegistrationUrcConfig),
+/// the reject cause with n=3 or 5, and the PSM timers with n=4 or 5.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GPRSNetworkRegistration {
     #[at_arg(position = 1)]
@@ -37,17 +46,36 @@ pub struct GPRSNetworkRegistration {
     pub act: Option<RatAct>,
     #[at_arg(position = 5)]
     pub rac: Option<String<2>>,
+    #[at_arg(position = 6)]
+    pub cause_type: Option<u8>,
+    #[at_arg(position = 7)]
+    pub reject_cause: Option<u8>,
+    /// Active time granted by the network, as a GPRS Timer 2 bit string
+    #[at_arg(position = 8)]
+    pub active_time: Option<String<8>>,
+    /// Periodic RAU time granted by the network, as a GPRS Timer 3 bit
+    /// string
+    #[at_arg(position = 9)]
+    pub periodic_rau: Option<String<8>>,
+    #[at_arg(position = 10)]
+    pub gprs_ready_timer: Option<String<8>>,
 }
 
 /// 18.28 Extended network registration status +UREG
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct ExtendedPSNetworkRegistration {
     #[at_arg(position = 1)]
     pub state: ExtendedPSNetworkRegistrationState,
 }
 
 /// 18.36 EPS network registration status +CEREG
-#[derive(Debug, Clone, AtatResp)]
+///
+/// The fields after `stat` are reported depending on the configured
+/// [`EPSNetworkRegistrationUrcConfig`](super::types::EPSNetworkRegistrationUrcConfig),
+/// the reject cause with n=3 or 5, and the PSM timers with n=4 or 5.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct EPSNetworkRegistration {
     #[at_arg(position = 1)]
     pub stat: EPSNetworkRegistrationStat,
@@ -61,4 +89,173 @@ pub struct EPSNetworkRegistration {
     pub cause_type: Option<u8>,
     #[at_arg(position = 6)]
     pub reject_cause: Option<u8>,
+    /// Active time granted by the network, as a GPRS Timer 2 bit string
+    #[at_arg(position = 7)]
+    pub active_time: Option<String<8>>,
+    /// Periodic TAU time granted by the network, as a GPRS Timer 3 bit
+    /// string
+    #[at_arg(position = 8)]
+    pub periodic_tau: Option<String<8>>,
+}
+
+/// 18.31 Packet switched event reporting +CGEV
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PacketDomainEvent {
+    #[at_arg(position = 0)]
+    pub event: PSEvent,
+}
+
+/// Events in the order they are matched, longer ones sharing a prefix first
+const PS_EVENTS: [(&[u8], PSEventKind); 8] = [
+    (b"NW PDN DEACT", PSEventKind::NetworkPDNDeactivate),
+    (b"ME PDN DEACT", PSEventKind::MobileStationPDNDeactivate),
+    (b"NW PDN ACT", PSEventKind::NetworkPDNActivate),
+    (b"ME PDN ACT", PSEventKind::MobileStationPDNActivate),
+    (b"NW DEACT", PSEventKind::NetworkDeactivate),
+    (b"ME DEACT", PSEventKind::MobileStationDeactivate),
+    (b"NW DETACH", PSEventKind::NetworkDetach),
+    (b"ME DETACH", PSEventKind::MobileStationDetach),
+];
+
+impl PSEvent {
+    /// Parse the payload of a +CGEV URC, with or without the prefix. The
+    /// context of a (de)activation is reported in one of the forms
+    ///
+    /// - `<PDP_type>,<PDP_addr>[,<cid>]` for NW/ME DEACT
+    /// - `<p_cid>,<cid>,<event_type>` for NW/ME DEACT of newer firmwares
+    /// - `<cid>[,...]` for the PDN variants
+    ///
+    /// Unknown events and fields are ignored.
+    pub fn parse(input: &[u8]) -> Self {
+        let input = input.strip_prefix(b"+CGEV:").unwrap_or(input).trim_ascii();
+
+        let mut event = Self {
+            kind: PSEventKind::Other,
+            pdp_type: None,
+            pdp_addr: None,
+            cid: None,
+        };
+
+        let Some((name, kind)) = PS_EVENTS.iter().find(|(name, _)| input.starts_with(name)) else {
+            return event;
+        };
+        event.kind = *kind;
+
+        let mut fields: [&[u8]; 3] = [&[]; 3];
+        for (slot, field) in fields
+            .iter_mut()
+            .zip(input[name.len()..].split(|&c| c == b','))
+        {
+            *slot = field.trim_ascii();
+        }
+
+        match event.kind {
+            PSEventKind::NetworkDeactivate | PSEventKind::MobileStationDeactivate => {
+                if fields[0].starts_with(b"\"") {
+                    event.pdp_type = parse_string(fields[0]);
+                    event.pdp_addr = parse_string(fields[1]);
+                    event.cid = parse_cid(fields[2]);
+                } else {
+                    event.cid = parse_cid(fields[1]);
+                }
+            }
+            PSEventKind::NetworkPDNDeactivate
+            | PSEventKind::MobileStationPDNDeactivate
+            | PSEventKind::NetworkPDNActivate
+            | PSEventKind::MobileStationPDNActivate => event.cid = parse_cid(fields[0]),
+            _ => {}
+        }
+
+        event
+    }
+}
+
+fn parse_string<const N: usize>(field: &[u8]) -> Option<String<N>> {
+    let field = field
+        .strip_prefix(b"\"")
+        .and_then(|f| f.strip_suffix(b"\""))
+        .unwrap_or(field);
+    if field.is_empty() {
+        return None;
+    }
+    String::try_from(core::str::from_utf8(field).ok()?).ok()
+}
+
+fn parse_cid(field: &[u8]) -> Option<ContextId> {
+    core::str::from_utf8(field)
+        .ok()?
+        .parse()
+        .ok()
+        .map(ContextId)
+}
+
+impl<'de> Deserialize<'de> for PSEvent {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PSEventVisitor;
+
+        impl<'de> de::Visitor<'de> for PSEventVisitor {
+            type Value = PSEvent;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a +CGEV packet domain event")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(PSEvent::parse(value))
+            }
+        }
+
+        deserializer.deserialize_bytes(PSEventVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ps_events() {
+        let event = PSEvent::parse(b"+CGEV: NW DEACT \"IP\",\"10.160.23.5\",1");
+        assert_eq!(event.kind, PSEventKind::NetworkDeactivate);
+        assert_eq!(event.pdp_type.as_deref(), Some("IP"));
+        assert_eq!(event.pdp_addr.as_deref(), Some("10.160.23.5"));
+        assert_eq!(event.cid, Some(ContextId(1)));
+
+        // Without the optional cid
+        let event = PSEvent::parse(b"ME DEACT \"IPV4V6\",\"10.160.23.5\"");
+        assert_eq!(event.kind, PSEventKind::MobileStationDeactivate);
+        assert_eq!(event.pdp_type.as_deref(), Some("IPV4V6"));
+        assert_eq!(event.cid, None);
+
+        // <p_cid>,<cid>,<event_type>
+        let event = PSEvent::parse(b"+CGEV: NW DEACT 1,2,0");
+        assert_eq!(event.kind, PSEventKind::NetworkDeactivate);
+        assert_eq!(event.pdp_addr, None);
+        assert_eq!(event.cid, Some(ContextId(2)));
+
+        let event = PSEvent::parse(b"+CGEV: NW PDN DEACT 3,1,\"extra\"");
+        assert_eq!(event.kind, PSEventKind::NetworkPDNDeactivate);
+        assert_eq!(event.cid, Some(ContextId(3)));
+
+        let event = PSEvent::parse(b"+CGEV: ME PDN ACT 1");
+        assert_eq!(event.kind, PSEventKind::MobileStationPDNActivate);
+        assert!(!event.kind.is_deactivation());
+        assert_eq!(event.cid, Some(ContextId(1)));
+
+        let event = PSEvent::parse(b"+CGEV: NW DETACH");
+        assert_eq!(event.kind, PSEventKind::NetworkDetach);
+        assert!(event.kind.is_deactivation());
+        assert_eq!(event.cid, None);
+
+        let event = PSEvent::parse(b"+CGEV: NW MODIFY 1,2,0");
+        assert_eq!(event.kind, PSEventKind::Other);
+        assert_eq!(event.cid, None);
+    }
 }
@@ -0,0 +1,89 @@
+//! Information responses made of repeated records
+//!
+//! Some commands answer with one record per line, each line repeating the
+//! `+CMD:` prefix, eg.
+//!
+//! ```text
+//! +UCELLINFO: 0,0,238,02,61EF,1A2B,12,35,255
+//! +UCELLINFO: 0,1,238,02,61EF,1A2C,14,80,20
+//! ```
+//!
+//! The derived `AtatResp` deserialization only knows about a single
+//! information response. Wrapping the record type in [`Records`] captures the
+//! remainder of the response, and accumulates every line that parses as a
+//! record.
+use heapless::Vec;
+use serde::{de, Deserialize, Deserializer};
+
+/// A single line of a multi-record information response
+pub trait Record: Sized {
+    /// Information response prefix repeated on every line, eg. `+UCELLINFO:`
+    const PREFIX: &'static [u8];
+
+    /// Parse a line, with the prefix stripped. Returning `None` skips the
+    /// line.
+    fn parse(line: &[u8]) -> Option<Self>;
+}
+
+/// Records of an information response, see the [module level
+/// documentation](self). Records beyond `N` are dropped.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Records<T, const N: usize>(pub Vec<T, N>);
+
+impl<T: Record, const N: usize> Records<T, N> {
+    pub fn parse(input: &[u8]) -> Self {
+        let mut records = Vec::new();
+
+        for line in input.split(|&c| c == b'\n') {
+            let line = line.trim_ascii();
+            let line = line.strip_prefix(T::PREFIX).unwrap_or(line).trim_ascii();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(record) = T::parse(line) {
+                if records.push(record).is_err() {
+                    warn!("More than {} records in response, dropping the rest", N);
+                    break;
+                }
+            }
+        }
+
+        Self(records)
+    }
+}
+
+impl<T, const N: usize> core::ops::Deref for Records<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'de, T: Record, const N: usize> Deserialize<'de> for Records<T, N> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct RecordsVisitor<T, const N: usize>(core::marker::PhantomData<T>);
+
+        impl<'de, T: Record, const N: usize> de::Visitor<'de> for RecordsVisitor<T, N> {
+            type Value = Records<T, N>;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a multi-record information response")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Records::parse(value))
+            }
+        }
+
+        deserializer.deserialize_bytes(RecordsVisitor(core::marker::PhantomData))
+    }
+}
@@ -0,0 +1,48 @@
+//! ### 10 - SIM management
+pub mod responses;
+pub mod types;
+
+use atat::atat_derive::AtatCmd;
+use responses::{GenericSimAccessResponse, RestrictedSimAccessResponse};
+use types::SimCommand;
+
+/// 10.1 Generic SIM access +CSIM
+///
+/// Transmits the APDU `command` to the SIM and returns the raw response of
+/// the SIM, including the SW1 and SW2 status words. The command and response
+/// are hex encoded, and `length` is the number of hex characters in
+/// `command`.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CSIM", GenericSimAccessResponse)]
+pub struct GenericSimAccess<'a> {
+    #[at_arg(position = 0)]
+    pub length: usize,
+    #[at_arg(position = 1, len = 112)]
+    pub command: &'a str,
+}
+
+/// 10.2 Restricted SIM access +CRSM
+///
+/// Gives limited access to the SIM database. The module handles the SIM
+/// interface locking and file selection, and reports the SW1 and SW2 status
+/// words of the SIM along with any returned data.
+///
+/// `fileid` is the decimal identifier of an elementary file, eg. 12258
+/// (0x2FE2) for EF_ICCID. `data` is the hex encoded data written by UPDATE
+/// BINARY and UPDATE RECORD.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CRSM", RestrictedSimAccessResponse)]
+pub struct RestrictedSimAccess<'a> {
+    #[at_arg(position = 0)]
+    pub command: SimCommand,
+    #[at_arg(position = 1)]
+    pub fileid: Option<u16>,
+    #[at_arg(position = 2)]
+    pub p1: Option<u8>,
+    #[at_arg(position = 3)]
+    pub p2: Option<u8>,
+    #[at_arg(position = 4)]
+    pub p3: Option<u8>,
+    #[at_arg(position = 5, len = 80)]
+    pub data: Option<&'a str>,
+}
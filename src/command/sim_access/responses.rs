@@ -0,0 +1,112 @@
+//! Responses for SIM management Commands
+use super::types::StatusWords;
+use crate::command::hex;
+use atat::atat_derive::AtatResp;
+use heapless::String;
+
+/// 10.1 Generic SIM access +CSIM
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GenericSimAccessResponse {
+    #[at_arg(position = 0)]
+    pub length: usize,
+    /// Hex encoded response APDU, ending with the SW1 and SW2 status words
+    #[at_arg(position = 1)]
+    pub response: String<516>,
+}
+
+impl GenericSimAccessResponse {
+    /// The trailing SW1 and SW2 status words of the response
+    pub fn status_words(&self) -> Option<StatusWords> {
+        let start = self.response.len().checked_sub(4)?;
+        let mut buf = [0; 2];
+        hex::decode(self.response.get(start..)?, &mut buf)?;
+        Some(StatusWords {
+            sw1: buf[0],
+            sw2: buf[1],
+        })
+    }
+
+    /// Decode the response data, without the status words, into `buf`.
+    /// Returns the number of bytes written.
+    pub fn data(&self, buf: &mut [u8]) -> Option<usize> {
+        let end = self.response.len().checked_sub(4)?;
+        hex::decode(self.response.get(..end)?, buf)
+    }
+}
+
+/// 10.2 Restricted SIM access +CRSM
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RestrictedSimAccessResponse {
+    #[at_arg(position = 0)]
+    pub sw1: u8,
+    #[at_arg(position = 1)]
+    pub sw2: u8,
+    /// Hex encoded response data of a successful command
+    #[at_arg(position = 2)]
+    pub response: Option<String<512>>,
+}
+
+impl RestrictedSimAccessResponse {
+    pub fn status_words(&self) -> StatusWords {
+        StatusWords {
+            sw1: self.sw1,
+            sw2: self.sw2,
+        }
+    }
+
+    /// Decode the response data into `buf`. Returns the number of bytes
+    /// written.
+    pub fn data(&self, buf: &mut [u8]) -> Option<usize> {
+        match &self.response {
+            Some(response) => hex::decode(response, buf),
+            None => Some(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atat::serde_at::from_slice;
+
+    #[test]
+    fn restricted_sim_access_response() {
+        let res: RestrictedSimAccessResponse =
+            from_slice(b"+CRSM: 144,0,\"98101430121181157002\"").unwrap();
+        assert!(res.status_words().is_ok());
+        let mut buf = [0; 10];
+        assert_eq!(res.data(&mut buf), Some(10));
+        assert_eq!(buf[..2], [0x98, 0x10]);
+
+        let res: RestrictedSimAccessResponse = from_slice(b"+CRSM: 106,130").unwrap();
+        assert!(res.status_words().is_file_not_found());
+        assert!(res.response.is_none());
+    }
+
+    /// A READ BINARY with P3 of 0 returns 256 bytes.
+    #[test]
+    fn restricted_sim_access_response_full_length() {
+        let mut line = heapless::Vec::<u8, 600>::new();
+        line.extend_from_slice(b"+CRSM: 144,0,\"").unwrap();
+        for _ in 0..256 {
+            line.extend_from_slice(b"A5").unwrap();
+        }
+        line.push(b'"').unwrap();
+
+        let res: RestrictedSimAccessResponse = from_slice(&line).unwrap();
+        let mut buf = [0; 256];
+        assert_eq!(res.data(&mut buf), Some(256));
+        assert!(buf.iter().all(|&b| b == 0xA5));
+    }
+
+    #[test]
+    fn generic_sim_access_response() {
+        let res: GenericSimAccessResponse = from_slice(b"+CSIM: 6,\"AB9000\"").unwrap();
+        assert_eq!(res.status_words(), Some(StatusWords { sw1: 0x90, sw2: 0 }));
+        let mut buf = [0; 4];
+        assert_eq!(res.data(&mut buf), Some(1));
+        assert_eq!(buf[0], 0xAB);
+    }
+}
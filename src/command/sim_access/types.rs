@@ -0,0 +1,47 @@
+//! Argument and parameter types used by SIM management Commands and Responses
+use atat::atat_derive::AtatEnum;
+
+/// Command passed on by the MT to the SIM with +CRSM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimCommand {
+    /// • 176: READ BINARY
+    ReadBinary = 176,
+    /// • 178: READ RECORD
+    ReadRecord = 178,
+    /// • 192: GET RESPONSE
+    GetResponse = 192,
+    /// • 214: UPDATE BINARY
+    UpdateBinary = 214,
+    /// • 220: UPDATE RECORD
+    UpdateRecord = 220,
+    /// • 242: STATUS
+    Status = 242,
+}
+
+/// SW1 and SW2 status words returned by the SIM, see ETSI TS 102 221 and
+/// 3GPP TS 51.011
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StatusWords {
+    pub sw1: u8,
+    pub sw2: u8,
+}
+
+impl StatusWords {
+    /// Normal ending of the command, possibly with extra information from
+    /// the SIM
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        matches!(self.sw1, 0x90 | 0x91 | 0x92)
+    }
+
+    /// The requested file, or record, does not exist
+    #[must_use]
+    pub fn is_file_not_found(&self) -> bool {
+        matches!(
+            (self.sw1, self.sw2),
+            (0x6A, 0x82) | (0x6A, 0x83) | (0x94, 0x02) | (0x94, 0x04)
+        )
+    }
+}
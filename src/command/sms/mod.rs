@@ -1,12 +1,68 @@
 //! ### 11 - Short Messages Service
 
+pub mod pdu;
 pub mod responses;
 pub mod types;
 pub mod urc;
 
 use super::NoResponse;
 use atat::atat_derive::AtatCmd;
-use types::MessageWaitingMode;
+use responses::{MessageReference, StoredMessage};
+use types::{MessageFormat, MessageWaitingMode};
+
+/// 11.4 Message format +CMGF
+///
+/// Selects the format of the messages sent and read, PDU mode or text mode.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CMGF", NoResponse)]
+pub struct SetMessageFormat {
+    #[at_arg(position = 0)]
+    pub mode: MessageFormat,
+}
+
+/// 11.13 Read message +CMGR
+///
+/// Reads the message at `index` of the message storage. In PDU mode the
+/// information text response carries the hex encoded PDU.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CMGR", StoredMessage, timeout_ms = 10000)]
+pub struct ReadMessage {
+    #[at_arg(position = 0)]
+    pub index: u16,
+}
+
+/// 11.15 Send message +CMGS
+///
+/// Sends a message in PDU mode. `length` is the length of the TPDU in
+/// octets, without the SMSC address. The module answers with the `>` prompt,
+/// after which the PDU is sent with [`SendMessagePdu`].
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+CMGS", NoResponse)]
+pub struct PrepareSendMessagePdu {
+    #[at_arg(position = 0)]
+    pub length: usize,
+}
+
+/// The hex encoded PDU, including the SMSC address, following the prompt
+/// of [`PrepareSendMessagePdu`]. It is terminated with Ctrl-Z.
+#[derive(Clone, AtatCmd)]
+#[at_cmd(
+    "",
+    MessageReference,
+    value_sep = false,
+    cmd_prefix = "",
+    termination = "\x1a",
+    force_receive_state = true,
+    attempts = 1,
+    timeout_ms = 180000
+)]
+pub struct SendMessagePdu<'a> {
+    // `pdu::MAX_SUBMIT_HEX_LEN`
+    #[at_arg(position = 0, len = 316)]
+    pub pdu: &'a atat::serde_bytes::Bytes,
+}
+
+const _: () = assert!(pdu::MAX_SUBMIT_HEX_LEN == 316);
 
 /// 11.29 Message waiting indication +UMWI
 ///
@@ -0,0 +1,731 @@
+//! SMS TPDUs of PDU mode (+CMGF=0), as specified by 3GPP TS 23.040. Messages
+//! are sent as SMS-SUBMIT and received as SMS-DELIVER, with user data in the
+//! GSM 7 bit default alphabet, 8 bit data or UCS2.
+//!
+//! Concatenated messages are not reassembled, but the user data header of
+//! each part is exposed, see [`UserDataHeader::concatenation`].
+
+use heapless::{String, Vec};
+
+/// Maximum length of the user data of a single message, in octets
+pub const MAX_USER_DATA_LEN: usize = 140;
+
+/// Maximum number of GSM 7 bit characters in the user data of a single
+/// message
+pub const MAX_SEPTETS: usize = 160;
+
+/// Maximum number of digits of a phone number
+pub const MAX_ADDRESS_DIGITS: usize = 20;
+
+/// Maximum length of an SMS-SUBMIT TPDU, without a validity period
+pub const MAX_SUBMIT_LEN: usize = 4 + MAX_ADDRESS_DIGITS / 2 + 3 + MAX_USER_DATA_LEN;
+
+/// Maximum length of an SMS-SUBMIT sent with +CMGS, hex encoded and
+/// preceded by the empty SMSC address
+pub const MAX_SUBMIT_HEX_LEN: usize = 2 + 2 * MAX_SUBMIT_LEN;
+
+/// Maximum length of a PDU read with +CMGR, including the SMSC address
+pub const MAX_PDU_LEN: usize =
+    2 + MAX_ADDRESS_DIGITS / 2 + 3 + MAX_ADDRESS_DIGITS / 2 + 10 + MAX_USER_DATA_LEN;
+
+/// Maximum length of the text of a single message, UTF-8 encoded
+pub const MAX_TEXT_LEN: usize = 2 * MAX_SEPTETS;
+
+/// Maximum length of an address, UTF-8 encoded. Alphanumeric addresses
+/// take up to 11 characters.
+pub const MAX_ADDRESS_LEN: usize = 32;
+
+const TON_MASK: u8 = 0x70;
+const TON_INTERNATIONAL: u8 = 0x10;
+const TON_ALPHANUMERIC: u8 = 0x50;
+
+/// Type of address of international numbers with the ISDN numbering plan
+const TOA_INTERNATIONAL: u8 = 0x91;
+/// Type of address of other numbers with the ISDN numbering plan
+const TOA_UNKNOWN: u8 = 0x81;
+
+/// TP-MTI of an SMS-DELIVER, and of an SMS-SUBMIT
+const MTI_MASK: u8 = 0x03;
+const MTI_DELIVER: u8 = 0x00;
+const MTI_SUBMIT: u8 = 0x01;
+/// TP-UDHI, the user data starts with a header
+const UDHI: u8 = 0x40;
+
+/// Escape to the extension table of the GSM 7 bit default alphabet
+const GSM7_ESCAPE: u8 = 0x1B;
+
+/// Concatenated short messages, 8 bit and 16 bit reference number
+const IEI_CONCAT_8: u8 = 0x00;
+const IEI_CONCAT_16: u8 = 0x08;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PduError {
+    /// The PDU ends early, or its lengths are inconsistent
+    Truncated,
+    /// The PDU is not an SMS-DELIVER
+    UnsupportedType,
+    /// The address is too long, or not a phone number
+    InvalidAddress,
+    /// The user data does not fit a single message
+    TooLong,
+    /// The user data is 8 bit data, or not valid for its alphabet
+    NotText,
+}
+
+/// Character set of the user data, by the data coding scheme (3GPP TS
+/// 23.038)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Alphabet {
+    Gsm7,
+    Data8,
+    Ucs2,
+}
+
+impl Alphabet {
+    pub fn from_dcs(dcs: u8) -> Self {
+        match dcs >> 4 {
+            // General data coding, with or without automatic deletion
+            0x0..=0x7 => match (dcs >> 2) & 0x03 {
+                1 => Self::Data8,
+                2 => Self::Ucs2,
+                _ => Self::Gsm7,
+            },
+            // Message waiting indication group, store message, UCS2
+            0xE => Self::Ucs2,
+            // Data coding/message class
+            0xF if dcs & 0x04 != 0 => Self::Data8,
+            _ => Self::Gsm7,
+        }
+    }
+
+    /// Data coding scheme without message class, for general data coding
+    fn dcs(self) -> u8 {
+        match self {
+            Self::Gsm7 => 0x00,
+            Self::Data8 => 0x04,
+            Self::Ucs2 => 0x08,
+        }
+    }
+}
+
+/// Originator or destination address, or the address of the service centre
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Address {
+    /// Digits of the number, with a leading `+` if it is international, or
+    /// the text of an alphanumeric address
+    pub number: String<MAX_ADDRESS_LEN>,
+    pub type_of_address: u8,
+}
+
+/// Service centre time stamp of a received message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timestamp {
+    /// Year of the century
+    pub year: u8,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Offset of the local time to UTC, in quarters of an hour
+    pub utc_offset: i8,
+}
+
+/// Part of a concatenated message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Concatenation {
+    /// Reference number, the same for all parts of a message
+    pub reference: u16,
+    /// Number of parts of the message
+    pub total: u8,
+    /// Sequence number of this part, starting at 1
+    pub sequence: u8,
+}
+
+/// Information elements of the user data header, without the header
+/// length
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UserDataHeader(pub Vec<u8, MAX_USER_DATA_LEN>);
+
+impl UserDataHeader {
+    /// The information elements, as identifier and data. A truncated
+    /// element ends the list.
+    pub fn elements(&self) -> impl Iterator<Item = (u8, &[u8])> {
+        let mut rest = self.0.as_slice();
+        core::iter::from_fn(move || {
+            let (&[iei, len], tail) = rest.split_first_chunk::<2>()?;
+            let data = tail.get(..len as usize)?;
+            rest = &tail[len as usize..];
+            Some((iei, data))
+        })
+    }
+
+    /// The concatenation information, if the message is a part of a
+    /// concatenated message.
+    pub fn concatenation(&self) -> Option<Concatenation> {
+        self.elements().find_map(|(iei, data)| match (iei, data) {
+            (IEI_CONCAT_8, &[reference, total, sequence]) => Some(Concatenation {
+                reference: reference.into(),
+                total,
+                sequence,
+            }),
+            (IEI_CONCAT_16, &[hi, lo, total, sequence]) => Some(Concatenation {
+                reference: u16::from_be_bytes([hi, lo]),
+                total,
+                sequence,
+            }),
+            _ => None,
+        })
+    }
+}
+
+/// A received message
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsDeliver {
+    /// The service centre that delivered the message, if the PDU carries it
+    pub smsc: Option<Address>,
+    pub originator: Address,
+    pub protocol_id: u8,
+    pub dcs: u8,
+    pub timestamp: Timestamp,
+    pub header: Option<UserDataHeader>,
+    /// The user data following the header. GSM 7 bit characters are
+    /// unpacked to one per byte, UCS2 is kept big endian.
+    pub data: Vec<u8, MAX_SEPTETS>,
+}
+
+impl SmsDeliver {
+    /// Decode an SMS-DELIVER PDU, starting with the SMSC address as read
+    /// with +CMGR.
+    pub fn decode(pdu: &[u8]) -> Result<Self, PduError> {
+        let mut r = Reader(pdu);
+
+        let smsc = match r.u8()? {
+            0 => None,
+            len => {
+                let type_of_address = r.u8()?;
+                let digits = r.take(len as usize - 1)?;
+                Some(decode_number(digits, 2 * digits.len(), type_of_address)?)
+            }
+        };
+
+        let first_octet = r.u8()?;
+        if first_octet & MTI_MASK != MTI_DELIVER {
+            return Err(PduError::UnsupportedType);
+        }
+
+        let digits = r.u8()? as usize;
+        let type_of_address = r.u8()?;
+        let value = r.take(digits.div_ceil(2))?;
+        let originator = if type_of_address & TON_MASK == TON_ALPHANUMERIC {
+            let septets = unpack_septets(value, digits * 4 / 7)?;
+            Address {
+                number: decode_gsm7(&septets).map_err(|_| PduError::InvalidAddress)?,
+                type_of_address,
+            }
+        } else {
+            decode_number(value, digits, type_of_address)?
+        };
+
+        let protocol_id = r.u8()?;
+        let dcs = r.u8()?;
+        let timestamp = decode_timestamp(r.take(7)?);
+
+        let udl = r.u8()? as usize;
+        let alphabet = Alphabet::from_dcs(dcs);
+        let user_data = match alphabet {
+            Alphabet::Gsm7 => r.take((udl * 7).div_ceil(8))?,
+            _ => r.take(udl)?,
+        };
+
+        let header_len = match first_octet & UDHI {
+            0 => 0,
+            _ => 1 + *user_data.first().ok_or(PduError::Truncated)? as usize,
+        };
+        let header = match header_len {
+            0 => None,
+            len => Some(UserDataHeader(
+                Vec::from_slice(user_data.get(1..len).ok_or(PduError::Truncated)?)
+                    .map_err(|_| PduError::Truncated)?,
+            )),
+        };
+
+        let data = match alphabet {
+            Alphabet::Gsm7 => {
+                // The header is padded to a septet boundary
+                let mut septets = unpack_septets(user_data, udl)?;
+                let skip = (header_len * 8).div_ceil(7);
+                if skip > septets.len() {
+                    return Err(PduError::Truncated);
+                }
+                septets.rotate_left(skip);
+                septets.truncate(septets.len() - skip);
+                septets
+            }
+            _ => Vec::from_slice(user_data.get(header_len..).ok_or(PduError::Truncated)?)
+                .map_err(|_| PduError::Truncated)?,
+        };
+
+        Ok(Self {
+            smsc,
+            originator,
+            protocol_id,
+            dcs,
+            timestamp,
+            header,
+            data,
+        })
+    }
+
+    pub fn alphabet(&self) -> Alphabet {
+        Alphabet::from_dcs(self.dcs)
+    }
+
+    /// The user data as text. Fails with [`PduError::NotText`] for 8 bit
+    /// data.
+    pub fn text(&self) -> Result<String<MAX_TEXT_LEN>, PduError> {
+        match self.alphabet() {
+            Alphabet::Gsm7 => decode_gsm7(&self.data),
+            Alphabet::Ucs2 => decode_ucs2(&self.data),
+            Alphabet::Data8 => Err(PduError::NotText),
+        }
+    }
+}
+
+/// User data of a message to send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload<'a> {
+    /// Text, sent in the GSM 7 bit default alphabet if all of its characters
+    /// are part of it, otherwise as UCS2
+    Text(&'a str),
+    /// 8 bit data
+    Data(&'a [u8]),
+}
+
+/// A message to send
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmsSubmit<'a> {
+    /// Phone number, with a leading `+` if it is international
+    pub destination: &'a str,
+    pub payload: Payload<'a>,
+    /// Set to send a part of a concatenated message
+    pub concatenation: Option<Concatenation>,
+}
+
+impl SmsSubmit<'_> {
+    /// Encode the SMS-SUBMIT TPDU, without the SMSC address.
+    pub fn encode(&self) -> Result<Vec<u8, MAX_SUBMIT_LEN>, PduError> {
+        let mut header = Vec::<u8, 7>::new();
+        if let Some(concat) = self.concatenation {
+            let [hi, lo] = concat.reference.to_be_bytes();
+            let element: &[u8] = match hi {
+                0 => &[IEI_CONCAT_8, 3, lo, concat.total, concat.sequence],
+                _ => &[IEI_CONCAT_16, 4, hi, lo, concat.total, concat.sequence],
+            };
+            header.push(element.len() as u8).ok();
+            header.extend_from_slice(element).ok();
+        }
+
+        let (number, type_of_address) = match self.destination.strip_prefix('+') {
+            Some(number) => (number, TOA_INTERNATIONAL),
+            None => (self.destination, TOA_UNKNOWN),
+        };
+        if number.is_empty() || number.len() > MAX_ADDRESS_DIGITS {
+            return Err(PduError::InvalidAddress);
+        }
+
+        let mut out = Vec::new();
+        let first_octet = if header.is_empty() {
+            MTI_SUBMIT
+        } else {
+            MTI_SUBMIT | UDHI
+        };
+        // The message reference is set by the module
+        out.extend_from_slice(&[first_octet, 0, number.len() as u8, type_of_address])
+            .ok();
+        for pair in number.as_bytes().chunks(2) {
+            let lo = semi_octet(pair[0])?;
+            let hi = match pair.get(1) {
+                Some(&c) => semi_octet(c)?,
+                None => 0xF,
+            };
+            out.push((hi << 4) | lo).ok();
+        }
+
+        let (alphabet, user_data) = match self.payload {
+            Payload::Text(text) if text.chars().all(|c| gsm7_code(c).is_some()) => {
+                (Alphabet::Gsm7, UserData::Gsm7(encode_gsm7(text)?))
+            }
+            Payload::Text(text) => (Alphabet::Ucs2, UserData::Octets(encode_ucs2(text)?)),
+            Payload::Data(data) => (
+                Alphabet::Data8,
+                UserData::Octets(Vec::from_slice(data).map_err(|_| PduError::TooLong)?),
+            ),
+        };
+        // Protocol identifier, data coding scheme
+        out.extend_from_slice(&[0, alphabet.dcs()]).ok();
+
+        match user_data {
+            UserData::Gsm7(septets) => {
+                let skip = (header.len() * 8).div_ceil(7);
+                let udl = skip + septets.len();
+                if udl > MAX_SEPTETS {
+                    return Err(PduError::TooLong);
+                }
+                let mut packed = [0u8; MAX_USER_DATA_LEN];
+                packed[..header.len()].copy_from_slice(&header);
+                pack_septets(&septets, skip, &mut packed);
+                out.push(udl as u8).ok();
+                out.extend_from_slice(&packed[..(udl * 7).div_ceil(8)]).ok();
+            }
+            UserData::Octets(octets) => {
+                let udl = header.len() + octets.len();
+                if udl > MAX_USER_DATA_LEN {
+                    return Err(PduError::TooLong);
+                }
+                out.push(udl as u8).ok();
+                out.extend_from_slice(&header).ok();
+                out.extend_from_slice(&octets).ok();
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+enum UserData {
+    Gsm7(Vec<u8, MAX_SEPTETS>),
+    Octets(Vec<u8, MAX_USER_DATA_LEN>),
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, PduError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PduError> {
+        if len > self.0.len() {
+            return Err(PduError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+}
+
+fn semi_octet(c: u8) -> Result<u8, PduError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'*' => Ok(0xA),
+        b'#' => Ok(0xB),
+        b'a' => Ok(0xC),
+        b'b' => Ok(0xD),
+        b'c' => Ok(0xE),
+        _ => Err(PduError::InvalidAddress),
+    }
+}
+
+/// Decode the first `digits` semi-octets of `value`. A `0xF` filler ends
+/// the number early.
+fn decode_number(value: &[u8], digits: usize, type_of_address: u8) -> Result<Address, PduError> {
+    let mut number = String::new();
+    if type_of_address & TON_MASK == TON_INTERNATIONAL {
+        number.push('+').ok();
+    }
+
+    for i in 0..digits {
+        let octet = value.get(i / 2).ok_or(PduError::Truncated)?;
+        let c = match (octet >> (4 * (i % 2))) & 0x0F {
+            d @ 0..=9 => (b'0' + d) as char,
+            0xA => '*',
+            0xB => '#',
+            0xC => 'a',
+            0xD => 'b',
+            0xE => 'c',
+            _ => break,
+        };
+        number.push(c).map_err(|_| PduError::InvalidAddress)?;
+    }
+
+    Ok(Address {
+        number,
+        type_of_address,
+    })
+}
+
+fn decode_timestamp(scts: &[u8]) -> Timestamp {
+    let bcd = |o: u8| (o & 0x0F) * 10 + (o >> 4);
+    // Bit 3 is the sign of the time zone
+    let offset = ((scts[6] & 0x07) * 10 + (scts[6] >> 4)) as i8;
+    Timestamp {
+        year: bcd(scts[0]),
+        month: bcd(scts[1]),
+        day: bcd(scts[2]),
+        hour: bcd(scts[3]),
+        minute: bcd(scts[4]),
+        second: bcd(scts[5]),
+        utc_offset: if scts[6] & 0x08 != 0 { -offset } else { offset },
+    }
+}
+
+/// Unpack `count` septets, packed starting at the least significant bit of
+/// `packed`.
+fn unpack_septets(packed: &[u8], count: usize) -> Result<Vec<u8, MAX_SEPTETS>, PduError> {
+    if count > MAX_SEPTETS || (count * 7).div_ceil(8) > packed.len() {
+        return Err(PduError::Truncated);
+    }
+
+    let mut septets = Vec::new();
+    for i in 0..count {
+        let (byte, shift) = (i * 7 / 8, i * 7 % 8);
+        let mut septet = packed[byte] >> shift;
+        if shift > 1 {
+            septet |= packed[byte + 1] << (8 - shift);
+        }
+        septets.push(septet & 0x7F).ok();
+    }
+    Ok(septets)
+}
+
+/// Pack `septets` into `packed`, starting at septet `offset`.
+fn pack_septets(septets: &[u8], offset: usize, packed: &mut [u8]) {
+    for (i, &septet) in septets.iter().enumerate() {
+        let bit = (offset + i) * 7;
+        let (byte, shift) = (bit / 8, bit % 8);
+        packed[byte] |= septet << shift;
+        if shift > 1 {
+            packed[byte + 1] |= septet >> (8 - shift);
+        }
+    }
+}
+
+/// The GSM 7 bit default alphabet (3GPP TS 23.038 6.2.1). The escape to the
+/// extension table does not map to a character.
+const GSM7_BASIC: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì', 'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å', 'å', 'Δ', '_',
+    'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', '\u{1b}', 'Æ', 'æ', 'ß', 'É', ' ', '!', '"', '#',
+    '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', '0', '1', '2', '3', '4', '5', '6',
+    '7', '8', '9', ':', ';', '<', '=', '>', '?', '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I',
+    'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö',
+    'Ñ', 'Ü', '§', '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à',
+];
+
+/// The characters of the extension table of the GSM 7 bit default alphabet,
+/// following the escape
+const GSM7_EXTENSION: [(u8, char); 10] = [
+    (0x0A, '\u{0c}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// The GSM 7 bit code of `c`, and whether it is in the extension table.
+fn gsm7_code(c: char) -> Option<(u8, bool)> {
+    if let Some(code) = GSM7_BASIC.iter().position(|&b| b == c && c != '\u{1b}') {
+        return Some((code as u8, false));
+    }
+    GSM7_EXTENSION
+        .iter()
+        .find(|&&(_, e)| e == c)
+        .map(|&(code, _)| (code, true))
+}
+
+fn encode_gsm7(text: &str) -> Result<Vec<u8, MAX_SEPTETS>, PduError> {
+    let mut septets = Vec::new();
+    for c in text.chars() {
+        let (code, extended) = gsm7_code(c).ok_or(PduError::NotText)?;
+        if extended {
+            septets.push(GSM7_ESCAPE).map_err(|_| PduError::TooLong)?;
+        }
+        septets.push(code).map_err(|_| PduError::TooLong)?;
+    }
+    Ok(septets)
+}
+
+fn decode_gsm7<const N: usize>(septets: &[u8]) -> Result<String<N>, PduError> {
+    let mut text = String::new();
+    let mut codes = septets.iter();
+    while let Some(&code) = codes.next() {
+        let c = match code {
+            GSM7_ESCAPE => match codes.next() {
+                // An unknown extension shows the character of the default
+                // alphabet
+                Some(&ext) => GSM7_EXTENSION
+                    .iter()
+                    .find(|&&(code, _)| code == ext)
+                    .map_or(GSM7_BASIC[ext as usize & 0x7F], |&(_, c)| c),
+                None => break,
+            },
+            code => GSM7_BASIC[code as usize & 0x7F],
+        };
+        text.push(c).map_err(|_| PduError::TooLong)?;
+    }
+    Ok(text)
+}
+
+fn encode_ucs2(text: &str) -> Result<Vec<u8, MAX_USER_DATA_LEN>, PduError> {
+    let mut octets = Vec::new();
+    for unit in text.encode_utf16() {
+        octets
+            .extend_from_slice(&unit.to_be_bytes())
+            .map_err(|_| PduError::TooLong)?;
+    }
+    Ok(octets)
+}
+
+fn decode_ucs2(octets: &[u8]) -> Result<String<MAX_TEXT_LEN>, PduError> {
+    let units = octets
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    let mut text = String::new();
+    for c in char::decode_utf16(units) {
+        text.push(c.map_err(|_| PduError::NotText)?)
+            .map_err(|_| PduError::TooLong)?;
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::hex;
+
+    use super::*;
+
+    fn decode_hex(pdu: &str) -> Vec<u8, MAX_PDU_LEN> {
+        let mut buf = [0u8; MAX_PDU_LEN];
+        let len = hex::decode(pdu, &mut buf).unwrap();
+        Vec::from_slice(&buf[..len]).unwrap()
+    }
+
+    #[test]
+    fn deliver_gsm7() {
+        let pdu = decode_hex(
+            "07911326040000F0040B911346610089F60000208062917314210CC8F71D14969741F977FD07",
+        );
+        let sms = SmsDeliver::decode(&pdu).unwrap();
+        assert_eq!(sms.smsc.as_ref().unwrap().number.as_str(), "+31624000000");
+        assert_eq!(sms.originator.number.as_str(), "+31641600986");
+        assert_eq!(
+            sms.timestamp,
+            Timestamp {
+                year: 2,
+                month: 8,
+                day: 26,
+                hour: 19,
+                minute: 37,
+                second: 41,
+                utc_offset: 12,
+            }
+        );
+        assert_eq!(sms.header, None);
+        assert_eq!(sms.text().unwrap().as_str(), "How are you?");
+    }
+
+    #[test]
+    fn deliver_alphanumeric_ucs2() {
+        // From "Info", without the SMSC address
+        let pdu = decode_hex("000007D049B7F90D0008224051110000000C041F04400438043204350442");
+        let sms = SmsDeliver::decode(&pdu).unwrap();
+        assert_eq!(sms.smsc, None);
+        assert_eq!(sms.originator.number.as_str(), "Info");
+        assert_eq!(sms.alphabet(), Alphabet::Ucs2);
+        assert_eq!(sms.text().unwrap().as_str(), "Привет");
+
+        assert_eq!(
+            SmsDeliver::decode(&pdu[..pdu.len() - 1]),
+            Err(PduError::Truncated)
+        );
+    }
+
+    #[test]
+    fn deliver_concatenated() {
+        let pdu = decode_hex("00440B911346610089F6000020806291731421090500032A0201D069");
+        let sms = SmsDeliver::decode(&pdu).unwrap();
+        assert_eq!(
+            sms.header.as_ref().unwrap().concatenation(),
+            Some(Concatenation {
+                reference: 0x2A,
+                total: 2,
+                sequence: 1,
+            })
+        );
+        assert_eq!(sms.text().unwrap().as_str(), "hi");
+    }
+
+    #[test]
+    fn submit() {
+        let submit = SmsSubmit {
+            destination: "+46708251358",
+            payload: Payload::Text("hellohello"),
+            concatenation: None,
+        };
+        assert_eq!(
+            submit.encode().unwrap().as_slice(),
+            decode_hex("01000B916407281553F800000AE8329BFD4697D9EC37").as_slice()
+        );
+
+        let submit = SmsSubmit {
+            destination: "12345",
+            payload: Payload::Text("hi"),
+            concatenation: Some(Concatenation {
+                reference: 0x2A,
+                total: 2,
+                sequence: 1,
+            }),
+        };
+        assert_eq!(
+            submit.encode().unwrap().as_slice(),
+            decode_hex("410005812143F50000090500032A0201D069").as_slice()
+        );
+    }
+
+    #[test]
+    fn submit_ucs2_and_data() {
+        let submit = SmsSubmit {
+            destination: "+12",
+            payload: Payload::Text("€ Привет"),
+            concatenation: None,
+        };
+        // Not all characters are in the GSM 7 bit alphabet
+        assert_eq!(
+            submit.encode().unwrap().as_slice(),
+            decode_hex("010002912100081020AC0020041F04400438043204350442").as_slice()
+        );
+
+        let data = [0xAB; MAX_USER_DATA_LEN + 1];
+        let mut submit = SmsSubmit {
+            destination: "+12",
+            payload: Payload::Data(&data),
+            concatenation: None,
+        };
+        assert_eq!(submit.encode(), Err(PduError::TooLong));
+        submit.payload = Payload::Data(&data[..MAX_USER_DATA_LEN]);
+        assert_eq!(submit.encode().unwrap().len(), 7 + 1 + MAX_USER_DATA_LEN);
+    }
+
+    #[test]
+    fn gsm7_extension() {
+        let septets = encode_gsm7("[1€]").unwrap();
+        assert_eq!(
+            septets.as_slice(),
+            &[0x1B, 0x3C, 0x31, 0x1B, 0x65, 0x1B, 0x3E]
+        );
+        assert_eq!(decode_gsm7::<16>(&septets).unwrap().as_str(), "[1€]");
+    }
+}
@@ -1 +1,120 @@
+//! Responses for Short Messages Service Commands
+use atat::atat_derive::AtatResp;
+use heapless::String;
+use serde::{de, Deserialize, Deserializer};
 
+use super::pdu::{PduError, SmsDeliver, MAX_PDU_LEN};
+use crate::command::hex;
+
+/// 11.15 Send message +CMGS
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MessageReference {
+    #[at_arg(position = 0)]
+    pub reference: u8,
+}
+
+/// 11.13 Read message +CMGR
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StoredMessage {
+    #[at_arg(position = 0)]
+    pub message: MessagePdu,
+}
+
+/// A message of the message storage, as read in PDU mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MessagePdu {
+    /// • 0: received unread, 1: received read, 2: stored unsent, 3: stored
+    /// sent
+    pub status: u8,
+    /// Length of the TPDU in octets, without the SMSC address
+    pub length: usize,
+    /// The hex encoded PDU, including the SMSC address
+    pub pdu: String<{ 2 * MAX_PDU_LEN }>,
+}
+
+impl MessagePdu {
+    /// Parse the raw information response of `AT+CMGR` in PDU mode, eg.
+    /// `+CMGR: 0,,24\r\n07911326040000F0040B91...`
+    pub fn parse(mut input: &[u8]) -> Option<Self> {
+        if let Some(rest) = input.strip_prefix(b"+CMGR:") {
+            input = rest;
+        }
+
+        let end = input.iter().position(|&b| b == b'\n')?;
+        let (header, pdu) = (input[..end].trim_ascii(), input[end..].trim_ascii());
+
+        // <stat>,[<alpha>],<length>
+        let mut fields = header.split(|&b| b == b',');
+        let status = core::str::from_utf8(fields.next()?)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        let length = core::str::from_utf8(fields.next_back()?)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+
+        Some(Self {
+            status,
+            length,
+            pdu: String::try_from(core::str::from_utf8(pdu).ok()?).ok()?,
+        })
+    }
+
+    /// Decode the PDU of a received message.
+    pub fn decode(&self) -> Result<SmsDeliver, PduError> {
+        let mut buf = [0u8; MAX_PDU_LEN];
+        let len = hex::decode(&self.pdu, &mut buf).ok_or(PduError::Truncated)?;
+        SmsDeliver::decode(&buf[..len])
+    }
+}
+
+impl<'de> Deserialize<'de> for MessagePdu {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MessagePduVisitor;
+
+        impl<'de> de::Visitor<'de> for MessagePduVisitor {
+            type Value = MessagePdu;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a +CMGR message in PDU mode")
+            }
+
+            fn visit_bytes<E>(self, value: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                MessagePdu::parse(value).ok_or_else(|| E::custom("malformed +CMGR response"))
+            }
+        }
+
+        deserializer.deserialize_bytes(MessagePduVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_message_pdu() {
+        let message = MessagePdu::parse(
+            b"+CMGR: 1,,30\r\n07911326040000F0040B911346610089F60000208062917314210CC8F71D14969741F977FD07",
+        )
+        .unwrap();
+        assert_eq!(message.status, 1);
+        assert_eq!(message.length, 30);
+        assert_eq!(
+            message.decode().unwrap().text().unwrap().as_str(),
+            "How are you?"
+        );
+    }
+}
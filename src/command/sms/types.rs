@@ -3,6 +3,7 @@ use atat::atat_derive::AtatEnum;
 
 /// Indicates the basic message indication type
 #[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum MessageIndicationType {
     /// • 1: Voice Message Waiting (third level method) or Voice Message Waiting on Line 1
     /// (CPHS method)
@@ -30,3 +31,12 @@ pub enum MessageWaitingMode {
     #[at_arg(default)]
     Enabled = 1,
 }
+
+/// Format of the messages sent and read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+pub enum MessageFormat {
+    /// • 0 (factory-programmed value): PDU mode
+    Pdu = 0,
+    /// • 1: text mode
+    Text = 1,
+}
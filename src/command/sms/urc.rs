@@ -15,7 +15,8 @@ use atat::atat_derive::AtatResp;
 /// are not present, the information text response is an error result code
 /// ("+CME ERROR: operation not allowed" if +CMEE is set to 2) and no URCs will
 /// be displayed.
-#[derive(Debug, Clone, AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct MessageWaitingIndication {
     #[at_arg(position = 0)]
     pub status: u8,
@@ -6,9 +6,15 @@
 
 pub mod responses;
 pub mod types;
+pub mod urc;
 use atat::atat_derive::AtatCmd;
-use responses::{FactoryConfiguration, PowerSavingControl};
-use types::{FSFactoryRestoreType, NVMFactoryRestoreType, PowerSavingMode, Seconds};
+use responses::{
+    FactoryConfiguration, FirmwarePrevalidation, PowerSavingControl, SmartTemperatureSupervisor,
+    Temperature,
+};
+use types::{
+    FSFactoryRestoreType, NVMFactoryRestoreType, PowerSavingMode, Seconds, SmartTemperatureMode,
+};
 
 use super::NoResponse;
 
@@ -90,3 +96,71 @@ pub struct SetFactoryConfiguration {
 #[derive(Clone, AtatCmd)]
 #[at_cmd("+UFACTORY?", FactoryConfiguration)]
 pub struct GetFactoryConfiguration;
+
+/// 19.26 Firmware update file pre-validation +UFWPREVAL
+///
+/// Validates the firmware update file stored in the module file system,
+/// without starting the installation. The validation checks the integrity of
+/// the update file and that it applies to the firmware currently installed.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UFWPREVAL", FirmwarePrevalidation, timeout_ms = 60000)]
+pub struct PrevalidateFirmware<'a> {
+    #[at_arg(position = 0, len = 248)]
+    pub filename: &'a str,
+}
+
+/// 19.27 Firmware installation +UFWINSTALL
+///
+/// Triggers the FW installation procedure, starting from the update file
+/// stored in the module file system. The command causes a SW system reset
+/// with network deregistration. During the installation, the module reports
+/// the progress with the +UUFWINSTALL URC on the UART interface, and reboots
+/// once more when the installation is completed.
+///
+/// **NOTES:**
+/// - The module must not be switched off or reset during the installation.
+/// - The CMUX session is terminated by the reset, and the URCs are issued on
+///   the physical UART interface.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UFWINSTALL", NoResponse, timeout_ms = 20000)]
+pub struct InstallFirmware;
+
+/// 19.28 Temperature sensor +UTEMP
+///
+/// Reads the temperature measured by the module's internal sensor. See
+/// [`Temperature`] for the normalization of the reported value.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UTEMP?", Temperature)]
+pub struct GetTemperature;
+
+/// 19.29 Smart temperature supervisor +USTS
+///
+/// Enables the smart temperature supervisor. Whenever the internal temperature
+/// crosses one of the warning or dangerous limits, the module reports it with
+/// the +UUSTS URC.
+///
+/// **NOTE** Not supported on all modules.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USTS", NoResponse)]
+pub struct SetSmartTemperatureSupervisor {
+    #[at_arg(position = 0)]
+    pub mode: SmartTemperatureMode,
+}
+
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+USTS?", SmartTemperatureSupervisor)]
+pub struct GetSmartTemperatureSupervisor;
+
+/// 19.31 PSM state reporting +UPSMR
+///
+/// Enables the +UUPSMR URC, reporting when the module enters and leaves the
+/// power saving mode (PSM) deep sleep.
+///
+/// **NOTE** Not supported on all modules, eg. SARA-R5 and SARA-R4 only.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UPSMR", NoResponse)]
+pub struct SetPsmStateReporting {
+    /// 0: disabled, 1: enabled
+    #[at_arg(position = 0)]
+    pub mode: u8,
+}
@@ -1,9 +1,12 @@
 //! Responses for System features Commands
-use super::types::{FSFactoryRestoreType, NVMFactoryRestoreType, PowerSavingMode, Seconds};
+use super::types::{
+    FSFactoryRestoreType, NVMFactoryRestoreType, PowerSavingMode, Seconds, SmartTemperatureMode,
+};
 use atat::atat_derive::AtatResp;
 
 /// 19.8 Power saving control (Power Saving) +UPSV
-#[derive(AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PowerSavingControl {
     #[at_arg(position = 0)]
     pub mode: PowerSavingMode,
@@ -12,10 +15,94 @@ pub struct PowerSavingControl {
 }
 
 /// 19.25 Restore factory configuration +UFACTORY
-#[derive(AtatResp)]
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FactoryConfiguration {
     #[at_arg(position = 0)]
     pub fs_op: FSFactoryRestoreType,
     #[at_arg(position = 1)]
     pub nvm_op: NVMFactoryRestoreType,
 }
+
+/// 19.26 Firmware update file pre-validation +UFWPREVAL
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FirmwarePrevalidation {
+    /// 0: the update file is valid, otherwise a validation error code
+    #[at_arg(position = 0)]
+    pub result: u8,
+}
+
+/// Temperature reading +UTEMP
+///
+/// Depending on the module and firmware, the temperature is reported with or
+/// without the unit in front, and in whole degrees or in tenths of a degree.
+/// Use [`Temperature::deci_celsius`] to read it.
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Temperature {
+    #[at_arg(position = 0)]
+    pub first: i32,
+    #[at_arg(position = 1)]
+    pub second: Option<i32>,
+}
+
+impl Temperature {
+    /// Largest magnitude reported in whole degrees. The modules shut down far
+    /// below it, so larger readings are tenths of a degree.
+    const MAX_WHOLE_DEGREES: i32 = 150;
+
+    /// Reported value, without the unit
+    pub fn raw(&self) -> i32 {
+        self.second.unwrap_or(self.first)
+    }
+
+    /// Temperature in tenths of a degree Celsius.
+    ///
+    /// **NOTE** Readings in tenths between -15.0 and +15.0 degrees cannot be
+    /// told apart from whole degrees, and are taken as whole degrees.
+    pub fn deci_celsius(&self) -> i32 {
+        let raw = self.raw();
+        if raw.abs() > Self::MAX_WHOLE_DEGREES {
+            raw
+        } else {
+            raw * 10
+        }
+    }
+
+    /// Temperature in degrees Celsius
+    pub fn celsius(&self) -> f32 {
+        self.deci_celsius() as f32 / 10.0
+    }
+}
+
+/// Smart temperature supervisor +USTS
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmartTemperatureSupervisor {
+    #[at_arg(position = 0)]
+    pub mode: SmartTemperatureMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atat::serde_at::from_slice;
+
+    #[test]
+    fn temperature() {
+        let whole: Temperature = from_slice(b"+UTEMP: 37").unwrap();
+        assert_eq!(whole.deci_celsius(), 370);
+
+        let tenths: Temperature = from_slice(b"+UTEMP: 365").unwrap();
+        assert_eq!(tenths.deci_celsius(), 365);
+        assert_eq!(tenths.celsius(), 36.5);
+
+        let with_unit: Temperature = from_slice(b"+UTEMP: 0,-12").unwrap();
+        assert_eq!(with_unit.raw(), -12);
+        assert_eq!(with_unit.deci_celsius(), -120);
+
+        let cold_tenths: Temperature = from_slice(b"+UTEMP: -215").unwrap();
+        assert_eq!(cold_tenths.deci_celsius(), -215);
+    }
+}
@@ -2,7 +2,8 @@
 use atat::atat_derive::{AtatEnum, AtatLen};
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PowerSavingMode {
     /// Disabled: (default and factory-programmed value)
     Disabled = 0,
@@ -33,11 +34,13 @@ pub enum PowerSavingMode {
     CtrlByDtr = 3,
 }
 
-#[derive(Clone, PartialEq, Eq, AtatLen, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, AtatLen, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Seconds(pub u32);
 
 /// FS factory restore type
-#[derive(Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FSFactoryRestoreType {
     /// • 0 (factory-programmed value): no factory restore
     NoRestore = 0,
@@ -48,7 +51,8 @@ pub enum FSFactoryRestoreType {
 }
 
 /// NVM factory restore type:
-#[derive(Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum NVMFactoryRestoreType {
     /// • 0 (factory-programmed value): no factory restore
     NoRestore = 0,
@@ -57,3 +61,47 @@ pub enum NVMFactoryRestoreType {
     /// • 2: for internal use only
     InternalUseOnly = 2,
 }
+
+/// Failure of a firmware update started with +UFWPREVAL / +UFWINSTALL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FirmwareInstallError {
+    /// The update file failed the +UFWPREVAL validation, with the reported
+    /// error code
+    Validation(u8),
+    /// The installation failed, with the +UUFWINSTALL status code
+    Install(u8),
+    /// The installation did not complete in time
+    Timeout,
+}
+
+/// Smart temperature supervisor mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SmartTemperatureMode {
+    /// • 0 (factory-programmed value): smart temperature feature disabled
+    Disabled = 0,
+    /// • 1: temperature supervisor enabled, with +UUSTS indications
+    IndicationOnly = 1,
+    /// • 2: temperature supervisor enabled, the module shuts down when the
+    ///   temperature reaches a dangerous level
+    ShutdownEnabled = 2,
+}
+
+/// Temperature level reported by the +UUSTS URC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ThermalState {
+    /// • -2: temperature below the dangerous low limit
+    DangerousLow,
+    /// • -1: temperature below the warning low limit
+    WarningLow,
+    /// • 0: temperature within the normal operating range
+    Normal,
+    /// • 1: temperature above the warning high limit
+    WarningHigh,
+    /// • 2: temperature above the dangerous high limit
+    DangerousHigh,
+    /// • 10: temperature measurement not valid
+    Invalid,
+}
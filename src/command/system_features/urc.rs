@@ -0,0 +1,60 @@
+//! Unsolicited responses for System features Commands
+use atat::atat_derive::AtatResp;
+
+use super::types::ThermalState;
+
+/// 19.27 Firmware installation +UUFWINSTALL
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FirmwareInstallProgress {
+    /// - 0-100: installation progress in percent
+    /// - 128: installation completed successfully
+    /// - other values: installation failed
+    #[at_arg(position = 0)]
+    pub status: u8,
+}
+
+impl FirmwareInstallProgress {
+    pub const COMPLETED: u8 = 128;
+}
+
+/// 19.29 Smart temperature supervisor +UUSTS
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThermalWarning {
+    #[at_arg(position = 0)]
+    pub mode: u8,
+    #[at_arg(position = 1)]
+    pub event: i8,
+}
+
+impl ThermalWarning {
+    pub fn state(&self) -> ThermalState {
+        match self.event {
+            i8::MIN..=-2 => ThermalState::DangerousLow,
+            -1 => ThermalState::WarningLow,
+            0 => ThermalState::Normal,
+            1 => ThermalState::WarningHigh,
+            2..=9 => ThermalState::DangerousHigh,
+            _ => ThermalState::Invalid,
+        }
+    }
+}
+
+/// 19.31 PSM state reporting +UUPSMR
+#[derive(Debug, Clone, PartialEq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PsmState {
+    /// - 0: the module left PSM
+    /// - 1: the module is entering PSM
+    /// - 2: entering PSM is blocked, see `param`
+    #[at_arg(position = 0)]
+    pub state: u8,
+    #[at_arg(position = 1)]
+    pub param: Option<u8>,
+}
+
+impl PsmState {
+    pub const AWAKE: u8 = 0;
+    pub const ENTERING: u8 = 1;
+}
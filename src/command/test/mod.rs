@@ -0,0 +1,102 @@
+//! ### End user test
+//!
+//! Non-signaling RF test mode for production lines: transmit a carrier, or
+//! measure the received signal strength, on a given channel without any
+//! network. The module has to be deregistered, with the radio off, before
+//! entering the test mode, and is unusable for anything else until it leaves
+//! it again.
+pub mod responses;
+pub mod types;
+
+use atat::atat_derive::AtatCmd;
+use responses::RxMeasurement;
+use types::TestMode;
+
+use super::NoResponse;
+
+/// Longest `time` of a single [`RxTest`] or [`TxTest`]
+pub const MAX_TEST_TIME_MS: u16 = 10_000;
+
+/// End user test +UTEST
+///
+/// Enters or leaves the test mode.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UTEST", NoResponse, attempts = 1, timeout_ms = 10000)]
+pub struct SetTestMode {
+    /// [`TestMode::Normal`] or [`TestMode::Test`]
+    #[at_arg(position = 0)]
+    pub mode: TestMode,
+}
+
+/// End user test +UTEST=2
+///
+/// Measures the received signal strength on `rx_channel` of `band` for
+/// `time` ms.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UTEST", RxMeasurement, attempts = 1, timeout_ms = 15000)]
+pub struct RxTest {
+    #[at_arg(position = 0)]
+    mode: TestMode,
+    /// Band, in MHz for 2G, or the band number for 3G and LTE
+    #[at_arg(position = 1)]
+    pub band: u16,
+    /// ARFCN, UARFCN or EARFCN, depending on the RAT
+    #[at_arg(position = 2)]
+    pub rx_channel: u32,
+    /// Gain of the LNA, 0 being the highest
+    #[at_arg(position = 3)]
+    pub gain: u8,
+    /// Up to [`MAX_TEST_TIME_MS`]
+    #[at_arg(position = 4)]
+    pub time: u16,
+}
+
+impl RxTest {
+    pub fn new(band: u16, rx_channel: u32, gain: u8, time: u16) -> Self {
+        Self {
+            mode: TestMode::Rx,
+            band,
+            rx_channel,
+            gain,
+            time: time.min(MAX_TEST_TIME_MS),
+        }
+    }
+}
+
+/// End user test +UTEST=3
+///
+/// Transmits a carrier on `tx_channel` of `band` for `time` ms.
+#[derive(Clone, AtatCmd)]
+#[at_cmd("+UTEST", NoResponse, attempts = 1, timeout_ms = 15000)]
+pub struct TxTest {
+    #[at_arg(position = 0)]
+    mode: TestMode,
+    /// Band, in MHz for 2G, or the band number for 3G and LTE
+    #[at_arg(position = 1)]
+    pub band: u16,
+    /// ARFCN, UARFCN or EARFCN, depending on the RAT
+    #[at_arg(position = 2)]
+    pub tx_channel: u32,
+    /// Power control level for 2G, or the output power in dBm for 3G and LTE
+    #[at_arg(position = 3)]
+    pub power: i16,
+    /// Training sequence for 2G, 0 otherwise
+    #[at_arg(position = 4)]
+    pub train: u8,
+    /// Up to [`MAX_TEST_TIME_MS`]
+    #[at_arg(position = 5)]
+    pub time: u16,
+}
+
+impl TxTest {
+    pub fn new(band: u16, tx_channel: u32, power: i16, time: u16) -> Self {
+        Self {
+            mode: TestMode::Tx,
+            band,
+            tx_channel,
+            power,
+            train: 0,
+            time: time.min(MAX_TEST_TIME_MS),
+        }
+    }
+}
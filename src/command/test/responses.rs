@@ -0,0 +1,44 @@
+//! Responses for End user test Commands
+use atat::atat_derive::AtatResp;
+
+/// End user test +UTEST=2
+#[derive(Debug, Clone, PartialEq, Eq, AtatResp)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxMeasurement {
+    #[at_arg(position = 0)]
+    pub band: u16,
+    #[at_arg(position = 1)]
+    pub rx_channel: u32,
+    #[at_arg(position = 2)]
+    pub gain: u8,
+    #[at_arg(position = 3)]
+    pub time: u16,
+    /// Lowest RSSI measured, in dBm
+    #[at_arg(position = 4)]
+    pub min_rssi: i16,
+    /// Highest RSSI measured, in dBm
+    #[at_arg(position = 5)]
+    pub max_rssi: i16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rx_measurement() {
+        let res: RxMeasurement =
+            atat::serde_at::from_slice(b"+UTEST: 20,6300,0,1000,-98,-92").unwrap();
+        assert_eq!(
+            res,
+            RxMeasurement {
+                band: 20,
+                rx_channel: 6300,
+                gain: 0,
+                time: 1000,
+                min_rssi: -98,
+                max_rssi: -92,
+            }
+        );
+    }
+}
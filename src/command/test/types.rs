@@ -0,0 +1,15 @@
+//! Argument and parameter types used by End user test Commands and Responses
+use atat::atat_derive::AtatEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AtatEnum)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TestMode {
+    /// • 0 (default value): normal mode
+    Normal = 0,
+    /// • 1: non-signaling test mode
+    Test = 1,
+    /// • 2: RX test, only in test mode
+    Rx = 2,
+    /// • 3: TX test, only in test mode
+    Tx = 3,
+}
@@ -1,16 +1,24 @@
 use core::convert::Infallible;
+use embassy_time::Duration;
 use embedded_hal::digital::{ErrorType, InputPin, OutputPin, PinState};
 use embedded_io_async::{BufRead, Read, Write};
 
 use crate::{
     command::{
         control::types::BaudRate,
+        mobile_control::urc::IndicatorEvent,
+        network_service::responses::SignalQuality,
         networking::types::EmbeddedPortFilteringMode,
         psn::types::{ContextId, ProfileId},
     },
     DEFAULT_BAUD_RATE,
 };
 
+#[cfg(feature = "at-trace")]
+use crate::asynch::at_trace::AtTrace;
+#[cfg(feature = "internal-network-stack")]
+use crate::command::ip_transport_layer::types::DataConfiguration;
+
 pub struct NoPin;
 
 impl ErrorType for NoPin {
@@ -80,16 +88,25 @@ pub trait CellularConfig<'a> {
     type ResetPin: OutputPin;
     type PowerPin: OutputPin;
     type VintPin: InputPin;
+    type DtrPin: OutputPin;
 
     const AT_CONFIG: atat::Config = atat::Config::new();
 
     // Transport settings
+    /// Use RTS/CTS flow control. If the module does not answer with it, eg. as
+    /// RTS is not wired, init falls back to no flow control, see
+    /// `Control::flow_control_fallback`.
     const FLOW_CONTROL: bool = false;
     const BAUD_RATE: BaudRate = DEFAULT_BAUD_RATE;
 
     #[cfg(feature = "internal-network-stack")]
     const HEX_MODE: bool = true;
 
+    /// Parameters of the internal TCP/IP stack set at init, eg. the TCP
+    /// keepalive. The HEX mode is set from [`Self::HEX_MODE`] instead.
+    #[cfg(feature = "internal-network-stack")]
+    const DATA_CONFIGURATION: &'static [DataConfiguration] = &[];
+
     const EMBEDDED_PORT_FILTERING: EmbeddedPortFilteringMode =
         EmbeddedPortFilteringMode::Enable(6000, 6200);
 
@@ -101,6 +118,77 @@ pub trait CellularConfig<'a> {
     #[cfg(feature = "ppp")]
     const PPP_CONFIG: embassy_net_ppp::Config<'a>;
 
+    /// LCP echo keepalive of the PPP link, to detect a dead link within
+    /// seconds rather than on the next failing transfer. `None` disables it.
+    #[cfg(feature = "ppp")]
+    const PPP_LINK_SUPERVISION: Option<LinkSupervision> = None;
+
+    /// Retries of the power-on pulse, eg. for supplies that ramp up slowly.
+    /// Without `max_attempts`, each generic pull time is tried once, or only
+    /// the module's own pull time once the module is known.
+    const POWER_ON_RETRY: RetryPolicy = RetryPolicy::IMMEDIATE;
+
+    /// Retries of the whole power-on and init sequence.
+    const INIT_RETRY: RetryPolicy = RetryPolicy::IMMEDIATE;
+
+    /// Backoff between failed network attach and data context activation
+    /// attempts, kept across resets of the module. Rejections caused by the
+    /// SIM or the subscription stop the attempts altogether, until the desired
+    /// state is changed or another SIM is inserted. Congestion reported by the
+    /// network goes straight to `max_delay`, and `max_attempts` is not used.
+    const ATTACH_BACKOFF: RetryPolicy = RetryPolicy::ATTACH;
+
+    /// Give up initializing the module after this long, and report
+    /// [`InitError::Timeout`](crate::error::InitError::Timeout) through
+    /// `Control`. `None` retries forever.
+    const INIT_TIMEOUT: Option<Duration> = None;
+
+    /// Track the PSM deep sleep of the module, reported by +UUPSMR, or
+    /// detected by commands timing out while VINT is low. Commands sent while
+    /// the module sleeps wait for the runner to wake it with a pulse on
+    /// `PWR_ON`, at most [`MAX_PENDING_WAKE`] of them.
+    ///
+    /// The CMUX session has to survive the sleep, and the watchdog and signal
+    /// polling pause meanwhile.
+    const WAKE_FROM_PSM: bool = false;
+
+    /// +CIND indicators reported with +CIEV, as a bitmask of their indices
+    /// in [`IndicatorEvent`], "battchg" being bit 0, eg.
+    /// `1 << (IndicatorEvent::ROAM - 1)`. Changes keep
+    /// [`Control::indicators`](crate::asynch::control::Control::indicators)
+    /// up to date without polling. 0 disables +CIEV altogether, along with the
+    /// detection of SIM swaps.
+    const REPORTED_INDICATORS: u16 = 1 << (IndicatorEvent::SIMIND - 1);
+
+    /// How often to check for a SIM while none is inserted. The insertion is
+    /// picked up right away on modules reporting it with +CIEV, see
+    /// [`Self::REPORTED_INDICATORS`].
+    const SIM_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Number of commands of the runner in a row, its heartbeats included,
+    /// that have to time out before the AT interface is considered hung, and
+    /// the module reset.
+    const HANG_TIMEOUTS: u8 = 3;
+
+    /// Prefixes of URCs the crate does not know, eg. `"+UFOTASTAT"`. Lines
+    /// starting with one of them are handed out by
+    /// [`Control::custom_urc`](crate::asynch::control::Control::custom_urc)
+    /// as they are, and never taken as part of a response.
+    const CUSTOM_URCS: &'static [&'static str] = &[];
+
+    /// Polling of the signal quality by the runner, for
+    /// [`Control::signal_quality`](crate::asynch::control::Control::signal_quality).
+    /// `None` leaves it to the application.
+    const SIGNAL_MONITOR: Option<SignalMonitor> = None;
+
+    /// Sink of the raw AT traffic, eg. a
+    /// [`RingTrace`](crate::asynch::at_trace::RingTrace) to dump after a
+    /// failure. Called once, when the runner is created.
+    #[cfg(feature = "at-trace")]
+    fn at_trace(&mut self) -> Option<&'a mut dyn AtTrace> {
+        None
+    }
+
     fn reset_pin(&mut self) -> Option<&mut Self::ResetPin> {
         None
     }
@@ -113,9 +201,189 @@ pub trait CellularConfig<'a> {
         None
     }
 
+    /// DTR line of the module. When provided, UART power saving is enabled
+    /// with `AT+UPSV=3`, and DTR is asserted (driven low) while AT commands
+    /// are sent, or a PPP connection is up. The module may only sleep
+    /// while it is de-asserted.
+    fn dtr_pin(&mut self) -> Option<&mut Self::DtrPin> {
+        None
+    }
+
+    /// APN for the home network of a newly inserted SIM, looked up after a
+    /// SIM swap. [`Apn::None`] keeps the configured APNs.
     fn apn_lookup(&mut self, _mcc_mnc: (u16, u16)) -> Apn {
         Apn::None
     }
+
+    /// PIN to unlock the SIM with, if it is PIN protected.
+    ///
+    /// The PIN is only entered automatically while no attempt has been used
+    /// up, so a wrong PIN can never lead to a PUK locked SIM.
+    fn sim_pin(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Supervision of the PPP link with LCP Echo-Requests, sent while nothing
+/// has been received for `interval`. The link is considered dead once
+/// `max_failures` Echo-Requests in a row went unanswered. An `interval` of
+/// [`Duration::MAX`] never sends one.
+#[cfg(feature = "ppp")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkSupervision {
+    pub interval: Duration,
+    pub max_failures: u8,
+}
+
+/// Polling of the signal quality every `interval` while registered. The
+/// signal becomes usable once the RSRP reaches `rsrp_usable`, and unusable
+/// again once it drops below `rsrp_unusable`, which is lower so the state
+/// does not flap at the edge. Without an RSRP, eg. on 2G, the rxlev
+/// thresholds apply. All values are in the scales of +CESQ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SignalMonitor {
+    pub interval: Duration,
+    pub rsrp_usable: u8,
+    pub rsrp_unusable: u8,
+    pub rxlev_usable: u8,
+    pub rxlev_unusable: u8,
+}
+
+impl SignalMonitor {
+    /// Poll every 30 s. Usable from an RSRP of -115 dBm until below -121 dBm,
+    /// or an rxlev of -101 dBm until below -106 dBm.
+    pub const DEFAULT: Self = Self {
+        interval: Duration::from_secs(30),
+        rsrp_usable: 26,
+        rsrp_unusable: 20,
+        rxlev_usable: 10,
+        rxlev_unusable: 5,
+    };
+
+    /// Whether the signal of `quality` is usable, given whether it was
+    /// before.
+    pub fn is_usable(&self, quality: &SignalQuality, was_usable: bool) -> bool {
+        let (value, usable, unusable) = match (quality.rsrp, quality.rxlev) {
+            (rsrp, _) if rsrp != 255 => (rsrp, self.rsrp_usable, self.rsrp_unusable),
+            (_, rxlev) if rxlev != 99 => (rxlev, self.rxlev_usable, self.rxlev_unusable),
+            // Not known or not detectable
+            _ => return false,
+        };
+
+        if was_usable {
+            value >= unusable
+        } else {
+            value >= usable
+        }
+    }
+}
+
+impl Default for SignalMonitor {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Retry policy with exponential backoff, see [`JitterMode`] for how the
+/// delay before each retry is picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RetryPolicy {
+    /// Number of attempts before giving up. `None` retries forever.
+    pub max_attempts: Option<u32>,
+    pub base_delay: Duration,
+    pub factor: u32,
+    pub max_delay: Duration,
+    /// Random spread added to the delay, with [`JitterMode::Additive`]
+    pub jitter: Duration,
+    pub jitter_mode: JitterMode,
+}
+
+/// How [`RetryPolicy`] randomizes the delays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum JitterMode {
+    /// The delay before retry `n` (starting at 0) is `base_delay * factor^n`,
+    /// capped at `max_delay`, plus a random spread of up to `jitter`.
+    Additive,
+    /// The delay is picked at random between `base_delay` and `factor` times
+    /// the previous delay, capped at `max_delay`, so that devices failing at
+    /// the same time don't retry in lockstep.
+    Decorrelated,
+}
+
+impl RetryPolicy {
+    /// Retry forever, without any delay in between.
+    pub const IMMEDIATE: Self = Self {
+        max_attempts: None,
+        base_delay: Duration::from_ticks(0),
+        factor: 1,
+        max_delay: Duration::from_ticks(0),
+        jitter: Duration::from_ticks(0),
+        jitter_mode: JitterMode::Additive,
+    };
+
+    /// Network attach backoff, 5 s after the first failure, growing up to
+    /// 15 min with decorrelated jitter.
+    pub const ATTACH: Self = Self {
+        max_attempts: None,
+        base_delay: Duration::from_secs(5),
+        factor: 3,
+        max_delay: Duration::from_secs(15 * 60),
+        jitter: Duration::from_ticks(0),
+        jitter_mode: JitterMode::Decorrelated,
+    };
+
+    /// Delay before the given retry, following one that was delayed by
+    /// `previous`. `entropy` picks the jitter, eg. from a free running timer.
+    pub fn next_delay(&self, retry: u32, previous: Duration, entropy: u64) -> Duration {
+        match self.jitter_mode {
+            JitterMode::Additive => self.delay(retry, entropy),
+            JitterMode::Decorrelated => {
+                let base = self.base_delay.as_ticks();
+                let max = self.max_delay.as_ticks().max(base);
+                let upper = previous
+                    .as_ticks()
+                    .saturating_mul(self.factor as u64)
+                    .clamp(base, max);
+
+                let spread = match (upper - base).checked_add(1) {
+                    Some(range) => entropy % range,
+                    None => entropy,
+                };
+                Duration::from_ticks(base + spread)
+            }
+        }
+    }
+
+    /// Delay before the given retry with [`JitterMode::Additive`]. `entropy`
+    /// picks the jitter, eg. from a free running timer.
+    pub fn delay(&self, retry: u32, entropy: u64) -> Duration {
+        let ticks = self
+            .base_delay
+            .as_ticks()
+            .saturating_mul((self.factor as u64).saturating_pow(retry))
+            .min(self.max_delay.as_ticks());
+
+        let jitter = match self.jitter.as_ticks() {
+            0 => 0,
+            jitter => entropy % (jitter + 1),
+        };
+
+        Duration::from_ticks(ticks.saturating_add(jitter))
+    }
+
+    pub fn is_exhausted(&self, attempts: u32) -> bool {
+        self.max_attempts.is_some_and(|max| attempts >= max)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::IMMEDIATE
+    }
 }
 
 pub trait Transport: Write + Read + BufRead {
@@ -143,8 +411,166 @@ pub enum Apn {
     Automatic,
 }
 
+/// Maximum number of APNs to fall back between.
+pub const MAX_APN_CANDIDATES: usize = 4;
+
+/// Maximum number of commands waiting for the module to wake from PSM, see
+/// [`CellularConfig::WAKE_FROM_PSM`]. Further commands fail with
+/// [`Error::Busy`](crate::error::Error::Busy).
+pub const MAX_PENDING_WAKE: usize = 4;
+
+/// Maximum number of receivers of each of
+/// [`Control::operation_state_receiver`](crate::asynch::control::Control::operation_state_receiver)
+/// and
+/// [`Control::link_state_receiver`](crate::asynch::control::Control::link_state_receiver)
+/// at a time.
+pub const MAX_STATE_RECEIVERS: usize = 2;
+
+/// Number of diagnostic events kept for the slowest subscriber of
+/// [`Control::diagnostics`](crate::asynch::control::Control::diagnostics),
+/// before it loses the oldest.
+pub const DIAGNOSTICS_CAPACITY: usize = 8;
+
+/// Maximum number of subscribers of
+/// [`Control::diagnostics`](crate::asynch::control::Control::diagnostics)
+/// at a time.
+pub const MAX_DIAGNOSTICS_SUBSCRIBERS: usize = 2;
+
+/// Maximum number of names whose addresses are kept for the
+/// [`Dns`](embedded_nal_async::Dns) implementation of
+/// [`Control`](crate::asynch::control::Control).
+#[cfg(feature = "internal-network-stack")]
+pub const MAX_DNS_CACHE_ENTRIES: usize = 4;
+
+/// How long the addresses of a resolved name are handed out in turn, before
+/// the name is resolved again.
+#[cfg(feature = "internal-network-stack")]
+pub const DNS_ROTATION_WINDOW: Duration = Duration::from_secs(300);
+
+/// Maximum number of internal sockets tracked at a time, the most the
+/// modules support.
+#[cfg(feature = "internal-network-stack")]
+pub const MAX_SOCKETS: usize = 7;
+
+/// How often a [`TcpSocket`](crate::asynch::socket::TcpSocket) waiting for
+/// data asks the module, by default. Received data is announced by +UUSORD,
+/// so this only matters if the URC is lost.
+#[cfg(feature = "internal-network-stack")]
+pub const SOCKET_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often [`TcpSocket::is_connected`](crate::asynch::socket::TcpSocket::is_connected)
+/// asks the module for the TCP status, by default.
+#[cfg(feature = "internal-network-stack")]
+pub const SOCKET_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long [`TcpSocket::close`](crate::asynch::socket::TcpSocket::close)
+/// waits for the data written to be acknowledged by the peer, by default.
+#[cfg(feature = "internal-network-stack")]
+pub const SOCKET_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl Apn {
+    /// Whether both refer to the same APN, regardless of credentials.
+    pub(crate) fn same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Apn::Given { name: n1, .. }, Apn::Given { name: n2, .. }) => n1 == n2,
+            (Apn::None, Apn::None) => true,
+            #[cfg(any(feature = "automatic-apn"))]
+            (Apn::Automatic, Apn::Automatic) => true,
+            _ => false,
+        }
+    }
+}
+
 impl Default for Apn {
     fn default() -> Self {
         Self::None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_backoff() {
+        let policy = RetryPolicy {
+            max_attempts: Some(5),
+            base_delay: Duration::from_millis(500),
+            factor: 2,
+            max_delay: Duration::from_secs(3),
+            jitter: Duration::from_ticks(0),
+            jitter_mode: JitterMode::Additive,
+        };
+
+        assert_eq!(policy.delay(0, 1234), Duration::from_millis(500));
+        assert_eq!(policy.delay(1, 1234), Duration::from_millis(1000));
+        assert_eq!(policy.delay(2, 1234), Duration::from_millis(2000));
+        assert_eq!(policy.delay(3, 1234), Duration::from_secs(3));
+        assert_eq!(policy.delay(60, 1234), Duration::from_secs(3));
+
+        assert!(!policy.is_exhausted(4));
+        assert!(policy.is_exhausted(5));
+        assert!(!RetryPolicy::IMMEDIATE.is_exhausted(u32::MAX));
+        assert_eq!(
+            RetryPolicy::IMMEDIATE.delay(7, 1234),
+            Duration::from_ticks(0)
+        );
+    }
+
+    #[test]
+    fn retry_jitter() {
+        let policy = RetryPolicy {
+            jitter: Duration::from_millis(100),
+            ..RetryPolicy::IMMEDIATE
+        };
+
+        for entropy in [0, 1, 99, 12345, u64::MAX] {
+            assert!(policy.delay(0, entropy) <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn retry_decorrelated() {
+        let policy = RetryPolicy::ATTACH;
+
+        assert_eq!(
+            policy.next_delay(0, Duration::from_ticks(0), 1234),
+            policy.base_delay
+        );
+        for entropy in [0, 1, 99, 12345, u64::MAX] {
+            let delay = policy.next_delay(1, Duration::from_secs(10), entropy);
+            assert!(delay >= policy.base_delay);
+            assert!(delay <= Duration::from_secs(30));
+            let capped = policy.next_delay(9, Duration::from_secs(3600), entropy);
+            assert!(capped <= policy.max_delay);
+        }
+    }
+
+    #[test]
+    fn signal_hysteresis() {
+        let monitor = SignalMonitor::DEFAULT;
+        let lte = |rsrp| SignalQuality {
+            rxlev: 99,
+            ber: 99,
+            rscp: 255,
+            ecn0: 255,
+            rsrq: 255,
+            rsrp,
+        };
+
+        assert!(!monitor.is_usable(&lte(23), false));
+        assert!(monitor.is_usable(&lte(26), false));
+        // Between the thresholds, it stays as it was
+        assert!(monitor.is_usable(&lte(23), true));
+        assert!(!monitor.is_usable(&lte(19), true));
+        assert!(!monitor.is_usable(&lte(255), true));
+
+        // No RSRP on 2G
+        let gsm = SignalQuality {
+            rxlev: 7,
+            ..lte(255)
+        };
+        assert!(!monitor.is_usable(&gsm, false));
+        assert!(monitor.is_usable(&gsm, true));
+    }
+}
@@ -1,4 +1,15 @@
+#[cfg(feature = "http")]
+use crate::command::http::responses::HttpError;
+use crate::command::ip_transport_layer::types::SocketErrorKind;
+use crate::command::mobile_control::types::{CmeError, CmsError, ExtendedErrorCause};
+#[cfg(feature = "mqtt")]
+use crate::command::mqtt::responses::MqttError;
 use crate::command::network_service::types::Error as NetworkError;
+use crate::command::psn::types::RejectCause;
+use crate::command::sim_access::types::StatusWords;
+#[cfg(feature = "sms")]
+use crate::command::sms::pdu::PduError;
+use crate::command::system_features::types::FirmwareInstallError;
 
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -8,12 +19,45 @@ pub enum GenericError {
     Unsupported,
 }
 
+/// Why bringing up the module was given up on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InitError {
+    /// All `CellularConfig::INIT_RETRY` attempts failed
+    AttemptsExhausted,
+    /// The module did not come up within `CellularConfig::INIT_TIMEOUT`
+    Timeout,
+    /// The configuration does not fit the detected module, so retrying is
+    /// futile
+    Config(ConfigError),
+}
+
+/// An identifier out of the range the detected module supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// PDP context ids range from 1 to `max`
+    ContextId { cid: u8, max: u8 },
+    /// PSD profile ids range from 0 to `max`
+    ProfileId { profile_id: u8, max: u8 },
+    /// A security profile that was neither set up with
+    /// `Control::configure_security_profile` nor registered with
+    /// `Control::register_security_profile`, or out of the range 0-4
+    SecurityProfile { profile_id: u8 },
+}
+
 #[derive(Debug, PartialEq)]
 #[non_exhaustive]
 pub enum Error {
     // General device errors
     BaudDetection,
+    /// The runner gave up initializing the module
+    Init(InitError),
     SimCard,
+    /// The SIM is PIN locked, and no (valid) PIN is configured
+    SimPinRequired,
+    /// The SIM is PUK locked, after too many wrong PIN attempts
+    SimPukRequired,
     Busy,
     Uninitialized,
     StateTimeout,
@@ -21,12 +65,55 @@ pub enum Error {
     AttachTimeout,
     ContextActivationTimeout,
     InvalidStateTransition,
+    /// A provided argument or buffer does not fit the data
+    Overflow,
+    /// A context or profile id the module does not support
+    Config(ConfigError),
 
     // Network errors
     Network(NetworkError),
+    /// Attach, registration or context activation was rejected, with the
+    /// cause reported by +CEER
+    Rejected(ExtendedErrorCause),
+    /// The network rejected the attach for a reason retrying does not fix,
+    /// eg. the SIM or the subscription, so the runner stopped trying
+    AttachRejected(RejectCause),
 
     // Service specific errors
     // DataService(DataServiceError),
+    /// HTTP request failed, as reported by +UHTTPER
+    #[cfg(feature = "http")]
+    Http(HttpError),
+    /// MQTT action failed, as reported by +UMQTTER
+    #[cfg(feature = "mqtt")]
+    Mqtt(MqttError),
+    FileNotFound,
+    NotEnoughSpace,
+    /// A file read did not add up to the size the file system reports for
+    /// it, as when the file is written to while it is read
+    FileSizeMismatch,
+    FirmwareInstall(FirmwareInstallError),
+    /// SIM access command rejected by the SIM, with the SW1 and SW2 status
+    /// words
+    SimAccess(StatusWords),
+    /// Socket operation failed, with the error the module reports for the
+    /// socket
+    Socket(SocketErrorKind),
+    /// The local port of an internal socket is outside of the
+    /// `CellularConfig::EMBEDDED_PORT_FILTERING` range, so its traffic would
+    /// go to the PPP connection instead
+    PortNotFiltered(u16),
+    /// A name resolved to no address of the requested type, or an address to
+    /// no name
+    HostNotFound,
+
+    /// Command failed with a +CME ERROR
+    Cme(CmeError),
+    /// SMS command failed with a +CMS ERROR
+    Cms(CmsError),
+    /// An SMS could not be encoded, or a received one decoded
+    #[cfg(feature = "sms")]
+    Pdu(PduError),
 
     // Generic shared errors, e.g. from `core::`
     Generic(GenericError),
@@ -51,6 +138,9 @@ impl defmt::Format for Error {
     fn format(&self, f: defmt::Formatter<'_>) {
         match self {
             Self::BaudDetection => defmt::write!(f, "BaudDetection"),
+            Self::Init(e) => defmt::write!(f, "Init({:?})", e),
+            Self::SimPinRequired => defmt::write!(f, "SimPinRequired"),
+            Self::SimPukRequired => defmt::write!(f, "SimPukRequired"),
             Self::Busy => defmt::write!(f, "Busy"),
             Self::Uninitialized => defmt::write!(f, "Uninitialized"),
             Self::StateTimeout => defmt::write!(f, "StateTimeout"),
@@ -58,8 +148,28 @@ impl defmt::Format for Error {
             Self::AttachTimeout => defmt::write!(f, "AttachTimeout"),
             Self::ContextActivationTimeout => defmt::write!(f, "ContextActivationTimeout"),
             Self::InvalidStateTransition => defmt::write!(f, "InvalidStateTransition"),
+            Self::Overflow => defmt::write!(f, "Overflow"),
+            Self::Config(e) => defmt::write!(f, "Config({:?})", e),
             Self::Network(e) => defmt::write!(f, "Network({:?})", e),
+            Self::Rejected(e) => defmt::write!(f, "Rejected({:?})", e),
+            Self::AttachRejected(e) => defmt::write!(f, "AttachRejected({:?})", e),
             // Self::DataService(e) => defmt::write!(f, "DataService({:?})", e),
+            #[cfg(feature = "http")]
+            Self::Http(e) => defmt::write!(f, "Http({:?})", e),
+            #[cfg(feature = "mqtt")]
+            Self::Mqtt(e) => defmt::write!(f, "Mqtt({:?})", e),
+            Self::FileNotFound => defmt::write!(f, "FileNotFound"),
+            Self::NotEnoughSpace => defmt::write!(f, "NotEnoughSpace"),
+            Self::FileSizeMismatch => defmt::write!(f, "FileSizeMismatch"),
+            Self::FirmwareInstall(e) => defmt::write!(f, "FirmwareInstall({:?})", e),
+            Self::SimAccess(e) => defmt::write!(f, "SimAccess({:?})", e),
+            Self::Socket(e) => defmt::write!(f, "Socket({:?})", e),
+            Self::PortNotFiltered(port) => defmt::write!(f, "PortNotFiltered({})", port),
+            Self::HostNotFound => defmt::write!(f, "HostNotFound"),
+            Self::Cme(e) => defmt::write!(f, "Cme({:?})", e),
+            Self::Cms(e) => defmt::write!(f, "Cms({:?})", e),
+            #[cfg(feature = "sms")]
+            Self::Pdu(e) => defmt::write!(f, "Pdu({:?})", e),
             Self::Generic(e) => defmt::write!(f, "Generic({:?})", e),
             Self::Atat(e) => defmt::write!(f, "Atat({:?})", e),
             Self::_Unknown => defmt::write!(f, "_Unknown"),
@@ -68,6 +178,53 @@ impl defmt::Format for Error {
     }
 }
 
+impl embedded_io_async::Error for Error {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        use embedded_io_async::ErrorKind;
+
+        match self {
+            Self::Socket(e) => match e {
+                SocketErrorKind::BrokenPipe => ErrorKind::BrokenPipe,
+                SocketErrorKind::AddrInUse => ErrorKind::AddrInUse,
+                SocketErrorKind::ConnectionReset => ErrorKind::ConnectionReset,
+                SocketErrorKind::NoBuffers => ErrorKind::OutOfMemory,
+                SocketErrorKind::NotConnected | SocketErrorKind::BadSocket => {
+                    ErrorKind::NotConnected
+                }
+                SocketErrorKind::TimedOut => ErrorKind::TimedOut,
+                SocketErrorKind::ConnectionRefused => ErrorKind::ConnectionRefused,
+                _ => ErrorKind::Other,
+            },
+            Self::InvalidStateTransition => ErrorKind::NotConnected,
+            Self::Overflow => ErrorKind::InvalidInput,
+            Self::HostNotFound => ErrorKind::NotFound,
+            Self::Generic(GenericError::Timeout) => ErrorKind::TimedOut,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl embedded_nal::TcpError for Error {
+    fn kind(&self) -> embedded_nal::TcpErrorKind {
+        match self {
+            Self::Socket(
+                SocketErrorKind::BrokenPipe
+                | SocketErrorKind::ConnectionReset
+                | SocketErrorKind::NotConnected
+                | SocketErrorKind::BadSocket,
+            ) => embedded_nal::TcpErrorKind::PipeClosed,
+            _ => embedded_nal::TcpErrorKind::Other,
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
 impl From<atat::Error> for Error {
     fn from(e: atat::Error) -> Self {
         Self::Atat(e)
@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{InitCommand, ModuleParams, SocketContextBinding, UCGED};
 use embassy_time::Duration;
 
 #[derive(Debug, Clone, Copy)]
@@ -35,4 +35,13 @@ impl ModuleParams for LaraR6 {
     fn max_num_simultaneous_rats(&self) -> u8 {
         3
     }
+    fn init_commands(&self) -> &'static [InitCommand] {
+        UCGED
+    }
+    fn socket_hostnames(&self) -> bool {
+        true
+    }
+    fn socket_context(&self) -> SocketContextBinding {
+        SocketContextBinding::Cid
+    }
 }
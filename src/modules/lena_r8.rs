@@ -25,4 +25,7 @@ impl ModuleParams for LenaR8 {
     fn at_c_fun_reboot_command(&self) -> Functionality {
         Functionality::SilentResetWithSimReset
     }
+    fn uses_cgauth(&self) -> bool {
+        true
+    }
 }
@@ -15,9 +15,47 @@ pub(crate) mod sara_u201;
 #[cfg(any(feature = "any-module", feature = "toby-r2"))]
 pub(crate) mod toby_r2;
 
-use crate::command::{general::responses::ModelId, mobile_control::types::Functionality};
+use crate::command::{
+    general::responses::ModelId,
+    mobile_control::types::Functionality,
+    psn::types::{ContextId, ProfileId},
+};
+use crate::error::ConfigError;
 use embassy_time::Duration;
 
+/// A module specific configuration command, sent after the common init
+/// sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InitCommand {
+    /// Start the cell environment reporting of +UCGED in `mode`
+    CellEnvironmentReporting { mode: u8 },
+}
+
+/// How a socket of the internal TCP/IP stack selects the PDP context it
+/// sends its data over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SocketContextBinding {
+    /// Sockets always use the default context
+    Unsupported,
+    /// The context is given with the `<cid>` of +USOCR
+    Cid,
+    /// Sockets use the context the active PSD profile is mapped to with
+    /// +UPSD, so only that one
+    PsdProfile,
+}
+
+/// The +UCGED reporting selected with the `ucged` features, for the modules
+/// supporting it
+const UCGED: &[InitCommand] = if cfg!(feature = "ucged") {
+    &[InitCommand::CellEnvironmentReporting {
+        mode: if cfg!(feature = "ucged5") { 5 } else { 2 },
+    }]
+} else {
+    &[]
+};
+
 pub trait ModuleParams: Copy {
     /// The time for which PWR_ON must be pulled down to effect power-on
     fn power_on_pull_time(&self) -> Option<Duration> {
@@ -29,7 +67,8 @@ pub trait ModuleParams: Copy {
         Duration::from_millis(3100)
     }
 
-    /// How long to wait before the module is ready after boot
+    /// Upper bound on the time the module takes to answer AT after boot.
+    /// The driver proceeds as soon as it does.
     fn boot_wait(&self) -> Duration {
         Duration::from_secs(5)
     }
@@ -57,6 +96,12 @@ pub trait ModuleParams: Copy {
         Functionality::AirplaneMode
     }
 
+    /// The type of AT+CFUN state the +UTEST test mode has to be entered
+    /// from: either 0, or 5 on the 2G and 3G modules
+    fn test_mode_cfun(&self) -> Functionality {
+        Functionality::Minimum
+    }
+
     /// How long the reset line has to be held for to reset the cellular module
     fn reset_hold(&self) -> Duration {
         Duration::from_millis(16500)
@@ -72,6 +117,59 @@ pub trait ModuleParams: Copy {
     fn at_c_fun_reboot_command(&self) -> Functionality {
         Functionality::SilentReset
     }
+
+    /// Whether the PDP context authentication is configured with the 3GPP
+    /// +CGAUTH rather than +UAUTHREQ, which some firmwares deprecate
+    fn uses_cgauth(&self) -> bool {
+        false
+    }
+
+    /// Module specific commands to send after the common init sequence
+    fn init_commands(&self) -> &'static [InitCommand] {
+        &[]
+    }
+
+    /// Whether +USOCO and +USOST take a domain name as the remote host, and
+    /// resolve it themselves
+    fn socket_hostnames(&self) -> bool {
+        false
+    }
+
+    /// How +USOCR selects the PDP context of a socket
+    fn socket_context(&self) -> SocketContextBinding {
+        SocketContextBinding::Unsupported
+    }
+
+    /// The highest PDP context id, counting from 1
+    fn max_contexts(&self) -> u8 {
+        8
+    }
+
+    /// The number of PSD profiles of +UPSD, counting from 0
+    fn max_psd_profiles(&self) -> u8 {
+        7
+    }
+
+    fn check_context_id(&self, cid: ContextId) -> Result<(), ConfigError> {
+        let max = self.max_contexts();
+        if (1..=max).contains(&cid.0) {
+            Ok(())
+        } else {
+            Err(ConfigError::ContextId { cid: cid.0, max })
+        }
+    }
+
+    fn check_profile_id(&self, profile_id: ProfileId) -> Result<(), ConfigError> {
+        let max = self.max_psd_profiles() - 1;
+        if profile_id.0 <= max {
+            Ok(())
+        } else {
+            Err(ConfigError::ProfileId {
+                profile_id: profile_id.0,
+                max,
+            })
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -114,6 +212,17 @@ impl Module {
     }
 }
 
+/// Data-only variants, without voice call support.
+#[cfg(feature = "audio")]
+const DATA_ONLY_MODELS: &[&str] = &["LARA-L6004D", "LARA-R6001D", "LARA-R6401D"];
+
+/// Whether the module with the given model identification supports voice
+/// calls.
+#[cfg(feature = "audio")]
+pub(crate) fn supports_voice(model: &str) -> bool {
+    !DATA_ONLY_MODELS.contains(&model)
+}
+
 macro_rules! inner {
     ($self: ident, $fn: ident) => {
         match $self {
@@ -167,6 +276,10 @@ impl ModuleParams for Module {
         inner!(self, radio_off_cfun)
     }
 
+    fn test_mode_cfun(&self) -> Functionality {
+        inner!(self, test_mode_cfun)
+    }
+
     fn reset_hold(&self) -> Duration {
         inner!(self, reset_hold)
     }
@@ -178,6 +291,30 @@ impl ModuleParams for Module {
     fn at_c_fun_reboot_command(&self) -> Functionality {
         inner!(self, at_c_fun_reboot_command)
     }
+
+    fn uses_cgauth(&self) -> bool {
+        inner!(self, uses_cgauth)
+    }
+
+    fn init_commands(&self) -> &'static [InitCommand] {
+        inner!(self, init_commands)
+    }
+
+    fn socket_hostnames(&self) -> bool {
+        inner!(self, socket_hostnames)
+    }
+
+    fn socket_context(&self) -> SocketContextBinding {
+        inner!(self, socket_context)
+    }
+
+    fn max_contexts(&self) -> u8 {
+        inner!(self, max_contexts)
+    }
+
+    fn max_psd_profiles(&self) -> u8 {
+        inner!(self, max_psd_profiles)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -195,4 +332,44 @@ impl ModuleParams for Generic {
     fn power_down_wait(&self) -> Duration {
         Duration::from_secs(5)
     }
+
+    // A module built for by itself, but not recognized from its model ID,
+    // still gets its setup
+    fn init_commands(&self) -> &'static [InitCommand] {
+        if cfg!(all(
+            not(feature = "any-module"),
+            any(
+                feature = "sara-r410m",
+                feature = "sara-r412m",
+                feature = "sara-r422",
+                feature = "lara-r6"
+            )
+        )) {
+            UCGED
+        } else {
+            &[]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_ranges() {
+        assert_eq!(Generic.check_context_id(ContextId(8)), Ok(()));
+        assert_eq!(
+            Generic.check_context_id(ContextId(0)),
+            Err(ConfigError::ContextId { cid: 0, max: 8 })
+        );
+        assert_eq!(Generic.check_profile_id(ProfileId(6)), Ok(()));
+        assert_eq!(
+            Generic.check_profile_id(ProfileId(7)),
+            Err(ConfigError::ProfileId {
+                profile_id: 7,
+                max: 6
+            })
+        );
+    }
 }
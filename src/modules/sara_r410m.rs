@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{InitCommand, ModuleParams, UCGED};
 use crate::command::mobile_control::types::Functionality;
 use embassy_time::Duration;
 
@@ -19,4 +19,10 @@ impl ModuleParams for SaraR410m {
     fn max_num_simultaneous_rats(&self) -> u8 {
         2
     }
+    fn init_commands(&self) -> &'static [InitCommand] {
+        UCGED
+    }
+    fn max_contexts(&self) -> u8 {
+        1
+    }
 }
@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{InitCommand, ModuleParams, UCGED};
 use crate::command::mobile_control::types::Functionality;
 use embassy_time::Duration;
 
@@ -26,4 +26,10 @@ impl ModuleParams for SaraR412m {
     fn at_c_fun_reboot_command(&self) -> Functionality {
         Functionality::SilentReset
     }
+    fn init_commands(&self) -> &'static [InitCommand] {
+        UCGED
+    }
+    fn max_contexts(&self) -> u8 {
+        1
+    }
 }
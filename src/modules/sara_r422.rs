@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{InitCommand, ModuleParams, UCGED};
 use crate::command::mobile_control::types::Functionality;
 use embassy_time::Duration;
 
@@ -22,4 +22,7 @@ impl ModuleParams for SaraR422 {
     fn max_num_simultaneous_rats(&self) -> u8 {
         3
     }
+    fn init_commands(&self) -> &'static [InitCommand] {
+        UCGED
+    }
 }
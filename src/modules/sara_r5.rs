@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{ModuleParams, SocketContextBinding};
 use crate::command::mobile_control::types::Functionality;
 use embassy_time::Duration;
 
@@ -31,4 +31,10 @@ impl ModuleParams for SaraR5 {
     fn at_c_fun_reboot_command(&self) -> Functionality {
         Functionality::SilentResetWithSimReset
     }
+    fn socket_hostnames(&self) -> bool {
+        true
+    }
+    fn socket_context(&self) -> SocketContextBinding {
+        SocketContextBinding::PsdProfile
+    }
 }
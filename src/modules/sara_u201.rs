@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{ModuleParams, SocketContextBinding};
 use crate::command::mobile_control::types::Functionality;
 use embassy_time::Duration;
 
@@ -19,10 +19,16 @@ impl ModuleParams for SaraU201 {
     fn command_delay_default(&self) -> Duration {
         Duration::from_millis(20)
     }
+    fn test_mode_cfun(&self) -> Functionality {
+        Functionality::TestMode
+    }
     fn reset_hold(&self) -> Duration {
         Duration::from_millis(75)
     }
     fn max_num_simultaneous_rats(&self) -> u8 {
         2
     }
+    fn socket_context(&self) -> SocketContextBinding {
+        SocketContextBinding::PsdProfile
+    }
 }
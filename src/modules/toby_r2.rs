@@ -1,4 +1,4 @@
-use super::ModuleParams;
+use super::{ModuleParams, SocketContextBinding};
 use crate::command::mobile_control::types::Functionality;
 use embassy_time::Duration;
 
@@ -16,6 +16,9 @@ impl ModuleParams for TobyR2 {
     fn command_delay_default(&self) -> Duration {
         Duration::from_millis(20)
     }
+    fn test_mode_cfun(&self) -> Functionality {
+        Functionality::TestMode
+    }
     fn reset_hold(&self) -> Duration {
         Duration::from_millis(50)
     }
@@ -23,4 +26,7 @@ impl ModuleParams for TobyR2 {
         // TODO: Is this correct?
         3
     }
+    fn socket_context(&self) -> SocketContextBinding {
+        SocketContextBinding::Cid
+    }
 }
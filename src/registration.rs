@@ -6,7 +6,7 @@ use crate::command::{
     },
     psn::{
         responses::{EPSNetworkRegistrationStatus, GPRSNetworkRegistrationStatus},
-        types::{EPSNetworkRegistrationStat, GPRSNetworkRegistrationStat},
+        types::{EPSNetworkRegistrationStat, GPRSNetworkRegistrationStat, RejectCause},
         urc::{EPSNetworkRegistration, GPRSNetworkRegistration},
     },
 };
@@ -140,6 +140,16 @@ pub struct RegistrationParams {
 
     cell_id: Option<String<8>>,
     lac: Option<String<4>>,
+    /// Cause from a +CxREG with n=3 or 5, for rejected registrations. The MM
+    /// and GMM causes of +CREG and +CGREG share the numbering of the EMM
+    /// ones.
+    reject_cause: Option<RejectCause>,
+    /// Active time and periodic TAU from a +CEREG with n=4 or 5
+    active_time: Option<Duration>,
+    periodic_tau: Option<Duration>,
+    /// Whether this is a URC, ie. the network just took part in a
+    /// registration or tracking area update, rather than the read command
+    urc: bool,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -211,6 +221,18 @@ pub struct RegistrationState {
     /// Current Radio Access Technology (2G/3G/4G etc.)
     pub(crate) current_act: Option<RatAct>,
 
+    /// Cause of the last rejected EPS registration, cleared once registered
+    pub(crate) reject_cause: Option<RejectCause>,
+
+    /// PSM active time (T3324) granted with the last EPS registration, None
+    /// if PSM is not granted or not reported
+    pub(crate) active_time: Option<Duration>,
+    /// Periodic TAU time (T3412) granted with the last EPS registration
+    pub(crate) periodic_tau: Option<Duration>,
+    /// When the module last reported an EPS registration or tracking area
+    /// update
+    pub(crate) last_tau: Option<Instant>,
+
     #[cfg(not(feature = "use-upsd-context-activation"))]
     pub(crate) profile_state: ProfileState,
 }
@@ -229,6 +251,10 @@ impl RegistrationState {
             eps: CellularRegistrationStatus::new(),
             cgi: CellularGlobalIdentity::new(),
             current_act: None,
+            reject_cause: None,
+            active_time: None,
+            periodic_tau: None,
+            last_tau: None,
 
             #[cfg(not(feature = "use-upsd-context-activation"))]
             profile_state: ProfileState::Unknown,
@@ -252,10 +278,19 @@ impl RegistrationState {
         self.eps.get_status() == Status::Denied
     }
 
+    /// Cause of the last rejected EPS registration, if the module reported one
+    pub fn reject_cause(&self) -> Option<RejectCause> {
+        self.reject_cause
+    }
+
     pub fn reset(&mut self) {
         self.csd.reset();
         self.psd.reset();
         self.eps.reset();
+        self.reject_cause = None;
+        self.active_time = None;
+        self.periodic_tau = None;
+        self.last_tau = None;
     }
 
     /// Compare and set registration state, returning true if RAT changed
@@ -269,6 +304,17 @@ impl RegistrationState {
             }
             RegType::Cereg => {
                 self.eps.set_status(new_params.status);
+                if self.eps.registered() {
+                    self.reject_cause = None;
+                    self.active_time = new_params.active_time;
+                    self.periodic_tau = new_params.periodic_tau;
+                    if new_params.urc {
+                        self.last_tau = self.eps.updated();
+                    }
+                } else if new_params.reject_cause.is_some() {
+                    warn!("EPS registration rejected: {:?}", new_params.reject_cause);
+                    self.reject_cause = new_params.reject_cause;
+                }
             }
             RegType::Unknown => {
                 error!("unknown reg type");
@@ -304,8 +350,15 @@ impl From<NetworkRegistration> for RegistrationParams {
             act: None,
             reg_type: RegType::Creg,
             status: v.stat.into(),
-            cell_id: None,
-            lac: None,
+            cell_id: v.ci,
+            lac: v.lac,
+            reject_cause: v
+                .cause_type
+                .zip(v.reject_cause)
+                .map(|(cause_type, cause)| RejectCause::new(cause_type, cause)),
+            active_time: None,
+            periodic_tau: None,
+            urc: true,
         }
     }
 }
@@ -319,6 +372,10 @@ impl From<NetworkRegistrationStatus> for RegistrationParams {
             status: v.stat.into(),
             cell_id: v.ci,
             lac: v.lac,
+            reject_cause: None,
+            active_time: None,
+            periodic_tau: None,
+            urc: false,
         }
     }
 }
@@ -331,6 +388,13 @@ impl From<GPRSNetworkRegistration> for RegistrationParams {
             status: v.stat.into(),
             cell_id: v.ci,
             lac: v.lac,
+            reject_cause: v
+                .cause_type
+                .zip(v.reject_cause)
+                .map(|(cause_type, cause)| RejectCause::new(cause_type, cause)),
+            active_time: None,
+            periodic_tau: None,
+            urc: true,
         }
     }
 }
@@ -343,6 +407,10 @@ impl From<GPRSNetworkRegistrationStatus> for RegistrationParams {
             cell_id: v.ci,
             lac: v.lac,
             act: v.act,
+            reject_cause: None,
+            active_time: None,
+            periodic_tau: None,
+            urc: false,
         }
     }
 }
@@ -355,6 +423,13 @@ impl From<EPSNetworkRegistration> for RegistrationParams {
             cell_id: v.ci,
             lac: v.tac,
             act: v.act,
+            reject_cause: v
+                .cause_type
+                .zip(v.reject_cause)
+                .map(|(cause_type, cause)| RejectCause::new(cause_type, cause)),
+            active_time: v.active_time.as_deref().and_then(gprs_timer_2),
+            periodic_tau: v.periodic_tau.as_deref().and_then(gprs_timer_3),
+            urc: true,
         }
     }
 }
@@ -367,10 +442,56 @@ impl From<EPSNetworkRegistrationStatus> for RegistrationParams {
             cell_id: v.ci,
             lac: v.tac,
             act: v.act,
+            reject_cause: v
+                .cause_type
+                .zip(v.reject_cause)
+                .map(|(cause_type, cause)| RejectCause::new(cause_type, cause)),
+            active_time: v.active_time.as_deref().and_then(gprs_timer_2),
+            periodic_tau: v.periodic_tau.as_deref().and_then(gprs_timer_3),
+            urc: false,
         }
     }
 }
 
+/// Value of a GPRS Timer 2 or 3 bit string, eg. `"00100100"`: the unit in the
+/// upper three bits, the multiplier in the lower five. See 3GPP TS 24.008
+/// 10.5.7.4 and 10.5.7.4a.
+fn gprs_timer(bits: &str) -> Option<(u8, u64)> {
+    if bits.len() != 8 {
+        return None;
+    }
+    let v = u8::from_str_radix(bits, 2).ok()?;
+    Some((v >> 5, u64::from(v & 0x1f)))
+}
+
+/// Decode the active time (T3324), a GPRS Timer 2. None if deactivated.
+fn gprs_timer_2(bits: &str) -> Option<Duration> {
+    match gprs_timer(bits)? {
+        (0b000, v) => Some(Duration::from_secs(2 * v)),
+        (0b010, v) => Some(Duration::from_secs(6 * 60 * v)),
+        (0b111, _) => None,
+        // Other units are read as minutes
+        (_, v) => Some(Duration::from_secs(60 * v)),
+    }
+}
+
+/// Decode the periodic TAU time (T3412), a GPRS Timer 3. None if
+/// deactivated.
+fn gprs_timer_3(bits: &str) -> Option<Duration> {
+    let (unit, v) = gprs_timer(bits)?;
+    let secs = match unit {
+        0b000 => 10 * 60,
+        0b001 => 60 * 60,
+        0b010 => 10 * 60 * 60,
+        0b011 => 2,
+        0b100 => 30,
+        0b101 => 60,
+        0b110 => 320 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs * v))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum RadioAccessNetwork {
@@ -390,3 +511,65 @@ impl From<usize> for RadioAccessNetwork {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gprs_timers() {
+        assert_eq!(gprs_timer_2("00000101"), Some(Duration::from_secs(10)));
+        assert_eq!(gprs_timer_2("00100100"), Some(Duration::from_secs(4 * 60)));
+        assert_eq!(gprs_timer_2("01000011"), Some(Duration::from_secs(18 * 60)));
+        assert_eq!(gprs_timer_2("11100000"), None);
+        assert_eq!(gprs_timer_2("11111111"), None);
+
+        assert_eq!(
+            gprs_timer_3("01000111"),
+            Some(Duration::from_secs(70 * 3600))
+        );
+        assert_eq!(gprs_timer_3("00000110"), Some(Duration::from_secs(60 * 60)));
+        assert_eq!(gprs_timer_3("10100001"), Some(Duration::from_secs(60)));
+        assert_eq!(
+            gprs_timer_3("11000001"),
+            Some(Duration::from_secs(320 * 3600))
+        );
+        assert_eq!(gprs_timer_3("11111111"), None);
+
+        assert_eq!(gprs_timer_3(""), None);
+        assert_eq!(gprs_timer_3("0100011"), None);
+        assert_eq!(gprs_timer_3("0100011x"), None);
+    }
+
+    #[test]
+    fn psm_timers_from_cereg() {
+        let mut state = RegistrationState::new();
+        let urc = EPSNetworkRegistration {
+            stat: EPSNetworkRegistrationStat::Registered,
+            tac: None,
+            ci: None,
+            act: None,
+            cause_type: None,
+            reject_cause: None,
+            active_time: Some(String::try_from("00100100").unwrap()),
+            periodic_tau: Some(String::try_from("01000111").unwrap()),
+        };
+
+        state.compare_and_set(urc.clone().into());
+        assert_eq!(state.active_time, Some(Duration::from_secs(4 * 60)));
+        assert_eq!(state.periodic_tau, Some(Duration::from_secs(70 * 3600)));
+        assert!(state.last_tau.is_some());
+
+        // PSM no longer granted
+        state.compare_and_set(
+            EPSNetworkRegistration {
+                active_time: Some(String::try_from("11100000").unwrap()),
+                periodic_tau: None,
+                ..urc
+            }
+            .into(),
+        );
+        assert_eq!(state.active_time, None);
+        assert_eq!(state.periodic_tau, None);
+    }
+}